@@ -0,0 +1,63 @@
+//! Solves the workloads of `chalk_bench` with each engine, and checks their
+//! programs, so that the results of the engines can be compared with each
+//! other, and with a saved baseline.
+
+use chalk_bench::{workloads, ENGINES};
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+/// Solves the goals of each workload, each with a fresh solver so that no
+/// goal reuses the work done for the others, or in earlier iterations. The
+/// program clauses of the environments are computed once, by the database.
+fn solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    for workload in workloads() {
+        for engine in ENGINES {
+            let solver_choice = SolverChoice::from_name(engine).unwrap();
+            let db = workload.database(solver_choice);
+            let goals = workload.lowered_goals(&db);
+            group.bench_function(BenchmarkId::new(*engine, workload.name), |b| {
+                b.iter(|| {
+                    for goal in &goals {
+                        black_box(solver_choice.into_solver().solve(&db, goal));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Performs the coherence and well-formedness checks of each program, which
+/// solve goals with the engine of the database.
+fn check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("check");
+    // Checking the larger programs takes a good fraction of a second.
+    group.sample_size(10);
+    for workload in workloads() {
+        for engine in ENGINES {
+            let solver_choice = SolverChoice::from_name(engine).unwrap();
+            group.bench_function(BenchmarkId::new(*engine, workload.name), |b| {
+                b.iter_batched(
+                    || {
+                        let db = ChalkDatabase::with(&workload.program, solver_choice);
+                        db.program_ir().unwrap();
+                        db
+                    },
+                    |db| {
+                        db.checked_program().unwrap();
+                        // Dropped once the time is measured.
+                        db
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, solve, check);
+criterion_main!(benches);
@@ -0,0 +1,231 @@
+//! Representative programs for benchmarking the solvers (see
+//! `benches/solve.rs`).
+//!
+//! Each workload is a program, generated at a size where the solvers do a
+//! noticeable amount of work, and goals which exercise it.
+
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+use std::fmt::Write;
+
+/// The engines the workloads are solved with, by their names for
+/// `SolverChoice::from_name`.
+pub const ENGINES: &[&str] = &["slg", "recursive"];
+
+pub type UCanonicalGoal = UCanonical<InEnvironment<Goal<ChalkIr>>>;
+
+/// A program and the goals to solve in it.
+#[derive(Clone, Debug)]
+pub struct Workload {
+    pub name: &'static str,
+    pub program: String,
+    pub goals: Vec<String>,
+}
+
+impl Workload {
+    /// A database for the program, with the coherence and well-formedness
+    /// checks already performed.
+    pub fn database(&self, solver_choice: SolverChoice) -> ChalkDatabase {
+        let db = ChalkDatabase::with(&self.program, solver_choice);
+        if let Err(error) = db.checked_program() {
+            panic!("workload `{}` is not a valid program: {}", self.name, error);
+        }
+        db
+    }
+
+    /// The goals, lowered in the program of `db`.
+    pub fn lowered_goals(&self, db: &ChalkDatabase) -> Vec<UCanonicalGoal> {
+        self.goals
+            .iter()
+            .map(|goal| {
+                db.parse_and_lower_goal(goal)
+                    .unwrap_or_else(|error| {
+                        panic!("goal `{}` of `{}` is invalid: {}", goal, self.name, error)
+                    })
+                    .into_peeled_goal(db.interner())
+            })
+            .collect()
+    }
+}
+
+/// All the workloads, at their benchmarking sizes.
+pub fn workloads() -> Vec<Workload> {
+    vec![
+        deep_auto_traits(32),
+        wide_impls(200),
+        generic_associated_types(8),
+        coherence(40),
+    ]
+}
+
+/// An auto trait through a tree of structs `depth` levels deep, each of
+/// which holds the next level directly and in a `Vec`.
+pub fn deep_auto_traits(depth: usize) -> Workload {
+    let mut program = String::from(
+        "#[auto] trait Send {}\n\
+         struct Vec<T> { elem: T }\n\
+         struct Rc<T> { elem: T }\n\
+         impl<T> !Send for Rc<T> {}\n",
+    );
+    for level in 0..depth {
+        writeln!(
+            program,
+            "struct Node{0} {{ left: Node{1}, right: Vec<Node{1}> }}",
+            level,
+            level + 1
+        )
+        .unwrap();
+    }
+    writeln!(program, "struct Node{} {{}}", depth).unwrap();
+    Workload {
+        name: "deep_auto_traits",
+        program,
+        goals: vec![
+            "Node0: Send".to_string(),
+            "Vec<Node0>: Send".to_string(),
+            "Rc<Node0>: Send".to_string(),
+        ],
+    }
+}
+
+/// A trait with `width` impls for concrete types, and blanket impls for
+/// wrappers of them, with goals for a single type and for all of them.
+pub fn wide_impls(width: usize) -> Workload {
+    let mut program = String::from(
+        "trait Trait {}\n\
+         trait Other {}\n\
+         struct Vec<T> {}\n\
+         struct Box<T> {}\n\
+         impl<T> Trait for Vec<T> where T: Trait {}\n\
+         impl<T> Trait for Box<T> where T: Other {}\n",
+    );
+    for index in 0..width {
+        writeln!(program, "struct Ty{} {{}}", index).unwrap();
+        writeln!(program, "impl Trait for Ty{} {{}}", index).unwrap();
+        if index % 2 == 0 {
+            writeln!(program, "impl Other for Ty{} {{}}", index).unwrap();
+        }
+    }
+    Workload {
+        name: "wide_impls",
+        program,
+        goals: vec![
+            format!("Vec<Vec<Ty{}>>: Trait", width - 1),
+            format!("Box<Ty{}>: Trait", width - 1),
+            "exists<T> { Vec<T>: Trait }".to_string(),
+            "exists<T> { Box<T>: Trait }".to_string(),
+        ],
+    }
+}
+
+/// Generic associated types, each defined through the one before it, so
+/// normalizing the last one goes through `depth` projections.
+pub fn generic_associated_types(depth: usize) -> Workload {
+    let mut program = String::from(
+        "trait Family { type Member<T>; }\n\
+         trait Clone {}\n\
+         struct Box<T> {}\n\
+         struct Int {}\n\
+         impl Clone for Int {}\n\
+         impl<T> Clone for Box<T> where T: Clone {}\n\
+         struct Family0 {}\n\
+         impl Family for Family0 { type Member<T> = Box<T>; }\n",
+    );
+    for level in 1..=depth {
+        writeln!(program, "struct Family{} {{}}", level).unwrap();
+        writeln!(
+            program,
+            "impl Family for Family{} {{ type Member<T> = <Family{} as Family>::Member<Box<T>>; }}",
+            level,
+            level - 1
+        )
+        .unwrap();
+    }
+    Workload {
+        name: "generic_associated_types",
+        program,
+        goals: vec![
+            format!(
+                "exists<U> {{ Normalize(<Family{} as Family>::Member<Int> -> U) }}",
+                depth
+            ),
+            format!("<Family{} as Family>::Member<Int>: Clone", depth),
+            format!(
+                "forall<T> {{ if (T: Clone) {{ <Family{} as Family>::Member<T>: Clone }} }}",
+                depth
+            ),
+        ],
+    }
+}
+
+/// Traits whose impls must all be checked for overlap with each other:
+/// `count` impls for distinct types, plus blanket impls which overlap with
+/// none of them because of their where clauses.
+///
+/// The work of this workload is in checking the program; its goals are
+/// solved too, but are cheap.
+pub fn coherence(count: usize) -> Workload {
+    let mut program = String::from(
+        "trait Trait<T> {}\n\
+         trait Marker {}\n\
+         struct Vec<T> {}\n\
+         struct Wrapper<T> {}\n\
+         impl<T> Trait<T> for Wrapper<T> where T: Marker {}\n",
+    );
+    for index in 0..count {
+        writeln!(program, "struct Ty{} {{}}", index).unwrap();
+        writeln!(program, "impl Trait<Ty{0}> for Vec<Ty{0}> {{}}", index).unwrap();
+        writeln!(
+            program,
+            "impl<T> Trait<Ty{0}> for Wrapper<Vec<T>> {{}}",
+            index
+        )
+        .unwrap();
+    }
+    Workload {
+        name: "coherence",
+        program,
+        goals: vec![format!("Vec<Ty{0}>: Trait<Ty{0}>", count - 1)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The workloads are valid programs at small sizes, and the engines
+    /// agree on which of their goals have solutions.
+    #[test]
+    fn workloads_solve() {
+        let small = vec![
+            deep_auto_traits(3),
+            wide_impls(5),
+            generic_associated_types(2),
+            coherence(3),
+        ];
+        for workload in small {
+            let solved: Vec<Vec<bool>> = ENGINES
+                .iter()
+                .map(|engine| {
+                    let db = workload.database(SolverChoice::from_name(engine).unwrap());
+                    workload
+                        .lowered_goals(&db)
+                        .iter()
+                        .map(|goal| db.solve(goal).is_some())
+                        .collect()
+                })
+                .collect();
+            assert!(
+                solved.windows(2).all(|pair| pair[0] == pair[1]),
+                "the engines disagree on `{}`: {:?}",
+                workload.name,
+                solved
+            );
+        }
+    }
+}
@@ -3,7 +3,9 @@ extern crate proc_macro;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use quote::ToTokens;
-use syn::{parse_quote, DeriveInput, GenericParam, Ident, TypeParamBound};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, DeriveInput, GenericParam, Ident, Token, Type, TypeParamBound};
 
 use synstructure::decl_derive;
 
@@ -121,7 +123,9 @@ decl_derive!([HasInterner, attributes(has_interner)] => derive_has_interner);
 decl_derive!([Visit, attributes(has_interner)] => derive_visit);
 decl_derive!([SuperVisit, attributes(has_interner)] => derive_super_visit);
 decl_derive!([Fold, attributes(has_interner)] => derive_fold);
+decl_derive!([FallibleFolder, attributes(has_interner)] => derive_fallible_folder);
 decl_derive!([Zip, attributes(has_interner)] => derive_zip);
+decl_derive!([SimpleInterner, attributes(interner)] => derive_simple_interner);
 
 fn derive_has_interner(mut s: synstructure::Structure) -> TokenStream {
     s.underscore_const(true);
@@ -181,11 +185,11 @@ fn derive_any_visit(
                 &self,
                 visitor: &mut dyn ::chalk_ir::visit::Visitor < #interner, BreakTy = B >,
                 outer_binder: ::chalk_ir::DebruijnIndex,
-            ) -> std::ops::ControlFlow<B> {
+            ) -> ::core::ops::ControlFlow<B> {
                 match *self {
                     #body
                 }
-                std::ops::ControlFlow::Continue(())
+                ::core::ops::ControlFlow::Continue(())
             }
         },
     )
@@ -265,7 +269,7 @@ fn derive_fold(mut s: synstructure::Structure) -> TokenStream {
         vi.construct(|_, index| {
             let bind = &bindings[index];
             quote! {
-                ::chalk_ir::fold::Fold::fold_with(#bind, folder, outer_binder)?
+                ::chalk_ir::fold::Fold::try_fold_with(#bind, folder, outer_binder)?
             }
         })
     });
@@ -293,13 +297,512 @@ fn derive_fold(mut s: synstructure::Structure) -> TokenStream {
         quote! {
             type Result = #result;
 
-            fn fold_with<E>(
+            fn try_fold_with<E>(
                 self,
-                folder: &mut dyn ::chalk_ir::fold::Folder < #interner, Error = E >,
+                folder: &mut dyn ::chalk_ir::fold::FallibleFolder < #interner, Error = E >,
                 outer_binder: ::chalk_ir::DebruijnIndex,
-            ) -> ::std::result::Result<Self::Result, E> {
+            ) -> ::core::result::Result<Self::Result, E> {
                 Ok(match self { #body })
             }
         },
     )
 }
+
+/// Derives `FallibleFolder` for a folder that implements `Folder`, by
+/// forwarding each method to the infallible one.
+fn derive_fallible_folder(mut s: synstructure::Structure) -> TokenStream {
+    s.underscore_const(true);
+
+    let interner =
+        match has_interner_attr(s.ast()) {
+            Some(arg) => arg,
+            None => {
+                let param =
+                    s.ast().generics.params.iter().find_map(is_interner).expect(
+                        "deriving this trait requires a parameter that implements Interner",
+                    );
+                quote! { #param }
+            }
+        };
+
+    s.add_bounds(synstructure::AddBounds::None);
+    s.bound_impl(
+        quote!(::chalk_ir::fold::FallibleFolder<#interner>),
+        quote! {
+            type Error = ::core::convert::Infallible;
+
+            fn as_dyn(
+                &mut self,
+            ) -> &mut dyn ::chalk_ir::fold::FallibleFolder<#interner, Error = Self::Error> {
+                self
+            }
+
+            fn try_fold_ty(
+                &mut self,
+                ty: ::chalk_ir::Ty<#interner>,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Ty<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_ty(self, ty, outer_binder))
+            }
+
+            fn try_fold_lifetime(
+                &mut self,
+                lifetime: ::chalk_ir::Lifetime<#interner>,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Lifetime<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_lifetime(self, lifetime, outer_binder))
+            }
+
+            fn try_fold_const(
+                &mut self,
+                constant: ::chalk_ir::Const<#interner>,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Const<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_const(self, constant, outer_binder))
+            }
+
+            fn try_fold_program_clause(
+                &mut self,
+                clause: ::chalk_ir::ProgramClause<#interner>,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::ProgramClause<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_program_clause(self, clause, outer_binder))
+            }
+
+            fn try_fold_goal(
+                &mut self,
+                goal: ::chalk_ir::Goal<#interner>,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Goal<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_goal(self, goal, outer_binder))
+            }
+
+            fn forbid_free_vars(&self) -> bool {
+                ::chalk_ir::fold::Folder::forbid_free_vars(self)
+            }
+
+            fn try_fold_free_var_ty(
+                &mut self,
+                bound_var: ::chalk_ir::BoundVar,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Ty<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_free_var_ty(self, bound_var, outer_binder))
+            }
+
+            fn try_fold_free_var_lifetime(
+                &mut self,
+                bound_var: ::chalk_ir::BoundVar,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Lifetime<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_free_var_lifetime(
+                    self,
+                    bound_var,
+                    outer_binder,
+                ))
+            }
+
+            fn try_fold_free_var_const(
+                &mut self,
+                ty: ::chalk_ir::Ty<#interner>,
+                bound_var: ::chalk_ir::BoundVar,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Const<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_free_var_const(
+                    self,
+                    ty,
+                    bound_var,
+                    outer_binder,
+                ))
+            }
+
+            fn forbid_free_placeholders(&self) -> bool {
+                ::chalk_ir::fold::Folder::forbid_free_placeholders(self)
+            }
+
+            fn try_fold_free_placeholder_ty(
+                &mut self,
+                universe: ::chalk_ir::PlaceholderIndex,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Ty<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_free_placeholder_ty(
+                    self,
+                    universe,
+                    outer_binder,
+                ))
+            }
+
+            fn try_fold_free_placeholder_lifetime(
+                &mut self,
+                universe: ::chalk_ir::PlaceholderIndex,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Lifetime<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_free_placeholder_lifetime(
+                    self,
+                    universe,
+                    outer_binder,
+                ))
+            }
+
+            fn try_fold_free_placeholder_const(
+                &mut self,
+                ty: ::chalk_ir::Ty<#interner>,
+                universe: ::chalk_ir::PlaceholderIndex,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Const<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_free_placeholder_const(
+                    self,
+                    ty,
+                    universe,
+                    outer_binder,
+                ))
+            }
+
+            fn forbid_inference_vars(&self) -> bool {
+                ::chalk_ir::fold::Folder::forbid_inference_vars(self)
+            }
+
+            fn try_fold_inference_ty(
+                &mut self,
+                var: ::chalk_ir::InferenceVar,
+                kind: ::chalk_ir::TyVariableKind,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Ty<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_inference_ty(
+                    self,
+                    var,
+                    kind,
+                    outer_binder,
+                ))
+            }
+
+            fn try_fold_inference_lifetime(
+                &mut self,
+                var: ::chalk_ir::InferenceVar,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Lifetime<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_inference_lifetime(
+                    self,
+                    var,
+                    outer_binder,
+                ))
+            }
+
+            fn try_fold_inference_const(
+                &mut self,
+                ty: ::chalk_ir::Ty<#interner>,
+                var: ::chalk_ir::InferenceVar,
+                outer_binder: ::chalk_ir::DebruijnIndex,
+            ) -> ::core::result::Result<::chalk_ir::Const<#interner>, Self::Error> {
+                Ok(::chalk_ir::fold::Folder::fold_inference_const(
+                    self,
+                    ty,
+                    var,
+                    outer_binder,
+                ))
+            }
+
+            fn interner(&self) -> #interner {
+                ::chalk_ir::fold::Folder::interner(self)
+            }
+        },
+    )
+}
+
+/// An argument of the `#[interner(...)]` attribute: either a flag, like
+/// `arena`, or a type, like `def_id = MyDefId`.
+struct InternerArg {
+    name: Ident,
+    ty: Option<Type>,
+}
+
+impl Parse for InternerArg {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let ty = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(InternerArg { name, ty })
+    }
+}
+
+/// The types given in the `#[interner(...)]` attribute.
+struct InternerArgs {
+    arena: bool,
+    def_id: Type,
+    adt_id: Type,
+    identifier: Type,
+    fn_abi: Type,
+    concrete_const: Type,
+}
+
+fn interner_args(input: &DeriveInput) -> InternerArgs {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|a| a.path.is_ident("interner"))
+        .expect("deriving SimpleInterner requires an `#[interner(...)]` attribute");
+    let args = attr
+        .parse_args_with(Punctuated::<InternerArg, Token![,]>::parse_terminated)
+        .expect("Expected interner arguments");
+
+    let mut arena = false;
+    let (mut def_id, mut adt_id, mut identifier, mut fn_abi, mut concrete_const) =
+        (None, None, None, None, None);
+    for InternerArg { name, ty } in args {
+        let slot = match name.to_string().as_str() {
+            "arena" => {
+                assert!(ty.is_none(), "`arena` does not take a type");
+                arena = true;
+                continue;
+            }
+            "def_id" => &mut def_id,
+            "adt_id" => &mut adt_id,
+            "identifier" => &mut identifier,
+            "fn_abi" => &mut fn_abi,
+            "concrete_const" => &mut concrete_const,
+            name => panic!("unknown interner argument `{}`", name),
+        };
+        *slot = Some(ty.unwrap_or_else(|| panic!("interner argument `{}` requires a type", name)));
+    }
+
+    let def_id: Type = def_id.expect("interner argument `def_id` is required");
+    InternerArgs {
+        arena,
+        adt_id: adt_id.unwrap_or_else(|| def_id.clone()),
+        def_id,
+        identifier: identifier.expect("interner argument `identifier` is required"),
+        fn_abi: fn_abi.unwrap_or_else(|| parse_quote! { () }),
+        concrete_const: concrete_const.unwrap_or_else(|| parse_quote! { () }),
+    }
+}
+
+/// Derives the boilerplate of an interner, configured with an attribute
+/// like `#[interner(def_id = MyDefId, identifier = MyIdent)]`. The
+/// arguments are the types for the corresponding `Interner` associated
+/// types: `def_id` and `identifier` are required, `adt_id` defaults to
+/// `def_id`, and `fn_abi` and `concrete_const` default to `()`. The
+/// derived interners have no custom types or predicates.
+///
+/// By default, this implements `Interner` for the type, storing
+/// everything in `Arc`s. With the `arena` flag, it instead implements
+/// `ArenaInternerTypes`, so that `ArenaInterner<'_, TheType>` can be used
+/// as the interner.
+fn derive_simple_interner(mut s: synstructure::Structure) -> TokenStream {
+    s.underscore_const(true);
+    let InternerArgs {
+        arena,
+        def_id,
+        adt_id,
+        identifier,
+        fn_abi,
+        concrete_const,
+    } = interner_args(s.ast());
+
+    s.add_bounds(synstructure::AddBounds::None);
+    if arena {
+        return s.bound_impl(
+            quote!(::chalk_ir::arena::ArenaInternerTypes),
+            quote! {
+                type DefId = #def_id;
+                type InternedAdtId = #adt_id;
+                type Identifier = #identifier;
+                type FnAbi = #fn_abi;
+                type ConcreteConst = #concrete_const;
+            },
+        );
+    }
+
+    let has_interner = s.bound_impl(
+        quote!(::chalk_ir::interner::HasInterner),
+        quote! {
+            type Interner = Self;
+        },
+    );
+
+    // Generates the methods for interning lists of `$elem`.
+    let list = |intern: Ident, data: Ident, elem: TokenStream| {
+        quote! {
+            fn #intern<E>(
+                self,
+                data: impl IntoIterator<Item = ::std::result::Result<#elem, E>>,
+            ) -> ::std::result::Result<::std::sync::Arc<[#elem]>, E> {
+                data.into_iter().collect()
+            }
+
+            fn #data<'a>(self, data: &'a ::std::sync::Arc<[#elem]>) -> &'a [#elem] {
+                data
+            }
+        }
+    };
+    let goals = list(
+        parse_quote!(intern_goals),
+        parse_quote!(goals_data),
+        quote!(::chalk_ir::Goal<Self>),
+    );
+    let substitution = list(
+        parse_quote!(intern_substitution),
+        parse_quote!(substitution_data),
+        quote!(::chalk_ir::GenericArg<Self>),
+    );
+    let program_clauses = list(
+        parse_quote!(intern_program_clauses),
+        parse_quote!(program_clauses_data),
+        quote!(::chalk_ir::ProgramClause<Self>),
+    );
+    let quantified_where_clauses = list(
+        parse_quote!(intern_quantified_where_clauses),
+        parse_quote!(quantified_where_clauses_data),
+        quote!(::chalk_ir::QuantifiedWhereClause<Self>),
+    );
+    let variable_kinds = list(
+        parse_quote!(intern_generic_arg_kinds),
+        parse_quote!(variable_kinds_data),
+        quote!(::chalk_ir::VariableKind<Self>),
+    );
+    let canonical_var_kinds = list(
+        parse_quote!(intern_canonical_var_kinds),
+        parse_quote!(canonical_var_kinds_data),
+        quote!(::chalk_ir::CanonicalVarKind<Self>),
+    );
+    let constraints = list(
+        parse_quote!(intern_constraints),
+        parse_quote!(constraints_data),
+        quote!(::chalk_ir::InEnvironment<::chalk_ir::Constraint<Self>>),
+    );
+    let variances = list(
+        parse_quote!(intern_variances),
+        parse_quote!(variances_data),
+        quote!(::chalk_ir::Variance),
+    );
+
+    let interner = s.bound_impl(
+        quote!(::chalk_ir::interner::Interner),
+        quote! {
+            type InternedType = ::std::sync::Arc<::chalk_ir::TyData<Self>>;
+            type InternedLifetime = ::std::sync::Arc<::chalk_ir::LifetimeData<Self>>;
+            type InternedConst = ::std::sync::Arc<::chalk_ir::ConstData<Self>>;
+            type InternedConcreteConst = #concrete_const;
+            type InternedGenericArg = ::std::sync::Arc<::chalk_ir::GenericArgData<Self>>;
+            type InternedGoal = ::std::sync::Arc<::chalk_ir::GoalData<Self>>;
+            type InternedGoals = ::std::sync::Arc<[::chalk_ir::Goal<Self>]>;
+            type InternedSubstitution = ::std::sync::Arc<[::chalk_ir::GenericArg<Self>]>;
+            type InternedProgramClauses = ::std::sync::Arc<[::chalk_ir::ProgramClause<Self>]>;
+            type InternedProgramClause = ::std::sync::Arc<::chalk_ir::ProgramClauseData<Self>>;
+            type InternedQuantifiedWhereClauses =
+                ::std::sync::Arc<[::chalk_ir::QuantifiedWhereClause<Self>]>;
+            type InternedVariableKinds = ::std::sync::Arc<[::chalk_ir::VariableKind<Self>]>;
+            type InternedCanonicalVarKinds =
+                ::std::sync::Arc<[::chalk_ir::CanonicalVarKind<Self>]>;
+            type InternedConstraints =
+                ::std::sync::Arc<[::chalk_ir::InEnvironment<::chalk_ir::Constraint<Self>>]>;
+            type InternedVariances = ::std::sync::Arc<[::chalk_ir::Variance]>;
+            type DefId = #def_id;
+            type InternedAdtId = #adt_id;
+            type Identifier = #identifier;
+            type FnAbi = #fn_abi;
+            type CustomTy = ();
+            type CustomPredicate = ();
+
+            fn intern_ty(self, kind: ::chalk_ir::TyKind<Self>) -> Self::InternedType {
+                let flags = kind.compute_flags(self);
+                let outer_exclusive_binder = kind.compute_outer_exclusive_binder(self);
+                ::std::sync::Arc::new(::chalk_ir::TyData {
+                    kind,
+                    flags,
+                    outer_exclusive_binder,
+                })
+            }
+
+            fn ty_data<'a>(self, ty: &'a Self::InternedType) -> &'a ::chalk_ir::TyData<Self> {
+                ty
+            }
+
+            fn intern_lifetime(
+                self,
+                lifetime: ::chalk_ir::LifetimeData<Self>,
+            ) -> Self::InternedLifetime {
+                ::std::sync::Arc::new(lifetime)
+            }
+
+            fn lifetime_data<'a>(
+                self,
+                lifetime: &'a Self::InternedLifetime,
+            ) -> &'a ::chalk_ir::LifetimeData<Self> {
+                lifetime
+            }
+
+            fn intern_const(self, constant: ::chalk_ir::ConstData<Self>) -> Self::InternedConst {
+                ::std::sync::Arc::new(constant)
+            }
+
+            fn const_data<'a>(
+                self,
+                constant: &'a Self::InternedConst,
+            ) -> &'a ::chalk_ir::ConstData<Self> {
+                constant
+            }
+
+            fn const_eq(
+                self,
+                _ty: &Self::InternedType,
+                c1: &#concrete_const,
+                c2: &#concrete_const,
+            ) -> bool {
+                c1 == c2
+            }
+
+            fn intern_generic_arg(
+                self,
+                data: ::chalk_ir::GenericArgData<Self>,
+            ) -> Self::InternedGenericArg {
+                ::std::sync::Arc::new(data)
+            }
+
+            fn generic_arg_data<'a>(
+                self,
+                data: &'a Self::InternedGenericArg,
+            ) -> &'a ::chalk_ir::GenericArgData<Self> {
+                data
+            }
+
+            fn intern_goal(self, data: ::chalk_ir::GoalData<Self>) -> Self::InternedGoal {
+                ::std::sync::Arc::new(data)
+            }
+
+            fn goal_data<'a>(self, goal: &'a Self::InternedGoal) -> &'a ::chalk_ir::GoalData<Self> {
+                goal
+            }
+
+            fn intern_program_clause(
+                self,
+                data: ::chalk_ir::ProgramClauseData<Self>,
+            ) -> Self::InternedProgramClause {
+                ::std::sync::Arc::new(data)
+            }
+
+            fn program_clause_data<'a>(
+                self,
+                clause: &'a Self::InternedProgramClause,
+            ) -> &'a ::chalk_ir::ProgramClauseData<Self> {
+                clause
+            }
+
+            #goals
+            #substitution
+            #program_clauses
+            #quantified_where_clauses
+            #variable_kinds
+            #canonical_var_kinds
+            #constraints
+            #variances
+        },
+    );
+
+    quote! {
+        #has_interner
+        #interner
+    }
+}
@@ -8,7 +8,8 @@
 use crate::CompleteAnswer;
 use chalk_ir::interner::Interner;
 use chalk_ir::Substitution;
-use std::fmt::Debug;
+use chalk_solve::AmbiguityCause;
+use core::fmt::Debug;
 
 pub enum AnswerResult<I: Interner> {
     /// The next available answer.
@@ -47,7 +48,7 @@ impl<I: Interner> AnswerResult<I> {
 }
 
 impl<I: Interner> Debug for AnswerResult<I> {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             AnswerResult::Answer(answer) => write!(fmt, "{:?}", answer),
             AnswerResult::Floundered => write!(fmt, "Floundered"),
@@ -68,5 +69,9 @@ pub trait AnswerStream<I: Interner> {
 
     /// Invokes `test` with each possible future answer, returning true immediately
     /// if we find any answer for which `test` returns true.
-    fn any_future_answer(&self, test: impl Fn(&Substitution<I>) -> bool) -> bool;
+    fn any_future_answer(&self, test: impl FnMut(&Substitution<I>) -> bool) -> bool;
+
+    /// Why the goal floundered, or why its ambiguous answers are, judging
+    /// from the tables it depends on.
+    fn ambiguity_cause(&self) -> AmbiguityCause;
 }
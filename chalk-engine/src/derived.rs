@@ -3,9 +3,9 @@
 // object that are not needed.
 
 use super::*;
-use std::cmp::{Eq, PartialEq};
-use std::hash::{Hash, Hasher};
-use std::mem;
+use core::cmp::{Eq, PartialEq};
+use core::hash::{Hash, Hasher};
+use core::mem;
 
 ///////////////////////////////////////////////////////////////////////////
 
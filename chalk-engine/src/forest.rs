@@ -1,12 +1,22 @@
 use crate::context::{AnswerResult, AnswerStream};
 use crate::logic::RootSearchFail;
 use crate::slg::SlgContextOps;
+use crate::stack::Stack;
+use crate::strand::CanonicalStrand;
 use crate::table::AnswerIndex;
 use crate::tables::Tables;
 use crate::{TableIndex, TimeStamp};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 
+use crate::{FxHashMap, FxHashSet};
+use alloc::collections::VecDeque;
 use chalk_ir::interner::Interner;
 use chalk_ir::{Goal, InEnvironment, Substitution, UCanonical};
+use chalk_solve::{AmbiguityCause, SolverEvent, SolverEventSink};
+use hashbrown::hash_map::Entry;
 use tracing::debug;
 
 pub(crate) struct Forest<I: Interner> {
@@ -17,13 +27,86 @@ pub(crate) struct Forest<I: Interner> {
     /// This effectively gives us way to track what depth
     /// and loop a table or strand was last followed.
     pub(crate) clock: TimeStamp,
+
+    /// The number of program clauses used to build tables, and the number
+    /// of duplicate clauses removed, for `SolverStats`.
+    pub(crate) clauses: usize,
+    pub(crate) duplicate_clauses: usize,
+
+    /// The chains of growing goals found on the stack, outermost goal
+    /// first, whose last goal was floundered (see `SlgContextOps::growth_limit`).
+    pub(crate) growing_chains: Vec<Vec<TableIndex>>,
+
+    /// The subgoals which were too big to be made into tables, with the
+    /// tables on the stack at that point, outermost first.
+    pub(crate) overflows: Vec<StackOverflow<I>>,
+
+    /// The tables which floundered because one of their answers was too
+    /// big.
+    pub(crate) truncated_tables: FxHashSet<TableIndex>,
+
+    /// The search `step` is stepping through, if any.
+    pub(crate) steps: Option<Steps<I>>,
+
+    /// The callback to report `SolverEvent`s to, if any.
+    pub(crate) event_sink: Option<SolverEventSink<I>>,
+}
+
+/// A search for an answer which is being stepped through: the table and
+/// index of the answer, and the stack of the search, as the last step left
+/// it.
+pub(crate) struct Steps<I: Interner> {
+    pub(crate) table: TableIndex,
+    pub(crate) answer: AnswerIndex,
+    pub(crate) stack: Stack<I>,
 }
 
+/// The tables on the stack, outermost first, and the subgoal of the top
+/// table which was too big to be made into a table.
+pub(crate) type StackOverflow<I> = (Vec<TableIndex>, UCanonical<InEnvironment<Goal<I>>>);
+
 impl<I: Interner> Forest<I> {
     pub fn new() -> Self {
         Forest {
             tables: Tables::new(),
             clock: TimeStamp::default(),
+            clauses: 0,
+            duplicate_clauses: 0,
+            growing_chains: vec![],
+            overflows: vec![],
+            truncated_tables: FxHashSet::default(),
+            steps: None,
+            event_sink: None,
+        }
+    }
+
+    /// Drops the tables and everything learned while building them, keeping
+    /// the event sink.
+    #[cfg(feature = "std")]
+    pub(crate) fn clear(&mut self) {
+        let event_sink = self.event_sink.take();
+        *self = Forest {
+            event_sink,
+            ..Forest::new()
+        };
+    }
+
+    /// Reports the event `event` builds to the event sink, if there is one.
+    pub(crate) fn record_event(&mut self, event: impl FnOnce() -> SolverEvent<I>) {
+        if let Some(sink) = &mut self.event_sink {
+            sink(&event());
+        }
+    }
+
+    /// Reports the event `event` builds from the goal of `table` to the
+    /// event sink, if there is one.
+    pub(crate) fn record_table_event(
+        &mut self,
+        table: TableIndex,
+        event: impl FnOnce(UCanonical<InEnvironment<Goal<I>>>) -> SolverEvent<I>,
+    ) {
+        if let Some(sink) = &mut self.event_sink {
+            sink(&event(self.tables[table].table_goal.clone()));
         }
     }
 
@@ -33,6 +116,39 @@ impl<I: Interner> Forest<I> {
         self.clock
     }
 
+    /// Maps `table`, and each table it depends on directly or not, to the
+    /// table it was first reached from by a breadth-first search of the
+    /// dependencies of `table` (`None` for `table` itself).
+    pub(crate) fn dependency_tree(
+        &self,
+        table: TableIndex,
+    ) -> FxHashMap<TableIndex, Option<TableIndex>> {
+        let mut tree = FxHashMap::default();
+        tree.insert(table, None);
+        let mut queue = VecDeque::from(vec![table]);
+        while let Some(table) = queue.pop_front() {
+            for (dependency, _) in self.tables[table].dependencies() {
+                if let Entry::Vacant(entry) = tree.entry(dependency) {
+                    entry.insert(Some(table));
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        tree
+    }
+
+    /// The tables on the stack of the search `step` is stepping through,
+    /// outermost first, with the strand being pursued for each of them, if
+    /// any. Empty if no search is being stepped through.
+    pub(crate) fn step_stack(
+        &self,
+    ) -> impl Iterator<Item = (TableIndex, Option<&CanonicalStrand<I>>)> + '_ {
+        self.steps
+            .iter()
+            .flat_map(|steps| steps.stack.entries())
+            .map(|entry| (entry.table, entry.active_strand.as_ref()))
+    }
+
     /// Returns a "solver" for a given goal in the form of an
     /// iterator. Each time you invoke `next`, it will do the work to
     /// extract one more answer. These answers are cached in between
@@ -51,6 +167,52 @@ impl<I: Interner> Forest<I> {
             answer,
         }
     }
+
+    /// Draws the forest in the GraphViz dot language: each table is a node
+    /// labeled with its goal and its number of answers, with an edge to the
+    /// table of each subgoal it has selected, dashed for negative ones.
+    /// Floundered tables are drawn in red and coinductive ones doubled.
+    pub(crate) fn graphviz(&self, interner: I) -> String {
+        let mut dot = String::from("digraph forest {\n    node [shape=box];\n");
+        for (index, table) in self.tables.iter().enumerate() {
+            let goal = &table.table_goal.canonical().value;
+            let mut label = format!("{:?}", goal.goal);
+            let clauses = goal.environment.clauses.len(interner);
+            if clauses > 0 {
+                label += &format!("\nin an environment of {} clauses", clauses);
+            }
+            match table.num_answers() {
+                1 => label += "\n1 answer",
+                answers => label += &format!("\n{} answers", answers),
+            }
+            let mut attributes = format!("label=\"{}\"", escape(&label));
+            if table.is_floundered() {
+                attributes += ", color=red";
+            }
+            if table.coinductive_goal {
+                attributes += ", peripheries=2";
+            }
+            dot += &format!("    t{} [{}];\n", index, attributes);
+
+            let mut dependencies: Vec<_> = table.dependencies().collect();
+            dependencies.sort();
+            for (dependency, negative) in dependencies {
+                let style = if negative { " [style=dashed]" } else { "" };
+                dot += &format!("    t{} -> t{}{};\n", index, dependency.value, style);
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+}
+
+/// Escapes `label` for a quoted string in the dot language, where `\n`
+/// breaks the line.
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
 }
 
 struct ForestSolver<'me, I: Interner> {
@@ -110,7 +272,33 @@ impl<'me, I: Interner> AnswerStream<I> for ForestSolver<'me, I> {
         answer
     }
 
-    fn any_future_answer(&self, test: impl Fn(&Substitution<I>) -> bool) -> bool {
+    fn any_future_answer(&self, test: impl FnMut(&Substitution<I>) -> bool) -> bool {
         self.forest.any_future_answer(self.table, self.answer, test)
     }
+
+    /// The answers are truncated if a subgoal or an answer of a table the
+    /// goal depends on was too big, or a chain of growing goals was given
+    /// up on. Otherwise, the answers are ambiguous because a subgoal
+    /// floundered, or was a `CannotProve` goal.
+    fn ambiguity_cause(&self) -> AmbiguityCause {
+        let forest = &*self.forest;
+        let tree = forest.dependency_tree(self.table);
+        let truncated = forest
+            .overflows
+            .iter()
+            .any(|(stack, _)| tree.contains_key(stack.last().unwrap()))
+            || forest
+                .growing_chains
+                .iter()
+                .any(|chain| tree.contains_key(&chain[0]))
+            || forest
+                .truncated_tables
+                .iter()
+                .any(|table| tree.contains_key(table));
+        if truncated {
+            AmbiguityCause::Truncated
+        } else {
+            AmbiguityCause::FlounderedNegative
+        }
+    }
 }
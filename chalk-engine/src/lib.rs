@@ -53,8 +53,19 @@
 //! - HH: Hereditary harrop predicates. What Chalk deals in.
 //!   Popularized by Lambda Prolog.
 
-use std::cmp::min;
-use std::usize;
+#![no_std]
+
+// Only `alloc` is needed, unless the `std` feature is enabled (it is by
+// default) for `SyncForest`.
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+use alloc::vec::Vec;
+use core::cmp::min;
+use core::hash::BuildHasherDefault;
+use core::usize;
+use rustc_hash::FxHasher;
 
 use chalk_derive::{Fold, HasInterner, Visit};
 use chalk_ir::interner::Interner;
@@ -62,7 +73,7 @@ use chalk_ir::{
     AnswerSubst, Canonical, ConstrainedSubst, Constraint, DebruijnIndex, Goal, InEnvironment,
     Substitution,
 };
-use std::ops::ControlFlow;
+use core::ops::ControlFlow;
 
 pub mod context;
 mod derived;
@@ -74,9 +85,15 @@ pub mod slg;
 pub mod solve;
 mod stack;
 mod strand;
+#[cfg(feature = "std")]
+pub mod sync;
 mod table;
 mod tables;
 
+/// `rustc_hash` only provides its hash maps and sets with `std`.
+type FxHashMap<K, V> = hashbrown::HashMap<K, V, BuildHasherDefault<FxHasher>>;
+type FxHashSet<T> = hashbrown::HashSet<T, BuildHasherDefault<FxHasher>>;
+
 index_struct! {
     pub struct TableIndex { // FIXME: pub b/c Fold
         value: usize,
@@ -129,9 +146,7 @@ pub struct TimeStamp {
 }
 
 impl TimeStamp {
-    const MAX: TimeStamp = TimeStamp {
-        clock: ::std::u64::MAX,
-    };
+    const MAX: TimeStamp = TimeStamp { clock: u64::MAX };
 
     fn increment(&mut self) {
         self.clock += 1;
@@ -314,13 +329,13 @@ macro_rules! index_struct {
 
             // TODO: Once the Step trait is stabilized (https://github.com/rust-lang/rust/issues/42168), instead implement it and use the Iterator implementation of Range
             #[allow(dead_code)]
-            pub fn iterate_range(range: ::std::ops::Range<Self>) -> impl Iterator<Item = $n> {
+            pub fn iterate_range(range: ::core::ops::Range<Self>) -> impl Iterator<Item = $n> {
                 (range.start.value..range.end.value).into_iter().map(|i| Self { value: i })
             }
         }
 
-        impl ::std::fmt::Debug for $n {
-            fn fmt(&self, fmt: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        impl ::core::fmt::Debug for $n {
+            fn fmt(&self, fmt: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                 write!(fmt, "{}({})", stringify!($n), self.value)
             }
         }
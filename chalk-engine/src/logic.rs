@@ -1,13 +1,16 @@
-use crate::forest::Forest;
+use crate::context::AnswerResult;
+use crate::forest::{Forest, Steps};
 use crate::normalize_deep::DeepNormalizer;
-use crate::slg::{ResolventOps, SlgContext, SlgContextOps};
+use crate::slg::{strictly_embeds, subsumes, ResolventOps, SlgContext, SlgContextOps};
 use crate::stack::{Stack, StackIndex};
 use crate::strand::{CanonicalStrand, SelectedSubgoal, Strand};
-use crate::table::{AnswerIndex, Table};
+use crate::table::{AnswerIndex, Subsumes, Table};
 use crate::{
     Answer, AnswerMode, CompleteAnswer, ExClause, FlounderedSubgoal, Literal, Minimums, TableIndex,
     TimeStamp,
 };
+use alloc::vec;
+use alloc::vec::Vec;
 
 use chalk_ir::could_match::CouldMatch;
 use chalk_ir::interner::Interner;
@@ -15,11 +18,12 @@ use chalk_ir::{
     AnswerSubst, Canonical, ConstrainedSubst, Constraints, FallibleOrFloundered, Floundered, Goal,
     GoalData, InEnvironment, NoSolution, ProgramClause, Substitution, UCanonical, UniverseMap,
 };
-use chalk_solve::clauses::program_clauses_that_could_match;
+use chalk_solve::clauses::{dedup_program_clauses, program_clauses_that_could_match};
 use chalk_solve::coinductive_goal::IsCoinductive;
 use chalk_solve::infer::ucanonicalize::UCanonicalized;
 use chalk_solve::infer::InferenceTable;
 use chalk_solve::solve::truncate;
+use chalk_solve::{NoSolutionCause, SolverEvent};
 use tracing::{debug, debug_span, info, instrument};
 
 type RootSearchResult<T> = Result<T, RootSearchFail>;
@@ -87,6 +91,7 @@ impl<I: Interner> Forest<I> {
         table: TableIndex,
         answer_index: AnswerIndex,
     ) -> RootSearchResult<CompleteAnswer<I>> {
+        self.abandon_steps(context);
         let stack = Stack::default();
 
         let mut state = SolveState {
@@ -95,28 +100,127 @@ impl<I: Interner> Forest<I> {
             stack,
         };
 
-        match state.ensure_root_answer(table, answer_index) {
-            Ok(()) => {
-                assert!(state.stack.is_empty());
-                let answer = state.forest.answer(table, answer_index);
-                if !answer.subst.value.delayed_subgoals.is_empty() {
-                    return Err(RootSearchFail::InvalidAnswer);
-                }
-                Ok(CompleteAnswer {
-                    subst: Canonical {
-                        binders: answer.subst.binders.clone(),
-                        value: ConstrainedSubst {
-                            subst: answer.subst.value.subst.clone(),
-                            constraints: answer.subst.value.constraints.clone(),
-                        },
-                    },
-                    ambiguous: answer.ambiguous,
-                })
+        state.ensure_root_answer(table, answer_index)?;
+        assert!(state.stack.is_empty());
+        drop(state);
+        self.complete_answer(context, table, answer_index)
+    }
+
+    /// Advances the search for the next answer of `table` by one strand
+    /// transition (see `pursue_next_strand`), keeping the stack of the
+    /// search in `self.steps` for the next call, and for debuggers to
+    /// inspect. Stepping through another table, or a search started by
+    /// `root_answer`, abandons the search in progress, if any. Returns
+    /// `None` while the search goes on; once it finds an answer, the next
+    /// step starts the search for the answer after it.
+    pub(crate) fn step(
+        &mut self,
+        context: &SlgContextOps<I>,
+        table: TableIndex,
+    ) -> Option<AnswerResult<I>> {
+        let (answer_index, stack) = match self.steps.take() {
+            Some(steps) if steps.table == table => (steps.answer, steps.stack),
+            steps => {
+                self.steps = steps;
+                self.abandon_steps(context);
+                (AnswerIndex::ZERO, Stack::default())
             }
-            Err(err) => Err(err),
+        };
+        let mut state = SolveState {
+            forest: self,
+            context,
+            stack,
+        };
+
+        let result = if state.stack.is_empty() {
+            state
+                .start_root_search(table, answer_index)
+                .and_then(|available| Ok(available || state.pursue_next_strand()?))
+        } else {
+            state.pursue_next_strand()
+        };
+        let result = match result {
+            Ok(false) => {
+                // Keep the stack, rather than letting `state` unwind it.
+                let stack = core::mem::take(&mut state.stack);
+                drop(state);
+                self.steps = Some(Steps {
+                    table,
+                    answer: answer_index,
+                    stack,
+                });
+                return None;
+            }
+            Ok(true) => {
+                drop(state);
+                self.complete_answer(context, table, answer_index)
+            }
+            Err(err) => {
+                drop(state);
+                Err(err)
+            }
+        };
+
+        // As in `ForestSolver::peek_answer`.
+        let mut next_answer = answer_index;
+        next_answer.increment();
+        let (answer, result) = match result {
+            Ok(answer) => (next_answer, Some(AnswerResult::Answer(answer))),
+            Err(RootSearchFail::InvalidAnswer) => (next_answer, None),
+            Err(RootSearchFail::QuantumExceeded) => (answer_index, None),
+            Err(RootSearchFail::Floundered) => return Some(AnswerResult::Floundered),
+            Err(RootSearchFail::NoMoreSolutions) => return Some(AnswerResult::NoMoreSolutions),
+            Err(RootSearchFail::NegativeCycle) => panic!("negative cycle was detected"),
+        };
+        self.steps = Some(Steps {
+            table,
+            answer,
+            stack: Stack::default(),
+        });
+        result
+    }
+
+    /// Abandons the search `step` has in progress, if any, returning the
+    /// strands on its stack to their tables.
+    fn abandon_steps(&mut self, context: &SlgContextOps<I>) {
+        if let Some(steps) = self.steps.take() {
+            drop(SolveState {
+                forest: self,
+                context,
+                stack: steps.stack,
+            });
         }
     }
 
+    /// The answer with the given index of the given table, which must be
+    /// available, as a complete answer.
+    fn complete_answer(
+        &self,
+        context: &SlgContextOps<I>,
+        table: TableIndex,
+        answer_index: AnswerIndex,
+    ) -> RootSearchResult<CompleteAnswer<I>> {
+        let answer = self.answer(table, answer_index);
+        if !answer.subst.value.delayed_subgoals.is_empty() {
+            return Err(RootSearchFail::InvalidAnswer);
+        }
+        let constraints = &answer.subst.value.constraints;
+        Ok(CompleteAnswer {
+            subst: Canonical {
+                binders: answer.subst.binders.clone(),
+                value: ConstrainedSubst {
+                    subst: answer.subst.value.subst.clone(),
+                    constraints: if context.constraint_normalization() {
+                        constraints.normalized(context.program().interner())
+                    } else {
+                        constraints.clone()
+                    },
+                },
+            },
+            ambiguous: answer.ambiguous,
+        })
+    }
+
     pub(super) fn any_future_answer(
         &self,
         table: TableIndex,
@@ -212,7 +316,7 @@ impl<I: Interner> Forest<I> {
             "creating new table with goal = {:#?}",
             goal,
         );
-        let table = Self::build_table(context, self.tables.next_index(), goal);
+        let table = self.build_table(context, self.tables.next_index(), goal);
         self.tables.insert(table)
     }
 
@@ -228,29 +332,38 @@ impl<I: Interner> Forest<I> {
     /// Clause Resolution* step being applied eagerly, as many times
     /// as possible.
     fn build_table(
+        &mut self,
         context: &SlgContextOps<I>,
         table_idx: TableIndex,
         goal: UCanonical<InEnvironment<Goal<I>>>,
     ) -> Table<I> {
-        let coinductive = goal.is_coinductive(context.program());
+        let coinductive =
+            goal.is_coinductive_with(context.program(), context.coinductive_overrides());
         let mut table = Table::new(goal.clone(), coinductive);
+        self.record_event(|| SolverEvent::TableCreated {
+            goal: table.table_goal.clone(),
+        });
 
-        let goal_data = goal.canonical.value.goal.data(context.program().interner());
+        let goal_data = goal
+            .canonical()
+            .value
+            .goal
+            .data(context.program().interner());
         match goal_data {
             GoalData::DomainGoal(domain_goal) => {
-                let canon_domain_goal = UCanonical {
-                    canonical: Canonical {
-                        binders: goal.canonical.binders,
+                let canon_domain_goal = UCanonical::new(
+                    Canonical {
+                        binders: goal.canonical().binders.clone(),
                         value: InEnvironment::new(
-                            &goal.canonical.value.environment,
+                            &goal.canonical().value.environment,
                             domain_goal.clone(),
                         ),
                     },
-                    universes: goal.universes,
-                };
+                    goal.universes(),
+                );
 
                 let db = context.program();
-                let canon_goal = canon_domain_goal.canonical.value.goal.clone();
+                let canon_goal = canon_domain_goal.canonical().value.goal.clone();
                 let could_match = |c: &ProgramClause<I>| {
                     c.could_match(db.interner(), db.unification_database(), &canon_goal)
                 };
@@ -263,8 +376,8 @@ impl<I: Interner> Forest<I> {
                         let (infer, subst, goal) =
                             chalk_solve::infer::InferenceTable::from_canonical(
                                 context.program().interner(),
-                                canon_domain_goal.universes,
-                                canon_domain_goal.canonical,
+                                canon_domain_goal.universes(),
+                                canon_domain_goal.into_canonical(),
                             );
 
                         clauses.extend(
@@ -273,29 +386,41 @@ impl<I: Interner> Forest<I> {
                                 .cloned()
                                 .filter(could_match),
                         );
-
-                        let InEnvironment { environment, goal } = goal;
+                        self.duplicate_clauses += dedup_program_clauses(&mut clauses);
+                        self.clauses += clauses.len();
 
                         for clause in clauses {
                             info!("program clause = {:#?}", clause);
+                            self.record_event(|| SolverEvent::ClauseTried {
+                                goal: table.table_goal.clone(),
+                                clause: clause.clone(),
+                            });
                             let mut infer = infer.clone();
-                            if let Ok(resolvent) = infer.resolvent_clause(
-                                context.unification_database(),
+                            match infer.resolvent_clause(
+                                context.program(),
                                 context.program().interner(),
-                                &environment,
                                 &goal,
                                 &subst,
                                 &clause,
+                                context.leak_check(),
                             ) {
-                                info!("pushing initial strand with ex-clause: {:#?}", &resolvent,);
-                                let strand = Strand {
-                                    ex_clause: resolvent,
-                                    selected_subgoal: None,
-                                    last_pursued_time: TimeStamp::default(),
-                                };
-                                let canonical_strand =
-                                    Self::canonicalize_strand_from(context, &mut infer, &strand);
-                                table.enqueue_strand(canonical_strand);
+                                Ok(resolvent) => {
+                                    info!(
+                                        "pushing initial strand with ex-clause: {:#?}",
+                                        &resolvent,
+                                    );
+                                    let strand = Strand {
+                                        ex_clause: resolvent,
+                                        selected_subgoal: None,
+                                        last_pursued_time: TimeStamp::default(),
+                                        answer_sources: vec![],
+                                    };
+                                    let canonical_strand = Self::canonicalize_strand_from(
+                                        context, &mut infer, &strand,
+                                    );
+                                    table.enqueue_strand(canonical_strand);
+                                }
+                                Err(cause) => table.record_no_solution_cause(cause),
                             }
                         }
                     }
@@ -306,6 +431,9 @@ impl<I: Interner> Forest<I> {
                             table_idx
                         );
                         table.mark_floundered();
+                        self.record_event(|| SolverEvent::StrandFloundered {
+                            goal: table.table_goal.clone(),
+                        });
                     }
                 }
             }
@@ -314,8 +442,8 @@ impl<I: Interner> Forest<I> {
                 let (mut infer, subst, InEnvironment { environment, goal }) =
                     chalk_solve::infer::InferenceTable::from_canonical(
                         context.program().interner(),
-                        goal.universes,
-                        goal.canonical,
+                        goal.universes(),
+                        goal.into_canonical(),
                     );
                 // The goal for this table is not a domain goal, so we instead
                 // simplify it into a series of *literals*, all of which must be
@@ -324,7 +452,9 @@ impl<I: Interner> Forest<I> {
                 // where B, C, and D are the simplified subgoals. You can think
                 // of this as applying built-in "meta program clauses" that
                 // reduce goals into Domain goals.
-                match Self::simplify_goal(context, &mut infer, subst, environment, goal) {
+                let mut cause = NoSolutionCause::Unknown;
+                match Self::simplify_goal(context, &mut infer, subst, environment, goal, &mut cause)
+                {
                     FallibleOrFloundered::Ok(ex_clause) => {
                         info!(
                             ex_clause = ?DeepNormalizer::normalize_deep(
@@ -338,13 +468,19 @@ impl<I: Interner> Forest<I> {
                             ex_clause,
                             selected_subgoal: None,
                             last_pursued_time: TimeStamp::default(),
+                            answer_sources: vec![],
                         };
                         let canonical_strand =
                             Self::canonicalize_strand_from(context, &mut infer, &strand);
                         table.enqueue_strand(canonical_strand);
                     }
-                    FallibleOrFloundered::NoSolution => {}
-                    FallibleOrFloundered::Floundered => table.mark_floundered(),
+                    FallibleOrFloundered::NoSolution => table.record_no_solution_cause(cause),
+                    FallibleOrFloundered::Floundered => {
+                        table.mark_floundered();
+                        self.record_event(|| SolverEvent::StrandFloundered {
+                            goal: table.table_goal.clone(),
+                        });
+                    }
                 }
             }
         }
@@ -484,6 +620,22 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
         initial_table: TableIndex,
         initial_answer: AnswerIndex,
     ) -> RootSearchResult<()> {
+        if self.start_root_search(initial_table, initial_answer)? {
+            return Ok(());
+        }
+        while !self.pursue_next_strand()? {}
+        Ok(())
+    }
+
+    /// Starts the search for the answer with the given index of the given
+    /// table, pushing the table on the (empty) stack. Returns `Ok(true)`
+    /// if the answer is already available, in which case there is nothing
+    /// to search for.
+    fn start_root_search(
+        &mut self,
+        initial_table: TableIndex,
+        initial_answer: AnswerIndex,
+    ) -> RootSearchResult<bool> {
         info!(
             "table goal = {:#?}",
             self.forest.tables[initial_table].table_goal
@@ -495,7 +647,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
         // Check for a tabled answer.
         if let Some(answer) = self.forest.tables[initial_table].answer(initial_answer) {
             info!("answer cached = {:?}", answer);
-            return Ok(());
+            return Ok(true);
         }
 
         // If no tabled answer is present, we ought to be requesting
@@ -507,56 +659,63 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
 
         self.stack
             .push(initial_table, Minimums::MAX, self.forest.increment_clock());
-        loop {
-            let clock = self.stack.top().clock;
-            // If we had an active strand, continue to pursue it
-            let table = self.stack.top().table;
-            let table_answer_mode = self.forest.tables[table].answer_mode;
-
-            // We track when we last pursued each strand. If all the strands have been
-            // pursued at this depth, then that means they all encountered a cycle.
-            // We also know that if the first strand has been pursued at this depth,
-            // then all have. Otherwise, an answer to any strand would have provided an
-            // answer for the table.
-            let forest = &mut self.forest;
-            let next_strand = self.stack.top().active_strand.take().or_else(|| {
-                forest.tables[table].dequeue_next_strand_that(|strand| {
-                    let time_eligble = strand.value.last_pursued_time < clock;
-                    let mode_eligble = match (table_answer_mode, strand.value.ex_clause.ambiguous) {
-                        (AnswerMode::Complete, false) => true,
-                        (AnswerMode::Complete, true) => false,
-                        (AnswerMode::Ambiguous, _) => true,
-                    };
-                    time_eligble && mode_eligble
-                })
-            });
-            match next_strand {
-                Some(mut canonical_strand) => {
-                    debug!("starting next strand = {:#?}", canonical_strand);
-
-                    canonical_strand.value.last_pursued_time = clock;
-                    match self.select_subgoal(&mut canonical_strand) {
-                        SubGoalSelection::Selected => {
-                            // A subgoal has been selected. We now check this subgoal
-                            // table for an existing answer or if it's in a cycle.
-                            // If neither of those are the case, a strand is selected
-                            // and the next loop iteration happens.
-                            self.on_subgoal_selected(canonical_strand)?;
-                            continue;
-                        }
-                        SubGoalSelection::NotSelected => {
-                            match self.on_no_remaining_subgoals(canonical_strand) {
-                                NoRemainingSubgoalsResult::RootAnswerAvailable => return Ok(()),
-                                NoRemainingSubgoalsResult::RootSearchFail(e) => return Err(e),
-                                NoRemainingSubgoalsResult::Success => continue,
-                            };
+        Ok(false)
+    }
+
+    /// Pursues the next strand of the table on top of the stack, up to its
+    /// next transition: selecting a subgoal (which may push the subgoal's
+    /// table), or finishing the strand. Returns `Ok(true)` once an answer is
+    /// available for the table at the bottom of the stack, which is then
+    /// empty.
+    fn pursue_next_strand(&mut self) -> RootSearchResult<bool> {
+        let clock = self.stack.top().clock;
+        // If we had an active strand, continue to pursue it
+        let table = self.stack.top().table;
+        let table_answer_mode = self.forest.tables[table].answer_mode;
+
+        // We track when we last pursued each strand. If all the strands have been
+        // pursued at this depth, then that means they all encountered a cycle.
+        // We also know that if the first strand has been pursued at this depth,
+        // then all have. Otherwise, an answer to any strand would have provided an
+        // answer for the table.
+        let forest = &mut self.forest;
+        let next_strand = self.stack.top().active_strand.take().or_else(|| {
+            forest.tables[table].dequeue_next_strand_that(|strand| {
+                let time_eligble = strand.value.last_pursued_time < clock;
+                let mode_eligble = match (table_answer_mode, strand.value.ex_clause.ambiguous) {
+                    (AnswerMode::Complete, false) => true,
+                    (AnswerMode::Complete, true) => false,
+                    (AnswerMode::Ambiguous, _) => true,
+                };
+                time_eligble && mode_eligble
+            })
+        });
+        match next_strand {
+            Some(mut canonical_strand) => {
+                debug!("starting next strand = {:#?}", canonical_strand);
+
+                canonical_strand.value.last_pursued_time = clock;
+                match self.select_subgoal(&mut canonical_strand) {
+                    SubGoalSelection::Selected => {
+                        // A subgoal has been selected. We now check this subgoal
+                        // table for an existing answer or if it's in a cycle.
+                        // If neither of those are the case, a strand is selected
+                        // and the next strand is pursued.
+                        self.on_subgoal_selected(canonical_strand)?;
+                        Ok(false)
+                    }
+                    SubGoalSelection::NotSelected => {
+                        match self.on_no_remaining_subgoals(canonical_strand) {
+                            NoRemainingSubgoalsResult::RootAnswerAvailable => Ok(true),
+                            NoRemainingSubgoalsResult::RootSearchFail(e) => Err(e),
+                            NoRemainingSubgoalsResult::Success => Ok(false),
                         }
                     }
                 }
-                None => {
-                    self.on_no_strands_left()?;
-                    continue;
-                }
+            }
+            None => {
+                self.on_no_strands_left()?;
+                Ok(false)
             }
         }
     }
@@ -631,6 +790,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                     ex_clause: strand.ex_clause.clone(),
                     selected_subgoal: Some(next_subgoal),
                     last_pursued_time: strand.last_pursued_time,
+                    answer_sources: strand.answer_sources.clone(),
                 };
                 let table = self.stack.top().table;
                 let canonical_next_strand =
@@ -656,7 +816,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                 use chalk_solve::infer::ucanonicalize::UniverseMapExt;
                 let table_goal = universe_map.map_from_canonical(
                     self.context.program().interner(),
-                    &self.forest.tables[subgoal_table].table_goal.canonical,
+                    self.forest.tables[subgoal_table].table_goal.canonical(),
                 );
                 let answer_subst = universe_map.map_from_canonical(
                     self.context.program().interner(),
@@ -685,6 +845,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                         // Increment the answer time for the `ex_clause`. Floundered
                         // subgoals may be eligble to be pursued again.
                         ex_clause.answer_time.increment();
+                        strand.answer_sources.push((subgoal_table, answer_index));
 
                         // Ok, we've applied the answer to this Strand.
                         Ok(())
@@ -936,7 +1097,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             // We need to check if we can merge it into the current `Strand`.
             let num_universes = self.forest.tables[self.stack.top().table]
                 .table_goal
-                .universes;
+                .universes();
             let (mut infer, _, mut strand) = chalk_solve::infer::InferenceTable::from_canonical(
                 self.context.program().interner(),
                 num_universes,
@@ -974,7 +1135,13 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                 negative: TimeStamp::MAX,
             };
 
-            if self.top_of_stack_is_coinductive_from(cyclic_depth) {
+            let coinductive = self.top_of_stack_is_coinductive_from(cyclic_depth);
+            self.forest
+                .record_table_event(subgoal_table, |goal| SolverEvent::CycleDetected {
+                    goal,
+                    coinductive,
+                });
+            if coinductive {
                 debug!("table is coinductive");
                 return self.on_coinductive_subgoal(canonical_strand);
             }
@@ -1095,7 +1262,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             return None;
         }
 
-        let num_universes = self.forest.tables[table].table_goal.universes;
+        let num_universes = self.forest.tables[table].table_goal.universes();
         let (
             mut infer,
             _,
@@ -1129,6 +1296,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             },
             selected_subgoal: None,
             last_pursued_time: TimeStamp::default(),
+            answer_sources: vec![],
         };
 
         Some(Forest::canonicalize_strand_from(
@@ -1168,9 +1336,15 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             {
                 // T' wanted an answer from T, but none is
                 // forthcoming.  Therefore, the active strand from T'
-                // has failed and can be discarded.
+                // has failed and can be discarded. If T has no answers
+                // at all, it failed for the same reason as T.
                 Literal::Positive(_) => {
                     debug!("discarding strand because positive literal");
+                    if self.forest.tables[table].num_answers() == 0 {
+                        let cause = self.forest.tables[table].no_solution_cause().clone();
+                        let caller_table = self.stack.top().table;
+                        self.forest.tables[caller_table].record_no_solution_cause(cause);
+                    }
                     self.stack.top().active_strand.take();
                     self.unwind_stack();
                     Err(RootSearchFail::QuantumExceeded)
@@ -1338,6 +1512,10 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                             .floundered_subgoals
                             .is_empty());
                         canonical_strand.value.ex_clause.ambiguous = true;
+                        let table = self.stack.top().table;
+                        self.forest.record_table_event(table, |goal| {
+                            SolverEvent::StrandFloundered { goal }
+                        });
                         return SubGoalSelection::NotSelected;
                     }
 
@@ -1350,7 +1528,7 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                 // Get or create table for this subgoal.
                 let num_universes = self.forest.tables[self.stack.top().table]
                     .table_goal
-                    .universes;
+                    .universes();
                 let (mut infer, _, strand) = chalk_solve::infer::InferenceTable::from_canonical(
                     self.context.program().interner(),
                     num_universes,
@@ -1361,7 +1539,19 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                     &mut infer,
                     &strand.ex_clause.subgoals[subgoal_index],
                 ) {
+                    Some((subgoal_table, _)) if self.is_growing(subgoal_table) => {
+                        // The subgoal keeps growing as it recurses, so
+                        // give up on it like on a subgoal that is too big.
+                        self.flounder_subgoal(&mut canonical_strand.value.ex_clause, subgoal_index);
+                    }
+
                     Some((subgoal_table, universe_map)) => {
+                        let negative = matches!(
+                            strand.ex_clause.subgoals[subgoal_index],
+                            Literal::Negative(_)
+                        );
+                        self.forest.tables[self.stack.top().table]
+                            .add_dependency(subgoal_table, negative);
                         canonical_strand.value.selected_subgoal = Some(SelectedSubgoal {
                             subgoal_index,
                             subgoal_table,
@@ -1373,7 +1563,8 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                     None => {
                         // If we failed to create a table for the subgoal,
                         // that is because we have a floundered negative
-                        // literal.
+                        // literal, or because the subgoal is too big.
+                        self.record_overflow(&mut infer, &strand.ex_clause.subgoals[subgoal_index]);
                         self.flounder_subgoal(&mut canonical_strand.value.ex_clause, subgoal_index);
                     }
                 }
@@ -1402,6 +1593,74 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
         }
     }
 
+    /// Returns true if the (newly selected) subgoal `table`, which has no
+    /// answers yet, completes a chain of `growth_limit` goals on the stack
+    /// each strictly embedding the one before it (see `strictly_embeds`),
+    /// like `T: Foo`, `Vec<T>: Foo` and `Vec<Vec<T>>: Foo`. The chain is
+    /// recorded in the forest.
+    fn is_growing(&mut self, table: TableIndex) -> bool {
+        let growth_limit = match self.context.growth_limit() {
+            Some(growth_limit) => growth_limit,
+            None => return false,
+        };
+        if self.forest.tables[table]
+            .answer(AnswerIndex::ZERO)
+            .is_some()
+        {
+            return false;
+        }
+
+        let interner = self.context.program().interner();
+        let db = self.context.unification_database();
+        let tables = &self.forest.tables;
+        let mut chain = vec![table];
+        for ancestor in self.stack.tables_from_top() {
+            if chain.len() >= growth_limit {
+                break;
+            }
+            let goal = &tables[*chain.last().unwrap()].table_goal;
+            if strictly_embeds(interner, db, &tables[ancestor].table_goal, goal) {
+                chain.push(ancestor);
+            }
+        }
+        if chain.len() < growth_limit {
+            return false;
+        }
+
+        info!(?chain, "growing goals detected");
+        chain.reverse();
+        self.forest.growing_chains.push(chain);
+        true
+    }
+
+    /// Records `subgoal`, which could not be made into a table, in the
+    /// forest's overflows if that is because it is too big (see
+    /// `Forest::abstract_positive_literal`).
+    fn record_overflow(&mut self, infer: &mut InferenceTable<I>, subgoal: &Literal<I>) {
+        let goal = match subgoal {
+            Literal::Positive(goal) | Literal::Negative(goal) => goal,
+        };
+        let interner = self.context.program().interner();
+        if !truncate::needs_truncation(interner, infer, self.context.max_size(), goal) {
+            return;
+        }
+
+        let canonical_goal = infer.canonicalize(interner, goal.clone()).quantified;
+        let goal = InferenceTable::u_canonicalize(interner, &canonical_goal).quantified;
+        if self
+            .forest
+            .overflows
+            .iter()
+            .any(|(_, overflowed)| *overflowed == goal)
+        {
+            return;
+        }
+        info!(?goal, "subgoal overflowed");
+        let mut stack: Vec<_> = self.stack.tables_from_top().collect();
+        stack.reverse();
+        self.forest.overflows.push((stack, goal));
+    }
+
     /// Invoked when a strand represents an **answer**. This means
     /// that the strand has no subgoals left. There are two possibilities:
     ///
@@ -1462,6 +1721,9 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             &subst,
         ) {
             self.forest.tables[table].mark_floundered();
+            self.forest.truncated_tables.insert(table);
+            self.forest
+                .record_table_event(table, |goal| SolverEvent::StrandFloundered { goal });
             return None;
         }
 
@@ -1482,11 +1744,18 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
             })
             .collect();
 
+        let interner = self.context.program().interner();
+        let mut constraints = Constraints::from_iter(interner, constraints);
+        if self.context.constraint_normalization() {
+            constraints =
+                constraints.without_implied(interner, &table_goal.canonical().value.environment);
+        }
+
         let subst = Canonical {
             binders,
             value: AnswerSubst {
                 subst,
-                constraints: Constraints::from_iter(self.context.program().interner(), constraints),
+                constraints,
                 delayed_subgoals: filtered_delayed_subgoals,
             },
         };
@@ -1563,7 +1832,22 @@ impl<'forest, I: Interner> SolveState<'forest, I> {
                     .is_empty(self.context.program().interner())
         };
 
-        if let Some(answer_index) = self.forest.tables[table].push_answer(answer) {
+        let interner = self.context.program().interner();
+        let universes = self.forest.tables[table].table_goal.universes();
+        let subsumption = |general: &Answer<I>, specific: &Answer<I>| {
+            subsumes(interner, universes, general, specific)
+        };
+        let subsumption: Option<Subsumes<'_, I>> = if self.context.answer_subsumption() {
+            Some(&subsumption)
+        } else {
+            None
+        };
+        if let Some(answer_index) = self.forest.tables[table].push_answer(answer, subsumption) {
+            self.forest
+                .record_table_event(table, |goal| SolverEvent::AnswerProduced {
+                    goal,
+                    ambiguous,
+                });
             // See above, if we have a *complete* and trivial answer, we don't
             // want to follow any more strands
             if !ambiguous && is_trivial_answer {
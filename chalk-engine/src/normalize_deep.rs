@@ -1,9 +1,11 @@
+use chalk_derive::FallibleFolder;
 use chalk_ir::fold::shift::Shift;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use chalk_solve::infer::InferenceTable;
 
+#[derive(FallibleFolder)]
 pub(crate) struct DeepNormalizer<'table, I: Interner> {
     table: &'table mut InferenceTable<I>,
     interner: I,
@@ -26,39 +28,45 @@ impl<I: Interner> DeepNormalizer<'_, I> {
         interner: I,
         value: T,
     ) -> T::Result {
-        value
-            .fold_with(
-                &mut DeepNormalizer { interner, table },
-                DebruijnIndex::INNERMOST,
-            )
-            .unwrap()
+        value.fold_with(
+            &mut DeepNormalizer { interner, table },
+            DebruijnIndex::INNERMOST,
+        )
     }
 }
 
 impl<I: Interner> Folder<I> for DeepNormalizer<'_, I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_type_flags(
+            self.interner,
+            TypeFlags::HAS_INFER | TypeFlags::HAS_BOUND_VARS,
+        ) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
     fn fold_inference_ty(
         &mut self,
         var: InferenceVar,
         kind: TyVariableKind,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    ) -> Ty<I> {
         let interner = self.interner;
         match self.table.probe_var(var) {
-            Some(ty) => Ok(ty
+            Some(ty) => ty
                 .assert_ty_ref(interner)
                 .clone()
-                .fold_with(self, DebruijnIndex::INNERMOST)?
-                .shifted_in(interner)), // FIXME shift
+                .fold_with(self, DebruijnIndex::INNERMOST)
+                .shifted_in(interner), // FIXME shift
             None => {
                 // Normalize all inference vars which have been unified into a
                 // single variable. Ena calls this the "root" variable.
-                Ok(self.table.inference_var_root(var).to_ty(interner, kind))
+                self.table.inference_var_root(var).to_ty(interner, kind)
             }
         }
     }
@@ -67,15 +75,15 @@ impl<I: Interner> Folder<I> for DeepNormalizer<'_, I> {
         &mut self,
         var: InferenceVar,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         let interner = self.interner;
         match self.table.probe_var(var) {
-            Some(l) => Ok(l
+            Some(l) => l
                 .assert_lifetime_ref(interner)
                 .clone()
-                .fold_with(self, DebruijnIndex::INNERMOST)?
-                .shifted_in(interner)),
-            None => Ok(var.to_lifetime(interner)), // FIXME shift
+                .fold_with(self, DebruijnIndex::INNERMOST)
+                .shifted_in(interner),
+            None => var.to_lifetime(interner), // FIXME shift
         }
     }
 
@@ -84,15 +92,15 @@ impl<I: Interner> Folder<I> for DeepNormalizer<'_, I> {
         ty: Ty<I>,
         var: InferenceVar,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         let interner = self.interner;
         match self.table.probe_var(var) {
-            Some(c) => Ok(c
+            Some(c) => c
                 .assert_const_ref(interner)
                 .clone()
-                .fold_with(self, DebruijnIndex::INNERMOST)?
-                .shifted_in(interner)),
-            None => Ok(var.to_const(interner, ty)), // FIXME shift
+                .fold_with(self, DebruijnIndex::INNERMOST)
+                .shifted_in(interner),
+            None => var.to_const(interner, ty), // FIXME shift
         }
     }
 
@@ -108,6 +116,8 @@ impl<I: Interner> Folder<I> for DeepNormalizer<'_, I> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
     use chalk_integration::interner::ChalkIr;
     use chalk_integration::{arg, ty};
 
@@ -1,6 +1,7 @@
 use crate::forest::Forest;
 use crate::slg::SlgContextOps;
 use crate::{ExClause, Literal, TimeStamp};
+use alloc::vec;
 
 use chalk_ir::cast::{Cast, Caster};
 use chalk_ir::interner::Interner;
@@ -8,19 +9,23 @@ use chalk_ir::{
     Environment, FallibleOrFloundered, Goal, GoalData, InEnvironment, QuantifierKind, Substitution,
     TyKind, TyVariableKind, Variance,
 };
-use chalk_solve::infer::InferenceTable;
+use chalk_solve::ext::GoalExt;
+use chalk_solve::infer::{InferenceTable, VariableOrigin};
+use chalk_solve::NoSolutionCause;
 use tracing::debug;
 
 impl<I: Interner> Forest<I> {
     /// Simplifies a goal into a series of positive domain goals
     /// and negative goals. This operation may fail if the goal
-    /// includes unifications that cannot be completed.
+    /// includes unifications that cannot be completed, in which case
+    /// the cause of the failure is stored in `cause`.
     pub(super) fn simplify_goal(
         context: &SlgContextOps<I>,
         infer: &mut InferenceTable<I>,
         subst: Substitution<I>,
         initial_environment: Environment<I>,
         initial_goal: Goal<I>,
+        cause: &mut NoSolutionCause<I>,
     ) -> FallibleOrFloundered<ExClause<I>> {
         let mut ex_clause = ExClause {
             subst,
@@ -33,6 +38,7 @@ impl<I: Interner> Forest<I> {
         };
 
         // A stack of higher-level goals to process.
+        let initial_goal = initial_goal.simplified(context.program());
         let mut pending_goals = vec![(initial_environment, initial_goal)];
 
         while let Some((environment, goal)) = pending_goals.pop() {
@@ -45,9 +51,15 @@ impl<I: Interner> Forest<I> {
                     pending_goals.push((environment, subgoal.clone()));
                 }
                 GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
-                    let subgoal = infer.instantiate_binders_existentially(
+                    let subgoal = infer.instantiate_binders_existentially_with_origin(
                         context.program().interner(),
                         subgoal.clone(),
+                        |index| {
+                            Some(VariableOrigin::Existential {
+                                goal: goal.clone(),
+                                index,
+                            })
+                        },
                     );
                     pending_goals.push((environment, subgoal.clone()));
                 }
@@ -77,11 +89,20 @@ impl<I: Interner> Forest<I> {
                     let a = &goal.a;
                     let b = &goal.b;
 
-                    let result =
-                        match infer.relate(interner, db, &environment, Variance::Invariant, a, b) {
-                            Ok(r) => r,
-                            Err(_) => return FallibleOrFloundered::NoSolution,
-                        };
+                    let result = match infer.relate_or_cause(
+                        interner,
+                        db,
+                        &environment,
+                        Variance::Invariant,
+                        a,
+                        b,
+                    ) {
+                        Ok(r) => r,
+                        Err(e) => {
+                            *cause = e;
+                            return FallibleOrFloundered::NoSolution;
+                        }
+                    };
                     ex_clause.subgoals.extend(
                         result
                             .goals
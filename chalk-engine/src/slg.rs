@@ -1,13 +1,19 @@
-use crate::ExClause;
+use crate::{Answer, ExClause};
+use alloc::vec;
+use alloc::vec::Vec;
 
 use chalk_derive::HasInterner;
+use chalk_ir::fold::Fold;
 use chalk_ir::interner::Interner;
+use chalk_ir::visit::{Visit, Visitor};
+use chalk_ir::zip::{Zip, Zipper};
 use chalk_ir::*;
 use chalk_solve::infer::InferenceTable;
-use chalk_solve::RustIrDatabase;
+use chalk_solve::{NoSolutionCause, RustIrDatabase};
 
-use std::fmt::Debug;
-use std::marker::PhantomData;
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
 
 pub(crate) mod aggregate;
 mod resolvent;
@@ -34,6 +40,22 @@ pub(crate) struct SlgContextOps<'me, I: Interner> {
     program: &'me dyn RustIrDatabase<I>,
     max_size: usize,
     expected_answers: Option<usize>,
+    /// The maximum number of candidate substitutions to collect when
+    /// the aggregated solution is ambiguous. Zero disables collection.
+    max_candidates: usize,
+    /// Whether to do the leak check when selecting program clauses.
+    leak_check: bool,
+    /// Whether to drop the answers subsumed by other answers of a table.
+    answer_subsumption: bool,
+    /// Whether to normalize the region constraints of complete answers.
+    constraint_normalization: bool,
+    /// The length of a chain of growing goals (see `strictly_embeds`) on
+    /// the stack at which a subgoal is given up on, if any.
+    growth_limit: Option<usize>,
+    /// The maximum number of answers combined into a solution, if any.
+    max_answers: Option<usize>,
+    /// Traits whose goals are coinductive, on top of those of the program.
+    coinductive_overrides: Vec<TraitId<I>>,
 }
 
 impl<I: Interner> SlgContextOps<'_, I> {
@@ -46,6 +68,78 @@ impl<I: Interner> SlgContextOps<'_, I> {
             program,
             max_size,
             expected_answers,
+            max_candidates: 0,
+            leak_check: false,
+            answer_subsumption: true,
+            constraint_normalization: true,
+            growth_limit: None,
+            max_answers: None,
+            coinductive_overrides: vec![],
+        }
+    }
+
+    /// Truncate subgoals past `max_size` instead.
+    pub(crate) fn with_max_size(self, max_size: usize) -> Self {
+        SlgContextOps { max_size, ..self }
+    }
+
+    /// Combine at most `max_answers` answers into a solution; see
+    /// `GoalOptions::max_answers`.
+    pub(crate) fn with_max_answers(self, max_answers: Option<usize>) -> Self {
+        SlgContextOps {
+            max_answers,
+            ..self
+        }
+    }
+
+    /// Treat the goals of `coinductive_overrides` as coinductive.
+    pub(crate) fn with_coinductive_overrides(self, coinductive_overrides: Vec<TraitId<I>>) -> Self {
+        SlgContextOps {
+            coinductive_overrides,
+            ..self
+        }
+    }
+
+    /// Collect up to `max_candidates` candidate substitutions when
+    /// the aggregated solution is ambiguous; see `Solution::Ambig`.
+    pub(crate) fn with_max_candidates(self, max_candidates: usize) -> Self {
+        SlgContextOps {
+            max_candidates,
+            ..self
+        }
+    }
+
+    /// Do the leak check (see `InferenceTable::leak_check`) when selecting
+    /// the program clauses which may prove a goal.
+    pub(crate) fn with_leak_check(self, leak_check: bool) -> Self {
+        SlgContextOps { leak_check, ..self }
+    }
+
+    /// Drop the answers of a table subsumed by its other answers (see
+    /// `Table::push_answer`).
+    pub(crate) fn with_answer_subsumption(self, answer_subsumption: bool) -> Self {
+        SlgContextOps {
+            answer_subsumption,
+            ..self
+        }
+    }
+
+    /// Normalize the region constraints of complete answers (see
+    /// `Constraints::normalized`).
+    pub(crate) fn with_constraint_normalization(self, constraint_normalization: bool) -> Self {
+        SlgContextOps {
+            constraint_normalization,
+            ..self
+        }
+    }
+
+    /// Give up on (flounder) a subgoal which completes a chain of
+    /// `growth_limit` goals on the stack, each strictly embedding the one
+    /// before it (see `SolveState::growing_chain`).
+    pub(crate) fn with_growth_limit(self, growth_limit: Option<usize>) -> Self {
+        SlgContextOps {
+            growth_limit,
+            ..self
         }
     }
 
@@ -55,8 +149,8 @@ impl<I: Interner> SlgContextOps<'_, I> {
     ) -> Canonical<ConstrainedSubst<I>> {
         let (mut infer, subst, _) = InferenceTable::from_canonical(
             self.program.interner(),
-            goal.universes,
-            goal.canonical.clone(),
+            goal.universes(),
+            goal.canonical().clone(),
         );
         infer
             .canonicalize(
@@ -77,6 +171,26 @@ impl<I: Interner> SlgContextOps<'_, I> {
         self.max_size
     }
 
+    pub(crate) fn leak_check(&self) -> bool {
+        self.leak_check
+    }
+
+    pub(crate) fn answer_subsumption(&self) -> bool {
+        self.answer_subsumption
+    }
+
+    pub(crate) fn constraint_normalization(&self) -> bool {
+        self.constraint_normalization
+    }
+
+    pub(crate) fn growth_limit(&self) -> Option<usize> {
+        self.growth_limit
+    }
+
+    pub(crate) fn coinductive_overrides(&self) -> &[TraitId<I>] {
+        &self.coinductive_overrides
+    }
+
     pub(crate) fn unification_database(&self) -> &dyn UnificationDatabase<I> {
         self.program.unification_database()
     }
@@ -87,16 +201,19 @@ pub trait ResolventOps<I: Interner> {
     /// given program clause to yield the start of a new strand (a
     /// canonical ex-clause).
     ///
-    /// The bindings in `infer` are unaffected by this operation.
+    /// The bindings in `infer` are unaffected by this operation. If
+    /// `leak_check` is true, the clause is rejected if it fails the leak
+    /// check (see `InferenceTable::leak_check`). A clause which does not
+    /// unify with the goal is rejected with the cause of the failure.
     fn resolvent_clause(
         &mut self,
-        ops: &dyn UnificationDatabase<I>,
+        db: &dyn RustIrDatabase<I>,
         interner: I,
-        environment: &Environment<I>,
-        goal: &DomainGoal<I>,
+        goal: &InEnvironment<DomainGoal<I>>,
         subst: &Substitution<I>,
         clause: &ProgramClause<I>,
-    ) -> Fallible<ExClause<I>>;
+        leak_check: bool,
+    ) -> Result<ExClause<I>, NoSolutionCause<I>>;
 
     fn apply_answer_subst(
         &mut self,
@@ -121,6 +238,206 @@ impl<I: Interner> SubstitutionExt<I> for Substitution<I> {
     }
 }
 
+/// Returns true if the answer `general` subsumes the answer `specific`, i.e.
+/// if `specific` is an instance of `general`, so that a table which has
+/// `general` as an answer gains nothing from having `specific` too.
+///
+/// This is conservative, and only holds if neither answer has region
+/// constraints or delayed subgoals, and if every variable of `general` is
+/// used at most once (`may_invalidate` does not check that the occurrences
+/// of a variable are instantiated consistently) and is in a universe which
+/// can name anything `specific` contains. `universes` is the number of
+/// universes of the table goal, whose placeholders the answers may contain.
+pub(crate) fn subsumes<I: Interner>(
+    interner: I,
+    universes: usize,
+    general: &Answer<I>,
+    specific: &Answer<I>,
+) -> bool {
+    if general.ambiguous && !specific.ambiguous {
+        return false;
+    }
+    let is_unconditional = |answer: &Answer<I>| {
+        answer.subst.value.constraints.is_empty(interner)
+            && answer.subst.value.delayed_subgoals.is_empty()
+    };
+    if !is_unconditional(general) || !is_unconditional(specific) {
+        return false;
+    }
+
+    let max_universe = specific
+        .subst
+        .binders
+        .iter(interner)
+        .map(|kind| kind.skip_kind().counter)
+        .chain(Some(universes - 1))
+        .max()
+        .unwrap();
+    if general
+        .subst
+        .binders
+        .iter(interner)
+        .any(|kind| kind.skip_kind().counter < max_universe)
+    {
+        return false;
+    }
+
+    let mut linear = LinearVars {
+        interner,
+        seen: vec![false; general.subst.binders.len(interner)],
+    };
+    if general
+        .subst
+        .value
+        .subst
+        .visit_with(&mut linear, DebruijnIndex::INNERMOST)
+        .is_break()
+    {
+        return false;
+    }
+
+    let general_subst = Canonical {
+        binders: general.subst.binders.clone(),
+        value: general.subst.value.subst.clone(),
+    };
+    !specific
+        .subst
+        .value
+        .subst
+        .may_invalidate(interner, &general_subst)
+}
+
+/// Breaks when visiting a variable for the second time.
+struct LinearVars<I: Interner> {
+    interner: I,
+    seen: Vec<bool>,
+}
+
+impl<I: Interner> Visitor<I> for LinearVars<I> {
+    type BreakTy = ();
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn visit_free_var(
+        &mut self,
+        bound_var: BoundVar,
+        _outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        if core::mem::replace(&mut self.seen[bound_var.index], true) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
+
+/// Returns true if `goal` strictly embeds `ancestor`: if the two goals
+/// are the same but for some types of `ancestor`, which are strict
+/// subterms of the matching types of `goal` (as in `T: Foo` and
+/// `Vec<T>: Foo`). This is the classic pattern of a goal which keeps
+/// growing as it recurses, and never terminates.
+///
+/// This is a heuristic: the variables of the two (canonical) goals are
+/// compared by index, and types beneath binders are never found in each
+/// other.
+pub(crate) fn strictly_embeds<I: Interner>(
+    interner: I,
+    db: &dyn UnificationDatabase<I>,
+    ancestor: &UCanonical<InEnvironment<Goal<I>>>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> bool {
+    let mut zipper = GrowthZipper {
+        interner,
+        db,
+        grew: false,
+    };
+    Zip::zip_with(
+        &mut zipper,
+        Variance::Invariant,
+        &ancestor.canonical().value,
+        &goal.canonical().value,
+    )
+    .is_ok()
+        && zipper.grew
+}
+
+/// Zips an ancestor goal with a goal, requiring each type of the goal to be
+/// equal to the matching type of the ancestor or to contain it.
+struct GrowthZipper<'i, I: Interner> {
+    interner: I,
+    db: &'i dyn UnificationDatabase<I>,
+    grew: bool,
+}
+
+impl<I: Interner> Zipper<I> for GrowthZipper<'_, I> {
+    fn zip_tys(&mut self, variance: Variance, ancestor: &Ty<I>, goal: &Ty<I>) -> Fallible<()> {
+        let interner = self.interner;
+        if ancestor == goal {
+            return Ok(());
+        }
+        if goal
+            .walk(interner)
+            .any(|component| component.ty(interner) == Some(ancestor))
+        {
+            self.grew = true;
+            return Ok(());
+        }
+        match (ancestor.kind(interner), goal.kind(interner)) {
+            (TyKind::Adt(id_a, substitution_a), TyKind::Adt(id_b, substitution_b))
+                if id_a == id_b =>
+            {
+                self.zip_substs(
+                    variance,
+                    None,
+                    substitution_a.as_slice(interner),
+                    substitution_b.as_slice(interner),
+                )
+            }
+            _ => Err(NoSolution),
+        }
+    }
+
+    fn zip_lifetimes(&mut self, _: Variance, a: &Lifetime<I>, b: &Lifetime<I>) -> Fallible<()> {
+        if a == b {
+            Ok(())
+        } else {
+            Err(NoSolution)
+        }
+    }
+
+    fn zip_consts(&mut self, _: Variance, a: &Const<I>, b: &Const<I>) -> Fallible<()> {
+        if a == b {
+            Ok(())
+        } else {
+            Err(NoSolution)
+        }
+    }
+
+    fn zip_binders<T>(&mut self, variance: Variance, a: &Binders<T>, b: &Binders<T>) -> Fallible<()>
+    where
+        T: Clone + interner::HasInterner<Interner = I> + Zip<I> + Fold<I, Result = T>,
+    {
+        if a.binders != b.binders {
+            return Err(NoSolution);
+        }
+        Zip::zip_with(self, variance, a.skip_binders(), b.skip_binders())
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self.db
+    }
+}
+
 // This is a struct in case we need to add state at any point like in AntiUnifier
 struct MayInvalidate<I> {
     interner: I,
@@ -195,6 +512,11 @@ impl<I: Interner> MayInvalidate<I> {
                 TyKind::Alias(AliasTy::Opaque(opaque_ty2)),
             ) => self.aggregate_opaque_ty_tys(opaque_ty1, opaque_ty2),
 
+            (
+                TyKind::Alias(AliasTy::Inherent(inherent_ty1)),
+                TyKind::Alias(AliasTy::Inherent(inherent_ty2)),
+            ) => self.aggregate_inherent_projection_tys(inherent_ty1, inherent_ty2),
+
             (TyKind::Adt(id_a, substitution_a), TyKind::Adt(id_b, substitution_b)) => {
                 self.aggregate_name_and_substs(id_a, substitution_a, id_b, substitution_b)
             }
@@ -238,6 +560,7 @@ impl<I: Interner> MayInvalidate<I> {
                 TyKind::GeneratorWitness(id_b, substitution_b),
             ) => self.aggregate_name_and_substs(id_a, substitution_a, id_b, substitution_b),
             (TyKind::Foreign(id_a), TyKind::Foreign(id_b)) => id_a != id_b,
+            (TyKind::Custom(custom_a), TyKind::Custom(custom_b)) => custom_a != custom_b,
             (TyKind::Error, TyKind::Error) => false,
 
             (_, _) => true,
@@ -326,6 +649,28 @@ impl<I: Interner> MayInvalidate<I> {
         )
     }
 
+    fn aggregate_inherent_projection_tys(
+        &mut self,
+        new: &InherentProjectionTy<I>,
+        current: &InherentProjectionTy<I>,
+    ) -> bool {
+        let InherentProjectionTy {
+            associated_ty_id: new_name,
+            substitution: new_substitution,
+        } = new;
+        let InherentProjectionTy {
+            associated_ty_id: current_name,
+            substitution: current_substitution,
+        } = current;
+
+        self.aggregate_name_and_substs(
+            new_name,
+            new_substitution,
+            current_name,
+            current_substitution,
+        )
+    }
+
     fn aggregate_opaque_ty_tys(&mut self, new: &OpaqueTy<I>, current: &OpaqueTy<I>) -> bool {
         let OpaqueTy {
             opaque_ty_id: new_name,
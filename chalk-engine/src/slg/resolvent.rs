@@ -1,13 +1,17 @@
 use crate::normalize_deep::DeepNormalizer;
 use crate::slg::ResolventOps;
 use crate::{ExClause, Literal, TimeStamp};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::vec;
 use chalk_ir::cast::Caster;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
+use chalk_ir::visit::VisitExt;
 use chalk_ir::zip::{Zip, Zipper};
 use chalk_ir::*;
 use chalk_solve::infer::InferenceTable;
+use chalk_solve::{NoSolutionCause, RustIrDatabase};
 use tracing::{debug, instrument};
 
 ///////////////////////////////////////////////////////////////////////////
@@ -54,17 +58,24 @@ impl<I: Interner> ResolventOps<I> for InferenceTable<I> {
     /// # Parameters
     ///
     /// - `goal` is the goal G that we are trying to solve
-    /// - `clause` is the program clause that may be useful to that end
-    #[instrument(level = "debug", skip(self, interner, environment, subst))]
+    /// - `clause` is the program clause that may be useful to that end; the
+    ///   variables of a quantified consequence which the clause does not
+    ///   bind itself are instantiated like its binders (clauses written as
+    ///   implications are given as `Goal::to_program_clauses` gives them)
+    /// - `leak_check` is whether to reject the clause if unifying it with
+    ///   the goal leaks a placeholder
+    #[instrument(level = "debug", skip(self, interner, goal, subst))]
     fn resolvent_clause(
         &mut self,
-        db: &dyn UnificationDatabase<I>,
+        db: &dyn RustIrDatabase<I>,
         interner: I,
-        environment: &Environment<I>,
-        goal: &DomainGoal<I>,
+        goal: &InEnvironment<DomainGoal<I>>,
         subst: &Substitution<I>,
         clause: &ProgramClause<I>,
-    ) -> Fallible<ExClause<I>> {
+        leak_check: bool,
+    ) -> Result<ExClause<I>, NoSolutionCause<I>> {
+        let InEnvironment { environment, goal } = goal;
+
         // Relating the above description to our situation:
         //
         // - `goal` G, except with binders for any existential variables.
@@ -74,27 +85,36 @@ impl<I: Interner> ResolventOps<I> for InferenceTable<I> {
         // C' in the description above is `consequence :- conditions`.
         //
         // Note that G and C' have no variables in common.
+        //
+        // A clause built from the body of a quantified goal without binding
+        // the variables of the quantifier, like `Vec<^1.0>: Clone` for
+        // `forall<T> { Vec<T>: Clone }`, has them free: they are
+        // instantiated like the binders of the clause, instead of reaching
+        // unification as bound variables.
+        let clause = match clause.has_free_vars(interner) {
+            true => Cow::Owned(self.instantiate_free_vars_existentially(interner, clause.clone())),
+            false => Cow::Borrowed(clause),
+        };
         let ProgramClauseImplication {
             consequence,
             conditions,
             constraints,
             priority: _,
-        } = {
-            let ProgramClauseData(implication) = clause.data(interner);
-
-            self.instantiate_binders_existentially(interner, implication.clone())
-        };
+        } = self.instantiate_clause_existentially(db, &clause);
         debug!(?consequence, ?conditions, ?constraints);
 
         // Unify the selected literal Li with C'.
-        let unification_result = self.relate(
+        let unification_result = self.relate_or_cause(
             interner,
-            db,
+            db.unification_database(),
             environment,
             Variance::Invariant,
             goal,
             &consequence,
         )?;
+        if leak_check {
+            self.leak_check(interner, &unification_result.goals)?;
+        }
 
         // Final X-clause that we will return.
         let mut ex_clause = ExClause {
@@ -575,6 +595,9 @@ impl<'i, I: Interner> Zipper<I> for AnswerSubstitutor<'i, I> {
             (TyKind::Foreign(id_a), TyKind::Foreign(id_b)) => {
                 Zip::zip_with(self, variance, id_a, id_b)
             }
+            (TyKind::Custom(custom_a), TyKind::Custom(custom_b)) => {
+                interner.zip_custom_tys(self, variance, custom_a, custom_b)
+            }
             (TyKind::Error, TyKind::Error) => Ok(()),
 
             (_, _) => panic!(
@@ -729,3 +752,117 @@ impl<'i, I: Interner> Zipper<I> for AnswerSubstitutor<'i, I> {
         self.unification_database
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use chalk_integration::db::ChalkDatabase;
+    use chalk_integration::interner::ChalkIr;
+    use chalk_integration::SolverChoice;
+    use chalk_solve::ext::GoalExt;
+
+    const PROGRAM: &str = "
+        trait Clone {}
+        struct Vec<T> {}
+        struct Foo {}
+    ";
+
+    /// The subgoals of the resolvent of `goal` with `clause`, or `None` if
+    /// the clause does not apply.
+    fn resolvent_subgoals(
+        db: &ChalkDatabase,
+        goal: &str,
+        clause: &ProgramClause<ChalkIr>,
+    ) -> Option<Vec<String>> {
+        let interner = ChalkIr;
+        let goal = db
+            .parse_and_lower_goal(goal)
+            .unwrap()
+            .into_closed_goal(interner);
+        db.with_program(|_| {
+            let (mut infer, subst, goal) =
+                InferenceTable::from_canonical(interner, goal.universes(), goal.into_canonical());
+            let goal = match goal.goal.data(interner) {
+                GoalData::DomainGoal(domain_goal) => {
+                    InEnvironment::new(&goal.environment, domain_goal.clone())
+                }
+                goal => panic!("unexpected goal {:?}", goal),
+            };
+            let ex_clause = infer
+                .resolvent_clause(db, interner, &goal, &subst, clause, false)
+                .ok()?;
+            let ex_clause = DeepNormalizer::normalize_deep(&mut infer, interner, ex_clause);
+            Some(
+                ex_clause
+                    .subgoals
+                    .iter()
+                    .map(|subgoal| format!("{:?}", subgoal))
+                    .collect(),
+            )
+        })
+    }
+
+    #[test]
+    fn quantified_consequence() {
+        let interner = ChalkIr;
+        let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+        // The clause `Vec<^1.0>: Clone`, built from the body of
+        // `forall<T> { Vec<T>: Clone }` without binding `T`.
+        let quantified = db
+            .parse_and_lower_goal("forall<T> { Vec<T>: Clone }")
+            .unwrap();
+        let consequence = match quantified.data(interner) {
+            GoalData::Quantified(QuantifierKind::ForAll, body) => {
+                match body.skip_binders().data(interner) {
+                    GoalData::DomainGoal(domain_goal) => domain_goal.clone(),
+                    goal => panic!("unexpected goal {:?}", goal),
+                }
+            }
+            goal => panic!("unexpected goal {:?}", goal),
+        };
+        let clause = ProgramClauseData(Binders::empty(
+            interner,
+            ProgramClauseImplication {
+                consequence: consequence.shifted_in(interner),
+                conditions: Goals::empty(interner),
+                constraints: Constraints::empty(interner),
+                priority: ClausePriority::High,
+            },
+        ))
+        .intern(interner);
+
+        assert_eq!(
+            resolvent_subgoals(&db, "Vec<Foo>: Clone", &clause),
+            Some(vec![])
+        );
+        assert_eq!(resolvent_subgoals(&db, "Foo: Clone", &clause), None);
+    }
+
+    #[test]
+    fn implication_consequence() {
+        let interner = ChalkIr;
+        let db = ChalkDatabase::with(PROGRAM, SolverChoice::default());
+        let implication = db
+            .parse_and_lower_goal("forall<T> { if (T: Clone) { Vec<T>: Clone } }")
+            .unwrap();
+        let clauses = implication.to_program_clauses(interner).unwrap();
+        assert_eq!(clauses.len(), 1);
+
+        let subgoals = resolvent_subgoals(&db, "Vec<Foo>: Clone", &clauses[0]).unwrap();
+        db.with_program(|_| {
+            assert_eq!(
+                subgoals,
+                vec![format!(
+                    "{:?}",
+                    Literal::Positive(InEnvironment::new(
+                        &Environment::new(interner),
+                        db.parse_and_lower_goal("FromEnv(Foo: Clone)").unwrap()
+                    ))
+                )]
+            )
+        });
+    }
+}
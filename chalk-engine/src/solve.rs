@@ -2,16 +2,28 @@ use crate::context::{AnswerResult, AnswerStream};
 use crate::forest::Forest;
 use crate::slg::aggregate::AggregateOps;
 use crate::slg::SlgContextOps;
+use crate::{Answer, CompleteAnswer, Literal};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_ir::interner::Interner;
-use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, UCanonical};
-use chalk_solve::{RustIrDatabase, Solution, Solver, SubstitutionResult};
+use chalk_ir::{Canonical, ConstrainedSubst, Goal, GoalData, InEnvironment, UCanonical};
+use chalk_solve::{
+    ActiveStrand, GoalOptions, NoSolutionCause, Overflow, RustIrDatabase, Solution, Solver,
+    SolverEventSink, SolverStats, SolverStep, StackFrame, SubstitutionResult,
+};
 
-use std::fmt;
+use core::fmt;
 
 pub struct SLGSolver<I: Interner> {
     pub(crate) forest: Forest<I>,
     pub(crate) max_size: usize,
     pub(crate) expected_answers: Option<usize>,
+    pub(crate) max_candidates: usize,
+    pub(crate) leak_check: bool,
+    pub(crate) answer_subsumption: bool,
+    pub(crate) constraint_normalization: bool,
+    pub(crate) growth_limit: Option<usize>,
 }
 
 impl<I: Interner> SLGSolver<I> {
@@ -20,6 +32,158 @@ impl<I: Interner> SLGSolver<I> {
             forest: Forest::new(),
             max_size,
             expected_answers,
+            max_candidates: 0,
+            leak_check: false,
+            answer_subsumption: true,
+            constraint_normalization: true,
+            growth_limit: None,
+        }
+    }
+
+    /// Sets the number of candidate substitutions an ambiguous solution
+    /// reports (see `Solution::Ambig`): those of the first
+    /// `max_candidates` answers of the goal. None are by default.
+    pub fn with_max_candidates(self, max_candidates: usize) -> Self {
+        Self {
+            max_candidates,
+            ..self
+        }
+    }
+
+    /// Enables or disables the leak check (see
+    /// `InferenceTable::leak_check`) when selecting the program clauses
+    /// which may prove a goal. It is disabled by default.
+    pub fn with_leak_check(self, leak_check: bool) -> Self {
+        Self { leak_check, ..self }
+    }
+
+    /// Enables or disables answer subsumption: dropping the answers of a
+    /// table which are instances of its other, more general answers. It is
+    /// enabled by default; disabling it can help when debugging, to see
+    /// every answer the solver finds.
+    pub fn with_answer_subsumption(self, answer_subsumption: bool) -> Self {
+        Self {
+            answer_subsumption,
+            ..self
+        }
+    }
+
+    /// Enables or disables the normalization of the region constraints of
+    /// solutions (see `Constraints::normalized`), which drops the trivial
+    /// and repeated ones, and those implied by the outlives facts of the
+    /// environment (see `Constraints::without_implied`), and sorts them. It is enabled by default;
+    /// disabling it can help when debugging, to see every constraint as
+    /// the solver found it.
+    pub fn with_constraint_normalization(self, constraint_normalization: bool) -> Self {
+        Self {
+            constraint_normalization,
+            ..self
+        }
+    }
+
+    /// Sets the length of the chains of growing goals (like `T: Foo`,
+    /// `Vec<T>: Foo`, `Vec<Vec<T>>: Foo`) the solver detects, counting the
+    /// subgoal which completes the chain. Such a subgoal is given up on,
+    /// making the answers which depend on it ambiguous, without waiting
+    /// for it to grow past `max_size`; `try_solve` reports the chain.
+    /// Detection is disabled by default, and the limit must be at least 2.
+    pub fn with_growth_limit(self, growth_limit: Option<usize>) -> Self {
+        assert!(growth_limit.is_none_or(|limit| limit >= 2));
+        Self {
+            growth_limit,
+            ..self
+        }
+    }
+
+    /// Reports the `SolverEvent`s of the solver to `event_sink`, as they
+    /// happen: the tables it creates, the clauses it tries, the answers
+    /// they produce, and the floundering and cycles it runs into.
+    pub fn with_event_sink(mut self, event_sink: SolverEventSink<I>) -> Self {
+        self.forest.event_sink = Some(event_sink);
+        self
+    }
+
+    fn ops<'p>(&self, program: &'p dyn RustIrDatabase<I>) -> SlgContextOps<'p, I> {
+        SlgContextOps::new(program, self.max_size, self.expected_answers)
+            .with_max_candidates(self.max_candidates)
+            .with_leak_check(self.leak_check)
+            .with_answer_subsumption(self.answer_subsumption)
+            .with_constraint_normalization(self.constraint_normalization)
+            .with_growth_limit(self.growth_limit)
+    }
+}
+
+/// A table of the SLG solver, as `SLGSolver::tables` shows it to
+/// debuggers.
+#[derive(Clone, Debug)]
+pub struct TableSummary<I: Interner> {
+    pub goal: UCanonical<InEnvironment<Goal<I>>>,
+    pub answers: Vec<Answer<I>>,
+    /// The number of strands left to pursue, not counting those being
+    /// pursued on the stack of a search.
+    pub strands: usize,
+    pub floundered: bool,
+    pub coinductive: bool,
+}
+
+impl<I: Interner> SLGSolver<I> {
+    /// The tables the solver has built so far, by index, for debuggers.
+    pub fn tables(&self) -> Vec<TableSummary<I>> {
+        self.forest
+            .tables
+            .iter()
+            .map(|table| TableSummary {
+                goal: table.table_goal.clone(),
+                answers: table.answers().to_vec(),
+                strands: table.strands().count(),
+                floundered: table.is_floundered(),
+                coinductive: table.coinductive_goal,
+            })
+            .collect()
+    }
+}
+
+/// A chain of goals which keep growing as they recurse, so that solving
+/// them would never terminate. Each goal strictly embeds the one before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NonTermination<I: Interner> {
+    pub goals: Vec<UCanonical<InEnvironment<Goal<I>>>>,
+}
+
+impl<I: Interner> SLGSolver<I> {
+    /// Solves `goal` like `Solver::solve`, but if the solution is
+    /// ambiguous because a chain of growing goals was given up on (see
+    /// `with_growth_limit`), returns that chain instead.
+    pub fn try_solve(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Result<Option<Solution<I>>, NonTermination<I>> {
+        let solution = self.solve(program, goal);
+        if !matches!(solution, Some(Solution::Ambig(..))) {
+            return Ok(solution);
+        }
+
+        // The chain may have been found while solving `goal`, or while
+        // solving an earlier goal whose tables `goal` reuses.
+        let tables = &self.forest.tables;
+        let reachable = match tables.index_of(goal) {
+            Some(table) => self.forest.dependency_tree(table),
+            None => return Ok(solution),
+        };
+        match self
+            .forest
+            .growing_chains
+            .iter()
+            .find(|chain| reachable.contains_key(&chain[0]))
+        {
+            Some(chain) => Err(NonTermination {
+                goals: chain
+                    .iter()
+                    .map(|&table| tables[table].table_goal.clone())
+                    .collect(),
+            }),
+            None => Ok(solution),
         }
     }
 }
@@ -36,17 +200,110 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<Solution<I>> {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
+        let ops = self.ops(program);
         ops.make_solution(goal, self.forest.iter_answers(&ops, goal), || true)
     }
 
+    fn solve_explained(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Result<Solution<I>, NoSolutionCause<I>> {
+        match self.solve(program, goal) {
+            Some(solution) => Ok(solution),
+            None => {
+                let tables = &self.forest.tables;
+                Err(match tables.index_of(goal) {
+                    Some(table) => tables[table].no_solution_cause().clone(),
+                    None => NoSolutionCause::Unknown,
+                })
+            }
+        }
+    }
+
+    fn solve_with_options(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        options: &GoalOptions<I>,
+    ) -> Option<Solution<I>> {
+        let max_size = options.max_size.unwrap_or(self.max_size);
+        let ops = self
+            .ops(program)
+            .with_max_size(max_size)
+            .with_max_answers(options.max_answers)
+            .with_coinductive_overrides(options.coinductive_overrides.clone());
+        let deadline = options.deadline();
+        let should_continue = || deadline.is_none_or(|deadline| !deadline.is_reached());
+
+        // Tables built with other limits would give other answers to later
+        // goals, so they are kept apart. A timeout only stops the search
+        // early, which leaves the tables consistent.
+        if max_size != self.max_size || !options.coinductive_overrides.is_empty() {
+            let mut forest = Forest::new();
+            forest.event_sink = self.forest.event_sink.take();
+            let solution =
+                ops.make_solution(goal, forest.iter_answers(&ops, goal), should_continue);
+            self.forest.event_sink = forest.event_sink.take();
+            solution
+        } else {
+            ops.make_solution(goal, self.forest.iter_answers(&ops, goal), should_continue)
+        }
+    }
+
+    fn solve_or_overflow(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Result<Option<Solution<I>>, Overflow<I>> {
+        let solution = self.solve(program, goal);
+        if !matches!(solution, Some(Solution::Ambig(..))) {
+            return Ok(solution);
+        }
+
+        // As in `try_solve`, the overflow may have happened while solving
+        // an earlier goal. Its stack then starts with the path from `goal`
+        // to the first table of the stack that `goal` depends on.
+        let tables = &self.forest.tables;
+        let tree = match tables.index_of(goal) {
+            Some(table) => self.forest.dependency_tree(table),
+            None => return Ok(solution),
+        };
+        match self
+            .forest
+            .overflows
+            .iter()
+            .find(|(stack, _)| tree.contains_key(stack.last().unwrap()))
+        {
+            Some((stack, overflowed)) => {
+                let start = stack
+                    .iter()
+                    .position(|table| tree.contains_key(table))
+                    .unwrap();
+                let mut path = vec![stack[start]];
+                while let Some(&Some(parent)) = tree.get(path.last().unwrap()) {
+                    path.push(parent);
+                }
+                path.reverse();
+                Err(Overflow::new(
+                    path.iter()
+                        .chain(&stack[start + 1..])
+                        .map(|&table| tables[table].table_goal.clone())
+                        .chain(Some(overflowed.clone()))
+                        .collect(),
+                ))
+            }
+            None => Ok(solution),
+        }
+    }
+
     fn solve_limited(
         &mut self,
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
-        should_continue: &dyn std::ops::Fn() -> bool,
+        should_continue: &dyn core::ops::Fn() -> bool,
     ) -> Option<Solution<I>> {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
+        let ops = self.ops(program);
         ops.make_solution(goal, self.forest.iter_answers(&ops, goal), should_continue)
     }
 
@@ -56,23 +313,12 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
+        let ops = self.ops(program);
         let mut answers = self.forest.iter_answers(&ops, goal);
         loop {
             let subst = match answers.next_answer(|| true) {
                 AnswerResult::Answer(answer) => {
-                    if !answer.ambiguous {
-                        SubstitutionResult::Definite(answer.subst)
-                    } else if answer
-                        .subst
-                        .value
-                        .subst
-                        .is_identity_subst(ops.program().interner())
-                    {
-                        SubstitutionResult::Floundered
-                    } else {
-                        SubstitutionResult::Ambiguous(answer.subst)
-                    }
+                    substitution_result(answer, ops.program().interner())
                 }
                 AnswerResult::Floundered => SubstitutionResult::Floundered,
                 AnswerResult::NoMoreSolutions => {
@@ -86,4 +332,96 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
             }
         }
     }
+
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            tables: self.forest.tables.iter().count(),
+            answers: Some(
+                self.forest
+                    .tables
+                    .iter()
+                    .map(|table| table.num_answers())
+                    .sum(),
+            ),
+            clauses: self.forest.clauses,
+            duplicate_clauses: self.forest.duplicate_clauses,
+        }
+    }
+
+    fn graphviz(&self, program: &dyn RustIrDatabase<I>) -> Option<String> {
+        Some(self.forest.graphviz(program.interner()))
+    }
+
+    fn step(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<SolverStep<I>> {
+        let ops = self.ops(program);
+        let table = self
+            .forest
+            .get_or_create_table_for_ucanonical_goal(&ops, goal.clone());
+        Some(match self.forest.step(&ops, table) {
+            None | Some(AnswerResult::QuantumExceeded) => SolverStep::Searching,
+            Some(AnswerResult::Answer(answer)) => {
+                SolverStep::Answer(substitution_result(answer, program.interner()))
+            }
+            Some(AnswerResult::Floundered) => SolverStep::Answer(SubstitutionResult::Floundered),
+            Some(AnswerResult::NoMoreSolutions) => SolverStep::NoMoreSolutions,
+        })
+    }
+
+    fn step_stack(&self, program: &dyn RustIrDatabase<I>) -> Vec<StackFrame<I>> {
+        let interner = program.interner();
+        self.forest
+            .step_stack()
+            .map(|(table, strand)| StackFrame {
+                goal: self.forest.tables[table].table_goal.clone(),
+                active_strand: strand.map(|strand| {
+                    let ex_clause = &strand.value.ex_clause;
+                    ActiveStrand {
+                        binders: strand.binders.clone(),
+                        subst: ex_clause.subst.clone(),
+                        subgoals: ex_clause
+                            .subgoals
+                            .iter()
+                            .map(|subgoal| match subgoal {
+                                Literal::Positive(goal) => goal.clone(),
+                                Literal::Negative(goal) => InEnvironment::new(
+                                    &goal.environment,
+                                    GoalData::Not(goal.goal.clone()).intern(interner),
+                                ),
+                            })
+                            .collect(),
+                        selected_subgoal: strand
+                            .value
+                            .selected_subgoal
+                            .as_ref()
+                            .map(|selected| selected.subgoal_index),
+                        ambiguous: ex_clause.ambiguous,
+                        answer_sources: strand
+                            .value
+                            .answer_sources
+                            .iter()
+                            .map(|&(table, answer)| (table.value, answer.value))
+                            .collect(),
+                    }
+                }),
+            })
+            .collect()
+    }
+}
+
+/// The substitution of `answer`, as `Solver::solve_multiple` gives it.
+fn substitution_result<I: Interner>(
+    answer: CompleteAnswer<I>,
+    interner: I,
+) -> SubstitutionResult<Canonical<ConstrainedSubst<I>>> {
+    if !answer.ambiguous {
+        SubstitutionResult::Definite(answer.subst)
+    } else if answer.subst.value.subst.is_identity_subst(interner) {
+        SubstitutionResult::Floundered
+    } else {
+        SubstitutionResult::Ambiguous(answer.subst)
+    }
 }
@@ -2,8 +2,10 @@ use crate::index_struct;
 use crate::strand::CanonicalStrand;
 use crate::tables::Tables;
 use crate::{Minimums, TableIndex, TimeStamp};
-use std::fmt;
-use std::ops::{Index, IndexMut, Range};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Index, IndexMut, Range};
 
 use chalk_ir::interner::Interner;
 
@@ -105,6 +107,16 @@ impl<I: Interner> Stack<I> {
             .next()
     }
 
+    /// The entries of the stack, from the bottom up.
+    pub(super) fn entries(&self) -> impl Iterator<Item = &StackEntry<I>> + '_ {
+        self.stack.iter()
+    }
+
+    /// The tables on the stack, from the top down.
+    pub(super) fn tables_from_top(&self) -> impl Iterator<Item = TableIndex> + '_ {
+        self.stack.iter().rev().map(|entry| entry.table)
+    }
+
     pub(super) fn top_of_stack_from(&self, depth: StackIndex) -> Range<StackIndex> {
         depth..StackIndex::from(self.stack.len())
     }
@@ -1,9 +1,10 @@
 use crate::table::AnswerIndex;
 use crate::{ExClause, TableIndex, TimeStamp};
-use std::fmt::Debug;
+use alloc::vec::Vec;
+use core::fmt::Debug;
 
 use chalk_derive::HasInterner;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::fold::{FallibleFolder, Fold};
 use chalk_ir::interner::Interner;
 use chalk_ir::{Canonical, DebruijnIndex, UniverseMap};
 
@@ -15,6 +16,10 @@ pub(crate) struct Strand<I: Interner> {
     pub(crate) selected_subgoal: Option<SelectedSubgoal>,
 
     pub(crate) last_pursued_time: TimeStamp,
+
+    /// The answers applied to positive subgoals so far, with the tables
+    /// they come from, in the order they were applied.
+    pub(crate) answer_sources: Vec<(TableIndex, AnswerIndex)>,
 }
 
 pub(crate) type CanonicalStrand<I> = Canonical<Strand<I>>;
@@ -37,15 +42,16 @@ pub(crate) struct SelectedSubgoal {
 
 impl<I: Interner> Fold<I> for Strand<I> {
     type Result = Strand<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         Ok(Strand {
-            ex_clause: self.ex_clause.fold_with(folder, outer_binder)?,
+            ex_clause: self.ex_clause.try_fold_with(folder, outer_binder)?,
             last_pursued_time: self.last_pursued_time,
             selected_subgoal: self.selected_subgoal,
+            answer_sources: self.answer_sources,
         })
     }
 }
@@ -0,0 +1,177 @@
+//! Sharing one SLG forest between threads.
+
+use crate::solve::SLGSolver;
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_ir::interner::Interner;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::{RustIrDatabase, Solution, Solver, SolverStats};
+use rustc_hash::{FxHashMap, FxHasher};
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, RwLock};
+
+type UCanonicalGoal<I> = UCanonical<InEnvironment<Goal<I>>>;
+
+/// Identifies a program to a [`SyncForest`], as the same goal can have
+/// different solutions in different programs. The caller gives each
+/// program a different id, e.g. a generation bumped whenever its program
+/// changes, and never reuses the id of a program for another one.
+pub type ProgramId = u64;
+
+/// The solution of a root goal, computed at most once.
+type SolutionSlot<I> = Arc<OnceLock<Option<Solution<I>>>>;
+
+/// The solution slots of the root goals of the program solved last.
+struct Solutions<I: Interner> {
+    program_id: Option<ProgramId>,
+    slots: FxHashMap<UCanonicalGoal<I>, SolutionSlot<I>>,
+}
+
+/// A solver, and the program its tables were built for, if any.
+struct Shard<I: Interner> {
+    solver: SLGSolver<I>,
+    program_id: Option<ProgramId>,
+}
+
+/// An SLG solver which can be shared by reference between threads, for
+/// hosts which answer queries from several threads and want them to reuse
+/// each other's tables.
+///
+/// Each root goal gets its own lock, so that a thread asking for a goal
+/// which is already solved, or being solved by another thread, only waits
+/// on that goal. The locking of the tables is coarse though: they are
+/// split into shards, each being a whole solver behind one lock, and a
+/// root goal is always solved in the shard its hash picks. The strands of
+/// a goal read and extend the tables of all its subgoals, so threads
+/// solving goals of the same shard take turns, even when their goals share
+/// no table.
+///
+/// The solutions and tables are only valid for one program: they are
+/// dropped when a goal of another program is solved.
+pub struct SyncForest<I: Interner> {
+    shards: Vec<Mutex<Shard<I>>>,
+    solutions: RwLock<Solutions<I>>,
+}
+
+impl<I: Interner> SyncForest<I> {
+    /// Shares `solver`, with its configuration and its tables, as a single
+    /// shard.
+    pub fn new(solver: SLGSolver<I>) -> Self {
+        Self::with_shards(vec![solver])
+    }
+
+    /// Shares `solvers`, each being one shard. They should have the same
+    /// configuration, or the solution of a goal depends on its shard.
+    pub fn with_shards(solvers: Vec<SLGSolver<I>>) -> Self {
+        assert!(!solvers.is_empty(), "a forest needs at least one shard");
+        SyncForest {
+            shards: solvers
+                .into_iter()
+                .map(|solver| {
+                    Mutex::new(Shard {
+                        solver,
+                        program_id: None,
+                    })
+                })
+                .collect(),
+            solutions: RwLock::new(Solutions {
+                program_id: None,
+                slots: FxHashMap::default(),
+            }),
+        }
+    }
+
+    /// Solves `goal` in `program`, identified by `program_id`, like
+    /// `Solver::solve`. The solution is remembered, so later calls with the
+    /// same program and goal, from any thread, return it without running
+    /// the engine, until a goal of another program is solved.
+    pub fn solve(
+        &self,
+        program: &dyn RustIrDatabase<I>,
+        program_id: ProgramId,
+        goal: &UCanonicalGoal<I>,
+    ) -> Option<Solution<I>> {
+        let slot = self.slot(program_id, goal);
+        slot.get_or_init(|| {
+            let mut shard = self.lock(self.shard_of(goal));
+            let previous = shard.program_id.replace(program_id);
+            if matches!(previous, Some(previous) if previous != program_id) {
+                shard.solver.forest.clear();
+            }
+            shard.solver.solve(program, goal)
+        })
+        .clone()
+    }
+
+    /// Statistics about the shared tables, summed over the shards.
+    pub fn stats(&self) -> SolverStats {
+        (0..self.shards.len())
+            .map(|shard| self.lock(shard).solver.stats())
+            .fold(SolverStats::default(), |sum, stats| SolverStats {
+                tables: sum.tables + stats.tables,
+                answers: Some(sum.answers.unwrap_or(0) + stats.answers.unwrap_or(0)),
+                clauses: sum.clauses + stats.clauses,
+                duplicate_clauses: sum.duplicate_clauses + stats.duplicate_clauses,
+            })
+    }
+
+    /// Unwraps the solvers of the shards, with the tables built by every
+    /// thread.
+    pub fn into_inner(self) -> Vec<SLGSolver<I>> {
+        self.shards
+            .into_iter()
+            .map(|shard| {
+                shard
+                    .into_inner()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .solver
+            })
+            .collect()
+    }
+
+    fn shard_of(&self, goal: &UCanonicalGoal<I>) -> usize {
+        let mut hasher = FxHasher::default();
+        goal.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    /// The slot of `goal`, dropping those of the previous program if
+    /// `program_id` is another one.
+    fn slot(&self, program_id: ProgramId, goal: &UCanonicalGoal<I>) -> SolutionSlot<I> {
+        let solutions = self
+            .solutions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        if solutions.program_id == Some(program_id) {
+            if let Some(slot) = solutions.slots.get(goal) {
+                return slot.clone();
+            }
+        }
+        drop(solutions);
+        let mut solutions = self
+            .solutions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if solutions.program_id.replace(program_id) != Some(program_id) {
+            solutions.slots.clear();
+        }
+        solutions.slots.entry(goal.clone()).or_default().clone()
+    }
+
+    /// A goal which panics (e.g. because of a negative cycle) unwinds the
+    /// stack of the forest before the lock is released, so the tables stay
+    /// usable for the other threads.
+    fn lock(&self, shard: usize) -> MutexGuard<'_, Shard<I>> {
+        self.shards[shard]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<I: Interner> fmt::Debug for SyncForest<I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "SyncForest")
+    }
+}
@@ -1,13 +1,16 @@
 use crate::index_struct;
 use crate::strand::CanonicalStrand;
-use crate::{Answer, AnswerMode};
-use rustc_hash::FxHashMap;
-use std::collections::hash_map::Entry;
-use std::collections::VecDeque;
-use std::mem;
+use crate::{Answer, AnswerMode, TableIndex};
+use crate::{FxHashMap, FxHashSet};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::mem;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use hashbrown::hash_map::Entry;
 
 use chalk_ir::interner::Interner;
 use chalk_ir::{AnswerSubst, Canonical, Goal, InEnvironment, UCanonical};
+use chalk_solve::NoSolutionCause;
 use tracing::{debug, info, instrument};
 
 #[derive(Debug)]
@@ -24,6 +27,10 @@ pub(crate) struct Table<I: Interner> {
     /// enough types specified for us to solve.
     floundered: bool,
 
+    /// The first known cause of the strands of this table that failed,
+    /// which is why the table has no answers if it ends up with none.
+    no_solution_cause: NoSolutionCause<I>,
+
     /// Stores the answers that we have found thus far. When we get a request
     /// for an answer N, we will first check this vector.
     answers: Vec<Answer<I>>,
@@ -40,16 +47,30 @@ pub(crate) struct Table<I: Interner> {
     /// to do so though it can result in more answers than we need.
     answers_hash: FxHashMap<Canonical<AnswerSubst<I>>, bool>,
 
+    /// The number of answers which may have been read (by `answer`): the
+    /// answers from this index on can still be dropped when subsumed by a
+    /// new answer, as nothing can have used them yet. Atomic rather than a
+    /// `Cell` so that tables, and so forests, can be shared across threads.
+    answers_read: AtomicUsize,
+
     /// Stores the active strands that we can "pull on" to find more
     /// answers.
     strands: VecDeque<CanonicalStrand<I>>,
 
+    /// The tables of the subgoals strands of this table have selected,
+    /// and whether the subgoal was negative. Only used to draw the
+    /// forest (see `Forest::graphviz`).
+    dependencies: FxHashSet<(TableIndex, bool)>,
+
     pub(crate) answer_mode: AnswerMode,
 }
 
+/// Tells whether an answer subsumes another one (see `Table::push_answer`).
+pub(crate) type Subsumes<'a, I> = &'a dyn Fn(&Answer<I>, &Answer<I>) -> bool;
+
 index_struct! {
     pub(crate) struct AnswerIndex {
-        value: usize,
+        pub(crate) value: usize,
     }
 }
 
@@ -63,8 +84,11 @@ impl<I: Interner> Table<I> {
             coinductive_goal,
             answers: Vec::new(),
             floundered: false,
+            no_solution_cause: NoSolutionCause::Unknown,
             answers_hash: FxHashMap::default(),
+            answers_read: AtomicUsize::new(0),
             strands: VecDeque::new(),
+            dependencies: FxHashSet::default(),
             answer_mode: AnswerMode::Complete,
         }
     }
@@ -113,8 +137,26 @@ impl<I: Interner> Table<I> {
         self.floundered
     }
 
+    /// Records that a strand of the table failed because of `cause`, which
+    /// is kept unless a cause is already known.
+    pub(crate) fn record_no_solution_cause(&mut self, cause: NoSolutionCause<I>) {
+        self.no_solution_cause =
+            mem::replace(&mut self.no_solution_cause, NoSolutionCause::Unknown).or(cause);
+    }
+
+    /// Why the table has no answers, if it has none.
+    pub(crate) fn no_solution_cause(&self) -> &NoSolutionCause<I> {
+        &self.no_solution_cause
+    }
+
     /// Adds `answer` to our list of answers, unless it is already present.
     ///
+    /// If `subsumes` is given, it tells whether an answer subsumes another
+    /// one (is more general). `answer` is then not added if an existing
+    /// answer subsumes it, and replaces the existing answers it subsumes
+    /// which have not been read yet. (The ones which have been read must
+    /// stay, as the answer indices of their readers would shift.)
+    ///
     /// Returns true if `answer` was added.
     ///
     /// # Panics
@@ -123,14 +165,34 @@ impl<I: Interner> Table<I> {
     /// tests trigger this case, and assumptions upstream assume that when
     /// `true` is returned here, that a *new* answer was added (instead of an)
     /// existing answer replaced.
-    #[instrument(level = "debug", skip(self))]
-    pub(super) fn push_answer(&mut self, answer: Answer<I>) -> Option<AnswerIndex> {
+    #[instrument(level = "debug", skip(self, subsumes))]
+    pub(super) fn push_answer(
+        &mut self,
+        answer: Answer<I>,
+        subsumes: Option<Subsumes<'_, I>>,
+    ) -> Option<AnswerIndex> {
         assert!(!self.floundered);
         debug!(
             "pre-existing entry: {:?}",
             self.answers_hash.get(&answer.subst)
         );
 
+        if let Some(subsumes) = subsumes {
+            if self.answers.iter().any(|old| subsumes(old, &answer)) {
+                debug!("answer subsumed by an existing answer");
+                return None;
+            }
+            let unread = self.answers.split_off(*self.answers_read.get_mut());
+            for old in unread {
+                if subsumes(&answer, &old) {
+                    debug!(?old, "answer subsumes an unread answer");
+                    self.answers_hash.remove(&old.subst);
+                } else {
+                    self.answers.push(old);
+                }
+            }
+        }
+
         let added = match self.answers_hash.entry(answer.subst.clone()) {
             Entry::Vacant(entry) => {
                 entry.insert(answer.ambiguous);
@@ -160,7 +222,27 @@ impl<I: Interner> Table<I> {
     }
 
     pub(super) fn answer(&self, index: AnswerIndex) -> Option<&Answer<I>> {
-        self.answers.get(index.value)
+        let answer = self.answers.get(index.value)?;
+        self.answers_read
+            .fetch_max(index.value + 1, Ordering::Relaxed);
+        Some(answer)
+    }
+
+    pub(crate) fn add_dependency(&mut self, table: TableIndex, negative: bool) {
+        self.dependencies.insert((table, negative));
+    }
+
+    pub(super) fn dependencies(&self) -> impl Iterator<Item = (TableIndex, bool)> + '_ {
+        self.dependencies.iter().copied()
+    }
+
+    /// The answers of the table, without marking them as read.
+    pub(super) fn answers(&self) -> &[Answer<I>] {
+        &self.answers
+    }
+
+    pub(super) fn num_answers(&self) -> usize {
+        self.answers.len()
     }
 
     pub(super) fn next_answer_index(&self) -> AnswerIndex {
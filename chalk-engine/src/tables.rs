@@ -1,7 +1,8 @@
 use crate::table::Table;
+use crate::FxHashMap;
 use crate::TableIndex;
-use rustc_hash::FxHashMap;
-use std::ops::{Index, IndexMut};
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut};
 
 use chalk_ir::interner::Interner;
 use chalk_ir::{Goal, InEnvironment, UCanonical};
@@ -40,6 +41,10 @@ impl<I: Interner> Tables<I> {
         index
     }
 
+    pub(super) fn iter(&self) -> impl Iterator<Item = &Table<I>> {
+        self.tables.iter()
+    }
+
     pub(super) fn index_of(
         &self,
         literal: &UCanonical<InEnvironment<Goal<I>>>,
@@ -0,0 +1,252 @@
+//! Solves each goal of a list in a program, and reports the results as
+//! text, or as JSON for other tools to read. Used by `chalk solve` and by
+//! the browser bindings in `chalk-wasm`.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::query::LoweringDatabase;
+use chalk_ir::{Canonical, GenericArg};
+use chalk_solve::display::DisplayWithDb;
+use chalk_solve::ext::*;
+use chalk_solve::{AmbiguityCause, Guidance, RustIrDatabase, Solution, SolverStats};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The result of one goal.
+struct Report<'g> {
+    /// The line the goal starts on in the goals file.
+    line: usize,
+    goal: &'g str,
+    outcome: Result<Solved, String>,
+}
+
+struct Solved {
+    solution: Option<Solution<ChalkIr>>,
+    /// Not measured on `wasm32`, where there is no clock (`Instant::now`
+    /// panics).
+    time: Option<Duration>,
+    /// The statistics of the solver after solving the goal; the solver
+    /// keeps its tables from one goal to the next.
+    stats: SolverStats,
+}
+
+/// Solves `goals`, given with the line each starts on, in the program of
+/// `db`, and returns the report in `format`. Goals that don't parse or
+/// lower are reported as errors.
+///
+/// Must be called while the program of `db` is the current one (see
+/// `ChalkDatabase::with_program`), for the names in the report.
+pub fn solve(
+    db: &ChalkDatabase,
+    program: &str,
+    solver: &str,
+    goals: &[(usize, String)],
+    format: Format,
+) -> String {
+    let reports: Vec<_> = goals
+        .iter()
+        .map(|(line, goal)| Report {
+            line: *line,
+            goal,
+            outcome: solve_goal(db, goal),
+        })
+        .collect();
+    match format {
+        Format::Text => text(db, &reports),
+        Format::Json => json(db, program, solver, &reports),
+    }
+}
+
+fn solve_goal(db: &ChalkDatabase, goal: &str) -> Result<Solved, String> {
+    let goal = db
+        .parse_and_lower_goal(goal)
+        .map_err(|e| e.to_string())?
+        .into_peeled_goal(db.interner());
+    let (solution, time) = timed(|| db.solve(&goal));
+    let stats = db.solver().lock().unwrap().stats();
+    Ok(Solved {
+        solution,
+        time,
+        stats,
+    })
+}
+
+fn timed<T>(op: impl FnOnce() -> T) -> (T, Option<Duration>) {
+    if cfg!(target_arch = "wasm32") {
+        return (op(), None);
+    }
+    let start = Instant::now();
+    let result = op();
+    (result, Some(start.elapsed()))
+}
+
+fn text(db: &ChalkDatabase, reports: &[Report<'_>]) -> String {
+    let mut out = String::new();
+    for report in reports {
+        let _ = writeln!(out, "?- {}", report.goal);
+        let _ = match &report.outcome {
+            Ok(Solved { solution, .. }) => match solution {
+                Some(solution) => writeln!(out, "{}\n", solution.display_with(db)),
+                None => writeln!(out, "No possible solution.\n"),
+            },
+            Err(error) => writeln!(out, "error: {}\n", error),
+        };
+    }
+    out
+}
+
+fn json(db: &ChalkDatabase, program: &str, solver: &str, reports: &[Report<'_>]) -> String {
+    let goals: Vec<_> = reports
+        .iter()
+        .map(|report| {
+            let mut fields = Map::new();
+            fields.insert("line".to_string(), report.line.into());
+            fields.insert("goal".to_string(), report.goal.into());
+            match &report.outcome {
+                Ok(solved) => {
+                    fields.extend(solution_fields(db, &solved.solution));
+                    if let Some(time) = solved.time {
+                        // In milliseconds, to the microsecond.
+                        let time_ms = (time.as_secs_f64() * 1e6).round() / 1e3;
+                        fields.insert("time_ms".to_string(), time_ms.into());
+                    }
+                    let stats = solved.stats;
+                    let mut stats_fields = Map::new();
+                    stats_fields.insert("tables".to_string(), stats.tables.into());
+                    if let Some(answers) = stats.answers {
+                        stats_fields.insert("answers".to_string(), answers.into());
+                    }
+                    stats_fields.insert("clauses".to_string(), stats.clauses.into());
+                    stats_fields.insert(
+                        "duplicate_clauses".to_string(),
+                        stats.duplicate_clauses.into(),
+                    );
+                    fields.insert("stats".to_string(), Value::Object(stats_fields));
+                }
+                Err(error) => {
+                    fields.insert("result".to_string(), "error".into());
+                    fields.insert("error".to_string(), error.as_str().into());
+                }
+            }
+            Value::Object(fields)
+        })
+        .collect();
+    let report = json!({
+        "program": program,
+        "solver": solver,
+        "goals": goals,
+    });
+    let mut out = serde_json::to_string_pretty(&report).expect("reports are always serializable");
+    out.push('\n');
+    out
+}
+
+/// The fields describing `solution`: its kind, the values of the goal's
+/// variables, and for unique solutions, the lifetime constraints.
+fn solution_fields(db: &ChalkDatabase, solution: &Option<Solution<ChalkIr>>) -> Map<String, Value> {
+    let mut fields = Map::new();
+    match solution {
+        Some(Solution::Unique(subst)) => {
+            fields.insert("result".to_string(), "unique".into());
+            fields.insert(
+                "substitution".to_string(),
+                args(db, subst.value.subst.as_slice(ChalkIr)),
+            );
+            let constraints: Vec<_> = subst
+                .value
+                .constraints
+                .iter(ChalkIr)
+                .map(|constraint| constraint.display_with(db).to_string())
+                .collect();
+            fields.insert("constraints".to_string(), constraints.into());
+        }
+        Some(Solution::Ambig(guidance, cause, _)) => {
+            let (kind, subst) = match guidance {
+                Guidance::Definite(subst) => ("definite", Some(subst)),
+                Guidance::Suggested(subst) => ("suggested", Some(subst)),
+                Guidance::Unknown => ("unknown", None),
+            };
+            fields.insert("result".to_string(), "ambiguous".into());
+            fields.insert("guidance".to_string(), kind.into());
+            if let Some(Canonical { value, .. }) = subst {
+                fields.insert(
+                    "substitution".to_string(),
+                    args(db, value.as_slice(ChalkIr)),
+                );
+            }
+            let cause = match cause {
+                AmbiguityCause::MultipleCandidates { count } => {
+                    fields.insert("candidates".to_string(), (*count).into());
+                    "multiple-candidates"
+                }
+                AmbiguityCause::Overflow => "overflow",
+                AmbiguityCause::FlounderedNegative => "floundered-negative",
+                AmbiguityCause::Truncated => "truncated",
+            };
+            fields.insert("cause".to_string(), cause.into());
+        }
+        None => {
+            fields.insert("result".to_string(), "no-solution".into());
+        }
+    }
+    fields
+}
+
+/// A JSON array of `args`, as strings.
+fn args(db: &ChalkDatabase, args: &[GenericArg<ChalkIr>]) -> Value {
+    args.iter()
+        .map(|arg| Value::from(arg.display_with(db).to_string()))
+        .collect()
+}
+
+/// Splits `text` into entries, each on one line, or continued on the
+/// following lines until its brackets are balanced. Blank lines and lines
+/// starting with `//` are skipped. Each entry is given with the line it
+/// starts on, counting from 1.
+pub fn entries(text: &str) -> Vec<(usize, String)> {
+    let mut entries: Vec<(usize, String)> = Vec::new();
+    let mut continued = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if continued {
+            let (_, entry) = entries.last_mut().unwrap();
+            entry.push('\n');
+            entry.push_str(line);
+            continued = is_incomplete(entry);
+        } else if line.is_empty() || line.starts_with("//") {
+            // Skip blank lines and comments.
+        } else {
+            entries.push((index + 1, line.to_string()));
+            continued = is_incomplete(line);
+        }
+    }
+    entries
+}
+
+/// Whether `text` opens more brackets than it closes, so that the entry
+/// continues on the next line.
+pub fn is_incomplete(text: &str) -> bool {
+    let depth = text.chars().fold(0, |depth, c| match c {
+        '(' | '[' | '{' => depth + 1,
+        ')' | ']' | '}' => depth - 1,
+        _ => depth,
+    });
+    depth > 0
+}
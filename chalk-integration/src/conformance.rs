@@ -0,0 +1,213 @@
+//! Compares chalk with rustc on a corpus of rustc UI tests: each test is
+//! read as Rust source (see `rust_source`), and chalk's verdict on whether
+//! it compiles is compared with the outcome the test expects of rustc.
+//! Used by `chalk conformance`.
+//!
+//! Chalk rejects a test if its program fails the coherence or
+//! well-formedness checks, or if one of the obligations of its function
+//! bodies does not have a unique solution. Tests which use something the
+//! Rust frontend does not support, or which refer to items they do not
+//! define (like those of the standard library), are not expressible, and
+//! are left out of the comparison.
+
+use crate::db::ChalkDatabase;
+use crate::lowering::lower_goal;
+use crate::query::LoweringDatabase;
+use crate::rust_source::parse_rust_obligations;
+use crate::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Whether rustc is expected to accept a test.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    Pass,
+    Fail,
+}
+
+impl Expectation {
+    /// The expectation of the test `text`, from its header: tests marked
+    /// `// check-pass`, `// build-pass` or `// run-pass` (or with `//@`)
+    /// compile, and the others, as in rustc's UI test suite, do not.
+    pub fn of_test(text: &str) -> Expectation {
+        let passes = text.lines().any(|line| {
+            let line = line.trim();
+            let directive = line
+                .strip_prefix("//@")
+                .or_else(|| line.strip_prefix("//"))
+                .map(str::trim);
+            matches!(directive, Some("check-pass" | "build-pass" | "run-pass"))
+        });
+        if passes {
+            Expectation::Pass
+        } else {
+            Expectation::Fail
+        }
+    }
+}
+
+/// What chalk makes of a test.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    /// The test does not compile, for the given reason.
+    Fail(String),
+    /// The test cannot be expressed, for the given reason.
+    Unsupported(String),
+}
+
+/// A test of the corpus, with what rustc and chalk make of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Case {
+    /// The file name of the test.
+    pub name: String,
+    pub expected: Expectation,
+    pub verdict: Verdict,
+}
+
+impl Case {
+    /// Whether chalk agrees with rustc on the test, or `None` if the test
+    /// is not expressible.
+    pub fn agrees(&self) -> Option<bool> {
+        match (&self.verdict, self.expected) {
+            (Verdict::Unsupported(_), _) => None,
+            (Verdict::Pass, expected) => Some(expected == Expectation::Pass),
+            (Verdict::Fail(_), expected) => Some(expected == Expectation::Fail),
+        }
+    }
+}
+
+/// The cases of a corpus, sorted by name. Displayed as a table, followed by
+/// a summary.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub cases: Vec<Case>,
+}
+
+impl Report {
+    /// The cases on which chalk does not agree with rustc.
+    pub fn divergences(&self) -> impl Iterator<Item = &Case> {
+        self.cases
+            .iter()
+            .filter(|case| case.agrees() == Some(false))
+    }
+
+    /// The cases which are not expressible.
+    pub fn unsupported(&self) -> impl Iterator<Item = &Case> {
+        self.cases.iter().filter(|case| case.agrees().is_none())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .cases
+            .iter()
+            .map(|case| case.name.len())
+            .chain(Some("test".len()))
+            .max()
+            .unwrap();
+        writeln!(
+            f,
+            "{:width$}  rustc  chalk  result    note",
+            "test",
+            width = width
+        )?;
+        for case in &self.cases {
+            let expected = match case.expected {
+                Expectation::Pass => "pass",
+                Expectation::Fail => "fail",
+            };
+            let (verdict, note) = match &case.verdict {
+                Verdict::Pass => ("pass", ""),
+                Verdict::Fail(reason) => ("fail", reason.as_str()),
+                Verdict::Unsupported(reason) => ("-", reason.as_str()),
+            };
+            let result = match case.agrees() {
+                Some(true) => "agree",
+                Some(false) => "DIVERGE",
+                None => "skipped",
+            };
+            let line = format!(
+                "{:width$}  {:5}  {:5}  {:8}  {}",
+                case.name,
+                expected,
+                verdict,
+                result,
+                note.lines().next().unwrap_or(""),
+                width = width
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        writeln!(
+            f,
+            "\n{} tests: {} agree, {} diverge, {} not expressible",
+            self.cases.len(),
+            self.cases
+                .iter()
+                .filter(|case| case.agrees() == Some(true))
+                .count(),
+            self.divergences().count(),
+            self.unsupported().count()
+        )
+    }
+}
+
+/// Checks each `.rs` file in `dir` with `solver_choice`.
+pub fn check_corpus(dir: &Path, solver_choice: SolverChoice) -> io::Result<Report> {
+    let mut cases = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            let text = fs::read_to_string(&path)?;
+            cases.push(Case {
+                name: path.file_name().unwrap().to_string_lossy().into_owned(),
+                expected: Expectation::of_test(&text),
+                verdict: check_test(&text, solver_choice),
+            });
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Report { cases })
+}
+
+/// Chalk's verdict on the test `text`.
+pub fn check_test(text: &str, solver_choice: SolverChoice) -> Verdict {
+    let obligations = match parse_rust_obligations(text, None) {
+        Ok(obligations) => obligations,
+        Err(error) => return Verdict::Unsupported(error.to_string()),
+    };
+    let db = ChalkDatabase::with_rust(text, solver_choice);
+    // Errors of lowering are mostly names the test does not define.
+    let program = match db.program_ir() {
+        Ok(program) => program,
+        Err(error) => return Verdict::Unsupported(error.to_string()),
+    };
+    if let Err(error) = db.checked_program() {
+        return Verdict::Fail(error.to_string());
+    }
+
+    for obligation in obligations {
+        let goal = match lower_goal(&obligation.goal, &program) {
+            Ok(goal) => goal.into_peeled_goal(db.interner()),
+            Err(error) => {
+                return Verdict::Unsupported(format!(
+                    "the obligation of `{}`: {}",
+                    obligation.callee, error
+                ))
+            }
+        };
+        let solution = db.with_program(|_| db.solve(&goal));
+        if !matches!(solution, Some(Solution::Unique(_))) {
+            return Verdict::Fail(format!(
+                "the obligation of `{}` on line {} does not hold",
+                obligation.callee, obligation.location.start.line
+            ));
+        }
+    }
+    Verdict::Pass
+}
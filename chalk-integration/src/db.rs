@@ -1,43 +1,199 @@
 use crate::{
     error::ChalkError,
-    interner::ChalkIr,
+    interner::{ChalkIr, ChalkPredicate},
     lowering::lower_goal,
-    program::Program,
+    program::{discriminant_type, impl_provided_for, retain_impls_matching, Program},
     query::{Lowering, LoweringDatabase},
-    tls, SolverChoice,
+    tls, SolverChoice, SourceLanguage,
 };
 use chalk_ir::{
-    AdtId, AssocTypeId, Binders, Canonical, CanonicalVarKinds, ClosureId, ConstrainedSubst,
-    Environment, FnDefId, GeneratorId, GenericArg, Goal, ImplId, InEnvironment, OpaqueTyId,
-    ProgramClause, ProgramClauses, Substitution, TraitId, Ty, TyKind, UCanonical,
+    AdtId, AssocTypeId, Binders, Canonical, ClosureId, ConstrainedSubst, CustomPredicate,
+    Environment, Floundered, FnDefId, GeneratorId, Goal, ImplId, InEnvironment, OpaqueTyId,
+    ProgramClause, ProgramClauses, Substitution, TraitId, TraitRef, Ty, TyKind, UCanonical,
     UnificationDatabase, Variances,
 };
+use chalk_solve::clauses::builder::ClauseBuilder;
+use chalk_solve::clauses::custom_builtins::{CustomBuiltin, CustomBuiltins};
 use chalk_solve::rust_ir::{
     AdtDatum, AdtRepr, AdtSizeAlign, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId,
     ClosureKind, FnDefDatum, FnDefInputsAndOutputDatum, GeneratorDatum, GeneratorWitnessDatum,
-    ImplDatum, OpaqueTyDatum, TraitDatum, WellKnownTrait,
+    ImplDatum, ImplType, InherentAssocTyDatum, OpaqueTyDatum, ProgramFeatures, TraitDatum,
+    WellKnownTrait,
 };
+use chalk_solve::simplified_type::{ImplFilter, SimplifiedType};
+use chalk_solve::variance::VarianceItem;
 use chalk_solve::{RustIrDatabase, Solution, SubstitutionResult};
 use salsa::Database;
+use std::collections::BTreeMap;
 use std::fmt;
-use std::sync::Arc;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 #[salsa::database(Lowering)]
 #[derive(Default)]
 pub struct ChalkDatabase {
     storage: salsa::Storage<Self>,
+    /// The queries executed since `record_executed_queries`, if it was
+    /// called.
+    executed_queries: Mutex<Option<Vec<String>>>,
+    custom_builtins: CustomBuiltins<ChalkIr>,
+    custom_predicate_clauses: Option<CustomPredicateClauses>,
 }
 
-impl Database for ChalkDatabase {}
+/// Pushes the clauses proving a custom predicate of `ChalkIr` (see
+/// `ChalkPredicate`).
+pub type CustomPredicateClauses = Arc<
+    dyn Fn(&mut ClauseBuilder<'_, ChalkIr>, &ChalkPredicate) -> Result<(), Floundered>
+        + Send
+        + Sync,
+>;
+
+impl Database for ChalkDatabase {
+    fn salsa_event(&self, event: salsa::Event) {
+        if let salsa::EventKind::WillExecute { database_key } = event.kind {
+            let mut executed_queries = self.executed_queries.lock().unwrap();
+            if let Some(executed_queries) = &mut *executed_queries {
+                executed_queries.push(format!("{:?}", database_key.debug(self)));
+            }
+        }
+    }
+}
 
 impl ChalkDatabase {
     pub fn with(program_text: &str, solver_choice: SolverChoice) -> Self {
         let mut db = ChalkDatabase::default();
         db.set_program_text(Arc::new(program_text.to_string()));
         db.set_solver_choice(solver_choice);
+        db.set_program_path(None);
+        db.set_module_texts(Arc::default());
+        db.set_program_language(SourceLanguage::Chalk);
+        db
+    }
+
+    /// Creates a database for a program written in Rust (see
+    /// `rust_source`).
+    pub fn with_rust(program_text: &str, solver_choice: SolverChoice) -> Self {
+        let mut db = ChalkDatabase::with(program_text, solver_choice);
+        db.set_program_language(SourceLanguage::Rust);
         db
     }
 
+    /// Creates a database for the program in the file at `path`, whose
+    /// modules are loaded from the files next to it (see
+    /// `chalk_parse::parse_program_file`). Files with the `.rs` extension
+    /// are read as Rust.
+    pub fn with_file(path: &Path, solver_choice: SolverChoice) -> io::Result<Self> {
+        let mut db = ChalkDatabase::with("", solver_choice);
+        db.set_program_path(Some(Arc::new(path.to_path_buf())));
+        db.set_program_language(SourceLanguage::of_file(path));
+        db.reload()?;
+        Ok(db)
+    }
+
+    /// Reads the file of the program again, along with those of its
+    /// modules, so that the changes made to them since they were last read
+    /// are seen. Does nothing for a program which was not read from a file.
+    pub fn reload(&mut self) -> io::Result<()> {
+        let path = match self.program_path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let program_text = fs::read_to_string(&*path)?;
+        let mut module_texts = BTreeMap::new();
+        if self.program_language() == SourceLanguage::Chalk {
+            // Parsing finds the files of the modules. The other errors are
+            // reported when the program is lowered.
+            let mut read_error = None;
+            let _ = chalk_parse::parse_program_with(&program_text, &path, &mut |file| {
+                match fs::read_to_string(file) {
+                    Ok(text) => {
+                        module_texts.insert(file.to_path_buf(), Some(text.clone()));
+                        Ok(Some(text))
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                        module_texts.insert(file.to_path_buf(), None);
+                        Ok(None)
+                    }
+                    Err(e) => {
+                        let message = format!("error reading `{}`: {}", file.display(), e);
+                        read_error = Some(e);
+                        Err(message.into())
+                    }
+                }
+            });
+            if let Some(e) = read_error {
+                return Err(e);
+            }
+        }
+        self.set_program_text(Arc::new(program_text));
+        self.set_module_texts(Arc::new(module_texts));
+        Ok(())
+    }
+
+    /// Gives the trait `trait_id` the builtin impls generated by `builtin`
+    /// (see `CustomBuiltins`). Solutions are cached, so this should be done
+    /// before solving any goal involving the trait.
+    pub fn register_custom_builtin(
+        &mut self,
+        trait_id: TraitId<ChalkIr>,
+        builtin: impl Fn(
+                &mut ClauseBuilder<'_, ChalkIr>,
+                TraitRef<ChalkIr>,
+                Ty<ChalkIr>,
+            ) -> Result<(), Floundered>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.custom_builtins.register(trait_id, builtin);
+    }
+
+    /// Proves custom predicates with the clauses `clauses` pushes for them
+    /// (see `RustIrDatabase::custom_predicate_clauses`). As with
+    /// `register_custom_builtin`, this should be done before solving.
+    pub fn set_custom_predicate_clauses(
+        &mut self,
+        clauses: impl Fn(&mut ClauseBuilder<'_, ChalkIr>, &ChalkPredicate) -> Result<(), Floundered>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.custom_predicate_clauses = Some(Arc::new(clauses));
+    }
+
+    /// Starts recording which queries are executed, rather than reused from
+    /// an earlier revision, so that tests can check what a change to the
+    /// program recomputes.
+    pub fn record_executed_queries(&self) {
+        *self.executed_queries.lock().unwrap() = Some(Vec::new());
+    }
+
+    /// The queries executed since `record_executed_queries` or the last call
+    /// to this method, like `impl_wf(ImplId(#2))`.
+    pub fn take_executed_queries(&self) -> Vec<String> {
+        self.executed_queries
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Whether `query`, like `impl_wf(ImplId(#2))`, or `impl_wf` for any
+    /// key, was executed since `record_executed_queries` or the last call to
+    /// `take_executed_queries`.
+    pub fn did_recompute(&self, query: &str) -> bool {
+        let executed_queries = self.executed_queries.lock().unwrap();
+        executed_queries.iter().flatten().any(|executed| {
+            executed == query
+                || executed
+                    .strip_prefix(query)
+                    .is_some_and(|key| key.starts_with('('))
+        })
+    }
+
     pub fn with_program<R>(&self, op: impl FnOnce(&Program) -> R) -> R {
         let program = &self.checked_program().unwrap();
         tls::set_current_program(program, || op(program))
@@ -74,107 +230,148 @@ impl ChalkDatabase {
 
 impl UnificationDatabase<ChalkIr> for ChalkDatabase {
     fn fn_def_variance(&self, fn_def_id: FnDefId<ChalkIr>) -> Variances<ChalkIr> {
-        self.program_ir().unwrap().fn_def_variance(fn_def_id)
+        self.lowered_fn_def_variances(fn_def_id)
     }
 
     fn adt_variance(&self, adt_id: AdtId<ChalkIr>) -> Variances<ChalkIr> {
-        self.program_ir().unwrap().adt_variance(adt_id)
+        self.lowered_adt_variances(adt_id)
     }
 }
 
 impl RustIrDatabase<ChalkIr> for ChalkDatabase {
     fn custom_clauses(&self) -> Vec<ProgramClause<ChalkIr>> {
-        self.program_ir().unwrap().custom_clauses()
+        (*self.lowered_custom_clauses()).clone()
     }
 
     fn associated_ty_data(&self, ty: AssocTypeId<ChalkIr>) -> Arc<AssociatedTyDatum<ChalkIr>> {
-        self.program_ir().unwrap().associated_ty_data(ty)
+        self.lowered_assoc_ty(ty)
     }
 
     fn trait_datum(&self, id: TraitId<ChalkIr>) -> Arc<TraitDatum<ChalkIr>> {
-        self.program_ir().unwrap().trait_datum(id)
+        self.lowered_trait(id)
     }
 
     fn impl_datum(&self, id: ImplId<ChalkIr>) -> Arc<ImplDatum<ChalkIr>> {
-        self.program_ir().unwrap().impl_datum(id)
+        self.lowered_impl(id)
     }
 
     fn associated_ty_value(
         &self,
         id: AssociatedTyValueId<ChalkIr>,
     ) -> Arc<AssociatedTyValue<ChalkIr>> {
-        self.program_ir().unwrap().associated_ty_values[&id].clone()
+        self.lowered_assoc_ty_value(id)
+    }
+
+    fn inherent_assoc_ty_data(
+        &self,
+        id: AssocTypeId<ChalkIr>,
+    ) -> Option<Arc<InherentAssocTyDatum<ChalkIr>>> {
+        self.lowered_inherent_assoc_ty(id)
     }
 
     fn opaque_ty_data(&self, id: OpaqueTyId<ChalkIr>) -> Arc<OpaqueTyDatum<ChalkIr>> {
-        self.program_ir().unwrap().opaque_ty_data(id)
+        self.lowered_opaque_ty(id)
     }
 
     fn hidden_opaque_type(&self, id: OpaqueTyId<ChalkIr>) -> Ty<ChalkIr> {
-        self.program_ir().unwrap().hidden_opaque_type(id)
+        self.lowered_hidden_opaque_type(id)
     }
 
     fn adt_datum(&self, id: AdtId<ChalkIr>) -> Arc<AdtDatum<ChalkIr>> {
-        self.program_ir().unwrap().adt_datum(id)
+        self.lowered_adt(id)
     }
 
     fn generator_datum(&self, id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
-        self.program_ir().unwrap().generator_datum(id)
+        self.lowered_generator(id)
     }
 
     fn generator_witness_datum(
         &self,
         id: GeneratorId<ChalkIr>,
     ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
-        self.program_ir().unwrap().generator_witness_datum(id)
+        self.lowered_generator_witness(id)
     }
 
     fn adt_repr(&self, id: AdtId<ChalkIr>) -> Arc<AdtRepr<ChalkIr>> {
-        self.program_ir().unwrap().adt_repr(id)
+        self.lowered_adt_repr(id)
     }
 
     fn adt_size_align(&self, id: AdtId<ChalkIr>) -> Arc<AdtSizeAlign> {
-        self.program_ir().unwrap().adt_size_align(id)
+        self.lowered_adt_size_align(id)
     }
 
     fn fn_def_datum(&self, id: FnDefId<ChalkIr>) -> Arc<FnDefDatum<ChalkIr>> {
-        self.program_ir().unwrap().fn_def_datum(id)
+        self.lowered_fn_def(id)
     }
 
     fn impls_for_trait(
         &self,
         trait_id: TraitId<ChalkIr>,
-        generic_args: &[GenericArg<ChalkIr>],
-        binders: &CanonicalVarKinds<ChalkIr>,
+        filter: &ImplFilter<'_, ChalkIr>,
     ) -> Vec<ImplId<ChalkIr>> {
-        self.program_ir()
-            .unwrap()
-            .impls_for_trait(trait_id, generic_args, binders)
+        let mut impl_ids =
+            (*self.impls_for_self_type(trait_id, filter.self_type().copied())).clone();
+        retain_impls_matching(self, &mut impl_ids, filter);
+        impl_ids
+    }
+
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+        self_ty: &SimplifiedType<ChalkIr>,
+    ) -> Vec<ImplId<ChalkIr>> {
+        (*self.impls_for_self_type(trait_id, Some(*self_ty))).clone()
     }
 
     fn local_impls_to_coherence_check(&self, trait_id: TraitId<ChalkIr>) -> Vec<ImplId<ChalkIr>> {
-        self.program_ir()
-            .unwrap()
-            .local_impls_to_coherence_check(trait_id)
+        self.impls_for_self_type(trait_id, None)
+            .iter()
+            .copied()
+            .filter(|&impl_id| self.lowered_impl(impl_id).impl_type == ImplType::Local)
+            .collect()
     }
 
     fn impl_provided_for(&self, auto_trait_id: TraitId<ChalkIr>, ty: &TyKind<ChalkIr>) -> bool {
-        self.program_ir()
-            .unwrap()
-            .impl_provided_for(auto_trait_id, ty)
+        let impls = self.impls_for_self_type(auto_trait_id, None);
+        let impls = impls.iter().map(|&impl_id| self.lowered_impl(impl_id));
+        impl_provided_for(impls, auto_trait_id, ty)
     }
 
     fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<ChalkIr>> {
-        self.program_ir()
-            .unwrap()
-            .well_known_trait_id(well_known_trait)
+        self.well_known_trait(well_known_trait)
+    }
+
+    fn custom_builtin(&self, trait_id: TraitId<ChalkIr>) -> Option<CustomBuiltin<ChalkIr>> {
+        self.custom_builtins.get(trait_id)
+    }
+
+    fn custom_predicate_clauses(
+        &self,
+        builder: &mut ClauseBuilder<'_, ChalkIr>,
+        predicate: &CustomPredicate<ChalkIr>,
+    ) -> Result<(), Floundered> {
+        match &self.custom_predicate_clauses {
+            Some(clauses) => clauses(builder, &predicate.0),
+            None => Ok(()),
+        }
+    }
+
+    fn program_features(&self) -> ProgramFeatures {
+        LoweringDatabase::program_features(self)
+    }
+
+    fn variance_of(&self, item: VarianceItem<ChalkIr>) -> Variances<ChalkIr> {
+        match item {
+            VarianceItem::Adt(id) => self.lowered_adt_variances(id),
+            VarianceItem::FnDef(id) => self.lowered_fn_def_variances(id),
+        }
     }
 
     fn program_clauses_for_env(
         &self,
         environment: &Environment<ChalkIr>,
     ) -> ProgramClauses<ChalkIr> {
-        chalk_solve::program_clauses_for_env(self, environment)
+        self.env_clauses(environment.clone())
     }
 
     fn interner(&self) -> ChalkIr {
@@ -182,51 +379,47 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
     }
 
     fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
-        self.program_ir().unwrap().is_object_safe(trait_id)
+        self.trait_is_object_safe(trait_id)
     }
 
     fn closure_inputs_and_output(
         &self,
         closure_id: ClosureId<ChalkIr>,
-        substs: &Substitution<ChalkIr>,
+        _substs: &Substitution<ChalkIr>,
     ) -> Binders<FnDefInputsAndOutputDatum<ChalkIr>> {
-        self.program_ir()
-            .unwrap()
-            .closure_inputs_and_output(closure_id, substs)
+        self.lowered_closure_inputs_and_output(closure_id)
     }
 
     fn closure_kind(
         &self,
         closure_id: ClosureId<ChalkIr>,
-        substs: &Substitution<ChalkIr>,
+        _substs: &Substitution<ChalkIr>,
     ) -> ClosureKind {
-        self.program_ir().unwrap().closure_kind(closure_id, substs)
+        self.lowered_closure_kind(closure_id)
     }
 
     fn closure_upvars(
         &self,
         closure_id: ClosureId<ChalkIr>,
-        substs: &Substitution<ChalkIr>,
+        _substs: &Substitution<ChalkIr>,
     ) -> Binders<Ty<ChalkIr>> {
-        self.program_ir()
-            .unwrap()
-            .closure_upvars(closure_id, substs)
+        self.lowered_closure_upvars(closure_id)
     }
 
     fn closure_fn_substitution(
         &self,
-        closure_id: ClosureId<ChalkIr>,
+        _closure_id: ClosureId<ChalkIr>,
         substs: &Substitution<ChalkIr>,
     ) -> Substitution<ChalkIr> {
-        self.program_ir()
-            .unwrap()
-            .closure_fn_substitution(closure_id, substs)
+        substs.clone()
     }
 
     fn unification_database(&self) -> &dyn UnificationDatabase<ChalkIr> {
         self
     }
 
+    // The names are only needed to write values out, so they are read from
+    // the whole program.
     fn trait_name(&self, trait_id: TraitId<ChalkIr>) -> String {
         self.program_ir().unwrap().trait_name(trait_id)
     }
@@ -248,7 +441,7 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
     }
 
     fn discriminant_type(&self, ty: Ty<ChalkIr>) -> Ty<ChalkIr> {
-        self.program_ir().unwrap().discriminant_type(ty)
+        discriminant_type(self, ty)
     }
 }
 
@@ -0,0 +1,355 @@
+//! The state of `chalk debug`, which steps through the search of the SLG
+//! solver for the answers to a goal (see `Solver::step`), showing after
+//! each step the tables of the solver, the stack of the search, the answers
+//! of a table and the strand being pursued for a goal of the stack.
+//!
+//! The debugger is driven by commands (see `Debugger::command`), and drawn
+//! as text by `Debugger::render`; the terminal is left to the binary.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::query::LoweringDatabase;
+use crate::SolverChoice;
+use chalk_engine::solve::{SLGSolver, TableSummary};
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::{RustIrDatabase, Solver, SolverStep, StackFrame};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// How many steps `run` takes at most before giving up on the next answer.
+pub const RUN_LIMIT: usize = 10_000;
+
+/// The keys of the commands, as shown at the bottom of the screen.
+const KEYS: &str = "[s]tep  [r]un to answer  [b <table>] toggle breakpoint  \
+                    [t <table>] show table  [f <frame>] show strand  [q]uit";
+
+/// What to do after a command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Continue,
+    Quit,
+}
+
+pub struct Debugger<'db> {
+    db: &'db ChalkDatabase,
+    solver: SLGSolver<ChalkIr>,
+    goal: UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    /// The number of steps taken so far.
+    steps: usize,
+    /// The answers to the goal found so far.
+    answers: Vec<String>,
+    /// Whether the goal has no more answers.
+    finished: bool,
+    /// The tables `run` stops at when they are pushed on the stack.
+    breakpoints: BTreeSet<usize>,
+    /// The table whose answers are shown, if not the one on top of the
+    /// stack.
+    table: Option<usize>,
+    /// The frame of the stack whose strand is shown, if not the topmost
+    /// one which has a strand.
+    frame: Option<usize>,
+    /// What the last command did.
+    status: String,
+}
+
+impl<'db> Debugger<'db> {
+    /// A debugger for `goal`, with the SLG solver `db` is set up with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `db` is set up with another solver.
+    pub fn new(db: &'db ChalkDatabase, goal: UCanonical<InEnvironment<Goal<ChalkIr>>>) -> Self {
+        let solver = match db.solver_choice() {
+            SolverChoice::SLG {
+                max_size,
+                expected_answers,
+            } => SLGSolver::new(max_size, expected_answers),
+            SolverChoice::Recursive { .. } => panic!("only the SLG solver can be debugged"),
+        };
+        Debugger {
+            db,
+            solver,
+            goal,
+            steps: 0,
+            answers: vec![],
+            finished: false,
+            breakpoints: BTreeSet::new(),
+            table: None,
+            frame: None,
+            status: String::from("Ready"),
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The answers to the goal found so far.
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The tables of the goals on the stack, outermost first.
+    pub fn stack_tables(&self) -> Vec<usize> {
+        let tables = self.solver.tables();
+        self.stack()
+            .iter()
+            .map(|frame| table_of(&tables, frame))
+            .collect()
+    }
+
+    /// Takes one step, returning whether it found an answer, or found that
+    /// there are no more.
+    pub fn step(&mut self) -> bool {
+        if self.finished {
+            self.status = String::from("No more solutions");
+            return true;
+        }
+        self.steps += 1;
+        let db = self.db;
+        let step = db.with_program(|_| {
+            let step = self
+                .solver
+                .step(db, &self.goal)
+                .expect("the SLG solver can be stepped through");
+            match step {
+                SolverStep::Searching => None,
+                SolverStep::Answer(answer) => {
+                    let answer = answer.as_ref().map(|a| a.display(db.interner()));
+                    match answer.to_string() {
+                        // The answers of goals without variables.
+                        answer if answer.is_empty() => Some(Some(String::from("Proven"))),
+                        answer => Some(Some(answer)),
+                    }
+                }
+                SolverStep::NoMoreSolutions => Some(None),
+            }
+        });
+        match step {
+            None => {
+                self.status = String::from("Searching");
+                false
+            }
+            Some(Some(answer)) => {
+                self.status = format!("Answer: {}", answer);
+                self.answers.push(answer);
+                true
+            }
+            Some(None) => {
+                self.finished = true;
+                self.status = String::from("No more solutions");
+                true
+            }
+        }
+    }
+    /// Steps until the next answer, or until a table with a breakpoint is
+    /// pushed on the stack, taking at most `RUN_LIMIT` steps.
+    pub fn run(&mut self) {
+        for _ in 0..RUN_LIMIT {
+            let depth = self.stack().len();
+            if self.step() {
+                return;
+            }
+            let stack = self.stack_tables();
+            if let Some(&top) = stack.last() {
+                if stack.len() > depth && self.breakpoints.contains(&top) {
+                    self.status = format!("Breakpoint: table {} was pushed on the stack", top);
+                    return;
+                }
+            }
+        }
+        self.status = format!("No answer after {} steps", RUN_LIMIT);
+    }
+
+    /// Sets a breakpoint on `table`, or removes the one it has.
+    pub fn toggle_breakpoint(&mut self, table: usize) {
+        if self.breakpoints.remove(&table) {
+            self.status = format!("Removed the breakpoint on table {}", table);
+        } else {
+            self.breakpoints.insert(table);
+            self.status = format!("Set a breakpoint on table {}", table);
+        }
+    }
+
+    /// Runs the command `line`, as typed by the user. An empty line takes a
+    /// step.
+    pub fn command(&mut self, line: &str) -> Command {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        let key = words.next().unwrap_or("s");
+        let arg = words.next().map(str::parse::<usize>);
+        match (key, arg) {
+            ("s", None) => {
+                self.step();
+            }
+            ("r", None) => self.run(),
+            ("b", Some(Ok(table))) => self.toggle_breakpoint(table),
+            ("t", Some(Ok(table))) => {
+                self.table = Some(table);
+                self.status = format!("Showing table {}", table);
+            }
+            ("t", None) => {
+                self.table = None;
+                self.status = String::from("Showing the table on top of the stack");
+            }
+            ("f", Some(Ok(frame))) => {
+                self.frame = Some(frame);
+                self.status = format!("Showing the strand of frame {}", frame);
+            }
+            ("f", None) => {
+                self.frame = None;
+                self.status = String::from("Showing the topmost strand");
+            }
+            ("q", None) => return Command::Quit,
+            _ => self.status = format!("Unknown command `{}`; the commands are {}", line, KEYS),
+        }
+        Command::Continue
+    }
+
+    fn stack(&self) -> Vec<StackFrame<ChalkIr>> {
+        self.solver.step_stack(self.db)
+    }
+
+    /// Draws the state of the debugger.
+    pub fn render(&self) -> String {
+        self.db.with_program(|_| self.render_with_program())
+    }
+
+    fn render_with_program(&self) -> String {
+        let tables = self.solver.tables();
+        let stack = self.stack();
+        let stack_tables: Vec<_> = stack.iter().map(|frame| table_of(&tables, frame)).collect();
+        let mut out = String::new();
+
+        writeln!(out, "Goal: {:?}", self.goal.canonical().value.goal).unwrap();
+        writeln!(out, "Step {}: {}", self.steps, self.status).unwrap();
+        if !self.answers.is_empty() {
+            writeln!(out, "Answers: {}", self.answers.join("; ")).unwrap();
+        }
+
+        heading(&mut out, "Tables");
+        for (index, table) in tables.iter().enumerate() {
+            let on_stack = if stack_tables.contains(&index) {
+                '*'
+            } else {
+                ' '
+            };
+            let breakpoint = if self.breakpoints.contains(&index) {
+                'b'
+            } else {
+                ' '
+            };
+            let mut notes = vec![
+                plural(table.answers.len(), "answer"),
+                plural(table.strands, "strand"),
+            ];
+            if table.floundered {
+                notes.push(String::from("floundered"));
+            }
+            if table.coinductive {
+                notes.push(String::from("coinductive"));
+            }
+            writeln!(
+                out,
+                "{}{} {:3} {:?} ({})",
+                on_stack,
+                breakpoint,
+                index,
+                table.goal.canonical().value.goal,
+                notes.join(", ")
+            )
+            .unwrap();
+        }
+
+        heading(&mut out, "Stack");
+        for (depth, (frame, table)) in stack.iter().zip(&stack_tables).enumerate() {
+            writeln!(
+                out,
+                "{:3} table {}: {:?}",
+                depth,
+                table,
+                frame.goal.canonical().value.goal
+            )
+            .unwrap();
+        }
+
+        let table = self
+            .table
+            .or_else(|| stack_tables.last().copied())
+            .filter(|&table| table < tables.len());
+        match table {
+            Some(table) => {
+                heading(&mut out, &format!("Answers of table {}", table));
+                for answer in &tables[table].answers {
+                    let ambiguous = if answer.ambiguous { " (ambiguous)" } else { "" };
+                    writeln!(out, "    {:?}{}", answer.subst.value.subst, ambiguous).unwrap();
+                }
+            }
+            None => heading(&mut out, "Answers"),
+        }
+
+        let frame = match self.frame {
+            Some(frame) => Some(frame).filter(|&frame| frame < stack.len()),
+            None => stack
+                .iter()
+                .rposition(|frame| frame.active_strand.is_some()),
+        };
+        match frame.and_then(|frame| Some((frame, stack[frame].active_strand.as_ref()?))) {
+            Some((frame, strand)) => {
+                heading(&mut out, &format!("Strand of frame {}", frame));
+                let ambiguous = if strand.ambiguous { " (ambiguous)" } else { "" };
+                writeln!(out, "    {:?}{}", strand.subst, ambiguous).unwrap();
+                for (index, subgoal) in strand.subgoals.iter().enumerate() {
+                    let selected = if strand.selected_subgoal == Some(index) {
+                        "->"
+                    } else {
+                        "  "
+                    };
+                    writeln!(out, "  {} {:?}", selected, subgoal.goal).unwrap();
+                }
+                if !strand.answer_sources.is_empty() {
+                    let sources: Vec<_> = strand
+                        .answer_sources
+                        .iter()
+                        .map(|(table, answer)| format!("answer {} of table {}", answer, table))
+                        .collect();
+                    writeln!(out, "    applied {}", sources.join(", ")).unwrap();
+                }
+            }
+            None => heading(&mut out, "Strand"),
+        }
+
+        writeln!(out, "\n{}", KEYS).unwrap();
+        out
+    }
+}
+
+/// The index of the table of the goal of `frame`.
+fn table_of(tables: &[TableSummary<ChalkIr>], frame: &StackFrame<ChalkIr>) -> usize {
+    tables
+        .iter()
+        .position(|table| table.goal == frame.goal)
+        .expect("the goals on the stack have tables")
+}
+
+fn heading(out: &mut String, title: &str) {
+    writeln!(
+        out,
+        "\n── {} {}",
+        title,
+        "─".repeat(60 - title.len().min(60))
+    )
+    .unwrap();
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
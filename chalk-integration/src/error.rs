@@ -1,6 +1,10 @@
 use crate::interner::ChalkIr;
-use chalk_parse::ast::{Identifier, Kind};
+use crate::program::Program;
+use chalk_parse::ast::{self, Identifier, Kind};
+use chalk_parse::ParseDiagnostics;
 use chalk_solve::coherence::CoherenceError;
+use chalk_solve::kind_check::KindError;
+use chalk_solve::rust_ir::Span;
 use chalk_solve::wf::WfError;
 use string_cache::DefaultAtom as Atom;
 
@@ -11,12 +15,40 @@ pub struct ChalkError {
     /// For now, we just convert the error into a string, which makes
     /// it trivially hashable etc.
     error_text: String,
+    /// The syntax errors, if this error comes from parsing.
+    parse_diagnostics: Option<ParseDiagnostics>,
+    /// The errors found in the program, if this error comes from lowering.
+    lowering_errors: Vec<LoweringError>,
+}
+
+impl ChalkError {
+    /// The syntax errors that caused this error, if it comes from parsing.
+    pub fn parse_diagnostics(&self) -> Option<&ParseDiagnostics> {
+        self.parse_diagnostics.as_ref()
+    }
+
+    /// The errors found in the program, one for each item which could not
+    /// be lowered, if this error comes from lowering.
+    pub fn lowering_errors(&self) -> &[LoweringError] {
+        &self.lowering_errors
+    }
+
+    /// Points the error at the declaration of `program` at `span`, if
+    /// there is one.
+    pub(crate) fn at_span(mut self, program: &Program, span: Option<Span>) -> Self {
+        if let Some(location) = span.and_then(|span| program.describe_span(span)) {
+            self.error_text = format!("{}\n{}", self.error_text, location);
+        }
+        self
+    }
 }
 
 impl From<Box<dyn std::error::Error>> for ChalkError {
     fn from(value: Box<dyn std::error::Error>) -> Self {
         ChalkError {
             error_text: value.to_string(),
+            parse_diagnostics: value.downcast_ref::<ParseDiagnostics>().cloned(),
+            lowering_errors: vec![],
         }
     }
 }
@@ -25,6 +57,18 @@ impl From<WfError<ChalkIr>> for ChalkError {
     fn from(value: WfError<ChalkIr>) -> Self {
         ChalkError {
             error_text: value.to_string(),
+            parse_diagnostics: None,
+            lowering_errors: vec![],
+        }
+    }
+}
+
+impl From<KindError<ChalkIr>> for ChalkError {
+    fn from(value: KindError<ChalkIr>) -> Self {
+        ChalkError {
+            error_text: value.to_string(),
+            parse_diagnostics: None,
+            lowering_errors: vec![],
         }
     }
 }
@@ -33,6 +77,23 @@ impl From<CoherenceError<ChalkIr>> for ChalkError {
     fn from(value: CoherenceError<ChalkIr>) -> Self {
         ChalkError {
             error_text: value.to_string(),
+            parse_diagnostics: None,
+            lowering_errors: vec![],
+        }
+    }
+}
+
+impl From<Vec<LoweringError>> for ChalkError {
+    fn from(value: Vec<LoweringError>) -> Self {
+        let error_text = value
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        ChalkError {
+            error_text,
+            parse_diagnostics: None,
+            lowering_errors: value,
         }
     }
 }
@@ -41,6 +102,8 @@ impl From<RustIrError> for ChalkError {
     fn from(value: RustIrError) -> Self {
         ChalkError {
             error_text: value.to_string(),
+            parse_diagnostics: None,
+            lowering_errors: vec![],
         }
     }
 }
@@ -53,7 +116,64 @@ impl std::fmt::Display for ChalkError {
 
 impl std::error::Error for ChalkError {}
 
-#[derive(Debug)]
+/// An error found while lowering a program, at the name it is about if
+/// there is one, or else at the item it was found in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LoweringError {
+    error: RustIrError,
+    span: Option<ast::Span>,
+    /// The description of `span` in the sources of the program.
+    location: Option<String>,
+}
+
+impl LoweringError {
+    pub(crate) fn new(
+        error: RustIrError,
+        item_span: Option<ast::Span>,
+        program: &ast::Program,
+    ) -> Self {
+        let span = error.identifier().map(|name| name.span).or(item_span);
+        let location = span.and_then(|span| {
+            let source = program.sources.get(span.source)?;
+            source.text.get(span.lo..span.hi)?;
+            let location = chalk_parse::Location::new(&source.text, span);
+            let description = location.display(source.path.as_deref()).to_string();
+            Some(description)
+        });
+        LoweringError {
+            error,
+            span,
+            location,
+        }
+    }
+
+    pub fn error(&self) -> &RustIrError {
+        &self.error
+    }
+
+    /// The code of the error, see `RustIrError::code`.
+    pub fn code(&self) -> &'static str {
+        self.error.code()
+    }
+
+    pub fn span(&self) -> Option<ast::Span> {
+        self.span
+    }
+}
+
+impl std::fmt::Display for LoweringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.error, self.code())?;
+        if let Some(location) = &self.location {
+            write!(f, "\n{}", location)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LoweringError {}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum RustIrError {
     InvalidParameterName(Identifier),
     InvalidTraitName(Identifier),
@@ -64,8 +184,12 @@ pub enum RustIrError {
     AutoTraitParameters(Identifier),
     AutoTraitWhereClauses(Identifier),
     InvalidFundamentalTypesParameters(Identifier),
+    DuplicateDiscriminant(Identifier),
+    UnloadedModule(Identifier),
+    UnknownFeature(Identifier),
     NegativeImplAssociatedValues(Identifier),
     MissingAssociatedType(Identifier),
+    MissingInherentAssociatedType(Identifier),
     IncorrectNumberOfVarianceParameters {
         identifier: Identifier,
         expected: usize,
@@ -100,6 +224,68 @@ pub enum RustIrError {
     InvalidExternAbi(Atom),
 }
 
+impl RustIrError {
+    /// A code identifying the kind of the error, like `L0001`, which stays
+    /// the same when its message changes.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RustIrError::InvalidParameterName(_) => "L0001",
+            RustIrError::InvalidTraitName(_) => "L0002",
+            RustIrError::NotTrait(_) => "L0003",
+            RustIrError::NotStruct(_) => "L0004",
+            RustIrError::DuplicateOrShadowedParameters => "L0005",
+            RustIrError::AutoTraitAssociatedTypes(_) => "L0006",
+            RustIrError::AutoTraitParameters(_) => "L0007",
+            RustIrError::AutoTraitWhereClauses(_) => "L0008",
+            RustIrError::InvalidFundamentalTypesParameters(_) => "L0009",
+            RustIrError::DuplicateDiscriminant(_) => "L0010",
+            RustIrError::UnloadedModule(_) => "L0011",
+            RustIrError::UnknownFeature(_) => "L0012",
+            RustIrError::NegativeImplAssociatedValues(_) => "L0013",
+            RustIrError::MissingAssociatedType(_) => "L0014",
+            RustIrError::MissingInherentAssociatedType(_) => "L0015",
+            RustIrError::IncorrectNumberOfVarianceParameters { .. } => "L0016",
+            RustIrError::IncorrectNumberOfTypeParameters { .. } => "L0017",
+            RustIrError::IncorrectNumberOfAssociatedTypeParameters { .. } => "L0018",
+            RustIrError::IncorrectParameterKind { .. } => "L0019",
+            RustIrError::IncorrectTraitParameterKind { .. } => "L0020",
+            RustIrError::IncorrectAssociatedTypeParameterKind { .. } => "L0021",
+            RustIrError::CannotApplyTypeParameter(_) => "L0022",
+            RustIrError::InvalidExternAbi(_) => "L0023",
+        }
+    }
+
+    /// The name the error is about, if any.
+    fn identifier(&self) -> Option<&Identifier> {
+        match self {
+            RustIrError::InvalidParameterName(name)
+            | RustIrError::InvalidTraitName(name)
+            | RustIrError::NotTrait(name)
+            | RustIrError::NotStruct(name)
+            | RustIrError::AutoTraitAssociatedTypes(name)
+            | RustIrError::AutoTraitParameters(name)
+            | RustIrError::AutoTraitWhereClauses(name)
+            | RustIrError::InvalidFundamentalTypesParameters(name)
+            | RustIrError::DuplicateDiscriminant(name)
+            | RustIrError::UnloadedModule(name)
+            | RustIrError::UnknownFeature(name)
+            | RustIrError::NegativeImplAssociatedValues(name)
+            | RustIrError::MissingAssociatedType(name)
+            | RustIrError::MissingInherentAssociatedType(name)
+            | RustIrError::CannotApplyTypeParameter(name) => Some(name),
+            RustIrError::IncorrectNumberOfVarianceParameters { identifier, .. }
+            | RustIrError::IncorrectNumberOfTypeParameters { identifier, .. }
+            | RustIrError::IncorrectNumberOfAssociatedTypeParameters { identifier, .. }
+            | RustIrError::IncorrectParameterKind { identifier, .. }
+            | RustIrError::IncorrectTraitParameterKind { identifier, .. }
+            | RustIrError::IncorrectAssociatedTypeParameterKind { identifier, .. } => {
+                Some(identifier)
+            }
+            RustIrError::DuplicateOrShadowedParameters | RustIrError::InvalidExternAbi(_) => None,
+        }
+    }
+}
+
 impl std::fmt::Display for RustIrError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -134,6 +320,17 @@ impl std::fmt::Display for RustIrError {
                 "only a single parameter supported for fundamental type `{}`",
                 name
             ),
+            RustIrError::DuplicateDiscriminant(name) => write!(
+                f,
+                "discriminant of variant `{}` is already used by another variant",
+                name
+            ),
+            RustIrError::UnloadedModule(name) => write!(
+                f,
+                "module `{}` cannot be loaded: modules are only supported in programs read from files",
+                name
+            ),
+            RustIrError::UnknownFeature(name) => write!(f, "unknown feature `{}`", name),
             RustIrError::NegativeImplAssociatedValues(name) => write!(
                 f,
                 "negative impl for trait `{}` cannot define associated values",
@@ -142,6 +339,9 @@ impl std::fmt::Display for RustIrError {
             RustIrError::MissingAssociatedType(name) => {
                 write!(f, "no associated type `{}` defined in trait", name)
             }
+            RustIrError::MissingInherentAssociatedType(name) => {
+                write!(f, "no associated type `{}` defined in inherent impls", name)
+            }
             RustIrError::IncorrectNumberOfVarianceParameters {
                 identifier,
                 expected,
@@ -0,0 +1,279 @@
+//! Explains how a goal is proven, or why it can't be, as a tree of the
+//! goal's subgoals and of the clauses tried for each of them.
+//!
+//! The solvers don't record their proofs, so the tree is rebuilt after the
+//! fact: each domain goal is unified with the clauses that could prove it,
+//! like the recursive solver does, and the solver is asked for the answer
+//! of each of the clauses' conditions. Subgoals are explained one at a
+//! time, without what the solver infers from their siblings.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use chalk_ir::{
+    DomainGoal, Environment, Goal, GoalData, InEnvironment, ProgramClauseData, QuantifierKind,
+    UCanonical, UnificationError, Variance,
+};
+use chalk_solve::clauses::program_clauses_for_goal;
+use chalk_solve::infer::InferenceTable;
+use chalk_solve::{RustIrDatabase, Solution};
+use std::fmt;
+
+/// A step in the explanation of a goal, with the steps it depends on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub step: Step,
+    pub children: Vec<Explanation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// A goal, and the answer of the solver for it.
+    Goal { goal: String, answer: Answer },
+    /// A clause that unifies with the goal it is a child of, and what it
+    /// became when unified with the goal. Its children are its conditions.
+    Clause { clause: String, unified: String },
+    /// A clause that could match the goal it is a child of, but does not
+    /// unify with it.
+    Mismatch { clause: String, reason: String },
+    /// The depth limit was reached before the goal was explained.
+    DepthLimit,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Answer {
+    Unique,
+    Ambiguous,
+    NoSolution,
+}
+
+impl Answer {
+    fn of(solution: &Option<Solution<ChalkIr>>) -> Self {
+        match solution {
+            Some(Solution::Unique(_)) => Answer::Unique,
+            Some(Solution::Ambig(..)) => Answer::Ambiguous,
+            None => Answer::NoSolution,
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unique => write!(f, "Unique"),
+            Answer::Ambiguous => write!(f, "Ambiguous"),
+            Answer::NoSolution => write!(f, "No possible solution"),
+        }
+    }
+}
+
+/// Explains `goal` down to `depth` goals deep. For a goal that can be
+/// proven, only the clauses whose conditions can all be proven are shown;
+/// for one that can't, all the clauses that could match it are.
+///
+/// The names in the explanation are only available while the program of
+/// `db` is the current one, see `ChalkDatabase::with_program`.
+pub fn explain(
+    db: &ChalkDatabase,
+    goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    depth: usize,
+) -> Explanation {
+    let (mut infer, _, goal) =
+        InferenceTable::from_canonical(ChalkIr, goal.universes(), goal.canonical().clone());
+    Explainer { db }.goal(&mut infer, &goal.environment, goal.goal, depth)
+}
+
+struct Explainer<'db> {
+    db: &'db ChalkDatabase,
+}
+
+impl Explainer<'_> {
+    fn goal(
+        &self,
+        infer: &mut InferenceTable<ChalkIr>,
+        environment: &Environment<ChalkIr>,
+        goal: Goal<ChalkIr>,
+        depth: usize,
+    ) -> Explanation {
+        // Clauses wrap their conditions in quantifiers that bind nothing.
+        if let GoalData::Quantified(_, subgoal) = goal.data(ChalkIr) {
+            if subgoal.binders.is_empty(ChalkIr) {
+                return self.goal(infer, environment, subgoal.skip_binders().clone(), depth);
+            }
+        }
+
+        let canonical = infer
+            .canonicalize(ChalkIr, InEnvironment::new(environment, goal.clone()))
+            .quantified;
+        let answer = Answer::of(
+            &self
+                .db
+                .solve(&InferenceTable::u_canonicalize(ChalkIr, &canonical).quantified),
+        );
+        let step = Step::Goal {
+            goal: format!("{:?}", canonical.value.goal),
+            answer,
+        };
+
+        let children = match goal.data(ChalkIr) {
+            GoalData::EqGoal(_) | GoalData::SubtypeGoal(_) | GoalData::CannotProve => vec![],
+            _ if depth == 0 => vec![Explanation {
+                step: Step::DepthLimit,
+                children: vec![],
+            }],
+            GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                let subgoal = infer.instantiate_binders_universally(ChalkIr, subgoal.clone());
+                vec![self.goal(infer, environment, subgoal, depth - 1)]
+            }
+            GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
+                let subgoal = infer.instantiate_binders_existentially(ChalkIr, subgoal.clone());
+                vec![self.goal(infer, environment, subgoal, depth - 1)]
+            }
+            GoalData::Implies(clauses, subgoal) => {
+                let environment = environment.add_clauses(ChalkIr, clauses.iter(ChalkIr).cloned());
+                vec![self.goal(infer, &environment, subgoal.clone(), depth - 1)]
+            }
+            GoalData::All(goals) => goals
+                .iter(ChalkIr)
+                .map(|subgoal| self.goal(infer, environment, subgoal.clone(), depth - 1))
+                .collect(),
+            GoalData::Not(subgoal) => {
+                vec![self.goal(infer, environment, subgoal.clone(), depth - 1)]
+            }
+            GoalData::DomainGoal(domain_goal) => {
+                let canonical = infer
+                    .canonicalize(
+                        ChalkIr,
+                        InEnvironment::new(environment, domain_goal.clone()),
+                    )
+                    .quantified;
+                let canonical = InferenceTable::u_canonicalize(ChalkIr, &canonical).quantified;
+                self.clauses(&canonical, answer, depth - 1)
+            }
+        };
+        Explanation { step, children }
+    }
+
+    /// Explains the clauses tried for the domain goal `goal`, whose answer
+    /// is `answer`.
+    fn clauses(
+        &self,
+        goal: &UCanonical<InEnvironment<DomainGoal<ChalkIr>>>,
+        answer: Answer,
+        depth: usize,
+    ) -> Vec<Explanation> {
+        let mut clauses = match program_clauses_for_goal(self.db, goal) {
+            Ok(clauses) => clauses,
+            // The goal is too unconstrained to enumerate its clauses.
+            Err(_) => return vec![],
+        };
+        // A clause can come both from the program and from the environment.
+        let mut index = 0;
+        while index < clauses.len() {
+            if clauses[..index].contains(&clauses[index]) {
+                clauses.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        let (infer, _, goal) =
+            InferenceTable::from_canonical(ChalkIr, goal.universes(), goal.canonical().clone());
+
+        let mut explanations = Vec::new();
+        for clause in clauses {
+            let ProgramClauseData(implication) = clause.data(ChalkIr);
+            let mut infer = infer.clone();
+            let implication = infer.instantiate_binders_existentially(ChalkIr, implication.clone());
+            let unified = infer.relate_explained(
+                ChalkIr,
+                self.db.unification_database(),
+                &goal.environment,
+                Variance::Invariant,
+                &goal.goal,
+                &implication.consequence,
+            );
+            let explanation = match unified {
+                Ok(result) => {
+                    let unified = infer.canonicalize(ChalkIr, implication.clone()).quantified;
+                    let conditions = result
+                        .goals
+                        .into_iter()
+                        .chain(implication.conditions.iter(ChalkIr).map(|condition| {
+                            InEnvironment::new(&goal.environment, condition.clone())
+                        }))
+                        .map(|condition| {
+                            self.goal(&mut infer, &condition.environment, condition.goal, depth)
+                        })
+                        .collect::<Vec<_>>();
+                    let holds = conditions.iter().all(|condition| match condition.step {
+                        Step::Goal { answer, .. } => answer != Answer::NoSolution,
+                        _ => true,
+                    });
+                    if answer != Answer::NoSolution && !holds {
+                        continue;
+                    }
+                    Explanation {
+                        step: Step::Clause {
+                            clause: format!("{:?}", clause),
+                            unified: format!("{:?}", unified.value),
+                        },
+                        children: conditions,
+                    }
+                }
+                Err(error) => {
+                    if answer != Answer::NoSolution {
+                        continue;
+                    }
+                    let reason = match error {
+                        UnificationError::NoSolution => "does not unify with the goal".to_string(),
+                        UnificationError::OccursCheck { var, ty } => format!(
+                            "unifying with the goal would make `{:?}` occur in its own value `{:?}`",
+                            var, ty
+                        ),
+                    };
+                    Explanation {
+                        step: Step::Mismatch {
+                            clause: format!("{:?}", clause),
+                            reason,
+                        },
+                        children: vec![],
+                    }
+                }
+            };
+            explanations.push(explanation);
+        }
+        explanations
+    }
+}
+
+/// Writes the explanation as an ASCII tree, one step per line.
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, "", "")
+    }
+}
+
+impl Explanation {
+    /// Writes this step after `first` and its children after `rest`.
+    fn write(&self, f: &mut fmt::Formatter<'_>, first: &str, rest: &str) -> fmt::Result {
+        match &self.step {
+            Step::Goal { goal, answer } => writeln!(f, "{}{}: {}", first, goal, answer)?,
+            Step::Clause { clause, unified } => {
+                writeln!(f, "{}clause {}", first, clause)?;
+                let bar = if self.children.is_empty() { " " } else { "|" };
+                writeln!(f, "{}{}   unified: {}", rest, bar, unified)?;
+            }
+            Step::Mismatch { clause, reason } => {
+                writeln!(f, "{}clause {}: {}", first, clause, reason)?
+            }
+            Step::DepthLimit => writeln!(f, "{}...", first)?,
+        }
+        for (index, child) in self.children.iter().enumerate() {
+            if index + 1 == self.children.len() {
+                child.write(f, &format!("{}`-- ", rest), &format!("{}    ", rest))?;
+            } else {
+                child.write(f, &format!("{}|-- ", rest), &format!("{}|   ", rest))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+//! Generates random well-formed programs and goals, and solves them with
+//! both the SLG and the recursive solver, looking for panics, goals that
+//! don't terminate and goals the solvers disagree on.
+//!
+//! Programs are generated as `.chalk` text from a seed, so a failing case
+//! can be reproduced from its seed alone, and can be handed to `chalk
+//! minimize` as is. Generated programs are kept simple enough to be
+//! well-formed and coherent by construction: no struct has where clauses,
+//! and each trait has at most one impl per struct.
+
+use crate::db::ChalkDatabase;
+use crate::interner::ChalkIr;
+use crate::query::LoweringDatabase;
+use crate::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// How large the generated programs are, and how hard the solvers may
+/// work on each goal.
+#[derive(Clone, Debug)]
+pub struct FuzzConfig {
+    pub traits: usize,
+    pub structs: usize,
+    /// How many impls are attempted; ones that would overlap with an
+    /// earlier impl are left out.
+    pub impls: usize,
+    pub goals: usize,
+    /// How many times the SLG solver may ask whether to continue on one
+    /// goal before it is considered not to terminate.
+    pub fuel: usize,
+    /// How long either solver may take on one goal before it is
+    /// considered not to terminate. The recursive solver can't be given
+    /// fuel, so this is its only limit.
+    pub timeout: Duration,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        FuzzConfig {
+            traits: 4,
+            structs: 4,
+            impls: 8,
+            goals: 8,
+            fuel: 10_000,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A generated program, with the goals to solve in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzCase {
+    pub seed: u64,
+    pub program: String,
+    pub goals: Vec<String>,
+}
+
+/// A problem found in a `FuzzCase`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FuzzFailure {
+    /// The generated program or goal does not lower or is not
+    /// well-formed: a bug in the generator.
+    Invalid(String),
+    /// The solver panicked on the goal.
+    Panic { solver: SolverChoice, goal: String },
+    /// The solver ran out of fuel or time on the goal.
+    NonTermination { solver: SolverChoice, goal: String },
+    /// The solvers gave contradicting answers for the goal.
+    Disagreement {
+        goal: String,
+        slg: String,
+        recursive: String,
+    },
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FuzzFailure::Invalid(message) => write!(f, "invalid program or goal: {}", message),
+            FuzzFailure::Panic { solver, goal } => {
+                write!(f, "{:?} panicked on `{}`", solver, goal)
+            }
+            FuzzFailure::NonTermination { solver, goal } => {
+                write!(f, "{:?} did not terminate on `{}`", solver, goal)
+            }
+            FuzzFailure::Disagreement {
+                goal,
+                slg,
+                recursive,
+            } => write!(
+                f,
+                "the solvers disagree on `{}`: SLG says `{}`, recursive says `{}`",
+                goal, slg, recursive
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FuzzFailure {}
+
+/// Generates and checks the cases for each of `seeds`, returning the
+/// first case that shows a problem.
+pub fn fuzz(seeds: Range<u64>, config: &FuzzConfig) -> Option<(FuzzCase, FuzzFailure)> {
+    seeds.map(|seed| generate(seed, config)).find_map(|case| {
+        let failure = check(&case, config).err()?;
+        Some((case, failure))
+    })
+}
+
+/// Generates the case for `seed`. The same seed and configuration always
+/// give the same case.
+pub fn generate(seed: u64, config: &FuzzConfig) -> FuzzCase {
+    let mut generator = Generator {
+        rng: oorandom::Rand64::new(seed.into()),
+        traits: Vec::new(),
+        structs: Vec::new(),
+    };
+    let program = generator.program(config);
+    let goals = (0..config.goals).map(|_| generator.goal()).collect();
+    FuzzCase {
+        seed,
+        program,
+        goals,
+    }
+}
+
+/// Solves the goals of `case` with both solvers, and compares their
+/// answers. An ambiguous answer agrees with every other answer.
+pub fn check(case: &FuzzCase, config: &FuzzConfig) -> Result<(), FuzzFailure> {
+    let db = ChalkDatabase::with(&case.program, SolverChoice::default());
+    if let Err(error) = db.checked_program() {
+        return Err(FuzzFailure::Invalid(error.to_string()));
+    }
+
+    let slg = solve(case, SolverChoice::slg_default(), config)?;
+    let recursive = solve(case, SolverChoice::recursive_default(), config)?;
+    for ((goal, slg), recursive) in case.goals.iter().zip(slg).zip(recursive) {
+        if !slg.agrees_with(&recursive) {
+            return Err(FuzzFailure::Disagreement {
+                goal: goal.clone(),
+                slg: slg.to_string(),
+                recursive: recursive.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// An answer to a goal, with the substitution of unique answers as the
+/// REPL would print it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Answer {
+    Unique(String),
+    Ambiguous,
+    NoSolution,
+}
+
+impl Answer {
+    fn agrees_with(&self, other: &Answer) -> bool {
+        match (self, other) {
+            (Answer::Ambiguous, _) | (_, Answer::Ambiguous) => true,
+            _ => self == other,
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unique(solution) => write!(f, "{}", solution),
+            Answer::Ambiguous => write!(f, "Ambiguous"),
+            Answer::NoSolution => write!(f, "No possible solution"),
+        }
+    }
+}
+
+/// What solving one goal on the solver thread gave.
+enum Outcome {
+    Answer(Answer),
+    OutOfFuel,
+    Invalid(String),
+}
+
+/// Solves each goal of `case` with `solver_choice` on a new thread.
+fn solve(
+    case: &FuzzCase,
+    solver_choice: SolverChoice,
+    config: &FuzzConfig,
+) -> Result<Vec<Answer>, FuzzFailure> {
+    let (sender, receiver) = mpsc::channel();
+    let (program, goals, fuel) = (case.program.clone(), case.goals.clone(), config.fuel);
+    thread::spawn(move || {
+        let db = ChalkDatabase::with(&program, solver_choice);
+        db.with_program(|_| {
+            for goal in &goals {
+                let outcome = match db.parse_and_lower_goal(goal) {
+                    Ok(goal) => {
+                        let goal = goal.into_peeled_goal(db.interner());
+                        let steps = Cell::new(0);
+                        let should_continue = || {
+                            steps.set(steps.get() + 1);
+                            steps.get() <= fuel
+                        };
+                        let solver = db.solver();
+                        let solution =
+                            solver
+                                .lock()
+                                .unwrap()
+                                .solve_limited(&db, &goal, &should_continue);
+                        if steps.get() > fuel {
+                            Outcome::OutOfFuel
+                        } else {
+                            Outcome::Answer(match solution {
+                                Some(Solution::Unique(_)) => {
+                                    Answer::Unique(solution.unwrap().display(ChalkIr).to_string())
+                                }
+                                Some(Solution::Ambig(..)) => Answer::Ambiguous,
+                                None => Answer::NoSolution,
+                            })
+                        }
+                    }
+                    Err(error) => Outcome::Invalid(error.to_string()),
+                };
+                if sender.send(outcome).is_err() {
+                    return;
+                }
+            }
+        })
+    });
+
+    let mut answers = Vec::new();
+    for goal in &case.goals {
+        let goal = goal.clone();
+        match receiver.recv_timeout(config.timeout) {
+            Ok(Outcome::Answer(answer)) => answers.push(answer),
+            Ok(Outcome::Invalid(message)) => {
+                return Err(FuzzFailure::Invalid(format!("`{}`: {}", goal, message)))
+            }
+            Ok(Outcome::OutOfFuel) | Err(mpsc::RecvTimeoutError::Timeout) => {
+                return Err(FuzzFailure::NonTermination {
+                    solver: solver_choice,
+                    goal,
+                })
+            }
+            // The thread dropped the sender without sending: it panicked.
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(FuzzFailure::Panic {
+                    solver: solver_choice,
+                    goal,
+                })
+            }
+        }
+    }
+    Ok(answers)
+}
+
+/// The shape of a generated trait.
+struct TraitShape {
+    auto: bool,
+    /// Whether the trait has a type parameter (besides `Self`).
+    parameter: bool,
+    /// Whether the trait has an associated type `Assoc`.
+    assoc: bool,
+}
+
+struct Generator {
+    rng: oorandom::Rand64,
+    traits: Vec<TraitShape>,
+    /// The number of type parameters of each struct.
+    structs: Vec<usize>,
+}
+
+impl Generator {
+    fn below(&mut self, n: usize) -> usize {
+        self.rng.rand_range(0..n as u64) as usize
+    }
+
+    /// True one time in `n`.
+    fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn program(&mut self, config: &FuzzConfig) -> String {
+        let mut text = String::new();
+        for i in 0..config.traits.max(1) {
+            let shape = if self.one_in(5) {
+                text.push_str(&format!("#[auto] trait Tr{} {{}}\n", i));
+                TraitShape {
+                    auto: true,
+                    parameter: false,
+                    assoc: false,
+                }
+            } else {
+                let shape = TraitShape {
+                    auto: false,
+                    parameter: self.one_in(3),
+                    assoc: self.one_in(3),
+                };
+                text.push_str(&format!(
+                    "trait Tr{}{} {{{}}}\n",
+                    i,
+                    if shape.parameter { "<X>" } else { "" },
+                    if shape.assoc { " type Assoc; " } else { "" }
+                ));
+                shape
+            };
+            self.traits.push(shape);
+        }
+
+        for i in 0..config.structs.max(1) {
+            let arity = self.below(3);
+            let parameters = parameter_names(arity);
+            let fields = (0..self.below(3))
+                .map(|field| format!(" f{}: {},", field, self.ty(&parameters, 2)))
+                .collect::<String>();
+            text.push_str(&format!(
+                "struct S{}{} {{{} }}\n",
+                i,
+                generics(&parameters),
+                fields
+            ));
+            self.structs.push(arity);
+        }
+
+        let mut implemented = HashSet::new();
+        for _ in 0..config.impls {
+            let (trait_index, struct_index) = (
+                self.below(self.traits.len()),
+                self.below(self.structs.len()),
+            );
+            if !implemented.insert((trait_index, struct_index)) {
+                continue;
+            }
+            let parameters = parameter_names(self.structs[struct_index]);
+            let self_ty = format!("S{}{}", struct_index, generics(&parameters));
+            if self.traits[trait_index].auto {
+                text.push_str(&format!(
+                    "impl{} !Tr{} for {} {{}}\n",
+                    generics(&parameters),
+                    trait_index,
+                    self_ty
+                ));
+                continue;
+            }
+            let trait_ref = self.trait_ref(trait_index, &parameters);
+            let where_clauses = (0..self.below(3))
+                .filter(|_| !parameters.is_empty())
+                .map(|_| {
+                    let parameter = parameters[self.below(parameters.len())].clone();
+                    let bound = self.below(self.traits.len());
+                    format!("{}: {}", parameter, self.trait_ref(bound, &parameters))
+                })
+                .collect::<Vec<_>>();
+            let where_clauses = if where_clauses.is_empty() {
+                String::new()
+            } else {
+                format!(" where {}", where_clauses.join(", "))
+            };
+            let assoc = if self.traits[trait_index].assoc {
+                format!(" type Assoc = {}; ", self.ty(&parameters, 2))
+            } else {
+                String::new()
+            };
+            text.push_str(&format!(
+                "impl{} {} for {}{} {{{}}}\n",
+                generics(&parameters),
+                trait_ref,
+                self_ty,
+                where_clauses,
+                assoc
+            ));
+        }
+        text
+    }
+
+    fn goal(&mut self) -> String {
+        let trait_index = self.below(self.traits.len());
+        match self.below(5) {
+            0 => format!(
+                "exists<T> {{ T: {} }}",
+                self.trait_ref(trait_index, &["T".to_string()])
+            ),
+            1 => {
+                let variable = ["T".to_string()];
+                let assumption = self.below(self.traits.len());
+                format!(
+                    "forall<T> {{ if (T: {}) {{ {}: {} }} }}",
+                    self.trait_ref(assumption, &variable),
+                    self.ty(&variable, 2),
+                    self.trait_ref(trait_index, &variable)
+                )
+            }
+            2 => format!(
+                "not {{ {}: {} }}",
+                self.ty(&[], 2),
+                self.trait_ref(trait_index, &[])
+            ),
+            3 => match self.traits.iter().position(|shape| shape.assoc) {
+                Some(trait_index) => format!(
+                    "exists<U> {{ <{} as {}>::Assoc = U }}",
+                    self.ty(&[], 2),
+                    self.trait_ref(trait_index, &[])
+                ),
+                None => self.goal(),
+            },
+            _ => format!("{}: {}", self.ty(&[], 2), self.trait_ref(trait_index, &[])),
+        }
+    }
+
+    /// A reference to the trait `trait_index`, without its `Self` type.
+    fn trait_ref(&mut self, trait_index: usize, parameters: &[String]) -> String {
+        if self.traits[trait_index].parameter {
+            format!("Tr{}<{}>", trait_index, self.ty(parameters, 1))
+        } else {
+            format!("Tr{}", trait_index)
+        }
+    }
+
+    /// A type made of `parameters`, `u32` and the structs generated so
+    /// far, at most `depth` structs deep.
+    fn ty(&mut self, parameters: &[String], depth: usize) -> String {
+        let choice = self.below(parameters.len() + 1 + self.structs.len().min(depth * 3));
+        if choice < parameters.len() {
+            return parameters[choice].clone();
+        }
+        if choice == parameters.len() || depth == 0 || self.structs.is_empty() {
+            return "u32".to_string();
+        }
+        let struct_index = self.below(self.structs.len());
+        let arguments = (0..self.structs[struct_index])
+            .map(|_| self.ty(parameters, depth - 1))
+            .collect::<Vec<_>>();
+        format!("S{}{}", struct_index, generics(&arguments))
+    }
+}
+
+fn parameter_names(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("A{}", i)).collect()
+}
+
+fn generics(arguments: &[String]) -> String {
+    if arguments.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", arguments.join(", "))
+    }
+}
@@ -1,7 +1,10 @@
 use crate::tls;
 use chalk_ir::{
-    interner::{HasInterner, Interner},
-    TyKind,
+    fold::{FallibleFolder, Fold},
+    interner::{HasInterner, InlineSubstitution, Interner},
+    visit::{Visit, Visitor},
+    zip::{Zip, Zipper},
+    DebruijnIndex, Fallible, NoSolution, TyKind, TypeFlags,
 };
 use chalk_ir::{
     AdtId, AliasTy, AssocTypeId, CanonicalVarKind, CanonicalVarKinds, ConstData, Constraint,
@@ -13,14 +16,18 @@ use chalk_ir::{
     GenericArg, GenericArgData, Goal, GoalData, LifetimeData, ProgramClause, ProgramClauseData,
     QuantifiedWhereClause, Variance,
 };
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Debug;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 use string_cache::DefaultAtom;
 
 pub type Identifier = DefaultAtom;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RawId {
     pub index: u32,
 }
@@ -32,6 +39,7 @@ impl Debug for RawId {
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ChalkFnAbi {
     Rust,
     C,
@@ -50,6 +58,48 @@ impl Debug for ChalkFnAbi {
     }
 }
 
+/// The custom types of `ChalkIr`: pattern types `T is start..=end`, whose
+/// values are those of the integer type `T` within the given range. They
+/// have no surface syntax, and are only used to test `TyKind::Custom`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChalkPatternTy {
+    pub ty: Ty<ChalkIr>,
+    pub start: u128,
+    pub end: u128,
+}
+
+impl Debug for ChalkPatternTy {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{:?} is {}..={}", self.ty, self.start, self.end)
+    }
+}
+
+/// The custom predicates of `ChalkIr`: predicates `name(args..)` over
+/// generic arguments, like `ConstEvaluatable(C)`, which hold according to
+/// the clauses given by the host (see
+/// `ChalkDatabase::set_custom_predicate_clauses`). Like `ChalkPatternTy`,
+/// they have no surface syntax.
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChalkPredicate {
+    pub name: Identifier,
+    pub args: Vec<GenericArg<ChalkIr>>,
+}
+
+impl Debug for ChalkPredicate {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}(", self.name)?;
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, ", ")?;
+            }
+            write!(fmt, "{:?}", arg)?;
+        }
+        write!(fmt, ")")
+    }
+}
+
 /// The default "interner" and the only interner used by chalk
 /// itself. In this interner, no interning actually occurs.
 #[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
@@ -63,7 +113,7 @@ impl Interner for ChalkIr {
     type InternedGenericArg = GenericArgData<ChalkIr>;
     type InternedGoal = Arc<GoalData<ChalkIr>>;
     type InternedGoals = Vec<Goal<ChalkIr>>;
-    type InternedSubstitution = Vec<GenericArg<ChalkIr>>;
+    type InternedSubstitution = InlineSubstitution<ChalkIr>;
     type InternedProgramClause = ProgramClauseData<ChalkIr>;
     type InternedProgramClauses = Vec<ProgramClause<ChalkIr>>;
     type InternedQuantifiedWhereClauses = Vec<QuantifiedWhereClause<ChalkIr>>;
@@ -75,6 +125,8 @@ impl Interner for ChalkIr {
     type InternedAdtId = RawId;
     type Identifier = Identifier;
     type FnAbi = ChalkFnAbi;
+    type CustomTy = ChalkPatternTy;
+    type CustomPredicate = ChalkPredicate;
 
     fn debug_adt_id(
         type_kind_id: AdtId<ChalkIr>,
@@ -238,7 +290,12 @@ impl Interner for ChalkIr {
 
     fn intern_ty(self, kind: TyKind<ChalkIr>) -> Arc<TyData<ChalkIr>> {
         let flags = kind.compute_flags(self);
-        Arc::new(TyData { kind, flags })
+        let outer_exclusive_binder = kind.compute_outer_exclusive_binder(self);
+        Arc::new(TyData {
+            kind,
+            flags,
+            outer_exclusive_binder,
+        })
     }
 
     fn ty_data(self, ty: &Arc<TyData<ChalkIr>>) -> &TyData<Self> {
@@ -295,11 +352,14 @@ impl Interner for ChalkIr {
     fn intern_substitution<E>(
         self,
         data: impl IntoIterator<Item = Result<GenericArg<ChalkIr>, E>>,
-    ) -> Result<Vec<GenericArg<ChalkIr>>, E> {
+    ) -> Result<InlineSubstitution<ChalkIr>, E> {
         data.into_iter().collect()
     }
 
-    fn substitution_data(self, substitution: &Vec<GenericArg<ChalkIr>>) -> &[GenericArg<ChalkIr>] {
+    fn substitution_data(
+        self,
+        substitution: &InlineSubstitution<ChalkIr>,
+    ) -> &[GenericArg<ChalkIr>] {
         substitution
     }
 
@@ -387,6 +447,90 @@ impl Interner for ChalkIr {
     fn variances_data(self, variances: &Self::InternedVariances) -> &[Variance] {
         variances
     }
+
+    fn fold_custom_ty<E>(
+        self,
+        pattern_ty: &ChalkPatternTy,
+        folder: &mut dyn FallibleFolder<Self, Error = E>,
+        outer_binder: DebruijnIndex,
+    ) -> Result<ChalkPatternTy, E> {
+        Ok(ChalkPatternTy {
+            ty: folder.try_fold_ty(pattern_ty.ty.clone(), outer_binder)?,
+            ..pattern_ty.clone()
+        })
+    }
+
+    fn visit_custom_ty<B>(
+        self,
+        pattern_ty: &ChalkPatternTy,
+        visitor: &mut dyn Visitor<Self, BreakTy = B>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<B> {
+        visitor.visit_ty(&pattern_ty.ty, outer_binder)
+    }
+
+    fn zip_custom_tys<Z: Zipper<Self>>(
+        self,
+        zipper: &mut Z,
+        variance: Variance,
+        a: &ChalkPatternTy,
+        b: &ChalkPatternTy,
+    ) -> Fallible<()> {
+        if (a.start, a.end) != (b.start, b.end) {
+            return Err(NoSolution);
+        }
+        zipper.zip_tys(variance, &a.ty, &b.ty)
+    }
+
+    fn custom_ty_flags(self, pattern_ty: &ChalkPatternTy) -> TypeFlags {
+        pattern_ty.ty.data(self).flags
+    }
+
+    fn custom_ty_outer_exclusive_binder(self, pattern_ty: &ChalkPatternTy) -> DebruijnIndex {
+        pattern_ty.ty.outer_exclusive_binder(self)
+    }
+
+    fn fold_custom_predicate<E>(
+        self,
+        predicate: &ChalkPredicate,
+        folder: &mut dyn FallibleFolder<Self, Error = E>,
+        outer_binder: DebruijnIndex,
+    ) -> Result<ChalkPredicate, E> {
+        Ok(ChalkPredicate {
+            name: predicate.name.clone(),
+            args: predicate.args.clone().try_fold_with(folder, outer_binder)?,
+        })
+    }
+
+    fn visit_custom_predicate<B>(
+        self,
+        predicate: &ChalkPredicate,
+        visitor: &mut dyn Visitor<Self, BreakTy = B>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<B> {
+        predicate.args.visit_with(visitor, outer_binder)
+    }
+
+    // The arguments of predicates are invariant.
+    fn zip_custom_predicates<Z: Zipper<Self>>(
+        self,
+        zipper: &mut Z,
+        _variance: Variance,
+        a: &ChalkPredicate,
+        b: &ChalkPredicate,
+    ) -> Fallible<()> {
+        if a.name != b.name || a.args.len() != b.args.len() {
+            return Err(NoSolution);
+        }
+        Zip::zip_with(zipper, Variance::Invariant, &a.args, &b.args)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl chalk_ir::serialize::SerdeInterner for ChalkIr {
+    fn serde_interner() -> Self {
+        ChalkIr
+    }
 }
 
 impl HasInterner for ChalkIr {
@@ -1,13 +1,21 @@
 #![recursion_limit = "1024"]
 #![cfg_attr(feature = "bench", feature(test))]
 
+pub mod batch;
+pub mod conformance;
 pub mod db;
+pub mod debugger;
 pub mod error;
+pub mod explain;
+pub mod fuzz;
 pub mod interner;
 pub mod lowering;
+pub mod minimize;
+pub mod name_map;
 pub mod program;
 pub mod program_environment;
 pub mod query;
+pub mod rust_source;
 pub mod test_macros;
 pub mod tls;
 
@@ -17,10 +25,13 @@ use chalk_ir::Binders;
 use chalk_recursive::{Cache, RecursiveSolver};
 use chalk_solve::Solver;
 use interner::ChalkIr;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
 
 pub use interner::{Identifier, RawId};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TypeSort {
     Adt,
     FnDef,
@@ -31,6 +42,7 @@ pub enum TypeSort {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Unit;
 
 impl HasInterner for Unit {
@@ -38,6 +50,7 @@ impl HasInterner for Unit {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TypeKind {
     pub sort: TypeSort,
     pub name: Identifier,
@@ -91,6 +104,15 @@ impl SolverChoice {
         }
     }
 
+    /// Returns the default setup of the solver named `slg` or `recursive`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "slg" => Some(SolverChoice::slg_default()),
+            "recursive" => Some(SolverChoice::recursive_default()),
+            _ => None,
+        }
+    }
+
     pub fn into_solver(self) -> Box<dyn Solver<ChalkIr>> {
         match self {
             SolverChoice::SLG {
@@ -119,3 +141,23 @@ impl Default for SolverChoice {
         SolverChoice::slg(10, None)
     }
 }
+
+/// The language a program is written in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SourceLanguage {
+    /// The `.chalk` syntax.
+    Chalk,
+    /// The subset of Rust read by `rust_source`.
+    Rust,
+}
+
+impl SourceLanguage {
+    /// The language of the file at `path`: Rust for `.rs` files, and the
+    /// `.chalk` syntax otherwise.
+    pub fn of_file(path: &std::path::Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension == "rs" => SourceLanguage::Rust,
+            _ => SourceLanguage::Chalk,
+        }
+    }
+}
@@ -8,12 +8,13 @@ use chalk_ir::{
 };
 use chalk_parse::ast::*;
 use chalk_solve::rust_ir::{self, IntoWhereClauses};
-use program_lowerer::ProgramLowerer;
-use std::collections::BTreeMap;
+pub(crate) use program_lowerer::lower_program_features;
+pub use program_lowerer::ProgramLowerer;
+use std::collections::{BTreeMap, HashSet};
 use string_cache::DefaultAtom as Atom;
 use tracing::debug;
 
-use crate::error::RustIrError;
+use crate::error::{LoweringError, RustIrError};
 use crate::interner::{ChalkFnAbi, ChalkIr};
 use crate::program::Program as LoweredProgram;
 use crate::{Identifier as Ident, TypeSort};
@@ -35,21 +36,28 @@ pub trait Lower {
 }
 
 impl Lower for Program {
-    type Lowered = LowerResult<LoweredProgram>;
+    /// The lowered program, or the errors of all the items which could not
+    /// be lowered.
+    type Lowered = Result<LoweredProgram, Vec<LoweringError>>;
 
     fn lower(&self) -> Self::Lowered {
-        let mut lowerer = ProgramLowerer::default();
-
-        // Make a vector mapping each thing in `items` to an id,
-        // based just on its position:
-        let raw_ids = self
+        let unloaded_modules: Vec<_> = self
             .items
             .iter()
-            .map(|_| lowerer.next_item_id())
-            .collect::<Vec<_>>();
+            .filter_map(|item| match item {
+                Item::Mod(name) => Some(LoweringError::new(
+                    RustIrError::UnloadedModule(name.clone()),
+                    None,
+                    self,
+                )),
+                _ => None,
+            })
+            .collect();
+        if !unloaded_modules.is_empty() {
+            return Err(unloaded_modules);
+        }
 
-        lowerer.extract_associated_types(self, &raw_ids)?;
-        lowerer.extract_ids(self, &raw_ids)?;
+        let (lowerer, raw_ids) = ProgramLowerer::new(self)?;
         lowerer.lower(self, &raw_ids)
     }
 }
@@ -100,6 +108,7 @@ lower_param_map!(AdtDefn, None);
 lower_param_map!(FnDefn, None);
 lower_param_map!(ClosureDefn, None);
 lower_param_map!(Impl, None);
+lower_param_map!(InherentImpl, None);
 lower_param_map!(AssocTyDefn, None);
 lower_param_map!(AssocTyValue, None);
 lower_param_map!(Clause, None);
@@ -111,8 +120,12 @@ lower_param_map!(
     ))
 );
 
-fn get_type_of_usize() -> chalk_ir::Ty<ChalkIr> {
-    chalk_ir::TyKind::Scalar(chalk_ir::Scalar::Uint(chalk_ir::UintTy::Usize)).intern(ChalkIr)
+/// The type of a const annotated with `ty`: `usize` unless annotated otherwise.
+fn get_const_type(ty: &Option<ScalarType>) -> chalk_ir::Ty<ChalkIr> {
+    let scalar = ty.map_or(chalk_ir::Scalar::Uint(chalk_ir::UintTy::Usize), |ty| {
+        ty.lower()
+    });
+    chalk_ir::TyKind::Scalar(scalar).intern(ChalkIr)
 }
 
 impl Lower for VariableKind {
@@ -132,7 +145,9 @@ impl Lower for VariableKind {
                 n,
             ),
             VariableKind::Lifetime(n) => (chalk_ir::VariableKind::Lifetime, n),
-            VariableKind::Const(ref n) => (chalk_ir::VariableKind::Const(get_type_of_usize()), n),
+            VariableKind::Const(ref n, ty) => {
+                (chalk_ir::VariableKind::Const(get_const_type(ty)), n)
+            }
         };
 
         chalk_ir::WithKind::new(kind, n.str.clone())
@@ -142,13 +157,18 @@ impl Lower for VariableKind {
 impl LowerWithEnv for [QuantifiedWhereClause] {
     type Lowered = Vec<chalk_ir::QuantifiedWhereClause<ChalkIr>>;
 
+    /// Lowers a list of `where` clauses, dropping duplicates: each binding in
+    /// `T: Foo<A = U, B = V>` implies `T: Foo` again.
     fn lower(&self, env: &Env) -> LowerResult<Self::Lowered> {
-        self.iter()
-            .flat_map(|wc| match wc.lower(env) {
-                Ok(v) => v.into_iter().map(Ok).collect(),
-                Err(e) => vec![Err(e)],
-            })
-            .collect()
+        let mut where_clauses = Vec::new();
+        for wc in self {
+            for wc in wc.lower(env)? {
+                if !where_clauses.contains(&wc) {
+                    where_clauses.push(wc);
+                }
+            }
+        }
+        Ok(where_clauses)
     }
 }
 
@@ -222,6 +242,12 @@ impl LowerWithEnv for DomainGoal {
                     ty: ty.lower(env)?,
                 })]
             }
+            DomainGoal::NormalizeInherent { projection, ty } => {
+                vec![chalk_ir::DomainGoal::Normalize(chalk_ir::Normalize {
+                    alias: chalk_ir::AliasTy::Inherent(projection.lower(env)?),
+                    ty: ty.lower(env)?,
+                })]
+            }
             DomainGoal::TyWellFormed { ty } => vec![chalk_ir::DomainGoal::WellFormed(
                 chalk_ir::WellFormed::Ty(ty.lower(env)?),
             )],
@@ -252,6 +278,14 @@ impl LowerWithEnv for DomainGoal {
             DomainGoal::ObjectSafe { id } => {
                 vec![chalk_ir::DomainGoal::ObjectSafe(env.lookup_trait(id)?)]
             }
+            DomainGoal::FnPtrCoercion { source, target } => {
+                vec![chalk_ir::DomainGoal::FnPtrCoercion(
+                    chalk_ir::FnPtrCoercion {
+                        source: source.lower(env)?,
+                        target: target.lower(env)?,
+                    },
+                )]
+            }
         })
     }
 }
@@ -291,6 +325,16 @@ impl LowerWithEnv for (&AdtDefn, chalk_ir::AdtId<ChalkIr>) {
             ));
         }
 
+        let mut discriminants = HashSet::new();
+        let mut next_discriminant = 0;
+        for variant in &adt_defn.variants {
+            let discriminant = variant.discriminant.unwrap_or(next_discriminant);
+            if !discriminants.insert(discriminant) {
+                return Err(RustIrError::DuplicateDiscriminant(variant.name.clone()));
+            }
+            next_discriminant = discriminant.wrapping_add(1);
+        }
+
         let binders = env.in_binders(adt_defn.all_parameters(), |env| {
             Ok(rust_ir::AdtDatumBound {
                 variants: adt_defn
@@ -299,7 +343,10 @@ impl LowerWithEnv for (&AdtDefn, chalk_ir::AdtId<ChalkIr>) {
                     .map(|v| {
                         let fields: LowerResult<_> =
                             v.fields.iter().map(|f| f.ty.lower(env)).collect();
-                        Ok(rust_ir::AdtVariantDatum { fields: fields? })
+                        Ok(rust_ir::AdtVariantDatum {
+                            fields: fields?,
+                            discriminant: v.discriminant,
+                        })
                     })
                     .collect::<LowerResult<_>>()?,
                 where_clauses: adt_defn.where_clauses.lower(env)?,
@@ -321,6 +368,7 @@ impl LowerWithEnv for (&AdtDefn, chalk_ir::AdtId<ChalkIr>) {
                 AdtKind::Enum => rust_ir::AdtKind::Enum,
                 AdtKind::Union => rust_ir::AdtKind::Union,
             },
+            span: Some(adt_defn.span.lower()),
         })
     }
 }
@@ -336,6 +384,8 @@ impl LowerWithEnv for AdtRepr {
         Ok(rust_ir::AdtRepr {
             c: self.c,
             packed: self.packed,
+            transparent: self.transparent,
+            simd: self.simd,
             int: self.int.as_ref().map(|i| i.lower(env)).transpose()?,
         })
     }
@@ -372,6 +422,7 @@ impl LowerWithEnv for (&FnDefn, chalk_ir::FnDefId<ChalkIr>) {
             id: *fn_def_id,
             sig: fn_defn.sig.lower()?,
             binders,
+            span: Some(fn_defn.span.lower()),
         })
     }
 }
@@ -536,6 +587,7 @@ impl LowerWithEnv for InlineBound {
         Ok(match self {
             InlineBound::TraitBound(b) => rust_ir::InlineBound::TraitBound(b.lower(env)?),
             InlineBound::AliasEqBound(b) => rust_ir::InlineBound::AliasEqBound(b.lower(env)?),
+            InlineBound::Outlives(lifetime) => rust_ir::InlineBound::Outlives(lifetime.lower(env)?),
         })
     }
 }
@@ -553,18 +605,26 @@ impl LowerWithEnv for [QuantifiedInlineBound] {
     type Lowered = Vec<rust_ir::QuantifiedInlineBound<ChalkIr>>;
 
     fn lower(&self, env: &Env) -> LowerResult<Self::Lowered> {
-        fn trait_identifier(bound: &InlineBound) -> &Identifier {
+        fn trait_identifier(bound: &InlineBound) -> Option<&Identifier> {
             match bound {
-                InlineBound::TraitBound(tb) => &tb.trait_name,
-                InlineBound::AliasEqBound(ab) => &ab.trait_bound.trait_name,
+                InlineBound::TraitBound(tb) => Some(&tb.trait_name),
+                InlineBound::AliasEqBound(ab) => Some(&ab.trait_bound.trait_name),
+                InlineBound::Outlives(_) => None,
             }
         }
 
         let mut regular_traits = Vec::new();
         let mut auto_traits = Vec::new();
+        let mut outlives = Vec::new();
 
         for b in self {
-            let id = env.lookup_trait(trait_identifier(&b.bound))?;
+            let id = match trait_identifier(&b.bound) {
+                Some(identifier) => env.lookup_trait(identifier)?,
+                None => {
+                    outlives.push(b);
+                    continue;
+                }
+            };
             if env.auto_trait(id) {
                 auto_traits.push((b, id))
             } else {
@@ -577,7 +637,9 @@ impl LowerWithEnv for [QuantifiedInlineBound] {
         regular_traits
             .iter()
             .chain(auto_traits.iter())
-            .map(|(b, _)| b.lower(env))
+            .map(|&(b, _)| b)
+            .chain(outlives)
+            .map(|b| b.lower(env))
             .collect()
     }
 }
@@ -665,6 +727,20 @@ impl LowerWithEnv for ProjectionTy {
     }
 }
 
+impl LowerWithEnv for InherentProjectionTy {
+    type Lowered = chalk_ir::InherentProjectionTy<ChalkIr>;
+
+    fn lower(&self, env: &Env) -> LowerResult<Self::Lowered> {
+        let InherentProjectionTy { ref ty, ref name } = *self;
+        let ty = ty.lower(env)?;
+        let associated_ty_id = env.lookup_inherent_associated_ty(&ty, name)?;
+        Ok(chalk_ir::InherentProjectionTy {
+            associated_ty_id,
+            substitution: chalk_ir::Substitution::from1(env.interner(), ty),
+        })
+    }
+}
+
 impl LowerWithEnv for Ty {
     type Lowered = chalk_ir::Ty<ChalkIr>;
 
@@ -684,8 +760,9 @@ impl LowerWithEnv for Ty {
             Ty::Dyn {
                 ref bounds,
                 ref lifetime,
-            } => chalk_ir::TyKind::Dyn(chalk_ir::DynTy {
-                bounds: env.in_binders(
+            } => chalk_ir::TyKind::Dyn(chalk_ir::DynTy::new(
+                interner,
+                env.in_binders(
                     // FIXME: Figure out a proper name for this type parameter
                     Some(chalk_ir::WithKind::new(
                         chalk_ir::VariableKind::Ty(TyVariableKind::General),
@@ -707,8 +784,8 @@ impl LowerWithEnv for Ty {
                         ))
                     },
                 )?,
-                lifetime: lifetime.lower(env)?,
-            })
+                lifetime.lower(env)?,
+            ))
             .intern(interner),
 
             Ty::Apply { name, ref args } => {
@@ -753,12 +830,21 @@ impl LowerWithEnv for Ty {
                     TypeLookup::Closure(id) => tykind!(env.closure_kind(id), Closure, id),
                     TypeLookup::Opaque(id) => tykind!(env.opaque_kind(id), OpaqueType, id),
                     TypeLookup::Generator(id) => tykind!(env.generator_kind(id), Generator, id),
-                    TypeLookup::Foreign(_) | TypeLookup::Trait(_) => {
-                        panic!("Unexpected apply type")
+                    TypeLookup::Foreign(_) => {
+                        return Err(RustIrError::IncorrectNumberOfTypeParameters {
+                            identifier: name.clone(),
+                            expected: 0,
+                            actual: args.len(),
+                        })
                     }
+                    TypeLookup::Trait(_) => return Err(RustIrError::NotStruct(name.clone())),
                 }
             }
 
+            Ty::InherentProjection { ref proj } => {
+                chalk_ir::TyKind::Alias(chalk_ir::AliasTy::Inherent(proj.lower(env)?))
+                    .intern(interner)
+            }
             Ty::Projection { ref proj } => {
                 chalk_ir::TyKind::Alias(chalk_ir::AliasTy::Projection(proj.lower(env)?))
                     .intern(interner)
@@ -837,8 +923,8 @@ impl LowerWithEnv for Const {
                     })
                     .map(|c| c.clone())
             }
-            Const::Value(value) => Ok(chalk_ir::ConstData {
-                ty: get_type_of_usize(),
+            Const::Value(value, ty) => Ok(chalk_ir::ConstData {
+                ty: get_const_type(ty),
                 value: chalk_ir::ConstValue::Concrete(chalk_ir::ConcreteConst { interned: *value }),
             }
             .intern(interner)),
@@ -933,6 +1019,7 @@ impl LowerWithEnv for (&Impl, ImplId<ChalkIr>, &AssociatedTyValueIds) {
             binders,
             impl_type: impl_.impl_type.lower(),
             associated_ty_value_ids,
+            span: Some(impl_.span.lower()),
         })
     }
 }
@@ -1012,6 +1099,7 @@ impl LowerWithEnv for (&TraitDefn, chalk_ir::TraitId<ChalkIr>) {
             flags: trait_defn.flags.lower(),
             associated_ty_ids,
             well_known: trait_defn.well_known.map(|def| def.lower()),
+            span: Some(trait_defn.span.lower()),
         };
 
         debug!(?trait_datum);
@@ -1039,6 +1127,12 @@ pub fn lower_goal(goal: &Goal, program: &LoweredProgram) -> LowerResult<chalk_ir
         })
         .collect();
 
+    let inherent_assoc_ty_ids = program
+        .inherent_assoc_ty_data
+        .iter()
+        .map(|(&id, datum)| ((datum.adt_id, datum.name.clone()), id))
+        .collect();
+
     let auto_traits = program
         .trait_data
         .iter()
@@ -1059,9 +1153,11 @@ pub fn lower_goal(goal: &Goal, program: &LoweredProgram) -> LowerResult<chalk_ir
         trait_kinds: &program.trait_kinds,
         opaque_ty_kinds: &program.opaque_ty_kinds,
         associated_ty_lookups: &associated_ty_lookups,
+        inherent_assoc_ty_ids: &inherent_assoc_ty_ids,
         foreign_ty_ids: &program.foreign_ty_ids,
         parameter_map: BTreeMap::new(),
         auto_traits: &auto_traits,
+        well_known_traits: &program.well_known_traits,
     };
 
     goal.lower(&env)
@@ -1143,6 +1239,13 @@ impl Lower for WellKnownTrait {
             WellKnownTrait::DiscriminantKind => rust_ir::WellKnownTrait::DiscriminantKind,
             WellKnownTrait::Generator => rust_ir::WellKnownTrait::Generator,
             WellKnownTrait::DispatchFromDyn => rust_ir::WellKnownTrait::DispatchFromDyn,
+            WellKnownTrait::Tuple => rust_ir::WellKnownTrait::Tuple,
+            WellKnownTrait::FnPtr => rust_ir::WellKnownTrait::FnPtr,
+            WellKnownTrait::MetaSized => rust_ir::WellKnownTrait::MetaSized,
+            WellKnownTrait::PointeeSized => rust_ir::WellKnownTrait::PointeeSized,
+            WellKnownTrait::Deref => rust_ir::WellKnownTrait::Deref,
+            WellKnownTrait::IntoIterator => rust_ir::WellKnownTrait::IntoIterator,
+            WellKnownTrait::Iterator => rust_ir::WellKnownTrait::Iterator,
         }
     }
 }
@@ -1247,6 +1350,17 @@ impl Lower for Safety {
     }
 }
 
+impl Lower for Span {
+    type Lowered = rust_ir::Span;
+    fn lower(&self) -> Self::Lowered {
+        rust_ir::Span {
+            source: self.source,
+            lo: self.lo,
+            hi: self.hi,
+        }
+    }
+}
+
 impl Lower for Movability {
     type Lowered = rust_ir::Movability;
     fn lower(&self) -> Self::Lowered {
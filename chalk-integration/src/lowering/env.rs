@@ -5,7 +5,7 @@ use chalk_ir::{
 };
 use chalk_ir::{cast::Cast, ForeignDefId, WithKind};
 use chalk_parse::ast::*;
-use chalk_solve::rust_ir::AssociatedTyValueId;
+use chalk_solve::rust_ir::{AssociatedTyValueId, WellKnownTrait};
 use std::collections::BTreeMap;
 
 use crate::error::RustIrError;
@@ -28,7 +28,10 @@ pub type GeneratorKinds = BTreeMap<chalk_ir::GeneratorId<ChalkIr>, TypeKind>;
 pub type AssociatedTyLookups = BTreeMap<(chalk_ir::TraitId<ChalkIr>, Ident), AssociatedTyLookup>;
 pub type AssociatedTyValueIds =
     BTreeMap<(chalk_ir::ImplId<ChalkIr>, Ident), AssociatedTyValueId<ChalkIr>>;
+pub type InherentAssocTyIds =
+    BTreeMap<(chalk_ir::AdtId<ChalkIr>, Ident), chalk_ir::AssocTypeId<ChalkIr>>;
 pub type ForeignIds = BTreeMap<Ident, chalk_ir::ForeignDefId<ChalkIr>>;
+pub type WellKnownTraits = BTreeMap<WellKnownTrait, chalk_ir::TraitId<ChalkIr>>;
 
 pub type ParameterMap = BTreeMap<Ident, chalk_ir::WithKind<ChalkIr, BoundVar>>;
 
@@ -47,7 +50,9 @@ pub struct Env<'k> {
     pub opaque_ty_ids: &'k OpaqueTyIds,
     pub opaque_ty_kinds: &'k OpaqueTyVariableKinds,
     pub associated_ty_lookups: &'k AssociatedTyLookups,
+    pub inherent_assoc_ty_ids: &'k InherentAssocTyIds,
     pub auto_traits: &'k AutoTraits,
+    pub well_known_traits: &'k WellKnownTraits,
     pub foreign_ty_ids: &'k ForeignIds,
     pub generator_ids: &'k GeneratorIds,
     pub generator_kinds: &'k GeneratorKinds,
@@ -212,16 +217,49 @@ impl Env<'_> {
         &self.generator_kinds[&id]
     }
 
+    /// Looks up the associated type `ident` of the trait `trait_id`. The
+    /// associated types of `FnOnce` can also be named through `FnMut` and
+    /// `Fn`, which have the same parameters, as in `F: Fn(u32) -> u32`.
     pub fn lookup_associated_ty(
         &self,
         trait_id: TraitId<ChalkIr>,
         ident: &Identifier,
     ) -> LowerResult<&AssociatedTyLookup> {
-        self.associated_ty_lookups
-            .get(&(trait_id, ident.str.clone()))
+        let lookup = |trait_id| {
+            self.associated_ty_lookups
+                .get(&(trait_id, ident.str.clone()))
+        };
+        lookup(trait_id)
+            .or_else(|| {
+                let is_fn_trait = [WellKnownTrait::Fn, WellKnownTrait::FnMut]
+                    .iter()
+                    .any(|well_known| self.well_known_traits.get(well_known) == Some(&trait_id));
+                if is_fn_trait {
+                    lookup(*self.well_known_traits.get(&WellKnownTrait::FnOnce)?)
+                } else {
+                    None
+                }
+            })
             .ok_or_else(|| RustIrError::MissingAssociatedType(ident.clone()))
     }
 
+    /// Looks up the inherent associated type `ident` of `self_ty`, which
+    /// must be an ADT.
+    pub fn lookup_inherent_associated_ty(
+        &self,
+        self_ty: &chalk_ir::Ty<ChalkIr>,
+        ident: &Identifier,
+    ) -> LowerResult<chalk_ir::AssocTypeId<ChalkIr>> {
+        match self_ty.kind(self.interner()) {
+            chalk_ir::TyKind::Adt(adt_id, _) => self
+                .inherent_assoc_ty_ids
+                .get(&(*adt_id, ident.str.clone()))
+                .copied(),
+            _ => None,
+        }
+        .ok_or_else(|| RustIrError::MissingInherentAssociatedType(ident.clone()))
+    }
+
     /// Introduces new parameters, shifting the indices of existing
     /// parameters to accommodate them. The indices of the new binders
     /// will be assigned in order as they are iterated.
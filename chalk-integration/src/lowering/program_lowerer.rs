@@ -1,30 +1,42 @@
 use chalk_ir::cast::Cast;
 use chalk_ir::{
     self, AdtId, AssocTypeId, BoundVar, ClosureId, DebruijnIndex, FnDefId, ForeignDefId,
-    GeneratorId, ImplId, OpaqueTyId, TraitId, TyVariableKind, VariableKinds,
+    GeneratorId, ImplId, OpaqueTyId, TraitId, TyVariableKind, VariableKinds, Variances,
 };
 use chalk_parse::ast::*;
 use chalk_solve::rust_ir::{
-    self, Anonymize, AssociatedTyValueId, GeneratorDatum, GeneratorInputOutputDatum,
-    GeneratorWitnessDatum, GeneratorWitnessExistential, OpaqueTyDatum, OpaqueTyDatumBound,
+    self, Anonymize, AssociatedTyValueId, CoinductionSemantics, GeneratorDatum,
+    GeneratorInputOutputDatum, GeneratorWitnessDatum, GeneratorWitnessExistential, OpaqueTyDatum,
+    OpaqueTyDatumBound, ProgramFeatures,
 };
+use chalk_solve::simplified_type::SimplifiedType;
+use chalk_solve::variance::{infer_variances, VarianceItem};
 use rust_ir::IntoWhereClauses;
 use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 use string_cache::DefaultAtom as Atom;
 
 use super::{env::*, lower_adt_size_align, Lower, LowerParameterMap, LowerWithEnv, FIXME_SELF};
-use crate::error::RustIrError;
+use crate::error::{LoweringError, RustIrError};
 use crate::program::Program as LoweredProgram;
 use crate::RawId;
 use crate::{interner::ChalkIr, TypeKind, TypeSort};
 
-#[derive(Default)]
-pub(super) struct ProgramLowerer {
+/// The ids, names and kinds of the items of a program, which each of its
+/// items is lowered with. Nothing in here depends on the bodies of the
+/// items, nor on their spans.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProgramLowerer {
     next_item_index: u32,
 
-    associated_ty_lookups: AssociatedTyLookups,
-    associated_ty_value_ids: AssociatedTyValueIds,
+    pub(crate) associated_ty_lookups: AssociatedTyLookups,
+    pub(crate) associated_ty_value_ids: AssociatedTyValueIds,
+    inherent_assoc_ty_ids: InherentAssocTyIds,
+    /// The inherent impls giving a value to each inherent associated type,
+    /// in declaration order.
+    pub(crate) inherent_assoc_ty_impls: BTreeMap<AssocTypeId<ChalkIr>, Vec<RawId>>,
+    pub(crate) impl_ids: Vec<ImplId<ChalkIr>>,
+    pub(crate) clause_ids: Vec<RawId>,
     adt_ids: AdtIds,
     fn_def_ids: FnDefIds,
     closure_ids: ClosureIds,
@@ -38,11 +50,26 @@ pub(super) struct ProgramLowerer {
     closure_kinds: ClosureKinds,
     trait_kinds: TraitKinds,
     opaque_ty_kinds: OpaqueTyVariableKinds,
-    object_safe_traits: HashSet<TraitId<ChalkIr>>,
+    pub(crate) object_safe_traits: HashSet<TraitId<ChalkIr>>,
     foreign_ty_ids: ForeignIds,
+    pub(crate) well_known_traits: WellKnownTraits,
 }
 
 impl ProgramLowerer {
+    /// Hands out the ids of the items of `program`, based just on their
+    /// position, and collects their names and kinds.
+    pub fn new(program: &Program) -> Result<(Self, Vec<RawId>), Vec<LoweringError>> {
+        let mut lowerer = ProgramLowerer::default();
+        let raw_ids = program
+            .items
+            .iter()
+            .map(|_| lowerer.next_item_id())
+            .collect::<Vec<_>>();
+        lowerer.extract_associated_types(program, &raw_ids)?;
+        lowerer.extract_ids(program, &raw_ids)?;
+        Ok((lowerer, raw_ids))
+    }
+
     pub fn next_item_id(&mut self) -> RawId {
         let index = self.next_item_index;
         self.next_item_index += 1;
@@ -54,348 +81,496 @@ impl ProgramLowerer {
         &mut self,
         program: &Program,
         raw_ids: &[RawId],
-    ) -> LowerResult<()> {
+    ) -> Result<(), Vec<LoweringError>> {
+        let mut errors = vec![];
         for (item, &raw_id) in program.items.iter().zip(raw_ids) {
-            match item {
-                Item::TraitDefn(d) => {
-                    if d.flags.auto && !d.assoc_ty_defns.is_empty() {
-                        return Err(RustIrError::AutoTraitAssociatedTypes(d.name.clone()));
-                    }
-                    for defn in &d.assoc_ty_defns {
-                        let addl_variable_kinds = defn.all_parameters();
-                        let lookup = AssociatedTyLookup {
-                            id: AssocTypeId(self.next_item_id()),
-                            addl_variable_kinds: addl_variable_kinds.anonymize(),
-                        };
-                        self.associated_ty_lookups
-                            .insert((TraitId(raw_id), defn.name.str.clone()), lookup);
+            let extracted = (|| -> LowerResult<()> {
+                match item {
+                    Item::TraitDefn(d) => {
+                        if d.flags.auto && !d.assoc_ty_defns.is_empty() {
+                            return Err(RustIrError::AutoTraitAssociatedTypes(d.name.clone()));
+                        }
+                        for defn in &d.assoc_ty_defns {
+                            let addl_variable_kinds = defn.all_parameters();
+                            let lookup = AssociatedTyLookup {
+                                id: AssocTypeId(self.next_item_id()),
+                                addl_variable_kinds: addl_variable_kinds.anonymize(),
+                            };
+                            self.associated_ty_lookups
+                                .insert((TraitId(raw_id), defn.name.str.clone()), lookup);
+                        }
                     }
-                }
 
-                Item::Impl(d) => {
-                    for atv in &d.assoc_ty_values {
-                        let atv_id = AssociatedTyValueId(self.next_item_id());
-                        self.associated_ty_value_ids
-                            .insert((ImplId(raw_id), atv.name.str.clone()), atv_id);
+                    Item::Impl(d) => {
+                        for atv in &d.assoc_ty_values {
+                            let atv_id = AssociatedTyValueId(self.next_item_id());
+                            self.associated_ty_value_ids
+                                .insert((ImplId(raw_id), atv.name.str.clone()), atv_id);
+                        }
                     }
-                }
 
-                _ => {}
+                    _ => {}
+                }
+                Ok(())
+            })();
+            if let Err(error) = extracted {
+                errors.push(LoweringError::new(error, item_span(item), program));
             }
         }
-        Ok(())
+        errors_or(errors, ())
     }
 
-    pub fn extract_ids(&mut self, program: &Program, raw_ids: &[RawId]) -> LowerResult<()> {
+    pub fn extract_ids(
+        &mut self,
+        program: &Program,
+        raw_ids: &[RawId],
+    ) -> Result<(), Vec<LoweringError>> {
+        let mut errors = vec![];
         for (item, &raw_id) in program.items.iter().zip(raw_ids) {
-            match item {
-                Item::AdtDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = AdtId(raw_id);
-                    self.adt_ids.insert(type_kind.name.clone(), id);
-                    self.adt_kinds.insert(id, type_kind);
-                }
-                Item::FnDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = FnDefId(raw_id);
-                    self.fn_def_ids.insert(type_kind.name.clone(), id);
-                    self.fn_def_kinds.insert(id, type_kind);
-                }
-                Item::ClosureDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = ClosureId(raw_id);
-                    self.closure_ids.insert(defn.name.str.clone(), id);
-                    self.closure_kinds.insert(id, type_kind);
-                }
-                Item::TraitDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = TraitId(raw_id);
-                    self.trait_ids.insert(type_kind.name.clone(), id);
-                    self.trait_kinds.insert(id, type_kind);
-                    self.auto_traits.insert(id, defn.flags.auto);
-
-                    if defn.flags.object_safe {
-                        self.object_safe_traits.insert(id);
+            let extracted = (|| -> LowerResult<()> {
+                match item {
+                    Item::AdtDefn(defn) => {
+                        let type_kind = defn.lower_type_kind()?;
+                        let id = AdtId(raw_id);
+                        self.adt_ids.insert(type_kind.name.clone(), id);
+                        self.adt_kinds.insert(id, type_kind);
                     }
-                }
-                Item::OpaqueTyDefn(defn) => {
-                    let type_kind = defn.lower_type_kind()?;
-                    let id = OpaqueTyId(raw_id);
-                    self.opaque_ty_ids.insert(defn.name.str.clone(), id);
-                    self.opaque_ty_kinds.insert(id, type_kind);
-                }
-                Item::Foreign(ForeignDefn(ref ident)) => {
-                    self.foreign_ty_ids
-                        .insert(ident.str.clone(), ForeignDefId(raw_id));
-                }
-                Item::GeneratorDefn(defn) => {
-                    let id = GeneratorId(raw_id);
-                    self.generator_ids.insert(defn.name.str.clone(), id);
-                    self.generator_kinds.insert(id, defn.lower_type_kind()?);
-                }
-                Item::Impl(_) => continue,
-                Item::Clause(_) => continue,
-            };
-        }
-        Ok(())
-    }
+                    Item::FnDefn(defn) => {
+                        let type_kind = defn.lower_type_kind()?;
+                        let id = FnDefId(raw_id);
+                        self.fn_def_ids.insert(type_kind.name.clone(), id);
+                        self.fn_def_kinds.insert(id, type_kind);
+                    }
+                    Item::ClosureDefn(defn) => {
+                        let type_kind = defn.lower_type_kind()?;
+                        let id = ClosureId(raw_id);
+                        self.closure_ids.insert(defn.name.str.clone(), id);
+                        self.closure_kinds.insert(id, type_kind);
+                    }
+                    Item::TraitDefn(defn) => {
+                        let type_kind = defn.lower_type_kind()?;
+                        let id = TraitId(raw_id);
+                        self.trait_ids.insert(type_kind.name.clone(), id);
+                        self.trait_kinds.insert(id, type_kind);
+                        self.auto_traits.insert(id, defn.flags.auto);
+                        if let Some(well_known) = defn.well_known {
+                            self.well_known_traits.insert(well_known.lower(), id);
+                        }
 
-    pub fn lower(self, program: &Program, raw_ids: &[RawId]) -> LowerResult<LoweredProgram> {
-        let mut adt_data = BTreeMap::new();
-        let mut adt_reprs = BTreeMap::new();
-        let mut adt_size_aligns = BTreeMap::new();
-        let mut adt_variances = BTreeMap::new();
-        let mut fn_def_data = BTreeMap::new();
-        let mut fn_def_variances = BTreeMap::new();
-        let mut closure_inputs_and_output = BTreeMap::new();
-        let mut closure_closure_kind = BTreeMap::new();
-        let mut closure_upvars = BTreeMap::new();
-        let mut trait_data = BTreeMap::new();
-        let mut well_known_traits = BTreeMap::new();
-        let mut impl_data = BTreeMap::new();
-        let mut associated_ty_data = BTreeMap::new();
-        let mut associated_ty_values = BTreeMap::new();
-        let mut opaque_ty_data = BTreeMap::new();
-        let mut generator_data = BTreeMap::new();
-        let mut generator_witness_data = BTreeMap::new();
-        let mut hidden_opaque_types = BTreeMap::new();
-        let mut custom_clauses = Vec::new();
+                        if defn.flags.object_safe {
+                            self.object_safe_traits.insert(id);
+                        }
+                    }
+                    Item::OpaqueTyDefn(defn) => {
+                        let type_kind = defn.lower_type_kind()?;
+                        let id = OpaqueTyId(raw_id);
+                        self.opaque_ty_ids.insert(defn.name.str.clone(), id);
+                        self.opaque_ty_kinds.insert(id, type_kind);
+                    }
+                    Item::Foreign(ForeignDefn(ref ident)) => {
+                        self.foreign_ty_ids
+                            .insert(ident.str.clone(), ForeignDefId(raw_id));
+                    }
+                    Item::GeneratorDefn(defn) => {
+                        let id = GeneratorId(raw_id);
+                        self.generator_ids.insert(defn.name.str.clone(), id);
+                        self.generator_kinds.insert(id, defn.lower_type_kind()?);
+                    }
+                    Item::Impl(_) => self.impl_ids.push(ImplId(raw_id)),
+                    Item::Clause(_) => self.clause_ids.push(raw_id),
+                    Item::InherentImpl(_) | Item::Mod(_) | Item::Feature(_) | Item::Test(_) => {}
+                };
+                Ok(())
+            })();
+            if let Err(error) = extracted {
+                errors.push(LoweringError::new(error, item_span(item), program));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
+        // The inherent associated types are keyed by their ADT, so their
+        // ids can only be created once those of all ADTs are known.
         for (item, &raw_id) in program.items.iter().zip(raw_ids) {
-            let empty_env = Env {
-                adt_ids: &self.adt_ids,
-                adt_kinds: &self.adt_kinds,
-                fn_def_ids: &self.fn_def_ids,
-                fn_def_kinds: &self.fn_def_kinds,
-                closure_ids: &self.closure_ids,
-                closure_kinds: &self.closure_kinds,
-                trait_ids: &self.trait_ids,
-                trait_kinds: &self.trait_kinds,
-                opaque_ty_ids: &self.opaque_ty_ids,
-                opaque_ty_kinds: &self.opaque_ty_kinds,
-                generator_ids: &self.generator_ids,
-                generator_kinds: &self.generator_kinds,
-                associated_ty_lookups: &self.associated_ty_lookups,
-                parameter_map: BTreeMap::new(),
-                auto_traits: &self.auto_traits,
-                foreign_ty_ids: &self.foreign_ty_ids,
-            };
-
-            match *item {
-                Item::AdtDefn(ref d) => {
-                    let identifier = d.name.clone();
-                    let adt_id = AdtId(raw_id);
-                    adt_data.insert(adt_id, Arc::new((d, adt_id).lower(&empty_env)?));
-                    adt_reprs.insert(adt_id, Arc::new(d.repr.lower(&empty_env)?));
-                    adt_size_aligns.insert(adt_id, Arc::new(lower_adt_size_align(&d.flags)));
-                    let n_params = d.all_parameters().len();
-                    let variances = match d.variances.clone() {
-                        Some(v) => {
-                            if v.len() != n_params {
-                                return Err(RustIrError::IncorrectNumberOfVarianceParameters {
-                                    identifier,
-                                    expected: n_params,
-                                    actual: v.len(),
-                                });
-                            }
-                            v.into_iter()
-                                .map(|v| match v {
-                                    Variance::Invariant => chalk_ir::Variance::Invariant,
-                                    Variance::Covariant => chalk_ir::Variance::Covariant,
-                                    Variance::Contravariant => chalk_ir::Variance::Contravariant,
-                                })
-                                .collect()
+            let extracted = (|| -> LowerResult<()> {
+                if let Item::InherentImpl(d) = item {
+                    let adt_id = *self
+                        .adt_ids
+                        .get(&d.adt_name.str)
+                        .ok_or_else(|| RustIrError::NotStruct(d.adt_name.clone()))?;
+                    for atv in &d.assoc_ty_values {
+                        if !atv.variable_kinds.is_empty() {
+                            return Err(RustIrError::IncorrectNumberOfAssociatedTypeParameters {
+                                identifier: atv.name.clone(),
+                                expected: 0,
+                                actual: atv.variable_kinds.len(),
+                            });
                         }
-                        None => (0..n_params)
-                            .map(|_| chalk_ir::Variance::Invariant)
-                            .collect(),
-                    };
-                    adt_variances.insert(adt_id, variances);
-                }
-                Item::FnDefn(ref defn) => {
-                    let identifier = defn.name.clone();
-                    let fn_def_id = FnDefId(raw_id);
-                    fn_def_data.insert(fn_def_id, Arc::new((defn, fn_def_id).lower(&empty_env)?));
-                    let n_params = defn.all_parameters().len();
-                    let variances = match defn.variances.clone() {
-                        Some(v) => {
-                            if v.len() != n_params {
-                                return Err(RustIrError::IncorrectNumberOfVarianceParameters {
-                                    identifier,
-                                    expected: n_params,
-                                    actual: v.len(),
-                                });
+                        let key = (adt_id, atv.name.str.clone());
+                        let id = match self.inherent_assoc_ty_ids.get(&key) {
+                            Some(&id) => id,
+                            None => {
+                                let id = AssocTypeId(self.next_item_id());
+                                self.inherent_assoc_ty_ids.insert(key, id);
+                                id
                             }
-                            v.into_iter()
-                                .map(|v| match v {
-                                    Variance::Invariant => chalk_ir::Variance::Invariant,
-                                    Variance::Covariant => chalk_ir::Variance::Covariant,
-                                    Variance::Contravariant => chalk_ir::Variance::Contravariant,
-                                })
-                                .collect()
+                        };
+                        let impls = self.inherent_assoc_ty_impls.entry(id).or_default();
+                        if impls.last() != Some(&raw_id) {
+                            impls.push(raw_id);
                         }
-                        None => (0..n_params)
-                            .map(|_| chalk_ir::Variance::Invariant)
-                            .collect(),
-                    };
-                    fn_def_variances.insert(fn_def_id, variances);
-                }
-                Item::ClosureDefn(ref defn) => {
-                    let closure_def_id = ClosureId(raw_id);
-                    let (kind, inputs_and_output) = defn.lower(&empty_env)?;
-                    closure_closure_kind.insert(closure_def_id, kind);
-                    closure_inputs_and_output.insert(closure_def_id, inputs_and_output);
-                    let upvars =
-                        empty_env.in_binders(defn.all_parameters(), |env| {
-                            let upvar_tys: LowerResult<Vec<chalk_ir::Ty<ChalkIr>>> =
-                                defn.upvars.iter().map(|ty| ty.lower(env)).collect();
-                            let substitution = chalk_ir::Substitution::from_iter(
-                                ChalkIr,
-                                upvar_tys?.into_iter().map(|ty| ty.cast(ChalkIr)),
-                            );
-                            Ok(chalk_ir::TyKind::Tuple(defn.upvars.len(), substitution)
-                                .intern(ChalkIr))
-                        })?;
-                    closure_upvars.insert(closure_def_id, upvars);
+                    }
                 }
-                Item::TraitDefn(ref trait_defn) => {
-                    let trait_id = TraitId(raw_id);
-                    let trait_datum = (trait_defn, trait_id).lower(&empty_env)?;
+                Ok(())
+            })();
+            if let Err(error) = extracted {
+                errors.push(LoweringError::new(error, item_span(item), program));
+            }
+        }
+        errors_or(errors, ())
+    }
 
-                    if let Some(well_known) = trait_datum.well_known {
-                        well_known_traits.insert(well_known, trait_id);
-                    }
+    pub fn lower(
+        self,
+        program: &Program,
+        raw_ids: &[RawId],
+    ) -> Result<LoweredProgram, Vec<LoweringError>> {
+        let mut lowered = LoweredProgram::default();
+        let mut errors = vec![];
+
+        for (item, &raw_id) in program.items.iter().zip(raw_ids) {
+            if let Err(error) = self.lower_item_into(item, raw_id, &mut lowered) {
+                errors.push(LoweringError::new(error, item_span(item), program));
+            }
+        }
+
+        let program_features = lower_program_features(program);
+        if let Err(feature_errors) = &program_features {
+            errors.extend(feature_errors.iter().cloned());
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        lowered.adt_ids = self.adt_ids;
+        lowered.fn_def_ids = self.fn_def_ids;
+        lowered.closure_ids = self.closure_ids;
+        lowered.closure_kinds = self.closure_kinds;
+        lowered.trait_ids = self.trait_ids;
+        lowered.adt_kinds = self.adt_kinds;
+        lowered.fn_def_kinds = self.fn_def_kinds;
+        lowered.trait_kinds = self.trait_kinds;
+        lowered.generator_ids = self.generator_ids;
+        lowered.generator_kinds = self.generator_kinds;
+        lowered.well_known_traits = self.well_known_traits;
+        lowered.opaque_ty_ids = self.opaque_ty_ids;
+        lowered.opaque_ty_kinds = self.opaque_ty_kinds;
+        lowered.object_safe_traits = self.object_safe_traits;
+        lowered.foreign_ty_ids = self.foreign_ty_ids;
+        lowered.program_features = program_features.unwrap();
+        lowered.sources = program
+            .sources
+            .iter()
+            .map(|source| crate::program::Source {
+                path: source.path.clone(),
+                text: source.text.clone(),
+            })
+            .collect();
+        infer_undeclared_variances(&mut lowered);
+        Ok(lowered)
+    }
+
+    /// The item `item`, with the id `raw_id`, lowered on its own: the
+    /// returned program has the data of that item only, and the variances
+    /// it declares, if any.
+    pub fn lower_item(&self, item: &Item, raw_id: RawId) -> LowerResult<LoweredProgram> {
+        let mut lowered = LoweredProgram::default();
+        self.lower_item_into(item, raw_id, &mut lowered)?;
+        Ok(lowered)
+    }
 
-                    trait_data.insert(trait_id, Arc::new(trait_datum));
-
-                    for assoc_ty_defn in &trait_defn.assoc_ty_defns {
-                        let lookup = &self.associated_ty_lookups
-                            [&(trait_id, assoc_ty_defn.name.str.clone())];
-
-                        // The parameters in scope for the associated
-                        // type definitions are *both* those from the
-                        // trait *and* those from the associated type
-                        // itself.
-                        //
-                        // Insert the associated type parameters first
-                        // into the list so that they are given the
-                        // indices starting from 0. This corresponds
-                        // to the "de bruijn" convention where "more
-                        // inner" sets of parameters get the lower
-                        // indices:
-                        //
-                        // e.g., in this example, the indices would be
-                        // assigned `[A0, A1, T0, T1]`:
-                        //
-                        // ```
-                        // trait Foo<T0, T1> {
-                        //     type Bar<A0, A1>;
-                        // }
-                        // ```
-                        let mut variable_kinds = assoc_ty_defn.all_parameters();
-                        variable_kinds.extend(trait_defn.all_parameters());
-
-                        let binders = empty_env.in_binders(variable_kinds, |env| {
-                            Ok(rust_ir::AssociatedTyDatumBound {
-                                bounds: assoc_ty_defn.bounds.lower(env)?,
-                                where_clauses: assoc_ty_defn.where_clauses.lower(env)?,
-                            })
-                        })?;
-
-                        associated_ty_data.insert(
-                            lookup.id,
-                            Arc::new(rust_ir::AssociatedTyDatum {
-                                trait_id: TraitId(raw_id),
-                                id: lookup.id,
-                                name: assoc_ty_defn.name.str.clone(),
-                                binders,
-                            }),
-                        );
+    fn lower_item_into(
+        &self,
+        item: &Item,
+        raw_id: RawId,
+        lowered: &mut LoweredProgram,
+    ) -> LowerResult<()> {
+        let empty_env = Env {
+            adt_ids: &self.adt_ids,
+            adt_kinds: &self.adt_kinds,
+            fn_def_ids: &self.fn_def_ids,
+            fn_def_kinds: &self.fn_def_kinds,
+            closure_ids: &self.closure_ids,
+            closure_kinds: &self.closure_kinds,
+            trait_ids: &self.trait_ids,
+            trait_kinds: &self.trait_kinds,
+            opaque_ty_ids: &self.opaque_ty_ids,
+            opaque_ty_kinds: &self.opaque_ty_kinds,
+            generator_ids: &self.generator_ids,
+            generator_kinds: &self.generator_kinds,
+            associated_ty_lookups: &self.associated_ty_lookups,
+            inherent_assoc_ty_ids: &self.inherent_assoc_ty_ids,
+            parameter_map: BTreeMap::new(),
+            auto_traits: &self.auto_traits,
+            foreign_ty_ids: &self.foreign_ty_ids,
+            well_known_traits: &self.well_known_traits,
+        };
+
+        match *item {
+            Item::AdtDefn(ref d) => {
+                let identifier = d.name.clone();
+                let adt_id = AdtId(raw_id);
+                lowered
+                    .adt_data
+                    .insert(adt_id, Arc::new((d, adt_id).lower(&empty_env)?));
+                lowered
+                    .adt_reprs
+                    .insert(adt_id, Arc::new(d.repr.lower(&empty_env)?));
+                lowered
+                    .adt_size_aligns
+                    .insert(adt_id, Arc::new(lower_adt_size_align(&d.flags)));
+                let n_params = d.all_parameters().len();
+                // Items which do not declare their variances have
+                // them inferred once the program is lowered.
+                if let Some(v) = d.variances.clone() {
+                    if v.len() != n_params {
+                        return Err(RustIrError::IncorrectNumberOfVarianceParameters {
+                            identifier,
+                            expected: n_params,
+                            actual: v.len(),
+                        });
                     }
+                    let variances = v
+                        .into_iter()
+                        .map(|v| match v {
+                            Variance::Invariant => chalk_ir::Variance::Invariant,
+                            Variance::Covariant => chalk_ir::Variance::Covariant,
+                            Variance::Contravariant => chalk_ir::Variance::Contravariant,
+                        })
+                        .collect();
+                    lowered.adt_variances.insert(adt_id, variances);
                 }
-                Item::Impl(ref impl_defn) => {
-                    let impl_id = ImplId(raw_id);
-                    let impl_datum = Arc::new(
-                        (impl_defn, impl_id, &self.associated_ty_value_ids).lower(&empty_env)?,
-                    );
-                    impl_data.insert(impl_id, impl_datum.clone());
-                    let trait_id = impl_datum.trait_id();
-
-                    for atv in &impl_defn.assoc_ty_values {
-                        let atv_id = self.associated_ty_value_ids[&(impl_id, atv.name.str.clone())];
-                        let lookup = &self.associated_ty_lookups[&(trait_id, atv.name.str.clone())];
-
-                        // The parameters in scope for the associated
-                        // type definitions are *both* those from the
-                        // impl *and* those from the associated type
-                        // itself. As in the "trait" case above, we begin
-                        // with the parameters from the impl.
-                        let mut variable_kinds = atv.all_parameters();
-                        variable_kinds.extend(impl_defn.all_parameters());
-
-                        let value = empty_env.in_binders(variable_kinds, |env| {
-                            Ok(rust_ir::AssociatedTyValueBound {
-                                ty: atv.value.lower(env)?,
-                            })
-                        })?;
-
-                        associated_ty_values.insert(
-                            atv_id,
-                            Arc::new(rust_ir::AssociatedTyValue {
-                                impl_id,
-                                associated_ty_id: lookup.id,
-                                value,
-                            }),
-                        );
+            }
+            Item::FnDefn(ref defn) => {
+                let identifier = defn.name.clone();
+                let fn_def_id = FnDefId(raw_id);
+                lowered
+                    .fn_def_data
+                    .insert(fn_def_id, Arc::new((defn, fn_def_id).lower(&empty_env)?));
+                let n_params = defn.all_parameters().len();
+                if let Some(v) = defn.variances.clone() {
+                    if v.len() != n_params {
+                        return Err(RustIrError::IncorrectNumberOfVarianceParameters {
+                            identifier,
+                            expected: n_params,
+                            actual: v.len(),
+                        });
                     }
+                    let variances = v
+                        .into_iter()
+                        .map(|v| match v {
+                            Variance::Invariant => chalk_ir::Variance::Invariant,
+                            Variance::Covariant => chalk_ir::Variance::Covariant,
+                            Variance::Contravariant => chalk_ir::Variance::Contravariant,
+                        })
+                        .collect();
+                    lowered.fn_def_variances.insert(fn_def_id, variances);
+                }
+            }
+            Item::ClosureDefn(ref defn) => {
+                let closure_def_id = ClosureId(raw_id);
+                let (kind, inputs_and_output) = defn.lower(&empty_env)?;
+                lowered.closure_closure_kind.insert(closure_def_id, kind);
+                lowered
+                    .closure_inputs_and_output
+                    .insert(closure_def_id, inputs_and_output);
+                let upvars = empty_env.in_binders(defn.all_parameters(), |env| {
+                    let upvar_tys: LowerResult<Vec<chalk_ir::Ty<ChalkIr>>> =
+                        defn.upvars.iter().map(|ty| ty.lower(env)).collect();
+                    let substitution = chalk_ir::Substitution::from_iter(
+                        ChalkIr,
+                        upvar_tys?.into_iter().map(|ty| ty.cast(ChalkIr)),
+                    );
+                    Ok(chalk_ir::TyKind::Tuple(defn.upvars.len(), substitution).intern(ChalkIr))
+                })?;
+                lowered.closure_upvars.insert(closure_def_id, upvars);
+            }
+            Item::TraitDefn(ref trait_defn) => {
+                let trait_id = TraitId(raw_id);
+                let trait_datum = (trait_defn, trait_id).lower(&empty_env)?;
+                lowered.trait_data.insert(trait_id, Arc::new(trait_datum));
+
+                for assoc_ty_defn in &trait_defn.assoc_ty_defns {
+                    let lookup =
+                        &self.associated_ty_lookups[&(trait_id, assoc_ty_defn.name.str.clone())];
+
+                    // The parameters in scope for the associated
+                    // type definitions are *both* those from the
+                    // trait *and* those from the associated type
+                    // itself.
+                    //
+                    // Insert the associated type parameters first
+                    // into the list so that they are given the
+                    // indices starting from 0. This corresponds
+                    // to the "de bruijn" convention where "more
+                    // inner" sets of parameters get the lower
+                    // indices:
+                    //
+                    // e.g., in this example, the indices would be
+                    // assigned `[A0, A1, T0, T1]`:
+                    //
+                    // ```
+                    // trait Foo<T0, T1> {
+                    //     type Bar<A0, A1>;
+                    // }
+                    // ```
+                    let mut variable_kinds = assoc_ty_defn.all_parameters();
+                    variable_kinds.extend(trait_defn.all_parameters());
+
+                    let binders = empty_env.in_binders(variable_kinds, |env| {
+                        Ok(rust_ir::AssociatedTyDatumBound {
+                            bounds: assoc_ty_defn.bounds.lower(env)?,
+                            where_clauses: assoc_ty_defn.where_clauses.lower(env)?,
+                        })
+                    })?;
+
+                    lowered.associated_ty_data.insert(
+                        lookup.id,
+                        Arc::new(rust_ir::AssociatedTyDatum {
+                            trait_id: TraitId(raw_id),
+                            id: lookup.id,
+                            name: assoc_ty_defn.name.str.clone(),
+                            binders,
+                            span: Some(assoc_ty_defn.span.lower()),
+                        }),
+                    );
+                }
+            }
+            Item::Impl(ref impl_defn) => {
+                let impl_id = ImplId(raw_id);
+                let impl_datum = Arc::new(
+                    (impl_defn, impl_id, &self.associated_ty_value_ids).lower(&empty_env)?,
+                );
+                lowered.impl_data.insert(impl_id, impl_datum.clone());
+                let trait_id = impl_datum.trait_id();
+                lowered.impl_index.insert(
+                    trait_id,
+                    SimplifiedType::from_ty(
+                        ChalkIr,
+                        &impl_datum
+                            .binders
+                            .skip_binders()
+                            .trait_ref
+                            .self_type_parameter(ChalkIr),
+                    ),
+                    impl_id,
+                );
+
+                for atv in &impl_defn.assoc_ty_values {
+                    let atv_id = self.associated_ty_value_ids[&(impl_id, atv.name.str.clone())];
+                    let lookup = &self.associated_ty_lookups[&(trait_id, atv.name.str.clone())];
+
+                    // The parameters in scope for the associated
+                    // type definitions are *both* those from the
+                    // impl *and* those from the associated type
+                    // itself. As in the "trait" case above, we begin
+                    // with the parameters from the impl.
+                    let mut variable_kinds = atv.all_parameters();
+                    variable_kinds.extend(impl_defn.all_parameters());
+
+                    let value = empty_env.in_binders(variable_kinds, |env| {
+                        Ok(rust_ir::AssociatedTyValueBound {
+                            ty: atv.value.lower(env)?,
+                        })
+                    })?;
+
+                    lowered.associated_ty_values.insert(
+                        atv_id,
+                        Arc::new(rust_ir::AssociatedTyValue {
+                            impl_id,
+                            associated_ty_id: lookup.id,
+                            value,
+                            span: Some(atv.span.lower()),
+                        }),
+                    );
                 }
-                Item::Clause(ref clause) => {
-                    custom_clauses.extend(clause.lower(&empty_env)?);
+            }
+            Item::InherentImpl(ref impl_defn) => {
+                let adt_id = self.adt_ids[&impl_defn.adt_name.str];
+                let self_ty = Ty::Apply {
+                    name: impl_defn.adt_name.clone(),
+                    args: impl_defn.args.clone(),
+                };
+                for atv in &impl_defn.assoc_ty_values {
+                    let id = self.inherent_assoc_ty_ids[&(adt_id, atv.name.str.clone())];
+                    let value = empty_env.in_binders(impl_defn.all_parameters(), |env| {
+                        Ok(rust_ir::InherentAssocTyValue {
+                            self_ty: self_ty.lower(env)?,
+                            where_clauses: impl_defn.where_clauses.lower(env)?,
+                            ty: atv.value.lower(env)?,
+                        })
+                    })?;
+                    let datum = lowered.inherent_assoc_ty_data.entry(id).or_insert_with(|| {
+                        Arc::new(rust_ir::InherentAssocTyDatum {
+                            id,
+                            adt_id,
+                            name: atv.name.str.clone(),
+                            values: Vec::new(),
+                            span: Some(atv.span.lower()),
+                        })
+                    });
+                    Arc::make_mut(datum).values.push(value);
                 }
-                Item::OpaqueTyDefn(ref opaque_ty) => {
-                    if let Some(&opaque_ty_id) = self.opaque_ty_ids.get(&opaque_ty.name.str) {
-                        let variable_kinds = opaque_ty
-                            .variable_kinds
-                            .iter()
-                            .map(|k| k.lower())
-                            .collect::<Vec<_>>();
-
-                        // Introduce the parameters declared on the opaque type definition.
-                        // So if we have `type Foo<P1..Pn> = impl Trait<T1..Tn>`, this would introduce `P1..Pn`
-                        let binders = empty_env.in_binders(variable_kinds, |env| {
-                            let hidden_ty = opaque_ty.ty.lower(env)?;
-                            hidden_opaque_types.insert(opaque_ty_id, Arc::new(hidden_ty));
-
-                            // Introduce a variable to represent the hidden "self type". This will be used in the bounds.
-                            // So the `impl Trait<T1..Tn>` will be lowered to `exists<Self> { Self: Trait<T1..Tn> }`.
-                            let bounds: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
-                                .in_binders(
-                                    Some(chalk_ir::WithKind::new(
-                                        chalk_ir::VariableKind::Ty(TyVariableKind::General),
-                                        Atom::from(FIXME_SELF),
-                                    )),
-                                    |env| {
-                                        let interner = env.interner();
-                                        Ok(opaque_ty
-                                            .bounds
-                                            .lower(env)?
-                                            .iter()
-                                            .flat_map(|qil| {
-                                                // Instantiate the bounds with the innermost bound variable, which represents Self, as the self type.
-                                                qil.into_where_clauses(
-                                                    interner,
-                                                    chalk_ir::TyKind::BoundVar(BoundVar::new(
-                                                        DebruijnIndex::INNERMOST,
-                                                        0,
-                                                    ))
-                                                    .intern(interner),
-                                                )
-                                            })
-                                            .collect())
-                                    },
-                                )?;
-                            let where_clauses: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
-                                .in_binders(
+            }
+            Item::Clause(ref clause) => {
+                lowered.custom_clauses.extend(clause.lower(&empty_env)?);
+            }
+            Item::OpaqueTyDefn(ref opaque_ty) => {
+                if let Some(&opaque_ty_id) = self.opaque_ty_ids.get(&opaque_ty.name.str) {
+                    let variable_kinds = opaque_ty
+                        .variable_kinds
+                        .iter()
+                        .map(|k| k.lower())
+                        .collect::<Vec<_>>();
+
+                    // Introduce the parameters declared on the opaque type definition.
+                    // So if we have `type Foo<P1..Pn> = impl Trait<T1..Tn>`, this would introduce `P1..Pn`
+                    let binders = empty_env.in_binders(variable_kinds, |env| {
+                        let hidden_ty = opaque_ty.ty.lower(env)?;
+                        lowered
+                            .hidden_opaque_types
+                            .insert(opaque_ty_id, Arc::new(hidden_ty));
+
+                        // Introduce a variable to represent the hidden "self type". This will be used in the bounds.
+                        // So the `impl Trait<T1..Tn>` will be lowered to `exists<Self> { Self: Trait<T1..Tn> }`.
+                        let bounds: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env.in_binders(
+                            Some(chalk_ir::WithKind::new(
+                                chalk_ir::VariableKind::Ty(TyVariableKind::General),
+                                Atom::from(FIXME_SELF),
+                            )),
+                            |env| {
+                                let interner = env.interner();
+                                Ok(opaque_ty
+                                    .bounds
+                                    .lower(env)?
+                                    .iter()
+                                    .flat_map(|qil| {
+                                        // Instantiate the bounds with the innermost bound variable, which represents Self, as the self type.
+                                        qil.into_where_clauses(
+                                            interner,
+                                            chalk_ir::TyKind::BoundVar(BoundVar::new(
+                                                DebruijnIndex::INNERMOST,
+                                                0,
+                                            ))
+                                            .intern(interner),
+                                        )
+                                    })
+                                    .collect())
+                            },
+                        )?;
+                        let where_clauses: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
+                            .in_binders(
                                 Some(chalk_ir::WithKind::new(
                                     chalk_ir::VariableKind::Ty(TyVariableKind::General),
                                     Atom::from(FIXME_SELF),
@@ -403,108 +578,158 @@ impl ProgramLowerer {
                                 |env| opaque_ty.where_clauses.lower(env),
                             )?;
 
-                            Ok(OpaqueTyDatumBound {
-                                bounds,
-                                where_clauses,
-                            })
-                        })?;
+                        Ok(OpaqueTyDatumBound {
+                            bounds,
+                            where_clauses,
+                        })
+                    })?;
 
-                        opaque_ty_data.insert(
+                    lowered.opaque_ty_data.insert(
+                        opaque_ty_id,
+                        Arc::new(OpaqueTyDatum {
                             opaque_ty_id,
-                            Arc::new(OpaqueTyDatum {
-                                opaque_ty_id,
-                                bound: binders,
-                            }),
-                        );
-                    }
+                            bound: binders,
+                            span: Some(opaque_ty.span.lower()),
+                        }),
+                    );
                 }
-                Item::GeneratorDefn(ref defn) => {
-                    let variable_kinds = defn
-                        .variable_kinds
-                        .iter()
-                        .map(|k| k.lower())
-                        .collect::<Vec<_>>();
-
-                    let witness_lifetimes = defn
-                        .witness_lifetimes
-                        .iter()
-                        .map(|i| VariableKind::Lifetime(i.clone()).lower())
-                        .collect::<Vec<_>>();
-
-                    let input_output = empty_env.in_binders(variable_kinds.clone(), |env| {
-                        let yield_type = defn.yield_ty.lower(env)?;
-                        let resume_type = defn.resume_ty.lower(env)?;
-                        let return_type = defn.return_ty.lower(env)?;
-                        let upvars: Result<Vec<_>, _> =
-                            defn.upvars.iter().map(|ty| ty.lower(env)).collect();
-
-                        Ok(GeneratorInputOutputDatum {
-                            resume_type,
-                            yield_type,
-                            return_type,
-                            upvars: upvars?,
-                        })
+            }
+            Item::GeneratorDefn(ref defn) => {
+                let variable_kinds = defn
+                    .variable_kinds
+                    .iter()
+                    .map(|k| k.lower())
+                    .collect::<Vec<_>>();
+
+                let witness_lifetimes = defn
+                    .witness_lifetimes
+                    .iter()
+                    .map(|i| VariableKind::Lifetime(i.clone()).lower())
+                    .collect::<Vec<_>>();
+
+                let input_output = empty_env.in_binders(variable_kinds.clone(), |env| {
+                    let yield_type = defn.yield_ty.lower(env)?;
+                    let resume_type = defn.resume_ty.lower(env)?;
+                    let return_type = defn.return_ty.lower(env)?;
+                    let upvars: Result<Vec<_>, _> =
+                        defn.upvars.iter().map(|ty| ty.lower(env)).collect();
+
+                    Ok(GeneratorInputOutputDatum {
+                        resume_type,
+                        yield_type,
+                        return_type,
+                        upvars: upvars?,
+                    })
+                })?;
+
+                let inner_types = empty_env.in_binders(variable_kinds, |env| {
+                    let witnesses = env.in_binders(witness_lifetimes, |env| {
+                        let witnesses: Result<Vec<_>, _> =
+                            defn.witness_types.iter().map(|ty| ty.lower(env)).collect();
+                        witnesses
                     })?;
 
-                    let inner_types = empty_env.in_binders(variable_kinds, |env| {
-                        let witnesses = env.in_binders(witness_lifetimes, |env| {
-                            let witnesses: Result<Vec<_>, _> =
-                                defn.witness_types.iter().map(|ty| ty.lower(env)).collect();
-                            witnesses
-                        })?;
+                    Ok(GeneratorWitnessExistential { types: witnesses })
+                })?;
 
-                        Ok(GeneratorWitnessExistential { types: witnesses })
-                    })?;
+                let generator_datum = GeneratorDatum {
+                    movability: defn.movability.lower(),
+                    input_output,
+                };
+                let generator_witness = GeneratorWitnessDatum { inner_types };
 
-                    let generator_datum = GeneratorDatum {
-                        movability: defn.movability.lower(),
-                        input_output,
-                    };
-                    let generator_witness = GeneratorWitnessDatum { inner_types };
+                let id = self.generator_ids[&defn.name.str];
+                lowered.generator_data.insert(id, Arc::new(generator_datum));
+                lowered
+                    .generator_witness_data
+                    .insert(id, Arc::new(generator_witness));
+            }
+            Item::Foreign(_) | Item::Mod(_) | Item::Feature(_) | Item::Test(_) => {}
+        }
+        Ok(())
+    }
+}
 
-                    let id = self.generator_ids[&defn.name.str];
-                    generator_data.insert(id, Arc::new(generator_datum));
-                    generator_witness_data.insert(id, Arc::new(generator_witness));
+/// Infers the variances of the ADTs and fn defs of `program` which do not
+/// declare theirs, using the declared ones of the others.
+fn infer_undeclared_variances(program: &mut LoweredProgram) {
+    let interner = ChalkIr;
+    let declared = |item| {
+        let variances = match item {
+            VarianceItem::Adt(id) => program.adt_variances.get(&id),
+            VarianceItem::FnDef(id) => program.fn_def_variances.get(&id),
+        };
+        variances.map(|variances| Variances::from_iter(interner, variances.iter().copied()))
+    };
+    let inferred: Vec<_> = program
+        .adt_data
+        .keys()
+        .map(|&id| VarianceItem::Adt(id))
+        .chain(
+            program
+                .fn_def_data
+                .keys()
+                .map(|&id| VarianceItem::FnDef(id)),
+        )
+        .filter(|&item| declared(item).is_none())
+        .map(|item| (item, infer_variances(&*program, item, &declared)))
+        .collect();
+    for (item, variances) in inferred {
+        let variances = variances.as_slice(interner).to_vec();
+        match item {
+            VarianceItem::Adt(id) => program.adt_variances.insert(id, variances),
+            VarianceItem::FnDef(id) => program.fn_def_variances.insert(id, variances),
+        };
+    }
+}
+
+/// The features set by the `#![name]` attributes of `program`.
+pub(crate) fn lower_program_features(
+    program: &Program,
+) -> Result<ProgramFeatures, Vec<LoweringError>> {
+    let mut features = ProgramFeatures::default();
+    let mut errors = vec![];
+    for item in &program.items {
+        if let Item::Feature(name) = item {
+            match name.str.as_ref() {
+                "no_specialization" => features.specialization = false,
+                "no_implied_bounds" => features.implied_bounds = false,
+                "all_traits_coinductive" => features.coinduction = CoinductionSemantics::AllTraits,
+                "arbitrary_self_types" => features.arbitrary_self_types = true,
+                _ => {
+                    let error = RustIrError::UnknownFeature(name.clone());
+                    errors.push(LoweringError::new(error, None, program));
                 }
-                Item::Foreign(_) => {}
             }
         }
+    }
+    errors_or(errors, features)
+}
+
+/// `value`, unless there are `errors`.
+fn errors_or<T>(errors: Vec<LoweringError>, value: T) -> Result<T, Vec<LoweringError>> {
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
 
-        Ok(LoweredProgram {
-            adt_ids: self.adt_ids,
-            fn_def_ids: self.fn_def_ids,
-            closure_ids: self.closure_ids,
-            closure_upvars,
-            closure_kinds: self.closure_kinds,
-            trait_ids: self.trait_ids,
-            adt_kinds: self.adt_kinds,
-            fn_def_kinds: self.fn_def_kinds,
-            trait_kinds: self.trait_kinds,
-            adt_data,
-            adt_reprs,
-            adt_size_aligns,
-            adt_variances,
-            fn_def_data,
-            fn_def_variances,
-            closure_inputs_and_output,
-            closure_closure_kind,
-            generator_ids: self.generator_ids,
-            generator_kinds: self.generator_kinds,
-            generator_data,
-            generator_witness_data,
-            trait_data,
-            well_known_traits,
-            impl_data,
-            associated_ty_values,
-            associated_ty_data,
-            opaque_ty_ids: self.opaque_ty_ids,
-            opaque_ty_kinds: self.opaque_ty_kinds,
-            opaque_ty_data,
-            hidden_opaque_types,
-            custom_clauses,
-            object_safe_traits: self.object_safe_traits,
-            foreign_ty_ids: self.foreign_ty_ids,
-        })
+/// The span of `item`, which its lowering errors are reported at when they
+/// are not about a name.
+pub(super) fn item_span(item: &Item) -> Option<Span> {
+    match item {
+        Item::AdtDefn(defn) => Some(defn.span),
+        Item::FnDefn(defn) => Some(defn.span),
+        Item::ClosureDefn(defn) => Some(defn.name.span),
+        Item::TraitDefn(defn) => Some(defn.span),
+        Item::OpaqueTyDefn(defn) => Some(defn.span),
+        Item::GeneratorDefn(defn) => Some(defn.name.span),
+        Item::Impl(defn) => Some(defn.span),
+        Item::InherentImpl(defn) => Some(defn.span),
+        Item::Foreign(ForeignDefn(name)) | Item::Mod(name) | Item::Feature(name) => Some(name.span),
+        Item::Test(test) => Some(test.span),
+        Item::Clause(_) => None,
     }
 }
 
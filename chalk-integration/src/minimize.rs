@@ -0,0 +1,430 @@
+//! Shrinks a program that makes the solver misbehave on a goal to a
+//! smaller program that still does, to turn large reproducers (e.g. ones
+//! extracted from rustc) into bug reports a person can read.
+//!
+//! The minimizer repeatedly removes parts of the program -- items, where
+//! clauses, bounds, associated types, fields, variants and generic
+//! parameters -- and keeps each removal after which the program is still
+//! well-formed and the goal still shows the problem.
+
+use crate::db::ChalkDatabase;
+use crate::error::ChalkError;
+use crate::interner::ChalkIr;
+use crate::lowering::Lower;
+use crate::query::LoweringDatabase;
+use crate::SolverChoice;
+use chalk_parse::ast::{self, Item};
+use chalk_solve::ext::*;
+use chalk_solve::solve::Deadline;
+use chalk_solve::{GoalOptions, RustIrDatabase};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Once};
+use std::thread;
+use std::time::Duration;
+
+/// The name of the threads candidates are solved on, whose panics are
+/// expected and not printed.
+const CANDIDATE_THREAD: &str = "chalk-minimize-candidate";
+
+/// How many times the timeout a candidate is waited for before it is given
+/// up on, for solver paths which don't check the deadline.
+const HARD_TIMEOUT_FACTOR: u32 = 4;
+
+/// How much longer a candidate is waited for, to lower it before solving.
+const LOWERING_ALLOWANCE: Duration = Duration::from_secs(1);
+
+/// The problem a program is minimized for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// Solving the goal panics.
+    Panic,
+    /// Solving the goal gives this answer, as the REPL would print it
+    /// (e.g. "Unique; substitution [], lifetime constraints []" or
+    /// "No possible solution").
+    Answer(String),
+    /// Solving the goal takes longer than this.
+    Timeout(Duration),
+}
+
+/// What happened when a program was tried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Outcome {
+    /// The program, or the goal, does not lower.
+    Invalid,
+    Answer(String),
+    Panic,
+    Timeout,
+}
+
+/// Minimizes programs for a problem with one goal.
+pub struct Minimizer {
+    goal: String,
+    problem: Problem,
+    solver_choice: SolverChoice,
+}
+
+impl Minimizer {
+    pub fn new(goal: &str, problem: Problem, solver_choice: SolverChoice) -> Self {
+        Minimizer {
+            goal: goal.to_string(),
+            problem,
+            solver_choice,
+        }
+    }
+
+    /// Minimizes `program`, returning the text of the smallest program
+    /// found that still shows the problem. It is an error if `program`
+    /// itself does not show it.
+    ///
+    /// Candidates are tried through `Program::to_chalk_text`, so programs
+    /// with items that cannot be written out as text can't be minimized.
+    /// Each candidate is solved on a thread of its own, whose panics are
+    /// not printed. When looking for a timeout, the solver is given the
+    /// timeout (see `GoalOptions::timeout`); candidates which take much
+    /// longer anyway are given up on, and their threads left running.
+    pub fn minimize(&self, program: &ast::Program) -> Result<String, ChalkError> {
+        let text = write(&program.items)?;
+        match self.try_program(&text) {
+            outcome if self.shows_problem(&outcome) => {}
+            outcome => {
+                let error: Box<dyn std::error::Error> = format!(
+                    "the program does not show the problem: {}",
+                    describe(&outcome)
+                )
+                .into();
+                return Err(error.into());
+            }
+        }
+
+        let mut items = program.items.clone();
+        self.remove_item_chunks(&mut items);
+        while self.remove_parts(&mut items) {}
+        write(&items)
+    }
+
+    /// Tries to remove large runs of items at once, halving their length
+    /// each round, like delta debugging does.
+    fn remove_item_chunks(&self, items: &mut Vec<Item>) {
+        let mut chunk = items.len() / 2;
+        while chunk > 1 {
+            let mut start = 0;
+            while start < items.len() {
+                let end = (start + chunk).min(items.len());
+                let mut candidate = items.clone();
+                candidate.drain(start..end);
+                if self.still_shows_problem(&candidate) {
+                    *items = candidate;
+                } else {
+                    start = end;
+                }
+            }
+            chunk /= 2;
+        }
+    }
+
+    /// Tries to remove each part of the program in turn, keeping the
+    /// removals that preserve the problem. Returns whether anything was
+    /// removed.
+    fn remove_parts(&self, items: &mut Vec<Item>) -> bool {
+        let mut removed_any = false;
+        // Going backwards, removing a part never moves the ones left to try.
+        for part in parts(items).into_iter().rev() {
+            let mut candidate = items.clone();
+            part.remove_from(&mut candidate);
+            if self.still_shows_problem(&candidate) {
+                *items = candidate;
+                removed_any = true;
+            }
+        }
+        removed_any
+    }
+
+    fn still_shows_problem(&self, items: &[Item]) -> bool {
+        match write(items) {
+            Ok(text) => self.shows_problem(&self.try_program(&text)),
+            Err(_) => false,
+        }
+    }
+
+    fn shows_problem(&self, outcome: &Outcome) -> bool {
+        match (&self.problem, outcome) {
+            (Problem::Panic, Outcome::Panic) => true,
+            (Problem::Answer(expected), Outcome::Answer(answer)) => expected == answer,
+            (Problem::Timeout(_), Outcome::Timeout) => true,
+            _ => false,
+        }
+    }
+
+    /// Solves the goal in the program `text` on a new thread. When looking
+    /// for a timeout, the solver gives up on the goal once the timeout has
+    /// passed, and the thread is only waited for `HARD_TIMEOUT_FACTOR`
+    /// times the timeout (plus `LOWERING_ALLOWANCE`), in case the solver
+    /// doesn't.
+    fn try_program(&self, text: &str) -> Outcome {
+        silence_candidate_panics();
+        let timeout = match self.problem {
+            Problem::Timeout(timeout) => Some(timeout),
+            _ => None,
+        };
+        let (sender, receiver) = mpsc::channel();
+        let (text, goal, solver_choice) = (text.to_string(), self.goal.clone(), self.solver_choice);
+        thread::Builder::new()
+            .name(CANDIDATE_THREAD.to_string())
+            .spawn(move || {
+                let solved = panic::catch_unwind(AssertUnwindSafe(|| {
+                    solve(&text, &goal, solver_choice, timeout)
+                }));
+                let _ = sender.send(solved.unwrap_or(Outcome::Panic));
+            })
+            .expect("failed to spawn a thread to solve a candidate");
+        let received = match timeout {
+            Some(timeout) => receiver.recv_timeout(
+                timeout
+                    .saturating_mul(HARD_TIMEOUT_FACTOR)
+                    .saturating_add(LOWERING_ALLOWANCE),
+            ),
+            None => receiver
+                .recv()
+                .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(outcome) => outcome,
+            Err(mpsc::RecvTimeoutError::Timeout) => Outcome::Timeout,
+            // The thread dropped the sender without sending: it panicked
+            // outside of `catch_unwind`.
+            Err(mpsc::RecvTimeoutError::Disconnected) => Outcome::Panic,
+        }
+    }
+}
+
+/// Keeps the panic hook from printing the panics of the threads candidates
+/// are solved on, which are caught, while still printing those of the other
+/// threads.
+fn silence_candidate_panics() {
+    static SILENCED: Once = Once::new();
+    SILENCED.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if thread::current().name() != Some(CANDIDATE_THREAD) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Solves `goal` in the program `text`, giving up after `timeout`, and
+/// returns the answer as the REPL would print it. The goal timed out if the
+/// deadline has been reached once the solver gives up; it is only set once
+/// the program and goal are lowered, so that lowering them doesn't count.
+fn solve(
+    text: &str,
+    goal: &str,
+    solver_choice: SolverChoice,
+    timeout: Option<Duration>,
+) -> Outcome {
+    let db = ChalkDatabase::with(text, solver_choice);
+    if db.checked_program().is_err() {
+        return Outcome::Invalid;
+    }
+    let goal = match db.parse_and_lower_goal(goal) {
+        Ok(goal) => goal.into_peeled_goal(db.interner()),
+        Err(_) => return Outcome::Invalid,
+    };
+    let options = GoalOptions {
+        timeout,
+        ..GoalOptions::default()
+    };
+    let mut solver = solver_choice.into_solver();
+    db.with_program(|_| {
+        let deadline = timeout.and_then(Deadline::after);
+        let answer = match solver.solve_with_options(&db, &goal, &options) {
+            Some(solution) => solution.display(ChalkIr).to_string(),
+            None => "No possible solution".to_string(),
+        };
+        if deadline.is_some_and(|deadline| deadline.is_reached()) {
+            Outcome::Timeout
+        } else {
+            Outcome::Answer(answer)
+        }
+    })
+}
+
+fn describe(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Invalid => "it, or the goal, is invalid".to_string(),
+        Outcome::Answer(answer) => format!("the answer is `{}`", answer),
+        Outcome::Panic => "solving the goal panics".to_string(),
+        Outcome::Timeout => "solving the goal times out".to_string(),
+    }
+}
+
+/// Writes the program made of `items` out as text.
+fn write(items: &[Item]) -> Result<String, ChalkError> {
+    let program = ast::Program {
+        items: items.to_vec(),
+        sources: vec![],
+    };
+    program.lower()?.to_chalk_text()
+}
+
+/// A part of a program that the minimizer tries to remove. The first field
+/// is the index of the item the part is in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Item(usize),
+    WhereClause(usize, usize),
+    Parameter(usize, usize),
+    Field(usize, usize, usize),
+    Variant(usize, usize),
+    /// An associated type of a trait, or an associated type value of an
+    /// impl.
+    AssocTy(usize, usize),
+    AssocTyBound(usize, usize, usize),
+    AssocTyWhereClause(usize, usize, usize),
+    /// A bound of an opaque type.
+    Bound(usize, usize),
+}
+
+impl Part {
+    fn remove_from(self, items: &mut Vec<Item>) {
+        match self {
+            Part::Item(item) => {
+                items.remove(item);
+            }
+            Part::WhereClause(item, i) => {
+                if let Some(where_clauses) = where_clauses(&mut items[item]) {
+                    where_clauses.remove(i);
+                }
+            }
+            Part::Parameter(item, i) => {
+                let (variable_kinds, variances) = match &mut items[item] {
+                    Item::AdtDefn(adt) => (&mut adt.variable_kinds, adt.variances.as_mut()),
+                    Item::FnDefn(fn_def) => (&mut fn_def.variable_kinds, fn_def.variances.as_mut()),
+                    Item::TraitDefn(trait_defn) => (&mut trait_defn.variable_kinds, None),
+                    Item::OpaqueTyDefn(opaque_ty) => (&mut opaque_ty.variable_kinds, None),
+                    Item::Impl(impl_defn) => (&mut impl_defn.variable_kinds, None),
+                    Item::InherentImpl(impl_defn) => (&mut impl_defn.variable_kinds, None),
+                    _ => return,
+                };
+                variable_kinds.remove(i);
+                if let Some(variances) = variances {
+                    variances.remove(i);
+                }
+            }
+            Part::Field(item, variant, i) => {
+                if let Item::AdtDefn(adt) = &mut items[item] {
+                    adt.variants[variant].fields.remove(i);
+                }
+            }
+            Part::Variant(item, i) => {
+                if let Item::AdtDefn(adt) = &mut items[item] {
+                    adt.variants.remove(i);
+                }
+            }
+            Part::AssocTy(item, i) => match &mut items[item] {
+                Item::TraitDefn(trait_defn) => {
+                    trait_defn.assoc_ty_defns.remove(i);
+                }
+                Item::Impl(impl_defn) => {
+                    impl_defn.assoc_ty_values.remove(i);
+                }
+                Item::InherentImpl(impl_defn) => {
+                    impl_defn.assoc_ty_values.remove(i);
+                }
+                _ => {}
+            },
+            Part::AssocTyBound(item, assoc_ty, i) => {
+                if let Item::TraitDefn(trait_defn) = &mut items[item] {
+                    trait_defn.assoc_ty_defns[assoc_ty].bounds.remove(i);
+                }
+            }
+            Part::AssocTyWhereClause(item, assoc_ty, i) => {
+                if let Item::TraitDefn(trait_defn) = &mut items[item] {
+                    trait_defn.assoc_ty_defns[assoc_ty].where_clauses.remove(i);
+                }
+            }
+            Part::Bound(item, i) => {
+                if let Item::OpaqueTyDefn(opaque_ty) = &mut items[item] {
+                    opaque_ty.bounds.remove(i);
+                }
+            }
+        }
+    }
+}
+
+/// The where clauses of `item`, if it can have any.
+fn where_clauses(item: &mut Item) -> Option<&mut Vec<ast::QuantifiedWhereClause>> {
+    match item {
+        Item::AdtDefn(adt) => Some(&mut adt.where_clauses),
+        Item::FnDefn(fn_def) => Some(&mut fn_def.where_clauses),
+        Item::TraitDefn(trait_defn) => Some(&mut trait_defn.where_clauses),
+        Item::OpaqueTyDefn(opaque_ty) => Some(&mut opaque_ty.where_clauses),
+        Item::Impl(impl_defn) => Some(&mut impl_defn.where_clauses),
+        Item::InherentImpl(impl_defn) => Some(&mut impl_defn.where_clauses),
+        _ => None,
+    }
+}
+
+/// The parts of the program made of `items`, in order. The parts of an
+/// item come after the item, and the parts of a part after the part, so
+/// that going backwards, a part is only removed once what is in it has been
+/// tried.
+fn parts(items: &[Item]) -> Vec<Part> {
+    let mut parts = Vec::new();
+    for (item, defn) in items.iter().enumerate() {
+        parts.push(Part::Item(item));
+        let (variable_kinds, where_clauses) = match defn {
+            Item::AdtDefn(adt) => {
+                if adt.flags.kind == ast::AdtKind::Enum {
+                    parts.extend((0..adt.variants.len()).map(|i| Part::Variant(item, i)));
+                }
+                for (variant, fields) in adt.variants.iter().map(|v| &v.fields).enumerate() {
+                    parts.extend((0..fields.len()).map(|i| Part::Field(item, variant, i)));
+                }
+                (adt.variable_kinds.len(), adt.where_clauses.len())
+            }
+            Item::FnDefn(fn_def) => (fn_def.variable_kinds.len(), fn_def.where_clauses.len()),
+            Item::TraitDefn(trait_defn) => {
+                for (assoc_ty, defn) in trait_defn.assoc_ty_defns.iter().enumerate() {
+                    parts.push(Part::AssocTy(item, assoc_ty));
+                    parts.extend(
+                        (0..defn.bounds.len()).map(|i| Part::AssocTyBound(item, assoc_ty, i)),
+                    );
+                    parts.extend(
+                        (0..defn.where_clauses.len())
+                            .map(|i| Part::AssocTyWhereClause(item, assoc_ty, i)),
+                    );
+                }
+                (
+                    trait_defn.variable_kinds.len(),
+                    trait_defn.where_clauses.len(),
+                )
+            }
+            Item::OpaqueTyDefn(opaque_ty) => {
+                parts.extend((0..opaque_ty.bounds.len()).map(|i| Part::Bound(item, i)));
+                (
+                    opaque_ty.variable_kinds.len(),
+                    opaque_ty.where_clauses.len(),
+                )
+            }
+            Item::Impl(impl_defn) => {
+                parts.extend((0..impl_defn.assoc_ty_values.len()).map(|i| Part::AssocTy(item, i)));
+                (
+                    impl_defn.variable_kinds.len(),
+                    impl_defn.where_clauses.len(),
+                )
+            }
+            Item::InherentImpl(impl_defn) => {
+                parts.extend((0..impl_defn.assoc_ty_values.len()).map(|i| Part::AssocTy(item, i)));
+                (
+                    impl_defn.variable_kinds.len(),
+                    impl_defn.where_clauses.len(),
+                )
+            }
+            _ => (0, 0),
+        };
+        parts.extend((0..variable_kinds).map(|i| Part::Parameter(item, i)));
+        parts.extend((0..where_clauses).map(|i| Part::WhereClause(item, i)));
+    }
+    parts
+}
@@ -0,0 +1,94 @@
+//! Translation between the ids of the items of a program and their names.
+
+use crate::interner::ChalkIr;
+use crate::program::Program;
+use crate::Identifier;
+use chalk_ir::{AdtId, AssocTypeId, TraitId};
+use std::collections::BTreeMap;
+
+/// The names of the ADTs, traits and associated types of a program, by id
+/// and by name, so that tools can translate the ids in the output of the
+/// solvers back to the identifiers of the source (see `Program::name_map`).
+///
+/// Associated types are named by their trait and their own name, like
+/// `Iterator` and `Item`. Associated types of inherent impls are not
+/// included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameMap {
+    adt_ids: BTreeMap<Identifier, AdtId<ChalkIr>>,
+    adt_names: BTreeMap<AdtId<ChalkIr>, Identifier>,
+    trait_ids: BTreeMap<Identifier, TraitId<ChalkIr>>,
+    trait_names: BTreeMap<TraitId<ChalkIr>, Identifier>,
+    assoc_type_ids: BTreeMap<(TraitId<ChalkIr>, Identifier), AssocTypeId<ChalkIr>>,
+    assoc_type_names: BTreeMap<AssocTypeId<ChalkIr>, (TraitId<ChalkIr>, Identifier)>,
+}
+
+impl NameMap {
+    pub(crate) fn new(program: &Program) -> Self {
+        let adt_names = program
+            .adt_kinds
+            .iter()
+            .map(|(&id, kind)| (id, kind.name.clone()))
+            .collect();
+        let trait_names = program
+            .trait_kinds
+            .iter()
+            .map(|(&id, kind)| (id, kind.name.clone()))
+            .collect();
+        let assoc_type_names: BTreeMap<_, _> = program
+            .associated_ty_data
+            .iter()
+            .map(|(&id, datum)| (id, (datum.trait_id, datum.name.clone())))
+            .collect();
+        let assoc_type_ids = assoc_type_names
+            .iter()
+            .map(|(&id, name)| (name.clone(), id))
+            .collect();
+        NameMap {
+            adt_ids: program.adt_ids.clone(),
+            adt_names,
+            trait_ids: program.trait_ids.clone(),
+            trait_names,
+            assoc_type_ids,
+            assoc_type_names,
+        }
+    }
+
+    /// The ADT named `name`.
+    pub fn adt_id(&self, name: &str) -> Option<AdtId<ChalkIr>> {
+        self.adt_ids.get(&Identifier::from(name)).copied()
+    }
+
+    /// The name of the ADT `id`.
+    pub fn adt_name(&self, id: AdtId<ChalkIr>) -> Option<&str> {
+        self.adt_names.get(&id).map(|name| &**name)
+    }
+
+    /// The trait named `name`.
+    pub fn trait_id(&self, name: &str) -> Option<TraitId<ChalkIr>> {
+        self.trait_ids.get(&Identifier::from(name)).copied()
+    }
+
+    /// The name of the trait `id`.
+    pub fn trait_name(&self, id: TraitId<ChalkIr>) -> Option<&str> {
+        self.trait_names.get(&id).map(|name| &**name)
+    }
+
+    /// The associated type named `name` of the trait `trait_id`.
+    pub fn assoc_type_id(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+        name: &str,
+    ) -> Option<AssocTypeId<ChalkIr>> {
+        self.assoc_type_ids
+            .get(&(trait_id, Identifier::from(name)))
+            .copied()
+    }
+
+    /// The trait of the associated type `id`, and its name within the trait.
+    pub fn assoc_type_name(&self, id: AssocTypeId<ChalkIr>) -> Option<(TraitId<ChalkIr>, &str)> {
+        self.assoc_type_names
+            .get(&id)
+            .map(|(trait_id, name)| (*trait_id, &**name))
+    }
+}
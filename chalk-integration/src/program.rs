@@ -1,25 +1,36 @@
-use crate::interner::ChalkIr;
+use crate::error::ChalkError;
+use crate::interner::{ChalkFnAbi, ChalkIr};
+use crate::name_map::NameMap;
 use crate::{tls, Identifier, TypeKind};
 use chalk_ir::{could_match::CouldMatch, UnificationDatabase};
 use chalk_ir::{debug::Angle, Variance};
 use chalk_ir::{
-    debug::SeparatorTraitRef, AdtId, AliasTy, AssocTypeId, Binders, CanonicalVarKinds, ClosureId,
-    FnDefId, ForeignDefId, GeneratorId, GenericArg, Goal, Goals, ImplId, IntTy, Lifetime, OpaqueTy,
+    debug::SeparatorTraitRef, AdtId, AliasTy, AssocTypeId, Binders, ClosureId, FnDefId,
+    ForeignDefId, GeneratorId, GenericArg, Goal, Goals, ImplId, IntTy, Lifetime, OpaqueTy,
     OpaqueTyId, ProgramClause, ProgramClauseImplication, ProgramClauses, ProjectionTy, Scalar,
     Substitution, TraitId, Ty, TyKind, UintTy, Variances,
 };
+use chalk_solve::display::{write_items, write_program_features, WriterState};
+use chalk_solve::logging_db::RecordedItemId;
 use chalk_solve::rust_ir::{
-    AdtDatum, AdtRepr, AdtSizeAlign, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId,
-    ClosureKind, FnDefDatum, FnDefInputsAndOutputDatum, GeneratorDatum, GeneratorWitnessDatum,
-    ImplDatum, ImplType, OpaqueTyDatum, TraitDatum, WellKnownTrait,
+    AdtDatum, AdtKind, AdtRepr, AdtSizeAlign, AssociatedTyDatum, AssociatedTyValue,
+    AssociatedTyValueId, ClosureKind, FnDefDatum, FnDefInputsAndOutputDatum, GeneratorDatum,
+    GeneratorWitnessDatum, ImplDatum, ImplType, InherentAssocTyDatum, OpaqueTyDatum,
+    ProgramFeatures, Span, TraitDatum, WellKnownTrait,
 };
+use chalk_solve::simplified_type::{ImplFilter, ImplIndex, SimplifiedType};
 use chalk_solve::split::Split;
+use chalk_solve::variance::VarianceItem;
 use chalk_solve::RustIrDatabase;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashSet};
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Program {
     /// From ADT name to item-id. Used during lowering only.
     pub adt_ids: BTreeMap<Identifier, AdtId<ChalkIr>>,
@@ -74,6 +85,10 @@ pub struct Program {
     /// For each impl:
     pub impl_data: BTreeMap<ImplId<ChalkIr>, Arc<ImplDatum<ChalkIr>>>,
 
+    /// The impls of each trait, indexed by the simplified type of their
+    /// self type.
+    pub impl_index: ImplIndex<ChalkIr>,
+
     /// For each associated ty value `type Foo = XXX` found in an impl:
     pub associated_ty_values:
         BTreeMap<AssociatedTyValueId<ChalkIr>, Arc<AssociatedTyValue<ChalkIr>>>,
@@ -99,6 +114,10 @@ pub struct Program {
     /// For each associated ty declaration `type Foo` found in a trait:
     pub associated_ty_data: BTreeMap<AssocTypeId<ChalkIr>, Arc<AssociatedTyDatum<ChalkIr>>>,
 
+    /// For each associated ty `type Foo = XXX` found in inherent impls, by
+    /// ADT and name:
+    pub inherent_assoc_ty_data: BTreeMap<AssocTypeId<ChalkIr>, Arc<InherentAssocTyDatum<ChalkIr>>>,
+
     /// For each user-specified clause
     pub custom_clauses: Vec<ProgramClause<ChalkIr>>,
 
@@ -107,9 +126,111 @@ pub struct Program {
 
     /// For each foreign type `extern { type A; }`
     pub foreign_ty_ids: BTreeMap<Identifier, ForeignDefId<ChalkIr>>,
+
+    /// The features set by the `#![name]` attributes of the program.
+    pub program_features: ProgramFeatures,
+
+    /// The texts the program was parsed from, which the spans of its
+    /// declarations point into.
+    pub sources: Vec<Source>,
+}
+
+/// A text the program, or one of its modules, was parsed from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Source {
+    /// The file the text was read from, if any.
+    pub path: Option<PathBuf>,
+    pub text: String,
 }
 
 impl Program {
+    /// Describes where `span` is in the sources of the program, underlining
+    /// it in its line like syntax errors are.
+    pub fn describe_span(&self, span: Span) -> Option<String> {
+        let source = self.sources.get(span.source)?;
+        source.text.get(span.lo..span.hi)?;
+        let span = chalk_parse::ast::Span::new(span.source, span.lo, span.hi);
+        let location = chalk_parse::Location::new(&source.text, span);
+        let description = location.display(source.path.as_deref()).to_string();
+        Some(description)
+    }
+
+    /// Writes the program back out as `.chalk` text, which parses and
+    /// lowers to the same program, up to the spans of its declarations and
+    /// the names of enum variants and fields.
+    ///
+    /// Closures, generators, foreign types, custom clauses and functions
+    /// with a non-Rust ABI cannot be written out yet; programs with any of
+    /// them are an error.
+    pub fn to_chalk_text(&self) -> Result<String, ChalkError> {
+        let unsupported = if !self.closure_ids.is_empty() {
+            Some("closures")
+        } else if !self.generator_ids.is_empty() {
+            Some("generators")
+        } else if !self.foreign_ty_ids.is_empty() {
+            Some("foreign types")
+        } else if !self.custom_clauses.is_empty() {
+            Some("custom clauses")
+        } else if self
+            .fn_def_data
+            .values()
+            .any(|datum| datum.sig.abi != ChalkFnAbi::Rust)
+        {
+            Some("functions with a non-Rust ABI")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            let error: Box<dyn std::error::Error> =
+                format!("cannot write {} as `.chalk` text", unsupported).into();
+            return Err(error.into());
+        }
+
+        let mut text = String::new();
+        write_program_features(&mut text, self.program_features)
+            .and_then(|()| {
+                write_items::<_, _, Program, _, _>(
+                    &mut text,
+                    &WriterState::new(self),
+                    self.item_ids(),
+                )
+            })
+            .expect("writing to a string cannot fail");
+        Ok(text)
+    }
+
+    /// Returns the names of the ADTs, traits and associated types of the
+    /// program, by id and by name.
+    pub fn name_map(&self) -> NameMap {
+        NameMap::new(self)
+    }
+
+    /// Returns the ids of the items `to_chalk_text` writes out, in the
+    /// order they were declared in. Associated types are written along
+    /// with their traits, and associated type values with their impls.
+    pub fn item_ids(&self) -> impl Iterator<Item = RecordedItemId<ChalkIr>> + '_ {
+        macro_rules! grab_ids {
+            ($map:expr) => {
+                $map.keys()
+                    .copied()
+                    .map(|id| (id.0, RecordedItemId::from(id)))
+            };
+        }
+        let mut ids = std::iter::empty()
+            .chain(grab_ids!(self.adt_data))
+            .chain(grab_ids!(self.trait_data))
+            .chain(grab_ids!(self.impl_data))
+            .chain(grab_ids!(self.opaque_ty_data))
+            .chain(grab_ids!(self.fn_def_data))
+            .collect::<Vec<_>>();
+
+        // Raw ids are handed out in declaration order, which keeps the ids
+        // of the items the same when the written program is lowered again.
+        ids.sort_by_key(|(raw_id, _)| *raw_id);
+        ids.into_iter().map(|(_, id)| id)
+    }
+
     /// Returns the ids for all impls declared in this crate.
     pub(crate) fn local_impl_ids(&self) -> Vec<ImplId<ChalkIr>> {
         self.impl_data
@@ -118,6 +239,21 @@ impl Program {
             .map(|(&impl_id, _)| impl_id)
             .collect()
     }
+
+    /// Serializes this program into a compact binary snapshot (with
+    /// `bincode`), which can be cached (e.g., on disk) and loaded with
+    /// `from_bytes` instead of parsing and lowering the program again.
+    /// Snapshots are only meant to be loaded by the same version of chalk.
+    #[cfg(feature = "serde")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("programs are always serializable")
+    }
+
+    /// Loads a program from a snapshot written by `to_bytes`.
+    #[cfg(feature = "serde")]
+    pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Program> {
+        bincode::deserialize(bytes)
+    }
 }
 
 impl tls::DebugContext for Program {
@@ -156,6 +292,8 @@ impl tls::DebugContext for Program {
     ) -> Result<(), fmt::Error> {
         if let Some(d) = self.associated_ty_data.get(&assoc_type_id) {
             write!(fmt, "({:?}::{})", d.trait_id, d.name)
+        } else if let Some(d) = self.inherent_assoc_ty_data.get(&assoc_type_id) {
+            write!(fmt, "({:?}::{})", d.adt_id, d.name)
         } else {
             fmt.debug_struct("InvalidAssocTypeId")
                 .field("index", &assoc_type_id.0)
@@ -199,6 +337,15 @@ impl tls::DebugContext for Program {
         match alias_ty {
             AliasTy::Projection(projection_ty) => self.debug_projection_ty(projection_ty, fmt),
             AliasTy::Opaque(opaque_ty) => self.debug_opaque_ty(opaque_ty, fmt),
+            AliasTy::Inherent(inherent_ty) => {
+                let datum = &self.inherent_assoc_ty_data[&inherent_ty.associated_ty_id];
+                write!(
+                    fmt,
+                    "<{:?}>::{}",
+                    inherent_ty.self_type_parameter(ChalkIr),
+                    datum.name
+                )
+            }
         }
     }
 
@@ -405,6 +552,13 @@ impl RustIrDatabase<ChalkIr> for Program {
         self.associated_ty_values[&id].clone()
     }
 
+    fn inherent_assoc_ty_data(
+        &self,
+        id: AssocTypeId<ChalkIr>,
+    ) -> Option<Arc<InherentAssocTyDatum<ChalkIr>>> {
+        self.inherent_assoc_ty_data.get(&id).cloned()
+    }
+
     fn opaque_ty_data(&self, id: OpaqueTyId<ChalkIr>) -> Arc<OpaqueTyDatum<ChalkIr>> {
         self.opaque_ty_data[&id].clone()
     }
@@ -443,25 +597,23 @@ impl RustIrDatabase<ChalkIr> for Program {
     fn impls_for_trait(
         &self,
         trait_id: TraitId<ChalkIr>,
-        parameters: &[GenericArg<ChalkIr>],
-        _binders: &CanonicalVarKinds<ChalkIr>,
+        filter: &ImplFilter<'_, ChalkIr>,
     ) -> Vec<ImplId<ChalkIr>> {
-        let interner = self.interner();
-        self.impl_data
-            .iter()
-            .filter(|(_, impl_datum)| {
-                let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
-                trait_id == trait_ref.trait_id && {
-                    assert_eq!(trait_ref.substitution.len(interner), parameters.len());
-                    parameters.could_match(
-                        interner,
-                        self.unification_database(),
-                        trait_ref.substitution.as_slice(interner),
-                    )
-                }
-            })
-            .map(|(&impl_id, _)| impl_id)
-            .collect()
+        let mut impl_ids = self
+            .impl_index
+            .impls_for_self_type(trait_id, filter.self_type());
+        retain_impls_matching(self, &mut impl_ids, filter);
+        impl_ids
+    }
+
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+        self_ty: &SimplifiedType<ChalkIr>,
+    ) -> Vec<ImplId<ChalkIr>> {
+        let mut impl_ids = self.impl_index.impls_for_self_type(trait_id, Some(self_ty));
+        impl_ids.sort_unstable();
+        impl_ids
     }
 
     fn local_impls_to_coherence_check(&self, trait_id: TraitId<ChalkIr>) -> Vec<ImplId<ChalkIr>> {
@@ -479,52 +631,25 @@ impl RustIrDatabase<ChalkIr> for Program {
         auto_trait_id: TraitId<ChalkIr>,
         impl_ty: &TyKind<ChalkIr>,
     ) -> bool {
-        let interner = self.interner();
-
-        // we don't compare actual substitutions as
-        // - given a `struct S<T>`; an implementation for `S<A>` should suppress an auto impl for `S<B>`, and
-        // - an implementation for `[A]` should suppress an auto impl for `[B]`, and
-        // - an implementation for `(A, B, C)` should suppress an auto impl for `(D, E, F)`
-        // this may change later
-        self.impl_data.values().any(|impl_datum| {
-            if impl_datum.trait_id() != auto_trait_id {
-                return false;
-            }
-
-            let ty = impl_datum
-                .binders
-                .skip_binders()
-                .trait_ref
-                .self_type_parameter(interner);
-            match (impl_ty, ty.kind(interner)) {
-                (TyKind::Adt(id_a, _), TyKind::Adt(id_b, _)) => id_a == id_b,
-                (TyKind::AssociatedType(id_a, _), TyKind::AssociatedType(id_b, _)) => id_a == id_b,
-                (TyKind::Scalar(scalar_a), TyKind::Scalar(scalar_b)) => scalar_a == scalar_b,
-                (TyKind::Str, TyKind::Str) => true,
-                (TyKind::Tuple(arity_a, _), TyKind::Tuple(arity_b, _)) => arity_a == arity_b,
-                (TyKind::OpaqueType(id_a, _), TyKind::OpaqueType(id_b, _)) => id_a == id_b,
-                (TyKind::Slice(_), TyKind::Slice(_)) => true,
-                (TyKind::FnDef(id_a, _), TyKind::FnDef(id_b, _)) => id_a == id_b,
-                (TyKind::Ref(id_a, _, _), TyKind::Ref(id_b, _, _)) => id_a == id_b,
-                (TyKind::Raw(id_a, _), TyKind::Raw(id_b, _)) => id_a == id_b,
-                (TyKind::Never, TyKind::Never) => true,
-                (TyKind::Array(_, _), TyKind::Array(_, _)) => true,
-                (TyKind::Closure(id_a, _), TyKind::Closure(id_b, _)) => id_a == id_b,
-                (TyKind::Generator(id_a, _), TyKind::Generator(id_b, _)) => id_a == id_b,
-                (TyKind::GeneratorWitness(id_a, _), TyKind::GeneratorWitness(id_b, _)) => {
-                    id_a == id_b
-                }
-                (TyKind::Foreign(id_a), TyKind::Foreign(id_b)) => id_a == id_b,
-                (TyKind::Error, TyKind::Error) => true,
-                (_, _) => false,
-            }
-        })
+        impl_provided_for(self.impl_data.values().cloned(), auto_trait_id, impl_ty)
     }
 
     fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<ChalkIr>> {
         self.well_known_traits.get(&well_known_trait).copied()
     }
 
+    fn program_features(&self) -> ProgramFeatures {
+        self.program_features
+    }
+
+    /// The declared variances, or the ones inferred when lowering.
+    fn variance_of(&self, item: VarianceItem<ChalkIr>) -> Variances<ChalkIr> {
+        match item {
+            VarianceItem::Adt(id) => self.adt_variance(id),
+            VarianceItem::FnDef(id) => self.fn_def_variance(id),
+        }
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<ChalkIr>,
@@ -581,6 +706,25 @@ impl RustIrDatabase<ChalkIr> for Program {
         self
     }
 
+    // The default implementations of the other `*_name` methods go through
+    // the `Debug` implementations of the ids, which only know the names of
+    // the items while the program is set as the current one (see `tls`).
+    fn trait_name(&self, trait_id: TraitId<ChalkIr>) -> String {
+        self.trait_kinds[&trait_id].name.to_string()
+    }
+
+    fn adt_name(&self, adt_id: AdtId<ChalkIr>) -> String {
+        self.adt_kinds[&adt_id].name.to_string()
+    }
+
+    fn opaque_type_name(&self, opaque_ty_id: OpaqueTyId<ChalkIr>) -> String {
+        self.opaque_ty_kinds[&opaque_ty_id].name.to_string()
+    }
+
+    fn fn_def_name(&self, fn_def_id: FnDefId<ChalkIr>) -> String {
+        self.fn_def_kinds[&fn_def_id].name.to_string()
+    }
+
     // The default implementation for `RustIrDatabase::assoc_type_name` outputs
     // the name in the format `(Trait::AssocTypeName)`, which is reformatted to
     // `_Trait__AssocTypeName_`. This doesn't match the input names, which is
@@ -595,17 +739,92 @@ impl RustIrDatabase<ChalkIr> for Program {
             .to_string()
     }
 
-    // Mirrors current (07a63e6d1fabf3560e8e1e17c1d56b10a06152d9) implementation in rustc
     fn discriminant_type(&self, ty: Ty<ChalkIr>) -> Ty<ChalkIr> {
-        let interner = self.interner();
-        match ty.data(interner).kind {
-            TyKind::Adt(id, _) => self
-                .adt_repr(id)
-                .int
-                .clone()
-                .unwrap_or_else(|| TyKind::Scalar(Scalar::Int(IntTy::Isize)).intern(interner)),
-            TyKind::Generator(..) => TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(interner),
-            _ => TyKind::Scalar(Scalar::Uint(UintTy::U8)).intern(interner),
+        discriminant_type(self, ty)
+    }
+}
+
+// The following are shared with `ChalkDatabase`, which reads the items of
+// the program one by one.
+
+/// Keeps the impls among `impl_ids` whose trait parameters may match
+/// `filter`, sorted.
+pub(crate) fn retain_impls_matching(
+    db: &dyn RustIrDatabase<ChalkIr>,
+    impl_ids: &mut Vec<ImplId<ChalkIr>>,
+    filter: &ImplFilter<'_, ChalkIr>,
+) {
+    let interner = db.interner();
+    let parameters = filter.parameters();
+    impl_ids.retain(|&impl_id| {
+        let impl_datum = db.impl_datum(impl_id);
+        let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
+        let impl_parameters = trait_ref.substitution.as_slice(interner);
+        assert_eq!(impl_parameters.len(), parameters.len());
+        filter.may_match(impl_parameters)
+            && parameters.could_match(interner, db.unification_database(), impl_parameters)
+    });
+    impl_ids.sort_unstable();
+}
+
+/// Whether one of `impls` is an impl of `auto_trait_id` for the type
+/// constructor of `impl_ty` (see `RustIrDatabase::impl_provided_for`).
+pub(crate) fn impl_provided_for(
+    impls: impl IntoIterator<Item = Arc<ImplDatum<ChalkIr>>>,
+    auto_trait_id: TraitId<ChalkIr>,
+    impl_ty: &TyKind<ChalkIr>,
+) -> bool {
+    let interner = ChalkIr;
+
+    // we don't compare actual substitutions as
+    // - given a `struct S<T>`; an implementation for `S<A>` should suppress an auto impl for `S<B>`, and
+    // - an implementation for `[A]` should suppress an auto impl for `[B]`, and
+    // - an implementation for `(A, B, C)` should suppress an auto impl for `(D, E, F)`
+    // this may change later
+    impls.into_iter().any(|impl_datum| {
+        if impl_datum.trait_id() != auto_trait_id {
+            return false;
         }
+
+        let ty = impl_datum
+            .binders
+            .skip_binders()
+            .trait_ref
+            .self_type_parameter(interner);
+        match (impl_ty, ty.kind(interner)) {
+            (TyKind::Adt(id_a, _), TyKind::Adt(id_b, _)) => id_a == id_b,
+            (TyKind::AssociatedType(id_a, _), TyKind::AssociatedType(id_b, _)) => id_a == id_b,
+            (TyKind::Scalar(scalar_a), TyKind::Scalar(scalar_b)) => scalar_a == scalar_b,
+            (TyKind::Str, TyKind::Str) => true,
+            (TyKind::Tuple(arity_a, _), TyKind::Tuple(arity_b, _)) => arity_a == arity_b,
+            (TyKind::OpaqueType(id_a, _), TyKind::OpaqueType(id_b, _)) => id_a == id_b,
+            (TyKind::Slice(_), TyKind::Slice(_)) => true,
+            (TyKind::FnDef(id_a, _), TyKind::FnDef(id_b, _)) => id_a == id_b,
+            (TyKind::Ref(id_a, _, _), TyKind::Ref(id_b, _, _)) => id_a == id_b,
+            (TyKind::Raw(id_a, _), TyKind::Raw(id_b, _)) => id_a == id_b,
+            (TyKind::Never, TyKind::Never) => true,
+            (TyKind::Array(_, _), TyKind::Array(_, _)) => true,
+            (TyKind::Closure(id_a, _), TyKind::Closure(id_b, _)) => id_a == id_b,
+            (TyKind::Generator(id_a, _), TyKind::Generator(id_b, _)) => id_a == id_b,
+            (TyKind::GeneratorWitness(id_a, _), TyKind::GeneratorWitness(id_b, _)) => id_a == id_b,
+            (TyKind::Foreign(id_a), TyKind::Foreign(id_b)) => id_a == id_b,
+            (TyKind::Custom(_), TyKind::Custom(_)) => true,
+            (TyKind::Error, TyKind::Error) => true,
+            (_, _) => false,
+        }
+    })
+}
+
+// Mirrors current (07a63e6d1fabf3560e8e1e17c1d56b10a06152d9) implementation in rustc
+pub(crate) fn discriminant_type(db: &dyn RustIrDatabase<ChalkIr>, ty: Ty<ChalkIr>) -> Ty<ChalkIr> {
+    let interner = db.interner();
+    match ty.data(interner).kind {
+        TyKind::Adt(id, _) if db.adt_datum(id).kind == AdtKind::Enum => db
+            .adt_repr(id)
+            .int
+            .clone()
+            .unwrap_or_else(|| TyKind::Scalar(Scalar::Int(IntTy::Isize)).intern(interner)),
+        TyKind::Generator(..) => TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(interner),
+        _ => TyKind::Scalar(Scalar::Uint(UintTy::U8)).intern(interner),
     }
 }
@@ -3,17 +3,30 @@
 
 use crate::error::ChalkError;
 use crate::interner::ChalkIr;
-use crate::lowering::Lower;
+use crate::lowering::{lower_program_features, Lower, ProgramLowerer};
 use crate::program::Program;
 use crate::program_environment::ProgramEnvironment;
+use crate::rust_source;
 use crate::tls;
-use crate::SolverChoice;
-use chalk_ir::{Substitution, TraitId};
+use crate::{RawId, SolverChoice, SourceLanguage};
+use chalk_ir::{
+    AdtId, AssocTypeId, Binders, ClosureId, Environment, FnDefId, GeneratorId, ImplId, OpaqueTyId,
+    ProgramClause, ProgramClauses, Substitution, TraitId, Ty, Variances,
+};
+use chalk_parse::ast;
 use chalk_solve::clauses::builder::ClauseBuilder;
 use chalk_solve::clauses::program_clauses::ToProgramClauses;
 use chalk_solve::coherence::orphan;
-use chalk_solve::coherence::{CoherenceSolver, SpecializationPriorities};
-use chalk_solve::wf;
+use chalk_solve::coherence::{CoherenceError, CoherenceSolver, SpecializationPriorities};
+use chalk_solve::kind_check::KindChecker;
+use chalk_solve::rust_ir::{
+    AdtDatum, AdtRepr, AdtSizeAlign, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId,
+    ClosureKind, FnDefDatum, FnDefInputsAndOutputDatum, GeneratorDatum, GeneratorWitnessDatum,
+    ImplDatum, InherentAssocTyDatum, OpaqueTyDatum, ProgramFeatures, TraitDatum, WellKnownTrait,
+};
+use chalk_solve::simplified_type::{ImplIndex, SimplifiedType};
+use chalk_solve::variance::{infer_variances, VarianceItem};
+use chalk_solve::wf::{self, WfError};
 use chalk_solve::RustIrDatabase;
 use chalk_solve::Solver;
 use salsa::Database;
@@ -21,6 +34,7 @@ use std::clone::Clone;
 use std::cmp::{Eq, PartialEq};
 use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -34,8 +48,127 @@ pub trait LoweringDatabase:
     #[salsa::input]
     fn solver_choice(&self) -> SolverChoice;
 
+    /// The file the program text was read from, if any. Modules declared
+    /// in the program are loaded from the files next to it, whose texts
+    /// are the `module_texts`.
+    #[salsa::input]
+    fn program_path(&self) -> Option<Arc<PathBuf>>;
+
+    /// The texts of the files looked for when loading the modules of the
+    /// program, by path, or `None` for those which do not exist. Like the
+    /// program text, they are read when the program is loaded or reloaded
+    /// (see `ChalkDatabase::reload`).
+    #[salsa::input]
+    fn module_texts(&self) -> Arc<BTreeMap<PathBuf, Option<String>>>;
+
+    /// The language the program text is written in.
+    #[salsa::input]
+    fn program_language(&self) -> SourceLanguage;
+
+    /// The program text, parsed.
+    fn parsed_program(&self) -> Result<Arc<ast::Program>, ChalkError>;
+
+    /// The parsed item with the id `id`, which the item is lowered from.
+    fn parsed_item(&self, id: RawId) -> Arc<ast::Item>;
+
+    /// The ids, names and kinds of the items of the program, which the
+    /// items are lowered with. They don't change with the bodies of the
+    /// items.
+    fn program_lowerer(&self) -> Result<Arc<ProgramLowerer>, ChalkError>;
+
     fn program_ir(&self) -> Result<Arc<Program>, ChalkError>;
 
+    // The items of the program, as the solvers read them through
+    // `RustIrDatabase`. Each is lowered from its own parsed item (and
+    // `program_lowerer`), so it is only recomputed when that item changes,
+    // and only the queries which read an item whose lowering actually
+    // changed are recomputed after it. The items are lowered without their
+    // spans, which the solvers don't look at, so that moving an item
+    // doesn't change the ones after it. Ids are handed out in declaration
+    // order though, so adding or removing an item changes all the later
+    // ones.
+
+    fn lowered_adt(&self, id: AdtId<ChalkIr>) -> Arc<AdtDatum<ChalkIr>>;
+
+    fn lowered_adt_repr(&self, id: AdtId<ChalkIr>) -> Arc<AdtRepr<ChalkIr>>;
+
+    fn lowered_adt_size_align(&self, id: AdtId<ChalkIr>) -> Arc<AdtSizeAlign>;
+
+    fn lowered_adt_variances(&self, id: AdtId<ChalkIr>) -> Variances<ChalkIr>;
+
+    fn lowered_trait(&self, id: TraitId<ChalkIr>) -> Arc<TraitDatum<ChalkIr>>;
+
+    fn lowered_impl(&self, id: ImplId<ChalkIr>) -> Arc<ImplDatum<ChalkIr>>;
+
+    fn lowered_assoc_ty(&self, id: AssocTypeId<ChalkIr>) -> Arc<AssociatedTyDatum<ChalkIr>>;
+
+    fn lowered_assoc_ty_value(
+        &self,
+        id: AssociatedTyValueId<ChalkIr>,
+    ) -> Arc<AssociatedTyValue<ChalkIr>>;
+
+    fn lowered_inherent_assoc_ty(
+        &self,
+        id: AssocTypeId<ChalkIr>,
+    ) -> Option<Arc<InherentAssocTyDatum<ChalkIr>>>;
+
+    fn lowered_opaque_ty(&self, id: OpaqueTyId<ChalkIr>) -> Arc<OpaqueTyDatum<ChalkIr>>;
+
+    fn lowered_hidden_opaque_type(&self, id: OpaqueTyId<ChalkIr>) -> Ty<ChalkIr>;
+
+    fn lowered_fn_def(&self, id: FnDefId<ChalkIr>) -> Arc<FnDefDatum<ChalkIr>>;
+
+    fn lowered_fn_def_variances(&self, id: FnDefId<ChalkIr>) -> Variances<ChalkIr>;
+
+    fn lowered_closure_inputs_and_output(
+        &self,
+        id: ClosureId<ChalkIr>,
+    ) -> Binders<FnDefInputsAndOutputDatum<ChalkIr>>;
+
+    fn lowered_closure_kind(&self, id: ClosureId<ChalkIr>) -> ClosureKind;
+
+    fn lowered_closure_upvars(&self, id: ClosureId<ChalkIr>) -> Binders<Ty<ChalkIr>>;
+
+    fn lowered_generator(&self, id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>>;
+
+    fn lowered_generator_witness(
+        &self,
+        id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>>;
+
+    fn lowered_custom_clauses(&self) -> Arc<Vec<ProgramClause<ChalkIr>>>;
+
+    /// The impls of a trait which may apply to a self type that simplifies
+    /// to `self_ty`, or all of them if `self_ty` is `None`, sorted (see
+    /// `ImplIndex::impls_for_self_type`).
+    fn impls_for_self_type(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+        self_ty: Option<SimplifiedType<ChalkIr>>,
+    ) -> Arc<Vec<ImplId<ChalkIr>>>;
+
+    fn well_known_trait(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<ChalkIr>>;
+
+    fn trait_is_object_safe(&self, id: TraitId<ChalkIr>) -> bool;
+
+    fn program_features(&self) -> ProgramFeatures;
+
+    // The checks of each item. Their errors are pointed at the declaration
+    // of the item by `coherence`, `orphan_check` and `checked_program`.
+
+    fn trait_coherence(
+        &self,
+        id: TraitId<ChalkIr>,
+    ) -> Result<Arc<SpecializationPriorities<ChalkIr>>, CoherenceError<ChalkIr>>;
+
+    fn impl_orphan_check(&self, id: ImplId<ChalkIr>) -> Result<(), CoherenceError<ChalkIr>>;
+
+    fn adt_wf(&self, id: AdtId<ChalkIr>) -> Result<(), WfError<ChalkIr>>;
+
+    fn opaque_ty_wf(&self, id: OpaqueTyId<ChalkIr>) -> Result<(), WfError<ChalkIr>>;
+
+    fn impl_wf(&self, id: ImplId<ChalkIr>) -> Result<(), WfError<ChalkIr>>;
+
     /// Performs coherence check and computes which impls specialize
     /// one another (the "specialization priorities").
     fn coherence(
@@ -44,12 +177,23 @@ pub trait LoweringDatabase:
 
     fn orphan_check(&self) -> Result<(), ChalkError>;
 
-    /// The lowered IR, with coherence, orphan, and WF checks performed.
+    /// Checks that the items of the program are applied to generic
+    /// arguments of the right number and kinds.
+    fn kind_check(&self) -> Result<(), ChalkError>;
+
+    /// The lowered IR, with kind, coherence, orphan, and WF checks performed.
     fn checked_program(&self) -> Result<Arc<Program>, ChalkError>;
 
     /// The program as logic.
     fn environment(&self) -> Result<Arc<ProgramEnvironment>, ChalkError>;
 
+    /// The program clauses elaborated from the given environment (see
+    /// `chalk_solve::program_clauses_for_env`). Solving a goal asks for
+    /// the clauses of the same environment over and over, so they are
+    /// computed once per environment and recomputed only when the program
+    /// changes.
+    fn env_clauses(&self, environment: Environment<ChalkIr>) -> ProgramClauses<ChalkIr>;
+
     /// Creates the solver we can use to solve goals. This solver
     /// stores intermediate, cached state, which is why it is behind a
     /// mutex. Moreover, if the set of program clauses change, that
@@ -122,67 +266,417 @@ impl<T> Clone for ArcEq<T> {
     }
 }
 
-fn program_ir(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError> {
+fn parsed_program(db: &dyn LoweringDatabase) -> Result<Arc<ast::Program>, ChalkError> {
     let text = db.program_text();
-    Ok(Arc::new(chalk_parse::parse_program(&text)?.lower()?))
+    let path = db.program_path();
+    let program = match (db.program_language(), path) {
+        (SourceLanguage::Rust, path) => {
+            rust_source::parse_rust_program(&text, path.as_deref().map(|p| p.as_path()))?
+        }
+        (SourceLanguage::Chalk, Some(path)) => {
+            let module_texts = db.module_texts();
+            chalk_parse::parse_program_with(&text, &path, &mut |file| {
+                module_texts.get(file).cloned().ok_or_else(|| {
+                    format!("`{}` was not read with the program", file.display()).into()
+                })
+            })?
+        }
+        (SourceLanguage::Chalk, None) => chalk_parse::parse_program(&text)?,
+    };
+    Ok(Arc::new(program))
+}
+
+fn parsed_item(db: &dyn LoweringDatabase, id: RawId) -> Arc<ast::Item> {
+    Arc::new(db.parsed_program().unwrap().items[id.index as usize].clone())
+}
+
+fn program_lowerer(db: &dyn LoweringDatabase) -> Result<Arc<ProgramLowerer>, ChalkError> {
+    let program = db.parsed_program()?;
+    let (lowerer, _) = ProgramLowerer::new(&program)?;
+    Ok(Arc::new(lowerer))
+}
+
+fn program_ir(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError> {
+    Ok(Arc::new(db.parsed_program()?.lower()?))
+}
+
+/// The item with the id `id` lowered on its own (see
+/// `ProgramLowerer::lower_item`). Like the other queries reading the lowered
+/// items, this may only be called once `program_ir` lowered the program
+/// without errors.
+fn lower_item(db: &dyn LoweringDatabase, id: RawId) -> Program {
+    db.program_lowerer()
+        .unwrap()
+        .lower_item(&db.parsed_item(id), id)
+        .unwrap()
+}
+
+fn lowered_adt(db: &dyn LoweringDatabase, id: AdtId<ChalkIr>) -> Arc<AdtDatum<ChalkIr>> {
+    let datum = &lower_item(db, id.0).adt_data[&id];
+    Arc::new(AdtDatum {
+        span: None,
+        ..(**datum).clone()
+    })
+}
+
+fn lowered_adt_repr(db: &dyn LoweringDatabase, id: AdtId<ChalkIr>) -> Arc<AdtRepr<ChalkIr>> {
+    lower_item(db, id.0).adt_repr(id)
+}
+
+fn lowered_adt_size_align(db: &dyn LoweringDatabase, id: AdtId<ChalkIr>) -> Arc<AdtSizeAlign> {
+    lower_item(db, id.0).adt_size_align(id)
+}
+
+/// The variances `item` declares, if it does.
+fn declared_variances(
+    db: &dyn LoweringDatabase,
+    item: VarianceItem<ChalkIr>,
+) -> Option<Variances<ChalkIr>> {
+    let variances = match item {
+        VarianceItem::Adt(id) => lower_item(db, id.0).adt_variances.remove(&id),
+        VarianceItem::FnDef(id) => lower_item(db, id.0).fn_def_variances.remove(&id),
+    };
+    variances.map(|variances| Variances::from_iter(ChalkIr, variances))
+}
+
+fn lowered_adt_variances(db: &dyn LoweringDatabase, id: AdtId<ChalkIr>) -> Variances<ChalkIr> {
+    infer_variances(db.upcast(), VarianceItem::Adt(id), &|item| {
+        declared_variances(db, item)
+    })
+}
+
+fn lowered_trait(db: &dyn LoweringDatabase, id: TraitId<ChalkIr>) -> Arc<TraitDatum<ChalkIr>> {
+    let datum = &lower_item(db, id.0).trait_data[&id];
+    Arc::new(TraitDatum {
+        span: None,
+        ..(**datum).clone()
+    })
+}
+
+fn lowered_impl(db: &dyn LoweringDatabase, id: ImplId<ChalkIr>) -> Arc<ImplDatum<ChalkIr>> {
+    let datum = &lower_item(db, id.0).impl_data[&id];
+    Arc::new(ImplDatum {
+        span: None,
+        ..(**datum).clone()
+    })
+}
+
+fn lowered_assoc_ty(
+    db: &dyn LoweringDatabase,
+    id: AssocTypeId<ChalkIr>,
+) -> Arc<AssociatedTyDatum<ChalkIr>> {
+    let lowerer = db.program_lowerer().unwrap();
+    let (trait_id, _) = lowerer
+        .associated_ty_lookups
+        .iter()
+        .find(|(_, lookup)| lookup.id == id)
+        .map(|(key, _)| key)
+        .unwrap();
+    let datum = &lower_item(db, trait_id.0).associated_ty_data[&id];
+    Arc::new(AssociatedTyDatum {
+        span: None,
+        ..(**datum).clone()
+    })
+}
+
+fn lowered_assoc_ty_value(
+    db: &dyn LoweringDatabase,
+    id: AssociatedTyValueId<ChalkIr>,
+) -> Arc<AssociatedTyValue<ChalkIr>> {
+    let lowerer = db.program_lowerer().unwrap();
+    let (impl_id, _) = lowerer
+        .associated_ty_value_ids
+        .iter()
+        .find(|(_, &value_id)| value_id == id)
+        .map(|(key, _)| key)
+        .unwrap();
+    let value = &lower_item(db, impl_id.0).associated_ty_values[&id];
+    Arc::new(AssociatedTyValue {
+        span: None,
+        ..(**value).clone()
+    })
+}
+
+fn lowered_inherent_assoc_ty(
+    db: &dyn LoweringDatabase,
+    id: AssocTypeId<ChalkIr>,
+) -> Option<Arc<InherentAssocTyDatum<ChalkIr>>> {
+    let lowerer = db.program_lowerer().unwrap();
+    let mut values = Vec::new();
+    let mut datum = None;
+    for &impl_id in lowerer.inherent_assoc_ty_impls.get(&id)? {
+        let impl_datum = lower_item(db, impl_id).inherent_assoc_ty_data[&id].clone();
+        values.extend(impl_datum.values.iter().cloned());
+        datum.get_or_insert(impl_datum);
+    }
+    Some(Arc::new(InherentAssocTyDatum {
+        span: None,
+        values,
+        ..(*datum?).clone()
+    }))
+}
+
+fn lowered_opaque_ty(
+    db: &dyn LoweringDatabase,
+    id: OpaqueTyId<ChalkIr>,
+) -> Arc<OpaqueTyDatum<ChalkIr>> {
+    let datum = &lower_item(db, id.0).opaque_ty_data[&id];
+    Arc::new(OpaqueTyDatum {
+        span: None,
+        ..(**datum).clone()
+    })
+}
+
+fn lowered_hidden_opaque_type(db: &dyn LoweringDatabase, id: OpaqueTyId<ChalkIr>) -> Ty<ChalkIr> {
+    lower_item(db, id.0).hidden_opaque_type(id)
+}
+
+fn lowered_fn_def(db: &dyn LoweringDatabase, id: FnDefId<ChalkIr>) -> Arc<FnDefDatum<ChalkIr>> {
+    let datum = &lower_item(db, id.0).fn_def_data[&id];
+    Arc::new(FnDefDatum {
+        span: None,
+        ..(**datum).clone()
+    })
+}
+
+fn lowered_fn_def_variances(db: &dyn LoweringDatabase, id: FnDefId<ChalkIr>) -> Variances<ChalkIr> {
+    infer_variances(db.upcast(), VarianceItem::FnDef(id), &|item| {
+        declared_variances(db, item)
+    })
+}
+
+fn lowered_closure_inputs_and_output(
+    db: &dyn LoweringDatabase,
+    id: ClosureId<ChalkIr>,
+) -> Binders<FnDefInputsAndOutputDatum<ChalkIr>> {
+    lower_item(db, id.0).closure_inputs_and_output[&id].clone()
+}
+
+fn lowered_closure_kind(db: &dyn LoweringDatabase, id: ClosureId<ChalkIr>) -> ClosureKind {
+    lower_item(db, id.0).closure_closure_kind[&id]
+}
+
+fn lowered_closure_upvars(
+    db: &dyn LoweringDatabase,
+    id: ClosureId<ChalkIr>,
+) -> Binders<Ty<ChalkIr>> {
+    lower_item(db, id.0).closure_upvars[&id].clone()
+}
+
+fn lowered_generator(
+    db: &dyn LoweringDatabase,
+    id: GeneratorId<ChalkIr>,
+) -> Arc<GeneratorDatum<ChalkIr>> {
+    lower_item(db, id.0).generator_datum(id)
+}
+
+fn lowered_generator_witness(
+    db: &dyn LoweringDatabase,
+    id: GeneratorId<ChalkIr>,
+) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+    lower_item(db, id.0).generator_witness_datum(id)
+}
+
+fn lowered_custom_clauses(db: &dyn LoweringDatabase) -> Arc<Vec<ProgramClause<ChalkIr>>> {
+    let lowerer = db.program_lowerer().unwrap();
+    Arc::new(
+        lowerer
+            .clause_ids
+            .iter()
+            .flat_map(|&id| lower_item(db, id).custom_clauses)
+            .collect(),
+    )
+}
+
+fn impls_for_self_type(
+    db: &dyn LoweringDatabase,
+    trait_id: TraitId<ChalkIr>,
+    self_ty: Option<SimplifiedType<ChalkIr>>,
+) -> Arc<Vec<ImplId<ChalkIr>>> {
+    let mut impl_index = ImplIndex::default();
+    for &impl_id in &db.program_lowerer().unwrap().impl_ids {
+        let impl_datum = db.lowered_impl(impl_id);
+        if impl_datum.trait_id() == trait_id {
+            let self_ty = impl_datum
+                .binders
+                .skip_binders()
+                .trait_ref
+                .self_type_parameter(ChalkIr);
+            impl_index.insert(
+                trait_id,
+                SimplifiedType::from_ty(ChalkIr, &self_ty),
+                impl_id,
+            );
+        }
+    }
+    let mut impl_ids = impl_index.impls_for_self_type(trait_id, self_ty.as_ref());
+    impl_ids.sort_unstable();
+    Arc::new(impl_ids)
+}
+
+fn well_known_trait(
+    db: &dyn LoweringDatabase,
+    well_known_trait: WellKnownTrait,
+) -> Option<TraitId<ChalkIr>> {
+    db.program_lowerer()
+        .unwrap()
+        .well_known_traits
+        .get(&well_known_trait)
+        .copied()
+}
+
+fn trait_is_object_safe(db: &dyn LoweringDatabase, id: TraitId<ChalkIr>) -> bool {
+    db.program_lowerer()
+        .unwrap()
+        .object_safe_traits
+        .contains(&id)
+}
+
+fn program_features(db: &dyn LoweringDatabase) -> ProgramFeatures {
+    lower_program_features(&db.parsed_program().unwrap()).unwrap()
+}
+
+fn trait_coherence(
+    db: &dyn LoweringDatabase,
+    id: TraitId<ChalkIr>,
+) -> Result<Arc<SpecializationPriorities<ChalkIr>>, CoherenceError<ChalkIr>> {
+    let solver_choice = db.solver_choice();
+    let solver_builder = || solver_choice.into_solver();
+    CoherenceSolver::new(db.upcast(), &solver_builder, id).specialization_priorities()
+}
+
+fn impl_orphan_check(
+    db: &dyn LoweringDatabase,
+    id: ImplId<ChalkIr>,
+) -> Result<(), CoherenceError<ChalkIr>> {
+    let mut solver = db.solver_choice().into_solver();
+    orphan::perform_orphan_check::<ChalkIr>(db.upcast(), &mut *solver, id)
+}
+
+fn adt_wf(db: &dyn LoweringDatabase, id: AdtId<ChalkIr>) -> Result<(), WfError<ChalkIr>> {
+    let solver_choice = db.solver_choice();
+    let solver_builder = || solver_choice.into_solver();
+    wf::WfSolver::new(db.upcast(), &solver_builder).verify_adt_decl(id)
+}
+
+fn opaque_ty_wf(
+    db: &dyn LoweringDatabase,
+    id: OpaqueTyId<ChalkIr>,
+) -> Result<(), WfError<ChalkIr>> {
+    let solver_choice = db.solver_choice();
+    let solver_builder = || solver_choice.into_solver();
+    wf::WfSolver::new(db.upcast(), &solver_builder).verify_opaque_ty_decl(id)
 }
 
+fn impl_wf(db: &dyn LoweringDatabase, id: ImplId<ChalkIr>) -> Result<(), WfError<ChalkIr>> {
+    let solver_choice = db.solver_choice();
+    let solver_builder = || solver_choice.into_solver();
+    wf::WfSolver::new(db.upcast(), &solver_builder).verify_trait_impl(id)
+}
+
+// The checks of the items run with the program set as the current one, so
+// that their errors, written out here, name the items of the program.
+
 fn orphan_check(db: &dyn LoweringDatabase) -> Result<(), ChalkError> {
     let program = db.program_ir()?;
 
     tls::set_current_program(&program, || -> Result<(), ChalkError> {
-        let local_impls = program.local_impl_ids();
-        for impl_id in local_impls {
-            let mut solver = db.solver_choice().into_solver();
-            orphan::perform_orphan_check::<ChalkIr>(db.upcast(), &mut *solver, impl_id)?;
+        for impl_id in program.local_impl_ids() {
+            db.impl_orphan_check(impl_id).map_err(|e| {
+                ChalkError::from(e).at_span(&program, program.impl_data[&impl_id].span)
+            })?;
         }
         Ok(())
     })
 }
 
+fn kind_check(db: &dyn LoweringDatabase) -> Result<(), ChalkError> {
+    let program = db.program_ir()?;
+    let checker = KindChecker::new(&*program);
+
+    tls::set_current_program(&program, || -> Result<(), ChalkError> {
+        for (&id, adt_datum) in &program.adt_data {
+            checker
+                .check_adt_decl(id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, adt_datum.span))?;
+        }
+
+        for (&id, fn_def_datum) in &program.fn_def_data {
+            checker
+                .check_fn_def_decl(id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, fn_def_datum.span))?;
+        }
+
+        for (&id, trait_datum) in &program.trait_data {
+            checker
+                .check_trait_decl(id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, trait_datum.span))?;
+        }
+
+        for (&id, impl_datum) in &program.impl_data {
+            checker
+                .check_impl(id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, impl_datum.span))?;
+        }
+
+        for (&id, opaque_ty_datum) in &program.opaque_ty_data {
+            checker
+                .check_opaque_ty_decl(id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, opaque_ty_datum.span))?;
+        }
+
+        Ok(())
+    })
+}
+
 fn coherence(
     db: &dyn LoweringDatabase,
 ) -> Result<BTreeMap<TraitId<ChalkIr>, Arc<SpecializationPriorities<ChalkIr>>>, ChalkError> {
     let program = db.program_ir()?;
-    let solver_choice = db.solver_choice();
-    let priorities_map = tls::set_current_program(&program, || -> Result<_, ChalkError> {
-        let solver_builder = || solver_choice.into_solver();
-        let priorities_map: Result<BTreeMap<_, _>, ChalkError> = program
+    let priorities_map = tls::set_current_program(&program, || {
+        program
             .trait_data
-            .keys()
-            .map(|&trait_id| {
-                let solver: CoherenceSolver<ChalkIr> =
-                    CoherenceSolver::new(db.upcast(), &solver_builder, trait_id);
-                let priorities = solver.specialization_priorities()?;
+            .iter()
+            .map(|(&trait_id, trait_datum)| {
+                let priorities = db
+                    .trait_coherence(trait_id)
+                    .map_err(|e| ChalkError::from(e).at_span(&program, trait_datum.span))?;
                 Ok((trait_id, priorities))
             })
-            .collect();
-        let priorities_map = priorities_map?;
-        Ok(priorities_map)
+            .collect()
     });
     let () = db.orphan_check()?;
     priorities_map
 }
 
+fn env_clauses(
+    db: &dyn LoweringDatabase,
+    environment: Environment<ChalkIr>,
+) -> ProgramClauses<ChalkIr> {
+    chalk_solve::program_clauses_for_env(db.upcast(), &environment)
+}
+
 fn checked_program(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError> {
     let program = db.program_ir()?;
 
+    // The other checks run the solvers, which assume well-kinded items.
+    db.kind_check()?;
     db.coherence()?;
 
-    let solver_choice = db.solver_choice();
     let () = tls::set_current_program(&program, || -> Result<(), ChalkError> {
-        let solver_builder = || solver_choice.into_solver();
-        let solver: wf::WfSolver<ChalkIr> = wf::WfSolver::new(db.upcast(), &solver_builder);
-        for &id in program.adt_data.keys() {
-            solver.verify_adt_decl(id)?;
+        for (&id, adt_datum) in &program.adt_data {
+            db.adt_wf(id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, adt_datum.span))?;
         }
 
-        for &opaque_ty_id in program.opaque_ty_data.keys() {
-            solver.verify_opaque_ty_decl(opaque_ty_id)?;
+        for (&opaque_ty_id, opaque_ty_datum) in &program.opaque_ty_data {
+            db.opaque_ty_wf(opaque_ty_id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, opaque_ty_datum.span))?;
         }
 
-        for &impl_id in program.impl_data.keys() {
-            solver.verify_trait_impl(impl_id)?;
+        for (&impl_id, impl_datum) in &program.impl_data {
+            db.impl_wf(impl_id)
+                .map_err(|e| ChalkError::from(e).at_span(&program, impl_datum.span))?;
         }
 
         Ok(())
@@ -0,0 +1,1436 @@
+//! A frontend that reads a subset of Rust source code, so that chalk can be
+//! tried on the skeleton of a real crate without translating it to the
+//! `.chalk` syntax by hand.
+//!
+//! The structs, enums, unions, traits, trait impls and foreign types of the
+//! source are turned into the same AST the `.chalk` parser produces, and
+//! lowered the same way. Type aliases are expanded where they are used, and
+//! everything else (functions, inherent impls, `use` declarations, ...) is
+//! ignored. As in `.chalk` programs, paths are resolved by their last
+//! segment, there are no implicit `Sized` bounds, and the well-known traits
+//! are those marked with `#[lang = "..."]`.
+
+use chalk_parse::ast::*;
+use chalk_parse::Location;
+use proc_macro2::{LineColumn, Span as SynSpan};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use string_cache::DefaultAtom as Atom;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+
+/// An error in the Rust source of a program: a syntax error, or something
+/// this frontend does not support.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RustSourceError {
+    pub message: String,
+    /// The file the source was read from, if any.
+    pub path: Option<PathBuf>,
+    pub location: Location,
+}
+
+impl fmt::Display for RustSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error: {}\n{}",
+            self.message,
+            self.location.display(self.path.as_deref())
+        )
+    }
+}
+
+impl std::error::Error for RustSourceError {}
+
+/// Parses the Rust source `text`, read from `path` if it was read from a
+/// file, into a program. The error, if any, is a `RustSourceError`.
+pub fn parse_rust_program(
+    text: &str,
+    path: Option<&Path>,
+) -> std::result::Result<Program, Box<dyn std::error::Error>> {
+    let items = convert(text, path, |converter, items| {
+        let mut out = Vec::new();
+        converter.items(items, &mut out)?;
+        Ok(out)
+    })?;
+    Ok(Program {
+        items,
+        sources: vec![Source {
+            path: path.map(Path::to_path_buf),
+            text: text.to_string(),
+        }],
+    })
+}
+
+/// A trait obligation that a function body must satisfy to compile: the
+/// bounds of a generic function it calls with explicit generic arguments
+/// (`is_send::<Foo>()`), or the trait of a qualified path it calls
+/// (`<Foo as Clone>::clone(&foo)`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Obligation {
+    /// The goal that must hold, under the generics of the calling function.
+    pub goal: Box<Goal>,
+    /// The text of the called path.
+    pub callee: String,
+    pub location: Location,
+}
+
+/// Collects the obligations of the bodies of the free functions in the
+/// Rust source `text`, read from `path` if it was read from a file, to be
+/// solved in the program `parse_rust_program` makes of it. Calls whose
+/// generic arguments are inferred, and method calls, are not looked at.
+///
+/// The error, if any, is a `RustSourceError`, also for obligations which
+/// cannot be expressed as goals.
+pub fn parse_rust_obligations(
+    text: &str,
+    path: Option<&Path>,
+) -> std::result::Result<Vec<Obligation>, Box<dyn std::error::Error>> {
+    convert(text, path, |converter, items| {
+        let mut fns = Vec::new();
+        collect_fns(items, &mut fns);
+        // Functions defined in bodies are called like the others.
+        let mut bodies = Vec::new();
+        let mut next = 0;
+        while next < fns.len() {
+            let mut collector = BodyCollector::default();
+            collector.visit_block_mut(&mut fns[next].block.clone());
+            fns.extend(collector.fns);
+            bodies.push(collector.calls);
+            next += 1;
+        }
+
+        let by_name: HashMap<_, _> = fns.iter().map(|f| (f.sig.ident.to_string(), f)).collect();
+        let mut obligations = Vec::new();
+        for (caller, calls) in fns.iter().zip(&bodies) {
+            for call in calls {
+                obligations.extend(converter.call_obligation(caller, call, &by_name)?);
+            }
+        }
+        Ok(obligations)
+    })
+}
+
+/// Parses `text`, read from `path` if it was read from a file, and
+/// converts its items with `convert`, reporting syntax errors and the
+/// errors of `convert` as `RustSourceError`s.
+fn convert<T>(
+    text: &str,
+    path: Option<&Path>,
+    convert: impl for<'f> FnOnce(&Converter<'f>, &'f [syn::Item]) -> Result<T>,
+) -> std::result::Result<T, Box<dyn std::error::Error>> {
+    let line_starts = line_starts(text);
+    let error = |error: syn::Error| {
+        Box::new(RustSourceError {
+            message: error.to_string(),
+            path: path.map(Path::to_path_buf),
+            location: Location::new(text, to_span(text, &line_starts, error.span())),
+        })
+    };
+
+    let file = syn::parse_file(text).map_err(error)?;
+    let mut converter = Converter {
+        text,
+        line_starts: line_starts.clone(),
+        aliases: HashMap::new(),
+        expanding: RefCell::new(Vec::new()),
+    };
+    converter.collect_aliases(&file.items);
+    Ok(convert(&converter, &file.items).map_err(error)?)
+}
+
+/// The free functions among `items`, including those of inline modules.
+fn collect_fns(items: &[syn::Item], out: &mut Vec<syn::ItemFn>) {
+    for item in items {
+        match item {
+            syn::Item::Fn(item) => out.push(item.clone()),
+            syn::Item::Mod(syn::ItemMod {
+                content: Some((_, items)),
+                ..
+            }) => collect_fns(items, out),
+            _ => {}
+        }
+    }
+}
+
+/// The offsets at which the lines of `text` start.
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Converts `span` to a span of source 0, `text`, whose lines start at
+/// `line_starts`.
+fn to_span(text: &str, line_starts: &[usize], span: SynSpan) -> Span {
+    Span::new(
+        0,
+        offset(text, line_starts, span.start()),
+        offset(text, line_starts, span.end()),
+    )
+}
+
+/// The byte offset of `position` in `text`, whose lines start at
+/// `line_starts`. `syn` counts lines from 1 and columns, in characters,
+/// from 0.
+fn offset(text: &str, line_starts: &[usize], position: LineColumn) -> usize {
+    let line_start = match line_starts.get(position.line.saturating_sub(1)) {
+        Some(&line_start) => line_start,
+        None => return text.len(),
+    };
+    line_start
+        + text[line_start..]
+            .chars()
+            .take(position.column)
+            .map(char::len_utf8)
+            .sum::<usize>()
+}
+
+type Result<T> = syn::Result<T>;
+
+struct Converter<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    /// The type aliases of the source, by name.
+    aliases: HashMap<String, &'a syn::ItemType>,
+    /// The type aliases being expanded, innermost last.
+    expanding: RefCell<Vec<String>>,
+}
+
+/// What `Self` stands for where a type is written.
+#[derive(Copy, Clone, Default)]
+struct Scope<'s> {
+    /// In an impl, its self type. In a trait, `Self` is a parameter.
+    self_ty: Option<&'s syn::Type>,
+    /// In a trait or trait impl, the trait reference `Self::Name` is an
+    /// associated type of.
+    self_trait: Option<&'s TraitRef>,
+}
+
+impl<'a> Converter<'a> {
+    fn collect_aliases(&mut self, items: &'a [syn::Item]) {
+        for item in items {
+            match item {
+                syn::Item::Type(alias) => {
+                    self.aliases.insert(alias.ident.to_string(), alias);
+                }
+                syn::Item::Mod(syn::ItemMod {
+                    content: Some((_, items)),
+                    ..
+                }) => self.collect_aliases(items),
+                _ => {}
+            }
+        }
+    }
+
+    fn items(&self, items: &[syn::Item], out: &mut Vec<Item>) -> Result<()> {
+        for item in items {
+            match item {
+                syn::Item::Struct(item) => {
+                    let variant = Variant {
+                        // Like in `.chalk` programs, the single variant of
+                        // a struct is named after its index.
+                        name: Identifier {
+                            str: Atom::from("0"),
+                            span: self.span(item.ident.span()),
+                        },
+                        fields: self.fields(&item.fields)?,
+                        discriminant: None,
+                    };
+                    out.push(Item::AdtDefn(self.adt(
+                        item,
+                        &item.attrs,
+                        &item.ident,
+                        &item.generics,
+                        vec![variant],
+                        AdtKind::Struct,
+                    )?));
+                }
+                syn::Item::Enum(item) => {
+                    let variants = item
+                        .variants
+                        .iter()
+                        .map(|variant| {
+                            Ok(Variant {
+                                name: self.ident(&variant.ident),
+                                fields: self.fields(&variant.fields)?,
+                                discriminant: match &variant.discriminant {
+                                    Some((_, expr)) => Some(self.discriminant(expr)?),
+                                    None => None,
+                                },
+                            })
+                        })
+                        .collect::<Result<_>>()?;
+                    out.push(Item::AdtDefn(self.adt(
+                        item,
+                        &item.attrs,
+                        &item.ident,
+                        &item.generics,
+                        variants,
+                        AdtKind::Enum,
+                    )?));
+                }
+                syn::Item::Union(item) => {
+                    let variant = Variant {
+                        name: Identifier {
+                            str: Atom::from("0"),
+                            span: self.span(item.ident.span()),
+                        },
+                        fields: self.fields(&syn::Fields::Named(item.fields.clone()))?,
+                        discriminant: None,
+                    };
+                    out.push(Item::AdtDefn(self.adt(
+                        item,
+                        &item.attrs,
+                        &item.ident,
+                        &item.generics,
+                        vec![variant],
+                        AdtKind::Union,
+                    )?));
+                }
+                syn::Item::Trait(item) => out.push(Item::TraitDefn(self.trait_defn(item)?)),
+                // Inherent impls do not implement anything.
+                syn::Item::Impl(item) if item.trait_.is_some() => {
+                    out.push(Item::Impl(self.impl_defn(item)?))
+                }
+                syn::Item::ForeignMod(item) => {
+                    for foreign_item in &item.items {
+                        if let syn::ForeignItem::Type(ty) = foreign_item {
+                            out.push(Item::Foreign(ForeignDefn(self.ident(&ty.ident))));
+                        }
+                    }
+                }
+                syn::Item::Mod(item) => match &item.content {
+                    Some((_, items)) => self.items(items, out)?,
+                    None => {
+                        return Err(syn::Error::new(
+                            item.ident.span(),
+                            "modules must be declared inline, with `mod name { ... }`",
+                        ))
+                    }
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The obligation of calling `call` in the body of `caller`, if any.
+    /// `fns` are the functions of the source, by name.
+    fn call_obligation(
+        &self,
+        caller: &syn::ItemFn,
+        call: &syn::ExprPath,
+        fns: &HashMap<String, &syn::ItemFn>,
+    ) -> Result<Option<Obligation>> {
+        let scope = Scope::default();
+        let where_clauses = match &call.qself {
+            // `<Ty as Trait>::function`
+            Some(qself) if qself.position > 0 => {
+                let bound = syn::TypeParamBound::Trait(syn::TraitBound {
+                    paren_token: None,
+                    modifier: syn::TraitBoundModifier::None,
+                    lifetimes: None,
+                    path: syn::Path {
+                        leading_colon: call.path.leading_colon,
+                        segments: call
+                            .path
+                            .segments
+                            .iter()
+                            .take(qself.position)
+                            .cloned()
+                            .collect(),
+                    },
+                });
+                let ty = self.ty(&qself.ty, scope)?;
+                self.bounds_on(&ty, std::iter::once(&bound), &[], scope)?
+            }
+            Some(_) => return Ok(None),
+            None => {
+                let segment = last_segment(&call.path)?;
+                match (fns.get(&segment.ident.to_string()), &segment.arguments) {
+                    (Some(callee), syn::PathArguments::AngleBracketed(args)) => {
+                        self.callee_bounds(callee, args)?
+                    }
+                    _ => return Ok(None),
+                }
+            }
+        };
+        if where_clauses.is_empty() {
+            return Ok(None);
+        }
+
+        let mut goals = where_clauses.into_iter().map(|where_clause| {
+            let goal = Box::new(Goal::Leaf(LeafGoal::DomainGoal {
+                goal: DomainGoal::Holds {
+                    where_clause: where_clause.where_clause,
+                },
+            }));
+            if where_clause.variable_kinds.is_empty() {
+                goal
+            } else {
+                Box::new(Goal::ForAll(where_clause.variable_kinds, goal))
+            }
+        });
+        let first = goals.next().unwrap();
+        let rest: Vec<_> = goals.collect();
+        let mut goal = if rest.is_empty() {
+            first
+        } else {
+            Box::new(Goal::And(first, rest))
+        };
+
+        // The bounds of the caller are assumed in its body.
+        let (variable_kinds, hypotheses) = self.generics(&caller.sig.generics, scope)?;
+        if !hypotheses.is_empty() {
+            let clauses = hypotheses
+                .into_iter()
+                .map(|hypothesis| Clause {
+                    variable_kinds: hypothesis.variable_kinds,
+                    consequence: DomainGoal::Holds {
+                        where_clause: hypothesis.where_clause,
+                    },
+                    conditions: vec![],
+                })
+                .collect();
+            goal = Box::new(Goal::Implies(clauses, goal));
+        }
+        if !variable_kinds.is_empty() {
+            goal = Box::new(Goal::ForAll(variable_kinds, goal));
+        }
+
+        let span = self.span(call.span());
+        Ok(Some(Obligation {
+            goal,
+            callee: self.text[span.lo..span.hi].to_string(),
+            location: Location::new(self.text, span),
+        }))
+    }
+
+    /// The where clauses of `callee`, with its generic parameters replaced
+    /// by `args`. The lifetime arguments may be left out, as long as the
+    /// bounds do not mention them.
+    fn callee_bounds(
+        &self,
+        callee: &syn::ItemFn,
+        args: &syn::AngleBracketedGenericArguments,
+    ) -> Result<Vec<QuantifiedWhereClause>> {
+        let with_lifetimes = args
+            .args
+            .iter()
+            .any(|arg| matches!(arg, syn::GenericArgument::Lifetime(_)));
+        let params: Vec<_> = callee
+            .sig
+            .generics
+            .params
+            .iter()
+            .filter(|param| with_lifetimes || !matches!(param, syn::GenericParam::Lifetime(_)))
+            .collect();
+        let name = &callee.sig.ident;
+        if params.len() != args.args.len() {
+            return Err(syn::Error::new_spanned(
+                args,
+                format!(
+                    "function `{}` takes {} generic arguments, not {}",
+                    name,
+                    params.len(),
+                    args.args.len()
+                ),
+            ));
+        }
+
+        let mut substitution = ParamSubstitution::default();
+        for (param, arg) in params.into_iter().zip(&args.args) {
+            match (param, arg) {
+                (syn::GenericParam::Type(param), syn::GenericArgument::Type(ty)) => {
+                    substitution
+                        .types
+                        .insert(param.ident.to_string(), ty.clone());
+                }
+                (syn::GenericParam::Lifetime(param), syn::GenericArgument::Lifetime(lifetime)) => {
+                    substitution
+                        .lifetimes
+                        .insert(param.lifetime.ident.to_string(), lifetime.clone());
+                }
+                (syn::GenericParam::Const(_), _) => {
+                    return Err(syn::Error::new_spanned(
+                        param,
+                        "const parameters of called functions are not supported",
+                    ))
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        arg,
+                        format!("mismatched generic argument for function `{}`", name),
+                    ))
+                }
+            }
+        }
+        let mut generics = callee.sig.generics.clone();
+        substitution.visit_generics_mut(&mut generics);
+
+        let scope = Scope::default();
+        let mut where_clauses = Vec::new();
+        for param in &generics.params {
+            match param {
+                syn::GenericParam::Type(param) => {
+                    let ty = self.ty(&substitution.types[&param.ident.to_string()], scope)?;
+                    where_clauses.extend(self.bounds_on(&ty, &param.bounds, &[], scope)?);
+                }
+                syn::GenericParam::Lifetime(param) => {
+                    where_clauses.extend(self.outlives(&param.lifetime, &param.bounds)?);
+                }
+                syn::GenericParam::Const(_) => {}
+            }
+        }
+        where_clauses.extend(self.where_predicates(&generics.where_clause, scope)?);
+        Ok(where_clauses)
+    }
+
+    fn adt(
+        &self,
+        item: &impl Spanned,
+        attrs: &[syn::Attribute],
+        ident: &syn::Ident,
+        generics: &syn::Generics,
+        variants: Vec<Variant>,
+        kind: AdtKind,
+    ) -> Result<AdtDefn> {
+        let (variable_kinds, where_clauses) = self.generics(generics, Scope::default())?;
+        Ok(AdtDefn {
+            name: self.ident(ident),
+            variable_kinds,
+            where_clauses,
+            variants,
+            flags: AdtFlags {
+                upstream: false,
+                fundamental: has_attr(attrs, "fundamental"),
+                phantom_data: lang_item(attrs)?.as_deref() == Some("phantom_data"),
+                one_zst: false,
+                kind,
+            },
+            repr: self.repr(attrs)?,
+            variances: None,
+            span: self.span(item.span()),
+        })
+    }
+
+    fn fields(&self, fields: &syn::Fields) -> Result<Vec<Field>> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                Ok(Field {
+                    name: match &field.ident {
+                        Some(ident) => self.ident(ident),
+                        None => Identifier {
+                            str: Atom::from(i.to_string()),
+                            span: self.span(field.span()),
+                        },
+                    },
+                    ty: self.ty(&field.ty, Scope::default())?,
+                })
+            })
+            .collect()
+    }
+
+    fn discriminant(&self, expr: &syn::Expr) -> Result<i64> {
+        match expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(int),
+                ..
+            }) => int.base10_parse(),
+            syn::Expr::Unary(syn::ExprUnary {
+                op: syn::UnOp::Neg(_),
+                expr,
+                ..
+            }) => Ok(-self.discriminant(expr)?),
+            syn::Expr::Paren(syn::ExprParen { expr, .. }) => self.discriminant(expr),
+            _ => Err(syn::Error::new_spanned(
+                expr,
+                "only integer literals are supported as discriminants",
+            )),
+        }
+    }
+
+    fn repr(&self, attrs: &[syn::Attribute]) -> Result<AdtRepr> {
+        let mut repr = AdtRepr {
+            c: false,
+            packed: false,
+            transparent: false,
+            simd: false,
+            int: None,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+            let nested = match attr.parse_meta()? {
+                syn::Meta::List(list) => list.nested,
+                meta => return Err(syn::Error::new_spanned(meta, "malformed `repr` attribute")),
+            };
+            for meta in nested {
+                let name = match &meta {
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) => path.get_ident(),
+                    _ => None,
+                };
+                match name.map(|name| name.to_string()).as_deref() {
+                    Some("C") => repr.c = true,
+                    Some("packed") => repr.packed = true,
+                    Some("transparent") => repr.transparent = true,
+                    Some("simd") => repr.simd = true,
+                    Some(name) if scalar(name).is_some() => {
+                        repr.int = scalar(name).map(|ty| Ty::Scalar { ty });
+                    }
+                    _ => return Err(syn::Error::new_spanned(meta, "unsupported `repr`")),
+                }
+            }
+        }
+        Ok(repr)
+    }
+
+    fn trait_defn(&self, item: &syn::ItemTrait) -> Result<TraitDefn> {
+        let self_ident = Identifier {
+            str: Atom::from("Self"),
+            span: self.span(item.ident.span()),
+        };
+        let self_ty = Ty::Id { name: self_ident };
+        let self_trait = TraitRef {
+            trait_name: self.ident(&item.ident),
+            args: std::iter::once(GenericArg::Ty(self_ty.clone()))
+                .chain(
+                    item.generics
+                        .params
+                        .iter()
+                        .map(|param| self.param_arg(param)),
+                )
+                .collect(),
+        };
+        let scope = Scope {
+            self_ty: None,
+            self_trait: Some(&self_trait),
+        };
+
+        // Supertraits are bounds on `Self`.
+        let mut where_clauses = self.bounds_on(&self_ty, &item.supertraits, &[], scope)?;
+        let (variable_kinds, generic_where_clauses) = self.generics(&item.generics, scope)?;
+        where_clauses.extend(generic_where_clauses);
+
+        let mut assoc_ty_defns = Vec::new();
+        for trait_item in &item.items {
+            if let syn::TraitItem::Type(assoc_ty) = trait_item {
+                let (variable_kinds, where_clauses) = self.generics(&assoc_ty.generics, scope)?;
+                let bounds = assoc_ty
+                    .bounds
+                    .iter()
+                    .map(|bound| self.inline_bound(bound, scope))
+                    .collect::<Result<Vec<_>>>()?;
+                assoc_ty_defns.push(AssocTyDefn {
+                    name: self.ident(&assoc_ty.ident),
+                    variable_kinds,
+                    bounds: bounds.into_iter().flatten().collect(),
+                    where_clauses,
+                    span: self.span(assoc_ty.span()),
+                });
+            }
+        }
+
+        let lang_item = lang_item(&item.attrs)?;
+        Ok(TraitDefn {
+            name: self.ident(&item.ident),
+            variable_kinds,
+            where_clauses,
+            assoc_ty_defns,
+            flags: TraitFlags {
+                auto: item.auto_token.is_some(),
+                marker: has_attr(&item.attrs, "marker"),
+                upstream: false,
+                fundamental: has_attr(&item.attrs, "fundamental"),
+                non_enumerable: false,
+                coinductive: false,
+                object_safe: false,
+            },
+            well_known: lang_item.as_deref().and_then(well_known_trait),
+            span: self.span(item.span()),
+        })
+    }
+
+    fn impl_defn(&self, item: &syn::ItemImpl) -> Result<Impl> {
+        let (negation, path, _) = item.trait_.as_ref().unwrap();
+        let segment = last_segment(path)?;
+        let args_no_self = match &segment.arguments {
+            syn::PathArguments::None => vec![],
+            syn::PathArguments::AngleBracketed(args) => {
+                let impl_scope = Scope {
+                    self_ty: Some(&item.self_ty),
+                    self_trait: None,
+                };
+                self.generic_args(args, impl_scope)?
+            }
+            syn::PathArguments::Parenthesized(args) => {
+                return Err(syn::Error::new_spanned(
+                    args,
+                    "parenthesized arguments are only supported in bounds",
+                ))
+            }
+        };
+        let trait_ref = TraitRef {
+            trait_name: self.ident(&segment.ident),
+            args: std::iter::once(GenericArg::Ty(self.ty(&item.self_ty, Scope::default())?))
+                .chain(args_no_self)
+                .collect(),
+        };
+        let scope = Scope {
+            self_ty: Some(&item.self_ty),
+            self_trait: Some(&trait_ref),
+        };
+
+        let (variable_kinds, where_clauses) = self.generics(&item.generics, scope)?;
+        let mut assoc_ty_values = Vec::new();
+        for impl_item in &item.items {
+            if let syn::ImplItem::Type(value) = impl_item {
+                let (variable_kinds, _) = self.generics(&value.generics, scope)?;
+                assoc_ty_values.push(AssocTyValue {
+                    name: self.ident(&value.ident),
+                    variable_kinds,
+                    value: self.ty(&value.ty, scope)?,
+                    default: value.defaultness.is_some(),
+                    span: self.span(value.span()),
+                });
+            }
+        }
+
+        Ok(Impl {
+            variable_kinds,
+            trait_ref,
+            polarity: Polarity::from_bool(negation.is_none()),
+            where_clauses,
+            assoc_ty_values,
+            impl_type: ImplType::Local,
+            span: self.span(item.span()),
+        })
+    }
+
+    /// The parameters declared by `generics`, and the where clauses they
+    /// declare, both inline and in their where clause.
+    fn generics(
+        &self,
+        generics: &syn::Generics,
+        scope: Scope<'_>,
+    ) -> Result<(Vec<VariableKind>, Vec<QuantifiedWhereClause>)> {
+        let mut variable_kinds = Vec::new();
+        let mut where_clauses = Vec::new();
+        for param in &generics.params {
+            match param {
+                syn::GenericParam::Type(param) => {
+                    let name = self.ident(&param.ident);
+                    variable_kinds.push(VariableKind::Ty(name.clone()));
+                    where_clauses.extend(self.bounds_on(
+                        &Ty::Id { name },
+                        &param.bounds,
+                        &[],
+                        scope,
+                    )?);
+                }
+                syn::GenericParam::Lifetime(param) => {
+                    variable_kinds
+                        .push(VariableKind::Lifetime(self.lifetime_name(&param.lifetime)));
+                    where_clauses.extend(self.outlives(&param.lifetime, &param.bounds)?);
+                }
+                syn::GenericParam::Const(param) => {
+                    let ty = match &param.ty {
+                        syn::Type::Path(path) if path.qself.is_none() => path
+                            .path
+                            .get_ident()
+                            .and_then(|ident| scalar(&ident.to_string())),
+                        _ => None,
+                    };
+                    variable_kinds.push(VariableKind::Const(self.ident(&param.ident), ty));
+                }
+            }
+        }
+
+        where_clauses.extend(self.where_predicates(&generics.where_clause, scope)?);
+        Ok((variable_kinds, where_clauses))
+    }
+
+    /// The where clauses declared by a `where` clause.
+    fn where_predicates(
+        &self,
+        where_clause: &Option<syn::WhereClause>,
+        scope: Scope<'_>,
+    ) -> Result<Vec<QuantifiedWhereClause>> {
+        let mut where_clauses = Vec::new();
+        for predicate in where_clause.iter().flat_map(|w| &w.predicates) {
+            match predicate {
+                syn::WherePredicate::Type(predicate) => {
+                    let for_lifetimes = self.bound_lifetimes(&predicate.lifetimes);
+                    let ty = self.ty(&predicate.bounded_ty, scope)?;
+                    where_clauses.extend(self.bounds_on(
+                        &ty,
+                        &predicate.bounds,
+                        &for_lifetimes,
+                        scope,
+                    )?);
+                }
+                syn::WherePredicate::Lifetime(predicate) => {
+                    where_clauses.extend(self.outlives(&predicate.lifetime, &predicate.bounds)?);
+                }
+                syn::WherePredicate::Eq(predicate) => {
+                    return Err(syn::Error::new_spanned(
+                        predicate,
+                        "equality constraints are not supported",
+                    ))
+                }
+            }
+        }
+        Ok(where_clauses)
+    }
+
+    /// The where clauses `ty: bound` for each of `bounds`, under the
+    /// `for<...>` lifetimes `for_lifetimes`.
+    fn bounds_on<'b>(
+        &self,
+        ty: &Ty,
+        bounds: impl IntoIterator<Item = &'b syn::TypeParamBound>,
+        for_lifetimes: &[VariableKind],
+        scope: Scope<'_>,
+    ) -> Result<Vec<QuantifiedWhereClause>> {
+        let mut where_clauses = Vec::new();
+        for bound in bounds {
+            for bound in self.inline_bound(bound, scope)? {
+                let mut variable_kinds = for_lifetimes.to_vec();
+                variable_kinds.extend(bound.variable_kinds);
+                where_clauses.push(QuantifiedWhereClause {
+                    variable_kinds,
+                    where_clause: bound.bound.into_where_clause(ty.clone()),
+                });
+            }
+        }
+        Ok(where_clauses)
+    }
+
+    /// The where clauses `lifetime: bound` for each of `bounds`.
+    fn outlives<'b>(
+        &self,
+        lifetime: &syn::Lifetime,
+        bounds: impl IntoIterator<Item = &'b syn::Lifetime>,
+    ) -> Result<Vec<QuantifiedWhereClause>> {
+        bounds
+            .into_iter()
+            .map(|bound| {
+                Ok(QuantifiedWhereClause {
+                    variable_kinds: vec![],
+                    where_clause: WhereClause::LifetimeOutlives {
+                        a: self.lifetime(lifetime)?,
+                        b: self.lifetime(bound)?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// The inline bounds `bound` stands for, as in `Bound` in the grammar.
+    fn inline_bound(
+        &self,
+        bound: &syn::TypeParamBound,
+        scope: Scope<'_>,
+    ) -> Result<Vec<QuantifiedInlineBound>> {
+        let bound = match bound {
+            syn::TypeParamBound::Trait(bound) => bound,
+            syn::TypeParamBound::Lifetime(lifetime) => {
+                return Ok(vec![QuantifiedInlineBound {
+                    variable_kinds: vec![],
+                    bound: InlineBound::Outlives(self.lifetime(lifetime)?),
+                }])
+            }
+        };
+        let segment = last_segment(&bound.path)?;
+        if let syn::TraitBoundModifier::Maybe(_) = bound.modifier {
+            if segment.ident == "Sized" {
+                return Ok(vec![]);
+            }
+            return Err(syn::Error::new_spanned(
+                bound,
+                "`?Trait` bounds are only supported for `Sized`",
+            ));
+        }
+
+        let trait_name = self.ident(&segment.ident);
+        let bounds = match &segment.arguments {
+            syn::PathArguments::None => vec![InlineBound::TraitBound(TraitBound {
+                trait_name,
+                args_no_self: vec![],
+            })],
+            syn::PathArguments::AngleBracketed(args) => {
+                let args = args
+                    .args
+                    .iter()
+                    .map(|arg| self.bound_arg(arg, scope))
+                    .collect::<Result<_>>()?;
+                InlineBound::from_args(trait_name, args)
+                    .map_err(|e| syn::Error::new_spanned(segment, e))?
+            }
+            syn::PathArguments::Parenthesized(args) => {
+                // `Fn(A) -> B` is `Fn<(A,)>` with an `Output = B` binding.
+                let inputs = args
+                    .inputs
+                    .iter()
+                    .map(|input| Ok(Box::new(self.ty(input, scope)?)))
+                    .collect::<Result<_>>()?;
+                let value = match &args.output {
+                    syn::ReturnType::Default => Ty::Tuple { types: vec![] },
+                    syn::ReturnType::Type(_, ty) => self.ty(ty, scope)?,
+                };
+                vec![InlineBound::AliasEqBound(AliasEqBound {
+                    name: Identifier {
+                        str: Atom::from("Output"),
+                        span: trait_name.span,
+                    },
+                    trait_bound: TraitBound {
+                        trait_name,
+                        args_no_self: vec![GenericArg::Ty(Ty::Tuple { types: inputs })],
+                    },
+                    args: vec![],
+                    value,
+                })]
+            }
+        };
+        let variable_kinds = self.bound_lifetimes(&bound.lifetimes);
+        Ok(bounds
+            .into_iter()
+            .map(|bound| QuantifiedInlineBound {
+                variable_kinds: variable_kinds.clone(),
+                bound,
+            })
+            .collect())
+    }
+
+    fn bound_arg(&self, arg: &syn::GenericArgument, scope: Scope<'_>) -> Result<BoundArg> {
+        match arg {
+            syn::GenericArgument::Binding(binding) => Ok(BoundArg::Binding {
+                name: self.ident(&binding.ident),
+                args: vec![],
+                value: self.ty(&binding.ty, scope)?,
+            }),
+            arg => Ok(BoundArg::GenericArg(self.generic_arg(arg, scope)?)),
+        }
+    }
+
+    fn generic_args(
+        &self,
+        args: &syn::AngleBracketedGenericArguments,
+        scope: Scope<'_>,
+    ) -> Result<Vec<GenericArg>> {
+        args.args
+            .iter()
+            .map(|arg| self.generic_arg(arg, scope))
+            .collect()
+    }
+
+    fn generic_arg(&self, arg: &syn::GenericArgument, scope: Scope<'_>) -> Result<GenericArg> {
+        match arg {
+            syn::GenericArgument::Lifetime(lifetime) => {
+                Ok(GenericArg::Lifetime(self.lifetime(lifetime)?))
+            }
+            syn::GenericArgument::Type(ty) => {
+                // A lone name may be a type or a const parameter; lowering
+                // tells them apart.
+                if let syn::Type::Path(syn::TypePath { qself: None, path }) = ty {
+                    if let Some(ident) = path.get_ident() {
+                        let name = ident.to_string();
+                        if name != "Self"
+                            && name != "str"
+                            && scalar(&name).is_none()
+                            && !self.aliases.contains_key(&name)
+                        {
+                            return Ok(GenericArg::Id(self.ident(ident)));
+                        }
+                    }
+                }
+                Ok(GenericArg::Ty(self.ty(ty, scope)?))
+            }
+            syn::GenericArgument::Const(expr) => Ok(GenericArg::Const(self.const_value(expr)?)),
+            syn::GenericArgument::Binding(_) | syn::GenericArgument::Constraint(_) => {
+                Err(syn::Error::new_spanned(
+                    arg,
+                    "associated type bindings are only supported in bounds",
+                ))
+            }
+        }
+    }
+
+    /// The generic argument that refers to `param`.
+    fn param_arg(&self, param: &syn::GenericParam) -> GenericArg {
+        match param {
+            syn::GenericParam::Type(param) => GenericArg::Id(self.ident(&param.ident)),
+            syn::GenericParam::Lifetime(param) => GenericArg::Lifetime(Lifetime::Id {
+                name: self.lifetime_name(&param.lifetime),
+            }),
+            syn::GenericParam::Const(param) => GenericArg::Id(self.ident(&param.ident)),
+        }
+    }
+
+    fn ty(&self, ty: &syn::Type, scope: Scope<'_>) -> Result<Ty> {
+        Ok(match ty {
+            syn::Type::Paren(ty) => self.ty(&ty.elem, scope)?,
+            syn::Type::Group(ty) => self.ty(&ty.elem, scope)?,
+            syn::Type::Never(_) => Ty::Never,
+            syn::Type::Tuple(tuple) => Ty::Tuple {
+                types: tuple
+                    .elems
+                    .iter()
+                    .map(|ty| Ok(Box::new(self.ty(ty, scope)?)))
+                    .collect::<Result<_>>()?,
+            },
+            syn::Type::Slice(slice) => Ty::Slice {
+                ty: Box::new(self.ty(&slice.elem, scope)?),
+            },
+            syn::Type::Array(array) => Ty::Array {
+                ty: Box::new(self.ty(&array.elem, scope)?),
+                len: self.const_value(&array.len)?,
+            },
+            syn::Type::Ptr(ptr) => Ty::Raw {
+                mutability: mutability(ptr.mutability.is_some()),
+                ty: Box::new(self.ty(&ptr.elem, scope)?),
+            },
+            syn::Type::Reference(reference) => Ty::Ref {
+                mutability: mutability(reference.mutability.is_some()),
+                lifetime: match &reference.lifetime {
+                    Some(lifetime) => self.lifetime(lifetime)?,
+                    None => {
+                        return Err(syn::Error::new_spanned(
+                            reference,
+                            "elided lifetimes are not supported",
+                        ))
+                    }
+                },
+                ty: Box::new(self.ty(&reference.elem, scope)?),
+            },
+            syn::Type::BareFn(fn_ptr) => {
+                let mut types = fn_ptr
+                    .inputs
+                    .iter()
+                    .map(|input| Ok(Box::new(self.ty(&input.ty, scope)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                types.push(Box::new(match &fn_ptr.output {
+                    syn::ReturnType::Default => Ty::Tuple { types: vec![] },
+                    syn::ReturnType::Type(_, ty) => self.ty(ty, scope)?,
+                }));
+                Ty::ForAll {
+                    lifetime_names: self
+                        .bound_lifetimes(&fn_ptr.lifetimes)
+                        .into_iter()
+                        .filter_map(|kind| match kind {
+                            VariableKind::Lifetime(name) => Some(name),
+                            _ => None,
+                        })
+                        .collect(),
+                    types,
+                    sig: FnSig {
+                        abi: match &fn_ptr.abi {
+                            Some(abi) => FnAbi(Atom::from(
+                                abi.name
+                                    .as_ref()
+                                    .map_or("C".to_string(), |name| name.value()),
+                            )),
+                            None => FnAbi::default(),
+                        },
+                        safety: if fn_ptr.unsafety.is_some() {
+                            Safety::Unsafe
+                        } else {
+                            Safety::Safe
+                        },
+                        variadic: fn_ptr.variadic.is_some(),
+                    },
+                }
+            }
+            syn::Type::TraitObject(object) => {
+                // Like in Rust, trait objects outlive `'static` unless
+                // told otherwise.
+                let mut bounds = Vec::new();
+                let mut lifetime = Lifetime::Static;
+                for bound in &object.bounds {
+                    match bound {
+                        syn::TypeParamBound::Lifetime(l) => lifetime = self.lifetime(l)?,
+                        bound => bounds.extend(self.inline_bound(bound, scope)?),
+                    }
+                }
+                Ty::Dyn { bounds, lifetime }
+            }
+            syn::Type::Path(path) => self.path_ty(path, scope)?,
+            _ => return Err(syn::Error::new_spanned(ty, "unsupported type")),
+        })
+    }
+
+    fn path_ty(&self, ty: &syn::TypePath, scope: Scope<'_>) -> Result<Ty> {
+        let segments: Vec<_> = ty.path.segments.iter().collect();
+
+        // `<T as Trait>::Name`
+        if let Some(qself) = &ty.qself {
+            if qself.position == 0 || segments.len() != qself.position + 1 {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "only associated types of traits, like `<T as Trait>::Name`, are supported",
+                ));
+            }
+            let trait_segment = segments[qself.position - 1];
+            let mut args = vec![GenericArg::Ty(self.ty(&qself.ty, scope)?)];
+            match &trait_segment.arguments {
+                syn::PathArguments::None => {}
+                syn::PathArguments::AngleBracketed(trait_args) => {
+                    args.extend(self.generic_args(trait_args, scope)?)
+                }
+                syn::PathArguments::Parenthesized(trait_args) => {
+                    return Err(syn::Error::new_spanned(
+                        trait_args,
+                        "parenthesized arguments are only supported in bounds",
+                    ))
+                }
+            }
+            let trait_ref = TraitRef {
+                trait_name: self.ident(&trait_segment.ident),
+                args,
+            };
+            return self.projection(trait_ref, segments[qself.position], scope);
+        }
+
+        // `Self::Name`
+        if segments.len() == 2 && segments[0].ident == "Self" && segments[0].arguments.is_empty() {
+            let self_trait = scope.self_trait.ok_or_else(|| {
+                syn::Error::new_spanned(
+                    ty,
+                    "`Self::Name` is only supported in traits and trait impls",
+                )
+            })?;
+            return self.projection(self_trait.clone(), segments[1], scope);
+        }
+
+        let segment = *segments.last().unwrap();
+        let name = segment.ident.to_string();
+        if segment.arguments.is_empty() {
+            if name == "Self" {
+                return match scope.self_ty {
+                    Some(self_ty) => self.ty(self_ty, Scope::default()),
+                    None => Ok(Ty::Id {
+                        name: self.ident(&segment.ident),
+                    }),
+                };
+            }
+            if name == "str" {
+                return Ok(Ty::Str);
+            }
+            if let Some(ty) = scalar(&name) {
+                return Ok(Ty::Scalar { ty });
+            }
+        }
+        if let Some(alias) = self.aliases.get(&name) {
+            return self.expand_alias(alias, segment, scope);
+        }
+
+        match &segment.arguments {
+            syn::PathArguments::None => Ok(Ty::Id {
+                name: self.ident(&segment.ident),
+            }),
+            syn::PathArguments::AngleBracketed(args) => Ok(Ty::Apply {
+                name: self.ident(&segment.ident),
+                args: self.generic_args(args, scope)?,
+            }),
+            syn::PathArguments::Parenthesized(args) => Err(syn::Error::new_spanned(
+                args,
+                "parenthesized arguments are only supported in bounds",
+            )),
+        }
+    }
+
+    /// The associated type `segment` of `trait_ref`.
+    fn projection(
+        &self,
+        trait_ref: TraitRef,
+        segment: &syn::PathSegment,
+        scope: Scope<'_>,
+    ) -> Result<Ty> {
+        let args = match &segment.arguments {
+            syn::PathArguments::None => vec![],
+            syn::PathArguments::AngleBracketed(args) => self.generic_args(args, scope)?,
+            syn::PathArguments::Parenthesized(args) => {
+                return Err(syn::Error::new_spanned(
+                    args,
+                    "parenthesized arguments are only supported in bounds",
+                ))
+            }
+        };
+        Ok(Ty::Projection {
+            proj: ProjectionTy {
+                trait_ref,
+                name: self.ident(&segment.ident),
+                args,
+            },
+        })
+    }
+
+    /// The type `alias`, applied to the arguments of `segment`, stands for.
+    fn expand_alias(
+        &self,
+        alias: &syn::ItemType,
+        segment: &syn::PathSegment,
+        scope: Scope<'_>,
+    ) -> Result<Ty> {
+        let name = alias.ident.to_string();
+        if self.expanding.borrow().contains(&name) {
+            return Err(syn::Error::new_spanned(
+                segment,
+                format!("type alias `{}` is recursive", name),
+            ));
+        }
+
+        let args: Vec<_> = match &segment.arguments {
+            syn::PathArguments::None => vec![],
+            syn::PathArguments::AngleBracketed(args) => args.args.iter().collect(),
+            syn::PathArguments::Parenthesized(args) => {
+                return Err(syn::Error::new_spanned(
+                    args,
+                    "parenthesized arguments are only supported in bounds",
+                ))
+            }
+        };
+        if args.len() != alias.generics.params.len() {
+            return Err(syn::Error::new_spanned(
+                segment,
+                format!(
+                    "type alias `{}` takes {} generic arguments, not {}",
+                    name,
+                    alias.generics.params.len(),
+                    args.len()
+                ),
+            ));
+        }
+
+        let mut substitution = ParamSubstitution::default();
+        for (param, arg) in alias.generics.params.iter().zip(args) {
+            match (param, arg) {
+                (syn::GenericParam::Type(param), syn::GenericArgument::Type(ty)) => {
+                    substitution
+                        .types
+                        .insert(param.ident.to_string(), ty.clone());
+                }
+                (syn::GenericParam::Lifetime(param), syn::GenericArgument::Lifetime(lifetime)) => {
+                    substitution
+                        .lifetimes
+                        .insert(param.lifetime.ident.to_string(), lifetime.clone());
+                }
+                (syn::GenericParam::Const(_), _) => {
+                    return Err(syn::Error::new_spanned(
+                        param,
+                        "const parameters of type aliases are not supported",
+                    ))
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        arg,
+                        format!("mismatched generic argument for type alias `{}`", name),
+                    ))
+                }
+            }
+        }
+        let mut ty = (*alias.ty).clone();
+        substitution.visit_type_mut(&mut ty);
+
+        self.expanding.borrow_mut().push(name);
+        let expanded = self.ty(&ty, scope);
+        self.expanding.borrow_mut().pop();
+        expanded
+    }
+
+    fn const_value(&self, expr: &syn::Expr) -> Result<Const> {
+        match expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(int),
+                ..
+            }) => Ok(Const::Value(int.base10_parse()?, scalar(int.suffix()))),
+            syn::Expr::Path(syn::ExprPath {
+                qself: None, path, ..
+            }) if path.get_ident().is_some() => {
+                Ok(Const::Id(self.ident(path.get_ident().unwrap())))
+            }
+            syn::Expr::Block(block) if block.block.stmts.len() == 1 => {
+                match &block.block.stmts[0] {
+                    syn::Stmt::Expr(expr) => self.const_value(expr),
+                    _ => Err(syn::Error::new_spanned(expr, "unsupported constant")),
+                }
+            }
+            _ => Err(syn::Error::new_spanned(expr, "unsupported constant")),
+        }
+    }
+
+    fn lifetime(&self, lifetime: &syn::Lifetime) -> Result<Lifetime> {
+        match lifetime.ident.to_string().as_str() {
+            "static" => Ok(Lifetime::Static),
+            "_" => Err(syn::Error::new_spanned(
+                lifetime,
+                "elided lifetimes are not supported",
+            )),
+            _ => Ok(Lifetime::Id {
+                name: self.lifetime_name(lifetime),
+            }),
+        }
+    }
+
+    fn lifetime_name(&self, lifetime: &syn::Lifetime) -> Identifier {
+        Identifier {
+            str: Atom::from(lifetime.to_string()),
+            span: self.span(lifetime.span()),
+        }
+    }
+
+    /// The lifetimes of a `for<...>` binder.
+    fn bound_lifetimes(&self, lifetimes: &Option<syn::BoundLifetimes>) -> Vec<VariableKind> {
+        lifetimes
+            .iter()
+            .flat_map(|lifetimes| &lifetimes.lifetimes)
+            .map(|def| VariableKind::Lifetime(self.lifetime_name(&def.lifetime)))
+            .collect()
+    }
+
+    fn ident(&self, ident: &syn::Ident) -> Identifier {
+        Identifier {
+            str: Atom::from(ident.unraw().to_string()),
+            span: self.span(ident.span()),
+        }
+    }
+
+    fn span(&self, span: SynSpan) -> Span {
+        to_span(self.text, &self.line_starts, span)
+    }
+}
+
+/// Replaces generic parameters with arguments, to expand a type alias or
+/// to instantiate the bounds of a called function.
+#[derive(Default)]
+struct ParamSubstitution {
+    types: HashMap<String, syn::Type>,
+    lifetimes: HashMap<String, syn::Lifetime>,
+}
+
+impl VisitMut for ParamSubstitution {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if let syn::Type::Path(syn::TypePath { qself: None, path }) = ty {
+            if let Some(arg) = path
+                .get_ident()
+                .and_then(|i| self.types.get(&i.to_string()))
+            {
+                *ty = arg.clone();
+                return;
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if let Some(arg) = self.lifetimes.get(&lifetime.ident.to_string()) {
+            *lifetime = arg.clone();
+        }
+    }
+}
+
+/// Collects the paths called in a function body, and the functions
+/// defined in it, without looking into the bodies of the latter.
+#[derive(Default)]
+struct BodyCollector {
+    calls: Vec<syn::ExprPath>,
+    fns: Vec<syn::ItemFn>,
+}
+
+impl VisitMut for BodyCollector {
+    fn visit_expr_call_mut(&mut self, call: &mut syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*call.func {
+            self.calls.push(path.clone());
+        }
+        syn::visit_mut::visit_expr_call_mut(self, call);
+    }
+
+    fn visit_item_fn_mut(&mut self, item: &mut syn::ItemFn) {
+        self.fns.push(item.clone());
+    }
+}
+
+fn last_segment(path: &syn::Path) -> Result<&syn::PathSegment> {
+    path.segments
+        .last()
+        .ok_or_else(|| syn::Error::new_spanned(path, "empty path"))
+}
+
+fn mutability(mutable: bool) -> Mutability {
+    if mutable {
+        Mutability::Mut
+    } else {
+        Mutability::Not
+    }
+}
+
+fn has_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path.is_ident(name))
+}
+
+/// The name given by a `#[lang = "name"]` attribute, if any.
+fn lang_item(attrs: &[syn::Attribute]) -> Result<Option<String>> {
+    let attr = match attrs.iter().find(|attr| attr.path.is_ident("lang")) {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    match attr.parse_meta()? {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(name),
+            ..
+        }) => Ok(Some(name.value())),
+        meta => Err(syn::Error::new_spanned(meta, "malformed `lang` attribute")),
+    }
+}
+
+fn well_known_trait(lang_item: &str) -> Option<WellKnownTrait> {
+    Some(match lang_item {
+        "sized" => WellKnownTrait::Sized,
+        "copy" => WellKnownTrait::Copy,
+        "clone" => WellKnownTrait::Clone,
+        "drop" => WellKnownTrait::Drop,
+        "fn_once" => WellKnownTrait::FnOnce,
+        "fn_mut" => WellKnownTrait::FnMut,
+        "fn" => WellKnownTrait::Fn,
+        "unsize" => WellKnownTrait::Unsize,
+        "unpin" => WellKnownTrait::Unpin,
+        "coerce_unsized" => WellKnownTrait::CoerceUnsized,
+        "discriminant_kind" => WellKnownTrait::DiscriminantKind,
+        "generator" => WellKnownTrait::Generator,
+        "dispatch_from_dyn" => WellKnownTrait::DispatchFromDyn,
+        "tuple_trait" => WellKnownTrait::Tuple,
+        "fn_ptr_trait" => WellKnownTrait::FnPtr,
+        "meta_sized" => WellKnownTrait::MetaSized,
+        "pointee_sized" => WellKnownTrait::PointeeSized,
+        "deref" => WellKnownTrait::Deref,
+        "into_iterator" => WellKnownTrait::IntoIterator,
+        "iterator" => WellKnownTrait::Iterator,
+        _ => return None,
+    })
+}
+
+fn scalar(name: &str) -> Option<ScalarType> {
+    Some(match name {
+        "bool" => ScalarType::Bool,
+        "char" => ScalarType::Char,
+        "isize" => ScalarType::Int(IntTy::Isize),
+        "i8" => ScalarType::Int(IntTy::I8),
+        "i16" => ScalarType::Int(IntTy::I16),
+        "i32" => ScalarType::Int(IntTy::I32),
+        "i64" => ScalarType::Int(IntTy::I64),
+        "i128" => ScalarType::Int(IntTy::I128),
+        "usize" => ScalarType::Uint(UintTy::Usize),
+        "u8" => ScalarType::Uint(UintTy::U8),
+        "u16" => ScalarType::Uint(UintTy::U16),
+        "u32" => ScalarType::Uint(UintTy::U32),
+        "u64" => ScalarType::Uint(UintTy::U64),
+        "u128" => ScalarType::Uint(UintTy::U128),
+        "f32" => ScalarType::Float(FloatTy::F32),
+        "f64" => ScalarType::Float(FloatTy::F64),
+        _ => return None,
+    })
+}
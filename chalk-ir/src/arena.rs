@@ -0,0 +1,633 @@
+//! An arena-allocating, hash-consing `Interner`.
+//!
+//! `ArenaInterner` stores every interned value in an `Arenas`, which
+//! allocates them in chunks that are never moved nor freed before the
+//! arenas themselves are dropped. Interned values are hash-consed: each
+//! distinct value is allocated once, so cloning, comparing and hashing
+//! interned values are all pointer operations.
+//!
+//! The interner leaves the choice of definition ids, identifiers and so
+//! on to its user, through the `ArenaInternerTypes` trait:
+//!
+//! ```
+//! use chalk_ir::arena::{ArenaInterner, ArenaInternerTypes, Arenas};
+//! use chalk_ir::{IntTy, Scalar, TyKind};
+//!
+//! #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+//! struct MyTypes;
+//!
+//! impl ArenaInternerTypes for MyTypes {
+//!     type DefId = u32;
+//!     type InternedAdtId = u32;
+//!     type Identifier = String;
+//!     type FnAbi = ();
+//!     type ConcreteConst = u64;
+//! }
+//!
+//! let arenas = Arenas::<MyTypes>::new();
+//! let interner = ArenaInterner::new(&arenas);
+//! let a = TyKind::Scalar(Scalar::Int(IntTy::I32)).intern(interner);
+//! let b = TyKind::Scalar(Scalar::Int(IntTy::I32)).intern(interner);
+//! assert_eq!(a, b);
+//! ```
+//!
+//! `Arenas` are internally synchronized, so a single interner can be
+//! shared between threads. Programs that solve on several threads
+//! independently can instead avoid contention by giving each thread its
+//! own arenas, e.g. by leaking them into a `thread_local!`:
+//!
+//! ```
+//! # use chalk_ir::arena::{ArenaInterner, ArenaInternerTypes, Arenas};
+//! # #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+//! # struct MyTypes;
+//! # impl ArenaInternerTypes for MyTypes {
+//! #     type DefId = u32;
+//! #     type InternedAdtId = u32;
+//! #     type Identifier = String;
+//! #     type FnAbi = ();
+//! #     type ConcreteConst = u64;
+//! # }
+//! thread_local! {
+//!     static INTERNER: ArenaInterner<'static, MyTypes> = Arenas::new().leak();
+//! }
+//! ```
+
+use crate::interner::{HasInterner, Interner};
+use crate::*;
+use alloc::boxed::Box;
+use rustc_hash::FxHashSet;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::ptr;
+use std::sync::Mutex;
+
+#[cfg(all(test, feature = "bench"))]
+mod bench;
+
+/// The types that an `ArenaInterner` leaves to its user; see the
+/// corresponding associated types of `Interner`.
+pub trait ArenaInternerTypes {
+    /// See `Interner::DefId`.
+    type DefId: Debug + Copy + Eq + Hash;
+    /// See `Interner::InternedAdtId`.
+    type InternedAdtId: Debug + Copy + Eq + Hash;
+    /// See `Interner::Identifier`.
+    type Identifier: Debug + Clone + Eq + Hash;
+    /// See `Interner::FnAbi`.
+    type FnAbi: Debug + Copy + Eq + Hash;
+    /// See `Interner::InternedConcreteConst`. Concrete constants are
+    /// compared with `==` by `Interner::const_eq`.
+    type ConcreteConst: Debug + Clone + Eq + Hash;
+}
+
+/// An `Interner` that allocates all interned values in `Arenas` and
+/// hash-conses them. Interners created from different arenas are not
+/// equal, and values interned by one must not be used with another.
+pub struct ArenaInterner<'a, T: ArenaInternerTypes> {
+    arenas: &'a Arenas<'a, T>,
+}
+
+impl<'a, T: ArenaInternerTypes> ArenaInterner<'a, T> {
+    /// Creates an interner that allocates in `arenas`.
+    pub fn new(arenas: &'a Arenas<'a, T>) -> Self {
+        ArenaInterner { arenas }
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> Clone for ArenaInterner<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> Copy for ArenaInterner<'a, T> {}
+
+impl<'a, T: ArenaInternerTypes> PartialEq for ArenaInterner<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.arenas, other.arenas)
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> Eq for ArenaInterner<'a, T> {}
+
+impl<'a, T: ArenaInternerTypes> Hash for ArenaInterner<'a, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.arenas, state)
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> Debug for ArenaInterner<'a, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "ArenaInterner({:p})", self.arenas)
+    }
+}
+
+/// A value interned by an `ArenaInterner`. Since interned values are
+/// hash-consed, two `Interned` values are equal if and only if they
+/// point to the same allocation.
+pub struct Interned<'a, V: ?Sized>(&'a V);
+
+impl<'a, V: ?Sized> Clone for Interned<'a, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, V: ?Sized> Copy for Interned<'a, V> {}
+
+impl<'a, V: ?Sized> PartialEq for Interned<'a, V> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl<'a, V: ?Sized> Eq for Interned<'a, V> {}
+
+impl<'a, V: ?Sized> Hash for Interned<'a, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self.0, state)
+    }
+}
+
+impl<'a, V: ?Sized + Debug> Debug for Interned<'a, V> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl<'a, V: ?Sized> Deref for Interned<'a, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.0
+    }
+}
+
+/// A bump allocator for values of type `V`. Values are allocated in
+/// chunks of growing size; a chunk is never reallocated, so allocated
+/// values never move.
+struct TypedArena<V> {
+    chunks: Vec<Vec<V>>,
+}
+
+impl<V> TypedArena<V> {
+    const FIRST_CHUNK_CAPACITY: usize = 64;
+
+    fn new() -> Self {
+        TypedArena { chunks: vec![] }
+    }
+
+    /// Moves `value` into the arena.
+    ///
+    /// The returned pointer stays valid for as long as the arena itself.
+    fn alloc(&mut self, value: V) -> *const V {
+        let chunk = match self.chunks.last_mut() {
+            Some(chunk) if chunk.len() < chunk.capacity() => chunk,
+            _ => {
+                let capacity = self
+                    .chunks
+                    .last()
+                    .map_or(Self::FIRST_CHUNK_CAPACITY, |chunk| chunk.capacity() * 2);
+                self.chunks.push(Vec::with_capacity(capacity));
+                self.chunks.last_mut().unwrap()
+            }
+        };
+        // This never reallocates `chunk`, as it has spare capacity.
+        chunk.push(value);
+        chunk.last().unwrap()
+    }
+}
+
+/// The hash-consing table for one kind of interned value.
+struct InternTable<'a, V: ?Sized, S> {
+    /// The allocations backing the interned values: `V` for sized values,
+    /// `Box<V>` for slices.
+    arena: TypedArena<S>,
+    set: FxHashSet<&'a V>,
+}
+
+impl<'a, V: ?Sized + Eq + Hash, S> InternTable<'a, V, S> {
+    fn new() -> Self {
+        InternTable {
+            arena: TypedArena::new(),
+            set: FxHashSet::default(),
+        }
+    }
+
+    /// Returns the interned copy of `value`, allocating one with
+    /// `to_storage` and `from_storage` if there is none yet.
+    fn intern<Q>(
+        table: &'a Mutex<Self>,
+        value: Q,
+        to_storage: impl FnOnce(Q) -> S,
+        from_storage: impl FnOnce(&S) -> &V,
+    ) -> Interned<'a, V>
+    where
+        Q: Deref<Target = V>,
+    {
+        let mut table = table.lock().unwrap();
+        if let Some(&interned) = table.set.get(&*value) {
+            return Interned(interned);
+        }
+        let storage = table.arena.alloc(to_storage(value));
+        // SAFETY: the storage lives as long as the arena, which is part of
+        // the `Arenas` borrowed for `'a`, and is never mutated nor moved.
+        let interned: &'a V = from_storage(unsafe { &*storage });
+        table.set.insert(interned);
+        Interned(interned)
+    }
+
+    fn len(table: &Mutex<Self>) -> usize {
+        table.lock().unwrap().set.len()
+    }
+}
+
+/// A wrapper that lets sized values be interned through the same
+/// `Deref`-based interface as slices.
+struct Owned<V>(V);
+
+impl<V> Deref for Owned<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.0
+    }
+}
+
+type Table<'a, V> = Mutex<InternTable<'a, V, V>>;
+type SliceTable<'a, V> = Mutex<InternTable<'a, [V], Box<[V]>>>;
+
+/// The storage for all values interned by an `ArenaInterner`.
+pub struct Arenas<'a, T: ArenaInternerTypes> {
+    tys: Table<'a, TyData<ArenaInterner<'a, T>>>,
+    lifetimes: Table<'a, LifetimeData<ArenaInterner<'a, T>>>,
+    consts: Table<'a, ConstData<ArenaInterner<'a, T>>>,
+    generic_args: Table<'a, GenericArgData<ArenaInterner<'a, T>>>,
+    goals: Table<'a, GoalData<ArenaInterner<'a, T>>>,
+    goal_lists: SliceTable<'a, Goal<ArenaInterner<'a, T>>>,
+    substitutions: SliceTable<'a, GenericArg<ArenaInterner<'a, T>>>,
+    program_clauses: Table<'a, ProgramClauseData<ArenaInterner<'a, T>>>,
+    program_clause_lists: SliceTable<'a, ProgramClause<ArenaInterner<'a, T>>>,
+    quantified_where_clauses: SliceTable<'a, QuantifiedWhereClause<ArenaInterner<'a, T>>>,
+    variable_kinds: SliceTable<'a, VariableKind<ArenaInterner<'a, T>>>,
+    canonical_var_kinds: SliceTable<'a, CanonicalVarKind<ArenaInterner<'a, T>>>,
+    constraints: SliceTable<'a, InEnvironment<Constraint<ArenaInterner<'a, T>>>>,
+    variances: SliceTable<'a, Variance>,
+}
+
+impl<'a, T: ArenaInternerTypes> Arenas<'a, T> {
+    /// Creates empty arenas.
+    pub fn new() -> Self {
+        Arenas {
+            tys: Mutex::new(InternTable::new()),
+            lifetimes: Mutex::new(InternTable::new()),
+            consts: Mutex::new(InternTable::new()),
+            generic_args: Mutex::new(InternTable::new()),
+            goals: Mutex::new(InternTable::new()),
+            goal_lists: Mutex::new(InternTable::new()),
+            substitutions: Mutex::new(InternTable::new()),
+            program_clauses: Mutex::new(InternTable::new()),
+            program_clause_lists: Mutex::new(InternTable::new()),
+            quantified_where_clauses: Mutex::new(InternTable::new()),
+            variable_kinds: Mutex::new(InternTable::new()),
+            canonical_var_kinds: Mutex::new(InternTable::new()),
+            constraints: Mutex::new(InternTable::new()),
+            variances: Mutex::new(InternTable::new()),
+        }
+    }
+
+    /// The number of distinct types interned in these arenas.
+    pub fn interned_tys(&self) -> usize {
+        InternTable::len(&self.tys)
+    }
+
+    /// The number of distinct goals interned in these arenas.
+    pub fn interned_goals(&self) -> usize {
+        InternTable::len(&self.goals)
+    }
+}
+
+impl<T: ArenaInternerTypes> Arenas<'static, T> {
+    /// Leaks the arenas, returning an interner that can be used for the
+    /// rest of the program (e.g., from a `thread_local!`).
+    pub fn leak(self) -> ArenaInterner<'static, T> {
+        ArenaInterner::new(Box::leak(Box::new(self)))
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> Default for Arenas<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> Debug for Arenas<'a, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "Arenas({:p})", self)
+    }
+}
+
+/// Interns a sized value.
+fn intern<'a, V: Eq + Hash>(table: &'a Table<'a, V>, value: V) -> Interned<'a, V> {
+    InternTable::intern(table, Owned(value), |value| value.0, |value| value)
+}
+
+/// Interns a slice, collected from `data`.
+fn intern_slice<'a, V: Eq + Hash, E>(
+    table: &'a SliceTable<'a, V>,
+    data: impl IntoIterator<Item = Result<V, E>>,
+) -> Result<Interned<'a, [V]>, E> {
+    let data = data.into_iter().collect::<Result<Vec<V>, E>>()?;
+    Ok(InternTable::intern(
+        table,
+        data,
+        Vec::into_boxed_slice,
+        |data| data,
+    ))
+}
+
+impl<'a, T: ArenaInternerTypes> Interner for ArenaInterner<'a, T> {
+    type InternedType = Interned<'a, TyData<Self>>;
+    type InternedLifetime = Interned<'a, LifetimeData<Self>>;
+    type InternedConst = Interned<'a, ConstData<Self>>;
+    type InternedConcreteConst = T::ConcreteConst;
+    type InternedGenericArg = Interned<'a, GenericArgData<Self>>;
+    type InternedGoal = Interned<'a, GoalData<Self>>;
+    type InternedGoals = Interned<'a, [Goal<Self>]>;
+    type InternedSubstitution = Interned<'a, [GenericArg<Self>]>;
+    type InternedProgramClauses = Interned<'a, [ProgramClause<Self>]>;
+    type InternedProgramClause = Interned<'a, ProgramClauseData<Self>>;
+    type InternedQuantifiedWhereClauses = Interned<'a, [QuantifiedWhereClause<Self>]>;
+    type InternedVariableKinds = Interned<'a, [VariableKind<Self>]>;
+    type InternedCanonicalVarKinds = Interned<'a, [CanonicalVarKind<Self>]>;
+    type InternedConstraints = Interned<'a, [InEnvironment<Constraint<Self>>]>;
+    type InternedVariances = Interned<'a, [Variance]>;
+    type DefId = T::DefId;
+    type InternedAdtId = T::InternedAdtId;
+    type Identifier = T::Identifier;
+    type FnAbi = T::FnAbi;
+    // Custom types and predicates are not supported: they would need a way
+    // to walk through them, on top of the types in `ArenaInternerTypes`.
+    type CustomTy = ();
+    type CustomPredicate = ();
+
+    fn intern_ty(self, kind: TyKind<Self>) -> Self::InternedType {
+        let flags = kind.compute_flags(self);
+        let outer_exclusive_binder = kind.compute_outer_exclusive_binder(self);
+        intern(
+            &self.arenas.tys,
+            TyData {
+                kind,
+                flags,
+                outer_exclusive_binder,
+            },
+        )
+    }
+
+    fn ty_data(self, ty: &Self::InternedType) -> &TyData<Self> {
+        ty
+    }
+
+    fn intern_lifetime(self, lifetime: LifetimeData<Self>) -> Self::InternedLifetime {
+        intern(&self.arenas.lifetimes, lifetime)
+    }
+
+    fn lifetime_data(self, lifetime: &Self::InternedLifetime) -> &LifetimeData<Self> {
+        lifetime
+    }
+
+    fn intern_const(self, constant: ConstData<Self>) -> Self::InternedConst {
+        intern(&self.arenas.consts, constant)
+    }
+
+    fn const_data(self, constant: &Self::InternedConst) -> &ConstData<Self> {
+        constant
+    }
+
+    fn const_eq(
+        self,
+        _ty: &Self::InternedType,
+        c1: &T::ConcreteConst,
+        c2: &T::ConcreteConst,
+    ) -> bool {
+        c1 == c2
+    }
+
+    fn interned_eq_fast<V: PartialEq + ?Sized>(self, a: &V, b: &V) -> bool {
+        // interned values compare by address
+        a == b
+    }
+
+    fn intern_generic_arg(self, data: GenericArgData<Self>) -> Self::InternedGenericArg {
+        intern(&self.arenas.generic_args, data)
+    }
+
+    fn generic_arg_data(self, generic_arg: &Self::InternedGenericArg) -> &GenericArgData<Self> {
+        generic_arg
+    }
+
+    fn intern_goal(self, data: GoalData<Self>) -> Self::InternedGoal {
+        intern(&self.arenas.goals, data)
+    }
+
+    fn goal_data(self, goal: &Self::InternedGoal) -> &GoalData<Self> {
+        goal
+    }
+
+    fn intern_goals<E>(
+        self,
+        data: impl IntoIterator<Item = Result<Goal<Self>, E>>,
+    ) -> Result<Self::InternedGoals, E> {
+        intern_slice(&self.arenas.goal_lists, data)
+    }
+
+    fn goals_data(self, goals: &Self::InternedGoals) -> &[Goal<Self>] {
+        goals
+    }
+
+    fn intern_substitution<E>(
+        self,
+        data: impl IntoIterator<Item = Result<GenericArg<Self>, E>>,
+    ) -> Result<Self::InternedSubstitution, E> {
+        intern_slice(&self.arenas.substitutions, data)
+    }
+
+    fn substitution_data(self, substitution: &Self::InternedSubstitution) -> &[GenericArg<Self>] {
+        substitution
+    }
+
+    fn intern_program_clause(self, data: ProgramClauseData<Self>) -> Self::InternedProgramClause {
+        intern(&self.arenas.program_clauses, data)
+    }
+
+    fn program_clause_data(self, clause: &Self::InternedProgramClause) -> &ProgramClauseData<Self> {
+        clause
+    }
+
+    fn intern_program_clauses<E>(
+        self,
+        data: impl IntoIterator<Item = Result<ProgramClause<Self>, E>>,
+    ) -> Result<Self::InternedProgramClauses, E> {
+        intern_slice(&self.arenas.program_clause_lists, data)
+    }
+
+    fn program_clauses_data(
+        self,
+        clauses: &Self::InternedProgramClauses,
+    ) -> &[ProgramClause<Self>] {
+        clauses
+    }
+
+    fn intern_quantified_where_clauses<E>(
+        self,
+        data: impl IntoIterator<Item = Result<QuantifiedWhereClause<Self>, E>>,
+    ) -> Result<Self::InternedQuantifiedWhereClauses, E> {
+        intern_slice(&self.arenas.quantified_where_clauses, data)
+    }
+
+    fn quantified_where_clauses_data(
+        self,
+        clauses: &Self::InternedQuantifiedWhereClauses,
+    ) -> &[QuantifiedWhereClause<Self>] {
+        clauses
+    }
+
+    fn intern_generic_arg_kinds<E>(
+        self,
+        data: impl IntoIterator<Item = Result<VariableKind<Self>, E>>,
+    ) -> Result<Self::InternedVariableKinds, E> {
+        intern_slice(&self.arenas.variable_kinds, data)
+    }
+
+    fn variable_kinds_data(
+        self,
+        variable_kinds: &Self::InternedVariableKinds,
+    ) -> &[VariableKind<Self>] {
+        variable_kinds
+    }
+
+    fn intern_canonical_var_kinds<E>(
+        self,
+        data: impl IntoIterator<Item = Result<CanonicalVarKind<Self>, E>>,
+    ) -> Result<Self::InternedCanonicalVarKinds, E> {
+        intern_slice(&self.arenas.canonical_var_kinds, data)
+    }
+
+    fn canonical_var_kinds_data(
+        self,
+        canonical_var_kinds: &Self::InternedCanonicalVarKinds,
+    ) -> &[CanonicalVarKind<Self>] {
+        canonical_var_kinds
+    }
+
+    fn intern_constraints<E>(
+        self,
+        data: impl IntoIterator<Item = Result<InEnvironment<Constraint<Self>>, E>>,
+    ) -> Result<Self::InternedConstraints, E> {
+        intern_slice(&self.arenas.constraints, data)
+    }
+
+    fn constraints_data(
+        self,
+        constraints: &Self::InternedConstraints,
+    ) -> &[InEnvironment<Constraint<Self>>] {
+        constraints
+    }
+
+    fn intern_variances<E>(
+        self,
+        data: impl IntoIterator<Item = Result<Variance, E>>,
+    ) -> Result<Self::InternedVariances, E> {
+        intern_slice(&self.arenas.variances, data)
+    }
+
+    fn variances_data(self, variances: &Self::InternedVariances) -> &[Variance] {
+        variances
+    }
+}
+
+impl<'a, T: ArenaInternerTypes> HasInterner for ArenaInterner<'a, T> {
+    type Interner = Self;
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    pub(crate) struct TestTypes;
+
+    impl ArenaInternerTypes for TestTypes {
+        type DefId = u32;
+        type InternedAdtId = u32;
+        type Identifier = String;
+        type FnAbi = ();
+        type ConcreteConst = u64;
+    }
+
+    fn adt<'a>(
+        interner: ArenaInterner<'a, TestTypes>,
+        id: u32,
+        args: Vec<Ty<ArenaInterner<'a, TestTypes>>>,
+    ) -> Ty<ArenaInterner<'a, TestTypes>> {
+        TyKind::Adt(AdtId(id), Substitution::from_iter(interner, args)).intern(interner)
+    }
+
+    #[test]
+    fn hash_consing() {
+        let arenas = Arenas::new();
+        let interner = ArenaInterner::new(&arenas);
+        let unit = TyKind::Tuple(0, Substitution::empty(interner)).intern(interner);
+
+        let a = adt(interner, 0, vec![unit, adt(interner, 1, vec![])]);
+        let b = adt(interner, 0, vec![unit, adt(interner, 1, vec![])]);
+        let c = adt(interner, 0, vec![adt(interner, 1, vec![]), unit]);
+        assert!(ptr::eq(a.data(interner), b.data(interner)));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        // `()`, `A1`, `A0<(), A1>` and `A0<A1, ()>`.
+        assert_eq!(arenas.interned_tys(), 4);
+    }
+
+    #[test]
+    fn interned_eq_fast() {
+        let arenas = Arenas::new();
+        let interner = ArenaInterner::new(&arenas);
+        let unit = TyKind::Tuple(0, Substitution::empty(interner)).intern(interner);
+
+        let a = adt(interner, 0, vec![unit]);
+        let b = adt(interner, 0, vec![unit]);
+        let c = adt(interner, 1, vec![unit]);
+        assert!(interner.interned_eq_fast(a.interned(), b.interned()));
+        assert!(!interner.interned_eq_fast(a.interned(), c.interned()));
+    }
+
+    #[test]
+    fn many_values() {
+        let arenas = Arenas::new();
+        let interner = ArenaInterner::new(&arenas);
+
+        // Allocate enough values to need several chunks, and check that
+        // values from earlier chunks are still intact.
+        let tys: Vec<_> = (0..1000).map(|i| adt(interner, i, vec![])).collect();
+        for (i, ty) in tys.iter().enumerate() {
+            match ty.kind(interner) {
+                TyKind::Adt(id, _) => assert_eq!(id.0 as usize, i),
+                _ => panic!("unexpected type {:?}", ty),
+            }
+            assert_eq!(*ty, adt(interner, i as u32, vec![]));
+        }
+        assert_eq!(arenas.interned_tys(), 1000);
+    }
+
+    #[test]
+    fn separate_arenas() {
+        let arenas1 = Arenas::<TestTypes>::new();
+        let arenas2 = Arenas::<TestTypes>::new();
+        assert_ne!(ArenaInterner::new(&arenas1), ArenaInterner::new(&arenas2));
+        assert_eq!(ArenaInterner::new(&arenas1), ArenaInterner::new(&arenas1));
+    }
+}
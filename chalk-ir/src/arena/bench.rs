@@ -0,0 +1,44 @@
+//! Benchmarks for `ArenaInterner`.
+
+extern crate test;
+use self::test::Bencher;
+
+use super::tests::TestTypes;
+use super::{ArenaInterner, Arenas};
+use crate::*;
+
+type I<'a> = ArenaInterner<'a, TestTypes>;
+
+/// Builds `A0<A1<...<An>...>>`.
+fn nested_ty(interner: I<'_>, depth: u32) -> Ty<I<'_>> {
+    (0..depth).rev().fold(
+        TyKind::Tuple(0, Substitution::empty(interner)).intern(interner),
+        |ty, i| TyKind::Adt(AdtId(i), Substitution::from1(interner, ty)).intern(interner),
+    )
+}
+
+#[bench]
+fn intern_existing_tys(bencher: &mut Bencher) {
+    let arenas = Arenas::new();
+    let interner = ArenaInterner::new(&arenas);
+    nested_ty(interner, 100);
+    bencher.iter(|| nested_ty(interner, 100));
+}
+
+#[bench]
+fn intern_new_tys(bencher: &mut Bencher) {
+    bencher.iter(|| {
+        let arenas = Arenas::new();
+        let interner = ArenaInterner::new(&arenas);
+        nested_ty(interner, 100);
+    });
+}
+
+#[bench]
+fn compare_deep_tys(bencher: &mut Bencher) {
+    let arenas = Arenas::new();
+    let interner = ArenaInterner::new(&arenas);
+    let a = nested_ty(interner, 1000);
+    let b = nested_ty(interner, 1000);
+    bencher.iter(|| test::black_box(&a) == test::black_box(&b));
+}
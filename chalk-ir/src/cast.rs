@@ -1,7 +1,7 @@
 //! Upcasts, to avoid writing out wrapper types.
 
 use crate::*;
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 /// The `Cast` trait is used to make annoying upcasts between
 /// logically equivalent types that imply wrappers. For example, one
@@ -142,6 +142,18 @@ impl<I: Interner> CastTo<DomainGoal<I>> for Normalize<I> {
     }
 }
 
+impl<I: Interner> CastTo<DomainGoal<I>> for FnPtrCoercion<I> {
+    fn cast_to(self, _interner: I) -> DomainGoal<I> {
+        DomainGoal::FnPtrCoercion(self)
+    }
+}
+
+impl<I: Interner> CastTo<DomainGoal<I>> for CustomPredicate<I> {
+    fn cast_to(self, _interner: I) -> DomainGoal<I> {
+        DomainGoal::Custom(self)
+    }
+}
+
 impl<I: Interner> CastTo<DomainGoal<I>> for WellFormed<I> {
     fn cast_to(self, _interner: I) -> DomainGoal<I> {
         DomainGoal::WellFormed(self)
@@ -137,6 +137,9 @@ where
                     (TyKind::Foreign(foreign_ty_a), TyKind::Foreign(foreign_ty_b)) => {
                         foreign_ty_a == foreign_ty_b
                     }
+                    (TyKind::Custom(custom_a), TyKind::Custom(custom_b)) => interner
+                        .zip_custom_tys(self, variance, custom_a, custom_b)
+                        .is_ok(),
                     (TyKind::Error, TyKind::Error) => true,
 
                     _ => true,
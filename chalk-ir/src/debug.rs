@@ -1,6 +1,7 @@
 //! Debug impls for types.
 
-use std::fmt::{self, Debug, Display, Error, Formatter};
+use alloc::format;
+use core::fmt::{self, Debug, Display, Error, Formatter};
 
 use super::*;
 
@@ -38,26 +39,26 @@ impl<I: Interner> Debug for AssocTypeId<I> {
 }
 
 impl<I: Interner> Debug for FnDefId<I> {
-    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> core::fmt::Result {
         I::debug_fn_def_id(*self, fmt).unwrap_or_else(|| write!(fmt, "FnDefId({:?})", self.0))
     }
 }
 
 impl<I: Interner> Debug for ClosureId<I> {
-    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> core::fmt::Result {
         I::debug_closure_id(*self, fmt).unwrap_or_else(|| write!(fmt, "ClosureId({:?})", self.0))
     }
 }
 
 impl<I: Interner> Debug for GeneratorId<I> {
-    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> core::fmt::Result {
         I::debug_generator_id(*self, fmt)
             .unwrap_or_else(|| write!(fmt, "GeneratorId({:?})", self.0))
     }
 }
 
 impl<I: Interner> Debug for ForeignDefId<I> {
-    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> core::fmt::Result {
         I::debug_foreign_def_id(*self, fmt)
             .unwrap_or_else(|| write!(fmt, "ForeignDefId({:?})", self.0))
     }
@@ -158,6 +159,13 @@ impl<I: Interner> Debug for ProjectionTy<I> {
     }
 }
 
+impl<I: Interner> Debug for InherentProjectionTy<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        I::debug_alias(&AliasTy::Inherent(self.clone()), fmt)
+            .unwrap_or_else(|| write!(fmt, "InherentProjectionTy(?)"))
+    }
+}
+
 impl<I: Interner> Debug for OpaqueTy<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         I::debug_opaque_ty(self, fmt).unwrap_or_else(|| {
@@ -240,6 +248,7 @@ impl<I: Interner> Debug for TyKind<I> {
             }
             TyKind::Foreign(foreign_ty) => write!(fmt, "{:?}", foreign_ty),
             TyKind::Error => write!(fmt, "{{error}}"),
+            TyKind::Custom(custom_ty) => write!(fmt, "{:?}", custom_ty),
         }
     }
 }
@@ -550,6 +559,7 @@ impl<'a, I: Interner> Debug for TyKindDebug<'a, I> {
             }
             TyKind::Foreign(foreign_ty) => write!(fmt, "{:?}", foreign_ty,),
             TyKind::Error => write!(fmt, "{{error}}"),
+            TyKind::Custom(custom_ty) => write!(fmt, "{:?}", custom_ty),
         }
     }
 }
@@ -612,7 +622,7 @@ impl Debug for PlaceholderIndex {
 
 impl<I: Interner> TraitRef<I> {
     /// Returns a "Debuggable" type that prints like `P0 as Trait<P1..>`.
-    pub fn with_as(&self) -> impl std::fmt::Debug + '_ {
+    pub fn with_as(&self) -> impl core::fmt::Debug + '_ {
         SeparatorTraitRef {
             trait_ref: self,
             separator: " as ",
@@ -620,7 +630,7 @@ impl<I: Interner> TraitRef<I> {
     }
 
     /// Returns a "Debuggable" type that prints like `P0: Trait<P1..>`.
-    pub fn with_colon(&self) -> impl std::fmt::Debug + '_ {
+    pub fn with_colon(&self) -> impl core::fmt::Debug + '_ {
         SeparatorTraitRef {
             trait_ref: self,
             separator: ": ",
@@ -780,6 +790,18 @@ impl<I: Interner> Debug for Normalize<I> {
     }
 }
 
+impl<I: Interner> Debug for CustomPredicate<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "{:?}", self.0)
+    }
+}
+
+impl<I: Interner> Debug for FnPtrCoercion<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "FnPtrCoercion({:?}, {:?})", self.source, self.target)
+    }
+}
+
 impl<I: Interner> Debug for AliasEq<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         write!(fmt, "AliasEq({:?} = {:?})", self.alias, self.ty)
@@ -832,6 +854,8 @@ impl<I: Interner> Debug for DomainGoal<I> {
             DomainGoal::DownstreamType(n) => write!(fmt, "DownstreamType({:?})", n),
             DomainGoal::Reveal => write!(fmt, "Reveal"),
             DomainGoal::ObjectSafe(n) => write!(fmt, "ObjectSafe({:?})", n),
+            DomainGoal::FnPtrCoercion(n) => write!(fmt, "{:?}", n),
+            DomainGoal::Custom(n) => write!(fmt, "{:?}", n),
         }
     }
 }
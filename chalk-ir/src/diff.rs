@@ -0,0 +1,159 @@
+//! Structural "expected/found" diffs between types, for diagnostics.
+
+use crate::*;
+
+/// The first point at which two types differ; see `diff_tys`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TyDiff<I: Interner> {
+    /// Path from the root of the types to the differing component. Each
+    /// element is an index into the components of the type at that
+    /// level: the generic arguments of ADTs, tuples, closures, and so
+    /// on (in order), the lifetime and then the referent of a
+    /// reference, or the element type and then the length of an array.
+    pub path: Vec<usize>,
+    /// The component of the expected type.
+    pub expected: GenericArg<I>,
+    /// The component of the found type.
+    pub found: GenericArg<I>,
+}
+
+/// Compares two types structurally and returns the first (outermost,
+/// leftmost) pair of components where they differ, or `None` if the
+/// types are equal. This is intended to be used after unification of
+/// `expected` and `found` failed, to point the user at the part of the
+/// types that did not match.
+///
+/// Inference variables are compared as-is, so callers will usually
+/// want to resolve them before computing the diff.
+pub fn diff_tys<I: Interner>(interner: I, expected: &Ty<I>, found: &Ty<I>) -> Option<TyDiff<I>> {
+    let mut path = vec![];
+    let (expected, found) = diff_generic_args(
+        interner,
+        &mut path,
+        &expected.clone().cast(interner),
+        &found.clone().cast(interner),
+    )?;
+    Some(TyDiff {
+        path,
+        expected,
+        found,
+    })
+}
+
+fn diff_generic_args<I: Interner>(
+    interner: I,
+    path: &mut Vec<usize>,
+    expected: &GenericArg<I>,
+    found: &GenericArg<I>,
+) -> Option<(GenericArg<I>, GenericArg<I>)> {
+    if expected == found {
+        return None;
+    }
+
+    let components = match (expected.ty(interner), found.ty(interner)) {
+        (Some(a), Some(b)) => matching_components(interner, a, b),
+        _ => None,
+    };
+
+    if let Some((components_a, components_b)) = components {
+        for (i, (a, b)) in components_a.iter().zip(&components_b).enumerate() {
+            path.push(i);
+            if let Some(diff) = diff_generic_args(interner, path, a, b) {
+                return Some(diff);
+            }
+            path.pop();
+        }
+    }
+
+    // Either the outermost structure differs, or it is the same but the
+    // difference is not in any component (e.g., the binders of a function
+    // pointer); either way, this is where the types differ.
+    Some((expected.clone(), found.clone()))
+}
+
+type Components<I> = Vec<GenericArg<I>>;
+
+/// If `a` and `b` have the same outermost structure, returns their
+/// components, in the order documented on `TyDiff::path`.
+fn matching_components<I: Interner>(
+    interner: I,
+    a: &Ty<I>,
+    b: &Ty<I>,
+) -> Option<(Components<I>, Components<I>)> {
+    let substs = |a: &Substitution<I>, b: &Substitution<I>| {
+        Some((a.as_slice(interner).to_vec(), b.as_slice(interner).to_vec()))
+    };
+    match (a.kind(interner), b.kind(interner)) {
+        (TyKind::Adt(id_a, subst_a), TyKind::Adt(id_b, subst_b)) if id_a == id_b => {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::AssociatedType(id_a, subst_a), TyKind::AssociatedType(id_b, subst_b))
+            if id_a == id_b =>
+        {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::Tuple(arity_a, subst_a), TyKind::Tuple(arity_b, subst_b))
+            if arity_a == arity_b =>
+        {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::OpaqueType(id_a, subst_a), TyKind::OpaqueType(id_b, subst_b)) if id_a == id_b => {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::FnDef(id_a, subst_a), TyKind::FnDef(id_b, subst_b)) if id_a == id_b => {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::Closure(id_a, subst_a), TyKind::Closure(id_b, subst_b)) if id_a == id_b => {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::Generator(id_a, subst_a), TyKind::Generator(id_b, subst_b)) if id_a == id_b => {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::GeneratorWitness(id_a, subst_a), TyKind::GeneratorWitness(id_b, subst_b))
+            if id_a == id_b =>
+        {
+            substs(subst_a, subst_b)
+        }
+        (TyKind::Alias(AliasTy::Projection(a)), TyKind::Alias(AliasTy::Projection(b)))
+            if a.associated_ty_id == b.associated_ty_id =>
+        {
+            substs(&a.substitution, &b.substitution)
+        }
+        (TyKind::Alias(AliasTy::Inherent(a)), TyKind::Alias(AliasTy::Inherent(b)))
+            if a.associated_ty_id == b.associated_ty_id =>
+        {
+            substs(&a.substitution, &b.substitution)
+        }
+        (TyKind::Alias(AliasTy::Opaque(a)), TyKind::Alias(AliasTy::Opaque(b)))
+            if a.opaque_ty_id == b.opaque_ty_id =>
+        {
+            substs(&a.substitution, &b.substitution)
+        }
+        (TyKind::Function(a), TyKind::Function(b))
+            if a.num_binders == b.num_binders && a.sig == b.sig =>
+        {
+            substs(&a.substitution.0, &b.substitution.0)
+        }
+        (TyKind::Slice(a), TyKind::Slice(b)) => Some((
+            vec![a.clone().cast(interner)],
+            vec![b.clone().cast(interner)],
+        )),
+        (TyKind::Raw(mutbl_a, a), TyKind::Raw(mutbl_b, b)) if mutbl_a == mutbl_b => Some((
+            vec![a.clone().cast(interner)],
+            vec![b.clone().cast(interner)],
+        )),
+        (TyKind::Ref(mutbl_a, lifetime_a, a), TyKind::Ref(mutbl_b, lifetime_b, b))
+            if mutbl_a == mutbl_b =>
+        {
+            Some((
+                vec![lifetime_a.clone().cast(interner), a.clone().cast(interner)],
+                vec![lifetime_b.clone().cast(interner), b.clone().cast(interner)],
+            ))
+        }
+        (TyKind::Array(a, len_a), TyKind::Array(b, len_b)) => Some((
+            vec![a.clone().cast(interner), len_a.clone().cast(interner)],
+            vec![b.clone().cast(interner), len_b.clone().cast(interner)],
+        )),
+        _ => None,
+    }
+}
@@ -1,8 +1,11 @@
 //! Traits for transforming bits of IR.
 
 use crate::*;
-use std::fmt::Debug;
+use core::convert::Infallible;
+use core::fmt::Debug;
 
+#[cfg(all(test, feature = "bench"))]
+mod bench;
 mod binder_impls;
 mod boring_impls;
 mod in_place;
@@ -10,6 +13,7 @@ pub mod shift;
 mod subst;
 
 pub use self::shift::Shift;
+pub(crate) use self::subst::InferenceVarSubst;
 pub use self::subst::Subst;
 
 /// A "folder" is a transformer that can be used to make a copy of
@@ -54,11 +58,7 @@ pub use self::subst::Subst;
 /// ```rust,ignore
 /// let x = x.fold_with(&mut folder, 0);
 /// ```
-pub trait Folder<I: Interner> {
-    /// The type this folder returns when folding fails. This is
-    /// commonly [`NoSolution`].
-    type Error;
-
+pub trait Folder<I: Interner>: FallibleFolder<I, Error = Infallible> {
     /// Creates a `dyn` value from this folder. Unfortunately, this
     /// must be added manually to each impl of Folder; it permits the
     /// default implements below to create a `&mut dyn Folder` from
@@ -66,56 +66,309 @@ pub trait Folder<I: Interner> {
     /// method). Effectively, this limits impls of `Folder` to types
     /// for which we are able to create a dyn value (i.e., not `[T]`
     /// types).
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error>;
+    fn as_dyn(&mut self) -> &mut dyn Folder<I>;
 
     /// Top-level callback: invoked for each `Ty<I>` that is
     /// encountered when folding. By default, invokes
     /// `super_fold_with`, which will in turn invoke the more
     /// specialized folding methods below, like `fold_free_var_ty`.
-    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Result<Ty<I>, Self::Error> {
-        ty.super_fold_with(self.as_dyn(), outer_binder)
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
     }
 
     /// Top-level callback: invoked for each `Lifetime<I>` that is
     /// encountered when folding. By default, invokes
     /// `super_fold_with`, which will in turn invoke the more
     /// specialized folding methods below, like `fold_free_var_lifetime`.
-    fn fold_lifetime(
+    fn fold_lifetime(&mut self, lifetime: Lifetime<I>, outer_binder: DebruijnIndex) -> Lifetime<I> {
+        lifetime.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
+    /// Top-level callback: invoked for each `Const<I>` that is
+    /// encountered when folding. By default, invokes
+    /// `super_fold_with`, which will in turn invoke the more
+    /// specialized folding methods below, like `fold_free_var_const`.
+    fn fold_const(&mut self, constant: Const<I>, outer_binder: DebruijnIndex) -> Const<I> {
+        constant.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
+    /// Invoked for every program clause. By default, recursively folds the goals contents.
+    fn fold_program_clause(
+        &mut self,
+        clause: ProgramClause<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ProgramClause<I> {
+        clause.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
+    /// Invoked for every goal. By default, recursively folds the goals contents.
+    fn fold_goal(&mut self, goal: Goal<I>, outer_binder: DebruijnIndex) -> Goal<I> {
+        goal.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
+    /// If overridden to return true, then folding will panic if a
+    /// free variable is encountered. This should be done if free
+    /// type/lifetime variables are not expected.
+    fn forbid_free_vars(&self) -> bool {
+        false
+    }
+
+    /// Invoked for `TyKind::BoundVar` instances that are not bound
+    /// within the type being folded over:
+    ///
+    /// - `depth` is the depth of the `TyKind::BoundVar`; this has
+    ///   been adjusted to account for binders in scope.
+    /// - `binders` is the number of binders in scope.
+    ///
+    /// This should return a type suitable for a context with
+    /// `binders` in scope.
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) -> Ty<I> {
+        if Folder::forbid_free_vars(self) {
+            panic!(
+                "unexpected free variable with depth `{:?}` with outer binder {:?}",
+                bound_var, outer_binder
+            )
+        } else {
+            let bound_var = bound_var.shifted_in_from(outer_binder);
+            TyKind::<I>::BoundVar(bound_var).intern(Folder::interner(self))
+        }
+    }
+
+    /// As `fold_free_var_ty`, but for lifetimes.
+    fn fold_free_var_lifetime(
+        &mut self,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        if Folder::forbid_free_vars(self) {
+            panic!(
+                "unexpected free variable with depth `{:?}` with outer binder {:?}",
+                bound_var, outer_binder
+            )
+        } else {
+            let bound_var = bound_var.shifted_in_from(outer_binder);
+            LifetimeData::<I>::BoundVar(bound_var).intern(Folder::interner(self))
+        }
+    }
+
+    /// As `fold_free_var_ty`, but for constants.
+    fn fold_free_var_const(
+        &mut self,
+        ty: Ty<I>,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        if Folder::forbid_free_vars(self) {
+            panic!(
+                "unexpected free variable with depth `{:?}` with outer binder {:?}",
+                bound_var, outer_binder
+            )
+        } else {
+            let bound_var = bound_var.shifted_in_from(outer_binder);
+            ConstData {
+                ty: ty.fold_with(Folder::as_dyn(self), outer_binder),
+                value: ConstValue::<I>::BoundVar(bound_var),
+            }
+            .intern(Folder::interner(self))
+        }
+    }
+
+    /// If overridden to return true, we will panic when a free
+    /// placeholder type/lifetime/const is encountered.
+    fn forbid_free_placeholders(&self) -> bool {
+        false
+    }
+
+    /// Invoked for each occurrence of a placeholder type; these are
+    /// used when we instantiate binders universally. Returns a type
+    /// to use instead, which should be suitably shifted to account
+    /// for `binders`.
+    ///
+    /// - `universe` is the universe of the `TypeName::ForAll` that was found
+    /// - `binders` is the number of binders in scope
+    #[allow(unused_variables)]
+    fn fold_free_placeholder_ty(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Ty<I> {
+        if Folder::forbid_free_placeholders(self) {
+            panic!("unexpected placeholder type `{:?}`", universe)
+        } else {
+            universe.to_ty::<I>(Folder::interner(self))
+        }
+    }
+
+    /// As with `fold_free_placeholder_ty`, but for lifetimes.
+    #[allow(unused_variables)]
+    fn fold_free_placeholder_lifetime(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        if Folder::forbid_free_placeholders(self) {
+            panic!("unexpected placeholder lifetime `{:?}`", universe)
+        } else {
+            universe.to_lifetime(Folder::interner(self))
+        }
+    }
+
+    /// As with `fold_free_placeholder_ty`, but for constants.
+    #[allow(unused_variables)]
+    fn fold_free_placeholder_const(
+        &mut self,
+        ty: Ty<I>,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        if Folder::forbid_free_placeholders(self) {
+            panic!("unexpected placeholder const `{:?}`", universe)
+        } else {
+            universe.to_const(
+                Folder::interner(self),
+                ty.fold_with(Folder::as_dyn(self), outer_binder),
+            )
+        }
+    }
+
+    /// If overridden to return true, inference variables will trigger
+    /// panics when folded. Used when inference variables are
+    /// unexpected.
+    fn forbid_inference_vars(&self) -> bool {
+        false
+    }
+
+    /// Invoked for each occurrence of a inference type; these are
+    /// used when we instantiate binders universally. Returns a type
+    /// to use instead, which should be suitably shifted to account
+    /// for `binders`.
+    ///
+    /// - `universe` is the universe of the `TypeName::ForAll` that was found
+    /// - `binders` is the number of binders in scope
+    #[allow(unused_variables)]
+    fn fold_inference_ty(
+        &mut self,
+        var: InferenceVar,
+        kind: TyVariableKind,
+        outer_binder: DebruijnIndex,
+    ) -> Ty<I> {
+        if Folder::forbid_inference_vars(self) {
+            panic!("unexpected inference type `{:?}`", var)
+        } else {
+            var.to_ty(Folder::interner(self), kind)
+        }
+    }
+
+    /// As with `fold_inference_ty`, but for lifetimes.
+    #[allow(unused_variables)]
+    fn fold_inference_lifetime(
+        &mut self,
+        var: InferenceVar,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        if Folder::forbid_inference_vars(self) {
+            panic!("unexpected inference lifetime `'{:?}`", var)
+        } else {
+            var.to_lifetime(Folder::interner(self))
+        }
+    }
+
+    /// As with `fold_inference_ty`, but for constants.
+    #[allow(unused_variables)]
+    fn fold_inference_const(
+        &mut self,
+        ty: Ty<I>,
+        var: InferenceVar,
+        outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        if Folder::forbid_inference_vars(self) {
+            panic!("unexpected inference const `{:?}`", var)
+        } else {
+            var.to_const(
+                Folder::interner(self),
+                ty.fold_with(Folder::as_dyn(self), outer_binder),
+            )
+        }
+    }
+
+    /// Gets the interner that is being folded from.
+    fn interner(&self) -> I;
+}
+
+/// A folder that may fail. This is the same as [`Folder`], except that
+/// each callback returns a `Result` (and is prefixed with `try_`), so
+/// the folding stops at the first error. Fallible folders are applied
+/// with `Fold::try_fold_with`.
+///
+/// Every `Folder` is also a `FallibleFolder` that never fails; rather
+/// than implementing this trait by hand for infallible folders, derive
+/// it with `#[derive(FallibleFolder)]`.
+pub trait FallibleFolder<I: Interner> {
+    /// The type this folder returns when folding fails. This is
+    /// commonly [`NoSolution`].
+    type Error;
+
+    /// Creates a `dyn` value from this folder. Unfortunately, this
+    /// must be added manually to each impl of Folder; it permits the
+    /// default implements below to create a `&mut dyn Folder` from
+    /// `Self` without knowing what `Self` is (by invoking this
+    /// method). Effectively, this limits impls of `Folder` to types
+    /// for which we are able to create a dyn value (i.e., not `[T]`
+    /// types).
+    fn as_dyn(&mut self) -> &mut dyn FallibleFolder<I, Error = Self::Error>;
+
+    /// Top-level callback: invoked for each `Ty<I>` that is
+    /// encountered when folding. By default, invokes
+    /// `try_super_fold_with`, which will in turn invoke the more
+    /// specialized folding methods below, like `try_fold_free_var_ty`.
+    fn try_fold_ty(
+        &mut self,
+        ty: Ty<I>,
+        outer_binder: DebruijnIndex,
+    ) -> Result<Ty<I>, Self::Error> {
+        ty.try_super_fold_with(self.as_dyn(), outer_binder)
+    }
+
+    /// Top-level callback: invoked for each `Lifetime<I>` that is
+    /// encountered when folding. By default, invokes
+    /// `try_super_fold_with`, which will in turn invoke the more
+    /// specialized folding methods below, like `try_fold_free_var_lifetime`.
+    fn try_fold_lifetime(
         &mut self,
         lifetime: Lifetime<I>,
         outer_binder: DebruijnIndex,
     ) -> Result<Lifetime<I>, Self::Error> {
-        lifetime.super_fold_with(self.as_dyn(), outer_binder)
+        lifetime.try_super_fold_with(self.as_dyn(), outer_binder)
     }
 
     /// Top-level callback: invoked for each `Const<I>` that is
     /// encountered when folding. By default, invokes
-    /// `super_fold_with`, which will in turn invoke the more
-    /// specialized folding methods below, like `fold_free_var_const`.
-    fn fold_const(
+    /// `try_super_fold_with`, which will in turn invoke the more
+    /// specialized folding methods below, like `try_fold_free_var_const`.
+    fn try_fold_const(
         &mut self,
         constant: Const<I>,
         outer_binder: DebruijnIndex,
     ) -> Result<Const<I>, Self::Error> {
-        constant.super_fold_with(self.as_dyn(), outer_binder)
+        constant.try_super_fold_with(self.as_dyn(), outer_binder)
     }
 
     /// Invoked for every program clause. By default, recursively folds the goals contents.
-    fn fold_program_clause(
+    fn try_fold_program_clause(
         &mut self,
         clause: ProgramClause<I>,
         outer_binder: DebruijnIndex,
     ) -> Result<ProgramClause<I>, Self::Error> {
-        clause.super_fold_with(self.as_dyn(), outer_binder)
+        clause.try_super_fold_with(self.as_dyn(), outer_binder)
     }
 
     /// Invoked for every goal. By default, recursively folds the goals contents.
-    fn fold_goal(
+    fn try_fold_goal(
         &mut self,
         goal: Goal<I>,
         outer_binder: DebruijnIndex,
     ) -> Result<Goal<I>, Self::Error> {
-        goal.super_fold_with(self.as_dyn(), outer_binder)
+        goal.try_super_fold_with(self.as_dyn(), outer_binder)
     }
 
     /// If overridden to return true, then folding will panic if a
@@ -134,7 +387,7 @@ pub trait Folder<I: Interner> {
     ///
     /// This should return a type suitable for a context with
     /// `binders` in scope.
-    fn fold_free_var_ty(
+    fn try_fold_free_var_ty(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
@@ -150,8 +403,8 @@ pub trait Folder<I: Interner> {
         }
     }
 
-    /// As `fold_free_var_ty`, but for lifetimes.
-    fn fold_free_var_lifetime(
+    /// As `try_fold_free_var_ty`, but for lifetimes.
+    fn try_fold_free_var_lifetime(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
@@ -167,8 +420,8 @@ pub trait Folder<I: Interner> {
         }
     }
 
-    /// As `fold_free_var_ty`, but for constants.
-    fn fold_free_var_const(
+    /// As `try_fold_free_var_ty`, but for constants.
+    fn try_fold_free_var_const(
         &mut self,
         ty: Ty<I>,
         bound_var: BoundVar,
@@ -182,7 +435,7 @@ pub trait Folder<I: Interner> {
         } else {
             let bound_var = bound_var.shifted_in_from(outer_binder);
             Ok(ConstData {
-                ty: ty.fold_with(self.as_dyn(), outer_binder)?,
+                ty: ty.try_fold_with(self.as_dyn(), outer_binder)?,
                 value: ConstValue::<I>::BoundVar(bound_var),
             }
             .intern(self.interner()))
@@ -203,7 +456,7 @@ pub trait Folder<I: Interner> {
     /// - `universe` is the universe of the `TypeName::ForAll` that was found
     /// - `binders` is the number of binders in scope
     #[allow(unused_variables)]
-    fn fold_free_placeholder_ty(
+    fn try_fold_free_placeholder_ty(
         &mut self,
         universe: PlaceholderIndex,
         outer_binder: DebruijnIndex,
@@ -215,9 +468,9 @@ pub trait Folder<I: Interner> {
         }
     }
 
-    /// As with `fold_free_placeholder_ty`, but for lifetimes.
+    /// As with `try_fold_free_placeholder_ty`, but for lifetimes.
     #[allow(unused_variables)]
-    fn fold_free_placeholder_lifetime(
+    fn try_fold_free_placeholder_lifetime(
         &mut self,
         universe: PlaceholderIndex,
         outer_binder: DebruijnIndex,
@@ -229,9 +482,9 @@ pub trait Folder<I: Interner> {
         }
     }
 
-    /// As with `fold_free_placeholder_ty`, but for constants.
+    /// As with `try_fold_free_placeholder_ty`, but for constants.
     #[allow(unused_variables)]
-    fn fold_free_placeholder_const(
+    fn try_fold_free_placeholder_const(
         &mut self,
         ty: Ty<I>,
         universe: PlaceholderIndex,
@@ -240,7 +493,10 @@ pub trait Folder<I: Interner> {
         if self.forbid_free_placeholders() {
             panic!("unexpected placeholder const `{:?}`", universe)
         } else {
-            Ok(universe.to_const(self.interner(), ty.fold_with(self.as_dyn(), outer_binder)?))
+            Ok(universe.to_const(
+                self.interner(),
+                ty.try_fold_with(self.as_dyn(), outer_binder)?,
+            ))
         }
     }
 
@@ -259,7 +515,7 @@ pub trait Folder<I: Interner> {
     /// - `universe` is the universe of the `TypeName::ForAll` that was found
     /// - `binders` is the number of binders in scope
     #[allow(unused_variables)]
-    fn fold_inference_ty(
+    fn try_fold_inference_ty(
         &mut self,
         var: InferenceVar,
         kind: TyVariableKind,
@@ -272,9 +528,9 @@ pub trait Folder<I: Interner> {
         }
     }
 
-    /// As with `fold_inference_ty`, but for lifetimes.
+    /// As with `try_fold_inference_ty`, but for lifetimes.
     #[allow(unused_variables)]
-    fn fold_inference_lifetime(
+    fn try_fold_inference_lifetime(
         &mut self,
         var: InferenceVar,
         outer_binder: DebruijnIndex,
@@ -286,9 +542,9 @@ pub trait Folder<I: Interner> {
         }
     }
 
-    /// As with `fold_inference_ty`, but for constants.
+    /// As with `try_fold_inference_ty`, but for constants.
     #[allow(unused_variables)]
-    fn fold_inference_const(
+    fn try_fold_inference_const(
         &mut self,
         ty: Ty<I>,
         var: InferenceVar,
@@ -297,7 +553,10 @@ pub trait Folder<I: Interner> {
         if self.forbid_inference_vars() {
             panic!("unexpected inference const `{:?}`", var)
         } else {
-            Ok(var.to_const(self.interner(), ty.fold_with(self.as_dyn(), outer_binder)?))
+            Ok(var.to_const(
+                self.interner(),
+                ty.try_fold_with(self.as_dyn(), outer_binder)?,
+            ))
         }
     }
 
@@ -323,11 +582,20 @@ pub trait Fold<I: Interner>: Debug {
     /// folder. Typically `binders` starts as 0, but is adjusted when
     /// we encounter `Binders<T>` in the IR or other similar
     /// constructs.
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E>;
+
+    /// As `try_fold_with`, but for folders that cannot fail.
+    fn fold_with(self, folder: &mut dyn Folder<I>, outer_binder: DebruijnIndex) -> Self::Result
+    where
+        Self: Sized,
+    {
+        self.try_fold_with(FallibleFolder::as_dyn(folder), outer_binder)
+            .unwrap_or_else(|e| match e {})
+    }
 }
 
 /// For types where "fold" invokes a callback on the `Folder`, the
@@ -335,11 +603,24 @@ pub trait Fold<I: Interner>: Debug {
 /// the contents of the type.
 pub trait SuperFold<I: Interner>: Fold<I> {
     /// Recursively folds the value.
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E>;
+
+    /// As `try_super_fold_with`, but for folders that cannot fail.
+    fn super_fold_with(
+        self,
+        folder: &mut dyn Folder<I>,
+        outer_binder: DebruijnIndex,
+    ) -> Self::Result
+    where
+        Self: Sized,
+    {
+        self.try_super_fold_with(FallibleFolder::as_dyn(folder), outer_binder)
+            .unwrap_or_else(|e| match e {})
+    }
 }
 
 /// "Folding" a type invokes the `fold_ty` method on the folder; this
@@ -348,12 +629,12 @@ pub trait SuperFold<I: Interner>: Fold<I> {
 impl<I: Interner> Fold<I> for Ty<I> {
     type Result = Ty<I>;
 
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        folder.fold_ty(self, outer_binder)
+        folder.try_fold_ty(self, outer_binder)
     }
 }
 
@@ -362,9 +643,9 @@ impl<I> SuperFold<I> for Ty<I>
 where
     I: Interner,
 {
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Ty<I>, E> {
         let interner = folder.interner();
@@ -375,7 +656,7 @@ where
                     // that we have traversed during folding;
                     // therefore, it is free. Let the folder have a
                     // crack at it.
-                    folder.fold_free_var_ty(bound_var1, outer_binder)?
+                    folder.try_fold_free_var_ty(bound_var1, outer_binder)?
                 } else {
                     // This variable was bound within the binders that
                     // we folded over, so just return a bound
@@ -383,85 +664,92 @@ where
                     self
                 }
             }
-            TyKind::Dyn(clauses) => TyKind::Dyn(clauses.clone().fold_with(folder, outer_binder)?)
-                .intern(folder.interner()),
+            TyKind::Dyn(clauses) => {
+                TyKind::Dyn(clauses.clone().try_fold_with(folder, outer_binder)?)
+                    .intern(folder.interner())
+            }
             TyKind::InferenceVar(var, kind) => {
-                folder.fold_inference_ty(*var, *kind, outer_binder)?
+                folder.try_fold_inference_ty(*var, *kind, outer_binder)?
             }
-            TyKind::Placeholder(ui) => folder.fold_free_placeholder_ty(*ui, outer_binder)?,
-            TyKind::Alias(proj) => TyKind::Alias(proj.clone().fold_with(folder, outer_binder)?)
-                .intern(folder.interner()),
-            TyKind::Function(fun) => TyKind::Function(fun.clone().fold_with(folder, outer_binder)?)
+            TyKind::Placeholder(ui) => folder.try_fold_free_placeholder_ty(*ui, outer_binder)?,
+            TyKind::Alias(proj) => TyKind::Alias(proj.clone().try_fold_with(folder, outer_binder)?)
                 .intern(folder.interner()),
+            TyKind::Function(fun) => {
+                TyKind::Function(fun.clone().try_fold_with(folder, outer_binder)?)
+                    .intern(folder.interner())
+            }
             TyKind::Adt(id, substitution) => TyKind::Adt(
-                id.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                id.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::AssociatedType(assoc_ty, substitution) => TyKind::AssociatedType(
-                assoc_ty.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                assoc_ty.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
-            TyKind::Scalar(scalar) => {
-                TyKind::Scalar(scalar.fold_with(folder, outer_binder)?).intern(folder.interner())
-            }
+            TyKind::Scalar(scalar) => TyKind::Scalar(scalar.try_fold_with(folder, outer_binder)?)
+                .intern(folder.interner()),
             TyKind::Str => TyKind::Str.intern(folder.interner()),
             TyKind::Tuple(arity, substitution) => TyKind::Tuple(
                 *arity,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::OpaqueType(opaque_ty, substitution) => TyKind::OpaqueType(
-                opaque_ty.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                opaque_ty.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Slice(substitution) => {
-                TyKind::Slice(substitution.clone().fold_with(folder, outer_binder)?)
+                TyKind::Slice(substitution.clone().try_fold_with(folder, outer_binder)?)
                     .intern(folder.interner())
             }
             TyKind::FnDef(fn_def, substitution) => TyKind::FnDef(
-                fn_def.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                fn_def.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Ref(mutability, lifetime, ty) => TyKind::Ref(
-                mutability.fold_with(folder, outer_binder)?,
-                lifetime.clone().fold_with(folder, outer_binder)?,
-                ty.clone().fold_with(folder, outer_binder)?,
+                mutability.try_fold_with(folder, outer_binder)?,
+                lifetime.clone().try_fold_with(folder, outer_binder)?,
+                ty.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Raw(mutability, ty) => TyKind::Raw(
-                mutability.fold_with(folder, outer_binder)?,
-                ty.clone().fold_with(folder, outer_binder)?,
+                mutability.try_fold_with(folder, outer_binder)?,
+                ty.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Never => TyKind::Never.intern(folder.interner()),
             TyKind::Array(ty, const_) => TyKind::Array(
-                ty.clone().fold_with(folder, outer_binder)?,
-                const_.clone().fold_with(folder, outer_binder)?,
+                ty.clone().try_fold_with(folder, outer_binder)?,
+                const_.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Closure(id, substitution) => TyKind::Closure(
-                id.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                id.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Generator(id, substitution) => TyKind::Generator(
-                id.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                id.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::GeneratorWitness(id, substitution) => TyKind::GeneratorWitness(
-                id.fold_with(folder, outer_binder)?,
-                substitution.clone().fold_with(folder, outer_binder)?,
+                id.try_fold_with(folder, outer_binder)?,
+                substitution.clone().try_fold_with(folder, outer_binder)?,
             )
             .intern(folder.interner()),
             TyKind::Foreign(id) => {
-                TyKind::Foreign(id.fold_with(folder, outer_binder)?).intern(folder.interner())
+                TyKind::Foreign(id.try_fold_with(folder, outer_binder)?).intern(folder.interner())
             }
             TyKind::Error => TyKind::Error.intern(folder.interner()),
+            TyKind::Custom(custom_ty) => {
+                TyKind::Custom(interner.fold_custom_ty(custom_ty, folder, outer_binder)?)
+                    .intern(folder.interner())
+            }
         })
     }
 }
@@ -472,12 +760,12 @@ where
 impl<I: Interner> Fold<I> for Lifetime<I> {
     type Result = Lifetime<I>;
 
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        folder.fold_lifetime(self, outer_binder)
+        folder.try_fold_lifetime(self, outer_binder)
     }
 }
 
@@ -485,9 +773,9 @@ impl<I> SuperFold<I> for Lifetime<I>
 where
     I: Interner,
 {
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Lifetime<I>, E> {
         let interner = folder.interner();
@@ -498,7 +786,7 @@ where
                     // that we have traversed during folding;
                     // therefore, it is free. Let the folder have a
                     // crack at it.
-                    folder.fold_free_var_lifetime(bound_var1, outer_binder)
+                    folder.try_fold_free_var_lifetime(bound_var1, outer_binder)
                 } else {
                     // This variable was bound within the binders that
                     // we folded over, so just return a bound
@@ -506,9 +794,11 @@ where
                     Ok(self)
                 }
             }
-            LifetimeData::InferenceVar(var) => folder.fold_inference_lifetime(*var, outer_binder),
+            LifetimeData::InferenceVar(var) => {
+                folder.try_fold_inference_lifetime(*var, outer_binder)
+            }
             LifetimeData::Placeholder(universe) => {
-                folder.fold_free_placeholder_lifetime(*universe, outer_binder)
+                folder.try_fold_free_placeholder_lifetime(*universe, outer_binder)
             }
             LifetimeData::Static => Ok(LifetimeData::<I>::Static.intern(folder.interner())),
             LifetimeData::Empty(ui) => Ok(LifetimeData::<I>::Empty(*ui).intern(folder.interner())),
@@ -524,12 +814,12 @@ where
 impl<I: Interner> Fold<I> for Const<I> {
     type Result = Const<I>;
 
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        folder.fold_const(self, outer_binder)
+        folder.try_fold_const(self, outer_binder)
     }
 }
 
@@ -537,27 +827,27 @@ impl<I> SuperFold<I> for Const<I>
 where
     I: Interner,
 {
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Const<I>, E> {
         let interner = folder.interner();
         let ConstData { ref ty, ref value } = self.data(interner);
-        let mut fold_ty = || ty.clone().fold_with(folder, outer_binder);
+        let mut fold_ty = || ty.clone().try_fold_with(folder, outer_binder);
         match value {
             ConstValue::BoundVar(bound_var) => {
                 if let Some(bound_var1) = bound_var.shifted_out_to(outer_binder) {
-                    folder.fold_free_var_const(ty.clone(), bound_var1, outer_binder)
+                    folder.try_fold_free_var_const(ty.clone(), bound_var1, outer_binder)
                 } else {
                     Ok(self)
                 }
             }
             ConstValue::InferenceVar(var) => {
-                folder.fold_inference_const(ty.clone(), *var, outer_binder)
+                folder.try_fold_inference_const(ty.clone(), *var, outer_binder)
             }
             ConstValue::Placeholder(universe) => {
-                folder.fold_free_placeholder_const(ty.clone(), *universe, outer_binder)
+                folder.try_fold_free_placeholder_const(ty.clone(), *universe, outer_binder)
             }
             ConstValue::Concrete(ev) => Ok(ConstData {
                 ty: fold_ty()?,
@@ -570,25 +860,43 @@ where
     }
 }
 
+/// Custom predicates are folded by the interner.
+impl<I: Interner> Fold<I> for CustomPredicate<I> {
+    type Result = CustomPredicate<I>;
+
+    fn try_fold_with<E>(
+        self,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
+        outer_binder: DebruijnIndex,
+    ) -> Result<Self::Result, E> {
+        let interner = folder.interner();
+        Ok(CustomPredicate(interner.fold_custom_predicate(
+            &self.0,
+            folder,
+            outer_binder,
+        )?))
+    }
+}
+
 /// Folding a goal invokes the `fold_goal` callback (which will, by
 /// default, invoke super-fold).
 impl<I: Interner> Fold<I> for Goal<I> {
     type Result = Goal<I>;
 
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        folder.fold_goal(self, outer_binder)
+        folder.try_fold_goal(self, outer_binder)
     }
 }
 
 /// Superfold folds recursively.
 impl<I: Interner> SuperFold<I> for Goal<I> {
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
@@ -596,7 +904,7 @@ impl<I: Interner> SuperFold<I> for Goal<I> {
             interner,
             self.data(interner)
                 .clone()
-                .fold_with(folder, outer_binder)?,
+                .try_fold_with(folder, outer_binder)?,
         ))
     }
 }
@@ -607,11 +915,11 @@ impl<I: Interner> SuperFold<I> for Goal<I> {
 impl<I: Interner> Fold<I> for ProgramClause<I> {
     type Result = ProgramClause<I>;
 
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        folder.fold_program_clause(self, outer_binder)
+        folder.try_fold_program_clause(self, outer_binder)
     }
 }
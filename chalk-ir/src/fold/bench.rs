@@ -0,0 +1,78 @@
+//! Benchmarks for substituting into and shifting large canonical goals,
+//! most of which cannot contain the variables being replaced.
+
+extern crate test;
+use self::test::Bencher;
+
+use crate::arena::tests::TestTypes;
+use crate::arena::{ArenaInterner, Arenas};
+use crate::fold::Shift;
+use crate::*;
+
+type I<'a> = ArenaInterner<'a, TestTypes>;
+
+/// Builds `for<'a> fn(A0<'a, A1<'a, ...<'a, An>...>>)`, which is large
+/// and mentions bound variables, but none that are free.
+fn closed_fn_ty(interner: I<'_>, depth: u32) -> Ty<I<'_>> {
+    let lifetime =
+        LifetimeData::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(interner);
+    let nested = (0..depth).rev().fold(
+        TyKind::Tuple(0, Substitution::empty(interner)).intern(interner),
+        |ty, i| {
+            let args: [GenericArg<I<'_>>; 2] = [lifetime.cast(interner), ty.cast(interner)];
+            TyKind::Adt(AdtId(i), Substitution::from_iter(interner, args)).intern(interner)
+        },
+    );
+    TyKind::Function(FnPointer {
+        num_binders: 1,
+        sig: FnSig {
+            abi: (),
+            safety: Safety::Safe,
+            variadic: false,
+        },
+        substitution: FnSubst(Substitution::from1(interner, nested)),
+    })
+    .intern(interner)
+}
+
+/// Builds the canonical goal `for<T> Implemented(T: Trait<F, .., F>)`,
+/// where each `F` is a distinct `closed_fn_ty`.
+fn canonical_goal(interner: I<'_>, width: u32) -> Canonical<Goal<I<'_>>> {
+    let self_ty = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(interner);
+    let args = (0..width).map(|i| closed_fn_ty(interner, 50 + i).cast(interner));
+    let trait_ref = TraitRef {
+        trait_id: TraitId(0),
+        substitution: Substitution::from_iter(
+            interner,
+            Some(self_ty.cast(interner)).into_iter().chain(args),
+        ),
+    };
+    Canonical {
+        value: trait_ref.cast(interner),
+        binders: CanonicalVarKinds::from1(
+            interner,
+            CanonicalVarKind::new(
+                VariableKind::Ty(TyVariableKind::General),
+                UniverseIndex::ROOT,
+            ),
+        ),
+    }
+}
+
+#[bench]
+fn substitute_canonical_goal(bencher: &mut Bencher) {
+    let arenas = Arenas::new();
+    let interner = ArenaInterner::new(&arenas);
+    let canonical = canonical_goal(interner, 20);
+    let unit = TyKind::Tuple(0, Substitution::empty(interner)).intern(interner);
+    let subst = Substitution::from1(interner, unit);
+    bencher.iter(|| subst.apply(canonical.value, interner));
+}
+
+#[bench]
+fn shift_canonical_goal(bencher: &mut Bencher) {
+    let arenas = Arenas::new();
+    let interner = ArenaInterner::new(&arenas);
+    let canonical = canonical_goal(interner, 20);
+    bencher.iter(|| canonical.value.shifted_in(interner));
+}
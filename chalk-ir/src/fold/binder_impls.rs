@@ -3,13 +3,14 @@
 //!
 //! The more interesting impls of `Fold` remain in the `fold` module.
 
+use crate::fold::FallibleFolder;
 use crate::*;
 
 impl<I: Interner> Fold<I> for FnPointer<I> {
     type Result = FnPointer<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let FnPointer {
@@ -19,7 +20,7 @@ impl<I: Interner> Fold<I> for FnPointer<I> {
         } = self;
         Ok(FnPointer {
             num_binders,
-            substitution: substitution.fold_with(folder, outer_binder.shifted_in())?,
+            substitution: substitution.try_fold_with(folder, outer_binder.shifted_in())?,
             sig: FnSig {
                 abi: sig.abi,
                 safety: sig.safety,
@@ -36,16 +37,16 @@ where
     I: Interner,
 {
     type Result = Binders<T::Result>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let Binders {
             binders: self_binders,
             value: self_value,
         } = self;
-        let value = self_value.fold_with(folder, outer_binder.shifted_in())?;
+        let value = self_value.try_fold_with(folder, outer_binder.shifted_in())?;
         let binders = VariableKinds {
             interned: self_binders.interned().clone(),
         };
@@ -60,16 +61,16 @@ where
     <T as Fold<I>>::Result: HasInterner<Interner = I>,
 {
     type Result = Canonical<T::Result>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let Canonical {
             binders: self_binders,
             value: self_value,
         } = self;
-        let value = self_value.fold_with(folder, outer_binder.shifted_in())?;
+        let value = self_value.try_fold_with(folder, outer_binder.shifted_in())?;
         let binders = CanonicalVarKinds {
             interned: self_binders.interned().clone(),
         };
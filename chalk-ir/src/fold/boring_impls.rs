@@ -5,28 +5,30 @@
 //! The more interesting impls of `Fold` remain in the `fold` module.
 
 use super::in_place;
+use crate::fold::FallibleFolder;
 use crate::*;
-use std::marker::PhantomData;
+use alloc::boxed::Box;
+use core::marker::PhantomData;
 
 impl<T: Fold<I>, I: Interner> Fold<I> for Vec<T> {
     type Result = Vec<T::Result>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        in_place::fallible_map_vec(self, |e| e.fold_with(folder, outer_binder))
+        in_place::fallible_map_vec(self, |e| e.try_fold_with(folder, outer_binder))
     }
 }
 
 impl<T: Fold<I>, I: Interner> Fold<I> for Box<T> {
     type Result = Box<T::Result>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
-        in_place::fallible_map_box(self, |e| e.fold_with(folder, outer_binder))
+        in_place::fallible_map_box(self, |e| e.try_fold_with(folder, outer_binder))
     }
 }
 
@@ -34,11 +36,11 @@ macro_rules! tuple_fold {
     ($($n:ident),*) => {
         impl<$($n: Fold<I>,)* I: Interner> Fold<I> for ($($n,)*) {
             type Result = ($($n::Result,)*);
-            fn fold_with<Error>(self, folder: &mut dyn Folder<I, Error = Error>, outer_binder: DebruijnIndex) -> Result<Self::Result, Error>
+            fn try_fold_with<Error>(self, folder: &mut dyn FallibleFolder<I, Error = Error>, outer_binder: DebruijnIndex) -> Result<Self::Result, Error>
             {
                 #[allow(non_snake_case)]
                 let ($($n),*) = self;
-                Ok(($($n.fold_with(folder, outer_binder)?,)*))
+                Ok(($($n.try_fold_with(folder, outer_binder)?,)*))
             }
         }
     }
@@ -51,23 +53,23 @@ tuple_fold!(A, B, C, D, E);
 
 impl<T: Fold<I>, I: Interner> Fold<I> for Option<T> {
     type Result = Option<T::Result>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         match self {
             None => Ok(None),
-            Some(e) => Ok(Some(e.fold_with(folder, outer_binder)?)),
+            Some(e) => Ok(Some(e.try_fold_with(folder, outer_binder)?)),
         }
     }
 }
 
 impl<I: Interner> Fold<I> for GenericArg<I> {
     type Result = GenericArg<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
@@ -75,16 +77,16 @@ impl<I: Interner> Fold<I> for GenericArg<I> {
         let data = self
             .data(interner)
             .clone()
-            .fold_with(folder, outer_binder)?;
+            .try_fold_with(folder, outer_binder)?;
         Ok(GenericArg::new(interner, data))
     }
 }
 
 impl<I: Interner> Fold<I> for Substitution<I> {
     type Result = Substitution<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
@@ -92,71 +94,71 @@ impl<I: Interner> Fold<I> for Substitution<I> {
         let folded = self
             .iter(interner)
             .cloned()
-            .map(|p| p.fold_with(folder, outer_binder));
+            .map(|p| p.try_fold_with(folder, outer_binder));
         Substitution::from_fallible(interner, folded)
     }
 }
 
 impl<I: Interner> Fold<I> for Goals<I> {
     type Result = Goals<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
         let folded = self
             .iter(interner)
             .cloned()
-            .map(|p| p.fold_with(folder, outer_binder));
+            .map(|p| p.try_fold_with(folder, outer_binder));
         Goals::from_fallible(interner, folded)
     }
 }
 
 impl<I: Interner> Fold<I> for ProgramClauses<I> {
     type Result = ProgramClauses<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
         let folded = self
             .iter(interner)
             .cloned()
-            .map(|p| p.fold_with(folder, outer_binder));
+            .map(|p| p.try_fold_with(folder, outer_binder));
         ProgramClauses::from_fallible(interner, folded)
     }
 }
 
 impl<I: Interner> Fold<I> for QuantifiedWhereClauses<I> {
     type Result = QuantifiedWhereClauses<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
         let folded = self
             .iter(interner)
             .cloned()
-            .map(|p| p.fold_with(folder, outer_binder));
+            .map(|p| p.try_fold_with(folder, outer_binder));
         QuantifiedWhereClauses::from_fallible(interner, folded)
     }
 }
 
 impl<I: Interner> Fold<I> for Constraints<I> {
     type Result = Constraints<I>;
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
     ) -> Result<Self::Result, E> {
         let interner = folder.interner();
         let folded = self
             .iter(interner)
             .cloned()
-            .map(|p| p.fold_with(folder, outer_binder));
+            .map(|p| p.try_fold_with(folder, outer_binder));
         Constraints::from_fallible(interner, folded)
     }
 }
@@ -167,11 +169,11 @@ macro_rules! copy_fold {
     ($t:ty) => {
         impl<I: Interner> $crate::fold::Fold<I> for $t {
             type Result = Self;
-            fn fold_with<E>(
+            fn try_fold_with<E>(
                 self,
-                _folder: &mut dyn ($crate::fold::Folder<I, Error = E>),
+                _folder: &mut dyn ($crate::fold::FallibleFolder<I, Error = E>),
                 _outer_binder: DebruijnIndex,
-            ) -> ::std::result::Result<Self::Result, E> {
+            ) -> ::core::result::Result<Self::Result, E> {
                 Ok(self)
             }
         }
@@ -180,6 +182,7 @@ macro_rules! copy_fold {
 
 copy_fold!(bool);
 copy_fold!(usize);
+copy_fold!(i64);
 copy_fold!(UniverseIndex);
 copy_fold!(PlaceholderIndex);
 copy_fold!(QuantifierKind);
@@ -199,11 +202,11 @@ macro_rules! id_fold {
     ($t:ident) => {
         impl<I: Interner> $crate::fold::Fold<I> for $t<I> {
             type Result = $t<I>;
-            fn fold_with<E>(
+            fn try_fold_with<E>(
                 self,
-                _folder: &mut dyn ($crate::fold::Folder<I, Error = E>),
+                _folder: &mut dyn ($crate::fold::FallibleFolder<I, Error = E>),
                 _outer_binder: DebruijnIndex,
-            ) -> ::std::result::Result<Self::Result, E> {
+            ) -> ::core::result::Result<Self::Result, E> {
                 Ok(self)
             }
         }
@@ -221,24 +224,26 @@ id_fold!(GeneratorId);
 id_fold!(ForeignDefId);
 
 impl<I: Interner> SuperFold<I> for ProgramClauseData<I> {
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
-    ) -> ::std::result::Result<Self::Result, E> {
-        Ok(ProgramClauseData(self.0.fold_with(folder, outer_binder)?))
+    ) -> ::core::result::Result<Self::Result, E> {
+        Ok(ProgramClauseData(
+            self.0.try_fold_with(folder, outer_binder)?,
+        ))
     }
 }
 
 impl<I: Interner> SuperFold<I> for ProgramClause<I> {
-    fn super_fold_with<E>(
+    fn try_super_fold_with<E>(
         self,
-        folder: &mut dyn Folder<I, Error = E>,
+        folder: &mut dyn FallibleFolder<I, Error = E>,
         outer_binder: DebruijnIndex,
-    ) -> ::std::result::Result<Self::Result, E> {
+    ) -> ::core::result::Result<Self::Result, E> {
         let clause = self.data(folder.interner()).clone();
         Ok(clause
-            .super_fold_with(folder, outer_binder)?
+            .try_super_fold_with(folder, outer_binder)?
             .intern(folder.interner()))
     }
 }
@@ -246,11 +251,11 @@ impl<I: Interner> SuperFold<I> for ProgramClause<I> {
 impl<I: Interner> Fold<I> for PhantomData<I> {
     type Result = PhantomData<I>;
 
-    fn fold_with<E>(
+    fn try_fold_with<E>(
         self,
-        _folder: &mut dyn Folder<I, Error = E>,
+        _folder: &mut dyn FallibleFolder<I, Error = E>,
         _outer_binder: DebruijnIndex,
-    ) -> ::std::result::Result<Self::Result, E> {
+    ) -> ::core::result::Result<Self::Result, E> {
         Ok(PhantomData)
     }
 }
@@ -1,7 +1,9 @@
 //! Subroutines to help implementers of `Fold` avoid unnecessary heap allocations.
 
-use std::marker::PhantomData;
-use std::{mem, ptr};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::{mem, ptr};
 
 fn is_zst<T>() -> bool {
     mem::size_of::<T>() == 0
@@ -145,6 +147,10 @@ impl<T, U> Drop for VecMappedInPlace<T, U> {
 
 #[cfg(test)]
 mod tests {
+    use alloc::boxed::Box;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec::Vec;
     use std::fmt;
     use std::sync::{Arc, Mutex};
 
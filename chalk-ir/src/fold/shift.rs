@@ -1,6 +1,6 @@
 //! Shifting of debruijn indices
 
-use super::Fold;
+use super::{FallibleFolder, Fold};
 use crate::*;
 
 /// Methods for converting debruijn indices to move values into or out
@@ -36,11 +36,10 @@ impl<T: Fold<I>, I: Interner> Shift<I> for T {
             },
             DebruijnIndex::INNERMOST,
         )
-        .unwrap()
     }
 
     fn shifted_out_to(self, interner: I, target_binder: DebruijnIndex) -> Fallible<T::Result> {
-        self.fold_with(
+        self.try_fold_with(
             &mut DownShifter {
                 target_binder,
                 interner,
@@ -55,12 +54,13 @@ impl<T: Fold<I>, I: Interner> Shift<I> for T {
 }
 
 /// A folder that adjusts debruijn indices by a certain amount.
-struct Shifter<I> {
+#[derive(FallibleFolder)]
+struct Shifter<I: Interner> {
     source_binder: DebruijnIndex,
     interner: I,
 }
 
-impl<I> Shifter<I> {
+impl<I: Interner> Shifter<I> {
     /// Given a free variable at `depth`, shifts that depth to `depth
     /// + self.adjustment`, and then wraps *that* within the internal
     /// set `binders`.
@@ -72,29 +72,29 @@ impl<I> Shifter<I> {
 }
 
 impl<I: Interner> Folder<I> for Shifter<I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
-    fn fold_free_var_ty(
-        &mut self,
-        bound_var: BoundVar,
-        outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
-        Ok(TyKind::<I>::BoundVar(self.adjust(bound_var, outer_binder)).intern(self.interner()))
+    /// Types without variables bound at or above `outer_binder` are
+    /// left untouched.
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_vars_bound_at_or_above(self.interner, outer_binder) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) -> Ty<I> {
+        TyKind::<I>::BoundVar(self.adjust(bound_var, outer_binder)).intern(self.interner)
     }
 
     fn fold_free_var_lifetime(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
-        Ok(
-            LifetimeData::<I>::BoundVar(self.adjust(bound_var, outer_binder))
-                .intern(self.interner()),
-        )
+    ) -> Lifetime<I> {
+        LifetimeData::<I>::BoundVar(self.adjust(bound_var, outer_binder)).intern(self.interner)
     }
 
     fn fold_free_var_const(
@@ -102,11 +102,10 @@ impl<I: Interner> Folder<I> for Shifter<I> {
         ty: Ty<I>,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         // const types don't have free variables, so we can skip folding `ty`
-        Ok(self
-            .adjust(bound_var, outer_binder)
-            .to_const(self.interner(), ty))
+        self.adjust(bound_var, outer_binder)
+            .to_const(self.interner, ty)
     }
 
     fn interner(&self) -> I {
@@ -141,14 +140,21 @@ impl<I> DownShifter<I> {
     }
 }
 
-impl<I: Interner> Folder<I> for DownShifter<I> {
+impl<I: Interner> FallibleFolder<I> for DownShifter<I> {
     type Error = NoSolution;
 
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn FallibleFolder<I, Error = Self::Error> {
         self
     }
 
-    fn fold_free_var_ty(
+    fn try_fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        if !ty.has_vars_bound_at_or_above(self.interner, outer_binder) {
+            return Ok(ty);
+        }
+        ty.try_super_fold_with(self.as_dyn(), outer_binder)
+    }
+
+    fn try_fold_free_var_ty(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
@@ -156,7 +162,7 @@ impl<I: Interner> Folder<I> for DownShifter<I> {
         Ok(TyKind::<I>::BoundVar(self.adjust(bound_var, outer_binder)?).intern(self.interner()))
     }
 
-    fn fold_free_var_lifetime(
+    fn try_fold_free_var_lifetime(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
@@ -167,7 +173,7 @@ impl<I: Interner> Folder<I> for DownShifter<I> {
         )
     }
 
-    fn fold_free_var_const(
+    fn try_fold_free_var_const(
         &mut self,
         ty: Ty<I>,
         bound_var: BoundVar,
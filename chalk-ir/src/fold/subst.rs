@@ -2,6 +2,7 @@ use super::*;
 use crate::fold::shift::Shift;
 
 /// Substitution used during folding
+#[derive(FallibleFolder)]
 pub struct Subst<'s, I: Interner> {
     /// Values to substitute. A reference to a free variable with
     /// index `i` will be mapped to `parameters[i]` -- if `i >
@@ -13,25 +14,30 @@ pub struct Subst<'s, I: Interner> {
 impl<I: Interner> Subst<'_, I> {
     /// Applies the substitution by folding
     pub fn apply<T: Fold<I>>(interner: I, parameters: &[GenericArg<I>], value: T) -> T::Result {
-        value
-            .fold_with(
-                &mut Subst {
-                    parameters,
-                    interner,
-                },
-                DebruijnIndex::INNERMOST,
-            )
-            .unwrap()
+        value.fold_with(
+            &mut Subst {
+                parameters,
+                interner,
+            },
+            DebruijnIndex::INNERMOST,
+        )
     }
 }
 
 impl<I: Interner> Folder<I> for Subst<'_, I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
+    /// Types without variables bound at or above `outer_binder` are
+    /// left untouched.
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_vars_bound_at_or_above(self.interner, outer_binder) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
     /// We are eliminating one binder, but binders outside of that get preserved.
     ///
     /// So e.g. consider this:
@@ -51,24 +57,18 @@ impl<I: Interner> Folder<I> for Subst<'_, I> {
     /// for<A, B> { [A, u32] }
     ///              ^ represented as `^0.0`
     /// ```
-    fn fold_free_var_ty(
-        &mut self,
-        bound_var: BoundVar,
-        outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) -> Ty<I> {
         if let Some(index) = bound_var.index_if_innermost() {
-            match self.parameters[index].data(self.interner()) {
-                GenericArgData::Ty(t) => {
-                    Ok(t.clone().shifted_in_from(self.interner(), outer_binder))
-                }
+            match self.parameters[index].data(self.interner) {
+                GenericArgData::Ty(t) => t.clone().shifted_in_from(self.interner, outer_binder),
                 _ => panic!("mismatched kinds in substitution"),
             }
         } else {
-            Ok(bound_var
+            bound_var
                 .shifted_out()
                 .expect("cannot fail because this is not the innermost")
                 .shifted_in_from(outer_binder)
-                .to_ty(self.interner()))
+                .to_ty(self.interner)
         }
     }
 
@@ -77,20 +77,20 @@ impl<I: Interner> Folder<I> for Subst<'_, I> {
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         if let Some(index) = bound_var.index_if_innermost() {
-            match self.parameters[index].data(self.interner()) {
+            match self.parameters[index].data(self.interner) {
                 GenericArgData::Lifetime(l) => {
-                    Ok(l.clone().shifted_in_from(self.interner(), outer_binder))
+                    l.clone().shifted_in_from(self.interner, outer_binder)
                 }
                 _ => panic!("mismatched kinds in substitution"),
             }
         } else {
-            Ok(bound_var
+            bound_var
                 .shifted_out()
                 .unwrap()
                 .shifted_in_from(outer_binder)
-                .to_lifetime(self.interner()))
+                .to_lifetime(self.interner)
         }
     }
 
@@ -100,20 +100,118 @@ impl<I: Interner> Folder<I> for Subst<'_, I> {
         ty: Ty<I>,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         if let Some(index) = bound_var.index_if_innermost() {
-            match self.parameters[index].data(self.interner()) {
-                GenericArgData::Const(c) => {
-                    Ok(c.clone().shifted_in_from(self.interner(), outer_binder))
-                }
+            match self.parameters[index].data(self.interner) {
+                GenericArgData::Const(c) => c.clone().shifted_in_from(self.interner, outer_binder),
                 _ => panic!("mismatched kinds in substitution"),
             }
         } else {
-            Ok(bound_var
+            bound_var
                 .shifted_out()
                 .unwrap()
                 .shifted_in_from(outer_binder)
-                .to_const(self.interner(), ty))
+                .to_const(self.interner, ty)
+        }
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
+
+/// Substitution of the values of some inference variables, used during
+/// folding; see `Substitution::apply_to_inference_vars`.
+#[derive(FallibleFolder)]
+pub(crate) struct InferenceVarSubst<'s, I: Interner> {
+    /// The inference variables to substitute: an occurrence of
+    /// `free_vars[i]` is mapped to `parameters[i]`.
+    free_vars: &'s [GenericArg<I>],
+    parameters: &'s [GenericArg<I>],
+    interner: I,
+}
+
+impl<I: Interner> InferenceVarSubst<'_, I> {
+    /// Applies the substitution by folding
+    pub(crate) fn apply<T: Fold<I>>(
+        interner: I,
+        free_vars: &[GenericArg<I>],
+        parameters: &[GenericArg<I>],
+        value: T,
+    ) -> T::Result {
+        value.fold_with(
+            &mut InferenceVarSubst {
+                free_vars,
+                parameters,
+                interner,
+            },
+            DebruijnIndex::INNERMOST,
+        )
+    }
+
+    /// The value of `var`, if it is one of the substituted variables.
+    fn value_of(&self, var: InferenceVar) -> Option<&GenericArg<I>> {
+        let interner = self.interner;
+        let index = self.free_vars.iter().position(|free_var| {
+            let free_var = match free_var.data(interner) {
+                GenericArgData::Ty(ty) => ty.inference_var(interner),
+                GenericArgData::Lifetime(lifetime) => lifetime.inference_var(interner),
+                GenericArgData::Const(constant) => constant.inference_var(interner),
+            };
+            free_var == Some(var)
+        })?;
+        Some(&self.parameters[index])
+    }
+}
+
+impl<I: Interner> Folder<I> for InferenceVarSubst<'_, I> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
+        self
+    }
+
+    /// The value of the variable refers to binders outside of `value`, so
+    /// it is shifted in through the `outer_binder` binders of `value` the
+    /// variable is under.
+    fn fold_inference_ty(
+        &mut self,
+        var: InferenceVar,
+        kind: TyVariableKind,
+        outer_binder: DebruijnIndex,
+    ) -> Ty<I> {
+        let interner = self.interner;
+        match self.value_of(var).map(|value| value.data(interner)) {
+            Some(GenericArgData::Ty(t)) => t.clone().shifted_in_from(interner, outer_binder),
+            Some(_) => panic!("mismatched kinds in substitution"),
+            None => var.to_ty(interner, kind),
+        }
+    }
+
+    /// see `fold_inference_ty`
+    fn fold_inference_lifetime(
+        &mut self,
+        var: InferenceVar,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        let interner = self.interner;
+        match self.value_of(var).map(|value| value.data(interner)) {
+            Some(GenericArgData::Lifetime(l)) => l.clone().shifted_in_from(interner, outer_binder),
+            Some(_) => panic!("mismatched kinds in substitution"),
+            None => var.to_lifetime(interner),
+        }
+    }
+
+    /// see `fold_inference_ty`
+    fn fold_inference_const(
+        &mut self,
+        ty: Ty<I>,
+        var: InferenceVar,
+        outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        let interner = self.interner;
+        match self.value_of(var).map(|value| value.data(interner)) {
+            Some(GenericArgData::Const(c)) => c.clone().shifted_in_from(interner, outer_binder),
+            Some(_) => panic!("mismatched kinds in substitution"),
+            None => var.to_const(interner, ty.fold_with(Folder::as_dyn(self), outer_binder)),
         }
     }
 
@@ -1,4 +1,7 @@
 //! Encapsulates the concrete representation of core types such as types and goals.
+use crate::fold::FallibleFolder;
+use crate::visit::Visitor;
+use crate::zip::Zipper;
 use crate::AliasTy;
 use crate::AssocTypeId;
 use crate::CanonicalVarKind;
@@ -37,10 +40,15 @@ use crate::Variance;
 use crate::Variances;
 use crate::{AdtId, TyKind};
 use crate::{Const, ConstData};
-use std::fmt::{self, Debug};
-use std::hash::Hash;
-use std::marker::PhantomData;
-use std::sync::Arc;
+use crate::{DebruijnIndex, Fallible, NoSolution, TypeFlags};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use smallvec::SmallVec;
 
 /// A "interner" encapsulates the concrete representation of
 /// certain "core types" from chalk-ir. All the types in chalk-ir are
@@ -127,6 +135,10 @@ pub trait Interner: Debug + Copy + Eq + Hash + Sized {
     ///
     /// An `InternedSubstitution` is created by `intern_substitution` and can be
     /// converted back to its underlying data via `substitution_data`.
+    ///
+    /// Most substitutions are short, so interners that store them by
+    /// value may want to use [`InlineSubstitution`], which avoids
+    /// allocating for up to two arguments.
     type InternedSubstitution: Debug + Clone + Eq + Hash;
 
     /// "Interned" representation of a list of program clauses.  In normal user code,
@@ -199,6 +211,20 @@ pub trait Interner: Debug + Copy + Eq + Hash + Sized {
     /// Representation of function ABI (e.g. calling convention).
     type FnAbi: Debug + Copy + Eq + Hash;
 
+    /// Host-specific type forms, carried by `TyKind::Custom`, for
+    /// experiments (e.g. pattern types) which chalk knows nothing about.
+    /// Interners without any can use `()`. See `fold_custom_ty` and the
+    /// related methods for how chalk walks through them.
+    type CustomTy: Debug + Clone + Eq + Hash;
+
+    /// Host-specific predicates, carried by `DomainGoal::Custom`, like
+    /// `ConstEvaluatable(C)` or `TypeOutlivesStatic(T)`. Interners without
+    /// any can use `()`. The clauses proving them come from the host (see
+    /// `RustIrDatabase::custom_predicate_clauses` in chalk-solve), and chalk
+    /// walks through them with `fold_custom_predicate` and the related
+    /// methods.
+    type CustomPredicate: Debug + Clone + Eq + Hash;
+
     /// Prints the debug representation of a type-kind-id.
     /// Returns `None` to fallback to the default debug output.
     #[allow(unused_variables)]
@@ -501,6 +527,17 @@ pub trait Interner: Debug + Copy + Eq + Hash + Sized {
         c2: &Self::InternedConcreteConst,
     ) -> bool;
 
+    /// Returns true if `a` and `b`, two interned values of the same kind
+    /// (e.g., two `Self::InternedType`), are known to be equal without
+    /// looking at the data they intern. Interners that hash-cons their
+    /// values can compare them by address here, which lets zipping and
+    /// unification skip over identical subterms. Returning false is
+    /// always correct, and is what the default implementation does.
+    #[allow(unused_variables)]
+    fn interned_eq_fast<T: PartialEq + ?Sized>(self, a: &T, b: &T) -> bool {
+        false
+    }
+
     /// Create an "interned" parameter from `data`. This is not
     /// normally invoked directly; instead, you invoke
     /// `GenericArgData::intern` (which will ultimately call this
@@ -641,6 +678,113 @@ pub trait Interner: Debug + Copy + Eq + Hash + Sized {
     /// Lookup the slice of `Variance` that was interned to
     /// create a `Variances`.
     fn variances_data(self, variances: &Self::InternedVariances) -> &[Variance];
+
+    /// Folds the types, lifetimes and constants contained in a custom type.
+    /// The default implementation assumes there are none, and returns a
+    /// clone of `custom_ty`; see also `visit_custom_ty`, `zip_custom_tys`,
+    /// `custom_ty_flags` and `custom_ty_outer_exclusive_binder`, which
+    /// must agree with it.
+    #[allow(unused_variables)]
+    fn fold_custom_ty<E>(
+        self,
+        custom_ty: &Self::CustomTy,
+        folder: &mut dyn FallibleFolder<Self, Error = E>,
+        outer_binder: DebruijnIndex,
+    ) -> Result<Self::CustomTy, E> {
+        Ok(custom_ty.clone())
+    }
+
+    /// Visits the types, lifetimes and constants contained in a custom type.
+    /// The default implementation assumes there are none.
+    #[allow(unused_variables)]
+    fn visit_custom_ty<B>(
+        self,
+        custom_ty: &Self::CustomTy,
+        visitor: &mut dyn Visitor<Self, BreakTy = B>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<B> {
+        ControlFlow::Continue(())
+    }
+
+    /// Zips two custom types, e.g. to unify them, by zipping the types,
+    /// lifetimes and constants they contain if they are otherwise equal.
+    /// The default implementation assumes there are none, and just compares
+    /// `a` and `b`.
+    #[allow(unused_variables)]
+    fn zip_custom_tys<Z: Zipper<Self>>(
+        self,
+        zipper: &mut Z,
+        variance: Variance,
+        a: &Self::CustomTy,
+        b: &Self::CustomTy,
+    ) -> Fallible<()> {
+        if a == b {
+            Ok(())
+        } else {
+            Err(NoSolution)
+        }
+    }
+
+    /// Computes the flags of a custom type, i.e. the union of the flags of
+    /// the types, lifetimes and constants it contains. The default
+    /// implementation assumes there are none.
+    #[allow(unused_variables)]
+    fn custom_ty_flags(self, custom_ty: &Self::CustomTy) -> TypeFlags {
+        TypeFlags::empty()
+    }
+
+    /// Computes the outer exclusive binder of a custom type; see
+    /// [`Ty::outer_exclusive_binder`]. The default implementation assumes
+    /// it contains no types, lifetimes or constants.
+    #[allow(unused_variables)]
+    fn custom_ty_outer_exclusive_binder(self, custom_ty: &Self::CustomTy) -> DebruijnIndex {
+        DebruijnIndex::INNERMOST
+    }
+
+    /// Folds the types, lifetimes and constants contained in a custom
+    /// predicate. Like `fold_custom_ty`, the default implementation assumes
+    /// there are none; `visit_custom_predicate` and `zip_custom_predicates`
+    /// must agree with it.
+    #[allow(unused_variables)]
+    fn fold_custom_predicate<E>(
+        self,
+        predicate: &Self::CustomPredicate,
+        folder: &mut dyn FallibleFolder<Self, Error = E>,
+        outer_binder: DebruijnIndex,
+    ) -> Result<Self::CustomPredicate, E> {
+        Ok(predicate.clone())
+    }
+
+    /// Visits the types, lifetimes and constants contained in a custom
+    /// predicate. The default implementation assumes there are none.
+    #[allow(unused_variables)]
+    fn visit_custom_predicate<B>(
+        self,
+        predicate: &Self::CustomPredicate,
+        visitor: &mut dyn Visitor<Self, BreakTy = B>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<B> {
+        ControlFlow::Continue(())
+    }
+
+    /// Zips two custom predicates, e.g. to unify a goal with the
+    /// consequence of a clause, by zipping the types, lifetimes and
+    /// constants they contain if they are otherwise equal. The default
+    /// implementation assumes there are none, and just compares `a` and `b`.
+    #[allow(unused_variables)]
+    fn zip_custom_predicates<Z: Zipper<Self>>(
+        self,
+        zipper: &mut Z,
+        variance: Variance,
+        a: &Self::CustomPredicate,
+        b: &Self::CustomPredicate,
+    ) -> Fallible<()> {
+        if a == b {
+            Ok(())
+        } else {
+            Err(NoSolution)
+        }
+    }
 }
 
 /// Implemented by types that have an associated interner (which
@@ -662,6 +806,13 @@ impl<T: HasInterner> HasInterner for Vec<T> {
     type Interner = T::Interner;
 }
 
+/// A representation for `Interner::InternedSubstitution` that stores up
+/// to two generic arguments inline, and only allocates for longer
+/// substitutions. It can be created with `collect` in
+/// `Interner::intern_substitution`, and derefs to the slice needed by
+/// `Interner::substitution_data`.
+pub type InlineSubstitution<I> = SmallVec<[GenericArg<I>; 2]>;
+
 impl<T: HasInterner> HasInterner for Box<T> {
     type Interner = T::Interner;
 }
@@ -697,6 +848,6 @@ where
     type Interner = I;
 }
 
-impl<'a, T: HasInterner> HasInterner for std::slice::Iter<'a, T> {
+impl<'a, T: HasInterner> HasInterner for core::slice::Iter<'a, T> {
     type Interner = T::Interner;
 }
@@ -1,7 +1,15 @@
 //! Defines the IR for types and logical predicates.
 
+#![cfg_attr(feature = "bench", feature(test))]
 #![deny(rust_2018_idioms)]
 #![warn(missing_docs)]
+#![no_std]
+
+// Only `alloc` is needed, unless the `std` feature is enabled (it is by
+// default) for the pieces which need synchronization, like arenas.
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
 
 // Allows macros to refer to this crate as `::chalk_ir`
 extern crate self as chalk_ir;
@@ -9,16 +17,30 @@ extern crate self as chalk_ir;
 use crate::cast::{Cast, CastTo, Caster};
 use crate::fold::shift::Shift;
 use crate::fold::{Fold, Folder, Subst, SuperFold};
-use crate::visit::{SuperVisit, Visit, VisitExt, Visitor};
-use chalk_derive::{Fold, HasInterner, SuperVisit, Visit, Zip};
-use std::marker::PhantomData;
-use std::ops::ControlFlow;
+#[cfg(feature = "serde")]
+use crate::serialize::SerdeInterner;
+use crate::visit::{SuperVisit, TypeWalker, Visit, VisitExt, Visitor};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_derive::{FallibleFolder, Fold, HasInterner, SuperVisit, Visit, Zip};
+use core::hash::{BuildHasherDefault, Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use rustc_hash::FxHasher;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
 
 pub use crate::debug::SeparatorTraitRef;
 #[macro_use(bitflags)]
 extern crate bitflags;
+
+/// `rustc_hash` only provides its hash sets with `std`.
+type FxHashSet<T> = hashbrown::HashSet<T, BuildHasherDefault<FxHasher>>;
+
 /// Uninhabited (empty) type, used in combination with `PhantomData`.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Void {}
 
 /// Many of our internal operations (e.g., unification) are an attempt
@@ -40,6 +62,34 @@ pub enum FallibleOrFloundered<T> {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct NoSolution;
 
+/// A more detailed form of `NoSolution`, produced by unification, that
+/// records *why* two values could not be related.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UnificationError<I: Interner> {
+    /// The values are not compatible. See `chalk_ir::NoSolution`.
+    NoSolution,
+    /// Relating the inference variable `var` with `ty` would create a
+    /// cyclic type of infinite size, since `var` occurs within `ty`.
+    OccursCheck {
+        /// The variable being unified.
+        var: InferenceVar,
+        /// The type that `var` occurs in.
+        ty: Ty<I>,
+    },
+}
+
+impl<I: Interner> From<NoSolution> for UnificationError<I> {
+    fn from(_: NoSolution) -> Self {
+        UnificationError::NoSolution
+    }
+}
+
+impl<I: Interner> From<UnificationError<I>> for NoSolution {
+    fn from(_: UnificationError<I>) -> Self {
+        NoSolution
+    }
+}
+
 /// Indicates that the complete set of program clauses for this goal
 /// cannot be enumerated.
 pub struct Floundered;
@@ -47,8 +97,8 @@ pub struct Floundered;
 macro_rules! impl_debugs {
     ($($id:ident), *) => {
         $(
-            impl<I: Interner> std::fmt::Debug for $id<I> {
-                fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+            impl<I: Interner> core::fmt::Debug for $id<I> {
+                fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
                     write!(fmt, "{}({:?})", stringify!($id), self.0)
                 }
             }
@@ -67,14 +117,22 @@ pub mod visit;
 
 pub mod cast;
 
+#[cfg(feature = "std")]
+pub mod arena;
+
 pub mod interner;
+
+#[cfg(feature = "serde")]
+pub mod serialize;
 use interner::{HasInterner, Interner};
 
 pub mod could_match;
 pub mod debug;
+pub mod diff;
 
 /// Variance
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Variance {
     /// a <: b
     Covariant,
@@ -150,6 +208,11 @@ impl Variance {
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
 /// The set of assumptions we've made so far, and the current number of
 /// universal (forall) quantifiers we're within.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Environment<I: Interner> {
     /// The clauses in the environment.
     pub clauses: ProgramClauses<I>,
@@ -196,9 +259,88 @@ impl<I: Interner> Environment<I> {
     }
 }
 
+/// Builds an environment clause by clause, in a canonical form: the
+/// clauses are deduplicated and put in an order that does not depend on
+/// the order they were added in. So, unlike with
+/// `Environment::add_clauses`, environments built from the same set of
+/// clauses are equal (and hash equal), which lets solvers reuse the
+/// tables of goals in them.
+///
+/// The clauses are ordered by hash, and clauses whose hashes collide are
+/// kept in the order they were added in.
+#[derive(Clone, Debug)]
+pub struct EnvironmentBuilder<I: Interner> {
+    interner: I,
+    clauses: Vec<ProgramClause<I>>,
+}
+
+impl<I: Interner> EnvironmentBuilder<I> {
+    /// Creates a builder for an environment with no clauses.
+    pub fn new(interner: I) -> Self {
+        EnvironmentBuilder {
+            interner,
+            clauses: vec![],
+        }
+    }
+
+    /// Creates a builder for an environment with the clauses of
+    /// `environment`.
+    pub fn from_environment(interner: I, environment: &Environment<I>) -> Self {
+        let mut builder = EnvironmentBuilder::new(interner);
+        builder.add_clauses(environment.clauses.iter(interner).cloned());
+        builder
+    }
+
+    /// Adds a clause to the environment.
+    pub fn add_clause(&mut self, clause: ProgramClause<I>) -> &mut Self {
+        self.clauses.push(clause);
+        self
+    }
+
+    /// Adds (an iterator of) clauses to the environment.
+    pub fn add_clauses<II>(&mut self, clauses: II) -> &mut Self
+    where
+        II: IntoIterator<Item = ProgramClause<I>>,
+    {
+        self.clauses.extend(clauses);
+        self
+    }
+
+    /// Returns the environment with the clauses added so far, in canonical
+    /// form.
+    pub fn build(&self) -> Environment<I> {
+        let mut seen = FxHashSet::default();
+        let mut clauses: Vec<_> = self
+            .clauses
+            .iter()
+            .filter(|clause| seen.insert(*clause))
+            .map(|clause| {
+                let mut hasher = FxHasher::default();
+                clause.hash(&mut hasher);
+                (hasher.finish(), clause.clone())
+            })
+            .collect();
+        clauses.sort_by_key(|(hash, _)| *hash);
+        Environment {
+            clauses: ProgramClauses::from_iter(
+                self.interner,
+                clauses.into_iter().map(|(_, clause)| clause),
+            ),
+        }
+    }
+}
+
 /// A goal with an environment to solve it in.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "G: HasInterner + serde::Serialize, G::Interner: SerdeInterner",
+        deserialize = "G: HasInterner + serde::Deserialize<'de>, G::Interner: SerdeInterner"
+    ))
+)]
 pub struct InEnvironment<G: HasInterner> {
     pub environment: Environment<G::Interner>,
     pub goal: G,
@@ -238,6 +380,7 @@ impl<G: HasInterner> HasInterner for InEnvironment<G> {
 /// Different signed int types.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum IntTy {
     Isize,
     I8,
@@ -250,6 +393,7 @@ pub enum IntTy {
 /// Different unsigned int types.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UintTy {
     Usize,
     U8,
@@ -262,6 +406,7 @@ pub enum UintTy {
 /// Different kinds of float types.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FloatTy {
     F32,
     F64,
@@ -270,6 +415,7 @@ pub enum FloatTy {
 /// Types of scalar values.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Scalar {
     Bool,
     Char,
@@ -280,6 +426,7 @@ pub enum Scalar {
 
 /// Whether a function is safe or not.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Safety {
     /// Safe
     Safe,
@@ -289,6 +436,7 @@ pub enum Safety {
 
 /// Whether a type is mutable or not.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Mutability {
     /// Mutable
     Mut,
@@ -304,6 +452,7 @@ pub enum Mutability {
 /// `Goal(!U1)` (the quantifier was moved to the environment and replaced with a universe index)
 /// See <https://rustc-dev-guide.rust-lang.org/borrow_check/region_inference.html#placeholders-and-universes> for more.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UniverseIndex {
     /// The counter for the universe index, starts with 0.
     pub counter: usize,
@@ -339,16 +488,19 @@ impl UniverseIndex {
 #[derive(Clone, Debug)]
 pub struct UniverseMap {
     /// A reverse map -- for each universe Ux that appears in
-    /// `quantified`, the corresponding universe in the original was
-    /// `universes[x]`.
-    pub universes: Vec<UniverseIndex>,
+    /// `quantified`, the corresponding universes in the original were
+    /// `universes[x]`, in order. Each comes with the offset added to the
+    /// indices of its placeholders in Ux: there is more than one universe
+    /// when adjacent universes were merged together, and their
+    /// placeholders must then remain distinct.
+    pub universes: Vec<Vec<(UniverseIndex, usize)>>,
 }
 
 impl UniverseMap {
-    /// Creates a new universe map.
+    /// Creates a new universe map, with only the root universe.
     pub fn new() -> Self {
         UniverseMap {
-            universes: vec![UniverseIndex::root()],
+            universes: vec![vec![(UniverseIndex::root(), 0)]],
         }
     }
 
@@ -360,6 +512,11 @@ impl UniverseMap {
 
 /// The id for an Abstract Data Type (i.e. structs, unions and enums).
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AdtId<I: Interner>(pub I::InternedAdtId);
 
 /// The id of a trait definition; could be used to load the trait datum by
@@ -367,14 +524,29 @@ pub struct AdtId<I: Interner>(pub I::InternedAdtId);
 ///
 /// [`trait_datum`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.trait_datum
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitId<I: Interner>(pub I::DefId);
 
 /// The id for an impl.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ImplId<I: Interner>(pub I::DefId);
 
 /// Id for a specific clause.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ClauseId<I: Interner>(pub I::DefId);
 
 /// The id for the associated type member of a trait. The details of the type
@@ -382,30 +554,92 @@ pub struct ClauseId<I: Interner>(pub I::DefId);
 ///
 /// [`associated_ty_data`]: ../chalk_solve/trait.RustIrDatabase.html#tymethod.associated_ty_data
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssocTypeId<I: Interner>(pub I::DefId);
 
 /// Id for an opaque type.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTyId<I: Interner>(pub I::DefId);
 
 /// Function definition id.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnDefId<I: Interner>(pub I::DefId);
 
 /// Id for Rust closures.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ClosureId<I: Interner>(pub I::DefId);
 
 /// Id for Rust generators.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct GeneratorId<I: Interner>(pub I::DefId);
 
 /// Id for foreign types.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ForeignDefId<I: Interner>(pub I::DefId);
 
 impl_debugs!(ImplId, ClauseId);
 
+#[cfg(feature = "validate-bound-vars")]
+impl<I: Interner> TyKind<I> {
+    /// Checks that the types with binders of their own only refer to
+    /// variables of those binders, in the right kind, where they are
+    /// bound; see `VisitExt::debug_assert_bound_vars_consistent`.
+    fn debug_assert_binders_consistent(&self, interner: I) {
+        match self {
+            TyKind::Function(fn_pointer) => {
+                let binders = VariableKinds::from_iter(
+                    interner,
+                    (0..fn_pointer.num_binders).map(|_| VariableKind::Lifetime),
+                );
+                fn_pointer
+                    .substitution
+                    .debug_assert_bound_vars_consistent(interner, &binders);
+            }
+            TyKind::Dyn(dyn_ty) => {
+                let bounds = &dyn_ty.bounds;
+                bounds
+                    .skip_binders()
+                    .debug_assert_bound_vars_consistent(interner, &bounds.binders);
+                for bound in bounds.skip_binders().iter(interner) {
+                    bound
+                        .skip_binders()
+                        .debug_assert_bound_vars_consistent(interner, &bound.binders);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 /// A Rust type. The actual type data is stored in `TyKind`.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
 pub struct Ty<I: Interner> {
@@ -416,6 +650,8 @@ impl<I: Interner> Ty<I> {
     /// Creates a type from `TyKind`.
     pub fn new(interner: I, data: impl CastTo<TyKind<I>>) -> Self {
         let ty_kind = data.cast(interner);
+        #[cfg(feature = "validate-bound-vars")]
+        ty_kind.debug_assert_binders_consistent(interner);
         Ty {
             interned: I::intern_ty(interner, ty_kind),
         }
@@ -436,6 +672,37 @@ impl<I: Interner> Ty<I> {
         &I::ty_data(interner, &self.interned).kind
     }
 
+    /// Gets the flags computed when the type was interned.
+    pub fn flags(&self, interner: I) -> TypeFlags {
+        I::ty_data(interner, &self.interned).flags
+    }
+
+    /// Returns true if the type has any of the given flags.
+    pub fn has_type_flags(&self, interner: I, flags: TypeFlags) -> bool {
+        self.flags(interner).intersects(flags)
+    }
+
+    /// Returns the innermost binder that every bound variable appearing
+    /// in this type is bound *within*. For example, this is
+    /// `INNERMOST` for a type without free bound variables, and `2`
+    /// for `Vec<^1.0>`. Computed when the type was interned.
+    pub fn outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        I::ty_data(interner, &self.interned).outer_exclusive_binder
+    }
+
+    /// True if this type contains variables bound at `binder` or at
+    /// one of the binders outside of it, when it appears within
+    /// `binder` levels of binders.
+    pub fn has_vars_bound_at_or_above(&self, interner: I, binder: DebruijnIndex) -> bool {
+        self.outer_exclusive_binder(interner) > binder
+    }
+
+    /// Returns an iterator over this type and the types, lifetimes and
+    /// consts nested in it, in pre-order; see `VisitExt::walk`.
+    pub fn walk(&self, interner: I) -> TypeWalker<I> {
+        VisitExt::walk(self, interner)
+    }
+
     /// Creates a `FromEnv` constraint using this type.
     pub fn from_env(&self) -> FromEnv<I> {
         FromEnv::Ty(self.clone())
@@ -511,10 +778,9 @@ impl<I: Interner> Ty<I> {
     }
 
     /// True if this type contains "bound" types/lifetimes, and hence
-    /// needs to be shifted across binders. This is a very inefficient
-    /// check, intended only for debug assertions, because I am lazy.
+    /// needs to be shifted across binders.
     pub fn needs_shift(&self, interner: I) -> bool {
-        self.has_free_vars(interner)
+        self.has_vars_bound_at_or_above(interner, DebruijnIndex::INNERMOST)
     }
 }
 
@@ -525,11 +791,14 @@ pub struct TyData<I: Interner> {
     pub kind: TyKind<I>,
     /// Type flags
     pub flags: TypeFlags,
+    /// The binder just outside of all bound variables that appear
+    /// (free) in the type; see [`Ty::outer_exclusive_binder`].
+    pub outer_exclusive_binder: DebruijnIndex,
 }
 
 bitflags! {
     /// Contains flags indicating various properties of a Ty
-    pub struct TypeFlags : u16 {
+    pub struct TypeFlags : u32 {
         /// Does the type contain an InferenceVar
         const HAS_TY_INFER                = 1;
         /// Does the type contain a lifetime with an InferenceVar
@@ -560,6 +829,10 @@ bitflags! {
         const HAS_RE_ERASED               = 1 << 13;
         /// Does the type contain placeholders or inference variables that could be replaced later
         const STILL_FURTHER_SPECIALIZABLE = 1 << 14;
+        /// Does the type contain a bound type variable
+        const HAS_TY_BOUND                = 1 << 15;
+        /// Does the type contain a bound const variable
+        const HAS_CT_BOUND                = 1 << 16;
 
         /// True when the type contains free names local to a particular context
         const HAS_FREE_LOCAL_NAMES        = TypeFlags::HAS_TY_INFER.bits
@@ -572,10 +845,32 @@ bitflags! {
         const HAS_PROJECTION              = TypeFlags::HAS_TY_PROJECTION.bits
                                           | TypeFlags::HAS_TY_OPAQUE.bits
                                           | TypeFlags::HAS_CT_PROJECTION.bits;
+
+        /// Does the type contain any inference variable
+        const HAS_INFER                   = TypeFlags::HAS_TY_INFER.bits
+                                          | TypeFlags::HAS_RE_INFER.bits
+                                          | TypeFlags::HAS_CT_INFER.bits;
+
+        /// Does the type contain any placeholder
+        const HAS_PLACEHOLDER             = TypeFlags::HAS_TY_PLACEHOLDER.bits
+                                          | TypeFlags::HAS_RE_PLACEHOLDER.bits
+                                          | TypeFlags::HAS_CT_PLACEHOLDER.bits;
+
+        /// Does the type contain any bound variable. Variables bound
+        /// within the type itself (e.g., by a `dyn` or `fn` type) count
+        /// as well, so this is only an upper bound on free variables.
+        const HAS_BOUND_VARS              = TypeFlags::HAS_TY_BOUND.bits
+                                          | TypeFlags::HAS_RE_LATE_BOUND.bits
+                                          | TypeFlags::HAS_CT_BOUND.bits;
     }
 }
 /// Type data, which holds the actual type information.
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum TyKind<I: Interner> {
     /// Abstract data types, i.e., structs, unions, or enumerations.
     /// For example, a type like `Vec<T>`.
@@ -662,6 +957,12 @@ pub enum TyKind<I: Interner> {
 
     /// Inference variable defined in the current inference context.
     InferenceVar(InferenceVar, TyVariableKind),
+
+    /// A host-specific type form, which chalk itself never produces, and
+    /// only walks through using the `Interner::fold_custom_ty` family of
+    /// methods. Such types are only equal to themselves, and implement no
+    /// traits besides the ones in the program clauses given by the host.
+    Custom(I::CustomTy),
 }
 
 impl<I: Interner> Copy for TyKind<I>
@@ -672,6 +973,7 @@ where
     I::InternedQuantifiedWhereClauses: Copy,
     I::InternedType: Copy,
     I::InternedConst: Copy,
+    I::CustomTy: Copy,
 {
 }
 
@@ -706,7 +1008,8 @@ impl<I: Interner> TyKind<I> {
                 flags
                     | const_data.ty.data(interner).flags
                     | match const_data.value {
-                        ConstValue::BoundVar(_) | ConstValue::Concrete(_) => TypeFlags::empty(),
+                        ConstValue::BoundVar(_) => TypeFlags::HAS_CT_BOUND,
+                        ConstValue::Concrete(_) => TypeFlags::empty(),
                         ConstValue::InferenceVar(_) => {
                             TypeFlags::HAS_CT_INFER | TypeFlags::STILL_FURTHER_SPECIALIZABLE
                         }
@@ -741,9 +1044,77 @@ impl<I: Interner> TyKind<I> {
                 lifetime_flags | dyn_flags
             }
             TyKind::Alias(alias_ty) => alias_ty.compute_flags(interner),
-            TyKind::BoundVar(_) => TypeFlags::empty(),
+            TyKind::BoundVar(_) => TypeFlags::HAS_TY_BOUND,
             TyKind::InferenceVar(_, _) => TypeFlags::HAS_TY_INFER,
             TyKind::Function(fn_pointer) => fn_pointer.substitution.0.compute_flags(interner),
+            TyKind::Custom(custom_ty) => interner.custom_ty_flags(custom_ty),
+        }
+    }
+
+    /// Compute the outer exclusive binder for a TyKind; see
+    /// [`Ty::outer_exclusive_binder`].
+    pub fn compute_outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        match self {
+            TyKind::Adt(_, substitution)
+            | TyKind::AssociatedType(_, substitution)
+            | TyKind::Tuple(_, substitution)
+            | TyKind::Closure(_, substitution)
+            | TyKind::Generator(_, substitution)
+            | TyKind::GeneratorWitness(_, substitution)
+            | TyKind::FnDef(_, substitution)
+            | TyKind::OpaqueType(_, substitution) => substitution.outer_exclusive_binder(interner),
+            TyKind::Scalar(_)
+            | TyKind::Str
+            | TyKind::Never
+            | TyKind::Foreign(_)
+            | TyKind::Error
+            | TyKind::Placeholder(_)
+            | TyKind::InferenceVar(_, _) => DebruijnIndex::INNERMOST,
+            TyKind::Slice(ty) | TyKind::Raw(_, ty) => ty.outer_exclusive_binder(interner),
+            TyKind::Ref(_, lifetime, ty) => lifetime
+                .outer_exclusive_binder(interner)
+                .max(ty.outer_exclusive_binder(interner)),
+            TyKind::Array(ty, const_) => ty
+                .outer_exclusive_binder(interner)
+                .max(const_.outer_exclusive_binder(interner)),
+            TyKind::Dyn(dyn_ty) => {
+                let bounds = dyn_ty.bounds.skip_binders().iter(interner).map(|bound| {
+                    let binder = match bound.skip_binders() {
+                        WhereClause::Implemented(trait_ref) => {
+                            trait_ref.substitution.outer_exclusive_binder(interner)
+                        }
+                        WhereClause::AliasEq(alias_eq) => alias_eq
+                            .alias
+                            .outer_exclusive_binder(interner)
+                            .max(alias_eq.ty.outer_exclusive_binder(interner)),
+                        WhereClause::LifetimeOutlives(lifetime_outlives) => lifetime_outlives
+                            .a
+                            .outer_exclusive_binder(interner)
+                            .max(lifetime_outlives.b.outer_exclusive_binder(interner)),
+                        WhereClause::TypeOutlives(type_outlives) => type_outlives
+                            .ty
+                            .outer_exclusive_binder(interner)
+                            .max(type_outlives.lifetime.outer_exclusive_binder(interner)),
+                    };
+                    // each bound is within the binder for `Self` and its own binder
+                    binder
+                        .shifted_out_to(DebruijnIndex::new(2))
+                        .unwrap_or(DebruijnIndex::INNERMOST)
+                });
+                bounds.fold(
+                    dyn_ty.lifetime.outer_exclusive_binder(interner),
+                    DebruijnIndex::max,
+                )
+            }
+            TyKind::Alias(alias_ty) => alias_ty.outer_exclusive_binder(interner),
+            TyKind::BoundVar(bound_var) => bound_var.debruijn.shifted_in(),
+            TyKind::Function(fn_pointer) => fn_pointer
+                .substitution
+                .0
+                .outer_exclusive_binder(interner)
+                .shifted_out()
+                .unwrap_or(DebruijnIndex::INNERMOST),
+            TyKind::Custom(custom_ty) => interner.custom_ty_outer_exclusive_binder(custom_ty),
         }
     }
 }
@@ -773,6 +1144,7 @@ impl<I: Interner> TyKind<I> {
 /// they appear in the *innermost* binder enclosing the `...`. The
 /// indices identify the location *within* that binder.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BoundVar {
     /// Debruijn index, which identifies the binder.
     pub debruijn: DebruijnIndex,
@@ -864,6 +1236,7 @@ impl BoundVar {
 ///
 /// [de Bruijn index]: https://en.wikipedia.org/wiki/De_Bruijn_index
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DebruijnIndex {
     depth: u32,
 }
@@ -1022,6 +1395,11 @@ impl DebruijnIndex {
 /// a bound type with debruijn index 1 (i.e., skipping through one
 /// level of binder).
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct DynTy<I: Interner> {
     /// The unknown self type.
     pub bounds: Binders<QuantifiedWhereClauses<I>>,
@@ -1037,8 +1415,57 @@ where
 {
 }
 
+impl<I: Interner> DynTy<I> {
+    /// Creates a `dyn` type with the given bounds, normalized as by
+    /// [`DynTy::normalized`].
+    pub fn new(
+        interner: I,
+        bounds: Binders<QuantifiedWhereClauses<I>>,
+        lifetime: Lifetime<I>,
+    ) -> Self {
+        DynTy { bounds, lifetime }.normalized(interner)
+    }
+
+    /// Returns the same type with duplicate bounds removed, and the
+    /// bounds ordered by kind: trait bounds first, then associated type
+    /// bounds, then outlives bounds. Bounds of the same kind keep their
+    /// order, as ids can't be ordered; `dyn Foo + Bar` and `dyn Bar + Foo`
+    /// are equivalent, but not equal (see [`DynTy::is_equivalent`]).
+    pub fn normalized(self, interner: I) -> Self {
+        let DynTy { bounds, lifetime } = self;
+        let bounds = bounds.map(|bounds| {
+            let mut unique: Vec<QuantifiedWhereClause<I>> = Vec::new();
+            for bound in bounds.iter(interner) {
+                if !unique.contains(bound) {
+                    unique.push(bound.clone());
+                }
+            }
+            unique.sort_by_key(|bound| match bound.skip_binders() {
+                WhereClause::Implemented(_) => 0,
+                WhereClause::AliasEq(_) => 1,
+                WhereClause::LifetimeOutlives(_) => 2,
+                WhereClause::TypeOutlives(_) => 3,
+            });
+            QuantifiedWhereClauses::from_iter(interner, unique)
+        });
+        DynTy { bounds, lifetime }
+    }
+
+    /// Returns true if `self` and `other` have the same lifetime, and the
+    /// same bounds up to their order and duplicates.
+    pub fn is_equivalent(&self, interner: I, other: &DynTy<I>) -> bool {
+        let bounds = self.bounds.skip_binders().as_slice(interner);
+        let other_bounds = other.bounds.skip_binders().as_slice(interner);
+        self.lifetime == other.lifetime
+            && self.bounds.binders == other.bounds.binders
+            && bounds.iter().all(|bound| other_bounds.contains(bound))
+            && other_bounds.iter().all(|bound| bounds.contains(bound))
+    }
+}
+
 /// A type, lifetime or constant whose value is being inferred.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InferenceVar {
     index: u32,
 }
@@ -1078,6 +1505,11 @@ impl InferenceVar {
 /// A function signature.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, HasInterner, Debug)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnSig<I: Interner> {
     pub abi: I::FnAbi,
     pub safety: Safety,
@@ -1085,6 +1517,11 @@ pub struct FnSig<I: Interner> {
 }
 /// A wrapper for the substs on a Fn.
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnSubst<I: Interner>(pub Substitution<I>);
 
 impl<I: Interner> Copy for FnSubst<I> where I::InternedSubstitution: Copy {}
@@ -1093,6 +1530,11 @@ impl<I: Interner> Copy for FnSubst<I> where I::InternedSubstitution: Copy {}
 /// and we use deBruijn indices within `self.ty`
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnPointer<I: Interner> {
     pub num_binders: usize,
     pub sig: FnSig<I>,
@@ -1132,6 +1574,16 @@ pub struct Const<I: Interner> {
 }
 
 impl<I: Interner> Const<I> {
+    /// Compute the outer exclusive binder for Const
+    fn outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        let data = self.data(interner);
+        let ty_binder = data.ty.outer_exclusive_binder(interner);
+        match data.value {
+            ConstValue::BoundVar(bound_var) => ty_binder.max(bound_var.debruijn.shifted_in()),
+            _ => ty_binder,
+        }
+    }
+
     /// Create a `Const` using something that can be cast to const data.
     pub fn new(interner: I, data: impl CastTo<ConstData<I>>) -> Self {
         Const {
@@ -1181,6 +1633,11 @@ impl<I: Interner> Const<I> {
 
 /// Constant data, containing the constant's type and value.
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ConstData<I: Interner> {
     /// Type that holds the constant.
     pub ty: Ty<I>,
@@ -1190,6 +1647,11 @@ pub struct ConstData<I: Interner> {
 
 /// A constant value, not necessarily concrete.
 #[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum ConstValue<I: Interner> {
     /// Bound var (e.g. a parameter).
     BoundVar(BoundVar),
@@ -1213,6 +1675,11 @@ impl<I: Interner> ConstData<I> {
 /// Concrete constant, whose value is known (as opposed to
 /// inferred constants and placeholders).
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ConcreteConst<I: Interner> {
     /// The interned constant.
     pub interned: I::InternedConcreteConst,
@@ -1301,10 +1768,23 @@ impl<I: Interner> Lifetime<I> {
             LifetimeData::Erased => TypeFlags::HAS_RE_ERASED,
         }
     }
+
+    /// Compute the outer exclusive binder for Lifetime
+    fn outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        match self.data(interner) {
+            LifetimeData::BoundVar(bound_var) => bound_var.debruijn.shifted_in(),
+            _ => DebruijnIndex::INNERMOST,
+        }
+    }
 }
 
 /// Lifetime data, including what kind of lifetime it is and what it points to.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum LifetimeData<I: Interner> {
     /// See TyKind::BoundVar.
     BoundVar(BoundVar),
@@ -1337,6 +1817,7 @@ impl<I: Interner> LifetimeData<I> {
 /// Two indexes are required, the one of the universe itself
 /// and the relative index inside the universe.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlaceholderIndex {
     /// Index *of* the universe.
     pub ui: UniverseIndex,
@@ -1376,6 +1857,7 @@ impl PlaceholderIndex {
 /// inference process.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TyVariableKind {
     General,
     Integer,
@@ -1385,6 +1867,11 @@ pub enum TyVariableKind {
 /// The "kind" of variable. Type, lifetime or constant.
 #[derive(Clone, PartialEq, Eq, Hash)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum VariableKind<I: Interner> {
     Ty(TyVariableKind),
     Lifetime,
@@ -1442,6 +1929,12 @@ impl<I: Interner> GenericArg<I> {
         I::generic_arg_data(interner, &self.interned)
     }
 
+    /// Returns an iterator over this argument and the types, lifetimes
+    /// and consts nested in it, in pre-order; see `VisitExt::walk`.
+    pub fn walk(&self, interner: I) -> TypeWalker<I> {
+        VisitExt::walk(self, interner)
+    }
+
     /// Asserts that this is a type argument.
     pub fn assert_ty_ref(&self, interner: I) -> &Ty<I> {
         self.ty(interner).unwrap()
@@ -1499,7 +1992,7 @@ impl<I: Interner> GenericArg<I> {
                 let data = constant.data(interner);
                 let flags = data.ty.data(interner).flags;
                 match data.value {
-                    ConstValue::BoundVar(_) => flags,
+                    ConstValue::BoundVar(_) => flags | TypeFlags::HAS_CT_BOUND,
                     ConstValue::InferenceVar(_) => {
                         flags | TypeFlags::HAS_CT_INFER | TypeFlags::STILL_FURTHER_SPECIALIZABLE
                     }
@@ -1513,10 +2006,24 @@ impl<I: Interner> GenericArg<I> {
             }
         }
     }
+
+    /// Compute the outer exclusive binder for GenericArg<I>
+    fn outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        match self.data(interner) {
+            GenericArgData::Ty(ty) => ty.outer_exclusive_binder(interner),
+            GenericArgData::Lifetime(lifetime) => lifetime.outer_exclusive_binder(interner),
+            GenericArgData::Const(constant) => constant.outer_exclusive_binder(interner),
+        }
+    }
 }
 
 /// Generic arguments data.
 #[derive(Clone, PartialEq, Eq, Hash, Visit, Fold, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum GenericArgData<I: Interner> {
     /// Type argument
     Ty(Ty<I>),
@@ -1543,6 +2050,14 @@ impl<I: Interner> GenericArgData<I> {
 
 /// A value with an associated variable kind.
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "I: SerdeInterner, T: serde::Serialize",
+        deserialize = "I: SerdeInterner, T: serde::Deserialize<'de>"
+    ))
+)]
 pub struct WithKind<I: Interner, T> {
     /// The associated variable kind.
     pub kind: VariableKind<I>,
@@ -1602,11 +2117,18 @@ pub type CanonicalVarKind<I: Interner> = WithKind<I, UniverseIndex>;
 
 /// An alias, which is a trait indirection such as a projection or opaque type.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum AliasTy<I: Interner> {
     /// An associated type projection.
     Projection(ProjectionTy<I>),
     /// An opaque type.
     Opaque(OpaqueTy<I>),
+    /// An inherent associated type projection.
+    Inherent(InherentProjectionTy<I>),
 }
 
 impl<I: Interner> Copy for AliasTy<I> where I::InternedSubstitution: Copy {}
@@ -1626,12 +2148,33 @@ impl<I: Interner> AliasTy<I> {
             AliasTy::Opaque(opaque_ty) => {
                 TypeFlags::HAS_TY_OPAQUE | opaque_ty.substitution.compute_flags(interner)
             }
+            AliasTy::Inherent(inherent_ty) => {
+                TypeFlags::HAS_TY_PROJECTION | inherent_ty.substitution.compute_flags(interner)
+            }
+        }
+    }
+
+    /// Compute the outer exclusive binder for AliasTy
+    fn outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        match self {
+            AliasTy::Projection(projection_ty) => {
+                projection_ty.substitution.outer_exclusive_binder(interner)
+            }
+            AliasTy::Opaque(opaque_ty) => opaque_ty.substitution.outer_exclusive_binder(interner),
+            AliasTy::Inherent(inherent_ty) => {
+                inherent_ty.substitution.outer_exclusive_binder(interner)
+            }
         }
     }
 }
 
 /// A projection `<P0 as TraitName<P1..Pn>>::AssocItem<Pn+1..Pm>`.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProjectionTy<I: Interner> {
     /// The id for the associated type member.
     pub associated_ty_id: AssocTypeId<I>,
@@ -1654,6 +2197,11 @@ impl<I: Interner> ProjectionTy<I> {
 
 /// An opaque type `opaque type T<..>: Trait = HiddenTy`.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTy<I: Interner> {
     /// The id for the opaque type.
     pub opaque_ty_id: OpaqueTyId<I>,
@@ -1663,6 +2211,34 @@ pub struct OpaqueTy<I: Interner> {
 
 impl<I: Interner> Copy for OpaqueTy<I> where I::InternedSubstitution: Copy {}
 
+/// A projection `<P0>::AssocItem` of an inherent associated type, i.e. one
+/// declared in an inherent impl (`impl Foo { type Bar = ...; }`) rather than
+/// in a trait.
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct InherentProjectionTy<I: Interner> {
+    /// The id for the inherent associated type.
+    pub associated_ty_id: AssocTypeId<I>,
+    /// The substitution for the projection, containing only the `Self` type.
+    pub substitution: Substitution<I>,
+}
+
+impl<I: Interner> Copy for InherentProjectionTy<I> where I::InternedSubstitution: Copy {}
+
+impl<I: Interner> InherentProjectionTy<I> {
+    /// Gets the `Self` type of this projection.
+    pub fn self_type_parameter(&self, interner: I) -> Ty<I> {
+        self.substitution
+            .at(interner, 0)
+            .assert_ty_ref(interner)
+            .clone()
+    }
+}
+
 /// A trait reference describes the relationship between a type and a trait.
 /// This can be used in two forms:
 /// - `P0: Trait<P1..Pn>` (e.g. `i32: Copy`), which mentions that the type
@@ -1670,6 +2246,11 @@ impl<I: Interner> Copy for OpaqueTy<I> where I::InternedSubstitution: Copy {}
 /// - `<P0 as Trait<P1..Pn>>` (e.g. `i32 as Copy`), which casts the type to
 ///   that specific trait.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitRef<I: Interner> {
     /// The trait id.
     pub trait_id: TraitId<I>,
@@ -1708,6 +2289,11 @@ impl<I: Interner> TraitRef<I> {
 /// is a superset of the value of `'b`.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct LifetimeOutlives<I: Interner> {
     pub a: Lifetime<I>,
     pub b: Lifetime<I>,
@@ -1718,6 +2304,11 @@ impl<I: Interner> Copy for LifetimeOutlives<I> where I::InternedLifetime: Copy {
 /// Type outlives, which for `T: 'a` checks that the type `T`
 /// lives at least as long as the lifetime `'a`
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TypeOutlives<I: Interner> {
     /// The type which must outlive the given lifetime.
     pub ty: Ty<I>,
@@ -1734,6 +2325,11 @@ where
 
 /// Where clauses that can be written by a Rust programmer.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, SuperVisit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum WhereClause<I: Interner> {
     /// Type implements a trait.
     Implemented(TraitRef<I>),
@@ -1755,6 +2351,11 @@ where
 
 /// Checks whether a type or trait ref is well-formed.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum WellFormed<I: Interner> {
     /// A predicate which is true when some trait ref is well-formed.
     /// For example, given the following trait definitions:
@@ -1793,6 +2394,11 @@ where
 
 /// Checks whether a type or trait ref can be derived from the contents of the environment.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum FromEnv<I: Interner> {
     /// A predicate which enables deriving everything which should be true if we *know* that
     /// some trait ref is well-formed. For example given the above trait definitions, we can use
@@ -1832,6 +2438,11 @@ where
 /// logical statement. As much as possible, the Chalk solver should avoid
 /// decomposing this enum, and instead treat its values opaquely.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, SuperVisit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum DomainGoal<I: Interner> {
     /// Simple goal that is true if the where clause is true.
     Holds(WhereClause<I>),
@@ -1901,6 +2512,13 @@ pub enum DomainGoal<I: Interner> {
 
     /// Used to indicate that a trait is object safe.
     ObjectSafe(TraitId<I>),
+
+    /// Used to indicate that a function item or a non-capturing closure can
+    /// be coerced to a function pointer.
+    FnPtrCoercion(FnPtrCoercion<I>),
+
+    /// A predicate of the host, which chalk knows nothing about.
+    Custom(CustomPredicate<I>),
 }
 
 impl<I: Interner> Copy for DomainGoal<I>
@@ -1908,6 +2526,7 @@ where
     I::InternedSubstitution: Copy,
     I::InternedLifetime: Copy,
     I::InternedType: Copy,
+    I::CustomPredicate: Copy,
 {
 }
 
@@ -1992,6 +2611,11 @@ impl<I: Interner> DomainGoal<I> {
 /// Equality goal: tries to prove that two values are equal.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct EqGoal<I: Interner> {
     pub a: GenericArg<I>,
     pub b: GenericArg<I>,
@@ -2002,6 +2626,11 @@ impl<I: Interner> Copy for EqGoal<I> where I::InternedGenericArg: Copy {}
 /// Subtype goal: tries to prove that `a` is a subtype of `b`
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct SubtypeGoal<I: Interner> {
     pub a: Ty<I>,
     pub b: Ty<I>,
@@ -2015,6 +2644,11 @@ impl<I: Interner> Copy for SubtypeGoal<I> where I::InternedType: Copy {}
 /// `U = V`.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct Normalize<I: Interner> {
     pub alias: AliasTy<I>,
     pub ty: Ty<I>,
@@ -2027,9 +2661,46 @@ where
 {
 }
 
+/// Proves that a value of type `source`, a function item or a closure which
+/// captures nothing, can be coerced to the function pointer type `target`.
+/// For example, given `fn foo(x: u8) -> u32`, the goal
+/// `FnPtrCoercion(foo, fn(u8) -> u32)` holds, and so does the goal with
+/// `unsafe fn(u8) -> u32` as its target.
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip, HasInterner)]
+#[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct FnPtrCoercion<I: Interner> {
+    pub source: Ty<I>,
+    pub target: Ty<I>,
+}
+
+impl<I: Interner> Copy for FnPtrCoercion<I> where I::InternedType: Copy {}
+
+/// A predicate of the host (see `Interner::CustomPredicate`), like
+/// `ConstEvaluatable(C)`. Chalk walks through it with the methods of the
+/// interner, and proves it with the clauses the host gives for it.
+#[derive(Clone, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct CustomPredicate<I: Interner>(pub I::CustomPredicate);
+
+impl<I: Interner> Copy for CustomPredicate<I> where I::CustomPredicate: Copy {}
+
 /// Proves **equality** between an alias and a type.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, Zip)]
 #[allow(missing_docs)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AliasEq<I: Interner> {
     pub alias: AliasTy<I>,
     pub ty: Ty<I>,
@@ -2054,6 +2725,14 @@ impl<I: Interner> HasInterner for AliasEq<I> {
 /// (IOW, we use deBruijn indices, where binders are introduced in reverse order
 /// of `self.binders`.)
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "T: HasInterner + serde::Serialize, T::Interner: SerdeInterner",
+        deserialize = "T: HasInterner + serde::Deserialize<'de>, T::Interner: SerdeInterner"
+    ))
+)]
 pub struct Binders<T: HasInterner> {
     /// The binders that quantify over the value.
     pub binders: VariableKinds<T::Interner>,
@@ -2292,6 +2971,11 @@ where
 /// `conditions = cond_1 && cond_2 && ...` is the conjunction of the individual
 /// conditions.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProgramClauseImplication<I: Interner> {
     /// The consequence of the clause, which holds if the conditions holds.
     pub consequence: DomainGoal<I>,
@@ -2308,6 +2992,7 @@ pub struct ProgramClauseImplication<I: Interner> {
 
 /// Specifies how important an implication is.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ClausePriority {
     /// High priority, the solver should prioritize this.
     High,
@@ -2316,7 +3001,7 @@ pub enum ClausePriority {
     Low,
 }
 
-impl std::ops::BitAnd for ClausePriority {
+impl core::ops::BitAnd for ClausePriority {
     type Output = ClausePriority;
     fn bitand(self, rhs: ClausePriority) -> Self::Output {
         match (self, rhs) {
@@ -2328,6 +3013,11 @@ impl std::ops::BitAnd for ClausePriority {
 
 /// Contains the data for a program clause.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ProgramClauseData<I: Interner>(pub Binders<ProgramClauseImplication<I>>);
 
 impl<I: Interner> ProgramClauseImplication<I> {
@@ -2380,6 +3070,45 @@ impl<I: Interner> ProgramClause<I> {
         Self::new(interner, new_clause)
     }
 
+    /// The goal which holds when this clause does, if it has no
+    /// constraints: `forall<T> { if (C) { D } }` for the clause
+    /// `forall<T> { D :- C }`, where the conditions `C` are assumed as the
+    /// clauses of [`Goal::to_program_clauses`], elaborated into `FromEnv`
+    /// clauses as lowering does for the hypotheses of an `if`. Returns
+    /// `None` if one of the conditions cannot be assumed. The inverse of
+    /// [`Goal::to_program_clauses`] for such clauses.
+    pub fn to_goal(&self, interner: I) -> Option<Goal<I>> {
+        let implication = &self.data(interner).0;
+        let value = implication.skip_binders();
+        if !value.constraints.is_empty(interner) {
+            return None;
+        }
+        let mut goal: Goal<I> = value.consequence.clone().cast(interner);
+        if !value.conditions.is_empty(interner) {
+            // The conditions are proven from the last one, so the first
+            // hypothesis is the last condition.
+            let mut hypotheses = vec![];
+            for condition in value.conditions.iter(interner).rev() {
+                hypotheses.extend(
+                    condition
+                        .to_program_clauses(interner)?
+                        .into_iter()
+                        .map(|clause| clause.into_from_env_clause(interner)),
+                );
+            }
+            goal = goal.implied_by(interner, ProgramClauses::from_iter(interner, hypotheses));
+        }
+        if implication.binders.is_empty(interner) {
+            goal.shifted_out(interner).ok()
+        } else {
+            Some(goal.quantify(
+                interner,
+                QuantifierKind::ForAll,
+                implication.binders.clone(),
+            ))
+        }
+    }
+
     /// Get the interned program clause.
     pub fn interned(&self) -> &I::InternedProgramClause {
         &self.interned
@@ -2397,6 +3126,14 @@ impl<I: Interner> ProgramClause<I> {
 /// first appearance; the kind/universe of the variable is recorded in the
 /// `binders` field.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "T: HasInterner + serde::Serialize, T::Interner: SerdeInterner",
+        deserialize = "T: HasInterner + serde::Deserialize<'de>, T::Interner: SerdeInterner"
+    ))
+)]
 pub struct Canonical<T: HasInterner> {
     /// The item that is canonicalized.
     pub value: T,
@@ -2415,16 +3152,91 @@ impl<T: HasInterner> HasInterner for Canonical<T> {
 /// distinctions.
 ///
 /// To produce one of these values, use the `u_canonicalize` method.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+///
+/// A `UCanonical` also stores a 64-bit fingerprint of its contents,
+/// computed once when it is created with `UCanonical::new`. Hashing a
+/// `UCanonical` only hashes the fingerprint, and comparing two of them
+/// compares the fingerprints before the (possibly large) values, so
+/// looking up goals in tables and caches does not need to traverse the
+/// goals. The fields are therefore private, so that they cannot be
+/// modified after creation.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(bound(serialize = "T: HasInterner + serde::Serialize, T::Interner: SerdeInterner"))
+)]
 pub struct UCanonical<T: HasInterner> {
-    /// The wrapped `Canonical`.
-    pub canonical: Canonical<T>,
+    canonical: Canonical<T>,
+    universes: usize,
+    /// Computed from the other fields, rather than trusted, when
+    /// deserializing.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    fingerprint: u64,
+}
+
+impl<T: HasInterner + Hash> UCanonical<T> {
+    /// Creates a universe canonical value, computing its fingerprint.
+    pub fn new(canonical: Canonical<T>, universes: usize) -> Self {
+        let mut hasher = FxHasher::default();
+        canonical.hash(&mut hasher);
+        universes.hash(&mut hasher);
+        UCanonical {
+            canonical,
+            universes,
+            fingerprint: hasher.finish(),
+        }
+    }
+}
 
-    /// The number of universes that have been collapsed.
-    pub universes: usize,
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for UCanonical<T>
+where
+    T: HasInterner + Hash + serde::Deserialize<'de>,
+    T::Interner: SerdeInterner,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// The fields of a `UCanonical` which are serialized.
+        #[derive(Deserialize)]
+        #[serde(bound(
+            deserialize = "T: HasInterner + serde::Deserialize<'de>, T::Interner: SerdeInterner"
+        ))]
+        struct Fields<T: HasInterner> {
+            canonical: Canonical<T>,
+            universes: usize,
+        }
+
+        let Fields {
+            canonical,
+            universes,
+        } = Fields::deserialize(deserializer)?;
+        Ok(UCanonical::new(canonical, universes))
+    }
 }
 
 impl<T: HasInterner> UCanonical<T> {
+    /// A hash of the canonical value and the number of universes. It only
+    /// depends on the contents, so it is the same for equal values created
+    /// in different places (or processes, given the same interned data).
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// The wrapped `Canonical`.
+    pub fn canonical(&self) -> &Canonical<T> {
+        &self.canonical
+    }
+
+    /// Unwraps the `Canonical`.
+    pub fn into_canonical(self) -> Canonical<T> {
+        self.canonical
+    }
+
+    /// The number of universes that have been collapsed.
+    pub fn universes(&self) -> usize {
+        self.universes
+    }
+
     /// Checks whether the universe canonical value is a trivial
     /// substitution (e.g. an identity substitution).
     pub fn is_trivial_substitution(
@@ -2474,6 +3286,31 @@ impl<T: HasInterner> UCanonical<T> {
     }
 }
 
+impl<T: HasInterner + PartialEq> PartialEq for UCanonical<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.fingerprint == other.fingerprint
+            && self.universes == other.universes
+            && self.canonical == other.canonical
+    }
+}
+
+impl<T: HasInterner + Eq> Eq for UCanonical<T> {}
+
+impl<T: HasInterner> Hash for UCanonical<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.fingerprint);
+    }
+}
+
+impl<T: HasInterner + core::fmt::Debug> core::fmt::Debug for UCanonical<T> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.debug_struct("UCanonical")
+            .field("canonical", &self.canonical)
+            .field("universes", &self.universes)
+            .finish()
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, HasInterner)]
 /// A general goal; this is the full range of questions you can pose to Chalk.
 pub struct Goal<I: Interner> {
@@ -2532,6 +3369,89 @@ impl<I: Interner> Goal<I> {
         GoalData::Implies(predicates, self).intern(interner)
     }
 
+    /// The program clauses which hold if this goal is assumed, when it is
+    /// made of domain goals under `forall` binders, conjunctions and
+    /// implications, like the hypotheses some generated clause sets are
+    /// written as: `forall<T> { if (T: A) { T: B } }` gives the clause
+    /// `forall<T> { T: B :- T: A }`. Returns `None` for the goals which
+    /// cannot be assumed, like `exists` goals, negations and equalities,
+    /// or implications with hypotheses which cannot be turned back into
+    /// goals (see [`ProgramClause::to_goal`]).
+    pub fn to_program_clauses(&self, interner: I) -> Option<Vec<ProgramClause<I>>> {
+        match self.data(interner) {
+            GoalData::DomainGoal(domain_goal) => {
+                let implication = ProgramClauseImplication {
+                    consequence: domain_goal.clone().shifted_in(interner),
+                    conditions: Goals::empty(interner),
+                    constraints: Constraints::empty(interner),
+                    priority: ClausePriority::High,
+                };
+                Some(vec![ProgramClauseData(Binders::empty(
+                    interner,
+                    implication,
+                ))
+                .intern(interner)])
+            }
+            GoalData::All(goals) => {
+                let mut clauses = vec![];
+                for goal in goals.iter(interner) {
+                    clauses.extend(goal.to_program_clauses(interner)?);
+                }
+                Some(clauses)
+            }
+            GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
+                let clauses = subgoal.skip_binders().to_program_clauses(interner)?;
+                Some(
+                    clauses
+                        .into_iter()
+                        .map(|clause| {
+                            let implication = Binders::new(
+                                subgoal.binders.clone(),
+                                clause.data(interner).0.clone(),
+                            )
+                            .fuse_binders(interner);
+                            ProgramClauseData(implication).intern(interner)
+                        })
+                        .collect(),
+                )
+            }
+            GoalData::Implies(hypotheses, subgoal) => {
+                // The hypotheses become conditions of the clauses of the
+                // subgoal, under their binders. The conditions of a clause
+                // are proven from the last one, so those of the outer
+                // implications come last.
+                let conditions = hypotheses
+                    .iter(interner)
+                    .rev()
+                    .map(|hypothesis| hypothesis.to_goal(interner))
+                    .collect::<Option<Vec<_>>>()?;
+                let clauses = subgoal.to_program_clauses(interner)?;
+                Some(
+                    clauses
+                        .into_iter()
+                        .map(|clause| {
+                            let implication = clause.data(interner).0.map_ref(|implication| {
+                                ProgramClauseImplication {
+                                    conditions: Goals::from_iter(
+                                        interner,
+                                        implication.conditions.iter(interner).cloned().chain(
+                                            conditions
+                                                .iter()
+                                                .map(|goal| goal.clone().shifted_in(interner)),
+                                        ),
+                                    ),
+                                    ..implication.clone()
+                                }
+                            });
+                            ProgramClauseData(implication).intern(interner)
+                        })
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+
     /// True if this goal is "trivially true" -- i.e., no work is
     /// required to prove it.
     pub fn is_trivially_true(&self, interner: I) -> bool {
@@ -2569,10 +3489,80 @@ where
             GoalData::All(Goals::empty(interner)).intern(interner)
         }
     }
+
+    /// Simplifies this goal without changing whether it holds: nested
+    /// conjunctions are flattened, duplicate subgoals of a conjunction are
+    /// dropped, and so are the domain goals for which `is_trivially_true`
+    /// returns true. This reduces the number of subgoals a solver has to
+    /// set up for machine-generated goals.
+    pub fn simplify(self, interner: I, is_trivially_true: &dyn Fn(&DomainGoal<I>) -> bool) -> Self {
+        match self.data(interner) {
+            GoalData::All(_) => {
+                let mut subgoals = vec![];
+                self.push_conjuncts(
+                    interner,
+                    is_trivially_true,
+                    &mut subgoals,
+                    &mut FxHashSet::default(),
+                );
+                Goal::all(interner, subgoals)
+            }
+            GoalData::DomainGoal(domain_goal) if is_trivially_true(domain_goal) => {
+                GoalData::All(Goals::empty(interner)).intern(interner)
+            }
+            GoalData::Quantified(kind, subgoal) => GoalData::Quantified(
+                *kind,
+                subgoal.map_ref(|subgoal| subgoal.clone().simplify(interner, is_trivially_true)),
+            )
+            .intern(interner),
+            GoalData::Implies(clauses, subgoal) => GoalData::Implies(
+                clauses.clone(),
+                subgoal.clone().simplify(interner, is_trivially_true),
+            )
+            .intern(interner),
+            GoalData::Not(subgoal) => subgoal
+                .clone()
+                .simplify(interner, is_trivially_true)
+                .negate(interner),
+            _ => self,
+        }
+    }
+
+    /// Pushes the simplified conjuncts of `self` that are not in `seen` yet.
+    fn push_conjuncts(
+        self,
+        interner: I,
+        is_trivially_true: &dyn Fn(&DomainGoal<I>) -> bool,
+        subgoals: &mut Vec<Goal<I>>,
+        seen: &mut FxHashSet<Goal<I>>,
+    ) {
+        match self.data(interner) {
+            GoalData::All(goals) => {
+                for goal in goals.iter(interner) {
+                    goal.clone()
+                        .push_conjuncts(interner, is_trivially_true, subgoals, seen);
+                }
+            }
+            _ => {
+                let goal = self.simplify(interner, is_trivially_true);
+                if goal.is_trivially_true(interner) {
+                    return;
+                }
+                if seen.insert(goal.clone()) {
+                    subgoals.push(goal);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
 /// A general goal; this is the full range of questions you can pose to Chalk.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum GoalData<I: Interner> {
     /// Introduces a binding at depth 0, shifting other bindings up
     /// (deBruijn index).
@@ -2617,6 +3607,7 @@ where
     I::InternedGoals: Copy,
     I::InternedProgramClauses: Copy,
     I::InternedVariableKinds: Copy,
+    I::CustomPredicate: Copy,
 {
 }
 
@@ -2629,6 +3620,7 @@ impl<I: Interner> GoalData<I> {
 
 /// Kinds of quantifiers in the logic, such as `forall` and `exists`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum QuantifierKind {
     /// Universal quantifier `ForAll`.
     ///
@@ -2655,6 +3647,11 @@ pub enum QuantifierKind {
 /// for later checking. This allows for decoupling between type and region
 /// checking in the compiler.
 #[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum Constraint<I: Interner> {
     /// Outlives constraint `'a: 'b`, indicating that the value of `'a` must be
     /// a superset of the value of `'b`.
@@ -2713,6 +3710,59 @@ impl<I: Interner> Substitution<I> {
         Substitute::apply(self, value, interner)
     }
 
+    /// Composes `self` with `other`: each variable is mapped to its value
+    /// in `self`, with `other` applied to that value. Applying the result
+    /// is the same as applying `self`, then `other`.
+    pub fn compose(&self, interner: I, other: &Substitution<I>) -> Substitution<I> {
+        other.apply(self.clone(), interner)
+    }
+
+    /// Applies the answer to a canonical goal back onto the goal it was
+    /// canonicalized from. `free_vars` are the inference variables which
+    /// became the variables of the canonical goal, in order, and `self`
+    /// gives their values; each of them in `value` is replaced by its
+    /// value, shifted in through the binders of `value` it appears under.
+    /// The variables of a canonical answer are left bound to its binders,
+    /// so the result goes under them.
+    pub fn apply_to_inference_vars<T>(
+        &self,
+        interner: I,
+        free_vars: &[GenericArg<I>],
+        value: T,
+    ) -> T::Result
+    where
+        T: Fold<I>,
+    {
+        assert_eq!(
+            free_vars.len(),
+            self.len(interner),
+            "a value is needed for each free variable"
+        );
+        fold::InferenceVarSubst::apply(interner, free_vars, self.as_slice(interner), value)
+    }
+
+    /// The value of each variable, which is bound by the innermost binder.
+    pub fn to_map(&self, interner: I) -> BTreeMap<BoundVar, GenericArg<I>> {
+        self.iter(interner)
+            .enumerate()
+            .map(|(index, arg)| (BoundVar::new(DebruijnIndex::INNERMOST, index), arg.clone()))
+            .collect()
+    }
+
+    /// The substitution giving each variable its value in `map`, or `None`
+    /// unless the variables of `map` are `^0.0`, `^0.1`, ... with none
+    /// missing.
+    pub fn from_map(interner: I, map: BTreeMap<BoundVar, GenericArg<I>>) -> Option<Self> {
+        let is_dense = map
+            .keys()
+            .enumerate()
+            .all(|(index, var)| *var == BoundVar::new(DebruijnIndex::INNERMOST, index));
+        if !is_dense {
+            return None;
+        }
+        Some(Substitution::from_iter(interner, map.into_values()))
+    }
+
     /// Gets an iterator of all type parameters.
     pub fn type_parameters(&self, interner: I) -> impl Iterator<Item = Ty<I>> + '_ {
         self.iter(interner)
@@ -2728,8 +3778,16 @@ impl<I: Interner> Substitution<I> {
         }
         flags
     }
+
+    /// Compute the outer exclusive binder for Substitution<I>
+    fn outer_exclusive_binder(&self, interner: I) -> DebruijnIndex {
+        self.iter(interner)
+            .map(|generic_arg| generic_arg.outer_exclusive_binder(interner))
+            .fold(DebruijnIndex::INNERMOST, DebruijnIndex::max)
+    }
 }
 
+#[derive(FallibleFolder)]
 struct SubstFolder<'i, I: Interner, A: AsParameters<I>> {
     interner: I,
     subst: &'i A,
@@ -2795,15 +3853,13 @@ impl<I: Interner, A: AsParameters<I>> Substitute<I> for A {
     where
         T: Fold<I>,
     {
-        value
-            .fold_with(
-                &mut &SubstFolder {
-                    interner,
-                    subst: self,
-                },
-                DebruijnIndex::INNERMOST,
-            )
-            .unwrap()
+        value.fold_with(
+            &mut SubstFolder {
+                interner,
+                subst: self,
+            },
+            DebruijnIndex::INNERMOST,
+        )
     }
 }
 
@@ -2830,33 +3886,34 @@ impl<'a, I: Interner> ToGenericArg<I> for (usize, &'a VariableKind<I>) {
     }
 }
 
-impl<'i, I: Interner, A: AsParameters<I>> Folder<I> for &SubstFolder<'i, I, A> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+impl<'i, I: Interner, A: AsParameters<I>> Folder<I> for SubstFolder<'i, I, A> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
-    fn fold_free_var_ty(
-        &mut self,
-        bound_var: BoundVar,
-        outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_vars_bound_at_or_above(self.interner, outer_binder) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) -> Ty<I> {
         assert_eq!(bound_var.debruijn, DebruijnIndex::INNERMOST);
         let ty = self.at(bound_var.index);
         let ty = ty.assert_ty_ref(self.interner());
-        Ok(ty.clone().shifted_in_from(self.interner(), outer_binder))
+        ty.clone().shifted_in_from(self.interner(), outer_binder)
     }
 
     fn fold_free_var_lifetime(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         assert_eq!(bound_var.debruijn, DebruijnIndex::INNERMOST);
         let l = self.at(bound_var.index);
         let l = l.assert_lifetime_ref(self.interner());
-        Ok(l.clone().shifted_in_from(self.interner(), outer_binder))
+        l.clone().shifted_in_from(self.interner(), outer_binder)
     }
 
     fn fold_free_var_const(
@@ -2864,11 +3921,11 @@ impl<'i, I: Interner, A: AsParameters<I>> Folder<I> for &SubstFolder<'i, I, A> {
         _ty: Ty<I>,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         assert_eq!(bound_var.debruijn, DebruijnIndex::INNERMOST);
         let c = self.at(bound_var.index);
         let c = c.assert_const_ref(self.interner());
-        Ok(c.clone().shifted_in_from(self.interner(), outer_binder))
+        c.clone().shifted_in_from(self.interner(), outer_binder)
     }
 
     fn interner(&self) -> I {
@@ -2911,7 +3968,7 @@ macro_rules! interned_slice_common {
             }
 
             /// Get an iterator over the elements of the sequence.
-            pub fn iter(&self, interner: I) -> std::slice::Iter<'_, $elem> {
+            pub fn iter(&self, interner: I) -> core::slice::Iter<'_, $elem> {
                 self.as_slice(interner).iter()
             }
 
@@ -2992,6 +4049,132 @@ interned_slice!(
     intern_constraints => InternedConstraints
 );
 
+impl<I: Interner> Constraints<I> {
+    /// These constraints without those which trivially hold (`'a: 'a` and
+    /// `'static: 'a`) and without repeats, in an order which does not
+    /// depend on the order they were found in, so that solutions with the
+    /// same constraints are equal.
+    pub fn normalized(&self, interner: I) -> Self {
+        let mut seen = FxHashSet::default();
+        let mut constraints: Vec<_> = self
+            .iter(interner)
+            .filter(|constraint| match &constraint.goal {
+                Constraint::LifetimeOutlives(a, b) => {
+                    a != b && !matches!(a.data(interner), LifetimeData::Static)
+                }
+                Constraint::TypeOutlives(..) => true,
+            })
+            .filter(|&constraint| seen.insert(constraint))
+            .map(|constraint| {
+                let mut hasher = FxHasher::default();
+                constraint.hash(&mut hasher);
+                (hasher.finish(), constraint)
+            })
+            .collect();
+        // The rare constraints whose hashes collide keep the order they
+        // were found in.
+        constraints.sort_by_key(|&(hash, _)| hash);
+        Constraints::from_iter(
+            interner,
+            constraints
+                .into_iter()
+                .map(|(_, constraint)| constraint.clone()),
+        )
+    }
+
+    /// These constraints without those implied by the outlives facts of
+    /// `environment`, or of their own environments: with the facts
+    /// `'a: 'b` and `'b: 'c`, the constraint `'a: 'c` is implied, and so is
+    /// `T: 'c` with the facts `T: 'a` and `'a: 'c`. Only the facts without
+    /// variables are used, since the variables of `environment` need not
+    /// be those of the constraints.
+    pub fn without_implied(&self, interner: I, environment: &Environment<I>) -> Self {
+        let facts = OutlivesFacts::new(interner, environment);
+        Constraints::from_iter(
+            interner,
+            self.iter(interner)
+                .filter(|constraint| {
+                    !facts
+                        .with(interner, &constraint.environment)
+                        .implies(interner, &constraint.goal)
+                })
+                .cloned(),
+        )
+    }
+}
+
+/// The outlives facts of environments, for `Constraints::without_implied`.
+#[derive(Clone)]
+struct OutlivesFacts<I: Interner> {
+    lifetimes: Vec<(Lifetime<I>, Lifetime<I>)>,
+    tys: Vec<(Ty<I>, Lifetime<I>)>,
+}
+
+impl<I: Interner> OutlivesFacts<I> {
+    fn new(interner: I, environment: &Environment<I>) -> Self {
+        OutlivesFacts {
+            lifetimes: vec![],
+            tys: vec![],
+        }
+        .with(interner, environment)
+    }
+
+    /// These facts, and those of `environment`: its clauses which are
+    /// outlives goals without variables, conditions or constraints.
+    fn with(&self, interner: I, environment: &Environment<I>) -> Self {
+        let mut facts = self.clone();
+        for clause in environment.clauses.iter(interner) {
+            let implication = &clause.data(interner).0;
+            let value = implication.skip_binders();
+            if !implication.binders.is_empty(interner)
+                || !value.conditions.is_empty(interner)
+                || !value.constraints.is_empty(interner)
+                || value.consequence.has_free_vars(interner)
+            {
+                continue;
+            }
+            match &value.consequence {
+                DomainGoal::Holds(WhereClause::LifetimeOutlives(LifetimeOutlives { a, b })) => {
+                    facts.lifetimes.push((a.clone(), b.clone()))
+                }
+                DomainGoal::Holds(WhereClause::TypeOutlives(TypeOutlives { ty, lifetime })) => {
+                    facts.tys.push((ty.clone(), lifetime.clone()))
+                }
+                _ => {}
+            }
+        }
+        facts
+    }
+
+    fn implies(&self, interner: I, constraint: &Constraint<I>) -> bool {
+        match constraint {
+            Constraint::LifetimeOutlives(a, b) => self.outlives(interner, a, b),
+            Constraint::TypeOutlives(ty, b) => self
+                .tys
+                .iter()
+                .any(|(fact_ty, a)| fact_ty == ty && self.outlives(interner, a, b)),
+        }
+    }
+
+    /// Whether `a: b` follows from the facts.
+    fn outlives(&self, interner: I, a: &Lifetime<I>, b: &Lifetime<I>) -> bool {
+        let mut reached = vec![a];
+        let mut index = 0;
+        while let Some(&lifetime) = reached.get(index) {
+            if lifetime == b || matches!(lifetime.data(interner), LifetimeData::Static) {
+                return true;
+            }
+            for (fact_a, fact_b) in &self.lifetimes {
+                if fact_a == lifetime && !reached.contains(&fact_b) {
+                    reached.push(fact_b);
+                }
+            }
+            index += 1;
+        }
+        false
+    }
+}
+
 interned_slice!(
     Substitution,
     substitution_data => GenericArg<I>,
@@ -3038,6 +4221,11 @@ impl<I: Interner> Variances<I> {
 /// and the constraints represents any region constraints that must
 /// additionally be solved.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ConstrainedSubst<I: Interner> {
     /// The substitution that is being constrained.
     ///
@@ -3050,6 +4238,11 @@ pub struct ConstrainedSubst<I: Interner> {
 
 /// The resulting substitution after solving a goal.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AnswerSubst<I: Interner> {
     /// The substitution result.
     ///
@@ -3066,7 +4259,7 @@ pub struct AnswerSubst<I: Interner> {
 /// Logic to decide the Variance for a given subst
 pub trait UnificationDatabase<I>
 where
-    Self: std::fmt::Debug,
+    Self: core::fmt::Debug,
     I: Interner,
 {
     /// Gets the variances for the substitution of a fn def
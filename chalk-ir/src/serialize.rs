@@ -0,0 +1,109 @@
+//! Serialization of goals, clauses, types and so on with `serde`.
+//!
+//! Interned values are serialized as the data they intern, and
+//! re-interned when deserialized, so the serialized form does not depend
+//! on how the interner represents them. Definition ids, on the other hand,
+//! are serialized as-is: it is up to the interner to make sure they
+//! identify the same definitions when deserialized (e.g., in another
+//! process solving for the same program).
+
+use crate::interner::Interner;
+use crate::*;
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// An interner whose values can be serialized and deserialized.
+pub trait SerdeInterner:
+    Interner<
+    DefId: Serialize + DeserializeOwned,
+    InternedAdtId: Serialize + DeserializeOwned,
+    Identifier: Serialize + DeserializeOwned,
+    FnAbi: Serialize + DeserializeOwned,
+    CustomTy: Serialize + DeserializeOwned,
+    CustomPredicate: Serialize + DeserializeOwned,
+    InternedConcreteConst: Serialize + DeserializeOwned,
+>
+{
+    /// Returns the interner used to access the data of interned values
+    /// when serializing them, and to intern them again when deserializing.
+    fn serde_interner() -> Self;
+}
+
+/// Implements `Serialize` and `Deserialize` for an interned value, given
+/// how to access its data and how to intern it again (with a
+/// `fn(I, $data) -> $ty<I>`).
+macro_rules! interned_serde {
+    ($ty:ident, $data:ty, |$interner:ident, $value:ident| $get:expr, $intern:path) => {
+        impl<I: SerdeInterner> Serialize for $ty<I> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let $interner = I::serde_interner();
+                let $value = self;
+                $get.serialize(serializer)
+            }
+        }
+
+        impl<'de, I: SerdeInterner> Deserialize<'de> for $ty<I> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let data = <$data>::deserialize(deserializer)?;
+                Ok($intern(I::serde_interner(), data))
+            }
+        }
+    };
+}
+
+interned_serde!(Ty, TyKind<I>, |interner, ty| ty.kind(interner), Ty::new);
+interned_serde!(
+    Lifetime,
+    LifetimeData<I>,
+    |interner, lifetime| lifetime.data(interner),
+    Lifetime::new
+);
+interned_serde!(
+    Const,
+    ConstData<I>,
+    |interner, constant| constant.data(interner),
+    Const::new
+);
+interned_serde!(
+    GenericArg,
+    GenericArgData<I>,
+    |interner, arg| arg.data(interner),
+    GenericArg::new
+);
+interned_serde!(
+    Goal,
+    GoalData<I>,
+    |interner, goal| goal.data(interner),
+    Goal::new
+);
+interned_serde!(
+    ProgramClause,
+    ProgramClauseData<I>,
+    |interner, clause| clause.data(interner),
+    ProgramClause::new
+);
+
+/// Lists are serialized as sequences of their elements.
+macro_rules! interned_slice_serde {
+    ($($seq:ident => $elem:ty),* $(,)?) => {
+        $(
+            interned_serde!(
+                $seq,
+                Vec<$elem>,
+                |interner, seq| seq.as_slice(interner),
+                $seq::from_iter
+            );
+        )*
+    };
+}
+
+interned_slice_serde!(
+    QuantifiedWhereClauses => QuantifiedWhereClause<I>,
+    ProgramClauses => ProgramClause<I>,
+    VariableKinds => VariableKind<I>,
+    CanonicalVarKinds => CanonicalVarKind<I>,
+    Goals => Goal<I>,
+    Constraints => InEnvironment<Constraint<I>>,
+    Substitution => GenericArg<I>,
+    Variances => Variance,
+);
@@ -1,27 +1,27 @@
 //! Traits for visiting bits of IR.
-use std::fmt::Debug;
-use std::ops::ControlFlow;
+use core::fmt::Debug;
+use core::ops::ControlFlow;
 
 use crate::{
-    BoundVar, Const, ConstValue, DebruijnIndex, DomainGoal, Goal, InferenceVar, Interner, Lifetime,
-    LifetimeData, PlaceholderIndex, ProgramClause, Ty, TyKind, WhereClause,
+    BoundVar, Const, ConstValue, CustomPredicate, DebruijnIndex, DomainGoal, Goal, InferenceVar,
+    Interner, Lifetime, LifetimeData, PlaceholderIndex, ProgramClause, Ty, TyKind, WhereClause,
 };
 
 mod binder_impls;
 mod boring_impls;
 pub mod visitors;
 
-pub use visitors::VisitExt;
+pub use visitors::{TypeWalker, VisitExt};
 
 /// Unwraps a `ControlFlow` or propagates its `Break` value.
 /// This replaces the `Try` implementation that would be used
-/// with `std::ops::ControlFlow`.
+/// with `core::ops::ControlFlow`.
 #[macro_export]
 macro_rules! try_break {
     ($expr:expr) => {
         match $expr {
-            std::ops::ControlFlow::Continue(c) => c,
-            std::ops::ControlFlow::Break(b) => return std::ops::ControlFlow::Break(b),
+            core::ops::ControlFlow::Continue(c) => c,
+            core::ops::ControlFlow::Break(b) => return core::ops::ControlFlow::Break(b),
         }
     };
 }
@@ -37,7 +37,9 @@ macro_rules! try_break {
 /// ```
 pub trait Visitor<I: Interner> {
     /// The "break type" of the visitor, often `()`. It represents the result
-    /// the visitor yields when it stops visiting.
+    /// the visitor yields when it stops visiting. Searches can break with
+    /// the item they were looking for, which is then returned directly by
+    /// `visit_with` (see e.g. [`VisitExt::first_placeholder_in`]).
     type BreakTy;
 
     /// Creates a `dyn` value from this visitor. Unfortunately, this
@@ -297,6 +299,7 @@ where
             }
             TyKind::Foreign(foreign_ty) => foreign_ty.visit_with(visitor, outer_binder),
             TyKind::Error => ControlFlow::Continue(()),
+            TyKind::Custom(custom_ty) => interner.visit_custom_ty(custom_ty, visitor, outer_binder),
         }
     }
 }
@@ -393,6 +396,17 @@ impl<I: Interner> SuperVisit<I> for Goal<I> {
     }
 }
 
+impl<I: Interner> Visit<I> for CustomPredicate<I> {
+    fn visit_with<B>(
+        &self,
+        visitor: &mut dyn Visitor<I, BreakTy = B>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<B> {
+        let interner = visitor.interner();
+        interner.visit_custom_predicate(&self.0, visitor, outer_binder)
+    }
+}
+
 impl<I: Interner> Visit<I> for ProgramClause<I> {
     fn visit_with<B>(
         &self,
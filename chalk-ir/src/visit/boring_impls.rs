@@ -11,7 +11,10 @@ use crate::{
     QuantifiedWhereClauses, QuantifierKind, Safety, Scalar, Substitution, SuperVisit, TraitId,
     UintTy, UniverseIndex, Visit, Visitor,
 };
-use std::{marker::PhantomData, sync::Arc};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 /// Convenience function to visit all the items in the iterator it.
 pub fn visit_iter<'i, T, I, B>(
@@ -163,6 +166,7 @@ macro_rules! const_visit {
 
 const_visit!(bool);
 const_visit!(usize);
+const_visit!(i64);
 const_visit!(UniverseIndex);
 const_visit!(PlaceholderIndex);
 const_visit!(QuantifierKind);
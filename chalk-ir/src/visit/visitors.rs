@@ -1,27 +1,269 @@
 //! Visitor helpers
 
-use crate::{BoundVar, ControlFlow, DebruijnIndex, Interner, Visit, Visitor};
+use crate::cast::Cast;
+use crate::{
+    BoundVar, Const, ConstValue, ControlFlow, DebruijnIndex, GenericArg, GenericArgData, Interner,
+    Lifetime, LifetimeData, PlaceholderIndex, SuperVisit, Ty, TyKind, TypeFlags, UniverseIndex,
+    VariableKind, VariableKinds, Visit, Visitor,
+};
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Visitor extensions.
 pub trait VisitExt<I: Interner>: Visit<I> {
     /// Check whether there are free (non-bound) variables.
     fn has_free_vars(&self, interner: I) -> bool {
+        self.first_free_var(interner).is_some()
+    }
+
+    /// Returns the first free (non-bound) variable, if any. The
+    /// variable is relative to the outside of `self`.
+    fn first_free_var(&self, interner: I) -> Option<BoundVar> {
         let flow = self.visit_with(
             &mut FindFreeVarsVisitor { interner },
             DebruijnIndex::INNERMOST,
         );
-        matches!(flow, ControlFlow::Break(_))
+        break_value(flow)
+    }
+
+    /// Returns the first placeholder from the universe `universe`, if any.
+    fn first_placeholder_in(
+        &self,
+        interner: I,
+        universe: UniverseIndex,
+    ) -> Option<PlaceholderIndex> {
+        let flow = self.visit_with(
+            &mut FindPlaceholderVisitor { interner, universe },
+            DebruijnIndex::INNERMOST,
+        );
+        break_value(flow)
+    }
+
+    /// Returns the first variable bound by a binder just outside of `self`
+    /// which is not one of `binders`, or not of its kind, if any. The
+    /// variable is relative to the outside of `self`. Variables bound
+    /// further out are not checked.
+    fn inconsistent_bound_var(&self, interner: I, binders: &VariableKinds<I>) -> Option<BoundVar> {
+        let flow = self.visit_with(
+            &mut BoundVarChecker {
+                interner,
+                binders: binders.as_slice(interner),
+            },
+            DebruijnIndex::INNERMOST,
+        );
+        break_value(flow)
+    }
+
+    /// In debug builds, panics if `self` refers to a variable of the
+    /// binder just outside of it that is not one of `binders`, or not of
+    /// its kind; see `inconsistent_bound_var`. This catches a value which
+    /// was not shifted when moved in or out of binders where it is
+    /// constructed, rather than where the solver stumbles over it.
+    fn debug_assert_bound_vars_consistent(&self, interner: I, binders: &VariableKinds<I>) {
+        if cfg!(debug_assertions) {
+            if let Some(bound_var) = self.inconsistent_bound_var(interner, binders) {
+                panic!(
+                    "bound variable `{:?}` of `{:?}` is inconsistent with the binders `{:?}`",
+                    bound_var,
+                    self,
+                    binders.as_slice(interner)
+                );
+            }
+        }
+    }
+
+    /// Returns an iterator over the types, lifetimes and consts of `self`,
+    /// nested ones included, in pre-order (so a type comes before its
+    /// components). Components beneath binders in `self` are included too,
+    /// and may refer to those binders. The components of a type are only
+    /// visited when the iterator reaches it, so stopping early (e.g. with
+    /// `any`) skips the rest of the value.
+    fn walk(&self, interner: I) -> TypeWalker<I> {
+        let mut stack = ComponentCollector::collect(interner, |collector| {
+            self.visit_with(collector, DebruijnIndex::INNERMOST)
+        });
+        stack.reverse();
+        TypeWalker { interner, stack }
     }
 }
 
 impl<T, I: Interner> VisitExt<I> for T where T: Visit<I> {}
 
+/// Iterator over the types, lifetimes and consts of a value; see
+/// `VisitExt::walk`.
+#[derive(Clone, Debug)]
+pub struct TypeWalker<I: Interner> {
+    interner: I,
+    /// The components left to walk, the next one last.
+    stack: Vec<GenericArg<I>>,
+}
+
+impl<I: Interner> Iterator for TypeWalker<I> {
+    type Item = GenericArg<I>;
+
+    fn next(&mut self) -> Option<GenericArg<I>> {
+        let component = self.stack.pop()?;
+        let interner = self.interner;
+        let mut components =
+            ComponentCollector::collect(interner, |collector| match component.data(interner) {
+                GenericArgData::Ty(ty) => ty.super_visit_with(collector, DebruijnIndex::INNERMOST),
+                GenericArgData::Lifetime(lifetime) => {
+                    lifetime.super_visit_with(collector, DebruijnIndex::INNERMOST)
+                }
+                GenericArgData::Const(constant) => {
+                    constant.super_visit_with(collector, DebruijnIndex::INNERMOST)
+                }
+            });
+        components.reverse();
+        self.stack.append(&mut components);
+        Some(component)
+    }
+}
+
+fn break_value<B>(flow: ControlFlow<B>) -> Option<B> {
+    match flow {
+        ControlFlow::Break(value) => Some(value),
+        ControlFlow::Continue(()) => None,
+    }
+}
+
+/// Collects the outermost types, lifetimes and consts it visits, without
+/// their components.
+struct ComponentCollector<I: Interner> {
+    interner: I,
+    components: Vec<GenericArg<I>>,
+}
+
+impl<I: Interner> ComponentCollector<I> {
+    fn collect(
+        interner: I,
+        visit: impl FnOnce(&mut Self) -> ControlFlow<()>,
+    ) -> Vec<GenericArg<I>> {
+        let mut collector = ComponentCollector {
+            interner,
+            components: vec![],
+        };
+        let _ = visit(&mut collector);
+        collector.components
+    }
+}
+
+impl<I: Interner> Visitor<I> for ComponentCollector<I> {
+    type BreakTy = ();
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, _outer_binder: DebruijnIndex) -> ControlFlow<()> {
+        self.components.push(ty.clone().cast(self.interner));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_lifetime(
+        &mut self,
+        lifetime: &Lifetime<I>,
+        _outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        self.components.push(lifetime.clone().cast(self.interner));
+        ControlFlow::Continue(())
+    }
+
+    fn visit_const(
+        &mut self,
+        constant: &Const<I>,
+        _outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        self.components.push(constant.clone().cast(self.interner));
+        ControlFlow::Continue(())
+    }
+}
+
+/// Finds the first variable of the binder just outside the visited value
+/// which is not one of `binders`, or not of its kind.
+struct BoundVarChecker<'b, I: Interner> {
+    interner: I,
+    binders: &'b [VariableKind<I>],
+}
+
+impl<I: Interner> BoundVarChecker<'_, I> {
+    /// Checks `bound_var`, whose kind is checked by `is_kind`, if it is
+    /// bound by the binder just outside the visited value.
+    fn check(
+        &self,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+        is_kind: impl FnOnce(&VariableKind<I>) -> bool,
+    ) -> ControlFlow<BoundVar> {
+        match bound_var.index_if_bound_at(outer_binder) {
+            Some(index) if !self.binders.get(index).is_some_and(is_kind) => {
+                ControlFlow::Break(BoundVar::new(DebruijnIndex::INNERMOST, index))
+            }
+            _ => ControlFlow::Continue(()),
+        }
+    }
+}
+
+impl<I: Interner> Visitor<I> for BoundVarChecker<'_, I> {
+    type BreakTy = BoundVar;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> ControlFlow<BoundVar> {
+        if !ty.has_vars_bound_at_or_above(self.interner, outer_binder) {
+            return ControlFlow::Continue(());
+        }
+        if let TyKind::BoundVar(bound_var) = ty.kind(self.interner) {
+            self.check(*bound_var, outer_binder, |kind| {
+                matches!(kind, VariableKind::Ty(_))
+            })?;
+        }
+        ty.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_lifetime(
+        &mut self,
+        lifetime: &Lifetime<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<BoundVar> {
+        if let LifetimeData::BoundVar(bound_var) = lifetime.data(self.interner) {
+            self.check(*bound_var, outer_binder, |kind| {
+                matches!(kind, VariableKind::Lifetime)
+            })?;
+        }
+        lifetime.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_const(
+        &mut self,
+        constant: &Const<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<BoundVar> {
+        if let ConstValue::BoundVar(bound_var) = &constant.data(self.interner).value {
+            self.check(*bound_var, outer_binder, |kind| {
+                matches!(kind, VariableKind::Const(_))
+            })?;
+        }
+        constant.super_visit_with(self, outer_binder)
+    }
+}
+
 struct FindFreeVarsVisitor<I: Interner> {
     interner: I,
 }
 
 impl<I: Interner> Visitor<I> for FindFreeVarsVisitor<I> {
-    type BreakTy = ();
+    type BreakTy = BoundVar;
 
     fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
         self
@@ -31,11 +273,58 @@ impl<I: Interner> Visitor<I> for FindFreeVarsVisitor<I> {
         self.interner
     }
 
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> ControlFlow<BoundVar> {
+        if !ty.has_vars_bound_at_or_above(self.interner, outer_binder) {
+            return ControlFlow::Continue(());
+        }
+        ty.super_visit_with(self, outer_binder)
+    }
+
     fn visit_free_var(
         &mut self,
-        _bound_var: BoundVar,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<BoundVar> {
+        ControlFlow::Break(bound_var.shifted_out_to(outer_binder).unwrap())
+    }
+}
+
+struct FindPlaceholderVisitor<I: Interner> {
+    interner: I,
+    universe: UniverseIndex,
+}
+
+impl<I: Interner> Visitor<I> for FindPlaceholderVisitor<I> {
+    type BreakTy = PlaceholderIndex;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn visit_ty(
+        &mut self,
+        ty: &Ty<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<PlaceholderIndex> {
+        if !ty.has_type_flags(self.interner, TypeFlags::HAS_PLACEHOLDER) {
+            return ControlFlow::Continue(());
+        }
+        ty.super_visit_with(self, outer_binder)
+    }
+
+    fn visit_free_placeholder(
+        &mut self,
+        placeholder: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> ControlFlow<()> {
-        ControlFlow::Break(())
+    ) -> ControlFlow<PlaceholderIndex> {
+        if placeholder.ui == self.universe {
+            ControlFlow::Break(placeholder)
+        } else {
+            ControlFlow::Continue(())
+        }
     }
 }
@@ -2,8 +2,9 @@
 
 use crate::fold::Fold;
 use crate::*;
-use std::fmt::Debug;
-use std::sync::Arc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::fmt::Debug;
 
 /// When we zip types, we basically traverse the structure, ensuring
 /// that it matches.  When we come to types/lifetimes, we invoke the
@@ -221,6 +222,12 @@ impl<I: Interner> Zip<I> for Ty<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         zipper.zip_tys(variance, a, b)
     }
 }
@@ -232,6 +239,12 @@ impl<I: Interner> Zip<I> for Lifetime<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         zipper.zip_lifetimes(variance, a, b)
     }
 }
@@ -243,6 +256,12 @@ impl<I: Interner> Zip<I> for Const<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         zipper.zip_consts(variance, a, b)
     }
 }
@@ -334,6 +353,12 @@ impl<I: Interner> Zip<I> for Goals<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.as_slice(interner), b.as_slice(interner))?;
         Ok(())
@@ -347,6 +372,12 @@ impl<I: Interner> Zip<I> for ProgramClauses<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.as_slice(interner), b.as_slice(interner))?;
         Ok(())
@@ -360,6 +391,12 @@ impl<I: Interner> Zip<I> for Constraints<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.as_slice(interner), b.as_slice(interner))?;
         Ok(())
@@ -373,6 +410,12 @@ impl<I: Interner> Zip<I> for QuantifiedWhereClauses<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.as_slice(interner), b.as_slice(interner))?;
         Ok(())
@@ -389,6 +432,12 @@ impl<I: Interner> Zip<I> for Goal<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.data(interner), b.data(interner))
     }
@@ -422,6 +471,12 @@ impl<I: Interner> Zip<I> for GenericArg<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.data(interner), b.data(interner))
     }
@@ -434,6 +489,12 @@ impl<I: Interner> Zip<I> for ProgramClause<I> {
         a: &Self,
         b: &Self,
     ) -> Fallible<()> {
+        if zipper
+            .interner()
+            .interned_eq_fast(a.interned(), b.interned())
+        {
+            return Ok(());
+        }
         let interner = zipper.interner();
         Zip::zip_with(zipper, variance, a.data(interner), b.data(interner))
     }
@@ -475,6 +536,24 @@ impl<I: Interner> Zip<I> for ProjectionTy<I> {
     }
 }
 
+impl<I: Interner> Zip<I> for InherentProjectionTy<I> {
+    fn zip_with<Z: Zipper<I>>(
+        zipper: &mut Z,
+        variance: Variance,
+        a: &Self,
+        b: &Self,
+    ) -> Fallible<()> {
+        let interner = zipper.interner();
+        Zip::zip_with(zipper, variance, &a.associated_ty_id, &b.associated_ty_id)?;
+        zipper.zip_substs(
+            variance,
+            None,
+            a.substitution.as_slice(interner),
+            b.substitution.as_slice(interner),
+        )
+    }
+}
+
 impl<I: Interner> Zip<I> for OpaqueTy<I> {
     fn zip_with<Z: Zipper<I>>(
         zipper: &mut Z,
@@ -516,6 +595,18 @@ impl<I: Interner> Zip<I> for DynTy<I> {
     }
 }
 
+impl<I: Interner> Zip<I> for CustomPredicate<I> {
+    fn zip_with<Z: Zipper<I>>(
+        zipper: &mut Z,
+        variance: Variance,
+        a: &Self,
+        b: &Self,
+    ) -> Fallible<()> {
+        let interner = zipper.interner();
+        interner.zip_custom_predicates(zipper, variance, &a.0, &b.0)
+    }
+}
+
 impl<I: Interner> Zip<I> for FnSubst<I> {
     fn zip_with<Z: Zipper<I>>(
         zipper: &mut Z,
@@ -1,21 +1,37 @@
 use std::fmt;
+use std::path::PathBuf;
 use string_cache::DefaultAtom as Atom;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// A range of byte offsets into one of the sources of a program.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Span {
+    /// The index of the source in `Program::sources`; text parsed on its
+    /// own is source 0.
+    pub source: usize,
     pub lo: usize,
     pub hi: usize,
 }
 
 impl Span {
-    pub fn new(lo: usize, hi: usize) -> Self {
-        Span { lo, hi }
+    pub fn new(source: usize, lo: usize, hi: usize) -> Self {
+        Span { source, lo, hi }
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Program {
     pub items: Vec<Item>,
+    /// The texts the items were parsed from: the program itself, followed
+    /// by the modules it loaded.
+    pub sources: Vec<Source>,
+}
+
+/// A text a program, or one of its modules, was parsed from.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Source {
+    /// The file the text was read from, if any.
+    pub path: Option<PathBuf>,
+    pub text: String,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -27,8 +43,18 @@ pub enum Item {
     OpaqueTyDefn(OpaqueTyDefn),
     GeneratorDefn(GeneratorDefn),
     Impl(Impl),
+    InherentImpl(InherentImpl),
     Clause(Clause),
     Foreign(ForeignDefn),
+    /// `mod name;`, whose items are loaded from another file (see
+    /// `parse_program_file`).
+    Mod(Identifier),
+    /// `#![name]`, which sets a rustc feature the program is solved with,
+    /// like `#![no_specialization]`.
+    Feature(Identifier),
+    /// `#[test] goal { .. } yields { ".." }`, a goal and its expected
+    /// solution, which the program itself ignores.
+    Test(GoalTest),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -43,12 +69,15 @@ pub struct AdtDefn {
     pub flags: AdtFlags,
     pub repr: AdtRepr,
     pub variances: Option<Vec<Variance>>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Variant {
     pub name: Identifier,
     pub fields: Vec<Field>,
+    /// The explicit discriminant of the variant (`A = 1`), if any.
+    pub discriminant: Option<i64>,
 }
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Movability {
@@ -89,6 +118,8 @@ pub enum AdtKind {
 pub enum AdtReprAttr {
     C,
     Packed,
+    Transparent,
+    Simd,
     Int(Ty),
 }
 
@@ -96,6 +127,8 @@ pub enum AdtReprAttr {
 pub struct AdtRepr {
     pub c: bool,
     pub packed: bool,
+    pub transparent: bool,
+    pub simd: bool,
     pub int: Option<Ty>,
 }
 
@@ -115,6 +148,7 @@ pub struct FnDefn {
     pub return_type: Ty,
     pub sig: FnSig,
     pub variances: Option<Vec<Variance>>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -144,6 +178,7 @@ pub struct TraitDefn {
     pub assoc_ty_defns: Vec<AssocTyDefn>,
     pub flags: TraitFlags,
     pub well_known: Option<WellKnownTrait>,
+    pub span: Span,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -161,6 +196,13 @@ pub enum WellKnownTrait {
     DiscriminantKind,
     Generator,
     DispatchFromDyn,
+    Tuple,
+    FnPtr,
+    MetaSized,
+    PointeeSized,
+    Deref,
+    IntoIterator,
+    Iterator,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -180,6 +222,7 @@ pub struct AssocTyDefn {
     pub variable_kinds: Vec<VariableKind>,
     pub bounds: Vec<QuantifiedInlineBound>,
     pub where_clauses: Vec<QuantifiedWhereClause>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -189,6 +232,7 @@ pub struct OpaqueTyDefn {
     pub name: Identifier,
     pub bounds: Vec<QuantifiedInlineBound>,
     pub where_clauses: Vec<QuantifiedWhereClause>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -197,7 +241,9 @@ pub enum VariableKind {
     IntegerTy(Identifier),
     FloatTy(Identifier),
     Lifetime(Identifier),
-    Const(Identifier),
+    /// A const parameter, with the type it is annotated with, if any (as in
+    /// `const N: u32`); unannotated parameters are `usize`s.
+    Const(Identifier, Option<ScalarType>),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -211,7 +257,9 @@ pub enum GenericArg {
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Const {
     Id(Identifier),
-    Value(u32),
+    /// A literal, with the type of its suffix, if any (as in `3u32`);
+    /// unsuffixed literals are `usize`s.
+    Value(u32, Option<ScalarType>),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -219,6 +267,8 @@ pub enum Const {
 pub enum InlineBound {
     TraitBound(TraitBound),
     AliasEqBound(AliasEqBound),
+    /// `'a`, the bound of a type that outlives `'a`.
+    Outlives(Lifetime),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -245,7 +295,92 @@ pub struct AliasEqBound {
     pub value: Ty,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+impl InlineBound {
+    /// Builds the inline bounds that `Foo<A, Item = B>` stands for, given
+    /// its trait name and what is in its angle brackets: the bound `Foo<A>`
+    /// if there are no associated type bindings, and an alias equality
+    /// bound for each binding otherwise.
+    pub fn from_args(
+        trait_name: Identifier,
+        args: Vec<BoundArg>,
+    ) -> Result<Vec<InlineBound>, &'static str> {
+        let mut args_no_self = Vec::new();
+        let mut bindings = Vec::new();
+        for arg in args {
+            match arg {
+                BoundArg::GenericArg(arg) => {
+                    if !bindings.is_empty() {
+                        return Err("generic arguments must come before associated type bindings");
+                    }
+                    args_no_self.push(arg);
+                }
+                BoundArg::Binding { name, args, value } => bindings.push((name, args, value)),
+            }
+        }
+
+        let trait_bound = TraitBound {
+            trait_name,
+            args_no_self,
+        };
+        if bindings.is_empty() {
+            return Ok(vec![InlineBound::TraitBound(trait_bound)]);
+        }
+        Ok(bindings
+            .into_iter()
+            .map(|(name, args, value)| {
+                InlineBound::AliasEqBound(AliasEqBound {
+                    trait_bound: trait_bound.clone(),
+                    name,
+                    args,
+                    value,
+                })
+            })
+            .collect())
+    }
+
+    /// The where clause `self_ty: bound`.
+    pub fn into_where_clause(self, self_ty: Ty) -> WhereClause {
+        fn trait_ref(trait_bound: TraitBound, self_ty: Ty) -> TraitRef {
+            let mut args = vec![GenericArg::Ty(self_ty)];
+            args.extend(trait_bound.args_no_self);
+            TraitRef {
+                trait_name: trait_bound.trait_name,
+                args,
+            }
+        }
+        match self {
+            InlineBound::TraitBound(trait_bound) => WhereClause::Implemented {
+                trait_ref: trait_ref(trait_bound, self_ty),
+            },
+            InlineBound::AliasEqBound(bound) => WhereClause::ProjectionEq {
+                projection: ProjectionTy {
+                    trait_ref: trait_ref(bound.trait_bound, self_ty),
+                    name: bound.name,
+                    args: bound.args,
+                },
+                ty: bound.value,
+            },
+            InlineBound::Outlives(lifetime) => WhereClause::TypeOutlives {
+                ty: self_ty,
+                lifetime,
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+/// What can appear in the angle brackets of a bound: a generic argument of
+/// the trait, or an associated type binding like `Item = u32`.
+pub enum BoundArg {
+    GenericArg(GenericArg),
+    Binding {
+        name: Identifier,
+        args: Vec<GenericArg>,
+        value: Ty,
+    },
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub enum Kind {
     Ty,
     Lifetime,
@@ -270,6 +405,19 @@ pub struct Impl {
     pub where_clauses: Vec<QuantifiedWhereClause>,
     pub assoc_ty_values: Vec<AssocTyValue>,
     pub impl_type: ImplType,
+    pub span: Span,
+}
+
+/// An inherent impl `impl<..> Foo<..> { .. }` of an ADT, which may only
+/// define associated types.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InherentImpl {
+    pub variable_kinds: Vec<VariableKind>,
+    pub adt_name: Identifier,
+    pub args: Vec<GenericArg>,
+    pub where_clauses: Vec<QuantifiedWhereClause>,
+    pub assoc_ty_values: Vec<AssocTyValue>,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -284,6 +432,7 @@ pub struct AssocTyValue {
     pub variable_kinds: Vec<VariableKind>,
     pub value: Ty,
     pub default: bool,
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -302,6 +451,9 @@ pub enum Ty {
     Projection {
         proj: ProjectionTy,
     },
+    InherentProjection {
+        proj: InherentProjectionTy,
+    },
     ForAll {
         lifetime_names: Vec<Identifier>,
         types: Vec<Box<Ty>>,
@@ -401,6 +553,13 @@ pub struct ProjectionTy {
     pub args: Vec<GenericArg>,
 }
 
+/// A projection `<Ty>::Name` of an inherent associated type.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InherentProjectionTy {
+    pub ty: Box<Ty>,
+    pub name: Identifier,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct TraitRef {
     pub trait_name: Identifier,
@@ -426,7 +585,7 @@ impl Polarity {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Identifier {
     pub str: Atom,
     pub span: Span,
@@ -448,20 +607,53 @@ pub enum WhereClause {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum DomainGoal {
-    Holds { where_clause: WhereClause },
-    Normalize { projection: ProjectionTy, ty: Ty },
-    TraitRefWellFormed { trait_ref: TraitRef },
-    TyWellFormed { ty: Ty },
-    TyFromEnv { ty: Ty },
-    TraitRefFromEnv { trait_ref: TraitRef },
-    IsLocal { ty: Ty },
-    IsUpstream { ty: Ty },
-    IsFullyVisible { ty: Ty },
-    LocalImplAllowed { trait_ref: TraitRef },
+    Holds {
+        where_clause: WhereClause,
+    },
+    Normalize {
+        projection: ProjectionTy,
+        ty: Ty,
+    },
+    NormalizeInherent {
+        projection: InherentProjectionTy,
+        ty: Ty,
+    },
+    TraitRefWellFormed {
+        trait_ref: TraitRef,
+    },
+    TyWellFormed {
+        ty: Ty,
+    },
+    TyFromEnv {
+        ty: Ty,
+    },
+    TraitRefFromEnv {
+        trait_ref: TraitRef,
+    },
+    IsLocal {
+        ty: Ty,
+    },
+    IsUpstream {
+        ty: Ty,
+    },
+    IsFullyVisible {
+        ty: Ty,
+    },
+    LocalImplAllowed {
+        trait_ref: TraitRef,
+    },
     Compatible,
-    DownstreamType { ty: Ty },
+    DownstreamType {
+        ty: Ty,
+    },
     Reveal,
-    ObjectSafe { id: Identifier },
+    ObjectSafe {
+        id: Identifier,
+    },
+    FnPtrCoercion {
+        source: Ty,
+        target: Ty,
+    },
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -492,6 +684,18 @@ pub struct Clause {
     pub conditions: Vec<Box<Goal>>,
 }
 
+/// A goal embedded in a program along with the solution it should have,
+/// written the way the solution is displayed (`"Unique; substitution
+/// [?0 := Foo]"`).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GoalTest {
+    pub goal: Box<Goal>,
+    /// The text of the goal, between its braces.
+    pub goal_span: Span,
+    pub expected: String,
+    pub span: Span,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Goal {
     ForAll(Vec<VariableKind>, Box<Goal>),
@@ -0,0 +1,245 @@
+//! Structured reports of the syntax errors found while parsing.
+
+use crate::ast::Span;
+use lalrpop_util::lexer::Token;
+use lalrpop_util::{ErrorRecovery, ParseError};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A line and column in the parsed text, both starting at 1. Columns count
+/// characters, not bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where in the parsed text a syntax error was found.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The offending text, as byte offsets into the parsed text.
+    pub span: Span,
+    pub start: Position,
+    pub end: Position,
+    /// The line of the parsed text that `start` is on.
+    pub source_line: String,
+}
+
+/// A syntax error.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// What went wrong, e.g. "Unrecognized token `}`".
+    pub message: String,
+    /// Descriptions of the tokens that would have been accepted instead,
+    /// e.g. "`;`" or "identifier".
+    pub expected: Vec<String>,
+    /// Where the error was found; errors reported by the actions of the
+    /// grammar do not know where they are.
+    pub location: Option<Location>,
+}
+
+/// All the syntax errors found while parsing a program or goal. The parser
+/// skips over malformed items, so a program with several of them is
+/// reported in one pass.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParseDiagnostics {
+    /// The file the parsed text was read from, if any.
+    pub path: Option<PathBuf>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+type RawError<'input> = ParseError<usize, Token<'input>, &'static str>;
+
+impl ParseDiagnostics {
+    /// Builds the diagnostics for the errors the parser recovered from,
+    /// followed by the one it gave up on, if any, in `text`, the source
+    /// numbered `source`.
+    pub(crate) fn new<'input>(
+        text: &str,
+        source: usize,
+        recovered: Vec<ErrorRecovery<usize, Token<'input>, &'static str>>,
+        error: Option<RawError<'input>>,
+    ) -> Self {
+        let diagnostics = recovered
+            .into_iter()
+            .map(|recovery| recovery.error)
+            .chain(error)
+            .map(|error| Diagnostic::new(text, source, error))
+            .collect();
+        ParseDiagnostics {
+            path: None,
+            diagnostics,
+        }
+    }
+
+    /// Attributes the diagnostics to the file at `path`, unless they
+    /// already are attributed to a file.
+    pub fn in_file(mut self, path: &Path) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.to_path_buf());
+        }
+        self
+    }
+}
+
+impl Diagnostic {
+    fn new(text: &str, source: usize, error: RawError<'_>) -> Self {
+        let (message, expected, span) = match error {
+            ParseError::InvalidToken { location } => {
+                let len = text[location..].chars().next().map_or(0, char::len_utf8);
+                (
+                    "Invalid token".to_string(),
+                    vec![],
+                    Some(Span::new(source, location, location + len)),
+                )
+            }
+            ParseError::UnrecognizedEOF { location, expected } => (
+                "Unexpected end of input".to_string(),
+                expected,
+                Some(Span::new(source, location, location)),
+            ),
+            ParseError::UnrecognizedToken {
+                token: (lo, token, hi),
+                expected,
+            } => (
+                format!("Unrecognized token `{}`", token.1),
+                expected,
+                Some(Span::new(source, lo, hi)),
+            ),
+            ParseError::ExtraToken {
+                token: (lo, token, hi),
+            } => (
+                format!("Extra token `{}`", token.1),
+                vec![],
+                Some(Span::new(source, lo, hi)),
+            ),
+            ParseError::User { error } => (error.to_string(), vec![], None),
+        };
+
+        let mut descriptions: Vec<String> = Vec::new();
+        for description in expected.iter().filter_map(|e| describe_terminal(e)) {
+            if !descriptions.contains(&description) {
+                descriptions.push(description);
+            }
+        }
+
+        Diagnostic {
+            message,
+            expected: descriptions,
+            location: span.map(|span| Location::new(text, span)),
+        }
+    }
+}
+
+impl Location {
+    /// Locates `span` in `text`, the source it points into.
+    pub fn new(text: &str, span: Span) -> Self {
+        let line_start = text[..span.lo].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[span.lo..]
+            .find('\n')
+            .map_or(text.len(), |i| span.lo + i);
+        Location {
+            span,
+            start: position(text, span.lo),
+            end: position(text, span.hi),
+            source_line: text[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+        }
+    }
+
+    /// Displays the location as ` --> path:line:column`, followed by the
+    /// line it is on with the located text underlined.
+    pub fn display<'a>(&'a self, path: Option<&'a Path>) -> impl fmt::Display + 'a {
+        LocationDisplay {
+            location: self,
+            path,
+        }
+    }
+}
+
+struct LocationDisplay<'a> {
+    location: &'a Location,
+    path: Option<&'a Path>,
+}
+
+impl fmt::Display for LocationDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.location;
+        write!(f, " --> ")?;
+        if let Some(path) = self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        write!(f, "{}:{}", location.start.line, location.start.column)?;
+
+        // Underline the located text, or the place something is missing,
+        // on its first line.
+        let width = if location.end.line == location.start.line {
+            location.end.column - location.start.column
+        } else {
+            location.source_line.chars().count() + 1 - location.start.column
+        };
+        write!(
+            f,
+            "\n  | {}\n  | {}{}",
+            location.source_line,
+            " ".repeat(location.start.column - 1),
+            "^".repeat(width.max(1))
+        )
+    }
+}
+
+fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Describes a terminal of the grammar, as LALRPOP names it in the tokens
+/// it expected: literals are quoted (`"struct"`) and regular expressions
+/// are raw strings (`r#"[0-9]+"#`). Comments are not worth suggesting.
+fn describe_terminal(terminal: &str) -> Option<String> {
+    match terminal.strip_prefix("r#\"") {
+        // LALRPOP escapes the quotes of a regular expression.
+        Some(regex) if regex.starts_with("\\\"") => Some("string".to_string()),
+        Some(regex) => match regex.chars().next() {
+            Some('\'') => Some("lifetime".to_string()),
+            Some('[') => Some("number".to_string()),
+            Some('/') => None,
+            _ => Some("identifier".to_string()),
+        },
+        None => Some(format!("`{}`", terminal.trim_matches('"'))),
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "parse error: {}", self.message)?;
+        match self.expected.as_slice() {
+            [] => {}
+            [expected] => write!(f, ", expected {}", expected)?,
+            expected => write!(f, ", expected one of {}", expected.join(", "))?,
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diagnostic)?;
+            if let Some(location) = &diagnostic.location {
+                write!(f, "\n{}", location.display(self.path.as_deref()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseDiagnostics {}
@@ -5,54 +5,210 @@
 extern crate lalrpop_util;
 
 pub mod ast;
+mod diagnostics;
 #[rustfmt::skip]
 lalrpop_mod!(pub parser);
 
-use lalrpop_util::ParseError;
+pub use diagnostics::{Diagnostic, Location, ParseDiagnostics, Position};
+
+use lalrpop_util::lexer::Token;
+use lalrpop_util::{ErrorRecovery, ParseError};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// Parses a program. If it has syntax errors, the error is a
+/// `ParseDiagnostics` listing all of them.
 pub fn parse_program(text: &str) -> Result<ast::Program> {
-    parser::ProgramParser::new()
-        .parse(text)
-        .map_err(|e| format!("parse error: {}", e).into())
+    let items = parse_items(text, 0)?;
+    Ok(ast::Program {
+        items,
+        sources: vec![ast::Source {
+            path: None,
+            text: text.to_string(),
+        }],
+    })
 }
 
-pub fn parse_ty(text: &str) -> Result<ast::Ty> {
-    parser::TyParser::new()
-        .parse(text)
-        .map_err(|e| format!("error parsing `{}`: {}", text, e).into())
+/// Parses the items of the program in `text`, the source numbered `source`.
+fn parse_items(text: &str, source: usize) -> Result<Vec<ast::Item>> {
+    let program = with_diagnostics(text, source, |errors| {
+        parser::ProgramParser::new().parse(source, errors, text)
+    })?;
+    Ok(program.items)
 }
 
-pub fn parse_goal(text: &str) -> Result<Box<ast::Goal>> {
-    parser::GoalParser::new().parse(text).map_err(|e| {
-        let mut output = format!("parse error: {}", &e);
-        if let Some(s) = match e {
-            ParseError::InvalidToken { location } => {
-                Some(position_string(text, location, location + 1))
+/// Parses the program in the file at `path`, along with the modules it
+/// declares.
+///
+/// A module declared with `mod name;` in `dir/file.chalk` is loaded from
+/// `dir/name.chalk` or `dir/name/mod.chalk`, whichever exists (it is an
+/// error for both to), and its own modules are looked up in `dir/name/`. A
+/// file cannot declare the same module twice. Modules are not namespaces:
+/// the items of all modules are spliced into the program in place of their
+/// declarations, and can refer to each other by name.
+pub fn parse_program_file(path: &Path) -> Result<ast::Program> {
+    let text = read_program_file(path)?;
+    parse_program_at(&text, path)
+}
+
+/// Parses `text` as the contents of the file at `path`, loading the
+/// modules it declares like `parse_program_file` does.
+pub fn parse_program_at(text: &str, path: &Path) -> Result<ast::Program> {
+    parse_program_with(text, path, &mut read_module_file)
+}
+
+/// Like `parse_program_at`, but reads the files of the modules with
+/// `read_file`, which gives `None` for a file that does not exist.
+pub fn parse_program_with(
+    text: &str,
+    path: &Path,
+    read_file: &mut dyn FnMut(&Path) -> Result<Option<String>>,
+) -> Result<ast::Program> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut sources = Vec::new();
+    let items = parse_program_in(text, path, dir, &mut sources, read_file).map_err(|e| {
+        match e.downcast::<ParseDiagnostics>() {
+            Ok(diagnostics) => Box::new(diagnostics.in_file(path)),
+            Err(e) => e,
+        }
+    })?;
+    Ok(ast::Program { items, sources })
+}
+
+/// Parses the items in `text`, read from `path`, and those of the modules
+/// it declares, which are looked up in `dir` and read with `read_file`.
+/// Each file is added to `sources`.
+fn parse_program_in(
+    text: &str,
+    path: &Path,
+    dir: &Path,
+    sources: &mut Vec<ast::Source>,
+    read_file: &mut dyn FnMut(&Path) -> Result<Option<String>>,
+) -> Result<Vec<ast::Item>> {
+    let source = sources.len();
+    sources.push(ast::Source {
+        path: Some(path.to_path_buf()),
+        text: text.to_string(),
+    });
+    let program_items = parse_items(text, source)?;
+    let mut items = Vec::with_capacity(program_items.len());
+    let mut modules = HashSet::new();
+    for item in program_items {
+        match item {
+            ast::Item::Mod(name) => {
+                if !modules.insert(name.str.clone()) {
+                    return Err(format!(
+                        "module `{}` is declared more than once in `{}`",
+                        name.str,
+                        path.display()
+                    )
+                    .into());
+                }
+                let file = dir.join(format!("{}.chalk", name.str));
+                let mod_file = dir.join(&*name.str).join("mod.chalk");
+                let (path, text) = match (read_file(&file)?, read_file(&mod_file)?) {
+                    (Some(text), None) => (file, text),
+                    (None, Some(text)) => (mod_file, text),
+                    (Some(_), Some(_)) => {
+                        return Err(format!(
+                            "file for module `{}` found at both `{}` and `{}`",
+                            name.str,
+                            file.display(),
+                            mod_file.display()
+                        )
+                        .into())
+                    }
+                    (None, None) => {
+                        return Err(format!(
+                            "file not found for module `{}`: error reading `{}` or `{}`",
+                            name.str,
+                            file.display(),
+                            mod_file.display()
+                        )
+                        .into())
+                    }
+                };
+                let module_items =
+                    parse_program_in(&text, &path, &dir.join(&*name.str), sources, read_file)
+                        .map_err(|e| -> Box<dyn std::error::Error> {
+                            match e.downcast::<ParseDiagnostics>() {
+                                Ok(diagnostics) => Box::new(diagnostics.in_file(&path)),
+                                Err(e) => {
+                                    format!("in module `{}` ({}): {}", name.str, path.display(), e)
+                                        .into()
+                                }
+                            }
+                        })?;
+                items.extend(module_items);
             }
-            ParseError::UnrecognizedToken {
-                token: (start, _, end),
-                ..
-            } => Some(position_string(text, start, end)),
-            ParseError::ExtraToken {
-                token: (start, _, end),
-                ..
-            } => Some(position_string(text, start, end)),
-            _ => None,
-        } {
-            output.push('\n');
-            output += &s;
+            item => items.push(item),
         }
-        output.into()
+    }
+    Ok(items)
+}
+
+fn read_program_file(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .map_err(|e| format!("error reading `{}`: {}", path.display(), e).into())
+}
+
+/// Reads the file at `path`, as `parse_program_at` reads the files of
+/// modules: `None` if there is no such file.
+fn read_module_file(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("error reading `{}`: {}", path.display(), e).into()),
+    }
+}
+
+pub fn parse_ty(text: &str) -> Result<ast::Ty> {
+    with_diagnostics(text, 0, |errors| {
+        parser::TyParser::new().parse(0, errors, text)
     })
 }
 
-fn position_string(text: &str, start: usize, end: usize) -> String {
-    let text = text.replace('\n', " ").replace('\r', " ");
-    let mut output = format!("position: `{}`", text);
-    output += &" ".repeat(11 + start);
-    output += &"^".repeat(end - start);
-    output.push('\n');
-    output
+pub fn parse_goal(text: &str) -> Result<Box<ast::Goal>> {
+    with_diagnostics(text, 0, |errors| {
+        parser::GoalParser::new().parse(0, errors, text)
+    })
+}
+
+/// Replaces the escape sequences in the contents of a string literal: a
+/// backslash stands for the character after it.
+fn unescape(contents: &str) -> String {
+    let mut unescaped = String::with_capacity(contents.len());
+    let mut chars = contents.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+type Errors<'input> = Vec<ErrorRecovery<usize, Token<'input>, &'static str>>;
+
+/// Runs `parse` on `text`, the source numbered `source`, reporting both the
+/// errors it recovered from and the one it gave up on, if any, as
+/// `ParseDiagnostics`.
+fn with_diagnostics<'input, T>(
+    text: &'input str,
+    source: usize,
+    parse: impl FnOnce(
+        &mut Errors<'input>,
+    ) -> std::result::Result<T, ParseError<usize, Token<'input>, &'static str>>,
+) -> Result<T> {
+    let mut errors = Vec::new();
+    let error = match parse(&mut errors) {
+        Ok(value) if errors.is_empty() => return Ok(value),
+        Ok(_) => None,
+        Err(error) => Some(error),
+    };
+    Err(Box::new(ParseDiagnostics::new(text, source, errors, error)))
 }
@@ -1,11 +1,13 @@
+use alloc::vec::Vec;
 use chalk_solve::Solution;
 use tracing::debug;
 
 use chalk_ir::interner::Interner;
-use chalk_ir::{ClausePriority, DomainGoal, GenericArg};
+use chalk_ir::{CanonicalVarKinds, ClausePriority, DomainGoal, GenericArg};
 
 pub(super) fn with_priorities<I: Interner>(
     interner: I,
+    binders: &CanonicalVarKinds<I>,
     domain_goal: &DomainGoal<I>,
     a: Solution<I>,
     prio_a: ClausePriority,
@@ -30,10 +32,13 @@ pub(super) fn with_priorities<I: Interner>(
                 );
                 (higher, ClausePriority::High)
             } else {
-                (higher.combine(lower, interner), ClausePriority::High)
+                (
+                    higher.combine(lower, interner, binders),
+                    ClausePriority::High,
+                )
             }
         }
-        (_, _, a, b) => (a.combine(b, interner), prio_a),
+        (_, _, a, b) => (a.combine(b, interner, binders), prio_a),
     }
 }
 
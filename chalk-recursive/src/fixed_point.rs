@@ -1,5 +1,9 @@
-use std::fmt::Debug;
-use std::hash::Hash;
+use crate::FxHashMap;
+use alloc::vec::Vec;
+use chalk_solve::solve::Deadline;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::mem;
 use tracing::debug;
 use tracing::{info, instrument};
 
@@ -7,14 +11,15 @@ mod cache;
 mod search_graph;
 mod stack;
 
-pub use cache::Cache;
+pub use cache::{Cache, CacheBackend, CacheStats};
 use search_graph::{DepthFirstNumber, SearchGraph};
-use stack::{Stack, StackDepth};
+use stack::{ensure_sufficient_stack, Stack, StackDepth};
 
-pub(super) struct RecursiveContext<K, V>
+pub(super) struct RecursiveContext<K, V, C>
 where
     K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
+    C: CacheBackend<K, V>,
 {
     stack: Stack,
 
@@ -25,10 +30,29 @@ where
     /// The "cache" stores results for goals that we have completely solved.
     /// Things are added to the cache when we have completely processed their
     /// result.
-    cache: Option<Cache<K, V>>,
+    cache: Option<C>,
 
     /// The maximum size for goals.
     max_size: usize,
+
+    /// Whether to do the leak check when selecting program clauses.
+    leak_check: bool,
+
+    /// Whether to normalize the region constraints of unique solutions.
+    constraint_normalization: bool,
+
+    /// The number of program clauses tried, and the number of duplicate
+    /// clauses removed, for `SolverStats`.
+    clauses: usize,
+    duplicate_clauses: usize,
+
+    /// For each goal which was on the stack when the solver overflowed its
+    /// depth or size limits, the goals from it up to the one that
+    /// overflowed.
+    overflows: FxHashMap<K, Vec<K>>,
+
+    /// The time at which to give up on new goals, as if they overflowed.
+    deadline: Option<Deadline>,
 }
 
 pub(super) trait SolverStuff<K, V>: Copy
@@ -38,14 +62,30 @@ where
 {
     fn is_coinductive_goal(self, goal: &K) -> bool;
     fn initial_value(self, goal: &K, coinductive_goal: bool) -> V;
-    fn solve_iteration(
+    fn solve_iteration<C: CacheBackend<K, V>>(
         self,
-        context: &mut RecursiveContext<K, V>,
+        context: &mut RecursiveContext<K, V, C>,
         goal: &K,
         minimums: &mut Minimums,
     ) -> V;
     fn reached_fixed_point(self, old_value: &V, new_value: &V) -> bool;
     fn error_value(self) -> V;
+    /// The value of a goal given up on, because the solver overflowed its
+    /// depth limit or reached its deadline.
+    fn overflow_value(self) -> V;
+    fn record_event(self, event: GoalEvent<'_, K, V>);
+}
+
+/// What happens to a goal the context solves, as reported to
+/// `SolverStuff::record_event`.
+pub(super) enum GoalEvent<'a, K, V> {
+    /// A table was created for the goal.
+    TableCreated(&'a K),
+    /// The goal, which is on the stack, was found again: it is its own
+    /// subgoal. The cycle is `coinductive` if all the goals in it are.
+    CycleDetected { goal: &'a K, coinductive: bool },
+    /// The table of the goal was completed, with the given value.
+    Solved(&'a K, &'a V),
 }
 
 /// The `minimums` struct is used while solving to track whether we encountered
@@ -63,21 +103,28 @@ impl Minimums {
     }
 
     pub fn update_from(&mut self, minimums: Minimums) {
-        self.positive = ::std::cmp::min(self.positive, minimums.positive);
+        self.positive = ::core::cmp::min(self.positive, minimums.positive);
     }
 }
 
-impl<K, V> RecursiveContext<K, V>
+impl<K, V, C> RecursiveContext<K, V, C>
 where
     K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
+    C: CacheBackend<K, V>,
 {
-    pub fn new(overflow_depth: usize, max_size: usize, cache: Option<Cache<K, V>>) -> Self {
+    pub fn new(overflow_depth: usize, max_size: usize, cache: Option<C>) -> Self {
         RecursiveContext {
             stack: Stack::new(overflow_depth),
             search_graph: SearchGraph::new(),
             cache,
             max_size,
+            leak_check: false,
+            constraint_normalization: true,
+            clauses: 0,
+            duplicate_clauses: 0,
+            overflows: FxHashMap::default(),
+            deadline: None,
         }
     }
 
@@ -85,6 +132,100 @@ where
         self.max_size
     }
 
+    pub fn leak_check(&self) -> bool {
+        self.leak_check
+    }
+
+    pub fn set_leak_check(&mut self, leak_check: bool) {
+        self.leak_check = leak_check;
+    }
+
+    pub fn constraint_normalization(&self) -> bool {
+        self.constraint_normalization
+    }
+
+    pub fn set_constraint_normalization(&mut self, constraint_normalization: bool) {
+        self.constraint_normalization = constraint_normalization;
+    }
+
+    /// Records that `clauses` program clauses were tried for a goal, after
+    /// removing `duplicate_clauses` duplicates.
+    pub fn record_clauses(&mut self, clauses: usize, duplicate_clauses: usize) {
+        self.clauses += clauses;
+        self.duplicate_clauses += duplicate_clauses;
+    }
+
+    pub fn clauses(&self) -> usize {
+        self.clauses
+    }
+
+    pub fn duplicate_clauses(&self) -> usize {
+        self.duplicate_clauses
+    }
+
+    pub fn cache(&self) -> Option<&C> {
+        self.cache.as_ref()
+    }
+
+    /// Runs `op` with `max_size` and `deadline` as the limits instead of
+    /// the context's own. Unless `cached` is true, `op` solves without the
+    /// cache, and the overflows it runs into are not recorded.
+    pub fn with_limits<R>(
+        &mut self,
+        max_size: usize,
+        deadline: Option<Deadline>,
+        cached: bool,
+        op: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let old_max_size = mem::replace(&mut self.max_size, max_size);
+        let old_deadline = mem::replace(&mut self.deadline, deadline);
+        let saved = (!cached).then(|| (self.cache.take(), mem::take(&mut self.overflows)));
+        let result = op(self);
+        self.max_size = old_max_size;
+        self.deadline = old_deadline;
+        if let Some((cache, overflows)) = saved {
+            self.cache = cache;
+            self.overflows = overflows;
+        }
+        result
+    }
+
+    /// Records that the solver overflowed its depth or size limits on
+    /// `goal`, a subgoal of the goals on the stack.
+    pub fn record_overflow(&mut self, goal: K) {
+        let mut chain: Vec<K> = self.search_graph.goals_on_stack().cloned().collect();
+        chain.push(goal);
+        self.remember_overflow(chain);
+    }
+
+    /// If solving `goal`, whose result was just reused, overflowed, records
+    /// the overflow for the goals on the stack too.
+    fn propagate_overflow(&mut self, goal: &K) {
+        if let Some(suffix) = self.overflows.get(goal) {
+            let mut chain: Vec<K> = self.search_graph.goals_on_stack().cloned().collect();
+            chain.extend(suffix.iter().cloned());
+            self.remember_overflow(chain);
+        }
+    }
+
+    /// Remembers the overflow of the last goal of `chain` for the goals
+    /// before it, keeping no more goals than the stack can hold.
+    fn remember_overflow(&mut self, mut chain: Vec<K>) {
+        let max_len = self.stack.overflow_depth() + 1;
+        chain.drain(..chain.len().saturating_sub(max_len));
+        for i in 0..chain.len() - 1 {
+            self.overflows
+                .entry(chain[i].clone())
+                .or_insert_with(|| chain[i..].to_vec());
+        }
+    }
+
+    /// The goals from `goal` up to the one that overflowed, if solving
+    /// `goal` overflowed the depth or size limits.
+    pub fn overflow(&self, goal: &K) -> Option<&[K]> {
+        self.overflows.get(goal).map(|chain| &chain[..])
+    }
+
     /// Solves a canonical goal. The substitution returned in the
     /// solution will be for the fully decomposed goal. For example, given the
     /// program
@@ -125,6 +266,7 @@ where
         if let Some(cache) = &self.cache {
             if let Some(value) = cache.get(goal) {
                 debug!("solve_reduced_goal: cache hit, value={:?}", value);
+                self.propagate_overflow(goal);
                 return value;
             }
         }
@@ -134,6 +276,10 @@ where
             // Check if this table is still on the stack.
             if let Some(depth) = self.search_graph[dfn].stack_depth {
                 self.stack[depth].flag_cycle();
+                solver_stuff.record_event(GoalEvent::CycleDetected {
+                    goal,
+                    coinductive: self.stack.coinductive_cycle_from(depth),
+                });
                 // Mixed cycles are not allowed. For more information about this
                 // see the corresponding section in the coinduction chapter:
                 // https://rust-lang.github.io/chalk/book/recursive/coinduction.html#mixed-co-inductive-and-inductive-cycles
@@ -143,6 +289,9 @@ where
             }
 
             minimums.update_from(self.search_graph[dfn].links);
+            if self.search_graph[dfn].stack_depth.is_none() {
+                self.propagate_overflow(goal);
+            }
 
             // Return the solution from the table.
             let previous_solution = self.search_graph[dfn].solution.clone();
@@ -151,6 +300,13 @@ where
                 previous_solution,
             );
             previous_solution
+        } else if self.stack.is_full() {
+            info!("solve_goal: overflow depth reached");
+            self.record_overflow(goal.clone());
+            solver_stuff.overflow_value()
+        } else if self.deadline.is_some_and(|deadline| deadline.is_reached()) {
+            info!("solve_goal: deadline reached");
+            solver_stuff.overflow_value()
         } else {
             // Otherwise, push the goal onto the stack and create a table.
             // The initial result for this table depends on whether the goal is coinductive.
@@ -158,8 +314,10 @@ where
             let initial_solution = solver_stuff.initial_value(goal, coinductive_goal);
             let depth = self.stack.push(coinductive_goal);
             let dfn = self.search_graph.insert(goal, depth, initial_solution);
+            solver_stuff.record_event(GoalEvent::TableCreated(goal));
 
-            let subgoal_minimums = self.solve_new_subgoal(goal, depth, dfn, solver_stuff);
+            let subgoal_minimums =
+                ensure_sufficient_stack(|| self.solve_new_subgoal(goal, depth, dfn, solver_stuff));
 
             self.search_graph[dfn].links = subgoal_minimums;
             self.search_graph[dfn].stack_depth = None;
@@ -168,13 +326,14 @@ where
 
             // Read final result from table.
             let result = self.search_graph[dfn].solution.clone();
+            solver_stuff.record_event(GoalEvent::Solved(goal, &result));
 
             // If processing this subgoal did not involve anything
             // outside of its subtree, then we can promote it to the
             // cache now. This is a sort of hack to alleviate the
             // worst of the repeated work that we do during tabling.
             if subgoal_minimums.positive >= dfn {
-                if let Some(cache) = &mut self.cache {
+                if let Some(cache) = &self.cache {
                     self.search_graph.move_to_cache(dfn, cache);
                     debug!("solve_reduced_goal: SCC head encountered, moving to cache");
                 } else {
@@ -224,7 +383,7 @@ where
             }
 
             let old_answer =
-                std::mem::replace(&mut self.search_graph[dfn].solution, current_answer);
+                core::mem::replace(&mut self.search_graph[dfn].solution, current_answer);
 
             if solver_stuff.reached_fixed_point(&old_answer, &self.search_graph[dfn].solution) {
                 return *minimums;
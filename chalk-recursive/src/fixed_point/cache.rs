@@ -1,59 +1,182 @@
-use rustc_hash::FxHashMap;
-use std::fmt::Debug;
-use std::hash::Hash;
+use crate::FxHashMap;
+use alloc::collections::VecDeque;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::ops::DerefMut;
+#[cfg(feature = "std")]
 use std::sync::{Arc, Mutex};
 use tracing::debug;
 use tracing::instrument;
+#[cfg(not(feature = "std"))]
+use {alloc::rc::Rc, core::cell::RefCell};
+
+/// A store for the results of goals that the recursive solver has
+/// completely solved. The solver only ever reads and writes through a
+/// shared reference, so implementations that want to be shared amongst
+/// many solvers (possibly on different threads) should use interior
+/// mutability, as `Cache` does.
+pub trait CacheBackend<K, V> {
+    /// Looks up the result for `goal`, if one was recorded.
+    fn get(&self, goal: &K) -> Option<V>;
+
+    /// Records the result for `goal`.
+    fn insert(&self, goal: K, result: V);
+
+    /// Returns statistics about the use of this cache. Backends that do
+    /// not keep track of this report all zeroes.
+    fn stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+}
+
+/// Statistics about the use of a cache, as reported by `CacheBackend::stats`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of lookups that found a result.
+    pub hits: usize,
+    /// Number of lookups that did not find a result.
+    pub misses: usize,
+    /// Number of results recorded.
+    pub insertions: usize,
+    /// Number of results dropped to stay within the capacity of the cache.
+    pub evictions: usize,
+    /// Number of results currently stored.
+    pub entries: usize,
+}
+
 /// The "cache" stores results for goals that we have completely solved.
 /// Things are added to the cache when we have completely processed their
-/// result, and it can be shared amongst many solvers.
+/// result, and it can be shared amongst many solvers: clones of a `Cache`
+/// refer to the same underlying data. Without the `std` feature, the
+/// clones can only be used from one thread.
 pub struct Cache<K, V>
 where
-    K: Hash + Eq + Debug,
+    K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
 {
-    data: Arc<Mutex<CacheData<K, V>>>,
+    data: SharedCacheData<K, V>,
 }
+
+#[cfg(feature = "std")]
+type SharedCacheData<K, V> = Arc<Mutex<CacheData<K, V>>>;
+#[cfg(not(feature = "std"))]
+type SharedCacheData<K, V> = Rc<RefCell<CacheData<K, V>>>;
+
 struct CacheData<K, V>
 where
-    K: Hash + Eq + Debug,
+    K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
 {
     cache: FxHashMap<K, V>,
+    /// Keys in the order they were inserted, oldest first; only tracked
+    /// when `capacity` is set.
+    order: VecDeque<K>,
+    capacity: Option<usize>,
+    stats: CacheStats,
 }
 
 impl<K, V> Cache<K, V>
 where
-    K: Hash + Eq + Debug,
+    K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
 {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Creates a cache that holds at most `capacity` results; once it is
+    /// full, the oldest results are evicted to make room for new ones.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let cache = Self::new();
+        cache.data().capacity = Some(capacity);
+        cache
+    }
+
     /// Record a cache result.
     #[instrument(skip(self))]
     pub fn insert(&self, goal: K, result: V) {
-        let mut data = self.data.lock().unwrap();
+        let mut data = self.data();
+        data.stats.insertions += 1;
+        if let Some(capacity) = data.capacity {
+            if !data.cache.contains_key(&goal) {
+                while data.cache.len() >= capacity {
+                    let oldest = match data.order.pop_front() {
+                        Some(oldest) => oldest,
+                        None => break,
+                    };
+                    debug!(?oldest, "Cache eviction");
+                    data.cache.remove(&oldest);
+                    data.stats.evictions += 1;
+                }
+                if capacity == 0 {
+                    return;
+                }
+                data.order.push_back(goal.clone());
+            }
+        }
         data.cache.insert(goal, result);
     }
 
-    /// Record a cache result.
+    /// Look up a cache result.
     pub fn get(&self, goal: &K) -> Option<V> {
-        let data = self.data.lock().unwrap();
-        if let Some(result) = data.cache.get(goal) {
+        let mut data = self.data();
+        if let Some(result) = data.cache.get(goal).cloned() {
             debug!(?goal, ?result, "Cache hit");
-            Some(result.clone())
+            data.stats.hits += 1;
+            Some(result)
         } else {
             debug!(?goal, "Cache miss");
+            data.stats.misses += 1;
             None
         }
     }
+
+    /// Returns statistics about the use of this cache, accumulated over
+    /// all the solvers sharing it.
+    pub fn stats(&self) -> CacheStats {
+        let data = self.data();
+        CacheStats {
+            entries: data.cache.len(),
+            ..data.stats
+        }
+    }
+
+    /// Removes all results from the cache. The statistics are kept.
+    pub fn clear(&self) {
+        let mut data = self.data();
+        data.cache.clear();
+        data.order.clear();
+    }
+
+    fn data(&self) -> impl DerefMut<Target = CacheData<K, V>> + '_ {
+        #[cfg(feature = "std")]
+        return self.data.lock().unwrap();
+        #[cfg(not(feature = "std"))]
+        return self.data.borrow_mut();
+    }
+}
+
+impl<K, V> CacheBackend<K, V> for Cache<K, V>
+where
+    K: Hash + Eq + Debug + Clone,
+    V: Debug + Clone,
+{
+    fn get(&self, goal: &K) -> Option<V> {
+        Cache::get(self, goal)
+    }
+
+    fn insert(&self, goal: K, result: V) {
+        Cache::insert(self, goal, result)
+    }
+
+    fn stats(&self) -> CacheStats {
+        Cache::stats(self)
+    }
 }
 
 impl<K, V> Clone for Cache<K, V>
 where
-    K: Hash + Eq + Debug,
+    K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
 {
     fn clone(&self) -> Self {
@@ -65,7 +188,7 @@ where
 
 impl<K, V> Default for Cache<K, V>
 where
-    K: Hash + Eq + Debug,
+    K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
 {
     fn default() -> Self {
@@ -77,12 +200,15 @@ where
 
 impl<K, V> Default for CacheData<K, V>
 where
-    K: Hash + Eq + Debug,
+    K: Hash + Eq + Debug + Clone,
     V: Debug + Clone,
 {
     fn default() -> Self {
         Self {
             cache: Default::default(),
+            order: Default::default(),
+            capacity: None,
+            stats: Default::default(),
         }
     }
 }
@@ -1,12 +1,14 @@
 use super::stack::StackDepth;
-use super::{Cache, Minimums};
-use rustc_hash::FxHashMap;
-use std::fmt::Debug;
-use std::hash::Hash;
-use std::ops::Add;
-use std::ops::Index;
-use std::ops::IndexMut;
-use std::usize;
+use super::{CacheBackend, Minimums};
+use crate::FxHashMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::ops::Add;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::usize;
 use tracing::{debug, instrument};
 
 /// The "search graph" stores in-progress goals that are still
@@ -85,6 +87,14 @@ where
         dfn
     }
 
+    /// The goals of the nodes on the stack, from the bottom up.
+    pub(crate) fn goals_on_stack(&self) -> impl Iterator<Item = &K> + '_ {
+        self.nodes
+            .iter()
+            .filter(|node| node.stack_depth.is_some())
+            .map(|node| &node.goal)
+    }
+
     /// Clears all nodes with a depth-first number greater than or equal `dfn`.
     #[instrument(level = "debug", skip(self))]
     pub(crate) fn rollback_to(&mut self, dfn: DepthFirstNumber) {
@@ -95,7 +105,7 @@ where
     /// Removes all nodes with a depth-first-number greater than or
     /// equal to `dfn`, adding their final solutions into the cache.
     #[instrument(level = "debug", skip(self, cache))]
-    pub(crate) fn move_to_cache(&mut self, dfn: DepthFirstNumber, cache: &Cache<K, V>) {
+    pub(crate) fn move_to_cache(&mut self, dfn: DepthFirstNumber, cache: &impl CacheBackend<K, V>) {
         self.indices.retain(|_key, value| *value < dfn);
         for node in self.nodes.drain(dfn.index..) {
             assert!(node.stack_depth.is_none());
@@ -1,7 +1,9 @@
-use std::mem;
-use std::ops::Index;
-use std::ops::IndexMut;
-use std::usize;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::usize;
 
 pub(super) struct Stack {
     // program: Arc<ProgramEnvironment>,
@@ -39,18 +41,22 @@ impl Stack {
         self.entries.is_empty()
     }
 
+    /// True if no more goals can be pushed without exceeding the
+    /// overflow depth.
+    pub(super) fn is_full(&self) -> bool {
+        self.entries.len() >= self.overflow_depth
+    }
+
+    pub(super) fn overflow_depth(&self) -> usize {
+        self.overflow_depth
+    }
+
     pub(super) fn push(&mut self, coinductive_goal: bool) -> StackDepth {
+        assert!(!self.is_full(), "overflow depth reached");
         let depth = StackDepth {
             depth: self.entries.len(),
         };
 
-        if depth.depth >= self.overflow_depth {
-            // This shoudl perhaps be a result or something, though
-            // really I'd prefer to move to subgoal abstraction for
-            // guaranteeing termination. -nmatsakis
-            panic!("overflow depth reached")
-        }
-
         self.entries.push(StackEntry {
             coinductive_goal,
             cycle: false,
@@ -80,6 +86,14 @@ impl Stack {
         let any_inductive = coinductive_count != total_count;
         any_coinductive && any_inductive
     }
+
+    /// True iff all the goals from the top of the stack down to (and
+    /// including) the given depth are coinductive.
+    pub(super) fn coinductive_cycle_from(&self, depth: StackDepth) -> bool {
+        self.entries[depth.depth..]
+            .iter()
+            .all(|entry| entry.coinductive_goal)
+    }
 }
 
 impl StackEntry {
@@ -105,3 +119,24 @@ impl IndexMut<StackDepth> for Stack {
         &mut self.entries[depth.depth]
     }
 }
+
+/// Amount of native stack that must be left to solve a goal on the
+/// current stack; with less, a new segment is allocated. Solving a goal
+/// up to its subgoals takes a few hundred KB in unoptimized builds.
+#[cfg(feature = "std")]
+const RED_ZONE: usize = 512 * 1024;
+
+/// Size of the native stack segments allocated by `ensure_sufficient_stack`.
+#[cfg(feature = "std")]
+const SEGMENT_SIZE: usize = 8 * 1024 * 1024;
+
+/// Runs `f`, which solves a goal, on a fresh segment of native stack if
+/// little is left of the current one, so that deeply nested goals
+/// (limited only by the overflow depth) do not overflow the native stack.
+/// Without the `std` feature, the stack cannot grow and `f` just runs.
+pub(super) fn ensure_sufficient_stack<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "std")]
+    return stacker::maybe_grow(RED_ZONE, SEGMENT_SIZE, f);
+    #[cfg(not(feature = "std"))]
+    return f();
+}
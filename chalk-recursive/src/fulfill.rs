@@ -1,22 +1,27 @@
 use crate::fixed_point::Minimums;
 use crate::solve::SolveDatabase;
+use crate::{FxHashSet, SolveResult};
+use alloc::borrow::ToOwned;
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_ir::cast::Cast;
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::visit::Visit;
 use chalk_ir::zip::Zip;
 use chalk_ir::{
-    Binders, BoundVar, Canonical, ConstrainedSubst, Constraint, Constraints, DomainGoal,
-    Environment, EqGoal, Fallible, GenericArg, GenericArgData, Goal, GoalData, InEnvironment,
-    NoSolution, ProgramClauseImplication, QuantifierKind, Substitution, SubtypeGoal, TyKind,
+    BoundVar, Canonical, ConstrainedSubst, Constraint, Constraints, DomainGoal, Environment,
+    EqGoal, Fallible, GenericArg, GenericArgData, Goal, GoalData, InEnvironment, NoSolution,
+    ProgramClause, ProgramClauseImplication, QuantifierKind, Substitution, SubtypeGoal, TyKind,
     TyVariableKind, UCanonical, UnificationDatabase, UniverseMap, Variance,
 };
 use chalk_solve::debug_span;
-use chalk_solve::infer::{InferenceTable, ParameterEnaVariableExt};
+use chalk_solve::ext::GoalExt;
+use chalk_solve::infer::{InferenceTable, ParameterEnaVariableExt, VariableOrigin};
 use chalk_solve::solve::truncate;
-use chalk_solve::{Guidance, Solution};
-use rustc_hash::FxHashSet;
-use std::fmt::Debug;
+use chalk_solve::{AmbiguityCause, Guidance, NoSolutionCause, Solution};
+use core::fmt::Debug;
+use core::hash::Hash;
 use tracing::{debug, instrument};
 
 enum Outcome {
@@ -56,7 +61,7 @@ struct PositiveSolution<I: Interner> {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum NegativeSolution {
     Refuted,
-    Ambiguous,
+    Ambiguous(AmbiguityCause),
 }
 
 fn canonicalize<I: Interner, T>(
@@ -84,7 +89,7 @@ fn u_canonicalize<I: Interner, T>(
 ) -> (UCanonical<T::Result>, UniverseMap)
 where
     T: Clone + HasInterner<Interner = I> + Fold<I> + Visit<I>,
-    T::Result: HasInterner<Interner = I>,
+    T::Result: HasInterner<Interner = I> + Hash,
 {
     let res = InferenceTable::u_canonicalize(interner, value0);
     (res.quantified, res.universes)
@@ -98,11 +103,11 @@ fn unify<I: Interner, T>(
     variance: Variance,
     a: &T,
     b: &T,
-) -> Fallible<Vec<InEnvironment<Goal<I>>>>
+) -> Result<Vec<InEnvironment<Goal<I>>>, NoSolutionCause<I>>
 where
     T: ?Sized + Zip<I>,
 {
-    let res = infer.relate(interner, db, environment, variance, a, b)?;
+    let res = infer.relate_or_cause(interner, db, environment, variance, a, b)?;
     Ok(res.goals)
 }
 
@@ -128,10 +133,18 @@ pub(super) struct Fulfill<'s, I: Interner, Solver: SolveDatabase<I>> {
     /// validated.
     constraints: FxHashSet<InEnvironment<Constraint<I>>>,
 
+    /// The environment of the goal, whose outlives facts discharge the
+    /// constraints they imply.
+    environment: Environment<I>,
+
     /// Record that a goal has been processed that can neither be proved nor
-    /// refuted. In such a case the solution will be either `CannotProve`, or `Err`
+    /// refuted, and why. In such a case the solution will be either `CannotProve`, or `Err`
     /// in the case where some other goal leads to an error.
-    cannot_prove: bool,
+    cannot_prove: Option<AmbiguityCause>,
+
+    /// The cause of the ambiguity of the obligations left ambiguous by the
+    /// last round of `fulfill`, if any.
+    ambiguity: Option<AmbiguityCause>,
 }
 
 impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
@@ -141,15 +154,17 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         infer: InferenceTable<I>,
         subst: Substitution<I>,
         canonical_goal: InEnvironment<DomainGoal<I>>,
-        clause: &Binders<ProgramClauseImplication<I>>,
-    ) -> Fallible<Self> {
+        clause: &ProgramClause<I>,
+    ) -> Result<Self, NoSolutionCause<I>> {
         let mut fulfill = Fulfill {
             solver,
             infer,
             subst,
             obligations: vec![],
             constraints: FxHashSet::default(),
-            cannot_prove: false,
+            environment: canonical_goal.environment.clone(),
+            cannot_prove: None,
+            ambiguity: None,
         };
 
         let ProgramClauseImplication {
@@ -159,7 +174,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             priority: _,
         } = fulfill
             .infer
-            .instantiate_binders_existentially(fulfill.solver.interner(), clause.clone());
+            .instantiate_clause_existentially(fulfill.solver.db(), clause);
 
         debug!(?consequence, ?conditions, ?constraints);
         fulfill
@@ -168,13 +183,22 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
 
         debug!("the subst is {:?}", fulfill.subst);
 
-        if let Err(e) = fulfill.unify(
+        let interner = fulfill.solver.interner();
+        let goals = unify(
+            &mut fulfill.infer,
+            interner,
+            fulfill.solver.db().unification_database(),
             &canonical_goal.environment,
             Variance::Invariant,
             &canonical_goal.goal,
             &consequence,
-        ) {
-            return Err(e);
+        )?;
+        if fulfill.solver.leak_check() {
+            fulfill.infer.leak_check(interner, &goals)?;
+        }
+        for goal in goals {
+            let goal = goal.cast(interner);
+            fulfill.push_obligation(Obligation::Prove(goal));
         }
 
         // if so, toss in all of its premises
@@ -192,52 +216,43 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         infer: InferenceTable<I>,
         subst: Substitution<I>,
         canonical_goal: InEnvironment<Goal<I>>,
-    ) -> Fallible<Self> {
+    ) -> Result<Self, NoSolutionCause<I>> {
         let mut fulfill = Fulfill {
             solver,
             infer,
             subst,
             obligations: vec![],
             constraints: FxHashSet::default(),
-            cannot_prove: false,
+            environment: canonical_goal.environment.clone(),
+            cannot_prove: None,
+            ambiguity: None,
         };
 
-        if let Err(e) = fulfill.push_goal(&canonical_goal.environment, canonical_goal.goal.clone())
-        {
-            return Err(e);
-        }
+        let goal = canonical_goal.goal.clone().simplified(fulfill.solver.db());
+        fulfill.push_goal(&canonical_goal.environment, goal)?;
 
         Ok(fulfill)
     }
 
     fn push_obligation(&mut self, obligation: Obligation<I>) {
         // truncate to avoid overflows
-        match &obligation {
-            Obligation::Prove(goal) => {
-                if truncate::needs_truncation(
-                    self.solver.interner(),
-                    &mut self.infer,
-                    self.solver.max_size(),
-                    goal,
-                ) {
-                    // the goal is too big. Record that we should return Ambiguous
-                    self.cannot_prove = true;
-                    return;
-                }
-            }
-            Obligation::Refute(goal) => {
-                if truncate::needs_truncation(
-                    self.solver.interner(),
-                    &mut self.infer,
-                    self.solver.max_size(),
-                    goal,
-                ) {
-                    // the goal is too big. Record that we should return Ambiguous
-                    self.cannot_prove = true;
-                    return;
-                }
-            }
+        let goal = match &obligation {
+            Obligation::Prove(goal) | Obligation::Refute(goal) => goal,
         };
+        if truncate::needs_truncation(
+            self.solver.interner(),
+            &mut self.infer,
+            self.solver.max_size(),
+            goal,
+        ) {
+            // the goal is too big. Record that we should return Ambiguous
+            let interner = self.solver.interner();
+            let (quantified, _) = canonicalize(&mut self.infer, interner, goal.clone());
+            let (quantified, _) = u_canonicalize(&mut self.infer, interner, &quantified);
+            self.solver.record_overflow(quantified);
+            self.cannot_prove = Some(AmbiguityCause::Truncated);
+            return;
+        }
         self.obligations.push(obligation);
     }
 
@@ -251,7 +266,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         variance: Variance,
         a: &T,
         b: &T,
-    ) -> Fallible<()>
+    ) -> Result<(), NoSolutionCause<I>>
     where
         T: ?Sized + Zip<I> + Debug,
     {
@@ -280,7 +295,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         &mut self,
         environment: &Environment<I>,
         goal: Goal<I>,
-    ) -> Fallible<()> {
+    ) -> Result<(), NoSolutionCause<I>> {
         let interner = self.solver.interner();
         match goal.data(interner) {
             GoalData::Quantified(QuantifierKind::ForAll, subgoal) => {
@@ -290,9 +305,16 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
                 self.push_goal(environment, subgoal)?;
             }
             GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
-                let subgoal = self
-                    .infer
-                    .instantiate_binders_existentially(self.solver.interner(), subgoal.clone());
+                let subgoal = self.infer.instantiate_binders_existentially_with_origin(
+                    self.solver.interner(),
+                    subgoal.clone(),
+                    |index| {
+                        Some(VariableOrigin::Existential {
+                            goal: goal.clone(),
+                            index,
+                        })
+                    },
+                );
                 self.push_goal(environment, subgoal)?;
             }
             GoalData::Implies(wc, subgoal) => {
@@ -329,14 +351,14 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
                     b.kind(interner),
                     TyKind::InferenceVar(_, TyVariableKind::General)
                 ) {
-                    self.cannot_prove = true;
+                    self.cannot_prove = Some(AmbiguityCause::FlounderedNegative);
                 } else {
                     self.unify(environment, Variance::Covariant, &a, &b)?;
                 }
             }
             GoalData::CannotProve => {
                 debug!("Pushed a CannotProve goal, setting cannot_prove = true");
-                self.cannot_prove = true;
+                self.cannot_prove = Some(AmbiguityCause::FlounderedNegative);
             }
         }
         Ok(())
@@ -347,7 +369,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         &mut self,
         wc: InEnvironment<Goal<I>>,
         minimums: &mut Minimums,
-    ) -> Fallible<PositiveSolution<I>> {
+    ) -> Result<PositiveSolution<I>, NoSolutionCause<I>> {
         let interner = self.solver.interner();
         let (quantified, free_vars) = canonicalize(&mut self.infer, interner, wc);
         let (quantified, universes) = u_canonicalize(&mut self.infer, interner, &quantified);
@@ -368,7 +390,9 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             None => {
                 // Treat non-ground negatives as ambiguous. Note that, as inference
                 // proceeds, we may wind up with more information here.
-                return Ok(NegativeSolution::Ambiguous);
+                return Ok(NegativeSolution::Ambiguous(
+                    AmbiguityCause::FlounderedNegative,
+                ));
             }
         };
 
@@ -377,10 +401,9 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             u_canonicalize(&mut self.infer, self.solver.interner(), &canonicalized);
         let mut minimums = Minimums::new(); // FIXME -- minimums here seems wrong
         if let Ok(solution) = self.solver.solve_goal(quantified, &mut minimums) {
-            if solution.is_unique() {
-                Err(NoSolution)
-            } else {
-                Ok(NegativeSolution::Ambiguous)
+            match solution.ambiguity_cause() {
+                None => Err(NoSolution),
+                Some(cause) => Ok(NegativeSolution::Ambiguous(cause)),
             }
         } else {
             Ok(NegativeSolution::Refuted)
@@ -431,7 +454,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
         }
     }
 
-    fn fulfill(&mut self, minimums: &mut Minimums) -> Fallible<Outcome> {
+    fn fulfill(&mut self, minimums: &mut Minimums) -> Result<Outcome, NoSolutionCause<I>> {
         debug_span!("fulfill", obligations=?self.obligations);
 
         // Try to solve all the obligations. We do this via a fixed-point
@@ -444,6 +467,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
 
         while progress {
             progress = false;
+            self.ambiguity = None;
             debug!("start of round, {} obligations", self.obligations.len());
 
             // Take the list of `obligations` to solve this round and replace it
@@ -454,7 +478,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             // directly.
             assert!(obligations.is_empty());
             while let Some(obligation) = self.obligations.pop() {
-                let ambiguous = match &obligation {
+                let ambiguity = match &obligation {
                     Obligation::Prove(wc) => {
                         let PositiveSolution {
                             free_vars,
@@ -481,16 +505,17 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
                             }
                         }
 
-                        solution.is_ambig()
-                    }
-                    Obligation::Refute(goal) => {
-                        let answer = self.refute(goal.clone())?;
-                        answer == NegativeSolution::Ambiguous
+                        solution.ambiguity_cause()
                     }
+                    Obligation::Refute(goal) => match self.refute(goal.clone())? {
+                        NegativeSolution::Refuted => None,
+                        NegativeSolution::Ambiguous(cause) => Some(cause),
+                    },
                 };
 
-                if ambiguous {
+                if let Some(cause) = ambiguity {
                     debug!("ambiguous result: {:?}", obligation);
+                    self.ambiguity.get_or_insert(cause);
                     obligations.push(obligation);
                 }
             }
@@ -514,15 +539,18 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
     /// Try to fulfill all pending obligations and build the resulting
     /// solution. The returned solution will transform `subst` substitution with
     /// the outcome of type inference by updating the replacements it provides.
-    pub(super) fn solve(mut self, minimums: &mut Minimums) -> Fallible<Solution<I>> {
+    pub(super) fn solve(mut self, minimums: &mut Minimums) -> SolveResult<I> {
         let outcome = match self.fulfill(minimums) {
             Ok(o) => o,
             Err(e) => return Err(e),
         };
 
-        if self.cannot_prove {
-            debug!("Goal cannot be proven (cannot_prove = true), returning ambiguous");
-            return Ok(Solution::Ambig(Guidance::Unknown));
+        if let Some(cause) = self.cannot_prove {
+            debug!(
+                "Goal cannot be proven (cannot_prove = {:?}), returning ambiguous",
+                cause
+            );
+            return Ok(Solution::Ambig(Guidance::Unknown, cause, vec![]));
         }
 
         if outcome.is_complete() {
@@ -530,22 +558,34 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             // and the current inference state is the unique way to solve them.
 
             let constraints = Constraints::from_iter(self.interner(), self.constraints.clone());
-            let constrained = canonicalize(
+            let mut constrained = canonicalize(
                 &mut self.infer,
                 self.solver.interner(),
                 ConstrainedSubst {
                     subst: self.subst,
                     constraints,
                 },
-            );
-            return Ok(Solution::Unique(constrained.0));
+            )
+            .0;
+            if self.solver.constraint_normalization() {
+                let interner = self.solver.interner();
+                constrained.value.constraints = constrained
+                    .value
+                    .constraints
+                    .without_implied(interner, &self.environment)
+                    .normalized(interner);
+            }
+            return Ok(Solution::Unique(constrained));
         }
 
         // Otherwise, we have (positive or negative) obligations remaining, but
         // haven't proved that it's *impossible* to satisfy out obligations. we
         // need to determine how to package up what we learned about type
-        // inference as an ambiguous solution.
+        // inference as an ambiguous solution, as ambiguous as they are.
 
+        let cause = self
+            .ambiguity
+            .expect("the remaining obligations are ambiguous");
         let canonical_subst =
             canonicalize(&mut self.infer, self.solver.interner(), self.subst.clone());
 
@@ -572,12 +612,16 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
                         solution.constrained_subst(self.solver.interner())
                     {
                         self.apply_solution(free_vars, universes, constrained_subst);
-                        return Ok(Solution::Ambig(Guidance::Suggested(canonical_subst.0)));
+                        return Ok(Solution::Ambig(
+                            Guidance::Suggested(canonical_subst.0),
+                            cause,
+                            vec![],
+                        ));
                     }
                 }
             }
 
-            Ok(Solution::Ambig(Guidance::Unknown))
+            Ok(Solution::Ambig(Guidance::Unknown, cause, vec![]))
         } else {
             // While we failed to prove the goal, we still learned that
             // something had to hold. Here's an example where this happens:
@@ -599,7 +643,11 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>> Fulfill<'s, I, Solver> {
             // for sure what `T` must be (it could be either `Foo<Bar>` or
             // `Foo<Baz>`, but we *can* say for sure that it must be of the
             // form `Foo<?0>`.
-            Ok(Solution::Ambig(Guidance::Definite(canonical_subst.0)))
+            Ok(Solution::Ambig(
+                Guidance::Definite(canonical_subst.0),
+                cause,
+                vec![],
+            ))
         }
     }
 
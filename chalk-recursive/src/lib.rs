@@ -1,12 +1,31 @@
+#![no_std]
+
+// Only `alloc` is needed, unless the `std` feature is enabled (it is by
+// default) to grow the stack, and share the cache between threads.
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
 use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_solve::{NoSolutionCause, Solution};
+use core::hash::BuildHasherDefault;
+use rustc_hash::FxHasher;
 
 pub type UCanonicalGoal<I> = UCanonical<InEnvironment<Goal<I>>>;
 
+/// The result of solving a goal, which the cache memoizes.
+pub type SolveResult<I> = Result<Solution<I>, NoSolutionCause<I>>;
+
 mod combine;
 mod fixed_point;
 mod fulfill;
 mod recursive;
 pub mod solve;
 
-pub use fixed_point::Cache;
+/// `rustc_hash` only provides its hash maps and sets with `std`.
+type FxHashMap<K, V> = hashbrown::HashMap<K, V, BuildHasherDefault<FxHasher>>;
+type FxHashSet<T> = hashbrown::HashSet<T, BuildHasherDefault<FxHasher>>;
+
+pub use fixed_point::{Cache, CacheBackend, CacheStats};
 pub use recursive::RecursiveSolver;
+pub use solve::{ClauseOrder, ClauseSource};
@@ -1,89 +1,197 @@
-use crate::fixed_point::{Cache, Minimums, RecursiveContext, SolverStuff};
-use crate::solve::{SolveDatabase, SolveIteration};
-use crate::UCanonicalGoal;
-use chalk_ir::{interner::Interner, NoSolution};
-use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, UCanonical};
-use chalk_ir::{Constraints, Fallible};
+use crate::fixed_point::{Cache, CacheBackend, GoalEvent, Minimums, RecursiveContext, SolverStuff};
+use crate::solve::{ClauseOrder, SolveDatabase, SolveIteration};
+use crate::{SolveResult, UCanonicalGoal};
+use alloc::boxed::Box;
+use alloc::vec;
+use chalk_ir::interner::Interner;
+use chalk_ir::Constraints;
+use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, TraitId, UCanonical};
 use chalk_solve::{coinductive_goal::IsCoinductive, RustIrDatabase, Solution};
-use std::fmt;
+use chalk_solve::{
+    AmbiguityCause, GoalOptions, Guidance, NoSolutionCause, Overflow, SolverEvent, SolverEventSink,
+    SolverStats, SubstitutionResult,
+};
+use core::cell::RefCell;
+use core::fmt;
 
 /// A Solver is the basic context in which you can propose goals for a given
 /// program. **All questions posed to the solver are in canonical, closed form,
 /// so that each question is answered with effectively a "clean slate"**. This
 /// allows for better caching, and simplifies management of the inference
 /// context.
-struct Solver<'me, I: Interner> {
-    program: &'me dyn RustIrDatabase<I>,
-    context: &'me mut RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+struct Solver<'me, I: Interner, C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>> {
+    program: Program<'me, I>,
+    context: &'me mut RecursiveContext<UCanonicalGoal<I>, SolveResult<I>, C>,
 }
 
-pub struct RecursiveSolver<I: Interner> {
-    ctx: Box<RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>>,
+/// The recursive solver. Results for goals that have been completely
+/// solved are memoized in a cache of type `C`, which defaults to `Cache`.
+pub struct RecursiveSolver<I: Interner, C = Cache<UCanonicalGoal<I>, SolveResult<I>>>
+where
+    C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>,
+{
+    ctx: Box<RecursiveContext<UCanonicalGoal<I>, SolveResult<I>, C>>,
+    clause_order: Option<ClauseOrder<I>>,
+    event_sink: Option<RefCell<SolverEventSink<I>>>,
 }
 
 impl<I: Interner> RecursiveSolver<I> {
     pub fn new(
         overflow_depth: usize,
         max_size: usize,
-        cache: Option<Cache<UCanonicalGoal<I>, Fallible<Solution<I>>>>,
+        cache: Option<Cache<UCanonicalGoal<I>, SolveResult<I>>>,
     ) -> Self {
         Self {
             ctx: Box::new(RecursiveContext::new(overflow_depth, max_size, cache)),
+            clause_order: None,
+            event_sink: None,
         }
     }
 }
 
-impl<I: Interner> fmt::Debug for RecursiveSolver<I> {
+impl<I: Interner, C> RecursiveSolver<I, C>
+where
+    C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>,
+{
+    /// Creates a solver that memoizes its results in `cache`. To share
+    /// results between solvers, give each of them a handle to the same
+    /// cache (e.g., clones of the same `Cache`).
+    pub fn with_cache(overflow_depth: usize, max_size: usize, cache: C) -> Self {
+        Self {
+            ctx: Box::new(RecursiveContext::new(overflow_depth, max_size, Some(cache))),
+            clause_order: None,
+            event_sink: None,
+        }
+    }
+
+    /// Enables or disables the leak check (see
+    /// `InferenceTable::leak_check`) when selecting the program clauses
+    /// which may prove a goal. It is disabled by default.
+    pub fn with_leak_check(mut self, leak_check: bool) -> Self {
+        self.ctx.set_leak_check(leak_check);
+        self
+    }
+
+    /// Enables or disables the normalization of the region constraints of
+    /// solutions (see `Constraints::normalized`), which drops the trivial
+    /// and repeated ones, and those implied by the outlives facts of the
+    /// environment (see `Constraints::without_implied`), and sorts them. It is enabled by default.
+    pub fn with_constraint_normalization(mut self, constraint_normalization: bool) -> Self {
+        self.ctx
+            .set_constraint_normalization(constraint_normalization);
+        self
+    }
+
+    /// Sets the order in which the clauses which may prove a goal are tried
+    /// (see `ClauseOrder`), e.g. the clauses of the environment before those
+    /// of the program. By default, they are tried in the order they are
+    /// gathered in. The order changes the order of the solutions
+    /// `solve_multiple` enumerates, and how much work solving takes.
+    pub fn with_clause_order(mut self, clause_order: ClauseOrder<I>) -> Self {
+        self.clause_order = Some(clause_order);
+        self
+    }
+
+    /// Reports the `SolverEvent`s of the solver to `event_sink`, as they
+    /// happen: the goals it creates tables for, the clauses it tries, the
+    /// solutions it settles on, and the floundering and cycles it runs
+    /// into.
+    pub fn with_event_sink(mut self, event_sink: SolverEventSink<I>) -> Self {
+        self.event_sink = Some(RefCell::new(event_sink));
+        self
+    }
+
+    /// The cache used by this solver, if caching is enabled.
+    pub fn cache(&self) -> Option<&C> {
+        self.ctx.cache()
+    }
+}
+
+impl<I: Interner, C> fmt::Debug for RecursiveSolver<I, C>
+where
+    C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>,
+{
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(fmt, "RecursiveSolver")
     }
 }
 
-impl<'me, I: Interner> Solver<'me, I> {
+impl<'me, I: Interner, C> Solver<'me, I, C>
+where
+    C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>,
+{
     pub(crate) fn new(
-        context: &'me mut RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>,
-        program: &'me dyn RustIrDatabase<I>,
+        context: &'me mut RecursiveContext<UCanonicalGoal<I>, SolveResult<I>, C>,
+        program: Program<'me, I>,
     ) -> Self {
         Self { program, context }
     }
 }
 
-impl<I: Interner> SolverStuff<UCanonicalGoal<I>, Fallible<Solution<I>>> for &dyn RustIrDatabase<I> {
+/// The program a goal is solved in, with the traits whose goals are
+/// coinductive on top of those of the program.
+#[derive(Copy, Clone)]
+struct Program<'me, I: Interner> {
+    db: &'me dyn RustIrDatabase<I>,
+    coinductive_overrides: &'me [TraitId<I>],
+    clause_order: Option<ClauseOrder<I>>,
+    event_sink: Option<&'me RefCell<SolverEventSink<I>>>,
+}
+
+impl<'me, I: Interner> Program<'me, I> {
+    fn new(
+        db: &'me dyn RustIrDatabase<I>,
+        clause_order: Option<ClauseOrder<I>>,
+        event_sink: Option<&'me RefCell<SolverEventSink<I>>>,
+    ) -> Self {
+        Program {
+            db,
+            coinductive_overrides: &[],
+            clause_order,
+            event_sink,
+        }
+    }
+
+    /// Reports the event `event` builds to the event sink, if there is one.
+    fn record_event(self, event: impl FnOnce() -> SolverEvent<I>) {
+        if let Some(sink) = self.event_sink {
+            (sink.borrow_mut())(&event());
+        }
+    }
+}
+
+impl<I: Interner> SolverStuff<UCanonicalGoal<I>, SolveResult<I>> for Program<'_, I> {
     fn is_coinductive_goal(self, goal: &UCanonicalGoal<I>) -> bool {
-        goal.is_coinductive(self)
+        goal.is_coinductive_with(self.db, self.coinductive_overrides)
     }
 
-    fn initial_value(
-        self,
-        goal: &UCanonicalGoal<I>,
-        coinductive_goal: bool,
-    ) -> Fallible<Solution<I>> {
+    fn initial_value(self, goal: &UCanonicalGoal<I>, coinductive_goal: bool) -> SolveResult<I> {
         if coinductive_goal {
             Ok(Solution::Unique(Canonical {
                 value: ConstrainedSubst {
-                    subst: goal.trivial_substitution(self.interner()),
-                    constraints: Constraints::empty(self.interner()),
+                    subst: goal.trivial_substitution(self.db.interner()),
+                    constraints: Constraints::empty(self.db.interner()),
                 },
-                binders: goal.canonical.binders.clone(),
+                binders: goal.canonical().binders.clone(),
             }))
         } else {
-            Err(NoSolution)
+            Err(NoSolutionCause::Unknown)
         }
     }
 
-    fn solve_iteration(
+    fn solve_iteration<C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>>(
         self,
-        context: &mut RecursiveContext<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+        context: &mut RecursiveContext<UCanonicalGoal<I>, SolveResult<I>, C>,
         goal: &UCanonicalGoal<I>,
         minimums: &mut Minimums,
-    ) -> Fallible<Solution<I>> {
+    ) -> SolveResult<I> {
         Solver::new(context, self).solve_iteration(goal, minimums)
     }
 
     fn reached_fixed_point(
         self,
-        old_answer: &Fallible<Solution<I>>,
-        current_answer: &Fallible<Solution<I>>,
+        old_answer: &SolveResult<I>,
+        current_answer: &SolveResult<I>,
     ) -> bool {
         // Some of our subgoals depended on us. We need to re-run
         // with the current answer.
@@ -98,61 +206,213 @@ impl<I: Interner> SolverStuff<UCanonicalGoal<I>, Fallible<Solution<I>>> for &dyn
         }
     }
 
-    fn error_value(self) -> Fallible<Solution<I>> {
-        Err(NoSolution)
+    fn error_value(self) -> SolveResult<I> {
+        Err(NoSolutionCause::Unknown)
+    }
+
+    fn overflow_value(self) -> SolveResult<I> {
+        Ok(Solution::Ambig(
+            Guidance::Unknown,
+            AmbiguityCause::Overflow,
+            vec![],
+        ))
+    }
+
+    fn record_event(self, event: GoalEvent<'_, UCanonicalGoal<I>, SolveResult<I>>) {
+        match event {
+            GoalEvent::TableCreated(goal) => {
+                self.record_event(|| SolverEvent::TableCreated { goal: goal.clone() })
+            }
+            GoalEvent::CycleDetected { goal, coinductive } => {
+                self.record_event(|| SolverEvent::CycleDetected {
+                    goal: goal.clone(),
+                    coinductive,
+                })
+            }
+            GoalEvent::Solved(goal, Ok(solution)) => {
+                self.record_event(|| SolverEvent::AnswerProduced {
+                    goal: goal.clone(),
+                    ambiguous: solution.is_ambig(),
+                })
+            }
+            GoalEvent::Solved(_, Err(_)) => {}
+        }
     }
 }
 
-impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
-    fn solve_goal(
-        &mut self,
-        goal: UCanonicalGoal<I>,
-        minimums: &mut Minimums,
-    ) -> Fallible<Solution<I>> {
+impl<'me, I: Interner, C> SolveDatabase<I> for Solver<'me, I, C>
+where
+    C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>,
+{
+    fn solve_goal(&mut self, goal: UCanonicalGoal<I>, minimums: &mut Minimums) -> SolveResult<I> {
         self.context.solve_goal(&goal, minimums, self.program)
     }
 
     fn interner(&self) -> I {
-        self.program.interner()
+        self.program.db.interner()
     }
 
     fn db(&self) -> &dyn RustIrDatabase<I> {
-        self.program
+        self.program.db
     }
 
     fn max_size(&self) -> usize {
         self.context.max_size()
     }
+
+    fn leak_check(&self) -> bool {
+        self.context.leak_check()
+    }
+
+    fn constraint_normalization(&self) -> bool {
+        self.context.constraint_normalization()
+    }
+
+    fn clause_order(&self) -> Option<ClauseOrder<I>> {
+        self.program.clause_order
+    }
+
+    fn record_clauses(&mut self, clauses: usize, duplicate_clauses: usize) {
+        self.context.record_clauses(clauses, duplicate_clauses);
+    }
+
+    fn record_overflow(&mut self, goal: UCanonicalGoal<I>) {
+        self.context.record_overflow(goal);
+    }
+
+    fn record_event(&mut self, event: impl FnOnce() -> SolverEvent<I>) {
+        self.program.record_event(event);
+    }
 }
 
-impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
+impl<I: Interner, C> chalk_solve::Solver<I> for RecursiveSolver<I, C>
+where
+    C: CacheBackend<UCanonicalGoal<I>, SolveResult<I>>,
+{
     fn solve(
         &mut self,
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<chalk_solve::Solution<I>> {
-        self.ctx.solve_root_goal(goal, program).ok()
+        self.solve_explained(program, goal).ok()
+    }
+
+    fn solve_explained(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> SolveResult<I> {
+        self.ctx.solve_root_goal(
+            goal,
+            Program::new(program, self.clause_order, self.event_sink.as_ref()),
+        )
+    }
+
+    fn solve_with_options(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        options: &GoalOptions<I>,
+    ) -> Option<Solution<I>> {
+        let max_size = options.max_size.unwrap_or_else(|| self.ctx.max_size());
+        // Goals given up on at the deadline are ambiguous, so their
+        // results can't be cached either.
+        let cached = max_size == self.ctx.max_size()
+            && options.coinductive_overrides.is_empty()
+            && options.timeout.is_none();
+        let program = Program {
+            db: program,
+            coinductive_overrides: &options.coinductive_overrides,
+            clause_order: self.clause_order,
+            event_sink: self.event_sink.as_ref(),
+        };
+        self.ctx
+            .with_limits(max_size, options.deadline(), cached, |ctx| {
+                ctx.solve_root_goal(goal, program)
+            })
+            .ok()
+    }
+
+    fn solve_or_overflow(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Result<Option<Solution<I>>, Overflow<I>> {
+        let solution = self.solve(program, goal);
+        match (&solution, self.ctx.overflow(goal)) {
+            (Some(Solution::Ambig(..)), Some(stack)) => Err(Overflow::new(stack.to_vec())),
+            _ => Ok(solution),
+        }
     }
 
     fn solve_limited(
         &mut self,
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
-        _should_continue: &dyn std::ops::Fn() -> bool,
+        _should_continue: &dyn core::ops::Fn() -> bool,
     ) -> Option<chalk_solve::Solution<I>> {
         // TODO support should_continue in recursive solver
-        self.ctx.solve_root_goal(goal, program).ok()
+        self.ctx
+            .solve_root_goal(
+                goal,
+                Program::new(program, self.clause_order, self.event_sink.as_ref()),
+            )
+            .ok()
     }
 
     fn solve_multiple(
         &mut self,
-        _program: &dyn RustIrDatabase<I>,
-        _goal: &UCanonical<InEnvironment<Goal<I>>>,
-        _f: &mut dyn FnMut(
-            chalk_solve::SubstitutionResult<Canonical<ConstrainedSubst<I>>>,
-            bool,
-        ) -> bool,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool {
-        unimplemented!("Recursive solver doesn't support multiple answers")
+        let interner = program.interner();
+        let solutions = Solver::new(
+            &mut self.ctx,
+            Program::new(program, self.clause_order, self.event_sink.as_ref()),
+        )
+        .enumerate_solutions(goal, &mut Minimums::new());
+        let mut solutions = solutions.into_iter().peekable();
+        while let Some(solution) = solutions.next() {
+            let subst = match solution {
+                Solution::Unique(subst) => SubstitutionResult::Definite(subst),
+                Solution::Ambig(Guidance::Definite(subst), ..)
+                | Solution::Ambig(Guidance::Suggested(subst), ..) => {
+                    SubstitutionResult::Ambiguous(Canonical {
+                        value: ConstrainedSubst {
+                            subst: subst.value,
+                            constraints: Constraints::empty(interner),
+                        },
+                        binders: subst.binders,
+                    })
+                }
+                // Without guidance, the solution is ambiguous about every
+                // variable of the goal.
+                Solution::Ambig(Guidance::Unknown, ..) => {
+                    SubstitutionResult::Ambiguous(Canonical {
+                        value: ConstrainedSubst {
+                            subst: goal.trivial_substitution(interner),
+                            constraints: Constraints::empty(interner),
+                        },
+                        binders: goal.canonical().binders.clone(),
+                    })
+                }
+            };
+            if !f(subst, solutions.peek().is_some()) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn stats(&self) -> SolverStats {
+        // Each cached goal has a single result, rather than answers.
+        let entries = self.cache().map_or(0, |cache| cache.stats().entries);
+        SolverStats {
+            tables: entries,
+            answers: None,
+            clauses: self.ctx.clauses(),
+            duplicate_clauses: self.ctx.duplicate_clauses(),
+        }
     }
 }
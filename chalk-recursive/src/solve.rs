@@ -1,29 +1,67 @@
 use super::combine;
 use super::fulfill::Fulfill;
 use crate::fixed_point::Minimums;
-use crate::UCanonicalGoal;
+use crate::{SolveResult, UCanonicalGoal};
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_ir::cast::Cast;
 use chalk_ir::could_match::CouldMatch;
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::{
-    Canonical, ClausePriority, DomainGoal, Fallible, Floundered, Goal, GoalData, InEnvironment,
-    NoSolution, ProgramClause, ProgramClauseData, Substitution, UCanonical,
+    Canonical, ClausePriority, DomainGoal, Floundered, Goal, GoalData, InEnvironment,
+    ProgramClause, ProgramClauseData, Substitution, UCanonical,
 };
-use chalk_solve::clauses::program_clauses_that_could_match;
+use chalk_solve::clauses::{dedup_program_clauses, program_clauses_that_could_match};
 use chalk_solve::debug_span;
 use chalk_solve::infer::InferenceTable;
-use chalk_solve::{Guidance, RustIrDatabase, Solution};
+use chalk_solve::{
+    AmbiguityCause, Guidance, NoSolutionCause, RustIrDatabase, Solution, SolverEvent,
+};
 use tracing::{debug, instrument};
 
+/// Where a clause the recursive solver tries to prove a goal with comes
+/// from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ClauseSource {
+    /// `RustIrDatabase::custom_clauses`.
+    Custom,
+    /// The clauses of the program, from its traits, impls and types.
+    Program,
+    /// The clauses of the environment of the goal.
+    Environment,
+}
+
+/// Ranks the clauses which may prove a goal: the recursive solver tries
+/// them by increasing rank, and in the order they are gathered in (custom
+/// clauses, then program clauses, then environment clauses) among those of
+/// the same rank. See `RecursiveSolver::with_clause_order`.
+pub type ClauseOrder<I> = fn(&dyn RustIrDatabase<I>, ClauseSource, &ProgramClause<I>) -> usize;
+
 pub(super) trait SolveDatabase<I: Interner>: Sized {
     fn solve_goal(
         &mut self,
         goal: UCanonical<InEnvironment<Goal<I>>>,
         minimums: &mut Minimums,
-    ) -> Fallible<Solution<I>>;
+    ) -> SolveResult<I>;
 
     fn max_size(&self) -> usize;
 
+    fn leak_check(&self) -> bool;
+
+    fn constraint_normalization(&self) -> bool;
+
+    fn clause_order(&self) -> Option<ClauseOrder<I>>;
+
+    fn record_clauses(&mut self, clauses: usize, duplicate_clauses: usize);
+
+    /// Records that `goal` exceeded the size or depth limits.
+    fn record_overflow(&mut self, goal: UCanonical<InEnvironment<Goal<I>>>);
+
+    /// Reports the event `event` builds to the event sink of the solver,
+    /// if it has one.
+    fn record_event(&mut self, event: impl FnOnce() -> SolverEvent<I>);
+
     fn interner(&self) -> I;
 
     fn db(&self) -> &dyn RustIrDatabase<I>;
@@ -40,28 +78,25 @@ pub(super) trait SolveIteration<I: Interner>: SolveDatabase<I> {
         &mut self,
         canonical_goal: &UCanonicalGoal<I>,
         minimums: &mut Minimums,
-    ) -> Fallible<Solution<I>> {
-        let UCanonical {
-            universes,
-            canonical:
-                Canonical {
-                    binders,
-                    value: InEnvironment { environment, goal },
-                },
-        } = canonical_goal.clone();
+    ) -> SolveResult<I> {
+        let universes = canonical_goal.universes();
+        let Canonical {
+            binders,
+            value: InEnvironment { environment, goal },
+        } = canonical_goal.clone().into_canonical();
 
         match goal.data(self.interner()) {
             GoalData::DomainGoal(domain_goal) => {
-                let canonical_goal = UCanonical {
-                    universes,
-                    canonical: Canonical {
+                let canonical_goal = UCanonical::new(
+                    Canonical {
                         binders,
                         value: InEnvironment {
                             environment,
                             goal: domain_goal.clone(),
                         },
                     },
-                };
+                    universes,
+                );
 
                 // "Domain" goals (i.e., leaf goals that are Rust-specific) are
                 // always solved via some form of implication. We can either
@@ -80,18 +115,81 @@ pub(super) trait SolveIteration<I: Interner>: SolveDatabase<I> {
             }
 
             _ => {
-                let canonical_goal = UCanonical {
-                    universes,
-                    canonical: Canonical {
+                let canonical_goal = UCanonical::new(
+                    Canonical {
                         binders,
                         value: InEnvironment { environment, goal },
                     },
-                };
+                    universes,
+                );
 
                 self.solve_via_simplification(&canonical_goal, minimums)
             }
         }
     }
+
+    /// Like `solve_iteration`, but rather than combining the solutions
+    /// obtained from each program clause into a single solution, returns
+    /// them separately (without duplicates). This is how the recursive
+    /// solver enumerates multiple answers: goals other than domain goals
+    /// only ever yield a single solution.
+    ///
+    /// As in `solve_from_clauses`, solutions from low-priority clauses are
+    /// only kept if no high-priority clause applies.
+    fn enumerate_solutions(
+        &mut self,
+        canonical_goal: &UCanonicalGoal<I>,
+        minimums: &mut Minimums,
+    ) -> Vec<Solution<I>> {
+        let universes = canonical_goal.universes();
+        let Canonical {
+            binders,
+            value: InEnvironment { environment, goal },
+        } = canonical_goal.clone().into_canonical();
+
+        let domain_goal = match goal.data(self.interner()) {
+            GoalData::DomainGoal(domain_goal) => domain_goal.clone(),
+            _ => {
+                return self
+                    .solve_goal(canonical_goal.clone(), minimums)
+                    .into_iter()
+                    .collect();
+            }
+        };
+        let canonical_goal = UCanonical::new(
+            Canonical {
+                binders,
+                value: InEnvironment {
+                    environment,
+                    goal: domain_goal,
+                },
+            },
+            universes,
+        );
+
+        let mut solutions: Vec<(Solution<I>, ClausePriority)> = vec![];
+        let result = self.solve_each_clause(&canonical_goal, minimums, |solution, priority| {
+            if !solutions.iter().any(|(s, _)| *s == solution) {
+                solutions.push((solution, priority));
+            }
+            true
+        });
+        if let Err(Floundered) = result {
+            return vec![Solution::Ambig(
+                Guidance::Unknown,
+                AmbiguityCause::FlounderedNegative,
+                vec![],
+            )];
+        }
+
+        if solutions
+            .iter()
+            .any(|(_, priority)| *priority == ClausePriority::High)
+        {
+            solutions.retain(|(_, priority)| *priority == ClausePriority::High);
+        }
+        solutions.into_iter().map(|(s, _)| s).collect()
+    }
 }
 
 impl<S, I> SolveIteration<I> for S
@@ -108,7 +206,7 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
         &mut self,
         canonical_goal: &UCanonicalGoal<I>,
         minimums: &mut Minimums,
-    ) -> Fallible<Solution<I>> {
+    ) -> SolveResult<I> {
         let (infer, subst, goal) = self.new_inference_table(canonical_goal);
         match Fulfill::new_with_simplification(self, infer, subst, goal) {
             Ok(fulfill) => fulfill.solve(minimums),
@@ -123,74 +221,152 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
         &mut self,
         canonical_goal: &UCanonical<InEnvironment<DomainGoal<I>>>,
         minimums: &mut Minimums,
-    ) -> Fallible<Solution<I>> {
+    ) -> SolveResult<I> {
+        let interner = self.interner();
+        let mut cur_solution = None;
+        let result = self.solve_each_clause(canonical_goal, minimums, |solution, priority| {
+            cur_solution = Some(match cur_solution.take() {
+                None => (solution, priority),
+                Some((cur, cur_priority)) => combine::with_priorities(
+                    interner,
+                    &canonical_goal.canonical().binders,
+                    &canonical_goal.canonical().value.goal,
+                    cur,
+                    cur_priority,
+                    solution,
+                    priority,
+                ),
+            });
+
+            // If we have a completely ambiguous answer, it's not going to get better, so stop
+            !matches!(
+                cur_solution,
+                Some((Solution::Ambig(Guidance::Unknown, ..), ClausePriority::High))
+            )
+        });
+        let cause = match result {
+            Ok(cause) => cause,
+            Err(Floundered) => {
+                return Ok(Solution::Ambig(
+                    Guidance::Unknown,
+                    AmbiguityCause::FlounderedNegative,
+                    vec![],
+                ));
+            }
+        };
+
+        match cur_solution {
+            Some((s, _)) => Ok(s),
+            None => Err(cause),
+        }
+    }
+
+    /// Tries to solve the goal by implication on each of the clauses that
+    /// could match it in turn, passing each solution found (and the
+    /// priority of the clause it came from) to `op`. Stops early if `op`
+    /// returns false. Returns the first known cause of the clauses that
+    /// had no solution (see `NoSolutionCause::or`).
+    fn solve_each_clause(
+        &mut self,
+        canonical_goal: &UCanonical<InEnvironment<DomainGoal<I>>>,
+        minimums: &mut Minimums,
+        mut op: impl FnMut(Solution<I>, ClausePriority) -> bool,
+    ) -> Result<NoSolutionCause<I>, Floundered> {
         let mut clauses = vec![];
+        let mut cause = NoSolutionCause::Unknown;
+
+        let interner = self.interner();
+        // The goal as the events report it.
+        let event_goal = || {
+            UCanonical::new(
+                Canonical {
+                    binders: canonical_goal.canonical().binders.clone(),
+                    value: canonical_goal
+                        .canonical()
+                        .value
+                        .clone()
+                        .map(|goal| goal.cast(interner)),
+                },
+                canonical_goal.universes(),
+            )
+        };
 
         let db = self.db();
         let could_match = |c: &ProgramClause<I>| {
             c.could_match(
                 db.interner(),
                 db.unification_database(),
-                &canonical_goal.canonical.value.goal,
+                &canonical_goal.canonical().value.goal,
             )
         };
-        clauses.extend(db.custom_clauses().into_iter().filter(could_match));
-        match program_clauses_that_could_match(db, canonical_goal) {
-            Ok(goal_clauses) => clauses.extend(goal_clauses.into_iter().filter(could_match)),
+        clauses.extend(
+            db.custom_clauses()
+                .into_iter()
+                .filter(could_match)
+                .map(|clause| (ClauseSource::Custom, clause)),
+        );
+        let program_clauses = match program_clauses_that_could_match(db, canonical_goal) {
+            Ok(program_clauses) => program_clauses,
             Err(Floundered) => {
-                return Ok(Solution::Ambig(Guidance::Unknown));
+                self.record_event(|| SolverEvent::StrandFloundered { goal: event_goal() });
+                return Err(Floundered);
             }
-        }
+        };
+        clauses.extend(
+            program_clauses
+                .into_iter()
+                .filter(could_match)
+                .map(|clause| (ClauseSource::Program, clause)),
+        );
 
         let (infer, subst, goal) = self.new_inference_table(canonical_goal);
         clauses.extend(
             db.program_clauses_for_env(&goal.environment)
                 .iter(db.interner())
                 .cloned()
-                .filter(could_match),
+                .filter(could_match)
+                .map(|clause| (ClauseSource::Environment, clause)),
         );
+        // The sort is stable, and duplicates are removed afterwards, keeping
+        // the clause of the lowest rank.
+        if let Some(clause_order) = self.clause_order() {
+            clauses.sort_by_key(|(source, clause)| clause_order(db, *source, clause));
+        }
+        let mut clauses: Vec<_> = clauses.into_iter().map(|(_, clause)| clause).collect();
+        let duplicate_clauses = dedup_program_clauses(&mut clauses);
+        self.record_clauses(clauses.len(), duplicate_clauses);
 
-        let mut cur_solution = None;
         for program_clause in clauses {
             debug_span!("solve_from_clauses", clause = ?program_clause);
 
-            // If we have a completely ambiguous answer, it's not going to get better, so stop
-            if cur_solution == Some((Solution::Ambig(Guidance::Unknown), ClausePriority::High)) {
-                return Ok(Solution::Ambig(Guidance::Unknown));
-            }
-
+            self.record_event(|| SolverEvent::ClauseTried {
+                goal: event_goal(),
+                clause: program_clause.clone(),
+            });
             let ProgramClauseData(implication) = program_clause.data(self.interner());
             let infer = infer.clone();
             let subst = subst.clone();
             let goal = goal.clone();
-            let res = match Fulfill::new_with_clause(self, infer, subst, goal, implication) {
+            let res = match Fulfill::new_with_clause(self, infer, subst, goal, &program_clause) {
                 Ok(fulfill) => (fulfill.solve(minimums), implication.skip_binders().priority),
                 Err(e) => (Err(e), ClausePriority::High),
             };
 
-            if let (Ok(solution), priority) = res {
-                debug!(?solution, ?priority, "Ok");
-                cur_solution = Some(match cur_solution {
-                    None => (solution, priority),
-                    Some((cur, cur_priority)) => combine::with_priorities(
-                        self.interner(),
-                        &canonical_goal.canonical.value.goal,
-                        cur,
-                        cur_priority,
-                        solution,
-                        priority,
-                    ),
-                });
-            } else {
-                debug!("Error");
+            match res {
+                (Ok(solution), priority) => {
+                    debug!(?solution, ?priority, "Ok");
+                    if !op(solution, priority) {
+                        break;
+                    }
+                }
+                (Err(e), _) => {
+                    debug!(?e, "Error");
+                    cause = cause.or(e);
+                }
             }
         }
 
-        if let Some((s, _)) = cur_solution {
-            Ok(s)
-        } else {
-            Err(NoSolution)
-        }
+        Ok(cause)
     }
 
     fn new_inference_table<T: Fold<I, Result = T> + HasInterner<Interner = I> + Clone>(
@@ -199,8 +375,8 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
     ) -> (InferenceTable<I>, Substitution<I>, InEnvironment<T::Result>) {
         let (infer, subst, canonical_goal) = InferenceTable::from_canonical(
             self.interner(),
-            ucanonical_goal.universes,
-            ucanonical_goal.canonical.clone(),
+            ucanonical_goal.universes(),
+            ucanonical_goal.canonical().clone(),
         );
         (infer, subst, canonical_goal)
     }
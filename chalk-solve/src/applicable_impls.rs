@@ -0,0 +1,67 @@
+//! Finds the impls which may apply to a trait reference, by matching their
+//! headers only, for tools like "go to implementation" which want the
+//! candidates rather than a proof.
+
+use crate::infer::{InferenceTable, VariableOrigin};
+use crate::simplified_type::ImplFilter;
+use crate::RustIrDatabase;
+use alloc::vec::Vec;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+
+/// Returns the positive impls whose trait reference unifies with
+/// `trait_ref`, each with the substitution of its parameters which makes
+/// it apply, like `[?0 := u32]` for `impl<T> Clone for Vec<T>` and
+/// `Vec<u32>: Clone`. The variables of the substitutions are the
+/// parameters left unconstrained by the match.
+///
+/// Unlike solving `trait_ref`, this does not prove the where clauses of
+/// the impls, nor the goals unification produces (the region constraints,
+/// and the normalization of the associated types in impl headers): the
+/// impls returned may not apply in the end, but those which are not
+/// returned never do. Builtin and auto trait impls are not considered.
+pub fn find_applicable_impls<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_ref: &Canonical<TraitRef<I>>,
+) -> Vec<(ImplId<I>, Canonical<Substitution<I>>)> {
+    let interner = db.interner();
+    let filter = ImplFilter::new(
+        interner,
+        trait_ref.value.substitution.as_slice(interner),
+        &trait_ref.binders,
+    );
+    db.impls_for_trait(trait_ref.value.trait_id, &filter)
+        .into_iter()
+        .filter_map(|impl_id| {
+            let impl_datum = db.impl_datum(impl_id);
+            if !impl_datum.is_positive() {
+                return None;
+            }
+
+            let mut table = InferenceTable::new();
+            let goal = table.instantiate_canonical(interner, trait_ref.clone());
+            let parameters = impl_datum.binders.identity_substitution(interner);
+            let parameters = table.instantiate_binders_existentially_with_origin(
+                interner,
+                impl_datum.binders.map_ref(|_| parameters),
+                |index| Some(VariableOrigin::ImplParameter { impl_id, index }),
+            );
+            let impl_trait_ref = impl_datum
+                .binders
+                .map_ref(|bound| &bound.trait_ref)
+                .cloned()
+                .substitute(interner, &parameters);
+            table
+                .relate(
+                    interner,
+                    db.unification_database(),
+                    &Environment::new(interner),
+                    Variance::Invariant,
+                    &goal,
+                    &impl_trait_ref,
+                )
+                .ok()?;
+            Some((impl_id, table.canonicalize(interner, parameters).quantified))
+        })
+        .collect()
+}
@@ -0,0 +1,111 @@
+//! The deref chain of a type (its "autoderef steps"), for hosts which
+//! dereference receivers or places implicitly: a reference steps to its
+//! referent, and any other type to the `Target` of its `Deref` impl (see
+//! `WellKnownTrait::Deref`).
+
+use crate::infer::InferenceTable;
+use crate::rust_ir::WellKnownTrait;
+use crate::solve::{Solution, Solver};
+use crate::RustIrDatabase;
+use alloc::vec;
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use core::iter;
+
+/// How many autoderef steps are taken at most, so that types which deref
+/// to bigger and bigger types do not make the chain endless.
+const AUTODEREF_LIMIT: usize = 16;
+
+/// The autoderef steps of `ty` in `environment`, starting with `ty` itself.
+/// The chain stops at types which do not deref, or do not deref to a
+/// single type, at types already stepped through (as `Deref` impls may form
+/// cycles), and after a fixed number of steps.
+///
+/// Stepping from a type which is not a reference solves
+/// `Normalize(<T as Deref>::Target -> ?U)` with `solver`, lazily, as the
+/// steps are consumed.
+pub fn autoderef<'a, I: Interner>(
+    db: &'a dyn RustIrDatabase<I>,
+    solver: &'a mut dyn Solver<I>,
+    environment: &'a Environment<I>,
+    ty: Canonical<Ty<I>>,
+) -> impl Iterator<Item = Canonical<Ty<I>>> + 'a {
+    let mut steps = vec![];
+    iter::successors(Some(ty), move |step| {
+        steps.push(step.clone());
+        if steps.len() >= AUTODEREF_LIMIT {
+            return None;
+        }
+        deref(db, solver, environment, step).filter(|next| !steps.contains(next))
+    })
+}
+
+/// The type `ty` derefs to, if it derefs to a single type.
+fn deref<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    solver: &mut dyn Solver<I>,
+    environment: &Environment<I>,
+    ty: &Canonical<Ty<I>>,
+) -> Option<Canonical<Ty<I>>> {
+    let interner = db.interner();
+    if let TyKind::Ref(_, _, referent) = ty.value.kind(interner) {
+        return Some(Canonical {
+            binders: ty.binders.clone(),
+            value: referent.clone(),
+        });
+    }
+
+    // Find the target by solving `Normalize(<T as Deref>::Target -> ?U)`
+    // for the variable `?U`, added to the binders of `ty`.
+    let deref_trait = db.well_known_trait_id(WellKnownTrait::Deref)?;
+    let target = *db.trait_datum(deref_trait).associated_ty_ids.first()?;
+    let target_var = ty.binders.len(interner);
+    let binders = CanonicalVarKinds::from_iter(
+        interner,
+        ty.binders
+            .iter(interner)
+            .cloned()
+            .chain(Some(CanonicalVarKind::new(
+                VariableKind::Ty(TyVariableKind::General),
+                UniverseIndex::root(),
+            ))),
+    );
+    let goal = DomainGoal::Normalize(Normalize {
+        alias: AliasTy::Projection(ProjectionTy {
+            associated_ty_id: target,
+            substitution: Substitution::from1(interner, ty.value.clone()),
+        }),
+        ty: TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, target_var)).intern(interner),
+    })
+    .cast(interner);
+    match solve(db, solver, environment, &binders, goal)? {
+        Solution::Unique(answer) => Some(Canonical {
+            value: answer
+                .value
+                .subst
+                .at(interner, target_var)
+                .assert_ty_ref(interner)
+                .clone(),
+            binders: answer.binders,
+        }),
+        Solution::Ambig(..) => None,
+    }
+}
+
+/// Solves `goal`, in which the variables of `binders` are bound.
+pub(crate) fn solve<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    solver: &mut dyn Solver<I>,
+    environment: &Environment<I>,
+    binders: &CanonicalVarKinds<I>,
+    goal: Goal<I>,
+) -> Option<Solution<I>> {
+    let interner = db.interner();
+    let canonical = Canonical {
+        binders: binders.clone(),
+        value: InEnvironment::new(environment, goal),
+    };
+    let goal = InferenceTable::u_canonicalize(interner, &canonical).quantified;
+    solver.solve(db, &goal)
+}
@@ -3,19 +3,23 @@ use self::env_elaborator::elaborate_env_clauses;
 use self::program_clauses::ToProgramClauses;
 use crate::goal_builder::GoalBuilder;
 use crate::rust_ir::{Movability, WellKnownTrait};
+use crate::simplified_type::ImplFilter;
 use crate::split::Split;
+use crate::FxHashSet;
 use crate::RustIrDatabase;
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_ir::cast::{Cast, Caster};
 use chalk_ir::could_match::CouldMatch;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use rustc_hash::FxHashSet;
-use std::iter;
-use std::marker::PhantomData;
+use core::iter;
+use core::marker::PhantomData;
 use tracing::{debug, instrument};
 
 pub mod builder;
 mod builtin_traits;
+pub mod custom_builtins;
 mod dyn_ty;
 mod env_elaborator;
 mod generalize;
@@ -78,6 +82,7 @@ fn constituent_types<I: Interner>(db: &dyn RustIrDatabase<I>, ty: &TyKind<I>) ->
         TyKind::Dyn(_) => panic!("this function should not be called for dyn types"),
         TyKind::Alias(_) => panic!("this function should not be called for alias"),
         TyKind::Foreign(_) => panic!("constituent_types of foreign types are unknown!"),
+        TyKind::Custom(_) => panic!("constituent_types of custom types are unknown!"),
         TyKind::Error => Vec::new(),
         TyKind::OpaqueType(_, _) => panic!("constituent_types of opaque types are unknown!"),
         TyKind::AssociatedType(_, _) => {
@@ -135,8 +140,10 @@ pub fn push_auto_trait_impls<I: Interner>(
     );
 
     // If there is a `impl AutoTrait for Foo<..>` or `impl !AutoTrait
-    // for Foo<..>`, where `Foo` is the adt we're looking at, then
-    // we don't generate our own rules.
+    // for Foo<..>`, where `Foo` is the adt (or generator, closure, ..)
+    // we're looking at, then we don't generate our own rules. This is
+    // also what lets `impl !Unpin for MyGen` opt a movable generator out
+    // of its builtin `Unpin` impl.
     if builder.db.impl_provided_for(auto_trait_id, ty) {
         debug!("impl provided");
         return Ok(());
@@ -157,18 +164,17 @@ pub fn push_auto_trait_impls<I: Interner>(
         }
         TyKind::InferenceVar(_, _) | TyKind::BoundVar(_) => Err(Floundered),
 
-        // auto traits are not implemented for foreign types
-        TyKind::Foreign(_) => Ok(()),
-
-        // closures require binders, while the other types do not
-        TyKind::Closure(closure_id, _) => {
-            let binders = builder
-                .db
-                .closure_upvars(*closure_id, &Substitution::empty(interner));
-            builder.push_binders(binders, |builder, upvar_ty| {
-                let conditions = iter::once(mk_ref(upvar_ty));
-                builder.push_clause(consequence, conditions);
-            });
+        // auto traits are not implemented for foreign or custom types
+        TyKind::Foreign(_) | TyKind::Custom(_) => Ok(()),
+
+        // closures implement auto traits if all their upvars do
+        TyKind::Closure(closure_id, substitution) => {
+            let db = builder.db;
+            let closure_fn_substitution = db.closure_fn_substitution(*closure_id, substitution);
+            let upvars = db
+                .closure_upvars(*closure_id, substitution)
+                .substitute(interner, &closure_fn_substitution);
+            builder.push_clause(consequence, iter::once(mk_ref(upvars)));
             Ok(())
         }
         TyKind::Generator(generator_id, _) => {
@@ -203,7 +209,9 @@ pub fn push_auto_trait_impls<I: Interner>(
         | TyKind::Dyn(_)
         | TyKind::Alias(_) => Ok(()),
 
-        // app_ty implements AutoTrait if all constituents of app_ty implement AutoTrait
+        // app_ty implements AutoTrait if all constituents of app_ty implement AutoTrait.
+        // For recursive types like `struct List { next: Box<List> }`, this leads
+        // back to `List: AutoTrait`, which holds as auto trait goals are coinductive.
         _ => {
             let conditions = constituent_types(builder.db, ty).into_iter().map(mk_ref);
 
@@ -261,7 +269,7 @@ pub fn push_auto_trait_impls_opaque<I: Interner>(
         // OpaqueType<...>: MyAutoTrait :- HiddenType: MyAutoTrait
         builder.push_clause(
             auto_trait_ref,
-            std::iter::once(TraitRef {
+            core::iter::once(TraitRef {
                 trait_id: auto_trait_id,
                 substitution: Substitution::from1(interner, hidden_ty.clone()),
             }),
@@ -275,17 +283,26 @@ pub fn push_auto_trait_impls_generator_witness<I: Interner>(
     auto_trait_id: TraitId<I>,
     generator_id: GeneratorId<I>,
 ) {
-    let witness_datum = builder.db.generator_witness_datum(generator_id);
-    let interner = builder.interner();
-
     // Must be an auto trait.
     assert!(builder.db.trait_datum(auto_trait_id).is_auto_trait());
 
-    // Auto traits never have generic parameters of their own (apart from `Self`).
-    assert_eq!(
-        builder.db.trait_datum(auto_trait_id).binders.len(interner),
-        1
-    );
+    push_generator_witness_clauses(builder, auto_trait_id, generator_id);
+}
+
+/// Pushes the clause that the witness of the generator `generator_id`
+/// implements `trait_id` if each of the types it holds does, for any
+/// lifetimes. Used for auto traits, and for the builtin `Copy` and `Clone`
+/// impls, none of which have generic parameters of their own.
+#[instrument(level = "debug", skip(builder))]
+pub fn push_generator_witness_clauses<I: Interner>(
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_id: TraitId<I>,
+    generator_id: GeneratorId<I>,
+) {
+    let witness_datum = builder.db.generator_witness_datum(generator_id);
+    let interner = builder.interner();
+
+    assert_eq!(builder.db.trait_datum(trait_id).binders.len(interner), 1);
 
     // Push binders for the generator generic parameters. These can be used by
     // both upvars and witness types
@@ -293,17 +310,17 @@ pub fn push_auto_trait_impls_generator_witness<I: Interner>(
         let witness_ty = TyKind::GeneratorWitness(generator_id, builder.substitution_in_scope())
             .intern(interner);
 
-        // trait_ref = `GeneratorWitness<...>: MyAutoTrait`
-        let auto_trait_ref = TraitRef {
-            trait_id: auto_trait_id,
+        // trait_ref = `GeneratorWitness<...>: MyTrait`
+        let trait_ref = TraitRef {
+            trait_id,
             substitution: Substitution::from1(interner, witness_ty),
         };
 
         // Create a goal of the form:
         // forall<L0, L1, ..., LN> {
-        //     WitnessType1<L0, L1, ... LN, P0, P1, ..., PN>: MyAutoTrait,
+        //     WitnessType1<L0, L1, ... LN, P0, P1, ..., PN>: MyTrait,
         //     ...
-        //     WitnessTypeN<L0, L1, ... LN, P0, P1, ..., PN>: MyAutoTrait,
+        //     WitnessTypeN<L0, L1, ... LN, P0, P1, ..., PN>: MyTrait,
         //
         // }
         //
@@ -313,7 +330,7 @@ pub fn push_auto_trait_impls_generator_witness<I: Interner>(
         // We create a 'forall' goal due to the fact that our witness lifetimes
         // are *existentially* quantified - the precise reigon is erased during
         // type checking, so we just know that the type takes *some* region
-        // as a parameter. Therefore, we require that the auto trait bound
+        // as a parameter. Therefore, we require that the bound
         // hold for *all* regions, which guarantees that the bound will
         // hold for the original lifetime (before it was erased).
         //
@@ -328,14 +345,14 @@ pub fn push_auto_trait_impls_generator_witness<I: Interner>(
         let gb = &mut GoalBuilder::new(builder.db);
         let witness_goal = gb.forall(
             &inner_types.types,
-            auto_trait_id,
-            |gb, _subst, types, auto_trait_id| {
+            trait_id,
+            |gb, _subst, types, trait_id| {
                 Goal::new(
                     gb.interner(),
                     GoalData::All(Goals::from_iter(
                         gb.interner(),
                         types.iter().map(|witness_ty| TraitRef {
-                            trait_id: auto_trait_id,
+                            trait_id,
                             substitution: Substitution::from1(gb.interner(), witness_ty.clone()),
                         }),
                     )),
@@ -345,7 +362,7 @@ pub fn push_auto_trait_impls_generator_witness<I: Interner>(
 
         // GeneratorWitnessType: AutoTrait :- forall<...> ...
         // where 'forall<...> ...' is the goal described above.
-        builder.push_clause(auto_trait_ref, std::iter::once(witness_goal));
+        builder.push_clause(trait_ref, core::iter::once(witness_goal));
     })
 }
 
@@ -365,10 +382,10 @@ pub fn program_clauses_for_goal<'db, I: Interner>(
     let clauses_that_could_match =
         program_clauses_that_could_match(db, goal).map(|cl| cl.into_iter())?;
 
-    let clauses: Vec<ProgramClause<I>> = custom_clauses
+    let mut clauses: Vec<ProgramClause<I>> = custom_clauses
         .chain(clauses_that_could_match)
         .chain(
-            db.program_clauses_for_env(&goal.canonical.value.environment)
+            db.program_clauses_for_env(&goal.canonical().value.environment)
                 .iter(interner)
                 .cloned(),
         )
@@ -376,16 +393,91 @@ pub fn program_clauses_for_goal<'db, I: Interner>(
             c.could_match(
                 interner,
                 db.unification_database(),
-                &goal.canonical.value.goal,
+                &goal.canonical().value.goal,
             )
         })
         .collect();
+    dedup_program_clauses(&mut clauses);
 
     debug!(?clauses);
 
     Ok(clauses)
 }
 
+/// Returns the clauses a `dyn Trait` type gets from its own bounds (its
+/// "object candidates"), as shown for `dyn_ty::build_dyn_self_ty_clauses`:
+/// `Implemented` for the principal trait, its supertraits and the auto
+/// traits, and the associated type bindings of the bounds as facts. Outlives
+/// bounds do not give clauses.
+pub fn dyn_object_candidate_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    dyn_ty: &DynTy<I>,
+) -> Vec<ProgramClause<I>> {
+    let interner = db.interner();
+    let mut clauses = vec![];
+    let builder = &mut ClauseBuilder::new(db, &mut clauses);
+    let self_ty = TyKind::Dyn(dyn_ty.clone()).intern(interner);
+    dyn_ty::build_dyn_self_ty_clauses(db, builder, self_ty);
+    dedup_program_clauses(&mut clauses);
+    clauses
+}
+
+/// Removes the duplicates from `clauses`, keeping the first occurrence of
+/// each clause, and returns the number of clauses removed. Duplicates arise,
+/// e.g., when a where clause in the environment is also an implied bound
+/// of another one, or when a custom clause is also generated from the
+/// program; without this, the solvers would try each of them in turn.
+pub fn dedup_program_clauses<I: Interner>(clauses: &mut Vec<ProgramClause<I>>) -> usize {
+    let len = clauses.len();
+    let mut seen = FxHashSet::default();
+    clauses.retain(|clause| seen.insert(clause.clone()));
+    len - clauses.len()
+}
+
+/// Returns the impl `clause` is a rule of, i.e. whose `Implemented-From-Impl`
+/// or `Normalize-From-Impl` rule it is, if any. This regenerates the rules
+/// of the impls of the clause's trait, so it is only meant for diagnostics.
+pub fn impl_of_clause<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    clause: &ProgramClause<I>,
+) -> Option<ImplId<I>> {
+    let interner = db.interner();
+    let ProgramClauseData(implication) = clause.data(interner);
+    // The parameters of the clause's trait reference, in which the clause's
+    // variables appear as bound variables.
+    let (trait_id, parameters) = match &implication.skip_binders().consequence {
+        DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => (
+            trait_ref.trait_id,
+            trait_ref.substitution.as_slice(interner),
+        ),
+        DomainGoal::Normalize(Normalize {
+            alias: AliasTy::Projection(projection),
+            ..
+        }) => (
+            db.associated_ty_data(projection.associated_ty_id).trait_id,
+            db.trait_parameters_from_projection(projection),
+        ),
+        _ => return None,
+    };
+
+    let environment = Environment::new(interner);
+    let binders = CanonicalVarKinds::empty(interner);
+    let filter = ImplFilter::new(interner, parameters, &binders);
+    db.impls_for_trait(trait_id, &filter)
+        .into_iter()
+        .find(|&impl_id| {
+            let mut clauses = vec![];
+            let builder = &mut ClauseBuilder::new(db, &mut clauses);
+            let impl_datum = db.impl_datum(impl_id);
+            impl_datum.to_program_clauses(builder, &environment);
+            for &atv_id in &impl_datum.associated_ty_value_ids {
+                db.associated_ty_value(atv_id)
+                    .to_program_clauses(builder, &environment);
+            }
+            clauses.contains(clause)
+        })
+}
+
 /// Returns a set of program clauses that could possibly match
 /// `goal`. This can be any superset of the correct set, but the
 /// more precise you can make it, the more efficient solving will
@@ -399,14 +491,10 @@ pub fn program_clauses_that_could_match<I: Interner>(
     let mut clauses: Vec<ProgramClause<I>> = vec![];
     let builder = &mut ClauseBuilder::new(db, &mut clauses);
 
-    let UCanonical {
-        canonical:
-            Canonical {
-                value: InEnvironment { environment, goal },
-                binders,
-            },
-        universes: _,
-    } = goal;
+    let Canonical {
+        value: InEnvironment { environment, goal },
+        binders,
+    } = goal.canonical();
 
     match goal {
         DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
@@ -469,11 +557,15 @@ pub fn program_clauses_that_could_match<I: Interner>(
             // as for the `Implemented(Foo) :- FromEnv(Foo)` rule.
             trait_datum.to_program_clauses(builder, environment);
 
-            for impl_id in db.impls_for_trait(
-                trait_ref.trait_id,
-                trait_ref.substitution.as_slice(interner),
-                binders,
-            ) {
+            // Impls are looked up by self type when it is known, as most
+            // databases index them by it.
+            let filter =
+                ImplFilter::new(interner, trait_ref.substitution.as_slice(interner), binders);
+            let impl_ids = match filter.self_type() {
+                Some(self_ty) => db.impls_for_trait_by_self_type(trait_id, self_ty),
+                None => db.impls_for_trait(trait_id, &filter),
+            };
+            for impl_id in impl_ids {
                 db.impl_datum(impl_id)
                     .to_program_clauses(builder, environment);
             }
@@ -498,6 +590,16 @@ pub fn program_clauses_that_could_match<I: Interner>(
                     binders,
                 )?;
             }
+
+            if let Some(builtin) = db.custom_builtin(trait_id) {
+                custom_builtins::add_custom_builtin_program_clauses(
+                    db,
+                    builder,
+                    &builtin,
+                    trait_ref.clone(),
+                    binders,
+                )?;
+            }
         }
         DomainGoal::Holds(WhereClause::AliasEq(alias_eq)) => match &alias_eq.alias {
             AliasTy::Projection(proj) => {
@@ -537,6 +639,11 @@ pub fn program_clauses_that_could_match<I: Interner>(
             AliasTy::Opaque(opaque_ty) => db
                 .opaque_ty_data(opaque_ty.opaque_ty_id)
                 .to_program_clauses(builder, environment),
+            AliasTy::Inherent(inherent_ty) => {
+                if let Some(datum) = db.inherent_assoc_ty_data(inherent_ty.associated_ty_id) {
+                    datum.to_program_clauses(builder, environment)
+                }
+            }
         },
         DomainGoal::Holds(WhereClause::LifetimeOutlives(..)) => {
             builder.push_bound_lifetime(|builder, a| {
@@ -643,8 +750,38 @@ pub fn program_clauses_that_could_match<I: Interner>(
                     );
                 }
             }
+            // Normalize goals for inherent associated types derive from
+            // the values in the inherent impls of the type, which don't
+            // go through a trait:
+            //
+            // ```ignore
+            // impl Foo {
+            //     type Bar = Baz; // <-- value
+            // }
+            // ```
+            AliasTy::Inherent(inherent_ty) => {
+                if let Some(datum) = db.inherent_assoc_ty_data(inherent_ty.associated_ty_id) {
+                    datum.to_program_clauses(builder, environment)
+                }
+            }
             AliasTy::Opaque(_) => (),
         },
+        DomainGoal::FnPtrCoercion(coercion) => {
+            builtin_traits::add_fn_ptr_coercion_program_clauses(
+                db,
+                builder,
+                coercion.clone(),
+                binders,
+            )?;
+        }
+        DomainGoal::Custom(predicate) => {
+            // If `predicate` contains bound vars, we want to universally quantify them.
+            // `Generalize` collects them for us.
+            let generalized = generalize::Generalize::apply(interner, predicate.clone());
+            builder.push_binders(generalized, |builder, predicate| {
+                db.custom_predicate_clauses(builder, &predicate)
+            })?;
+        }
         DomainGoal::Compatible | DomainGoal::Reveal => (),
     };
 
@@ -730,10 +867,8 @@ fn push_program_clauses_for_associated_type_values_in_impls_of<I: Interner>(
     trait_parameters: &[GenericArg<I>],
     binders: &CanonicalVarKinds<I>,
 ) {
-    for impl_id in builder
-        .db
-        .impls_for_trait(trait_id, trait_parameters, binders)
-    {
+    let filter = ImplFilter::new(builder.db.interner(), trait_parameters, binders);
+    for impl_id in builder.db.impls_for_trait(trait_id, &filter) {
         let impl_datum = builder.db.impl_datum(impl_id);
         if !impl_datum.is_positive() {
             continue;
@@ -771,7 +906,7 @@ fn push_alias_implemented_clause<I: Interner>(
         builder.push_binders(binders, |builder, bound_var| {
             let fresh_self_subst = Substitution::from_iter(
                 interner,
-                std::iter::once(bound_var.clone().cast(interner)).chain(
+                core::iter::once(bound_var.clone().cast(interner)).chain(
                     trait_ref.substitution.as_slice(interner)[1..]
                         .iter()
                         .cloned(),
@@ -818,7 +953,7 @@ fn push_alias_alias_eq_clause<I: Interner>(
         builder.push_binders(binders, |builder, bound_var| {
             let fresh_self_subst = Substitution::from_iter(
                 interner,
-                std::iter::once(bound_var.clone().cast(interner)).chain(
+                core::iter::once(bound_var.clone().cast(interner)).chain(
                     projection_ty.substitution.as_slice(interner)[1..]
                         .iter()
                         .cloned(),
@@ -890,6 +1025,7 @@ fn match_ty<I: Interner>(
         | TyKind::Never
         | TyKind::Scalar(_)
         | TyKind::Foreign(_)
+        | TyKind::Custom(_)
         | TyKind::Tuple(0, _) => {
             // These have no substitutions, so they are trivially WF
             builder.push_fact(WellFormed::Ty(ty.clone()));
@@ -1039,6 +1175,14 @@ fn match_ty<I: Interner>(
             .db
             .opaque_ty_data(opaque_ty.opaque_ty_id)
             .to_program_clauses(builder, environment),
+        TyKind::Alias(AliasTy::Inherent(inherent_ty)) => {
+            if let Some(datum) = builder
+                .db
+                .inherent_assoc_ty_data(inherent_ty.associated_ty_id)
+            {
+                datum.to_program_clauses(builder, environment)
+            }
+        }
         TyKind::Function(_quantified_ty) => {
             let ty = generalize::Generalize::apply(builder.db.interner(), ty.clone());
             builder.push_binders(ty, |builder, ty| builder.push_fact(WellFormed::Ty(ty)));
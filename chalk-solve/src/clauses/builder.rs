@@ -1,4 +1,6 @@
-use std::marker::PhantomData;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 
 use crate::cast::{Cast, CastTo};
 use crate::RustIrDatabase;
@@ -136,7 +138,7 @@ impl<'me, I: Interner> ClauseBuilder<'me, I> {
     ) -> R
     where
         V: Fold<I> + HasInterner<Interner = I>,
-        V::Result: std::fmt::Debug,
+        V::Result: core::fmt::Debug,
     {
         let old_len = self.binders.len();
         let interner = self.interner();
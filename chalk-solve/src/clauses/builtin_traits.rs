@@ -1,13 +1,15 @@
 use super::{builder::ClauseBuilder, generalize};
 use crate::{CanonicalVarKinds, Interner, RustIrDatabase, TraitRef, WellKnownTrait};
-use chalk_ir::{Floundered, Substitution, Ty};
+use chalk_ir::{Floundered, FnPtrCoercion, Substitution, Ty};
 
 mod clone;
 mod copy;
 mod discriminant_kind;
 mod fn_family;
+mod fn_ptr;
 mod generator;
 mod sized;
+mod tuple;
 mod unsize;
 
 /// For well known traits we have special hard-coded impls, either as an
@@ -33,6 +35,11 @@ pub fn add_builtin_program_clauses<I: Interner>(
             WellKnownTrait::Sized => {
                 sized::add_sized_program_clauses(db, builder, trait_ref, ty, binders)?;
             }
+            WellKnownTrait::MetaSized => {
+                sized::add_meta_sized_program_clauses(db, builder, trait_ref, ty, binders)?;
+            }
+            // Every type is `PointeeSized`
+            WellKnownTrait::PointeeSized => builder.push_fact(trait_ref),
             WellKnownTrait::Copy => {
                 copy::add_copy_program_clauses(db, builder, trait_ref, ty, binders)?;
             }
@@ -50,11 +57,18 @@ pub fn add_builtin_program_clauses<I: Interner>(
             WellKnownTrait::Generator => {
                 generator::add_generator_program_clauses(db, builder, self_ty)?;
             }
+            WellKnownTrait::Tuple => {
+                tuple::add_tuple_program_clauses(db, builder, trait_ref, ty);
+            }
+            WellKnownTrait::FnPtr => fn_ptr::add_fn_ptr_program_clauses(builder, trait_ref, ty),
             // There are no builtin impls provided for the following traits:
             WellKnownTrait::Unpin
             | WellKnownTrait::Drop
             | WellKnownTrait::CoerceUnsized
-            | WellKnownTrait::DispatchFromDyn => (),
+            | WellKnownTrait::DispatchFromDyn
+            | WellKnownTrait::Deref
+            | WellKnownTrait::IntoIterator
+            | WellKnownTrait::Iterator => (),
         }
         Ok(())
     })
@@ -93,6 +107,28 @@ pub fn add_builtin_assoc_program_clauses<I: Interner>(
     }
 }
 
+/// Like `add_builtin_program_clauses`, but for `DomainGoal::FnPtrCoercion`,
+/// which is not a trait but is built into the language in the same way.
+pub fn add_fn_ptr_coercion_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    coercion: FnPtrCoercion<I>,
+    binders: &CanonicalVarKinds<I>,
+) -> Result<(), Floundered> {
+    // Function items and closures are non-enumerable.
+    if coercion.source.is_general_var(db.interner(), binders) {
+        return Err(Floundered);
+    }
+
+    // If `coercion` contains bound vars, we want to universally quantify them.
+    // `Generalize` collects them for us.
+    let generalized = generalize::Generalize::apply(db.interner(), coercion);
+
+    builder.push_binders(generalized, |builder, coercion| {
+        fn_ptr::add_fn_ptr_coercion_program_clauses(db, builder, coercion)
+    })
+}
+
 /// Given a trait ref `T0: Trait` and a list of types `U0..Un`, pushes a clause of the form
 /// `Implemented(T0: Trait) :- Implemented(U0: Trait) .. Implemented(Un: Trait)`
 pub fn needs_impl_for_tys<I: Interner>(
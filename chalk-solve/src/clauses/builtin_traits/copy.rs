@@ -1,8 +1,9 @@
 use crate::clauses::builtin_traits::needs_impl_for_tys;
-use crate::clauses::ClauseBuilder;
+use crate::clauses::{push_generator_witness_clauses, ClauseBuilder};
+use crate::rust_ir::Movability;
 use crate::{Interner, RustIrDatabase, TraitRef};
 use chalk_ir::{CanonicalVarKinds, Floundered, Substitution, TyKind, TyVariableKind, VariableKind};
-use std::iter;
+use core::iter;
 use tracing::instrument;
 
 fn push_tuple_copy_conditions<I: Interner>(
@@ -54,6 +55,31 @@ pub fn add_copy_program_clauses<I: Interner>(
             let upvars = upvars.substitute(db.interner(), &closure_fn_substitution);
             needs_impl_for_tys(db, builder, trait_ref, Some(upvars).into_iter());
         }
+        // Movable generators are `Copy` if their upvars and the types they
+        // hold across yields are, like rustc's unstable `generator_clone`.
+        // Static generators may point into themselves, so never are.
+        TyKind::Generator(generator_id, ref substitution) => {
+            let generator_datum = db.generator_datum(generator_id);
+            if generator_datum.movability == Movability::Movable {
+                let interner = db.interner();
+                let upvars = generator_datum
+                    .input_output
+                    .clone()
+                    .substitute(interner, substitution)
+                    .upvars;
+                let witness =
+                    TyKind::GeneratorWitness(generator_id, substitution.clone()).intern(interner);
+                needs_impl_for_tys(
+                    db,
+                    builder,
+                    trait_ref,
+                    upvars.into_iter().chain(Some(witness)),
+                );
+            }
+        }
+        TyKind::GeneratorWitness(generator_id, _) => {
+            push_generator_witness_clauses(builder, trait_ref.trait_id, generator_id);
+        }
 
         // these impls are in libcore
         TyKind::Ref(_, _, _)
@@ -67,8 +93,7 @@ pub fn add_copy_program_clauses<I: Interner>(
         | TyKind::Slice(_)
         | TyKind::OpaqueType(_, _)
         | TyKind::Foreign(_)
-        | TyKind::Generator(_, _)
-        | TyKind::GeneratorWitness(_, _)
+        | TyKind::Custom(_)
         | TyKind::Error => {}
 
         TyKind::Function(_) => builder.push_fact(trait_ref),
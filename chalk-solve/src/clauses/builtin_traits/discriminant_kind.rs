@@ -26,6 +26,7 @@ pub fn add_discriminant_clauses<I: Interner>(
         | TyKind::Closure(..)
         | TyKind::GeneratorWitness(..)
         | TyKind::Foreign(_)
+        | TyKind::Custom(_)
         | TyKind::Dyn(_)
         | TyKind::Function(..)
         | TyKind::InferenceVar(_, TyVariableKind::Integer)
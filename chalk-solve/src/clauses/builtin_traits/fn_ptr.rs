@@ -0,0 +1,126 @@
+use crate::clauses::ClauseBuilder;
+use crate::rust_ir::FnDefInputsAndOutputDatum;
+use crate::{Interner, RustIrDatabase, TraitRef};
+use chalk_ir::cast::Cast;
+use chalk_ir::{
+    Binders, Floundered, FnPointer, FnPtrCoercion, FnSig, FnSubst, Safety, Substitution, Ty, TyKind,
+};
+use core::iter;
+
+/// `FnPtr` is implemented for all function pointer types, and for no other
+/// type.
+pub fn add_fn_ptr_program_clauses<I: Interner>(
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_ref: TraitRef<I>,
+    ty: TyKind<I>,
+) {
+    if let TyKind::Function(_) = ty {
+        builder.push_fact(trait_ref);
+    }
+}
+
+/// Pushes the fact `FnPtrCoercion(source, fn(A1, ..., AN) -> O)` for the
+/// function pointer type with the signature `sig` and the given inputs and
+/// output, whose binders become the binders of the function pointer.
+fn push_fn_ptr_coercion<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    source: Ty<I>,
+    sig: FnSig<I>,
+    inputs_and_output: Binders<FnDefInputsAndOutputDatum<I>>,
+) {
+    let interner = db.interner();
+    let num_binders = inputs_and_output.len(interner);
+    let inputs_and_output = inputs_and_output.skip_binders();
+    let substitution = Substitution::from_iter(
+        interner,
+        inputs_and_output
+            .argument_types
+            .iter()
+            .chain(iter::once(&inputs_and_output.return_type))
+            .map(|ty| ty.clone().cast(interner)),
+    );
+    let target = TyKind::Function(FnPointer {
+        num_binders,
+        sig,
+        substitution: FnSubst(substitution),
+    })
+    .intern(interner);
+    builder.push_fact(FnPtrCoercion { source, target });
+}
+
+/// Handles clauses for `FnPtrCoercion(source, target)`. Function items are
+/// coerced to the function pointer of their signature (safe ones also to
+/// its `unsafe` variant), and closures which
+/// capture nothing to the function pointer of their inputs and output.
+///
+/// Closures don't have an ABI or safety of their own: they are coerced to
+/// function pointers of any ABI, safe or `unsafe`, so we flounder if `target`
+/// is not known.
+pub fn add_fn_ptr_coercion_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    coercion: FnPtrCoercion<I>,
+) -> Result<(), Floundered> {
+    let interner = db.interner();
+    let FnPtrCoercion { source, target } = coercion;
+
+    match source.kind(interner) {
+        TyKind::FnDef(fn_def_id, substitution) => {
+            let fn_def_datum = db.fn_def_datum(*fn_def_id);
+            let bound = fn_def_datum
+                .binders
+                .clone()
+                .substitute(interner, substitution);
+            // Safe functions also coerce to `unsafe` function pointers.
+            if fn_def_datum.sig.safety == Safety::Safe {
+                push_fn_ptr_coercion(
+                    db,
+                    builder,
+                    source.clone(),
+                    FnSig {
+                        safety: Safety::Unsafe,
+                        ..fn_def_datum.sig
+                    },
+                    bound.inputs_and_output.clone(),
+                );
+            }
+            push_fn_ptr_coercion(
+                db,
+                builder,
+                source.clone(),
+                fn_def_datum.sig,
+                bound.inputs_and_output,
+            );
+        }
+        TyKind::Closure(closure_id, substitution) => {
+            let closure_fn_substitution = db.closure_fn_substitution(*closure_id, substitution);
+            let upvars = db
+                .closure_upvars(*closure_id, substitution)
+                .substitute(interner, &closure_fn_substitution);
+            if !matches!(upvars.kind(interner), TyKind::Tuple(0, _)) {
+                return Ok(());
+            }
+            let sig = match target.kind(interner) {
+                TyKind::Function(fn_ptr) => FnSig {
+                    variadic: false,
+                    ..fn_ptr.sig
+                },
+                TyKind::BoundVar(_) => return Err(Floundered),
+                _ => return Ok(()),
+            };
+            let inputs_and_output = db
+                .closure_inputs_and_output(*closure_id, substitution)
+                .substitute(interner, &closure_fn_substitution);
+            push_fn_ptr_coercion(
+                db,
+                builder,
+                source.clone(),
+                sig,
+                Binders::empty(interner, inputs_and_output),
+            );
+        }
+        _ => {}
+    }
+    Ok(())
+}
@@ -1,9 +1,10 @@
-use std::iter;
+use core::iter;
 
 use crate::clauses::builtin_traits::needs_impl_for_tys;
 use crate::clauses::ClauseBuilder;
 use crate::rust_ir::AdtKind;
-use crate::{Interner, RustIrDatabase, TraitRef};
+use crate::struct_tail::struct_tail;
+use crate::{Interner, RustIrDatabase, TraitRef, WellKnownTrait};
 use chalk_ir::{
     AdtId, CanonicalVarKinds, Floundered, Substitution, TyKind, TyVariableKind, VariableKind,
 };
@@ -25,17 +26,14 @@ fn push_adt_sized_conditions<I: Interner>(
 
     let interner = db.interner();
 
-    // To check if a struct S<..> is Sized, we only have to look at its last field.
-    // This is because the WF checks for ADTs require that all the other fields must be Sized.
-    let last_field_ty = adt_datum
-        .binders
-        .map_ref(|b| b.variants.clone())
-        .substitute(interner, substitution)
-        .into_iter()
-        .take(1) // We have a struct so we're guaranteed one variant
-        .flat_map(|mut v| v.fields.pop());
-
-    needs_impl_for_tys(db, builder, trait_ref, last_field_ty);
+    // To check if a struct S<..> is Sized, we only have to look at the unsized tail of
+    // its last field. This is because the WF checks for ADTs require that all the other
+    // fields must be Sized, and so must all but the tail of the last field.
+    let tail_ty = adt_datum
+        .tail_field()
+        .map(|field| struct_tail(db, field.substitute(interner, substitution)));
+
+    needs_impl_for_tys(db, builder, trait_ref, tail_ty.into_iter());
 }
 
 fn push_tuple_sized_conditions<I: Interner>(
@@ -53,8 +51,9 @@ fn push_tuple_sized_conditions<I: Interner>(
 
     let interner = db.interner();
 
-    // To check if a tuple is Sized, we only have to look at its last element.
-    // This is because the WF checks for tuples require that all the other elements must be Sized.
+    // To check if a tuple is Sized, we only have to look at the unsized tail of its last
+    // element. This is because the WF checks for tuples require that all the other
+    // elements must be Sized.
     let last_elem_ty = substitution
         .iter(interner)
         .last()
@@ -63,7 +62,12 @@ fn push_tuple_sized_conditions<I: Interner>(
         .unwrap()
         .clone();
 
-    needs_impl_for_tys(db, builder, trait_ref, iter::once(last_elem_ty));
+    needs_impl_for_tys(
+        db,
+        builder,
+        trait_ref,
+        iter::once(struct_tail(db, last_elem_ty)),
+    );
 }
 
 pub fn add_sized_program_clauses<I: Interner>(
@@ -95,6 +99,7 @@ pub fn add_sized_program_clauses<I: Interner>(
         | TyKind::OpaqueType(_, _)
         | TyKind::Str
         | TyKind::Foreign(_)
+        | TyKind::Custom(_)
         | TyKind::Error => {}
 
         TyKind::Function(_)
@@ -122,3 +127,74 @@ pub fn add_sized_program_clauses<I: Interner>(
     }
     Ok(())
 }
+
+/// Like `add_sized_program_clauses`, but for `MetaSized`: the size of
+/// slices, `str` and trait objects is known from the metadata of pointers
+/// to them, so only `extern` types, and the structs and tuples whose tail is
+/// one, are not `MetaSized`. Every `Sized` type is `MetaSized`, including
+/// the type parameters and projections bounded by `Sized`.
+pub fn add_meta_sized_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_ref: TraitRef<I>,
+    ty: TyKind<I>,
+    binders: &CanonicalVarKinds<I>,
+) -> Result<(), Floundered> {
+    match ty {
+        TyKind::Adt(adt_id, ref substitution) => {
+            push_adt_sized_conditions(db, builder, trait_ref, adt_id, substitution)
+        }
+        TyKind::Tuple(arity, ref substitution) => {
+            push_tuple_sized_conditions(db, builder, trait_ref, arity, substitution)
+        }
+        TyKind::Array(_, _)
+        | TyKind::Never
+        | TyKind::Closure(_, _)
+        | TyKind::FnDef(_, _)
+        | TyKind::Scalar(_)
+        | TyKind::Raw(_, _)
+        | TyKind::Generator(_, _)
+        | TyKind::GeneratorWitness(_, _)
+        | TyKind::Ref(_, _, _)
+        | TyKind::Function(_)
+        | TyKind::InferenceVar(_, TyVariableKind::Float)
+        | TyKind::InferenceVar(_, TyVariableKind::Integer) => builder.push_fact(trait_ref),
+
+        TyKind::Slice(_) | TyKind::Str | TyKind::Dyn(_) => builder.push_fact(trait_ref),
+
+        TyKind::Foreign(_) | TyKind::Custom(_) | TyKind::Error => {}
+
+        TyKind::Placeholder(_) | TyKind::AssociatedType(_, _) | TyKind::OpaqueType(_, _) => {
+            if let Some(sized_id) = db.well_known_trait_id(WellKnownTrait::Sized) {
+                let self_ty = trait_ref.self_type_parameter(db.interner());
+                builder.push_clause(
+                    trait_ref,
+                    iter::once(TraitRef {
+                        trait_id: sized_id,
+                        substitution: Substitution::from1(db.interner(), self_ty),
+                    }),
+                );
+            }
+        }
+
+        TyKind::BoundVar(bound_var) => {
+            let var_kind = &binders.at(db.interner(), bound_var.index).kind;
+            match var_kind {
+                VariableKind::Ty(TyVariableKind::Integer)
+                | VariableKind::Ty(TyVariableKind::Float) => builder.push_fact(trait_ref),
+
+                // Don't know enough
+                VariableKind::Ty(TyVariableKind::General) => return Err(Floundered),
+
+                VariableKind::Const(_) | VariableKind::Lifetime => {}
+            }
+        }
+
+        // We don't know enough here
+        TyKind::InferenceVar(_, TyVariableKind::General) => return Err(Floundered),
+
+        // This would be handled elsewhere
+        TyKind::Alias(_) => {}
+    }
+    Ok(())
+}
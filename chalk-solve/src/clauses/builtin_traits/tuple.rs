@@ -0,0 +1,16 @@
+use crate::clauses::ClauseBuilder;
+use crate::{Interner, RustIrDatabase, TraitRef};
+use chalk_ir::TyKind;
+
+/// `Tuple` is implemented for all tuple types, regardless of their elements,
+/// and for no other type.
+pub fn add_tuple_program_clauses<I: Interner>(
+    _db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_ref: TraitRef<I>,
+    ty: TyKind<I>,
+) {
+    if let TyKind::Tuple(..) = ty {
+        builder.push_fact(trait_ref);
+    }
+}
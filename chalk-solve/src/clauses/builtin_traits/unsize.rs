@@ -1,6 +1,7 @@
-use std::collections::HashSet;
-use std::iter;
-use std::ops::ControlFlow;
+use crate::FxHashSet;
+use alloc::vec::Vec;
+use core::iter;
+use core::ops::ControlFlow;
 
 use crate::clauses::ClauseBuilder;
 use crate::rust_ir::AdtKind;
@@ -16,7 +17,7 @@ use chalk_ir::{
 struct UnsizeParameterCollector<I: Interner> {
     interner: I,
     // FIXME should probably use a bitset instead
-    parameters: HashSet<usize>,
+    parameters: FxHashSet<usize>,
 }
 
 impl<I: Interner> Visitor<I> for UnsizeParameterCollector<I> {
@@ -61,10 +62,10 @@ impl<I: Interner> Visitor<I> for UnsizeParameterCollector<I> {
 fn outer_binder_parameters_used<I: Interner>(
     interner: I,
     v: &Binders<impl Visit<I> + HasInterner>,
-) -> HashSet<usize> {
+) -> FxHashSet<usize> {
     let mut visitor = UnsizeParameterCollector {
         interner,
-        parameters: HashSet::new(),
+        parameters: FxHashSet::default(),
     };
     v.visit_with(&mut visitor, DebruijnIndex::INNERMOST);
     visitor.parameters
@@ -73,7 +74,7 @@ fn outer_binder_parameters_used<I: Interner>(
 // has nothing to do with occurs check
 struct ParameterOccurenceCheck<'p, I: Interner> {
     interner: I,
-    parameters: &'p HashSet<usize>,
+    parameters: &'p FxHashSet<usize>,
 }
 
 impl<'p, I: Interner> Visitor<I> for ParameterOccurenceCheck<'p, I> {
@@ -125,7 +126,7 @@ impl<'p, I: Interner> Visitor<I> for ParameterOccurenceCheck<'p, I> {
 fn uses_outer_binder_params<I: Interner>(
     interner: I,
     v: &Binders<impl Visit<I> + HasInterner>,
-    parameters: &HashSet<usize>,
+    parameters: &FxHashSet<usize>,
 ) -> bool {
     let mut visitor = ParameterOccurenceCheck {
         interner,
@@ -342,6 +343,11 @@ pub fn add_unsize_program_clauses<I: Interner>(
                 return;
             }
 
+            let adt_tail_field = match adt_datum.tail_field() {
+                Some(field) => field,
+                None => return,
+            };
+
             // We have a `struct` so we're guaranteed a single variant
             let fields_len = adt_datum
                 .binders
@@ -352,15 +358,6 @@ pub fn add_unsize_program_clauses<I: Interner>(
                 .fields
                 .len();
 
-            if fields_len == 0 {
-                return;
-            }
-
-            let adt_tail_field = adt_datum
-                .binders
-                .map_ref(|bound| bound.variants.last().unwrap().fields.last().unwrap())
-                .cloned();
-
             // Collect unsize parameters that last field contains and
             // ensure there at least one of them.
             let unsize_parameter_candidates =
@@ -0,0 +1,87 @@
+//! Builtin impls for traits of the host, which it provides through
+//! [`RustIrDatabase::custom_builtin`]: like the builtin impls chalk gives
+//! the well-known traits, but generated by a callback, so that the host can
+//! experiment with new lang-item traits without changing chalk.
+
+use super::{builder::ClauseBuilder, generalize};
+use crate::FxHashMap;
+use crate::{CanonicalVarKinds, Interner, RustIrDatabase, TraitRef};
+use alloc::sync::Arc;
+use chalk_ir::{Floundered, TraitId, Ty};
+use core::fmt;
+
+/// Pushes the clauses of the builtin impls of a trait for the goal
+/// `SelfTy: Trait<..>`, given the trait reference of the goal and its self
+/// type. The bound variables of the goal are in scope in `builder`; the
+/// self type is never one of its general type variables.
+pub type CustomBuiltin<I> = Arc<
+    dyn Fn(&mut ClauseBuilder<'_, I>, TraitRef<I>, Ty<I>) -> Result<(), Floundered> + Send + Sync,
+>;
+
+/// The builtin impl generators of a host, by trait. Hosts can keep one and
+/// implement [`RustIrDatabase::custom_builtin`] with [`CustomBuiltins::get`].
+#[derive(Clone)]
+pub struct CustomBuiltins<I: Interner> {
+    builtins: FxHashMap<TraitId<I>, CustomBuiltin<I>>,
+}
+
+impl<I: Interner> CustomBuiltins<I> {
+    pub fn new() -> Self {
+        CustomBuiltins {
+            builtins: FxHashMap::default(),
+        }
+    }
+
+    /// Registers `builtin` as the generator of the builtin impls of
+    /// `trait_id`, replacing the one registered before, if any.
+    pub fn register(
+        &mut self,
+        trait_id: TraitId<I>,
+        builtin: impl Fn(&mut ClauseBuilder<'_, I>, TraitRef<I>, Ty<I>) -> Result<(), Floundered>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.builtins.insert(trait_id, Arc::new(builtin));
+    }
+
+    pub fn get(&self, trait_id: TraitId<I>) -> Option<CustomBuiltin<I>> {
+        self.builtins.get(&trait_id).cloned()
+    }
+}
+
+impl<I: Interner> Default for CustomBuiltins<I> {
+    fn default() -> Self {
+        CustomBuiltins::new()
+    }
+}
+
+impl<I: Interner> fmt::Debug for CustomBuiltins<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.builtins.keys()).finish()
+    }
+}
+
+/// Like `add_builtin_program_clauses`, but for a trait with a custom
+/// builtin.
+pub(super) fn add_custom_builtin_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    builtin: &CustomBuiltin<I>,
+    trait_ref: TraitRef<I>,
+    binders: &CanonicalVarKinds<I>,
+) -> Result<(), Floundered> {
+    // If `trait_ref` contains bound vars, we want to universally quantify them.
+    // `Generalize` collects them for us.
+    let generalized = generalize::Generalize::apply(db.interner(), trait_ref);
+
+    builder.push_binders(generalized, |builder, trait_ref| {
+        let self_ty = trait_ref.self_type_parameter(db.interner());
+        // Like the builtin impls of well-known traits, custom builtin impls
+        // are non-enumerable.
+        if self_ty.is_general_var(db.interner(), binders) {
+            return Err(Floundered);
+        }
+        builtin(builder, trait_ref, self_ty)
+    })
+}
@@ -69,6 +69,7 @@ pub(super) fn build_dyn_self_ty_clauses<I: Interner>(
                         builder.db,
                         builder,
                         trait_ref.clone(),
+                        &[],
                     )
                 }
                 // FIXME: Associated item bindings are just taken as facts (?)
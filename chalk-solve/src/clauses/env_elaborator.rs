@@ -3,15 +3,16 @@ use crate::clauses::builder::ClauseBuilder;
 use crate::clauses::{match_alias_ty, match_ty};
 use crate::DomainGoal;
 use crate::FromEnv;
+use crate::FxHashSet;
 use crate::ProgramClause;
 use crate::RustIrDatabase;
 use crate::Ty;
 use crate::{debug_span, TyKind};
+use alloc::vec;
 use chalk_ir::interner::Interner;
 use chalk_ir::visit::{Visit, Visitor};
 use chalk_ir::{DebruijnIndex, Environment};
-use rustc_hash::FxHashSet;
-use std::ops::ControlFlow;
+use core::ops::ControlFlow;
 use tracing::instrument;
 
 /// When proving a `FromEnv` goal, we elaborate all `FromEnv` goals
@@ -6,14 +6,17 @@
 //! happen with `dyn Trait` currently; that's the only case where we use the
 //! types passed to `program_clauses` in the clauses we generate.
 
+use crate::FxHashMap;
+use alloc::vec::Vec;
+use chalk_derive::FallibleFolder;
 use chalk_ir::{
     fold::{Fold, Folder},
     interner::{HasInterner, Interner},
-    Binders, BoundVar, Const, ConstData, ConstValue, DebruijnIndex, Fallible, Lifetime,
-    LifetimeData, NoSolution, Ty, TyKind, TyVariableKind, VariableKind, VariableKinds,
+    Binders, BoundVar, Const, ConstData, ConstValue, DebruijnIndex, Lifetime, LifetimeData, Ty,
+    TyKind, TyVariableKind, VariableKind, VariableKinds,
 };
-use rustc_hash::FxHashMap;
 
+#[derive(FallibleFolder)]
 pub struct Generalize<I: Interner> {
     binders: Vec<VariableKind<I>>,
     mapping: FxHashMap<BoundVar, usize>,
@@ -31,9 +34,7 @@ impl<I: Interner> Generalize<I> {
             mapping: FxHashMap::default(),
             interner,
         };
-        let value = value
-            .fold_with(&mut generalize, DebruijnIndex::INNERMOST)
-            .unwrap();
+        let value = value.fold_with(&mut generalize, DebruijnIndex::INNERMOST);
         Binders::new(
             VariableKinds::from_iter(interner, generalize.binders),
             value,
@@ -42,17 +43,11 @@ impl<I: Interner> Generalize<I> {
 }
 
 impl<I: Interner> Folder<I> for Generalize<I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
-    fn fold_free_var_ty(
-        &mut self,
-        bound_var: BoundVar,
-        outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, outer_binder: DebruijnIndex) -> Ty<I> {
         let binder_vec = &mut self.binders;
         let new_index = self.mapping.entry(bound_var).or_insert_with(|| {
             let i = binder_vec.len();
@@ -60,7 +55,7 @@ impl<I: Interner> Folder<I> for Generalize<I> {
             i
         });
         let new_var = BoundVar::new(outer_binder, *new_index);
-        Ok(TyKind::BoundVar(new_var).intern(self.interner()))
+        TyKind::BoundVar(new_var).intern(self.interner())
     }
 
     fn fold_free_var_const(
@@ -68,7 +63,7 @@ impl<I: Interner> Folder<I> for Generalize<I> {
         ty: Ty<I>,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         let binder_vec = &mut self.binders;
         let new_index = self.mapping.entry(bound_var).or_insert_with(|| {
             let i = binder_vec.len();
@@ -76,18 +71,18 @@ impl<I: Interner> Folder<I> for Generalize<I> {
             i
         });
         let new_var = BoundVar::new(outer_binder, *new_index);
-        Ok(ConstData {
+        ConstData {
             ty,
             value: ConstValue::BoundVar(new_var),
         }
-        .intern(self.interner()))
+        .intern(self.interner())
     }
 
     fn fold_free_var_lifetime(
         &mut self,
         bound_var: BoundVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         let binder_vec = &mut self.binders;
         let new_index = self.mapping.entry(bound_var).or_insert_with(|| {
             let i = binder_vec.len();
@@ -95,7 +90,7 @@ impl<I: Interner> Folder<I> for Generalize<I> {
             i
         });
         let new_var = BoundVar::new(outer_binder, *new_index);
-        Ok(LifetimeData::BoundVar(new_var).intern(self.interner()))
+        LifetimeData::BoundVar(new_var).intern(self.interner())
     }
 
     fn interner(&self) -> I {
@@ -1,10 +1,13 @@
 use crate::clauses::builder::ClauseBuilder;
 use crate::rust_ir::*;
 use crate::split::Split;
+use crate::RustIrDatabase;
+use alloc::vec::Vec;
 use chalk_ir::cast::{Cast, Caster};
+use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use std::iter;
+use core::iter;
 use tracing::instrument;
 
 /// Trait for lowering a given piece of rust-ir source (e.g., an impl
@@ -134,6 +137,93 @@ impl<I: Interner> ToProgramClauses<I> for AssociatedTyValue<I> {
     }
 }
 
+impl<I: Interner> ToProgramClauses<I> for InherentAssocTyDatum<I> {
+    /// Given an inherent associated type:
+    ///
+    /// ```notrust
+    /// impl<T> Foo<T> where T: Clone {
+    ///     type Bar = Vec<T>;
+    /// }
+    /// ```
+    ///
+    /// we generate, for each of its values, the rules:
+    ///
+    /// ```notrust
+    /// -- Rule Normalize-From-Inherent-Impl
+    /// forall<T> {
+    ///     Normalize(<Foo<T>>::Bar -> Vec<T>) :- Implemented(T: Clone).
+    /// }
+    ///
+    /// -- Rule WellFormed-InherentAssocTy
+    /// forall<T> {
+    ///     WellFormed(<Foo<T>>::Bar) :- Implemented(T: Clone).
+    /// }
+    /// ```
+    ///
+    /// and, as for associated types of traits, the rule:
+    ///
+    /// ```notrust
+    /// -- Rule AliasEq-Normalize
+    /// forall<Self, U> {
+    ///     AliasEq(<Self>::Bar = U) :- Normalize(<Self>::Bar -> U).
+    /// }
+    /// ```
+    ///
+    /// Since the value of an inherent associated type never depends on a
+    /// trait impl which may or may not exist, there is no placeholder
+    /// fallback: the projection only ever normalizes to its values.
+    fn to_program_clauses(
+        &self,
+        builder: &mut ClauseBuilder<'_, I>,
+        _environment: &Environment<I>,
+    ) {
+        let interner = builder.interner();
+        let projection = |self_ty: Ty<I>| {
+            AliasTy::Inherent(InherentProjectionTy {
+                associated_ty_id: self.id,
+                substitution: Substitution::from1(interner, self_ty),
+            })
+        };
+
+        for value in &self.values {
+            builder.push_binders(
+                value.clone(),
+                |builder,
+                 InherentAssocTyValue {
+                     self_ty,
+                     where_clauses,
+                     ty,
+                 }| {
+                    builder.push_clause(
+                        Normalize {
+                            alias: projection(self_ty.clone()),
+                            ty,
+                        },
+                        where_clauses.iter().cloned(),
+                    );
+                    builder.push_clause(
+                        WellFormed::Ty(projection(self_ty).intern(interner)),
+                        where_clauses,
+                    );
+                },
+            );
+        }
+
+        builder.push_bound_ty(|builder, self_ty| {
+            builder.push_bound_ty(|builder, ty| {
+                let alias = projection(self_ty);
+                builder.push_clause(
+                    AliasEq {
+                        alias: alias.clone(),
+                        ty: ty.clone(),
+                    },
+                    Some(Normalize { alias, ty }),
+                );
+            });
+        });
+    }
+}
+
 impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
     /// Given `opaque type T<U>: A + B = HiddenTy where U: C;`, we generate:
     ///
@@ -141,10 +231,12 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
     /// AliasEq(T<U> = HiddenTy) :- Reveal.
     /// AliasEq(T<U> = !T<U>).
     /// WF(T<U>) :- WF(U: C).
-    /// Implemented(!T<U>: A).
-    /// Implemented(!T<U>: B).
+    /// Implemented(!T<U>: A) :- Implemented(U: C).
+    /// Implemented(!T<U>: B) :- Implemented(U: C).
     /// ```
     /// where `!T<..>` is the placeholder for the unnormalized type `T<..>`.
+    /// As for type alias `impl Trait`s, the bounds are only known to hold
+    /// where the where clauses do.
     #[instrument(level = "debug", skip(builder))]
     fn to_program_clauses(
         &self,
@@ -184,16 +276,25 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
             ));
 
             // WF(!T<..>) :- WF(WC).
-            builder.push_binders(opaque_ty_bound.where_clauses, |builder, where_clauses| {
-                builder.push_clause(
-                    WellFormed::Ty(alias_placeholder_ty.clone()),
-                    where_clauses
-                        .into_iter()
-                        .map(|wc| wc.into_well_formed_goal(interner)),
-                );
-            });
+            builder.push_binders(
+                opaque_ty_bound.where_clauses.clone(),
+                |builder, where_clauses| {
+                    builder.push_clause(
+                        WellFormed::Ty(alias_placeholder_ty.clone()),
+                        where_clauses
+                            .into_iter()
+                            .map(|wc| wc.into_well_formed_goal(interner)),
+                    );
+                },
+            );
 
             let substitution = Substitution::from1(interner, alias_placeholder_ty);
+            let conditions: Vec<Goal<I>> = opaque_ty_bound
+                .where_clauses
+                .substitute(interner, &substitution)
+                .into_iter()
+                .map(|wc| wc.cast(interner))
+                .collect();
             for bound in opaque_ty_bound.bounds {
                 let bound_with_placeholder_ty = bound.substitute(interner, &substitution);
                 builder.push_binders(bound_with_placeholder_ty, |builder, bound| match &bound {
@@ -203,10 +304,11 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
                             builder.db,
                             builder,
                             trait_ref.clone(),
+                            &conditions,
                         )
                     }
                     // FIXME: Associated item bindings are just taken as facts (?)
-                    WhereClause::AliasEq(_) => builder.push_fact(bound),
+                    WhereClause::AliasEq(_) => builder.push_clause(bound, conditions.clone()),
                     WhereClause::LifetimeOutlives(..) => {}
                     WhereClause::TypeOutlives(..) => {}
                 });
@@ -215,6 +317,35 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
     }
 }
 
+/// Returns the `Args: Tuple` bound implied by `trait_ref` if it references
+/// one of the `Fn` family of traits, and the `Tuple` trait is declared.
+fn fn_args_tuple_bound<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    well_known: Option<WellKnownTrait>,
+    trait_ref: &TraitRef<I>,
+) -> Option<QuantifiedWhereClause<I>> {
+    match well_known? {
+        WellKnownTrait::FnOnce | WellKnownTrait::FnMut | WellKnownTrait::Fn => {}
+        _ => return None,
+    }
+    let interner = db.interner();
+    let tuple_trait = db.well_known_trait_id(WellKnownTrait::Tuple)?;
+    let args_ty = trait_ref
+        .substitution
+        .as_slice(interner)
+        .get(1)?
+        .ty(interner)?;
+    // The bound is under an (empty) binder, like the declared where clauses.
+    let args_ty = args_ty.clone().shifted_in(interner);
+    Some(Binders::empty(
+        interner,
+        WhereClause::Implemented(TraitRef {
+            trait_id: tuple_trait,
+            substitution: Substitution::from1(interner, args_ty),
+        }),
+    ))
+}
+
 /// Generates the "well-formed" program clauses for an applicative type
 /// with the name `type_name`. For example, given a struct definition:
 ///
@@ -305,6 +436,9 @@ fn fully_visible_program_clauses<I>(
 /// - builder -- the clause builder. We assume all the generic types from `S` are in scope.
 /// - type_name -- in our example above, the name `S`
 /// - where_clauses -- the list of where clauses declared on the type (`T: Eq`, in our example).
+///
+/// No rules are generated if the program is solved without implied bounds
+/// (see `ProgramFeatures::implied_bounds`).
 fn implied_bounds_program_clauses<'a, I, Wc>(
     builder: &'a mut ClauseBuilder<'_, I>,
     ty: &Ty<I>,
@@ -313,6 +447,9 @@ fn implied_bounds_program_clauses<'a, I, Wc>(
     I: Interner,
     Wc: Iterator<Item = &'a QuantifiedWhereClause<I>>,
 {
+    if !builder.db.program_features().implied_bounds {
+        return;
+    }
     let interner = builder.interner();
 
     for qwc in where_clauses {
@@ -598,12 +735,16 @@ impl<I: Interner> ToProgramClauses<I> for TraitDatum<I> {
     fn to_program_clauses(&self, builder: &mut ClauseBuilder<'_, I>, environment: &Environment<I>) {
         let interner = builder.interner();
         let binders = self.binders.map_ref(|b| &b.where_clauses).cloned();
-        builder.push_binders(binders, |builder, where_clauses| {
+        builder.push_binders(binders, |builder, mut where_clauses| {
             let trait_ref = chalk_ir::TraitRef {
                 trait_id: self.id,
                 substitution: builder.substitution_in_scope(),
             };
 
+            // The arguments of the `Fn` family of traits are implicitly
+            // required to be a tuple, as if the trait declared `Args: Tuple`.
+            where_clauses.extend(fn_args_tuple_bound(builder.db, self.well_known, &trait_ref));
+
             builder.push_clause(
                 trait_ref.clone().well_formed(),
                 where_clauses
@@ -1,19 +1,21 @@
-use rustc_hash::FxHashSet;
+use crate::FxHashSet;
+use alloc::vec::Vec;
 
 use super::builder::ClauseBuilder;
 use crate::RustIrDatabase;
 use chalk_ir::{
-    fold::shift::Shift, interner::Interner, Binders, BoundVar, DebruijnIndex, TraitId, TraitRef,
-    WhereClause,
+    cast::Cast, fold::shift::Shift, interner::Interner, Binders, BoundVar, DebruijnIndex, Goal,
+    TraitId, TraitRef, WhereClause,
 };
 
 /// Generate `Implemented` clauses for `dyn Trait` and opaque types. We need to generate
 /// `Implemented` clauses for all super traits, and for each trait we require
-/// its where clauses. (See #203.)
+/// its where clauses (see #203), along with the given `conditions`.
 pub(super) fn push_trait_super_clauses<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     builder: &mut ClauseBuilder<'_, I>,
     trait_ref: TraitRef<I>,
+    conditions: &[Goal<I>],
 ) {
     let interner = db.interner();
     // Given`trait SuperTrait: WC`, which is a super trait
@@ -21,8 +23,8 @@ pub(super) fn push_trait_super_clauses<I: Interner>(
     // then we want to push
     // - for `dyn Trait`:
     //     `Implemented(dyn Trait: SuperTrait) :- WC`.
-    // - for placeholder `!T` of `opaque type T: Trait = HiddenTy`:
-    //     `Implemented(!T: SuperTrait) :- WC`
+    // - for placeholder `!T` of `opaque type T: Trait = HiddenTy where OWC`:
+    //     `Implemented(!T: SuperTrait) :- WC, OWC`
 
     let super_trait_refs =
         super_traits(db, trait_ref.trait_id).substitute(interner, &trait_ref.substitution);
@@ -34,7 +36,8 @@ pub(super) fn push_trait_super_clauses<I: Interner>(
                 .where_clauses()
                 .cloned()
                 .substitute(interner, &super_trait_ref.substitution);
-            builder.push_clause(super_trait_ref, wc);
+            let wc = wc.into_iter().map(|wc| wc.cast::<Goal<I>>(interner));
+            builder.push_clause(super_trait_ref, wc.chain(conditions.iter().cloned()));
         });
     }
 }
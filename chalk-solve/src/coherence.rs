@@ -1,13 +1,14 @@
+use crate::FxHashMap;
+use alloc::boxed::Box;
 use indexmap::IndexMap;
 use petgraph::prelude::*;
-use rustc_hash::FxHashMap;
 
 use crate::solve::Solver;
 use crate::RustIrDatabase;
+use alloc::sync::Arc;
 use chalk_ir::interner::Interner;
 use chalk_ir::{self, ImplId, TraitId};
-use std::fmt;
-use std::sync::Arc;
+use core::fmt;
 
 pub mod orphan;
 mod solve;
@@ -18,7 +19,7 @@ pub struct CoherenceSolver<'a, I: Interner> {
     trait_id: TraitId<I>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CoherenceError<I: Interner> {
     OverlappingImpls(TraitId<I>),
     FailedOrphanCheck(TraitId<I>),
@@ -37,6 +38,7 @@ impl<I: Interner> fmt::Display for CoherenceError<I> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<I: Interner> std::error::Error for CoherenceError<I> {}
 
 /// Stores the specialization priorities for a set of impls.
@@ -3,6 +3,7 @@ use crate::debug_span;
 use crate::ext::*;
 use crate::rust_ir::*;
 use crate::{goal_builder::GoalBuilder, Solution};
+use alloc::boxed::Box;
 use chalk_ir::cast::*;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
@@ -36,6 +37,9 @@ impl<I: Interner> CoherenceSolver<'_, I> {
             // the other. Note that specialization can only run one way - if both
             // specialization checks return *either* true or false, that's an error.
             if !self.disjoint(lhs, rhs) {
+                if !self.db.program_features().specialization {
+                    return Err(CoherenceError::OverlappingImpls(self.trait_id));
+                }
                 match (self.specializes(l_id, r_id), self.specializes(r_id, l_id)) {
                     (true, false) => record_specialization(l_id, r_id),
                     (false, true) => record_specialization(r_id, l_id),
@@ -137,7 +141,7 @@ impl<I: Interner> CoherenceSolver<'_, I> {
             // to overlap
             Some(Solution::Unique(_)) => true,
             // Goal was ambiguous, so there *may* be overlap
-            Some(Solution::Ambig(_)) |
+            Some(Solution::Ambig(..)) |
             // Goal cannot be proven, so there is some impl that causes overlap
             None => false,
         };
@@ -1,3 +1,4 @@
+use crate::rust_ir::CoinductionSemantics;
 use crate::RustIrDatabase;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
@@ -11,33 +12,56 @@ pub trait IsCoinductive<I: Interner> {
     /// requirements and cyclic traits, which generates cycles in the
     /// proof tree which must not be rejected but instead must be
     /// treated as a success.
-    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool;
+    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool {
+        self.is_coinductive_with(db, &[])
+    }
+
+    /// Like `is_coinductive`, but also treats the traits in
+    /// `coinductive_traits` as coinductive.
+    fn is_coinductive_with(
+        &self,
+        db: &dyn RustIrDatabase<I>,
+        coinductive_traits: &[TraitId<I>],
+    ) -> bool;
 }
 
 impl<I: Interner> IsCoinductive<I> for Goal<I> {
-    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool {
+    fn is_coinductive_with(
+        &self,
+        db: &dyn RustIrDatabase<I>,
+        coinductive_traits: &[TraitId<I>],
+    ) -> bool {
         let interner = db.interner();
         match self.data(interner) {
             GoalData::DomainGoal(DomainGoal::Holds(wca)) => match wca {
                 WhereClause::Implemented(tr) => {
-                    db.trait_datum(tr.trait_id).is_auto_trait()
+                    db.program_features().coinduction == CoinductionSemantics::AllTraits
+                        || db.trait_datum(tr.trait_id).is_auto_trait()
                         || db.trait_datum(tr.trait_id).is_coinductive_trait()
+                        || coinductive_traits.contains(&tr.trait_id)
                 }
                 WhereClause::AliasEq(..) => false,
                 WhereClause::LifetimeOutlives(..) => false,
                 WhereClause::TypeOutlives(..) => false,
             },
             GoalData::DomainGoal(DomainGoal::WellFormed(WellFormed::Trait(..))) => true,
-            GoalData::Quantified(QuantifierKind::ForAll, goal) => {
-                goal.skip_binders().is_coinductive(db)
-            }
+            GoalData::Quantified(QuantifierKind::ForAll, goal) => goal
+                .skip_binders()
+                .is_coinductive_with(db, coinductive_traits),
             _ => false,
         }
     }
 }
 
 impl<I: Interner> IsCoinductive<I> for UCanonical<InEnvironment<Goal<I>>> {
-    fn is_coinductive(&self, db: &dyn RustIrDatabase<I>) -> bool {
-        self.canonical.value.goal.is_coinductive(db)
+    fn is_coinductive_with(
+        &self,
+        db: &dyn RustIrDatabase<I>,
+        coinductive_traits: &[TraitId<I>],
+    ) -> bool {
+        self.canonical()
+            .value
+            .goal
+            .is_coinductive_with(db, coinductive_traits)
     }
 }
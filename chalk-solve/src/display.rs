@@ -1,14 +1,23 @@
-use std::{
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::{
     borrow::Borrow,
-    fmt::{Display, Result},
-    sync::Arc,
+    fmt::{Display, Formatter, Result},
+    marker::PhantomData,
 };
 
 use crate::rust_ir::*;
 use chalk_ir::{interner::Interner, *};
 use itertools::Itertools;
 
-use crate::{logging_db::RecordedItemId, split::Split, RustIrDatabase};
+use crate::{
+    logging_db::RecordedItemId,
+    solve::{Guidance, Solution},
+    split::Split,
+    RustIrDatabase,
+};
 
 #[macro_use]
 mod utils;
@@ -17,6 +26,7 @@ mod bounds;
 mod identifiers;
 mod items;
 mod render_trait;
+mod solution;
 mod state;
 mod stub;
 mod ty;
@@ -29,13 +39,40 @@ use self::utils::as_display;
 
 fn write_item<F, I, T>(f: &mut F, ws: &InternalWriterState<'_, I>, v: &T) -> Result
 where
-    F: std::fmt::Write + ?Sized,
+    F: core::fmt::Write + ?Sized,
     I: Interner,
     T: RenderAsRust<I>,
 {
     writeln!(f, "{}", v.display(ws))
 }
 
+/// Writes out the `#![name]` attributes which set `features`; the features
+/// which have their default are left out.
+pub fn write_program_features<F>(f: &mut F, features: ProgramFeatures) -> Result
+where
+    F: core::fmt::Write + ?Sized,
+{
+    let ProgramFeatures {
+        specialization,
+        implied_bounds,
+        coinduction,
+        arbitrary_self_types,
+    } = features;
+    if !specialization {
+        writeln!(f, "#![no_specialization]")?;
+    }
+    if !implied_bounds {
+        writeln!(f, "#![no_implied_bounds]")?;
+    }
+    if coinduction == CoinductionSemantics::AllTraits {
+        writeln!(f, "#![all_traits_coinductive]")?;
+    }
+    if arbitrary_self_types {
+        writeln!(f, "#![arbitrary_self_types]")?;
+    }
+    Ok(())
+}
+
 /// Writes stubs for items which were referenced by name, but for which we
 /// didn't directly access. For instance, traits mentioned in where bounds which
 /// are only usually checked during well-formedness, when we weren't recording
@@ -48,7 +85,7 @@ where
 /// `RecordedItemId::Impl` is not supported.
 pub fn write_stub_items<F, I, DB, P, T>(f: &mut F, ws: &WriterState<I, DB, P>, ids: T) -> Result
 where
-    F: std::fmt::Write + ?Sized,
+    F: core::fmt::Write + ?Sized,
     I: Interner,
     DB: RustIrDatabase<I>,
     P: Borrow<DB>,
@@ -64,7 +101,7 @@ where
 /// [`LoggingRustIrDatabase`]: crate::logging_db::LoggingRustIrDatabase
 pub fn write_items<F, I, DB, P, T>(f: &mut F, ws: &WriterState<I, DB, P>, ids: T) -> Result
 where
-    F: std::fmt::Write + ?Sized,
+    F: core::fmt::Write + ?Sized,
     I: Interner,
     DB: RustIrDatabase<I>,
     P: Borrow<DB>,
@@ -102,6 +139,84 @@ where
     Ok(())
 }
 
+/// Values which can be written for users with the names and syntax of Rust
+/// source, like `Vec<u32>` rather than the `Debug` output of the interner.
+/// The REPL writes solutions this way.
+pub trait DisplayWithDb<I: Interner> {
+    fn fmt_with_db(
+        &self,
+        db: &dyn RustIrDatabase<I>,
+        options: PrettyOptions,
+        f: &mut Formatter<'_>,
+    ) -> Result;
+
+    fn display_with<'a>(&'a self, db: &'a dyn RustIrDatabase<I>) -> WithDb<'a, I, Self>
+    where
+        Self: Sized,
+    {
+        WithDb {
+            db,
+            value: self,
+            options: PrettyOptions::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Displays a [`DisplayWithDb`] value (see [`DisplayWithDb::display_with`]).
+pub struct WithDb<'a, I: Interner, T> {
+    db: &'a dyn RustIrDatabase<I>,
+    value: &'a T,
+    options: PrettyOptions,
+    _phantom: PhantomData<I>,
+}
+
+impl<'a, I: Interner, T> WithDb<'a, I, T> {
+    /// Displays the value with `options` rather than the defaults.
+    pub fn with_options(self, options: PrettyOptions) -> Self {
+        WithDb { options, ..self }
+    }
+}
+
+impl<I: Interner, T: DisplayWithDb<I>> Display for WithDb<'_, I, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.value.fmt_with_db(self.db, self.options, f)
+    }
+}
+
+macro_rules! display_with_db {
+    ($($t:ty),*) => {
+        $(
+            impl<I: Interner> DisplayWithDb<I> for $t {
+                fn fmt_with_db(
+                    &self,
+                    db: &dyn RustIrDatabase<I>,
+                    options: PrettyOptions,
+                    f: &mut Formatter<'_>,
+                ) -> Result {
+                    RenderAsRust::fmt(self, &InternalWriterState::for_db(db, options), f)
+                }
+            }
+        )*
+    };
+}
+
+display_with_db!(
+    Solution<I>,
+    Guidance<I>,
+    Canonical<ConstrainedSubst<I>>,
+    Canonical<Substitution<I>>,
+    ConstrainedSubst<I>,
+    Substitution<I>,
+    Constraints<I>,
+    InEnvironment<Constraint<I>>,
+    Constraint<I>,
+    Ty<I>,
+    Lifetime<I>,
+    Const<I>,
+    GenericArg<I>
+);
+
 /// Displays a set of bounds, all targeting `Self`, as just the trait names,
 /// separated by `+`.
 ///
@@ -164,6 +279,7 @@ fn display_self_where_clauses_as_bounds<'a, I: Interner>(
                                     .fmt(f)
                                 }
                                 AliasTy::Opaque(opaque) => opaque.display(s).fmt(f),
+                                AliasTy::Inherent(inherent) => inherent.display(s).fmt(f),
                             },
                             WhereClause::LifetimeOutlives(lifetime) => lifetime.display(s).fmt(f),
                             WhereClause::TypeOutlives(ty) => ty.display(s).fmt(f),
@@ -185,7 +301,7 @@ fn display_type_with_generics<'a, I: Interner>(
     trait_name: impl RenderAsRust<I> + 'a,
     trait_params: impl IntoIterator<Item = &'a GenericArg<I>> + 'a,
 ) -> impl Display + 'a {
-    use std::fmt::Write;
+    use core::fmt::Write;
     let trait_params = trait_params.into_iter().map(|param| param.display(s));
     let mut trait_params_str = String::new();
     write_joined_non_empty_list!(trait_params_str, "<{}>", trait_params, ", ").unwrap();
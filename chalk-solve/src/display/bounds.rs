@@ -1,7 +1,9 @@
 //! Writer logic for `where` clauses and other bounds.
 //!
 //! Contains logic for writing the various forms of `Foo: Bar`.
-use std::fmt::{Display, Formatter, Result};
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result};
 
 use crate::rust_ir::*;
 use chalk_ir::{interner::Interner, *};
@@ -20,6 +22,8 @@ impl<I: Interner> RenderAsRust<I> for InlineBound<I> {
             InlineBound::TraitBound(trait_bound) => trait_bound.fmt(s, f),
             // Foo: Iterator<Item=Foo>
             InlineBound::AliasEqBound(eq_bound) => eq_bound.fmt(s, f),
+            // Foo: 'a
+            InlineBound::Outlives(lifetime) => lifetime.fmt(s, f),
         }
     }
 }
@@ -149,6 +153,9 @@ impl<I: Interner> RenderAsRust<I> for AliasEq<I> {
                 )
             }
             AliasTy::Opaque(opaque) => write!(f, "{}", opaque.display(s)),
+            AliasTy::Inherent(inherent) => {
+                write!(f, "{} = {}", inherent.display(s), self.ty.display(s))
+            }
         }
     }
 }
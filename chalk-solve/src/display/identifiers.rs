@@ -2,7 +2,7 @@
 //!
 //! `RenderAsRust` impls for identifiers which are either too small or too
 //! shared to belong anywhere else belong here.
-use std::fmt::{Formatter, Result};
+use core::fmt::{Formatter, Result};
 
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
@@ -3,11 +3,16 @@
 //! Contains code specific to top-level items and other structures specific to a
 //! single top-level item.
 
-use std::fmt::{Formatter, Result};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::{Formatter, Result};
 
 use crate::rust_ir::*;
 use crate::split::Split;
+use crate::variance::{infer_variances, VarianceItem};
 use chalk_ir::interner::Interner;
+use chalk_ir::Safety;
 use itertools::Itertools;
 
 use super::{
@@ -63,6 +68,31 @@ macro_rules! write_flags {
     };
 }
 
+/// Writes the `#[variance(...)]` attribute of `item`. Nothing is written
+/// when its variances are the ones inferred from its definition and the
+/// variances of the other items, which is what it gets without the
+/// attribute.
+fn write_variances<I: Interner>(
+    s: &InternalWriterState<'_, I>,
+    f: &mut Formatter<'_>,
+    item: VarianceItem<I>,
+) -> Result {
+    let db = s.db();
+    let variances_of = |item| match item {
+        VarianceItem::Adt(id) => db.unification_database().adt_variance(id),
+        VarianceItem::FnDef(id) => db.unification_database().fn_def_variance(id),
+    };
+    let variances = variances_of(item);
+    let inferred = infer_variances(db, item, &|other| {
+        (other != item).then(|| variances_of(other))
+    });
+    if variances != inferred {
+        let variances = variances.as_slice(db.interner());
+        writeln!(f, "#[variance({:?})]", variances.iter().format(", "))?;
+    }
+    Ok(())
+}
+
 impl<'a, I: Interner> RenderAsRust<I> for (&'a GeneratorDatum<I>, &'a GeneratorWitnessDatum<I>) {
     fn fmt(&self, _s: &InternalWriterState<'_, I>, _f: &'_ mut Formatter<'_>) -> Result {
         unimplemented!()
@@ -76,6 +106,9 @@ impl<I: Interner> RenderAsRust<I> for AdtDatum<I> {
         let s = &s.add_debrujin_index(None);
         let value = self.binders.skip_binders();
 
+        // variances
+        write_variances(s, f, VarianceItem::Adt(self.id))?;
+
         // flags
         write_flags!(
             f,
@@ -87,6 +120,9 @@ impl<I: Interner> RenderAsRust<I> for AdtDatum<I> {
                 phantom_data
             }
         );
+        if s.db().adt_size_align(self.id).one_zst() {
+            writeln!(f, "#[one_zst]")?;
+        }
 
         // repr
         let repr = s.db().adt_repr(self.id);
@@ -97,6 +133,12 @@ impl<I: Interner> RenderAsRust<I> for AdtDatum<I> {
         if repr.packed {
             write!(f, "#[repr(packed)]")?;
         }
+        if repr.transparent {
+            write!(f, "#[repr(transparent)]")?;
+        }
+        if repr.simd {
+            write!(f, "#[repr(simd)]")?;
+        }
         if let Some(t) = &repr.int {
             write!(f, "#[repr({})]", t.display(s))?;
         }
@@ -147,7 +189,11 @@ impl<I: Interner> RenderAsRust<I> for AdtDatum<I> {
                         }),
                         ",\n"
                     )?;
-                    write!(f, "{}}},", s.indent())?;
+                    write!(f, "{}}}", s.indent())?;
+                    if let Some(discriminant) = variant.discriminant {
+                        write!(f, " = {}", discriminant)?;
+                    }
+                    write!(f, ",")?;
                 }
             }
         }
@@ -205,6 +251,13 @@ impl<I: Interner> RenderAsRust<I> for TraitDatum<I> {
                 WellKnownTrait::DiscriminantKind => "discriminant_kind",
                 WellKnownTrait::Generator => "generator",
                 WellKnownTrait::DispatchFromDyn => "dispatch_from_dyn",
+                WellKnownTrait::Tuple => "tuple_trait",
+                WellKnownTrait::FnPtr => "fn_ptr_trait",
+                WellKnownTrait::MetaSized => "meta_sized",
+                WellKnownTrait::PointeeSized => "pointee_sized",
+                WellKnownTrait::Deref => "deref",
+                WellKnownTrait::IntoIterator => "into_iterator",
+                WellKnownTrait::Iterator => "iterator",
             };
             writeln!(f, "#[lang({})]", name)?;
         }
@@ -321,12 +374,18 @@ impl<I: Interner> RenderAsRust<I> for OpaqueTyDatum<I> {
         {
             let s = &s.add_debrujin_index(Some(0));
             let clauses = bounds.bounds.skip_binders();
-            write!(
-                f,
-                ": {} = ",
-                display_self_where_clauses_as_bounds(s, clauses)
-            )?;
+            if !clauses.is_empty() {
+                write!(f, ": {}", display_self_where_clauses_as_bounds(s, clauses))?;
+            }
+            let where_clauses = bounds.where_clauses.skip_binders();
+            if !where_clauses.is_empty() {
+                let s = &s.add_indent();
+                write!(f, "\nwhere\n{}\n", where_clauses.display(s))?;
+            } else {
+                write!(f, " ")?;
+            }
         }
+        write!(f, "= ")?;
         write!(
             f,
             "{};",
@@ -454,9 +513,15 @@ impl<I: Interner> RenderAsRust<I> for FnDefDatum<I> {
         let s = &s.add_debrujin_index(None);
         let bound_datum = self.binders.skip_binders();
 
+        // variances
+        write_variances(s, f, VarianceItem::FnDef(self.id))?;
+
         // declaration
-        // fn foo<T>(arg: u32, arg2: T) -> Result<T> where T: Bar
-        // ^^^^^^
+        // unsafe fn foo<T>(arg: u32, arg2: T) -> Result<T> where T: Bar
+        // ^^^^^^^^^
+        if self.sig.safety == Safety::Unsafe {
+            write!(f, "unsafe ")?;
+        }
         write!(f, "fn {}", s.db().fn_def_name(self.id))?;
 
         // binders
@@ -472,11 +537,15 @@ impl<I: Interner> RenderAsRust<I> for FnDefDatum<I> {
             // arguments
             // fn foo<T>(arg: u32, arg2: T) -> Result<T> where T: Bar
             //          ^^^^^^^^^^^^^^^^^^^
-            let arguments = inputs_and_output
-                .argument_types
+            let argument_types = &inputs_and_output.argument_types;
+            let arguments = argument_types
                 .iter()
                 .enumerate()
                 .map(|(idx, arg)| format!("arg_{}: {}", idx, arg.display(s)))
+                .chain(
+                    Some(format!("arg_{}: ...", argument_types.len()))
+                        .filter(|_| self.sig.variadic),
+                )
                 .format(", ");
 
             write!(f, "({})", arguments)?;
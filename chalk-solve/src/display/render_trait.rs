@@ -1,5 +1,5 @@
 //! `RenderAsRust` trait and related utils.
-use std::fmt::{Display, Formatter, Result};
+use core::fmt::{Display, Formatter, Result};
 
 use chalk_ir::interner::Interner;
 
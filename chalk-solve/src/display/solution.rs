@@ -0,0 +1,156 @@
+//! Writer logic for solutions.
+//!
+//! Solutions are written for users, like `Unique; substitution [?0 := Vec<u32>]`:
+//! unlike the other items of this module, they are not Rust source.
+use alloc::format;
+use core::fmt::{Formatter, Result};
+
+use crate::solve::{Guidance, Solution};
+use chalk_ir::{
+    interner::{HasInterner, Interner},
+    *,
+};
+use itertools::Itertools;
+
+use super::{
+    render_trait::RenderAsRust, state::InternalWriterState, ty::is_default_const_ty,
+    utils::as_display,
+};
+
+impl<I: Interner> RenderAsRust<I> for Solution<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        match self {
+            // If a `Unique` solution has no associated data, omit the
+            // trailing semicolon.
+            Solution::Unique(Canonical {
+                binders,
+                value: ConstrainedSubst { subst, constraints },
+            }) if binders.is_empty(interner)
+                && subst.is_empty(interner)
+                && constraints.is_empty(interner) =>
+            {
+                write!(f, "Unique")
+            }
+            Solution::Unique(constrained) => {
+                write!(f, "Unique; {}", RenderAsRust::display(constrained, s))
+            }
+            Solution::Ambig(guidance, ..) => {
+                write!(f, "Ambiguous; {}", RenderAsRust::display(guidance, s))
+            }
+        }
+    }
+}
+
+impl<I: Interner> RenderAsRust<I> for Guidance<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        match self {
+            Guidance::Definite(subst) => write!(
+                f,
+                "definite substitution {}",
+                RenderAsRust::display(subst, s)
+            ),
+            Guidance::Suggested(subst) => write!(
+                f,
+                "suggested substitution {}",
+                RenderAsRust::display(subst, s)
+            ),
+            Guidance::Unknown => write!(f, "no inference guidance"),
+        }
+    }
+}
+
+/// The variables of the canonical value are introduced by a `for<..>`, like
+/// `for<_0_0, '_0_1> { substitution [?0 := Vec<_0_0>] }`.
+impl<I: Interner, T: RenderAsRust<I> + HasInterner<Interner = I>> RenderAsRust<I> for Canonical<T> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        if self.binders.is_empty(interner) {
+            return self.value.fmt(s, f);
+        }
+        // The variables are bound outside of the value, like the variables of
+        // an item: they are at the current depth.
+        let binders = self
+            .binders
+            .iter(interner)
+            .enumerate()
+            .map(|(idx, kind)| {
+                let var = s.apply_mappings(s.invert_debrujin_idx(0, idx));
+                match &kind.kind {
+                    VariableKind::Ty(_) => format!("{}", var),
+                    VariableKind::Lifetime => format!("'{}", var),
+                    VariableKind::Const(ty) if is_default_const_ty(interner, ty) => {
+                        format!("const {}", var)
+                    }
+                    VariableKind::Const(ty) => format!("const {}: {}", var, ty.display(s)),
+                }
+            })
+            .format(", ");
+        write!(f, "for<{}> {{ {} }}", binders, self.value.display(s))
+    }
+}
+
+impl<I: Interner> RenderAsRust<I> for ConstrainedSubst<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        let ConstrainedSubst { subst, constraints } = self;
+        let parts = [
+            (!subst.is_empty(interner))
+                .then(|| format!("substitution {}", RenderAsRust::display(subst, s))),
+            (!constraints.is_empty(interner))
+                .then(|| format!("lifetime constraints {}", constraints.display(s))),
+        ];
+        write!(f, "{}", parts.iter().flatten().format(", "))
+    }
+}
+
+/// The values of the variables of the goal, `?0`, `?1`, ... in order.
+impl<I: Interner> RenderAsRust<I> for Substitution<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        write!(
+            f,
+            "[{}]",
+            self.iter(interner)
+                .enumerate()
+                .map(|(index, arg)| as_display(move |f| write!(
+                    f,
+                    "?{} := {}",
+                    index,
+                    arg.display(s)
+                )))
+                .format(", ")
+        )
+    }
+}
+
+impl<I: Interner> RenderAsRust<I> for Constraints<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        let interner = s.db().interner();
+        write!(
+            f,
+            "[{}]",
+            self.iter(interner)
+                .map(|constraint| constraint.display(s))
+                .format(", ")
+        )
+    }
+}
+
+/// The environment of a constraint is not written.
+impl<I: Interner> RenderAsRust<I> for InEnvironment<Constraint<I>> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        self.goal.fmt(s, f)
+    }
+}
+
+impl<I: Interner> RenderAsRust<I> for Constraint<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        match self {
+            Constraint::LifetimeOutlives(a, b) => write!(f, "{}: {}", a.display(s), b.display(s)),
+            Constraint::TypeOutlives(ty, lifetime) => {
+                write!(f, "{}: {}", ty.display(s), lifetime.display(s))
+            }
+        }
+    }
+}
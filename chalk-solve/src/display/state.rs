@@ -1,21 +1,23 @@
 //! Persistent state passed down between writers.
 //!
 //! This is essentially `InternalWriterState` and other things supporting that.
-use core::hash::Hash;
-use std::{
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::{collections::BTreeMap, rc::Rc, string::String, sync::Arc};
+use core::{
     borrow::Borrow,
-    collections::BTreeMap,
     fmt::{Debug, Display, Formatter, Result},
+    hash::Hash,
     marker::PhantomData,
-    rc::Rc,
-    sync::{Arc, Mutex},
 };
 
-use crate::RustIrDatabase;
+use crate::{lock, FxIndexMap, Lock, RustIrDatabase};
 use chalk_ir::{interner::Interner, *};
-use indexmap::IndexMap;
 use itertools::Itertools;
 
+use super::{render_trait::RenderAsRust, ty::is_default_const_ty};
+
 /// Like a BoundVar, but with the debrujin index inverted so as to create a
 /// canonical name we can use anywhere for each bound variable.
 ///
@@ -38,6 +40,39 @@ impl Display for InvertedBoundVar {
     }
 }
 
+/// How values are written.
+///
+/// The defaults write the names and syntax of Rust source, as `.chalk`
+/// programs and the REPL do. The other settings show more of chalk's
+/// representation, when debugging, or shorten large types, in diagnostics.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// Writes placeholders with their universe, like `!1_0`, rather than just
+    /// their index, like `!0`.
+    pub show_universes: bool,
+    /// Writes bound variables with their debrujin index and their index
+    /// within the binder, like `^1.0`, rather than a name, like `_0_0` or
+    /// `Self`.
+    pub debrujin_indices: bool,
+    /// Writes opaque types as their bounds, like `impl Iterator<Item = u32>`,
+    /// rather than by name.
+    pub expand_aliases: bool,
+    /// Types nested deeper than this are elided, like `Vec<…>` for a depth
+    /// of 1.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        PrettyOptions {
+            show_universes: true,
+            debrujin_indices: false,
+            expand_aliases: false,
+            max_depth: None,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 enum UnifiedId<I: Interner> {
     AdtId(I::InternedAdtId),
@@ -48,7 +83,7 @@ enum UnifiedId<I: Interner> {
 pub struct IdAliasStore<T> {
     /// Map from the DefIds we've encountered to a u32 alias id unique to all ids
     /// the same name.
-    aliases: IndexMap<T, u32>,
+    aliases: FxIndexMap<T, u32>,
     /// Map from each name to the next unused u32 alias id.
     next_unused_for_name: BTreeMap<String, u32>,
 }
@@ -56,7 +91,7 @@ pub struct IdAliasStore<T> {
 impl<T> Default for IdAliasStore<T> {
     fn default() -> Self {
         IdAliasStore {
-            aliases: IndexMap::default(),
+            aliases: FxIndexMap::default(),
             next_unused_for_name: BTreeMap::default(),
         }
     }
@@ -112,7 +147,8 @@ where
     I: Interner,
 {
     pub(super) db: P,
-    id_aliases: Arc<Mutex<IdAliases<I>>>,
+    id_aliases: Arc<Lock<IdAliases<I>>>,
+    options: PrettyOptions,
     _phantom: PhantomData<DB>,
 }
 
@@ -126,6 +162,7 @@ where
         WriterState {
             db: self.db.clone(),
             id_aliases: self.id_aliases.clone(),
+            options: self.options,
             _phantom: PhantomData,
         }
     }
@@ -140,11 +177,17 @@ where
     pub fn new(db: P) -> Self {
         WriterState {
             db,
-            id_aliases: Arc::new(Mutex::new(IdAliases::default())),
+            id_aliases: Arc::new(Lock::new(IdAliases::default())),
+            options: PrettyOptions::default(),
             _phantom: PhantomData,
         }
     }
 
+    /// Writes values with `options` rather than the defaults.
+    pub fn with_options(self, options: PrettyOptions) -> Self {
+        WriterState { options, ..self }
+    }
+
     /// Returns a new version of self containing a wrapped database which
     /// references the outer data.
     ///
@@ -163,6 +206,7 @@ where
         WriterState {
             db: f(&self.db),
             id_aliases: self.id_aliases.clone(),
+            options: self.options,
             _phantom: PhantomData,
         }
     }
@@ -181,6 +225,8 @@ pub(super) struct InternalWriterState<'a, I: Interner> {
     persistent_state: WriterState<I, dyn RustIrDatabase<I> + 'a, &'a dyn RustIrDatabase<I>>,
     indent_level: usize,
     debrujin_indices_deep: u32,
+    // how many types the value being written is nested in
+    type_depth: usize,
     // lowered_(inverted_debrujin_idx, index) -> src_correct_(inverted_debrujin_idx, index)
     remapping: Rc<BTreeMap<InvertedBoundVar, InvertedBoundVar>>,
     // the inverted_bound_var which maps to "Self"
@@ -200,6 +246,20 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
                 .wrap_db_ref(|db| db.borrow() as &dyn RustIrDatabase<I>),
             indent_level: 0,
             debrujin_indices_deep: 0,
+            type_depth: 0,
+            remapping: Rc::new(BTreeMap::new()),
+            self_mapping: None,
+        }
+    }
+
+    /// Writer state for a value written on its own, like a solution, rather
+    /// than as an item of a program (see `DisplayWithDb`).
+    pub(super) fn for_db(db: &'a dyn RustIrDatabase<I>, options: PrettyOptions) -> Self {
+        InternalWriterState {
+            persistent_state: WriterState::new(db).with_options(options),
+            indent_level: 0,
+            debrujin_indices_deep: 0,
+            type_depth: 0,
             remapping: Rc::new(BTreeMap::new()),
             self_mapping: None,
         }
@@ -209,6 +269,26 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
         self.persistent_state.db
     }
 
+    pub(super) fn options(&self) -> &PrettyOptions {
+        &self.persistent_state.options
+    }
+
+    /// Whether the types written from here are nested too deep, and should
+    /// be elided (see `PrettyOptions::max_depth`).
+    pub(super) fn at_max_depth(&self) -> bool {
+        self.options()
+            .max_depth
+            .is_some_and(|max_depth| self.type_depth >= max_depth)
+    }
+
+    /// The state for writing the contents of a type.
+    pub(super) fn enter_type(&self) -> Self {
+        InternalWriterState {
+            type_depth: self.type_depth + 1,
+            ..self.clone()
+        }
+    }
+
     pub(super) fn add_indent(&self) -> Self {
         InternalWriterState {
             indent_level: self.indent_level + 1,
@@ -217,23 +297,17 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
     }
 
     pub(super) fn indent(&self) -> impl Display {
-        std::iter::repeat("  ").take(self.indent_level).format("")
+        core::iter::repeat("  ").take(self.indent_level).format("")
     }
 
     pub(super) fn alias_for_adt_id_name(&self, id: I::InternedAdtId, name: String) -> impl Display {
-        self.persistent_state
-            .id_aliases
-            .lock()
-            .unwrap()
+        lock(&self.persistent_state.id_aliases)
             .id_aliases
             .alias_for_id_name(UnifiedId::AdtId(id), name)
     }
 
     pub(super) fn alias_for_id_name(&self, id: I::DefId, name: String) -> impl Display {
-        self.persistent_state
-            .id_aliases
-            .lock()
-            .unwrap()
+        lock(&self.persistent_state.id_aliases)
             .id_aliases
             .alias_for_id_name(UnifiedId::DefId(id), name)
     }
@@ -297,6 +371,13 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
     }
 
     pub(super) fn apply_mappings(&self, b: InvertedBoundVar) -> impl Display {
+        if self.options().debrujin_indices {
+            return format!(
+                "^{}.{}",
+                (self.debrujin_indices_deep as i64) - b.inverted_debrujin_idx,
+                b.within_idx
+            );
+        }
         let remapped = self.remapping.get(&b).copied().unwrap_or(b);
         if self.self_mapping == Some(remapped) {
             "Self".to_owned()
@@ -305,6 +386,14 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
         }
     }
 
+    pub(super) fn display_placeholder(&self, placeholder: &PlaceholderIndex) -> impl Display {
+        if self.options().show_universes {
+            format!("!{}_{}", placeholder.ui.counter, placeholder.idx)
+        } else {
+            format!("!{}", placeholder.idx)
+        }
+    }
+
     pub(super) fn indices_for_bound_var(&self, b: &BoundVar) -> InvertedBoundVar {
         self.invert_debrujin_idx(b.debruijn.depth(), b.index)
     }
@@ -346,7 +435,12 @@ impl<'a, I: Interner> InternalWriterState<'a, I> {
             .map(move |(parameter, var)| match parameter {
                 VariableKind::Ty(_) => format!("{}", self.apply_mappings(var)),
                 VariableKind::Lifetime => format!("'{}", self.apply_mappings(var)),
-                VariableKind::Const(_ty) => format!("const {}", self.apply_mappings(var)),
+                VariableKind::Const(ty) if is_default_const_ty(self.db().interner(), ty) => {
+                    format!("const {}", self.apply_mappings(var))
+                }
+                VariableKind::Const(ty) => {
+                    format!("const {}: {}", self.apply_mappings(var), ty.display(self))
+                }
             })
     }
 }
@@ -1,6 +1,9 @@
 //! Contains a `LoggingIrDatabase` which returns stub versions of everything
 //! queried.
-use std::sync::Arc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
 
 use crate::rust_ir::{GeneratorDatum, GeneratorWitnessDatum};
 use crate::{
@@ -8,11 +11,12 @@ use crate::{
         AdtDatumBound, AdtKind, AdtVariantDatum, AssociatedTyDatumBound, FnDefDatumBound,
         OpaqueTyDatumBound, TraitDatumBound,
     },
+    simplified_type::ImplFilter,
     RustIrDatabase,
 };
 use chalk_ir::{
-    interner::Interner, Binders, CanonicalVarKinds, GeneratorId, Substitution, Ty,
-    UnificationDatabase, VariableKinds, Variances,
+    interner::Interner, Binders, GeneratorId, Substitution, Ty, UnificationDatabase, VariableKinds,
+    Variances,
 };
 
 #[derive(Debug)]
@@ -44,7 +48,7 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
     fn associated_ty_data(
         &self,
         ty: chalk_ir::AssocTypeId<I>,
-    ) -> std::sync::Arc<crate::rust_ir::AssociatedTyDatum<I>> {
+    ) -> alloc::sync::Arc<crate::rust_ir::AssociatedTyDatum<I>> {
         let mut v = (*self.db.associated_ty_data(ty)).clone();
         v.binders = Binders::new(
             v.binders.binders.clone(),
@@ -59,7 +63,7 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
     fn trait_datum(
         &self,
         trait_id: chalk_ir::TraitId<I>,
-    ) -> std::sync::Arc<crate::rust_ir::TraitDatum<I>> {
+    ) -> alloc::sync::Arc<crate::rust_ir::TraitDatum<I>> {
         let mut v = (*self.db.trait_datum(trait_id)).clone();
         v.binders = Binders::new(
             v.binders.binders.clone(),
@@ -70,10 +74,16 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
         Arc::new(v)
     }
 
-    fn adt_datum(&self, adt_id: chalk_ir::AdtId<I>) -> std::sync::Arc<crate::rust_ir::AdtDatum<I>> {
+    fn adt_datum(
+        &self,
+        adt_id: chalk_ir::AdtId<I>,
+    ) -> alloc::sync::Arc<crate::rust_ir::AdtDatum<I>> {
         let mut v = (*self.db.adt_datum(adt_id)).clone();
         let variants = match v.kind {
-            AdtKind::Struct | AdtKind::Union => vec![AdtVariantDatum { fields: vec![] }],
+            AdtKind::Struct | AdtKind::Union => vec![AdtVariantDatum {
+                fields: vec![],
+                discriminant: None,
+            }],
             AdtKind::Enum => vec![],
         };
         v.binders = Binders::new(
@@ -86,7 +96,7 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
         Arc::new(v)
     }
 
-    fn adt_repr(&self, id: chalk_ir::AdtId<I>) -> std::sync::Arc<crate::rust_ir::AdtRepr<I>> {
+    fn adt_repr(&self, id: chalk_ir::AdtId<I>) -> alloc::sync::Arc<crate::rust_ir::AdtRepr<I>> {
         self.db.adt_repr(id)
     }
 
@@ -97,7 +107,7 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
     fn fn_def_datum(
         &self,
         fn_def_id: chalk_ir::FnDefId<I>,
-    ) -> std::sync::Arc<crate::rust_ir::FnDefDatum<I>> {
+    ) -> alloc::sync::Arc<crate::rust_ir::FnDefDatum<I>> {
         let mut v = (*self.db.fn_def_datum(fn_def_id)).clone();
         v.binders = Binders::new(
             v.binders.binders.clone(),
@@ -112,21 +122,28 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
     fn impl_datum(
         &self,
         _impl_id: chalk_ir::ImplId<I>,
-    ) -> std::sync::Arc<crate::rust_ir::ImplDatum<I>> {
+    ) -> alloc::sync::Arc<crate::rust_ir::ImplDatum<I>> {
         unreachable!("impl items should never be stubbed")
     }
 
     fn associated_ty_value(
         &self,
         _id: crate::rust_ir::AssociatedTyValueId<I>,
-    ) -> std::sync::Arc<crate::rust_ir::AssociatedTyValue<I>> {
+    ) -> alloc::sync::Arc<crate::rust_ir::AssociatedTyValue<I>> {
         unreachable!("associated type values should never be stubbed")
     }
 
+    fn inherent_assoc_ty_data(
+        &self,
+        id: chalk_ir::AssocTypeId<I>,
+    ) -> Option<alloc::sync::Arc<crate::rust_ir::InherentAssocTyDatum<I>>> {
+        self.db.inherent_assoc_ty_data(id)
+    }
+
     fn opaque_ty_data(
         &self,
         id: chalk_ir::OpaqueTyId<I>,
-    ) -> std::sync::Arc<crate::rust_ir::OpaqueTyDatum<I>> {
+    ) -> alloc::sync::Arc<crate::rust_ir::OpaqueTyDatum<I>> {
         let mut v = (*self.db.opaque_ty_data(id)).clone();
         v.bound = Binders::new(
             v.bound.binders,
@@ -148,8 +165,7 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
     fn impls_for_trait(
         &self,
         _trait_id: chalk_ir::TraitId<I>,
-        _parameters: &[chalk_ir::GenericArg<I>],
-        _binders: &CanonicalVarKinds<I>,
+        _filter: &ImplFilter<'_, I>,
     ) -> Vec<chalk_ir::ImplId<I>> {
         // We panic here because the returned ids may not be collected,
         // resulting in unresolvable names.
@@ -180,6 +196,29 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
         self.db.well_known_trait_id(well_known_trait)
     }
 
+    fn custom_builtin(
+        &self,
+        trait_id: chalk_ir::TraitId<I>,
+    ) -> Option<crate::clauses::custom_builtins::CustomBuiltin<I>> {
+        self.db.custom_builtin(trait_id)
+    }
+
+    fn custom_predicate_clauses(
+        &self,
+        builder: &mut crate::clauses::builder::ClauseBuilder<'_, I>,
+        predicate: &chalk_ir::CustomPredicate<I>,
+    ) -> Result<(), chalk_ir::Floundered> {
+        self.db.custom_predicate_clauses(builder, predicate)
+    }
+
+    fn program_features(&self) -> crate::rust_ir::ProgramFeatures {
+        self.db.program_features()
+    }
+
+    fn variance_of(&self, item: crate::variance::VarianceItem<I>) -> Variances<I> {
+        self.db.variance_of(item)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -1,7 +1,8 @@
 //! Writer logic for types.
 //!
 //! Contains the highly-recursive logic for writing `TyKind` and its variants.
-use std::fmt::{Formatter, Result};
+use alloc::format;
+use core::fmt::{Display, Formatter, Result};
 
 use crate::split::Split;
 use chalk_ir::{interner::Interner, *};
@@ -9,7 +10,7 @@ use itertools::Itertools;
 
 use super::{
     display_self_where_clauses_as_bounds, display_type_with_generics, render_trait::RenderAsRust,
-    state::InternalWriterState,
+    state::InternalWriterState, utils::as_display,
 };
 
 impl<I: Interner> RenderAsRust<I> for TyKind<I> {
@@ -72,11 +73,9 @@ impl<I: Interner> RenderAsRust<I> for TyKind<I> {
                     }
                 )
             }
-            TyKind::OpaqueType(opaque_ty_id, substitution) => write!(
-                f,
-                "{}",
-                display_type_with_generics(s, *opaque_ty_id, substitution.as_slice(interner))
-            ),
+            TyKind::OpaqueType(opaque_ty_id, substitution) => {
+                display_opaque_ty(s, *opaque_ty_id, substitution).fmt(f)
+            }
             TyKind::Raw(mutability, ty) => match mutability {
                 Mutability::Mut => write!(f, "*mut {}", ty.display(s)),
                 Mutability::Not => write!(f, "*const {}", ty.display(s)),
@@ -94,6 +93,7 @@ impl<I: Interner> RenderAsRust<I> for TyKind<I> {
             TyKind::FnDef(..) => write!(f, "<fn_def>"),
             TyKind::Closure(..) => write!(f, "<closure>"),
             TyKind::Foreign(..) => write!(f, "<foreign>"),
+            TyKind::Custom(..) => write!(f, "<custom>"),
             TyKind::Generator(..) => write!(f, "<generator>"),
             TyKind::GeneratorWitness(..) => write!(f, "<generator_witness>"),
 
@@ -120,7 +120,7 @@ impl<I: Interner> RenderAsRust<I> for TyKind<I> {
             TyKind::InferenceVar(_, _) => write!(f, "_"),
             TyKind::Alias(alias_ty) => alias_ty.fmt(s, f),
             TyKind::Function(func) => func.fmt(s, f),
-            TyKind::Placeholder(_) => write!(f, "<placeholder>"),
+            TyKind::Placeholder(ix) => write!(f, "{}", s.display_placeholder(ix)),
         }
     }
 }
@@ -130,6 +130,7 @@ impl<I: Interner> RenderAsRust<I> for AliasTy<I> {
         match self {
             AliasTy::Projection(projection_ty) => projection_ty.fmt(s, f),
             AliasTy::Opaque(opaque_ty) => opaque_ty.fmt(s, f),
+            AliasTy::Inherent(inherent_ty) => inherent_ty.fmt(s, f),
         }
     }
 }
@@ -160,17 +161,51 @@ impl<I: Interner> RenderAsRust<I> for ProjectionTy<I> {
     }
 }
 
-impl<I: Interner> RenderAsRust<I> for OpaqueTy<I> {
+impl<I: Interner> RenderAsRust<I> for InherentProjectionTy<I> {
     fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        // <X>::Z
         let interner = s.db().interner();
         write!(
             f,
-            "{}",
-            display_type_with_generics(s, self.opaque_ty_id, self.substitution.as_slice(interner),)
+            "<{}>::{}",
+            self.self_type_parameter(interner).display(s),
+            self.associated_ty_id.display(s),
         )
     }
 }
 
+impl<I: Interner> RenderAsRust<I> for OpaqueTy<I> {
+    fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        display_opaque_ty(s, self.opaque_ty_id, &self.substitution).fmt(f)
+    }
+}
+
+/// Displays an opaque type by name, like `Foo<T>`, or, when expanding aliases,
+/// as its bounds, like `impl Iterator<Item=T>`.
+fn display_opaque_ty<'a, I: Interner>(
+    s: &'a InternalWriterState<'a, I>,
+    opaque_ty_id: OpaqueTyId<I>,
+    substitution: &'a Substitution<I>,
+) -> impl Display + 'a {
+    as_display(move |f| {
+        let interner = s.db().interner();
+        if !s.options().expand_aliases {
+            return display_type_with_generics(s, opaque_ty_id, substitution.as_slice(interner))
+                .fmt(f);
+        }
+        let datum = s.db().opaque_ty_data(opaque_ty_id);
+        let bounds = datum
+            .bound
+            .clone()
+            .substitute(interner, substitution)
+            .bounds;
+        // the bounds bind `Self`, like the bounds of `dyn Trait`
+        let s = &s.add_debrujin_index(None);
+        let bounds = display_self_where_clauses_as_bounds(s, bounds.skip_binders());
+        write!(f, "impl {}", bounds)
+    })
+}
+
 impl<I: Interner> RenderAsRust<I> for FnPointer<I> {
     fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
         let interner = s.db().interner();
@@ -236,9 +271,7 @@ impl<I: Interner> RenderAsRust<I> for LifetimeData<I> {
         match self {
             LifetimeData::BoundVar(v) => write!(f, "'{}", s.display_bound_var(v)),
             LifetimeData::InferenceVar(_) => write!(f, "'_"),
-            LifetimeData::Placeholder(ix) => {
-                write!(f, "'_placeholder_{}_{}", ix.ui.counter, ix.idx)
-            }
+            LifetimeData::Placeholder(ix) => write!(f, "'{}", s.display_placeholder(ix)),
             LifetimeData::Static => write!(f, "'static"),
             LifetimeData::Empty(_) => write!(f, "'<empty>"),
             LifetimeData::Erased => write!(f, "'_"),
@@ -249,9 +282,25 @@ impl<I: Interner> RenderAsRust<I> for LifetimeData<I> {
     }
 }
 
+/// Whether consts of type `ty` are written without their type, which is the
+/// case of `usize`, the type of unannotated consts.
+pub(super) fn is_default_const_ty<I: Interner>(interner: I, ty: &Ty<I>) -> bool {
+    matches!(
+        ty.kind(interner),
+        TyKind::Scalar(Scalar::Uint(UintTy::Usize))
+    )
+}
+
 impl<I: Interner> RenderAsRust<I> for ConstData<I> {
     fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
-        write!(f, "{}", self.value.display(s))
+        write!(f, "{}", self.value.display(s))?;
+        // Literals of other types are suffixed with their type, like `3u32`.
+        match self.value {
+            ConstValue::Concrete(_) if !is_default_const_ty(s.db().interner(), &self.ty) => {
+                write!(f, "{}", self.ty.display(s))
+            }
+            _ => Ok(()),
+        }
     }
 }
 
@@ -260,7 +309,7 @@ impl<I: Interner> RenderAsRust<I> for ConstValue<I> {
         match self {
             ConstValue::BoundVar(v) => write!(f, "{}", s.display_bound_var(v)),
             ConstValue::InferenceVar(_) => write!(f, "_"),
-            ConstValue::Placeholder(_) => write!(f, "<const placeholder>"),
+            ConstValue::Placeholder(ix) => write!(f, "{}", s.display_placeholder(ix)),
             ConstValue::Concrete(value) => write!(f, "{:?}", value.interned),
         }
     }
@@ -278,8 +327,11 @@ impl<I: Interner> RenderAsRust<I> for GenericArgData<I> {
 
 impl<I: Interner> RenderAsRust<I> for Ty<I> {
     fn fmt(&self, s: &InternalWriterState<'_, I>, f: &'_ mut Formatter<'_>) -> Result {
+        if s.at_max_depth() {
+            return write!(f, "…");
+        }
         // delegate to TyKind
-        self.kind(s.db().interner()).fmt(s, f)
+        self.kind(s.db().interner()).fmt(&s.enter_type(), f)
     }
 }
 
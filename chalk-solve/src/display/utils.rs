@@ -1,5 +1,7 @@
 //! Render utilities which don't belong anywhere else.
-use std::fmt::{Display, Formatter, Result};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use core::fmt::{Display, Formatter, Result};
 
 pub fn as_display<F: Fn(&mut Formatter<'_>) -> Result>(f: F) -> impl Display {
     struct ClosureDisplay<F: Fn(&mut Formatter<'_>) -> Result>(F);
@@ -30,7 +32,7 @@ macro_rules! write_joined_non_empty_list {
 /// This is specifically useful when implementing
 /// [`RustIrDatabase`][crate::RustIrDatabase] `name_*` methods.
 pub fn sanitize_debug_name(func: impl Fn(&mut Formatter<'_>) -> Option<Result>) -> String {
-    use std::fmt::Write;
+    use core::fmt::Write;
 
     // First, write the debug method contents to a String.
     let mut debug_out = String::new();
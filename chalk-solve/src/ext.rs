@@ -1,4 +1,6 @@
-use crate::infer::InferenceTable;
+use crate::infer::{InferenceTable, VariableOrigin};
+use crate::rust_ir::WellKnownTrait;
+use crate::RustIrDatabase;
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::*;
@@ -53,6 +55,7 @@ where
 pub trait GoalExt<I: Interner> {
     fn into_peeled_goal(self, interner: I) -> UCanonical<InEnvironment<Goal<I>>>;
     fn into_closed_goal(self, interner: I) -> UCanonical<InEnvironment<Goal<I>>>;
+    fn simplified(self, db: &dyn RustIrDatabase<I>) -> Goal<I>;
 }
 
 impl<I: Interner> GoalExt<I> for Goal<I> {
@@ -76,8 +79,16 @@ impl<I: Interner> GoalExt<I> for Goal<I> {
                     }
 
                     GoalData::Quantified(QuantifierKind::Exists, subgoal) => {
-                        let subgoal =
-                            infer.instantiate_binders_existentially(interner, subgoal.clone());
+                        let subgoal = infer.instantiate_binders_existentially_with_origin(
+                            interner,
+                            subgoal.clone(),
+                            |index| {
+                                Some(VariableOrigin::Existential {
+                                    goal: goal.clone(),
+                                    index,
+                                })
+                            },
+                        );
                         env_goal = InEnvironment::new(&environment, subgoal);
                     }
 
@@ -110,4 +121,33 @@ impl<I: Interner> GoalExt<I> for Goal<I> {
         let canonical_goal = infer.canonicalize(interner, env_goal).quantified;
         InferenceTable::u_canonicalize(interner, &canonical_goal).quantified
     }
+
+    /// Simplifies the goal with `Goal::simplify`, treating
+    /// `T: Sized` as trivially true when `T` is a primitive type that is
+    /// always sized.
+    fn simplified(self, db: &dyn RustIrDatabase<I>) -> Goal<I> {
+        let interner = db.interner();
+        let sized = match db.well_known_trait_id(WellKnownTrait::Sized) {
+            Some(sized) => sized,
+            None => return self.simplify(interner, &|_| false),
+        };
+        self.simplify(interner, &|domain_goal| match domain_goal {
+            DomainGoal::Holds(WhereClause::Implemented(trait_ref))
+                if trait_ref.trait_id == sized =>
+            {
+                matches!(
+                    trait_ref.self_type_parameter(interner).kind(interner),
+                    TyKind::Scalar(_)
+                        | TyKind::Never
+                        | TyKind::Ref(..)
+                        | TyKind::Raw(..)
+                        | TyKind::Array(..)
+                        | TyKind::FnDef(..)
+                        | TyKind::Function(_)
+                        | TyKind::Tuple(0, _)
+                )
+            }
+            _ => false,
+        })
+    }
 }
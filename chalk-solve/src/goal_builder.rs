@@ -7,6 +7,11 @@ use fold::shift::Shift;
 use fold::Fold;
 use interner::{HasInterner, Interner};
 
+mod fluent;
+mod protocols;
+
+pub use fluent::{goal, FluentGoal, LifetimeParam, TyParam};
+
 pub struct GoalBuilder<'i, I: Interner> {
     db: &'i dyn RustIrDatabase<I>,
 }
@@ -0,0 +1,359 @@
+//! A fluent interface for building goals, which takes care of the debruijn
+//! indices of the parameters introduced by `forall` and `exists` goals.
+//!
+//! ```ignore
+//! let goal = goal(db)
+//!     .forall(|t| goal(db).implies(vec![t.implements(clone)], t.is_well_formed()))
+//!     .build();
+//! ```
+//!
+//! builds `forall<T> { if (T: Clone) { WellFormed(T) } }`.
+//!
+//! While a goal is being built, each parameter is represented by a
+//! placeholder in a universe of its own (which never occurs in goals
+//! otherwise); when the `forall` or `exists` goal introducing it is
+//! done, its occurrences are replaced by a variable bound by that goal.
+//! The handles of parameters must therefore not be used outside of the
+//! closure they are passed to.
+
+use crate::RustIrDatabase;
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_derive::FallibleFolder;
+use chalk_ir::cast::{Cast, CastTo, Caster};
+use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The universe of the placeholders standing for parameters.
+const PARAMETER_UNIVERSE: UniverseIndex = UniverseIndex {
+    counter: usize::MAX,
+};
+
+/// Distinguishes the placeholders of different parameters, even those of
+/// different goals built at the same time.
+static NEXT_PARAMETER: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts building a goal; see the [module documentation](self).
+pub fn goal<I: Interner>(db: &dyn RustIrDatabase<I>) -> FluentGoal<'_, I> {
+    FluentGoal { db, goals: vec![] }
+}
+
+/// A goal being built: the conjunction of the goals added to it so far.
+pub struct FluentGoal<'i, I: Interner> {
+    db: &'i dyn RustIrDatabase<I>,
+    goals: Vec<Goal<I>>,
+}
+
+impl<'i, I: Interner> FluentGoal<'i, I> {
+    fn interner(&self) -> I {
+        self.db.interner()
+    }
+
+    /// Adds `goal`.
+    pub fn and(mut self, goal: impl CastTo<Goal<I>>) -> Self {
+        let goal = goal.cast(self.interner());
+        self.goals.push(goal);
+        self
+    }
+
+    /// Adds the goal `if (clauses) { goal }`. As in the `if` of a chalk
+    /// program, the clauses are assumptions about the environment, so a
+    /// clause `T: Clone` is added as `FromEnv(T: Clone)`.
+    pub fn implies<CS, C>(self, clauses: CS, goal: impl CastTo<Goal<I>>) -> Self
+    where
+        CS: IntoIterator<Item = C>,
+        C: CastTo<ProgramClause<I>>,
+    {
+        let interner = self.interner();
+        let goal = GoalData::Implies(
+            ProgramClauses::from_iter(
+                interner,
+                clauses
+                    .into_iter()
+                    .map(|clause| clause.cast(interner).into_from_env_clause(interner)),
+            ),
+            goal.cast(interner),
+        )
+        .intern(interner);
+        self.and(goal)
+    }
+
+    /// Like `implies`, but with hypotheses given as goals, like
+    /// `forall<T> { if (T: A) { T: B } }`, which are assumed as the clauses
+    /// of [`Goal::to_program_clauses`]. Returns `None` if one of the
+    /// hypotheses cannot be assumed.
+    pub fn implies_goals<HS, H>(self, hypotheses: HS, goal: impl CastTo<Goal<I>>) -> Option<Self>
+    where
+        HS: IntoIterator<Item = H>,
+        H: CastTo<Goal<I>>,
+    {
+        let interner = self.interner();
+        let mut clauses = vec![];
+        for hypothesis in hypotheses {
+            clauses.extend(hypothesis.cast(interner).to_program_clauses(interner)?);
+        }
+        Some(self.implies(clauses, goal))
+    }
+
+    /// Adds the goal `not { goal }`.
+    pub fn not(self, goal: impl CastTo<Goal<I>>) -> Self {
+        let interner = self.interner();
+        let goal = GoalData::Not(goal.cast(interner)).intern(interner);
+        self.and(goal)
+    }
+
+    /// Adds the goal `forall<T> { G }`, where `G` is returned by `body`
+    /// given the type `T`.
+    pub fn forall<G: CastTo<Goal<I>>>(self, body: impl FnOnce(TyParam<I>) -> G) -> Self {
+        self.ty_quantified(QuantifierKind::ForAll, body)
+    }
+
+    /// Adds the goal `exists<T> { G }`, where `G` is returned by `body`
+    /// given the type `T`.
+    pub fn exists<G: CastTo<Goal<I>>>(self, body: impl FnOnce(TyParam<I>) -> G) -> Self {
+        self.ty_quantified(QuantifierKind::Exists, body)
+    }
+
+    /// Adds the goal `forall<'a> { G }`, where `G` is returned by `body`
+    /// given the lifetime `'a`.
+    pub fn forall_lifetime<G: CastTo<Goal<I>>>(
+        self,
+        body: impl FnOnce(LifetimeParam<I>) -> G,
+    ) -> Self {
+        self.lifetime_quantified(QuantifierKind::ForAll, body)
+    }
+
+    /// Adds the goal `exists<'a> { G }`, where `G` is returned by `body`
+    /// given the lifetime `'a`.
+    pub fn exists_lifetime<G: CastTo<Goal<I>>>(
+        self,
+        body: impl FnOnce(LifetimeParam<I>) -> G,
+    ) -> Self {
+        self.lifetime_quantified(QuantifierKind::Exists, body)
+    }
+
+    fn ty_quantified<G: CastTo<Goal<I>>>(
+        self,
+        kind: QuantifierKind,
+        body: impl FnOnce(TyParam<I>) -> G,
+    ) -> Self {
+        let interner = self.interner();
+        let placeholder = fresh_placeholder();
+        let param = TyParam {
+            interner,
+            ty: placeholder.to_ty(interner),
+        };
+        let goal = body(param).cast(interner);
+        self.quantified(
+            kind,
+            VariableKind::Ty(TyVariableKind::General),
+            placeholder,
+            goal,
+        )
+    }
+
+    fn lifetime_quantified<G: CastTo<Goal<I>>>(
+        self,
+        kind: QuantifierKind,
+        body: impl FnOnce(LifetimeParam<I>) -> G,
+    ) -> Self {
+        let interner = self.interner();
+        let placeholder = fresh_placeholder();
+        let param = LifetimeParam {
+            interner,
+            lifetime: placeholder.to_lifetime(interner),
+        };
+        let goal = body(param).cast(interner);
+        self.quantified(kind, VariableKind::Lifetime, placeholder, goal)
+    }
+
+    /// Adds the goal binding `placeholder` in `goal` with `kind`.
+    fn quantified(
+        self,
+        kind: QuantifierKind,
+        variable_kind: VariableKind<I>,
+        placeholder: PlaceholderIndex,
+        goal: Goal<I>,
+    ) -> Self {
+        let interner = self.interner();
+        let goal = goal.fold_with(
+            &mut BindParameter {
+                interner,
+                placeholder,
+            },
+            DebruijnIndex::INNERMOST,
+        );
+        let binders = Binders::new(VariableKinds::from1(interner, variable_kind), goal);
+        self.and(GoalData::Quantified(kind, binders).intern(interner))
+    }
+
+    /// Returns the goal: the conjunction of all the goals added, or the goal
+    /// itself if only one was.
+    pub fn build(self) -> Goal<I> {
+        let interner = self.interner();
+        let mut goals = self.goals;
+        if goals.len() == 1 {
+            goals.pop().unwrap()
+        } else {
+            Goal::all(interner, goals.into_iter().casted(interner))
+        }
+    }
+}
+
+impl<I: Interner> CastTo<Goal<I>> for FluentGoal<'_, I> {
+    fn cast_to(self, _interner: I) -> Goal<I> {
+        self.build()
+    }
+}
+
+fn fresh_placeholder() -> PlaceholderIndex {
+    PlaceholderIndex {
+        ui: PARAMETER_UNIVERSE,
+        idx: NEXT_PARAMETER.fetch_add(1, Ordering::Relaxed),
+    }
+}
+
+/// A type parameter introduced by [`FluentGoal::forall`] or
+/// [`FluentGoal::exists`].
+#[derive(Clone, Debug)]
+pub struct TyParam<I: Interner> {
+    interner: I,
+    ty: Ty<I>,
+}
+
+impl<I: Interner> TyParam<I> {
+    /// The parameter, as a type to build other types with.
+    pub fn ty(&self) -> Ty<I> {
+        self.ty.clone()
+    }
+
+    /// `T: Trait`, for a trait without other parameters.
+    pub fn implements(&self, trait_id: TraitId<I>) -> TraitRef<I> {
+        self.implements_with(trait_id, None::<GenericArg<I>>)
+    }
+
+    /// `T: Trait<P1..Pn>`, given the parameters `P1..Pn` of the trait other
+    /// than the self type.
+    pub fn implements_with<A: CastTo<GenericArg<I>>>(
+        &self,
+        trait_id: TraitId<I>,
+        parameters: impl IntoIterator<Item = A>,
+    ) -> TraitRef<I> {
+        let interner = self.interner;
+        TraitRef {
+            trait_id,
+            substitution: Substitution::from_iter(
+                interner,
+                Some(self.ty().cast(interner))
+                    .into_iter()
+                    .chain(parameters.into_iter().casted(interner)),
+            ),
+        }
+    }
+
+    /// `WellFormed(T)`.
+    pub fn is_well_formed(&self) -> WellFormed<I> {
+        WellFormed::Ty(self.ty())
+    }
+
+    /// `T = U`.
+    pub fn equals(&self, other: impl CastTo<GenericArg<I>>) -> EqGoal<I> {
+        EqGoal {
+            a: self.ty().cast(self.interner),
+            b: other.cast(self.interner),
+        }
+    }
+}
+
+impl<I: Interner> CastTo<GenericArg<I>> for TyParam<I> {
+    fn cast_to(self, interner: I) -> GenericArg<I> {
+        self.ty.cast(interner)
+    }
+}
+
+/// A lifetime parameter introduced by [`FluentGoal::forall_lifetime`] or
+/// [`FluentGoal::exists_lifetime`].
+#[derive(Clone, Debug)]
+pub struct LifetimeParam<I: Interner> {
+    interner: I,
+    lifetime: Lifetime<I>,
+}
+
+impl<I: Interner> LifetimeParam<I> {
+    /// The parameter, as a lifetime to build types with.
+    pub fn lifetime(&self) -> Lifetime<I> {
+        self.lifetime.clone()
+    }
+
+    /// `'a: 'b`.
+    pub fn outlives(&self, b: impl CastTo<Lifetime<I>>) -> LifetimeOutlives<I> {
+        LifetimeOutlives {
+            a: self.lifetime(),
+            b: b.cast(self.interner),
+        }
+    }
+
+    /// `'a = 'b`.
+    pub fn equals(&self, other: impl CastTo<GenericArg<I>>) -> EqGoal<I> {
+        EqGoal {
+            a: self.lifetime().cast(self.interner),
+            b: other.cast(self.interner),
+        }
+    }
+}
+
+impl<I: Interner> CastTo<GenericArg<I>> for LifetimeParam<I> {
+    fn cast_to(self, interner: I) -> GenericArg<I> {
+        self.lifetime.cast(interner)
+    }
+}
+
+impl<I: Interner> CastTo<Lifetime<I>> for LifetimeParam<I> {
+    fn cast_to(self, _interner: I) -> Lifetime<I> {
+        self.lifetime
+    }
+}
+
+/// Replaces `placeholder` by the variable of the binder around the folded
+/// value.
+#[derive(FallibleFolder)]
+struct BindParameter<I: Interner> {
+    interner: I,
+    placeholder: PlaceholderIndex,
+}
+
+impl<I: Interner> Folder<I> for BindParameter<I> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
+        self
+    }
+
+    fn fold_free_placeholder_ty(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Ty<I> {
+        if universe == self.placeholder {
+            TyKind::BoundVar(BoundVar::new(outer_binder, 0)).intern(self.interner)
+        } else {
+            universe.to_ty(self.interner)
+        }
+    }
+
+    fn fold_free_placeholder_lifetime(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        if universe == self.placeholder {
+            LifetimeData::BoundVar(BoundVar::new(outer_binder, 0)).intern(self.interner)
+        } else {
+            universe.to_lifetime(self.interner)
+        }
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
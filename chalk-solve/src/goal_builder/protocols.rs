@@ -0,0 +1,68 @@
+//! Goals for the traits behind common desugarings, like `for` loops and
+//! calls, so that tests and hosts do not have to spell them out.
+
+use super::GoalBuilder;
+use crate::rust_ir::WellKnownTrait;
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+
+impl<'i, I: Interner> GoalBuilder<'i, I> {
+    /// `T: IntoIterator<Item = U>`, as needed by `for x in t` with `t: T`
+    /// and `x: U`. Returns `None` if the program has no
+    /// `#[lang(into_iterator)]` trait.
+    pub fn into_iterator_with_item(&mut self, ty: Ty<I>, item: Ty<I>) -> Option<Goal<I>> {
+        let substitution = Substitution::from1(self.interner(), ty);
+        self.implements_with_output(WellKnownTrait::IntoIterator, substitution, item)
+    }
+
+    /// `T: Iterator<Item = U>`. Returns `None` if the program has no
+    /// `#[lang(iterator)]` trait.
+    pub fn iterator_with_item(&mut self, ty: Ty<I>, item: Ty<I>) -> Option<Goal<I>> {
+        let substitution = Substitution::from1(self.interner(), ty);
+        self.implements_with_output(WellKnownTrait::Iterator, substitution, item)
+    }
+
+    /// `F: FnOnce<(A1, .., An), Output = R>`, as needed by the call
+    /// `f(a1, .., an)` with `f: F` returning an `R`. Returns `None` if the
+    /// program has no `#[lang(fn_once)]` trait.
+    pub fn callable(
+        &mut self,
+        callee: Ty<I>,
+        arguments: impl IntoIterator<Item = Ty<I>>,
+        output: Ty<I>,
+    ) -> Option<Goal<I>> {
+        let interner = self.interner();
+        let arguments = Substitution::from_iter(interner, arguments);
+        let arguments = TyKind::Tuple(arguments.len(interner), arguments).intern(interner);
+        let substitution =
+            Substitution::from_iter(interner, [callee.cast(interner), arguments.cast(interner)]);
+        self.implements_with_output(WellKnownTrait::FnOnce, substitution, output)
+    }
+
+    /// `Trait<..>` implemented with `substitution`, and its first associated
+    /// type, which does not have parameters of its own, equal to `output`.
+    fn implements_with_output(
+        &mut self,
+        well_known: WellKnownTrait,
+        substitution: Substitution<I>,
+        output: Ty<I>,
+    ) -> Option<Goal<I>> {
+        let interner = self.interner();
+        let trait_id = self.db().well_known_trait_id(well_known)?;
+        let associated_ty_id = *self.db().trait_datum(trait_id).associated_ty_ids.first()?;
+        let alias_eq = AliasEq {
+            alias: AliasTy::Projection(ProjectionTy {
+                associated_ty_id,
+                substitution: substitution.clone(),
+            }),
+            ty: output,
+        };
+        let trait_ref = TraitRef {
+            trait_id,
+            substitution,
+        };
+        // In the order `T: Trait<Assoc = U>` is lowered in.
+        Some(self.all([alias_eq.cast::<Goal<I>>(interner), trait_ref.cast(interner)]))
+    }
+}
@@ -1,29 +1,70 @@
+use crate::FxHashMap;
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::*;
 use chalk_ir::{cast::Cast, fold::Fold};
 use tracing::debug;
 
 mod canonicalize;
+mod fallback;
 pub(crate) mod instantiate;
 mod invert;
+mod leak_check;
 mod test;
 pub mod ucanonicalize;
 pub mod unify;
+#[cfg(any(not(feature = "std"), test))]
+mod union_find;
 mod var;
 
+/// The union-find table of the variables: `ena`'s, or without `std`, which
+/// `ena` needs, the one in `union_find`.
+#[cfg(feature = "std")]
+mod table {
+    pub(crate) use ena::unify::{UnifyKey, UnifyValue};
+
+    pub(crate) type UnificationTable<K> = ena::unify::InPlaceUnificationTable<K>;
+    pub(crate) type Snapshot<K> = ena::unify::Snapshot<ena::unify::InPlace<K>>;
+}
+#[cfg(not(feature = "std"))]
+use self::union_find as table;
+
+use self::table::{Snapshot, UnificationTable, UnifyKey};
 use self::var::*;
 
 #[derive(Clone)]
 pub struct InferenceTable<I: Interner> {
-    unify: ena::unify::InPlaceUnificationTable<EnaVariable<I>>,
+    unify: UnificationTable<EnaVariable<I>>,
     vars: Vec<EnaVariable<I>>,
     max_universe: UniverseIndex,
+    /// Type variables created via `new_diverging_variable`.
+    diverging: Vec<EnaVariable<I>>,
+    /// What created the variables made via `new_variable_with_origin`;
+    /// `None` unless `record_variable_origins` was called.
+    origins: Option<FxHashMap<EnaVariable<I>, VariableOrigin<I>>>,
 }
 
 pub struct InferenceSnapshot<I: Interner> {
-    unify_snapshot: ena::unify::Snapshot<ena::unify::InPlace<EnaVariable<I>>>,
+    unify_snapshot: Snapshot<EnaVariable<I>>,
     max_universe: UniverseIndex,
     vars: Vec<EnaVariable<I>>,
+    diverging: Vec<EnaVariable<I>>,
+    /// The number of variables, so the origins of the variables created
+    /// after the snapshot can be dropped on rollback.
+    num_vars: usize,
+}
+
+/// What created an inference variable. Diagnostics can use this to
+/// say e.g. "cannot infer type for the parameter `T` introduced by this
+/// impl".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariableOrigin<I: Interner> {
+    /// The `index`th parameter of `impl_id`, instantiated while matching
+    /// the impl.
+    ImplParameter { impl_id: ImplId<I>, index: usize },
+    /// The `index`th variable bound by the `exists` goal `goal`.
+    Existential { goal: Goal<I>, index: usize },
 }
 
 #[allow(type_alias_bounds)]
@@ -33,9 +74,11 @@ impl<I: Interner> InferenceTable<I> {
     /// Create an empty inference table with no variables.
     pub fn new() -> Self {
         InferenceTable {
-            unify: ena::unify::UnificationTable::new(),
+            unify: UnificationTable::new(),
             vars: vec![],
             max_universe: UniverseIndex::root(),
+            diverging: vec![],
+            origins: None,
         }
     }
 
@@ -89,6 +132,88 @@ impl<I: Interner> InferenceTable<I> {
         var
     }
 
+    /// Makes the table record the origins of the variables created from
+    /// now on (see `variable_origin`). This is off by default, as only
+    /// diagnostics need it.
+    pub fn record_variable_origins(&mut self) {
+        self.origins.get_or_insert_with(FxHashMap::default);
+    }
+
+    /// True if `record_variable_origins` was called.
+    pub fn records_variable_origins(&self) -> bool {
+        self.origins.is_some()
+    }
+
+    /// Creates a new inference variable, like `new_variable`, and
+    /// records `origin` as what created it if the table records
+    /// origins (see `variable_origin`).
+    pub fn new_variable_with_origin(
+        &mut self,
+        ui: UniverseIndex,
+        origin: VariableOrigin<I>,
+    ) -> EnaVariable<I> {
+        let var = self.new_variable(ui);
+        if let Some(origins) = &mut self.origins {
+            debug!(?var, ?origin, "recorded variable origin");
+            origins.insert(var, origin);
+        }
+        var
+    }
+
+    /// Returns what created `var`, if that was recorded. This is the
+    /// origin of `var` itself, not of the variables it has been
+    /// unified with.
+    pub fn variable_origin(&self, var: InferenceVar) -> Option<&VariableOrigin<I>> {
+        self.origins.as_ref()?.get(&EnaVariable::from(var))
+    }
+
+    /// Creates a new type inference variable for the type of an
+    /// expression that never returns (e.g. `return` or `panic!()`).
+    /// Such variables are *diverging*: if they remain unconstrained,
+    /// they are candidates for fallback via `fallback_unresolved_to`.
+    pub fn new_diverging_variable(&mut self, ui: UniverseIndex) -> EnaVariable<I> {
+        let var = self.new_variable(ui);
+        self.diverging.push(var);
+        debug!(?var, "marked variable as diverging");
+        var
+    }
+
+    /// Returns true if `var` has been unified with a diverging
+    /// variable (see `new_diverging_variable`).
+    pub fn is_diverging(&mut self, var: InferenceVar) -> bool {
+        let var = EnaVariable::from(var);
+        for i in 0..self.diverging.len() {
+            if self.unify.unioned(self.diverging[i], var) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Binds every diverging variable whose value is still unknown to
+    /// `ty`, returning the variables which were affected. This models
+    /// the "never type fallback" that happens at the end of type
+    /// checking, e.g. choosing between `!` and `()`; hosts will
+    /// typically solve their pending goals again afterwards to see how
+    /// the choice affects trait selection.
+    ///
+    /// `ty` must be nameable from every universe, so it should not
+    /// reference placeholders or inference variables.
+    pub fn fallback_unresolved_to(&mut self, interner: I, ty: Ty<I>) -> Vec<InferenceVar> {
+        let mut fallen_back = vec![];
+        for i in 0..self.diverging.len() {
+            let var = self.diverging[i];
+            if let InferenceValue::Unbound(_) = self.unify.probe_value(var) {
+                debug!(?var, ?ty, "falling back");
+                self.unify
+                    .unify_var_value(var, InferenceValue::from_ty(interner, ty.clone()))
+                    .unwrap();
+                fallen_back.push(var.into());
+            }
+        }
+        fallen_back
+    }
+
     /// Takes a "snapshot" of the current state of the inference
     /// table.  Later, you must invoke either `rollback_to` or
     /// `commit` with that snapshot.  Snapshots can be nested, but you
@@ -99,10 +224,14 @@ impl<I: Interner> InferenceTable<I> {
         let unify_snapshot = self.unify.snapshot();
         let vars = self.vars.clone();
         let max_universe = self.max_universe;
+        let diverging = self.diverging.clone();
+        let num_vars = self.unify.len();
         InferenceSnapshot {
             unify_snapshot,
             max_universe,
             vars,
+            diverging,
+            num_vars,
         }
     }
 
@@ -111,6 +240,11 @@ impl<I: Interner> InferenceTable<I> {
         self.unify.rollback_to(snapshot.unify_snapshot);
         self.vars = snapshot.vars;
         self.max_universe = snapshot.max_universe;
+        self.diverging = snapshot.diverging;
+        if let Some(origins) = &mut self.origins {
+            let num_vars = snapshot.num_vars;
+            origins.retain(|var, _| (var.index() as usize) < num_vars);
+        }
     }
 
     /// Make permanent the changes made since the snapshot was taken.
@@ -1,9 +1,11 @@
 use crate::debug_span;
+use alloc::vec::Vec;
+use chalk_derive::FallibleFolder;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::*;
-use std::cmp::max;
+use core::cmp::max;
 use tracing::{debug, instrument};
 
 use super::{InferenceTable, ParameterEnaVariable};
@@ -39,7 +41,7 @@ impl<I: Interner> InferenceTable<I> {
             max_universe: UniverseIndex::root(),
             interner,
         };
-        let value = value.fold_with(&mut q, DebruijnIndex::INNERMOST).unwrap();
+        let value = value.fold_with(&mut q, DebruijnIndex::INNERMOST);
         let free_vars = q.free_vars.clone();
 
         Canonicalized {
@@ -61,6 +63,7 @@ pub struct Canonicalized<T: HasInterner> {
     pub free_vars: Vec<ParameterEnaVariable<T::Interner>>,
 }
 
+#[derive(FallibleFolder)]
 struct Canonicalizer<'q, I: Interner> {
     table: &'q mut InferenceTable<I>,
     free_vars: Vec<ParameterEnaVariable<I>>,
@@ -102,30 +105,40 @@ impl<'q, I: Interner> Canonicalizer<'q, I> {
 }
 
 impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
+    /// Types without inference variables or placeholders are left untouched
+    /// (bound variables are kept as well, so that free ones are still caught).
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_type_flags(
+            self.interner,
+            TypeFlags::HAS_INFER | TypeFlags::HAS_PLACEHOLDER | TypeFlags::HAS_BOUND_VARS,
+        ) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
     fn fold_free_placeholder_ty(
         &mut self,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    ) -> Ty<I> {
         let interner = self.interner;
         self.max_universe = max(self.max_universe, universe.ui);
-        Ok(universe.to_ty(interner))
+        universe.to_ty(interner)
     }
 
     fn fold_free_placeholder_lifetime(
         &mut self,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         let interner = self.interner;
         self.max_universe = max(self.max_universe, universe.ui);
-        Ok(universe.to_lifetime(interner))
+        universe.to_lifetime(interner)
     }
 
     fn fold_free_placeholder_const(
@@ -133,10 +146,10 @@ impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
         ty: Ty<I>,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         let interner = self.interner;
         self.max_universe = max(self.max_universe, universe.ui);
-        Ok(universe.to_const(interner, ty))
+        universe.to_const(interner, ty)
     }
 
     fn forbid_free_vars(&self) -> bool {
@@ -149,16 +162,15 @@ impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
         var: InferenceVar,
         kind: TyVariableKind,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    ) -> Ty<I> {
         let interner = self.interner;
         match self.table.probe_var(var) {
             Some(ty) => {
                 let ty = ty.assert_ty_ref(interner);
                 debug!("bound to {:?}", ty);
-                Ok(ty
-                    .clone()
-                    .fold_with(self, DebruijnIndex::INNERMOST)?
-                    .shifted_in_from(interner, outer_binder))
+                ty.clone()
+                    .fold_with(self, DebruijnIndex::INNERMOST)
+                    .shifted_in_from(interner, outer_binder)
             }
             None => {
                 // If this variable is not yet bound, find its
@@ -170,7 +182,7 @@ impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
 
                 let bound_var = BoundVar::new(DebruijnIndex::INNERMOST, self.add(free_var));
                 debug!(position=?bound_var, "not yet unified");
-                Ok(TyKind::BoundVar(bound_var.shifted_in_from(outer_binder)).intern(interner))
+                TyKind::BoundVar(bound_var.shifted_in_from(outer_binder)).intern(interner)
             }
         }
     }
@@ -180,25 +192,22 @@ impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
         &mut self,
         var: InferenceVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         let interner = self.interner;
         match self.table.probe_var(var) {
             Some(l) => {
                 let l = l.assert_lifetime_ref(interner);
                 debug!("bound to {:?}", l);
-                Ok(l.clone()
-                    .fold_with(self, DebruijnIndex::INNERMOST)?
-                    .shifted_in_from(interner, outer_binder))
+                l.clone()
+                    .fold_with(self, DebruijnIndex::INNERMOST)
+                    .shifted_in_from(interner, outer_binder)
             }
             None => {
                 let free_var =
                     ParameterEnaVariable::new(VariableKind::Lifetime, self.table.unify.find(var));
                 let bound_var = BoundVar::new(DebruijnIndex::INNERMOST, self.add(free_var));
                 debug!(position=?bound_var, "not yet unified");
-                Ok(
-                    LifetimeData::BoundVar(bound_var.shifted_in_from(outer_binder))
-                        .intern(interner),
-                )
+                LifetimeData::BoundVar(bound_var.shifted_in_from(outer_binder)).intern(interner)
             }
         }
     }
@@ -209,15 +218,15 @@ impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
         ty: Ty<I>,
         var: InferenceVar,
         outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
+    ) -> Const<I> {
         let interner = self.interner;
         match self.table.probe_var(var) {
             Some(c) => {
                 let c = c.assert_const_ref(interner);
                 debug!("bound to {:?}", c);
-                Ok(c.clone()
-                    .fold_with(self, DebruijnIndex::INNERMOST)?
-                    .shifted_in_from(interner, outer_binder))
+                c.clone()
+                    .fold_with(self, DebruijnIndex::INNERMOST)
+                    .shifted_in_from(interner, outer_binder)
             }
             None => {
                 let free_var = ParameterEnaVariable::new(
@@ -226,18 +235,14 @@ impl<'i, I: Interner> Folder<I> for Canonicalizer<'i, I> {
                 );
                 let bound_var = BoundVar::new(DebruijnIndex::INNERMOST, self.add(free_var));
                 debug!(position = ?bound_var, "not yet unified");
-                Ok(bound_var
+                bound_var
                     .shifted_in_from(outer_binder)
-                    .to_const(interner, ty))
+                    .to_const(interner, ty)
             }
         }
     }
 
-    fn fold_lifetime(
-        &mut self,
-        lifetime: Lifetime<I>,
-        outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    fn fold_lifetime(&mut self, lifetime: Lifetime<I>, outer_binder: DebruijnIndex) -> Lifetime<I> {
         match *lifetime.data(self.interner) {
             LifetimeData::Empty(ui) if ui.counter != 0 => {
                 // ReEmpty in non-root universes is only used by lexical region
@@ -0,0 +1,88 @@
+//! Defaulting of unresolved integer and float inference variables
+//! (`{integer}` and `{float}`), as rustc does at the end of type checking.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::{SuperVisit, Visit, Visitor};
+use chalk_ir::*;
+use core::ops::ControlFlow;
+use tracing::debug;
+
+use super::var::{EnaVariable, InferenceValue};
+use super::InferenceTable;
+
+impl<I: Interner> InferenceTable<I> {
+    /// Finds every integer and float inference variable in `value`
+    /// whose value is still unknown, and binds it to its default type
+    /// (`i32` and `f64` respectively). Returns the variables which were
+    /// affected.
+    pub fn default_numeric_vars<T>(&mut self, interner: I, value: &T) -> Vec<InferenceVar>
+    where
+        T: Visit<I>,
+    {
+        let mut collector = NumericVarCollector {
+            table: self,
+            interner,
+            vars: vec![],
+        };
+        let _ = value.visit_with(&mut collector, DebruijnIndex::INNERMOST);
+        let vars = collector.vars;
+
+        for &(var, kind) in &vars {
+            let ty = match kind {
+                TyVariableKind::Integer => TyKind::Scalar(Scalar::Int(IntTy::I32)),
+                TyVariableKind::Float => TyKind::Scalar(Scalar::Float(FloatTy::F64)),
+                TyVariableKind::General => unreachable!(),
+            }
+            .intern(interner);
+            debug!(?var, ?ty, "defaulting numeric variable");
+            self.unify
+                .unify_var_value(
+                    EnaVariable::from(var),
+                    InferenceValue::from_ty(interner, ty),
+                )
+                .unwrap();
+        }
+
+        vars.into_iter().map(|(var, _)| var).collect()
+    }
+}
+
+/// Collects the (root) unbound integer and float variables in a value.
+struct NumericVarCollector<'t, I: Interner> {
+    table: &'t mut InferenceTable<I>,
+    interner: I,
+    vars: Vec<(InferenceVar, TyVariableKind)>,
+}
+
+impl<I: Interner> Visitor<I> for NumericVarCollector<'_, I> {
+    type BreakTy = ();
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> ControlFlow<()> {
+        let interner = self.interner;
+        match ty.kind(interner) {
+            TyKind::InferenceVar(var, kind) => {
+                if let Some(value) = self.table.probe_var(*var) {
+                    return value.assert_ty_ref(interner).visit_with(self, outer_binder);
+                }
+                if *kind != TyVariableKind::General {
+                    let root = self.table.inference_var_root(*var);
+                    if !self.vars.iter().any(|&(v, _)| v == root) {
+                        self.vars.push((root, *kind));
+                    }
+                }
+                ControlFlow::Continue(())
+            }
+            _ => ty.super_visit_with(self.as_dyn(), outer_binder),
+        }
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
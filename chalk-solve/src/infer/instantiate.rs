@@ -1,9 +1,13 @@
+use alloc::vec::Vec;
+use chalk_derive::FallibleFolder;
 use chalk_ir::fold::*;
 use chalk_ir::interner::HasInterner;
-use std::fmt::Debug;
+use core::fmt::Debug;
 use tracing::instrument;
 
 use super::*;
+use crate::clauses::impl_of_clause;
+use crate::{FxHashMap, RustIrDatabase};
 
 impl<I: Interner> InferenceTable<I> {
     /// Given the binders from a canonicalized value C, returns a
@@ -77,6 +81,93 @@ impl<I: Interner> InferenceTable<I> {
         )
     }
 
+    /// Variant on `instantiate_binders_existentially` that records
+    /// `origin(i)`, if any, as the origin of the variable created for the
+    /// `i`th binder (see `variable_origin`). `origin` is only called if
+    /// the table records origins.
+    pub fn instantiate_binders_existentially_with_origin<T>(
+        &mut self,
+        interner: I,
+        arg: Binders<T>,
+        origin: impl Fn(usize) -> Option<VariableOrigin<I>>,
+    ) -> T::Result
+    where
+        T: Fold<I> + HasInterner<Interner = I>,
+    {
+        if !self.records_variable_origins() {
+            return self.instantiate_binders_existentially(interner, arg);
+        }
+
+        let (value, binders) = arg.into_value_and_skipped_binders();
+
+        let max_universe = self.max_universe;
+        let subst = Substitution::from_iter(
+            interner,
+            binders.iter(interner).enumerate().map(|(index, kind)| {
+                let var = match origin(index) {
+                    Some(origin) => self.new_variable_with_origin(max_universe, origin),
+                    None => self.new_variable(max_universe),
+                };
+                WithKind::new(kind.clone(), var).to_generic_arg(interner)
+            }),
+        );
+        subst.apply(value, interner)
+    }
+
+    /// Instantiates the binders of `clause` with fresh existential
+    /// variables. If the table records origins and `clause` is the rule
+    /// of an impl, the variables of the impl's parameters are recorded as
+    /// coming from the impl.
+    pub fn instantiate_clause_existentially(
+        &mut self,
+        db: &dyn RustIrDatabase<I>,
+        clause: &ProgramClause<I>,
+    ) -> ProgramClauseImplication<I> {
+        let interner = db.interner();
+        let ProgramClauseData(implication) = clause.data(interner);
+        let impl_id = match self.records_variable_origins() {
+            true => impl_of_clause(db, clause),
+            false => None,
+        };
+        match impl_id {
+            Some(impl_id) => {
+                // The impl's parameters are the last binders of its rules
+                // (see `split_associated_ty_value_parameters`).
+                let offset =
+                    implication.len(interner) - db.impl_datum(impl_id).binders.len(interner);
+                self.instantiate_binders_existentially_with_origin(
+                    interner,
+                    implication.clone(),
+                    |index| {
+                        let index = index.checked_sub(offset)?;
+                        Some(VariableOrigin::ImplParameter { impl_id, index })
+                    },
+                )
+            }
+            None => self.instantiate_binders_existentially(interner, implication.clone()),
+        }
+    }
+
+    /// Instantiates the variables bound outside of `value` with fresh
+    /// existential variables, each once wherever it occurs, as if `value`
+    /// were under binders of its own. The clauses built from the body of a
+    /// quantified goal without binding the variables of the quantifier,
+    /// like `Vec<^1.0>: Clone` for `forall<T> { Vec<T>: Clone }`, have
+    /// them free.
+    pub fn instantiate_free_vars_existentially<T>(&mut self, interner: I, value: T) -> T::Result
+    where
+        T: Fold<I> + HasInterner<Interner = I>,
+    {
+        value.fold_with(
+            &mut FreeVarInstantiator {
+                table: self,
+                vars: FxHashMap::default(),
+                interner,
+            },
+            DebruijnIndex::INNERMOST,
+        )
+    }
+
     #[instrument(level = "debug", skip(self, interner))]
     pub fn instantiate_binders_universally<T>(&mut self, interner: I, arg: Binders<T>) -> T::Result
     where
@@ -113,3 +204,51 @@ impl<I: Interner> InferenceTable<I> {
         Subst::apply(interner, &parameters, value)
     }
 }
+
+#[derive(FallibleFolder)]
+struct FreeVarInstantiator<'t, I: Interner> {
+    table: &'t mut InferenceTable<I>,
+    vars: FxHashMap<BoundVar, EnaVariable<I>>,
+    interner: I,
+}
+
+impl<'t, I: Interner> FreeVarInstantiator<'t, I> {
+    fn var(&mut self, bound_var: BoundVar) -> EnaVariable<I> {
+        let table = &mut self.table;
+        *self
+            .vars
+            .entry(bound_var)
+            .or_insert_with(|| table.new_variable(table.max_universe))
+    }
+}
+
+impl<'t, I: Interner> Folder<I> for FreeVarInstantiator<'t, I> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
+        self
+    }
+
+    fn fold_free_var_ty(&mut self, bound_var: BoundVar, _outer_binder: DebruijnIndex) -> Ty<I> {
+        self.var(bound_var).to_ty(self.interner)
+    }
+
+    fn fold_free_var_lifetime(
+        &mut self,
+        bound_var: BoundVar,
+        _outer_binder: DebruijnIndex,
+    ) -> Lifetime<I> {
+        self.var(bound_var).to_lifetime(self.interner)
+    }
+
+    fn fold_free_var_const(
+        &mut self,
+        ty: Ty<I>,
+        bound_var: BoundVar,
+        _outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        self.var(bound_var).to_const(self.interner, ty)
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+}
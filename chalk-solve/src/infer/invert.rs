@@ -1,9 +1,10 @@
+use crate::FxHashMap;
+use chalk_derive::FallibleFolder;
 use chalk_ir::fold::shift::Shift;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::HasInterner;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use rustc_hash::FxHashMap;
 
 use super::canonicalize::Canonicalized;
 use super::{EnaVariable, InferenceTable};
@@ -90,8 +91,7 @@ impl<I: Interner> InferenceTable<I> {
         assert!(quantified.binders.is_empty(interner));
         let inverted = quantified
             .value
-            .fold_with(&mut Inverter::new(interner, self), DebruijnIndex::INNERMOST)
-            .unwrap();
+            .fold_with(&mut Inverter::new(interner, self), DebruijnIndex::INNERMOST);
         Some(inverted)
     }
 
@@ -113,6 +113,7 @@ impl<I: Interner> InferenceTable<I> {
     }
 }
 
+#[derive(FallibleFolder)]
 struct Inverter<'q, I: Interner> {
     table: &'q mut InferenceTable<I>,
     inverted_ty: FxHashMap<PlaceholderIndex, EnaVariable<I>>,
@@ -132,38 +133,44 @@ impl<'q, I: Interner> Inverter<'q, I> {
 }
 
 impl<'i, I: Interner> Folder<I> for Inverter<'i, I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_type_flags(
+            self.interner,
+            TypeFlags::HAS_PLACEHOLDER | TypeFlags::HAS_INFER | TypeFlags::HAS_BOUND_VARS,
+        ) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
     fn fold_free_placeholder_ty(
         &mut self,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
+    ) -> Ty<I> {
         let table = &mut self.table;
-        Ok(self
-            .inverted_ty
+        self.inverted_ty
             .entry(universe)
             .or_insert_with(|| table.new_variable(universe.ui))
             .to_ty(self.interner())
-            .shifted_in(self.interner()))
+            .shifted_in(self.interner())
     }
 
     fn fold_free_placeholder_lifetime(
         &mut self,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
+    ) -> Lifetime<I> {
         let table = &mut self.table;
-        Ok(self
-            .inverted_lifetime
+        self.inverted_lifetime
             .entry(universe)
             .or_insert_with(|| table.new_variable(universe.ui))
             .to_lifetime(self.interner())
-            .shifted_in(self.interner()))
+            .shifted_in(self.interner())
     }
 
     fn forbid_free_vars(&self) -> bool {
@@ -0,0 +1,52 @@
+use super::var::EnaVariable;
+use super::InferenceTable;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use tracing::debug;
+
+impl<I: Interner> InferenceTable<I> {
+    /// The "leak check" (as in rustc): given the goals produced by relating
+    /// a goal with a candidate (e.g., the consequence of a program clause),
+    /// fails if they require a placeholder lifetime to be related to another
+    /// placeholder, or to an inference variable which cannot name it (i.e.,
+    /// which was created in an outer universe).
+    ///
+    /// Without the leak check, such candidates are accepted with region
+    /// constraints which can never be satisfied; with it, they are rejected
+    /// during candidate selection, as rustc does for higher-ranked types.
+    pub fn leak_check(&mut self, interner: I, goals: &[InEnvironment<Goal<I>>]) -> Fallible<()> {
+        for goal in goals {
+            if let GoalData::DomainGoal(DomainGoal::Holds(WhereClause::LifetimeOutlives(
+                LifetimeOutlives { a, b },
+            ))) = goal.goal.data(interner)
+            {
+                if self.leaks(interner, a, b) || self.leaks(interner, b, a) {
+                    debug!("placeholder leaked in {:?}: {:?}", a, b);
+                    return Err(NoSolution);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether relating `a` to `b` leaks `a`, if it is a placeholder.
+    fn leaks(&mut self, interner: I, a: &Lifetime<I>, b: &Lifetime<I>) -> bool {
+        let a = self
+            .normalize_lifetime_shallow(interner, a)
+            .unwrap_or_else(|| a.clone());
+        let b = self
+            .normalize_lifetime_shallow(interner, b)
+            .unwrap_or_else(|| b.clone());
+        let placeholder = match a.data(interner) {
+            LifetimeData::Placeholder(placeholder) => *placeholder,
+            _ => return false,
+        };
+        match b.data(interner) {
+            LifetimeData::Placeholder(other) => *other != placeholder,
+            LifetimeData::InferenceVar(var) => !self
+                .universe_of_unbound_var(EnaVariable::from(*var))
+                .can_see(placeholder.ui),
+            _ => false,
+        }
+    }
+}
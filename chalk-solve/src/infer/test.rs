@@ -1,7 +1,9 @@
 #![cfg(test)]
 
+use super::ucanonicalize::UniverseMapExt;
 use super::unify::RelationResult;
 use super::*;
+use alloc::format;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::{arg, lifetime, ty};
 
@@ -69,6 +71,46 @@ fn cycle_error() {
         .unwrap_err();
 }
 
+#[test]
+fn cycle_error_explained() {
+    // exists(A -> A = foo A) ---> occurs check error
+    let interner = ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+    let foo_a = ty!(apply (item 0) (expr a));
+    let err = table
+        .relate_explained(
+            interner,
+            &TestDatabase,
+            &environment0,
+            Variance::Invariant,
+            &a,
+            &foo_a,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        UnificationError::OccursCheck {
+            var: a.inference_var(interner).unwrap(),
+            ty: foo_a,
+        }
+    );
+
+    // exists(A -> forall(X -> A = X)) ---> not a cycle
+    let err = table
+        .relate_explained(
+            interner,
+            &TestDatabase,
+            &environment0,
+            Variance::Invariant,
+            &a,
+            &ty!(placeholder 1),
+        )
+        .unwrap_err();
+    assert_eq!(err, UnificationError::NoSolution);
+}
+
 #[test]
 fn cycle_indirect() {
     // exists(A -> A = foo B, A = B) ---> error
@@ -420,3 +462,232 @@ fn lifetime_constraint_indirect() {
         "InEnvironment { environment: Env([]), goal: \'!1_0: \'?2 }",
     );
 }
+
+#[test]
+fn diverging_fallback() {
+    // exists(A, B, C -> A = B) with A diverging; falling back to `!`
+    // binds both A and B, but leaves C alone.
+    let interner = ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_diverging_variable(U0).to_ty(interner);
+    let b = table.new_variable(U0).to_ty(interner);
+    let c = table.new_variable(U0).to_ty(interner);
+    table
+        .relate(
+            interner,
+            &TestDatabase,
+            &environment0,
+            Variance::Invariant,
+            &a,
+            &b,
+        )
+        .unwrap();
+    assert!(table.is_diverging(b.inference_var(interner).unwrap()));
+    assert!(!table.is_diverging(c.inference_var(interner).unwrap()));
+
+    let never = TyKind::Never.intern(interner);
+    let fallen_back = table.fallback_unresolved_to(interner, never.clone());
+    assert_eq!(fallen_back, vec![a.inference_var(interner).unwrap()]);
+    assert_eq!(table.normalize_ty_shallow(interner, &b), Some(never));
+    assert_eq!(table.normalize_ty_shallow(interner, &c), None);
+
+    // Nothing left to fall back.
+    let unit = TyKind::Tuple(0, Substitution::empty(interner)).intern(interner);
+    assert!(table.fallback_unresolved_to(interner, unit).is_empty());
+}
+
+#[test]
+fn variable_origins() {
+    // Instantiating `exists<A, B> { A = B }` records, for each variable,
+    // which binder of the goal it came from, once the table records
+    // origins; rolling back forgets them.
+    let interner = ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let goal = GoalData::Quantified(
+        QuantifierKind::Exists,
+        Binders::new(
+            VariableKinds::from_iter(interner, vec![VariableKind::Ty(TyVariableKind::General); 2]),
+            GoalData::EqGoal(EqGoal {
+                a: ty!(bound 0).cast(interner),
+                b: ty!(bound 1).cast(interner),
+            })
+            .intern(interner),
+        ),
+    )
+    .intern(interner);
+    let binders = match goal.data(interner) {
+        GoalData::Quantified(_, binders) => binders.clone(),
+        _ => unreachable!(),
+    };
+    let origin = |index| {
+        Some(VariableOrigin::Existential {
+            goal: goal.clone(),
+            index,
+        })
+    };
+
+    let unrecorded =
+        table.instantiate_binders_existentially_with_origin(interner, binders.clone(), origin);
+    let unrecorded = match unrecorded.data(interner) {
+        GoalData::EqGoal(EqGoal { a, .. }) => a.ty(interner).unwrap().inference_var(interner),
+        _ => unreachable!(),
+    };
+    assert_eq!(table.variable_origin(unrecorded.unwrap()), None);
+
+    table.record_variable_origins();
+    let plain = table.new_variable(U0).to_ty(interner);
+    let snapshot = table.snapshot();
+    let subgoal = table.instantiate_binders_existentially_with_origin(interner, binders, origin);
+    let (a, b) = match subgoal.data(interner) {
+        GoalData::EqGoal(EqGoal { a, b }) => (
+            a.ty(interner).unwrap().inference_var(interner).unwrap(),
+            b.ty(interner).unwrap().inference_var(interner).unwrap(),
+        ),
+        _ => unreachable!(),
+    };
+    assert_eq!(
+        table.variable_origin(a),
+        Some(&VariableOrigin::Existential {
+            goal: goal.clone(),
+            index: 0
+        })
+    );
+    assert_eq!(
+        table.variable_origin(b),
+        Some(&VariableOrigin::Existential {
+            goal: goal.clone(),
+            index: 1
+        })
+    );
+    assert_eq!(
+        table.variable_origin(plain.inference_var(interner).unwrap()),
+        None
+    );
+
+    table.rollback_to(snapshot);
+    assert_eq!(table.variable_origin(a), None);
+}
+
+#[test]
+fn numeric_defaulting() {
+    // exists(A, B: {integer}, C: {float} -> A = B); defaulting `foo<A, C>`
+    // makes B (and hence A) `i32` and C `f64`.
+    let interner = ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+    let b = table
+        .new_variable(U0)
+        .to_ty_with_kind(interner, TyVariableKind::Integer);
+    let c = table
+        .new_variable(U0)
+        .to_ty_with_kind(interner, TyVariableKind::Float);
+    table
+        .relate(
+            interner,
+            &TestDatabase,
+            &environment0,
+            Variance::Invariant,
+            &a,
+            &b,
+        )
+        .unwrap();
+
+    let defaulted = table.default_numeric_vars(interner, &ty!(apply (item 0) (expr a) (expr c)));
+    assert_eq!(
+        defaulted,
+        vec![
+            b.inference_var(interner).unwrap(),
+            c.inference_var(interner).unwrap()
+        ]
+    );
+    assert_eq!(
+        table.normalize_ty_shallow(interner, &a),
+        Some(TyKind::Scalar(Scalar::Int(IntTy::I32)).intern(interner))
+    );
+    assert_eq!(
+        table.normalize_ty_shallow(interner, &c),
+        Some(TyKind::Scalar(Scalar::Float(FloatTy::F64)).intern(interner))
+    );
+}
+
+#[test]
+fn mismatch_diff() {
+    // foo<bar<A>, B> = foo<bar<baz>, B> fails, and the diff points at
+    // the first generic argument of `bar`.
+    let interner = ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = ty!(placeholder 0);
+    let b = table.new_variable(U0).to_ty(interner);
+    let expected = ty!(apply (item 0) (apply (item 1) (expr a)) (expr b));
+    let found = ty!(apply (item 0) (apply (item 1) (apply (item 2))) (expr b));
+    table
+        .relate(
+            interner,
+            &TestDatabase,
+            &environment0,
+            Variance::Invariant,
+            &expected,
+            &found,
+        )
+        .unwrap_err();
+
+    let diff = chalk_ir::diff::diff_tys(interner, &expected, &found).unwrap();
+    assert_eq!(diff.path, vec![0, 0]);
+    assert_eq!(diff.expected, a.cast(interner));
+    assert_eq!(diff.found, ty!(apply (item 2)).cast(interner));
+
+    assert_eq!(
+        chalk_ir::diff::diff_tys(interner, &expected, &expected),
+        None
+    );
+}
+
+/// `foo<!a_x, !b_y, ^0.0>`, canonical over one variable in universe `var`.
+fn placeholder_pair(
+    a: (usize, usize),
+    b: (usize, usize),
+    var: UniverseIndex,
+) -> Canonical<Ty<ChalkIr>> {
+    let interner = ChalkIr;
+    let placeholder = |(ui, idx)| {
+        PlaceholderIndex {
+            ui: UniverseIndex { counter: ui },
+            idx,
+        }
+        .to_ty(interner)
+    };
+    let (a, b) = (placeholder(a), placeholder(b));
+    Canonical {
+        value: ty!(apply (item 0) (expr a) (expr b) (bound 0)),
+        binders: CanonicalVarKinds::from1(
+            interner,
+            CanonicalVarKind::new(VariableKind::Ty(TyVariableKind::General), var),
+        ),
+    }
+}
+
+#[test]
+fn u_canonicalize_merges_universes() {
+    let interner = ChalkIr;
+    // No variable can tell `U1` and `U2` apart, so they are merged.
+    let spread = InferenceTable::u_canonicalize(interner, &placeholder_pair((1, 0), (2, 0), U2));
+    let merged = InferenceTable::u_canonicalize(interner, &placeholder_pair((1, 0), (1, 1), U1));
+    assert_eq!(spread.quantified, merged.quantified);
+    assert_eq!(spread.quantified.universes(), 2);
+
+    // The placeholders and universes are mapped back to the original ones.
+    let canonical = spread.quantified.canonical().clone();
+    assert_eq!(
+        spread.universes.map_from_canonical(interner, &canonical),
+        placeholder_pair((1, 0), (2, 0), U2)
+    );
+
+    // A variable in `U1` cannot name `!2_0`, so `U1` and `U2` must remain
+    // distinct.
+    let distinct = InferenceTable::u_canonicalize(interner, &placeholder_pair((1, 0), (2, 0), U1));
+    assert_eq!(distinct.quantified.universes(), 3);
+    assert_ne!(distinct.quantified, merged.quantified);
+}
@@ -1,9 +1,14 @@
 use crate::debug_span;
-use chalk_ir::fold::{Fold, Folder};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_derive::FallibleFolder;
+use chalk_ir::fold::{Fold, Folder, SuperFold};
 use chalk_ir::interner::{HasInterner, Interner};
-use chalk_ir::visit::{Visit, Visitor};
+use chalk_ir::visit::{SuperVisit, Visit, Visitor};
 use chalk_ir::*;
-use std::ops::ControlFlow;
+use core::hash::Hash;
+use core::ops::ControlFlow;
 
 use super::InferenceTable;
 
@@ -11,39 +16,38 @@ impl<I: Interner> InferenceTable<I> {
     pub fn u_canonicalize<T>(interner: I, value0: &Canonical<T>) -> UCanonicalized<T::Result>
     where
         T: Clone + HasInterner<Interner = I> + Fold<I> + Visit<I>,
-        T::Result: HasInterner<Interner = I>,
+        T::Result: HasInterner<Interner = I> + Hash,
     {
         debug_span!("u_canonicalize", "{:#?}", value0);
 
-        // First, find all the universes that appear in `value`.
-        let mut universes = UniverseMap::new();
+        // First, find all the universes that appear in `value`, and how.
+        let mut usages = BTreeMap::new();
+        usages.insert(UniverseIndex::root(), UniverseUsage::default());
 
         for universe in value0.binders.iter(interner) {
-            universes.add(*universe.skip_kind());
+            usages.entry(*universe.skip_kind()).or_default().has_vars = true;
         }
 
         value0.value.visit_with(
             &mut UCollector {
-                universes: &mut universes,
+                usages: &mut usages,
                 interner,
             },
             DebruijnIndex::INNERMOST,
         );
 
+        let universes = merge_universes(&usages);
+
         // Now re-map the universes found in value. We have to do this
         // in a second pass because it is only then that we know the
         // full set of universes found in the original value.
-        let value1 = value0
-            .value
-            .clone()
-            .fold_with(
-                &mut UMapToCanonical {
-                    universes: &universes,
-                    interner,
-                },
-                DebruijnIndex::INNERMOST,
-            )
-            .unwrap();
+        let value1 = value0.value.clone().fold_with(
+            &mut UMapToCanonical {
+                universes: &universes,
+                interner,
+            },
+            DebruijnIndex::INNERMOST,
+        );
         let binders = CanonicalVarKinds::from_iter(
             interner,
             value0
@@ -53,13 +57,13 @@ impl<I: Interner> InferenceTable<I> {
         );
 
         UCanonicalized {
-            quantified: UCanonical {
-                universes: universes.num_canonical_universes(),
-                canonical: Canonical {
+            quantified: UCanonical::new(
+                Canonical {
                     value: value1,
                     binders,
                 },
-            },
+                universes.num_canonical_universes(),
+            ),
             universes,
         }
     }
@@ -74,10 +78,62 @@ pub struct UCanonicalized<T: HasInterner> {
     pub universes: UniverseMap,
 }
 
+/// How a universe is used in a value being u-canonicalized.
+#[derive(Default)]
+struct UniverseUsage {
+    /// Whether some variable (i.e., canonical binder) is in the universe.
+    has_vars: bool,
+    /// One more than the largest index of the placeholders in the universe.
+    placeholders: usize,
+}
+
+/// Builds the map to the canonical universes, given the universes found
+/// in the original value. Each canonical universe is one of the original
+/// universes, together with the following ones, as long as no variable is
+/// in the universe before them: these are merged together, offsetting the
+/// indices of their placeholders so that they remain distinct.
+///
+/// Merging `Ub` into the preceding universe `Ua` in this way does not
+/// change the answers to the goal. Universes only matter in which
+/// placeholders variables can name: a variable in universe `Uv` can name
+/// the placeholders of the universes `Ux` with `x <= v`. As no variable is
+/// in `Ua`, and `Ua` and `Ub` are adjacent among the universes of the
+/// value, no variable is in a universe which can name the placeholders of
+/// `Ua` but not those of `Ub`. Variables therefore name the same
+/// placeholders before and after merging, and so the goal has the same
+/// answers (up to the renaming of placeholders, which `map_from_canonical`
+/// undoes). The variables created while solving the goal are in the last
+/// universe or in new ones after it, which are not affected either.
+///
+/// This makes goals which differ only in how their placeholders are spread
+/// over universes, like `Foo<!1_0, !2_0>` and `Foo<!1_0, !1_1>`, share the
+/// same u-canonical form, and thus the same table or cache entry. The
+/// root universe is never merged with others.
+fn merge_universes(usages: &BTreeMap<UniverseIndex, UniverseUsage>) -> UniverseMap {
+    let mut universes: Vec<Vec<(UniverseIndex, usize)>> = vec![];
+    let mut offset = 0;
+    let mut can_merge = false;
+    for (&universe, usage) in usages {
+        if can_merge {
+            universes.last_mut().unwrap().push((universe, offset));
+        } else {
+            universes.push(vec![(universe, 0)]);
+            offset = 0;
+        }
+        offset += usage.placeholders;
+        can_merge = !usage.has_vars && universe != UniverseIndex::root();
+    }
+    UniverseMap { universes }
+}
+
 pub trait UniverseMapExt {
-    fn add(&mut self, universe: UniverseIndex);
     fn map_universe_to_canonical(&self, universe: UniverseIndex) -> Option<UniverseIndex>;
+    fn map_placeholder_to_canonical(
+        &self,
+        placeholder: PlaceholderIndex,
+    ) -> Option<PlaceholderIndex>;
     fn map_universe_from_canonical(&self, universe: UniverseIndex) -> UniverseIndex;
+    fn map_placeholder_from_canonical(&self, placeholder: PlaceholderIndex) -> PlaceholderIndex;
     fn map_from_canonical<T, I>(
         &self,
         interner: I,
@@ -89,32 +145,49 @@ pub trait UniverseMapExt {
         I: Interner;
 }
 impl UniverseMapExt for UniverseMap {
-    fn add(&mut self, universe: UniverseIndex) {
-        if let Err(i) = self.universes.binary_search(&universe) {
-            self.universes.insert(i, universe);
-        }
-    }
-
     /// Given a universe U that appeared in our original value, return
-    /// the universe to use in the u-canonical value. This is done by
-    /// looking for the index I of U in `self.universes`. We will
-    /// return the universe with "counter" I. This effectively
+    /// the universe to use in the u-canonical value. This is the
+    /// universe with "counter" I, where I is the index of the universes
+    /// U was merged with in `self.universes`. This effectively
     /// "compresses" the range of universes to things from
     /// `0..self.universes.len()`. If the universe is not present in the map,
     /// we return `None`.
     fn map_universe_to_canonical(&self, universe: UniverseIndex) -> Option<UniverseIndex> {
-        self.universes
-            .binary_search(&universe)
-            .ok()
-            .map(|index| UniverseIndex { counter: index })
+        let index = self
+            .universes
+            .partition_point(|merged| merged[0].0 <= universe)
+            .checked_sub(1)?;
+        self.universes[index]
+            .iter()
+            .any(|&(ui, _)| ui == universe)
+            .then_some(UniverseIndex { counter: index })
+    }
+
+    /// Given a placeholder that appeared in our original value, return
+    /// the placeholder to use in the u-canonical value: its universe is
+    /// mapped with `map_universe_to_canonical`, and its index offset past
+    /// the placeholders of the universes merged before its own.
+    fn map_placeholder_to_canonical(
+        &self,
+        placeholder: PlaceholderIndex,
+    ) -> Option<PlaceholderIndex> {
+        let ui = self.map_universe_to_canonical(placeholder.ui)?;
+        let &(_, offset) = self.universes[ui.counter]
+            .iter()
+            .find(|&&(universe, _)| universe == placeholder.ui)?;
+        Some(PlaceholderIndex {
+            ui,
+            idx: placeholder.idx + offset,
+        })
     }
 
     /// Given a "canonical universe" -- one found in the
     /// `u_canonicalize` result -- returns the original universe that
-    /// it corresponded to.
+    /// it corresponded to (the last one, if several were merged: it can
+    /// name the placeholders of all of them).
     fn map_universe_from_canonical(&self, universe: UniverseIndex) -> UniverseIndex {
         if universe.counter < self.universes.len() {
-            self.universes[universe.counter]
+            self.universes[universe.counter].last().unwrap().0
         } else {
             // If this universe is out of bounds, we assume an
             // implicit `forall` binder, effectively, and map to a
@@ -122,7 +195,7 @@ impl UniverseMapExt for UniverseMap {
             // comments on `map_from_canonical` for a detailed
             // explanation.
             let difference = universe.counter - self.universes.len();
-            let max_counter = self.universes.last().unwrap().counter;
+            let max_counter = self.universes.last().unwrap().last().unwrap().0.counter;
             let new_counter = max_counter + difference + 1;
             UniverseIndex {
                 counter: new_counter,
@@ -130,6 +203,29 @@ impl UniverseMapExt for UniverseMap {
         }
     }
 
+    /// Given a placeholder found in the `u_canonicalize` result, returns
+    /// the original placeholder that it corresponded to, undoing the
+    /// offset of its index if its universe was merged with others.
+    fn map_placeholder_from_canonical(&self, placeholder: PlaceholderIndex) -> PlaceholderIndex {
+        match self.universes.get(placeholder.ui.counter) {
+            Some(merged) => {
+                let &(ui, offset) = merged
+                    .iter()
+                    .rev()
+                    .find(|&&(_, offset)| offset <= placeholder.idx)
+                    .unwrap();
+                PlaceholderIndex {
+                    ui,
+                    idx: placeholder.idx - offset,
+                }
+            }
+            None => PlaceholderIndex {
+                ui: self.map_universe_from_canonical(placeholder.ui),
+                idx: placeholder.idx,
+            },
+        }
+    }
+
     /// Returns a mapped version of `value` where the universes have
     /// been translated from canonical universes into the original
     /// universes.
@@ -174,17 +270,13 @@ impl UniverseMapExt for UniverseMap {
             .iter(interner)
             .map(|cvk| cvk.map_ref(|&universe| self.map_universe_from_canonical(universe)));
 
-        let value = canonical_value
-            .value
-            .clone()
-            .fold_with(
-                &mut UMapFromCanonical {
-                    interner,
-                    universes: self,
-                },
-                DebruijnIndex::INNERMOST,
-            )
-            .unwrap();
+        let value = canonical_value.value.clone().fold_with(
+            &mut UMapFromCanonical {
+                interner,
+                universes: self,
+            },
+            DebruijnIndex::INNERMOST,
+        );
 
         Canonical {
             binders: CanonicalVarKinds::from_iter(interner, binders),
@@ -194,9 +286,10 @@ impl UniverseMapExt for UniverseMap {
 }
 
 /// The `UCollector` is a "no-op" in terms of the value, but along the
-/// way it collects all universes that were found into a vector.
+/// way it collects all universes that were found, and the placeholders
+/// found in each.
 struct UCollector<'q, I> {
-    universes: &'q mut UniverseMap,
+    usages: &'q mut BTreeMap<UniverseIndex, UniverseUsage>,
     interner: I,
 }
 
@@ -207,12 +300,23 @@ impl<I: Interner> Visitor<I> for UCollector<'_, I> {
         self
     }
 
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> ControlFlow<()> {
+        if !ty.has_type_flags(
+            self.interner,
+            TypeFlags::HAS_PLACEHOLDER | TypeFlags::HAS_INFER,
+        ) {
+            return ControlFlow::Continue(());
+        }
+        ty.super_visit_with(self, outer_binder)
+    }
+
     fn visit_free_placeholder(
         &mut self,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
     ) -> ControlFlow<()> {
-        self.universes.add(universe.ui);
+        let usage = self.usages.entry(universe.ui).or_default();
+        usage.placeholders = usage.placeholders.max(universe.idx + 1);
         ControlFlow::Continue(())
     }
 
@@ -225,18 +329,27 @@ impl<I: Interner> Visitor<I> for UCollector<'_, I> {
     }
 }
 
-struct UMapToCanonical<'q, I> {
+#[derive(FallibleFolder)]
+struct UMapToCanonical<'q, I: Interner> {
     interner: I,
     universes: &'q UniverseMap,
 }
 
 impl<'i, I: Interner> Folder<I> for UMapToCanonical<'i, I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_type_flags(
+            self.interner,
+            TypeFlags::HAS_PLACEHOLDER | TypeFlags::HAS_INFER,
+        ) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
     fn forbid_inference_vars(&self) -> bool {
         true
     }
@@ -245,33 +358,22 @@ impl<'i, I: Interner> Folder<I> for UMapToCanonical<'i, I> {
         &mut self,
         universe0: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
-        let ui = self
-            .universes
-            .map_universe_to_canonical(universe0.ui)
-            .expect("Expected UCollector to encounter this universe");
-        Ok(PlaceholderIndex {
-            ui,
-            idx: universe0.idx,
-        }
-        .to_ty(self.interner()))
+    ) -> Ty<I> {
+        self.universes
+            .map_placeholder_to_canonical(universe0)
+            .expect("Expected UCollector to encounter this universe")
+            .to_ty(self.interner())
     }
 
     fn fold_free_placeholder_lifetime(
         &mut self,
         universe0: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
-        let universe = self
-            .universes
-            .map_universe_to_canonical(universe0.ui)
-            .expect("Expected UCollector to encounter this universe");
-
-        Ok(PlaceholderIndex {
-            ui: universe,
-            idx: universe0.idx,
-        }
-        .to_lifetime(self.interner()))
+    ) -> Lifetime<I> {
+        self.universes
+            .map_placeholder_to_canonical(universe0)
+            .expect("Expected UCollector to encounter this universe")
+            .to_lifetime(self.interner())
     }
 
     fn fold_free_placeholder_const(
@@ -279,17 +381,11 @@ impl<'i, I: Interner> Folder<I> for UMapToCanonical<'i, I> {
         ty: Ty<I>,
         universe0: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Const<I>> {
-        let universe = self
-            .universes
-            .map_universe_to_canonical(universe0.ui)
-            .expect("Expected UCollector to encounter this universe");
-
-        Ok(PlaceholderIndex {
-            ui: universe,
-            idx: universe0.idx,
-        }
-        .to_const(self.interner(), ty))
+    ) -> Const<I> {
+        self.universes
+            .map_placeholder_to_canonical(universe0)
+            .expect("Expected UCollector to encounter this universe")
+            .to_const(self.interner(), ty)
     }
 
     fn interner(&self) -> I {
@@ -297,42 +393,56 @@ impl<'i, I: Interner> Folder<I> for UMapToCanonical<'i, I> {
     }
 }
 
-struct UMapFromCanonical<'q, I> {
+#[derive(FallibleFolder)]
+struct UMapFromCanonical<'q, I: Interner> {
     interner: I,
     universes: &'q UniverseMap,
 }
 
 impl<'i, I: Interner> Folder<I> for UMapFromCanonical<'i, I> {
-    type Error = NoSolution;
-
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<I> {
         self
     }
 
+    fn fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Ty<I> {
+        if !ty.has_type_flags(
+            self.interner,
+            TypeFlags::HAS_PLACEHOLDER | TypeFlags::HAS_INFER,
+        ) {
+            return ty;
+        }
+        ty.super_fold_with(Folder::as_dyn(self), outer_binder)
+    }
+
     fn fold_free_placeholder_ty(
         &mut self,
         universe0: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Ty<I>> {
-        let ui = self.universes.map_universe_from_canonical(universe0.ui);
-        Ok(PlaceholderIndex {
-            ui,
-            idx: universe0.idx,
-        }
-        .to_ty(self.interner()))
+    ) -> Ty<I> {
+        self.universes
+            .map_placeholder_from_canonical(universe0)
+            .to_ty(self.interner())
     }
 
     fn fold_free_placeholder_lifetime(
         &mut self,
         universe0: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
-    ) -> Fallible<Lifetime<I>> {
-        let universe = self.universes.map_universe_from_canonical(universe0.ui);
-        Ok(PlaceholderIndex {
-            ui: universe,
-            idx: universe0.idx,
-        }
-        .to_lifetime(self.interner()))
+    ) -> Lifetime<I> {
+        self.universes
+            .map_placeholder_from_canonical(universe0)
+            .to_lifetime(self.interner())
+    }
+
+    fn fold_free_placeholder_const(
+        &mut self,
+        ty: Ty<I>,
+        universe0: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Const<I> {
+        self.universes
+            .map_placeholder_from_canonical(universe0)
+            .to_const(self.interner(), ty)
     }
 
     fn forbid_inference_vars(&self) -> bool {
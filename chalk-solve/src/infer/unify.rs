@@ -1,12 +1,15 @@
 use super::var::*;
 use super::*;
 use crate::debug_span;
+use crate::solve::NoSolutionCause;
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_ir::cast::Cast;
-use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::fold::{FallibleFolder, Fold, SuperFold};
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::zip::{Zip, Zipper};
 use chalk_ir::UnificationDatabase;
-use std::fmt::Debug;
+use core::fmt::Debug;
 use tracing::{debug, instrument};
 
 impl<I: Interner> InferenceTable<I> {
@@ -22,13 +25,80 @@ impl<I: Interner> InferenceTable<I> {
     where
         T: ?Sized + Zip<I>,
     {
+        self.relate_explained(interner, db, environment, variance, a, b)
+            .map_err(NoSolution::from)
+    }
+
+    /// Like `relate`, but on failure reports *why* `a` and `b` could
+    /// not be related (e.g., because doing so would create a cyclic
+    /// type), so that hosts can produce better diagnostics.
+    pub fn relate_explained<T>(
+        &mut self,
+        interner: I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &T,
+        b: &T,
+    ) -> Result<RelationResult<I>, UnificationError<I>>
+    where
+        T: ?Sized + Zip<I>,
+    {
+        self.relate_in_snapshot(
+            |table| Unifier::new(interner, db, table, environment).relate(variance, a, b),
+            |_, e| e,
+        )
+    }
+
+    /// Like `relate_explained`, but reports why `a` and `b` could not be
+    /// related as a `NoSolutionCause`, which is canonical, and so stays
+    /// meaningful outside of this table (unlike the variables of an
+    /// `UnificationError`, which may be rolled back).
+    pub fn relate_or_cause<T>(
+        &mut self,
+        interner: I,
+        db: &dyn UnificationDatabase<I>,
+        environment: &Environment<I>,
+        variance: Variance,
+        a: &T,
+        b: &T,
+    ) -> Result<RelationResult<I>, NoSolutionCause<I>>
+    where
+        T: ?Sized + Zip<I>,
+    {
+        let relate = |table: &mut Self| {
+            Unifier::new(interner, db, table, environment).relate(variance, a, b)
+        };
+        self.relate_in_snapshot(relate, |table, e| match e {
+            UnificationError::NoSolution => NoSolutionCause::Unknown,
+            UnificationError::OccursCheck { var, ty } => {
+                let var = var.to_ty(interner, TyVariableKind::General);
+                let goal = GoalData::EqGoal(EqGoal {
+                    a: var.cast(interner),
+                    b: ty.cast(interner),
+                })
+                .intern(interner);
+                NoSolutionCause::OccursCheck(table.canonicalize(interner, goal).quantified)
+            }
+        })
+    }
+
+    /// Runs `relate`, rolling back the table on failure. The error is
+    /// mapped by `on_error` before the rollback, while the variables it
+    /// mentions still exist.
+    fn relate_in_snapshot<E>(
+        &mut self,
+        relate: impl FnOnce(&mut Self) -> Result<RelationResult<I>, UnificationError<I>>,
+        on_error: impl FnOnce(&mut Self, UnificationError<I>) -> E,
+    ) -> Result<RelationResult<I>, E> {
         let snapshot = self.snapshot();
-        match Unifier::new(interner, db, self, environment).relate(variance, a, b) {
+        match relate(self) {
             Ok(r) => {
                 self.commit(snapshot);
                 Ok(r)
             }
             Err(e) => {
+                let e = on_error(self, e);
                 self.rollback_to(snapshot);
                 Err(e)
             }
@@ -42,6 +112,9 @@ struct Unifier<'t, I: Interner> {
     goals: Vec<InEnvironment<Goal<I>>>,
     interner: I,
     db: &'t dyn UnificationDatabase<I>,
+    /// The first occurs check failure encountered, if any, as the
+    /// variable and the type it was being unified with.
+    cycle: Option<(InferenceVar, Ty<I>)>,
 }
 
 #[derive(Debug)]
@@ -62,6 +135,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
             goals: vec![],
             interner,
             db,
+            cycle: None,
         }
     }
 
@@ -69,11 +143,21 @@ impl<'t, I: Interner> Unifier<'t, I> {
     /// only type meant to be called externally. Performs a
     /// relation of `a` and `b` and returns the Unification Result.
     #[instrument(level = "debug", skip(self))]
-    fn relate<T>(mut self, variance: Variance, a: &T, b: &T) -> Fallible<RelationResult<I>>
+    fn relate<T>(
+        mut self,
+        variance: Variance,
+        a: &T,
+        b: &T,
+    ) -> Result<RelationResult<I>, UnificationError<I>>
     where
         T: ?Sized + Zip<I>,
     {
-        Zip::zip_with(&mut self, variance, a, b)?;
+        if let Err(NoSolution) = Zip::zip_with(&mut self, variance, a, b) {
+            return Err(match self.cycle.take() {
+                Some((var, ty)) => UnificationError::OccursCheck { var, ty },
+                None => UnificationError::NoSolution,
+            });
+        }
         let interner = self.interner();
         let mut goals = self.goals;
         let table = self.table;
@@ -106,7 +190,9 @@ impl<'t, I: Interner> Unifier<'t, I> {
 
         debug_span!("relate_ty_ty", ?variance, ?a, ?b);
 
-        if a.kind(interner) == b.kind(interner) {
+        if interner.interned_eq_fast(a.interned(), b.interned())
+            || a.kind(interner) == b.kind(interner)
+        {
             return Ok(());
         }
 
@@ -154,7 +240,11 @@ impl<'t, I: Interner> Unifier<'t, I> {
 
             // Unifying `forall<X> { T }` with some other forall type `forall<X> { U }`
             (&TyKind::Function(ref fn1), &TyKind::Function(ref fn2)) => {
-                if fn1.sig == fn2.sig {
+                // Function pointers taking a different number of arguments
+                // are distinct types.
+                if fn1.sig == fn2.sig
+                    && fn1.substitution.0.len(interner) == fn2.substitution.0.len(interner)
+                {
                     Zip::zip_with(
                         self,
                         variance,
@@ -170,9 +260,10 @@ impl<'t, I: Interner> Unifier<'t, I> {
                 Zip::zip_with(self, variance, p1, p2)
             }
 
-            // Unifying two dyn is possible if they have the same bounds.
+            // Unifying two dyn is possible if they have the same bounds,
+            // in any order.
             (&TyKind::Dyn(ref qwc1), &TyKind::Dyn(ref qwc2)) => {
-                Zip::zip_with(self, variance, qwc1, qwc2)
+                self.equate_dyn(variance, qwc1, qwc2)
             }
 
             (TyKind::BoundVar(_), _) | (_, TyKind::BoundVar(_)) => panic!(
@@ -242,7 +333,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
                     variance,
                     Some(Variances::from_iter(
                         self.interner,
-                        std::iter::repeat(Variance::Covariant).take(*arity_a),
+                        core::iter::repeat(Variance::Covariant).take(*arity_a),
                     )),
                     substitution_a.as_slice(interner),
                     substitution_b.as_slice(interner),
@@ -349,6 +440,9 @@ impl<'t, I: Interner> Unifier<'t, I> {
             (TyKind::Foreign(id_a), TyKind::Foreign(id_b)) => {
                 Zip::zip_with(self, variance, id_a, id_b)
             }
+            (TyKind::Custom(custom_a), TyKind::Custom(custom_b)) => {
+                interner.zip_custom_tys(self, variance, custom_a, custom_b)
+            }
             (TyKind::Error, TyKind::Error) => Ok(()),
 
             (_, _) => Err(NoSolution),
@@ -489,6 +583,132 @@ impl<'t, I: Interner> Unifier<'t, I> {
         }
     }
 
+    /// Relates two `dyn` types whose bounds may be in different orders:
+    /// each bound of `a` is paired with a bound of `b` for the same trait
+    /// (or associated type, etc.), and the pairs are then related like the
+    /// bounds of two `dyn` types in the same order. The types relate if
+    /// exactly one pairing does, so the result does not depend on the
+    /// order of the bounds; if several do, possibly with different
+    /// inference bindings, or if there are too many pairings to try, the
+    /// relation is ambiguous.
+    #[instrument(level = "debug", skip(self))]
+    fn equate_dyn(&mut self, variance: Variance, a: &DynTy<I>, b: &DynTy<I>) -> Fallible<()> {
+        let interner = self.interner;
+        let a = a.clone().normalized(interner);
+        let b = b.clone().normalized(interner);
+        // Normalized bounds are sorted, so the same bounds are in the same
+        // order and relate without binding anything.
+        if a == b {
+            return Zip::zip_with(self, variance, &a, &b);
+        }
+        let a_bounds = a.bounds.skip_binders().as_slice(interner);
+        let b_bounds = b.bounds.skip_binders().as_slice(interner);
+        if a_bounds.len() != b_bounds.len() {
+            return Err(NoSolution);
+        }
+
+        // The bounds of `b` each bound of `a` may be paired with.
+        let candidates: Vec<Vec<usize>> = a_bounds
+            .iter()
+            .map(|a_bound| {
+                (0..b_bounds.len())
+                    .filter(|&j| {
+                        same_bound_kind(a_bound.skip_binders(), b_bounds[j].skip_binders())
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut search = DynPairings {
+            candidates,
+            paired: vec![],
+            used: vec![false; b_bounds.len()],
+            tried: 0,
+            related: vec![],
+            stopped: false,
+        };
+        self.find_dyn_pairings(variance, &a, &b, &mut search);
+
+        match (search.stopped, search.related.as_slice()) {
+            (false, []) => Err(NoSolution),
+            (false, [pairing]) => self.relate_dyn_pairing(variance, &a, &b, pairing),
+            _ => {
+                debug!("ambiguous pairing of dyn bounds");
+                self.goals.push(InEnvironment::new(
+                    self.environment,
+                    GoalData::CannotProve.intern(interner),
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// Extends the pairing of `search`, the bounds of `b` paired with the
+    /// first bounds of `a`, with every pairing of the next bound of `a` in
+    /// turn, recording the complete pairings which relate `a` and `b`. The
+    /// pairings are only probed: nothing they bind is kept.
+    fn find_dyn_pairings(
+        &mut self,
+        variance: Variance,
+        a: &DynTy<I>,
+        b: &DynTy<I>,
+        search: &mut DynPairings,
+    ) {
+        let next = search.paired.len();
+        if next == search.candidates.len() {
+            let snapshot = self.table.snapshot();
+            let (goals, cycle) = (self.goals.len(), self.cycle.clone());
+            if self
+                .relate_dyn_pairing(variance, a, b, &search.paired)
+                .is_ok()
+            {
+                search.related.push(search.paired.clone());
+            }
+            self.table.rollback_to(snapshot);
+            self.goals.truncate(goals);
+            self.cycle = cycle;
+            search.tried += 1;
+            search.stopped = search.related.len() > 1 || search.tried >= MAX_DYN_PAIRINGS;
+            return;
+        }
+        for j in search.candidates[next].clone() {
+            if search.used[j] {
+                continue;
+            }
+            search.used[j] = true;
+            search.paired.push(j);
+            self.find_dyn_pairings(variance, a, b, search);
+            search.paired.pop();
+            search.used[j] = false;
+            if search.stopped {
+                return;
+            }
+        }
+    }
+
+    /// Relates `a` and `b` with the `i`th bound of `a` paired with the
+    /// `pairing[i]`th bound of `b`.
+    fn relate_dyn_pairing(
+        &mut self,
+        variance: Variance,
+        a: &DynTy<I>,
+        b: &DynTy<I>,
+        pairing: &[usize],
+    ) -> Fallible<()> {
+        let interner = self.interner;
+        let b_bounds = b.bounds.skip_binders().as_slice(interner);
+        let reordered = DynTy {
+            bounds: Binders::new(
+                b.bounds.binders.clone(),
+                QuantifiedWhereClauses::from_iter(
+                    interner,
+                    pairing.iter().map(|&j| b_bounds[j].clone()),
+                ),
+            ),
+            lifetime: b.lifetime.clone(),
+        };
+        Zip::zip_with(self, variance, a, &reordered)
+    }
+
     #[instrument(level = "debug", skip(self))]
     fn generalize_ty(
         &mut self,
@@ -600,6 +820,10 @@ impl<'t, I: Interner> Unifier<'t, I> {
             )
             .intern(interner),
             TyKind::Foreign(id) => TyKind::Foreign(*id).intern(interner),
+            // Custom types are opaque to the generalizer: they are kept as is
+            // (the occurs check has already been applied to them), so anything
+            // they contain is related invariantly.
+            TyKind::Custom(_) => ty.clone(),
             TyKind::Error => TyKind::Error.intern(interner),
             TyKind::Dyn(dyn_ty) => {
                 let DynTy { bounds, lifetime } = dyn_ty;
@@ -667,6 +891,21 @@ impl<'t, I: Interner> Unifier<'t, I> {
                                                 associated_ty_id,
                                             })
                                         }
+                                        AliasTy::Inherent(inherent_ty) => {
+                                            let InherentProjectionTy {
+                                                ref substitution,
+                                                associated_ty_id,
+                                            } = *inherent_ty;
+                                            let substitution = self.generalize_substitution(
+                                                substitution,
+                                                universe_index,
+                                                |_| variance,
+                                            );
+                                            AliasTy::Inherent(InherentProjectionTy {
+                                                substitution,
+                                                associated_ty_id,
+                                            })
+                                        }
                                     };
                                     let ty =
                                         self.table.new_variable(universe_index).to_ty(interner);
@@ -862,6 +1101,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
             _ => return Err(NoSolution),
         }
 
+        let inference_var = var;
         let var = EnaVariable::from(var);
 
         // Determine the universe index associated with this
@@ -874,17 +1114,22 @@ impl<'t, I: Interner> Unifier<'t, I> {
 
         debug!("relate_var_ty: universe index of var: {:?}", universe_index);
 
-        debug!("trying fold_with on {:?}", ty);
-        let ty1 = ty
+        debug!("trying try_fold_with on {:?}", ty);
+        let mut occurs_check = OccursCheck::new(self, var, universe_index);
+        let ty1 = match ty
             .clone()
-            .fold_with(
-                &mut OccursCheck::new(self, var, universe_index),
-                DebruijnIndex::INNERMOST,
-            )
-            .map_err(|e| {
+            .try_fold_with(&mut occurs_check, DebruijnIndex::INNERMOST)
+        {
+            Ok(ty1) => ty1,
+            Err(e) => {
                 debug!("failed to fold {:?}", ty);
-                e
-            })?;
+                if occurs_check.cyclic {
+                    self.cycle
+                        .get_or_insert_with(|| (inference_var, ty.clone()));
+                }
+                return Err(e);
+            }
+        };
 
         // "Generalize" types. This ensures that we aren't accidentally forcing
         // too much onto `var`. Instead of directly setting `var` equal to `ty`,
@@ -944,6 +1189,10 @@ impl<'t, I: Interner> Unifier<'t, I> {
 
         debug_span!("relate_lifetime_lifetime", ?variance, ?a, ?b);
 
+        if interner.interned_eq_fast(a.interned(), b.interned()) {
+            return Ok(());
+        }
+
         match (a.data(interner), b.data(interner)) {
             (&LifetimeData::InferenceVar(var_a), &LifetimeData::InferenceVar(var_b)) => {
                 let var_a = EnaVariable::from(var_a);
@@ -1062,6 +1311,10 @@ impl<'t, I: Interner> Unifier<'t, I> {
 
         debug_span!("relate_const_const", ?variance, ?a, ?b);
 
+        if interner.interned_eq_fast(a.interned(), b.interned()) {
+            return Ok(());
+        }
+
         let ConstData {
             ty: a_ty,
             value: a_val,
@@ -1134,7 +1387,7 @@ impl<'t, I: Interner> Unifier<'t, I> {
         // as the variable is unified.
         let universe_index = self.table.universe_of_unbound_var(var);
 
-        let c1 = c.clone().fold_with(
+        let c1 = c.clone().try_fold_with(
             &mut OccursCheck::new(self, var, universe_index),
             DebruijnIndex::INNERMOST,
         )?;
@@ -1231,6 +1484,8 @@ struct OccursCheck<'u, 't, I: Interner> {
     unifier: &'u mut Unifier<'t, I>,
     var: EnaVariable<I>,
     universe_index: UniverseIndex,
+    /// Set if the fold failed because `var` occurs in the folded value.
+    cyclic: bool,
 }
 
 impl<'u, 't, I: Interner> OccursCheck<'u, 't, I> {
@@ -1243,18 +1498,29 @@ impl<'u, 't, I: Interner> OccursCheck<'u, 't, I> {
             unifier,
             var,
             universe_index,
+            cyclic: false,
         }
     }
 }
 
-impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
+impl<'i, I: Interner> FallibleFolder<I> for OccursCheck<'_, 'i, I> {
     type Error = NoSolution;
 
-    fn as_dyn(&mut self) -> &mut dyn Folder<I, Error = Self::Error> {
+    fn as_dyn(&mut self) -> &mut dyn FallibleFolder<I, Error = Self::Error> {
         self
     }
 
-    fn fold_free_placeholder_ty(
+    fn try_fold_ty(&mut self, ty: Ty<I>, outer_binder: DebruijnIndex) -> Fallible<Ty<I>> {
+        if !ty.has_type_flags(
+            self.interner(),
+            TypeFlags::HAS_INFER | TypeFlags::HAS_PLACEHOLDER | TypeFlags::HAS_BOUND_VARS,
+        ) {
+            return Ok(ty);
+        }
+        ty.try_super_fold_with(self.as_dyn(), outer_binder)
+    }
+
+    fn try_fold_free_placeholder_ty(
         &mut self,
         universe: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
@@ -1271,7 +1537,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
         }
     }
 
-    fn fold_free_placeholder_const(
+    fn try_fold_free_placeholder_const(
         &mut self,
         ty: Ty<I>,
         universe: PlaceholderIndex,
@@ -1286,7 +1552,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
     }
 
     #[instrument(level = "debug", skip(self))]
-    fn fold_free_placeholder_lifetime(
+    fn try_fold_free_placeholder_lifetime(
         &mut self,
         ui: PlaceholderIndex,
         _outer_binder: DebruijnIndex,
@@ -1320,7 +1586,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
         }
     }
 
-    fn fold_inference_ty(
+    fn try_fold_inference_ty(
         &mut self,
         var: InferenceVar,
         kind: TyVariableKind,
@@ -1334,7 +1600,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
                 let normalized_ty = normalized_ty.assert_ty_ref(interner);
                 let normalized_ty = normalized_ty
                     .clone()
-                    .fold_with(self, DebruijnIndex::INNERMOST)?;
+                    .try_fold_with(self, DebruijnIndex::INNERMOST)?;
                 assert!(!normalized_ty.needs_shift(interner));
                 Ok(normalized_ty)
             }
@@ -1348,6 +1614,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
                         "OccursCheck aborting because {:?} unioned with {:?}",
                         var, self.var,
                     );
+                    self.cyclic = true;
                     return Err(NoSolution);
                 }
 
@@ -1370,7 +1637,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
         }
     }
 
-    fn fold_inference_const(
+    fn try_fold_inference_const(
         &mut self,
         ty: Ty<I>,
         var: InferenceVar,
@@ -1384,7 +1651,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
                 let normalized_const = normalized_const.assert_const_ref(interner);
                 let normalized_const = normalized_const
                     .clone()
-                    .fold_with(self, DebruijnIndex::INNERMOST)?;
+                    .try_fold_with(self, DebruijnIndex::INNERMOST)?;
                 assert!(!normalized_const.needs_shift(interner));
                 Ok(normalized_const)
             }
@@ -1416,7 +1683,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
         }
     }
 
-    fn fold_inference_lifetime(
+    fn try_fold_inference_lifetime(
         &mut self,
         var: InferenceVar,
         outer_binder: DebruijnIndex,
@@ -1445,7 +1712,7 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
 
             InferenceValue::Bound(l) => {
                 let l = l.assert_lifetime_ref(interner);
-                let l = l.clone().fold_with(self, outer_binder)?;
+                let l = l.clone().try_fold_with(self, outer_binder)?;
                 assert!(!l.needs_shift(interner));
                 Ok(l)
             }
@@ -1460,3 +1727,44 @@ impl<'i, I: Interner> Folder<I> for OccursCheck<'_, 'i, I> {
         self.unifier.interner
     }
 }
+
+/// The most pairings of the bounds of two `dyn` types tried before giving
+/// up on finding the only one which relates them, as their number grows
+/// with the factorial of the number of bounds of the same kind.
+const MAX_DYN_PAIRINGS: usize = 64;
+
+/// The search for the pairings of the bounds of two `dyn` types which
+/// relate them.
+struct DynPairings {
+    /// The bounds of `b` each bound of `a` may be paired with.
+    candidates: Vec<Vec<usize>>,
+    /// The bounds of `b` paired with the first bounds of `a` so far.
+    paired: Vec<usize>,
+    /// Which bounds of `b` are in `paired`.
+    used: Vec<bool>,
+    /// How many complete pairings were tried.
+    tried: usize,
+    /// The pairings which relate the types, at most two of them.
+    related: Vec<Vec<usize>>,
+    /// Whether the search stopped before trying every pairing, because
+    /// several relate the types or too many were tried.
+    stopped: bool,
+}
+
+/// Whether two bounds of `dyn` types can only be related to each other: both
+/// are bounds for the same trait, the same associated type, etc.
+fn same_bound_kind<I: Interner>(a: &WhereClause<I>, b: &WhereClause<I>) -> bool {
+    match (a, b) {
+        (WhereClause::Implemented(a), WhereClause::Implemented(b)) => a.trait_id == b.trait_id,
+        (WhereClause::AliasEq(a), WhereClause::AliasEq(b)) => match (&a.alias, &b.alias) {
+            (AliasTy::Projection(a), AliasTy::Projection(b)) => {
+                a.associated_ty_id == b.associated_ty_id
+            }
+            (AliasTy::Opaque(a), AliasTy::Opaque(b)) => a.opaque_ty_id == b.opaque_ty_id,
+            _ => false,
+        },
+        (WhereClause::LifetimeOutlives(_), WhereClause::LifetimeOutlives(_))
+        | (WhereClause::TypeOutlives(_), WhereClause::TypeOutlives(_)) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,376 @@
+//! A union-find table with snapshots, holding the values of the inference
+//! variables without `std`. It only needs `alloc`, unlike the `ena` crate
+//! which is used with `std`, and has the subset of its API we use.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// A key of the table, which is a dense index.
+pub(crate) trait UnifyKey: Copy + Eq {
+    type Value: UnifyValue;
+
+    fn index(&self) -> u32;
+
+    fn from_index(u: u32) -> Self;
+}
+
+/// The value of a set of unified keys.
+pub(crate) trait UnifyValue: Clone {
+    type Error;
+
+    /// The value of the union of two sets with values `a` and `b`.
+    fn unify_values(a: &Self, b: &Self) -> Result<Self, Self::Error>;
+}
+
+#[derive(Clone)]
+struct VarValue<K: UnifyKey> {
+    parent: K,
+    value: K::Value,
+    rank: u32,
+}
+
+#[derive(Clone)]
+enum UndoLog<K: UnifyKey> {
+    /// A key was created, and is the last one.
+    NewElem,
+    /// The entry of a key was overwritten; this is its old value.
+    SetElem(usize, VarValue<K>),
+}
+
+/// A union-find table, with union by rank and path compression. While
+/// a snapshot is open, every change is logged so that it can be undone.
+#[derive(Clone)]
+pub(crate) struct UnificationTable<K: UnifyKey> {
+    values: Vec<VarValue<K>>,
+    undo_log: Vec<UndoLog<K>>,
+    num_open_snapshots: usize,
+}
+
+/// The state of a table when a snapshot was taken, see
+/// `UnificationTable::snapshot`.
+pub(crate) struct Snapshot<K: UnifyKey> {
+    undo_len: usize,
+    phantom: PhantomData<K>,
+}
+
+impl<K: UnifyKey> UnificationTable<K> {
+    pub(crate) fn new() -> Self {
+        UnificationTable {
+            values: Vec::new(),
+            undo_log: Vec::new(),
+            num_open_snapshots: 0,
+        }
+    }
+
+    /// The number of keys in the table.
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Creates a key which is only unified with itself, with `value`.
+    pub(crate) fn new_key(&mut self, value: K::Value) -> K {
+        let key = K::from_index(self.values.len() as u32);
+        self.values.push(VarValue {
+            parent: key,
+            value,
+            rank: 0,
+        });
+        if self.in_snapshot() {
+            self.undo_log.push(UndoLog::NewElem);
+        }
+        key
+    }
+
+    /// Starts logging the changes, which can be undone with `rollback_to`,
+    /// or kept with `commit`. Snapshots nest, and must be closed in the
+    /// reverse order they were taken in.
+    pub(crate) fn snapshot(&mut self) -> Snapshot<K> {
+        self.num_open_snapshots += 1;
+        Snapshot {
+            undo_len: self.undo_log.len(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Undoes the changes made since `snapshot` was taken.
+    pub(crate) fn rollback_to(&mut self, snapshot: Snapshot<K>) {
+        assert!(self.undo_log.len() >= snapshot.undo_len);
+        assert!(self.num_open_snapshots > 0);
+        while self.undo_log.len() > snapshot.undo_len {
+            match self.undo_log.pop().unwrap() {
+                UndoLog::NewElem => {
+                    self.values.pop();
+                }
+                UndoLog::SetElem(index, old_value) => self.values[index] = old_value,
+            }
+        }
+        self.num_open_snapshots -= 1;
+    }
+
+    /// Keeps the changes made since `snapshot` was taken. They can still
+    /// be undone by rolling back an enclosing snapshot.
+    pub(crate) fn commit(&mut self, snapshot: Snapshot<K>) {
+        assert!(self.undo_log.len() >= snapshot.undo_len);
+        assert!(self.num_open_snapshots > 0);
+        if self.num_open_snapshots == 1 {
+            // Nothing can be rolled back anymore, including what was
+            // logged before this snapshot was taken.
+            self.undo_log.clear();
+        }
+        self.num_open_snapshots -= 1;
+    }
+
+    /// The representative of the keys unified with `key`.
+    pub(crate) fn find(&mut self, key: impl Into<K>) -> K {
+        let key = key.into();
+        let index = key.index() as usize;
+        let parent = self.values[index].parent;
+        if parent == key {
+            return key;
+        }
+        let root = self.find(parent);
+        if root != parent {
+            // Path compression: point straight at the root next time.
+            self.update(index, |entry| entry.parent = root);
+        }
+        root
+    }
+
+    /// Whether `a` and `b` were unified.
+    pub(crate) fn unioned(&mut self, a: impl Into<K>, b: impl Into<K>) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// The value of the keys unified with `key`.
+    pub(crate) fn probe_value(&mut self, key: impl Into<K>) -> K::Value {
+        let root = self.find(key);
+        self.values[root.index() as usize].value.clone()
+    }
+
+    /// Unifies the keys of `a` and `b`, combining their values.
+    pub(crate) fn unify_var_var(
+        &mut self,
+        a: impl Into<K>,
+        b: impl Into<K>,
+    ) -> Result<(), <K::Value as UnifyValue>::Error> {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return Ok(());
+        }
+        let value = K::Value::unify_values(
+            &self.values[root_a.index() as usize].value,
+            &self.values[root_b.index() as usize].value,
+        )?;
+
+        let rank_a = self.values[root_a.index() as usize].rank;
+        let rank_b = self.values[root_b.index() as usize].rank;
+        let (rank, old_root, new_root) = if rank_a > rank_b {
+            (rank_a, root_b, root_a)
+        } else if rank_a < rank_b {
+            (rank_b, root_a, root_b)
+        } else {
+            (rank_a + 1, root_a, root_b)
+        };
+        self.update(old_root.index() as usize, |entry| entry.parent = new_root);
+        self.update(new_root.index() as usize, |entry| {
+            entry.rank = rank;
+            entry.value = value;
+        });
+        Ok(())
+    }
+
+    /// Combines the value of the keys unified with `key` with `value`.
+    pub(crate) fn unify_var_value(
+        &mut self,
+        key: impl Into<K>,
+        value: K::Value,
+    ) -> Result<(), <K::Value as UnifyValue>::Error> {
+        let root = self.find(key);
+        let index = root.index() as usize;
+        let value = K::Value::unify_values(&self.values[index].value, &value)?;
+        self.update(index, |entry| entry.value = value);
+        Ok(())
+    }
+
+    fn in_snapshot(&self) -> bool {
+        self.num_open_snapshots > 0
+    }
+
+    fn update(&mut self, index: usize, op: impl FnOnce(&mut VarValue<K>)) {
+        if self.in_snapshot() {
+            self.undo_log
+                .push(UndoLog::SetElem(index, self.values[index].clone()));
+        }
+        op(&mut self.values[index]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct Key(u32);
+
+    /// Either unknown, or a number, which unifies only with itself.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Value(Option<u32>);
+
+    impl UnifyKey for Key {
+        type Value = Value;
+
+        fn index(&self) -> u32 {
+            self.0
+        }
+
+        fn from_index(u: u32) -> Self {
+            Key(u)
+        }
+    }
+
+    impl UnifyValue for Value {
+        type Error = (u32, u32);
+
+        fn unify_values(a: &Self, b: &Self) -> Result<Self, (u32, u32)> {
+            match (a.0, b.0) {
+                (Some(a), Some(b)) if a != b => Err((a, b)),
+                (a, b) => Ok(Value(a.or(b))),
+            }
+        }
+    }
+
+    /// Makes a chain of `len` keys, each unified with the next one, so
+    /// that finding the first one compresses the path.
+    fn chain(table: &mut UnificationTable<Key>, len: u32) -> Vec<Key> {
+        let keys: Vec<Key> = (0..len).map(|_| table.new_key(Value(None))).collect();
+        for pair in keys.windows(2) {
+            // Union by rank would balance the tree, so link the roots by
+            // hand to get a chain.
+            let (a, b) = (pair[0], pair[1]);
+            table.values[a.0 as usize].parent = b;
+        }
+        keys
+    }
+
+    #[test]
+    fn nested_snapshots() {
+        let mut table = UnificationTable::<Key>::new();
+        let a = table.new_key(Value(None));
+        let b = table.new_key(Value(None));
+        let c = table.new_key(Value(None));
+
+        let outer = table.snapshot();
+        table.unify_var_var(a, b).unwrap();
+        let inner = table.snapshot();
+        table.unify_var_var(b, c).unwrap();
+        assert!(table.unioned(a, c));
+        table.rollback_to(inner);
+        assert!(table.unioned(a, b));
+        assert!(!table.unioned(a, c));
+
+        let inner = table.snapshot();
+        table.unify_var_value(c, Value(Some(1))).unwrap();
+        table.commit(inner);
+        assert_eq!(table.probe_value(c), Value(Some(1)));
+        table.commit(outer);
+
+        assert!(table.unioned(a, b));
+        assert_eq!(table.probe_value(c), Value(Some(1)));
+        assert!(table.undo_log.is_empty());
+    }
+
+    #[test]
+    fn rollback_after_new_key() {
+        let mut table = UnificationTable::<Key>::new();
+        let a = table.new_key(Value(Some(1)));
+
+        let snapshot = table.snapshot();
+        let b = table.new_key(Value(None));
+        table.unify_var_var(a, b).unwrap();
+        assert_eq!(table.len(), 2);
+        table.rollback_to(snapshot);
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.find(a), a);
+        assert_eq!(table.probe_value(a), Value(Some(1)));
+        // The index of `b` is reused.
+        assert_eq!(table.new_key(Value(None)), b);
+    }
+
+    #[test]
+    fn rollback_of_path_compression() {
+        let mut table = UnificationTable::<Key>::new();
+        let keys = chain(&mut table, 4);
+        let root = keys[3];
+
+        let snapshot = table.snapshot();
+        assert_eq!(table.find(keys[0]), root);
+        assert_eq!(table.values[0].parent, root);
+        table.rollback_to(snapshot);
+
+        assert_eq!(table.values[0].parent, keys[1]);
+        assert_eq!(table.values[1].parent, keys[2]);
+        assert_eq!(table.find(keys[0]), root);
+    }
+
+    #[test]
+    fn commit_then_outer_rollback() {
+        let mut table = UnificationTable::<Key>::new();
+        let a = table.new_key(Value(None));
+
+        let outer = table.snapshot();
+        let inner = table.snapshot();
+        let b = table.new_key(Value(None));
+        table.unify_var_var(a, b).unwrap();
+        table.unify_var_value(b, Value(Some(2))).unwrap();
+        table.commit(inner);
+        assert_eq!(table.probe_value(a), Value(Some(2)));
+        table.rollback_to(outer);
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.find(a), a);
+        assert_eq!(table.probe_value(a), Value(None));
+        assert!(table.undo_log.is_empty());
+    }
+
+    #[test]
+    fn commit_outermost_after_logging_before_it() {
+        let mut table = UnificationTable::<Key>::new();
+        let a = table.new_key(Value(None));
+
+        let first = table.snapshot();
+        let b = table.new_key(Value(None));
+        let second = table.snapshot();
+        table.unify_var_var(a, b).unwrap();
+        // Commit them out of order: the one closed last was taken after
+        // `b` was logged, which must not matter once none is open.
+        table.commit(first);
+        table.commit(second);
+
+        assert!(table.unioned(a, b));
+        assert!(table.undo_log.is_empty());
+    }
+
+    #[test]
+    fn unify_var_value_errors() {
+        let mut table = UnificationTable::<Key>::new();
+        let a = table.new_key(Value(Some(1)));
+        let b = table.new_key(Value(None));
+        table.unify_var_var(a, b).unwrap();
+
+        let snapshot = table.snapshot();
+        assert_eq!(table.unify_var_value(b, Value(Some(2))), Err((1, 2)));
+        // A failed unification leaves the value alone.
+        assert_eq!(table.probe_value(b), Value(Some(1)));
+        assert_eq!(table.unify_var_value(b, Value(Some(1))), Ok(()));
+        assert_eq!(table.unify_var_value(a, Value(None)), Ok(()));
+        assert_eq!(table.probe_value(a), Value(Some(1)));
+        table.rollback_to(snapshot);
+
+        let c = table.new_key(Value(Some(3)));
+        assert_eq!(table.unify_var_var(a, c), Err((1, 3)));
+        assert!(!table.unioned(a, c));
+        assert_eq!(table.probe_value(c), Value(Some(3)));
+    }
+}
@@ -1,11 +1,10 @@
+use super::table::{UnifyKey, UnifyValue};
 use chalk_ir::cast::Cast;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use ena::unify::{UnifyKey, UnifyValue};
-use std::cmp::min;
-use std::fmt;
-use std::marker::PhantomData;
-use std::u32;
+use core::cmp::min;
+use core::fmt;
+use core::marker::PhantomData;
 
 /// Wrapper around `chalk_ir::InferenceVar` for coherence purposes.
 /// An inference variable represents an unknown term -- either a type
@@ -97,6 +96,7 @@ impl<I: Interner> UnifyKey for EnaVariable<I> {
         EnaVariable::from(InferenceVar::from(u))
     }
 
+    #[cfg(feature = "std")]
     fn tag() -> &'static str {
         "EnaVariable"
     }
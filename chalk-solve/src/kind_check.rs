@@ -0,0 +1,286 @@
+//! Kind checking of the items of a program: every substitution applied to
+//! an item (an ADT, fn def, trait, associated type or opaque type) must
+//! have one argument per parameter of the item, each of the kind of its
+//! parameter. The solvers assume this holds and panic, or worse, when it
+//! does not, so hosts can run this check on their items first.
+//!
+//! Only the kind of const arguments is checked, not their type.
+
+use core::fmt;
+use core::ops::ControlFlow;
+
+use crate::RustIrDatabase;
+use chalk_ir::{
+    interner::Interner,
+    visit::{SuperVisit, Visit, Visitor},
+    *,
+};
+
+/// An item taking generic arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenericItem<I: Interner> {
+    Adt(AdtId<I>),
+    FnDef(FnDefId<I>),
+    Trait(TraitId<I>),
+    AssociatedTy(AssocTypeId<I>),
+    OpaqueTy(OpaqueTyId<I>),
+}
+
+impl<I: Interner> fmt::Display for GenericItem<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenericItem::Adt(id) => write!(f, "{:?}", id),
+            GenericItem::FnDef(id) => write!(f, "{:?}", id),
+            GenericItem::Trait(id) => write!(f, "{:?}", id),
+            GenericItem::AssociatedTy(id) => write!(f, "{:?}", id),
+            GenericItem::OpaqueTy(id) => write!(f, "{:?}", id),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KindError<I: Interner> {
+    /// `item` has `expected` parameters, but was given `actual` arguments.
+    WrongNumberOfArguments {
+        item: GenericItem<I>,
+        expected: usize,
+        actual: usize,
+    },
+    /// The argument at `index` given to `item` is not of the kind of the
+    /// parameter at `index`.
+    WrongKindOfArgument {
+        item: GenericItem<I>,
+        index: usize,
+        expected: VariableKind<I>,
+        actual: GenericArg<I>,
+    },
+}
+
+impl<I: Interner> fmt::Display for KindError<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KindError::WrongNumberOfArguments {
+                item,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "`{}` takes {} generic arguments, but {} were given",
+                item, expected, actual
+            ),
+            KindError::WrongKindOfArgument {
+                item,
+                index,
+                expected,
+                actual,
+            } => {
+                let expected = match expected {
+                    VariableKind::Ty(_) => "type",
+                    VariableKind::Lifetime => "lifetime",
+                    VariableKind::Const(_) => "const",
+                };
+                write!(
+                    f,
+                    "generic argument {} of `{}` should be a {}, but `{:?}` was given",
+                    index, item, expected, actual
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I: Interner> std::error::Error for KindError<I> {}
+
+pub struct KindChecker<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+}
+
+impl<'a, I> KindChecker<'a, I>
+where
+    I: Interner,
+{
+    /// Constructs a new `KindChecker`.
+    pub fn new(db: &'a dyn RustIrDatabase<I>) -> Self {
+        Self { db }
+    }
+
+    pub fn check_adt_decl(&self, adt_id: AdtId<I>) -> Result<(), KindError<I>> {
+        self.check(&*self.db.adt_datum(adt_id))
+    }
+
+    pub fn check_fn_def_decl(&self, fn_def_id: FnDefId<I>) -> Result<(), KindError<I>> {
+        self.check(&self.db.fn_def_datum(fn_def_id).binders)
+    }
+
+    /// Checks the trait and the declarations of its associated types,
+    /// including their bounds.
+    pub fn check_trait_decl(&self, trait_id: TraitId<I>) -> Result<(), KindError<I>> {
+        let interner = self.db.interner();
+        let trait_datum = self.db.trait_datum(trait_id);
+        self.check(&*trait_datum)?;
+        for &associated_ty_id in &trait_datum.associated_ty_ids {
+            let associated_ty_datum = self.db.associated_ty_data(associated_ty_id);
+            self.check(&associated_ty_datum.binders)?;
+            let bounds = associated_ty_datum.bounds_on_self(interner);
+            self.check(&Binders::new(
+                associated_ty_datum.binders.binders.clone(),
+                bounds,
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Checks the impl, including its trait reference and the values of
+    /// its associated types.
+    pub fn check_impl(&self, impl_id: ImplId<I>) -> Result<(), KindError<I>> {
+        let impl_datum = self.db.impl_datum(impl_id);
+        self.check(&*impl_datum)?;
+        let trait_ref = impl_datum.binders.map_ref(|bound| bound.trait_ref.clone());
+        self.check(&trait_ref.map(WhereClause::Implemented))?;
+        for &value_id in &impl_datum.associated_ty_value_ids {
+            self.check(&self.db.associated_ty_value(value_id).value)?;
+        }
+        Ok(())
+    }
+
+    pub fn check_opaque_ty_decl(&self, opaque_ty_id: OpaqueTyId<I>) -> Result<(), KindError<I>> {
+        self.check(&*self.db.opaque_ty_data(opaque_ty_id))
+    }
+
+    fn check(&self, value: &impl Visit<I>) -> Result<(), KindError<I>> {
+        let mut visitor = KindCheckVisitor { db: self.db };
+        match value.visit_with(&mut visitor, DebruijnIndex::INNERMOST) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(error) => Err(error),
+        }
+    }
+}
+
+struct KindCheckVisitor<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+}
+
+impl<'a, I: Interner> KindCheckVisitor<'a, I> {
+    fn check_arguments(
+        &self,
+        item: GenericItem<I>,
+        parameters: &VariableKinds<I>,
+        arguments: &Substitution<I>,
+    ) -> ControlFlow<KindError<I>> {
+        let interner = self.db.interner();
+        let (parameters, arguments) = (parameters.as_slice(interner), arguments.as_slice(interner));
+        if parameters.len() != arguments.len() {
+            return ControlFlow::Break(KindError::WrongNumberOfArguments {
+                item,
+                expected: parameters.len(),
+                actual: arguments.len(),
+            });
+        }
+        for (index, (parameter, argument)) in parameters.iter().zip(arguments).enumerate() {
+            match (parameter, argument.data(interner)) {
+                (VariableKind::Ty(_), GenericArgData::Ty(_))
+                | (VariableKind::Lifetime, GenericArgData::Lifetime(_))
+                | (VariableKind::Const(_), GenericArgData::Const(_)) => {}
+                _ => {
+                    return ControlFlow::Break(KindError::WrongKindOfArgument {
+                        item,
+                        index,
+                        expected: parameter.clone(),
+                        actual: argument.clone(),
+                    })
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn check_trait_ref(&self, trait_ref: &TraitRef<I>) -> ControlFlow<KindError<I>> {
+        self.check_arguments(
+            GenericItem::Trait(trait_ref.trait_id),
+            &self.db.trait_datum(trait_ref.trait_id).binders.binders,
+            &trait_ref.substitution,
+        )
+    }
+
+    fn check_alias(&self, alias: &AliasTy<I>) -> ControlFlow<KindError<I>> {
+        match alias {
+            AliasTy::Projection(projection) => self.check_arguments(
+                GenericItem::AssociatedTy(projection.associated_ty_id),
+                &self
+                    .db
+                    .associated_ty_data(projection.associated_ty_id)
+                    .binders
+                    .binders,
+                &projection.substitution,
+            ),
+            AliasTy::Opaque(opaque) => self.check_arguments(
+                GenericItem::OpaqueTy(opaque.opaque_ty_id),
+                &self.db.opaque_ty_data(opaque.opaque_ty_id).bound.binders,
+                &opaque.substitution,
+            ),
+            // Inherent associated types only take their `Self` type.
+            AliasTy::Inherent(inherent) => self.check_arguments(
+                GenericItem::AssociatedTy(inherent.associated_ty_id),
+                &VariableKinds::from1(
+                    self.db.interner(),
+                    VariableKind::Ty(TyVariableKind::General),
+                ),
+                &inherent.substitution,
+            ),
+        }
+    }
+}
+
+impl<'a, I: Interner> Visitor<I> for KindCheckVisitor<'a, I> {
+    type BreakTy = KindError<I>;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.db.interner()
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> ControlFlow<KindError<I>> {
+        match ty.kind(self.interner()) {
+            TyKind::Adt(id, substitution) => self.check_arguments(
+                GenericItem::Adt(*id),
+                &self.db.adt_datum(*id).binders.binders,
+                substitution,
+            )?,
+            TyKind::FnDef(id, substitution) => self.check_arguments(
+                GenericItem::FnDef(*id),
+                &self.db.fn_def_datum(*id).binders.binders,
+                substitution,
+            )?,
+            TyKind::AssociatedType(id, substitution) => self.check_arguments(
+                GenericItem::AssociatedTy(*id),
+                &self.db.associated_ty_data(*id).binders.binders,
+                substitution,
+            )?,
+            TyKind::OpaqueType(id, substitution) => self.check_arguments(
+                GenericItem::OpaqueTy(*id),
+                &self.db.opaque_ty_data(*id).bound.binders,
+                substitution,
+            )?,
+            TyKind::Alias(alias) => self.check_alias(alias)?,
+            _ => {}
+        }
+        ty.super_visit_with(self.as_dyn(), outer_binder)
+    }
+
+    fn visit_where_clause(
+        &mut self,
+        where_clause: &WhereClause<I>,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<KindError<I>> {
+        match where_clause {
+            WhereClause::Implemented(trait_ref) => self.check_trait_ref(trait_ref)?,
+            WhereClause::AliasEq(alias_eq) => self.check_alias(&alias_eq.alias)?,
+            WhereClause::LifetimeOutlives(..) | WhereClause::TypeOutlives(..) => {}
+        }
+        where_clause.super_visit_with(self.as_dyn(), outer_binder)
+    }
+}
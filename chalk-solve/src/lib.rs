@@ -1,27 +1,82 @@
 #![deny(rust_2018_idioms)]
+#![no_std]
 
+// Only `alloc` is needed, unless the `std` feature is enabled (it is by
+// default) for the pieces which need a lock, a clock or threads.
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+use crate::clauses::builder::ClauseBuilder;
+use crate::clauses::custom_builtins::CustomBuiltin;
 use crate::display::sanitize_debug_name;
 use crate::rust_ir::*;
+use crate::simplified_type::{ImplFilter, SimplifiedType};
+use crate::variance::VarianceItem;
 use chalk_ir::interner::Interner;
 
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use chalk_ir::*;
-use std::fmt::Debug;
-use std::sync::Arc;
+use core::fmt::Debug;
+use core::hash::BuildHasherDefault;
+use core::ops::DerefMut;
+use rustc_hash::FxHasher;
 
+pub mod applicable_impls;
+pub mod autoderef;
 pub mod clauses;
+#[cfg(feature = "std")]
 pub mod coherence;
 pub mod coinductive_goal;
 pub mod display;
 pub mod ext;
 pub mod goal_builder;
 pub mod infer;
+pub mod kind_check;
 pub mod logging;
 pub mod logging_db;
+pub mod memoizing_db;
+#[cfg(feature = "method-resolution")]
+pub mod method_resolution;
 pub mod rust_ir;
+pub mod simplified_type;
 pub mod solve;
 pub mod split;
+pub mod struct_tail;
+pub mod variance;
 pub mod wf;
 
+/// `rustc_hash` only provides its hash maps and sets with `std`.
+pub(crate) type FxHashMap<K, V> = hashbrown::HashMap<K, V, BuildHasherDefault<FxHasher>>;
+pub(crate) type FxHashSet<T> = hashbrown::HashSet<T, BuildHasherDefault<FxHasher>>;
+/// `indexmap` only has a default hasher with `std`.
+pub(crate) type FxIndexMap<K, V> = indexmap::IndexMap<K, V, BuildHasherDefault<FxHasher>>;
+pub(crate) type FxIndexSet<T> = indexmap::IndexSet<T, BuildHasherDefault<FxHasher>>;
+
+/// The lock of the state shared by the databases which record or name
+/// things: a `Mutex`, or without `std` a `RefCell`, which can then only be
+/// used from one thread.
+#[cfg(feature = "std")]
+pub(crate) type Lock<T> = std::sync::Mutex<T>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Lock<T> = core::cell::RefCell<T>;
+
+pub(crate) fn lock<T>(lock: &Lock<T>) -> impl DerefMut<Target = T> + '_ {
+    #[cfg(feature = "std")]
+    return lock.lock().unwrap();
+    #[cfg(not(feature = "std"))]
+    return lock.borrow_mut();
+}
+
+pub(crate) fn into_inner<T>(lock: Lock<T>) -> T {
+    #[cfg(feature = "std")]
+    return lock.into_inner().unwrap();
+    #[cfg(not(feature = "std"))]
+    return lock.into_inner();
+}
+
 /// Trait representing access to a database of rust types.
 ///
 /// # `*_name` methods
@@ -86,7 +141,7 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I>;
 
     /// Returns a list of potentially relevant impls for a given
-    /// trait-id; we also supply the type parameters that we are
+    /// trait-id; `filter` supplies the type parameters that we are
     /// trying to match (if known: these parameters may contain
     /// inference variables, for example). The implementor is
     /// permitted to return any superset of the applicable impls;
@@ -95,14 +150,54 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     /// implementor do less work, but can be completely ignored if
     /// desired.
     ///
-    /// The `binders` are for the `parameters`; if the recursive solver is used,
-    /// the parameters can contain bound variables referring to these binders.
-    fn impls_for_trait(
+    /// [`ImplFilter::may_match`] cheaply rejects impls that cannot match
+    /// the parameters given only the parameters of their trait reference,
+    /// so implementors can avoid building the full datum of every impl.
+    /// If the recursive solver is used, the parameters can contain bound
+    /// variables referring to [`ImplFilter::binders`].
+    fn impls_for_trait(&self, trait_id: TraitId<I>, filter: &ImplFilter<'_, I>) -> Vec<ImplId<I>>;
+
+    /// Returns the impls of `trait_id` whose self type could simplify to
+    /// `self_ty` (see [`SimplifiedType`]), including blanket impls like
+    /// `impl<T> Foo for T`. As with `impls_for_trait`, any superset of
+    /// those impls may be returned.
+    ///
+    /// The default implementation filters all the impls of the trait by
+    /// their self type; databases with an index of impls by self type
+    /// should override it.
+    fn impls_for_trait_by_self_type(
         &self,
         trait_id: TraitId<I>,
-        parameters: &[GenericArg<I>],
-        binders: &CanonicalVarKinds<I>,
-    ) -> Vec<ImplId<I>>;
+        self_ty: &SimplifiedType<I>,
+    ) -> Vec<ImplId<I>> {
+        let interner = self.interner();
+        // Asks for the impls of `forall<P0..Pn> P0: Trait<P1..Pn>`.
+        let trait_binders = &self.trait_datum(trait_id).binders;
+        let parameters = trait_binders.identity_substitution(interner);
+        let binders = CanonicalVarKinds::from_iter(
+            interner,
+            trait_binders
+                .binders
+                .iter(interner)
+                .map(|kind| WithKind::new(kind.clone(), UniverseIndex::root())),
+        );
+        let filter = ImplFilter::new(interner, parameters.as_slice(interner), &binders);
+        self.impls_for_trait(trait_id, &filter)
+            .into_iter()
+            .filter(|&impl_id| {
+                let impl_datum = self.impl_datum(impl_id);
+                let impl_self_ty = impl_datum
+                    .binders
+                    .skip_binders()
+                    .trait_ref
+                    .self_type_parameter(interner);
+                match SimplifiedType::from_ty(interner, &impl_self_ty) {
+                    Some(impl_self_ty) => impl_self_ty == *self_ty,
+                    None => true,
+                }
+            })
+            .collect()
+    }
 
     /// Returns the impls that require coherence checking. This is not the
     /// full set of impls that exist:
@@ -122,6 +217,48 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     /// Returns id of a trait lang item, if found
     fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>>;
 
+    /// Returns the generator of the builtin impls the host gives the trait
+    /// `trait_id`, if any, for lang-item traits chalk does not know about.
+    /// See [`CustomBuiltins`] for a registry of them.
+    ///
+    /// [`CustomBuiltins`]: crate::clauses::custom_builtins::CustomBuiltins
+    fn custom_builtin(&self, _trait_id: TraitId<I>) -> Option<CustomBuiltin<I>> {
+        None
+    }
+
+    /// Pushes the clauses proving the host's predicate `predicate` (see
+    /// `Interner::CustomPredicate`) to `builder`, in which the bound
+    /// variables of the goal are in scope. By default there are none, so
+    /// custom predicates only hold where the environment says they do.
+    fn custom_predicate_clauses(
+        &self,
+        _builder: &mut ClauseBuilder<'_, I>,
+        _predicate: &CustomPredicate<I>,
+    ) -> Result<(), Floundered> {
+        Ok(())
+    }
+
+    /// Returns the datum for the inherent associated type with the given
+    /// id, or `None` if it is not one. By default there are none, for the
+    /// hosts which have no inherent associated types.
+    fn inherent_assoc_ty_data(&self, _id: AssocTypeId<I>) -> Option<Arc<InherentAssocTyDatum<I>>> {
+        None
+    }
+
+    /// Returns the rustc features the program is solved with.
+    fn program_features(&self) -> ProgramFeatures {
+        ProgramFeatures::default()
+    }
+
+    /// Returns the variances of the parameters of the ADT or fn def
+    /// `item`. By default they are inferred from the definitions of the
+    /// items (see [`variance::infer_variances`]), so that hosts which do
+    /// not compute variances can give these from their
+    /// `UnificationDatabase`.
+    fn variance_of(&self, item: VarianceItem<I>) -> Variances<I> {
+        variance::infer_variances(self, item, &|_| None)
+    }
+
     /// Calculates program clauses from an env. This is intended to call the
     /// `program_clauses_for_env` function and then possibly cache the clauses.
     fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I>;
@@ -200,11 +337,24 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     fn discriminant_type(&self, ty: Ty<I>) -> Ty<I>;
 }
 
+pub use applicable_impls::find_applicable_impls;
 pub use clauses::program_clauses_for_env;
 
+#[cfg(feature = "std")]
+pub use solve::solve_all_parallel;
+pub use solve::ActiveStrand;
+pub use solve::AmbiguityCause;
+pub use solve::GoalOptions;
 pub use solve::Guidance;
+pub use solve::NoSolutionCause;
+pub use solve::Overflow;
 pub use solve::Solution;
 pub use solve::Solver;
+pub use solve::SolverEvent;
+pub use solve::SolverEventSink;
+pub use solve::SolverStats;
+pub use solve::SolverStep;
+pub use solve::StackFrame;
 pub use solve::SubstitutionResult;
 
 #[macro_use]
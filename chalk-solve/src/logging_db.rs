@@ -1,24 +1,30 @@
 //! Provides wrappers over `RustIrDatabase` which record used definitions and write
 //! `.chalk` files containing those definitions.
-use std::{
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{
     borrow::Borrow,
     fmt::{self, Debug, Display},
-    io::Write,
     marker::PhantomData,
-    sync::Arc,
-    sync::Mutex,
 };
+#[cfg(feature = "std")]
+use std::io::Write;
 
+use crate::clauses::builder::ClauseBuilder;
+use crate::clauses::custom_builtins::CustomBuiltin;
 use crate::rust_ir::*;
 use crate::{
     display::{self, WriterState},
-    RustIrDatabase,
+    lock,
+    simplified_type::{ImplFilter, SimplifiedType},
+    variance::VarianceItem,
+    FxIndexSet, Lock, RustIrDatabase,
 };
 use chalk_ir::{interner::Interner, *};
 
-use indexmap::IndexSet;
-
 mod id_collector;
+mod recording;
+
+pub use recording::{Recording, RecordingRustIrDatabase};
 
 /// Wraps another `RustIrDatabase` (`DB`) and records which definitions are
 /// used.
@@ -36,7 +42,7 @@ where
     I: Interner,
 {
     ws: WriterState<I, DB, P>,
-    def_ids: Mutex<IndexSet<RecordedItemId<I>>>,
+    def_ids: Lock<FxIndexSet<RecordedItemId<I>>>,
     _phantom: PhantomData<DB>,
 }
 
@@ -61,9 +67,10 @@ where
     P: Borrow<DB>,
     I: Interner,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let def_ids = self.def_ids.lock().unwrap();
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let def_ids = lock(&self.def_ids);
         let stub_ids = id_collector::collect_unrecorded_ids(self.ws.db(), &def_ids);
+        display::write_program_features(f, self.ws.db().program_features())?;
         display::write_stub_items(f, &self.ws, stub_ids)?;
         display::write_items(f, &self.ws, def_ids.iter().copied())
     }
@@ -76,7 +83,7 @@ where
     I: Interner,
 {
     fn record(&self, id: impl Into<RecordedItemId<I>>) {
-        self.def_ids.lock().unwrap().insert(id.into());
+        lock(&self.def_ids).insert(id.into());
     }
 
     fn record_all<T, U>(&self, ids: T)
@@ -84,10 +91,7 @@ where
         T: IntoIterator<Item = U>,
         U: Into<RecordedItemId<I>>,
     {
-        self.def_ids
-            .lock()
-            .unwrap()
-            .extend(ids.into_iter().map(Into::into));
+        lock(&self.def_ids).extend(ids.into_iter().map(Into::into));
     }
 }
 
@@ -180,19 +184,34 @@ where
         value
     }
 
+    fn inherent_assoc_ty_data(
+        &self,
+        id: chalk_ir::AssocTypeId<I>,
+    ) -> Option<Arc<crate::rust_ir::InherentAssocTyDatum<I>>> {
+        let ty_datum = self.ws.db().inherent_assoc_ty_data(id)?;
+        self.record(ty_datum.adt_id);
+        Some(ty_datum)
+    }
+
     fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
         self.record(id);
         self.ws.db().opaque_ty_data(id)
     }
 
-    fn impls_for_trait(
+    fn impls_for_trait(&self, trait_id: TraitId<I>, filter: &ImplFilter<'_, I>) -> Vec<ImplId<I>> {
+        self.record(trait_id);
+        let impl_ids = self.ws.db().impls_for_trait(trait_id, filter);
+        self.record_all(impl_ids.iter().copied());
+        impl_ids
+    }
+
+    fn impls_for_trait_by_self_type(
         &self,
         trait_id: TraitId<I>,
-        parameters: &[chalk_ir::GenericArg<I>],
-        binders: &CanonicalVarKinds<I>,
+        self_ty: &SimplifiedType<I>,
     ) -> Vec<ImplId<I>> {
         self.record(trait_id);
-        let impl_ids = self.ws.db().impls_for_trait(trait_id, parameters, binders);
+        let impl_ids = self.ws.db().impls_for_trait_by_self_type(trait_id, self_ty);
         self.record_all(impl_ids.iter().copied());
         impl_ids
     }
@@ -221,6 +240,27 @@ where
         trait_id
     }
 
+    fn custom_builtin(&self, trait_id: TraitId<I>) -> Option<CustomBuiltin<I>> {
+        self.record(trait_id);
+        self.ws.db().custom_builtin(trait_id)
+    }
+
+    fn custom_predicate_clauses(
+        &self,
+        builder: &mut ClauseBuilder<'_, I>,
+        predicate: &CustomPredicate<I>,
+    ) -> Result<(), Floundered> {
+        self.ws.db().custom_predicate_clauses(builder, predicate)
+    }
+
+    fn program_features(&self) -> ProgramFeatures {
+        self.ws.db().program_features()
+    }
+
+    fn variance_of(&self, item: VarianceItem<I>) -> Variances<I> {
+        self.ws.db().variance_of(item)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -306,6 +346,7 @@ where
 ///
 /// Uses a separate type, `P`, for the database stored inside to account for
 /// `Arc` or wrapping other storage mediums.
+#[cfg(feature = "std")]
 pub struct WriteOnDropRustIrDatabase<I, W, DB, P = DB>
 where
     I: Interner,
@@ -317,6 +358,7 @@ where
     write: W,
 }
 
+#[cfg(feature = "std")]
 impl<I, W, DB, P> fmt::Debug for WriteOnDropRustIrDatabase<I, W, DB, P>
 where
     I: Interner,
@@ -332,6 +374,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<I, W, DB, P> WriteOnDropRustIrDatabase<I, W, DB, P>
 where
     I: Interner,
@@ -351,6 +394,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<I, W, DB, P> Drop for WriteOnDropRustIrDatabase<I, W, DB, P>
 where
     I: Interner,
@@ -365,6 +409,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<I, W, DB, P> UnificationDatabase<I> for WriteOnDropRustIrDatabase<I, W, DB, P>
 where
     I: Interner,
@@ -384,6 +429,7 @@ where
     }
 }
 
+#[cfg(feature = "std")]
 impl<I, W, DB, P> RustIrDatabase<I> for WriteOnDropRustIrDatabase<I, W, DB, P>
 where
     I: Interner,
@@ -441,6 +487,13 @@ where
         self.db.associated_ty_value(id)
     }
 
+    fn inherent_assoc_ty_data(
+        &self,
+        id: chalk_ir::AssocTypeId<I>,
+    ) -> Option<Arc<crate::rust_ir::InherentAssocTyDatum<I>>> {
+        self.db.inherent_assoc_ty_data(id)
+    }
+
     fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
         self.db.opaque_ty_data(id)
     }
@@ -449,13 +502,16 @@ where
         self.db.hidden_opaque_type(id)
     }
 
-    fn impls_for_trait(
+    fn impls_for_trait(&self, trait_id: TraitId<I>, filter: &ImplFilter<'_, I>) -> Vec<ImplId<I>> {
+        self.db.impls_for_trait(trait_id, filter)
+    }
+
+    fn impls_for_trait_by_self_type(
         &self,
         trait_id: TraitId<I>,
-        parameters: &[chalk_ir::GenericArg<I>],
-        binders: &CanonicalVarKinds<I>,
+        self_ty: &SimplifiedType<I>,
     ) -> Vec<ImplId<I>> {
-        self.db.impls_for_trait(trait_id, parameters, binders)
+        self.db.impls_for_trait_by_self_type(trait_id, self_ty)
     }
 
     fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
@@ -473,6 +529,26 @@ where
         self.db.well_known_trait_id(well_known_trait)
     }
 
+    fn custom_builtin(&self, trait_id: TraitId<I>) -> Option<CustomBuiltin<I>> {
+        self.db.custom_builtin(trait_id)
+    }
+
+    fn custom_predicate_clauses(
+        &self,
+        builder: &mut ClauseBuilder<'_, I>,
+        predicate: &CustomPredicate<I>,
+    ) -> Result<(), Floundered> {
+        self.db.custom_predicate_clauses(builder, predicate)
+    }
+
+    fn program_features(&self) -> ProgramFeatures {
+        self.db.program_features()
+    }
+
+    fn variance_of(&self, item: VarianceItem<I>) -> Variances<I> {
+        self.db.variance_of(item)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -1,14 +1,12 @@
 use super::RecordedItemId;
-use crate::RustIrDatabase;
+use crate::{FxIndexSet, RustIrDatabase};
 use chalk_ir::{
     interner::Interner,
     visit::Visitor,
     visit::{SuperVisit, Visit},
     AliasTy, DebruijnIndex, TyKind, WhereClause,
 };
-use std::ops::ControlFlow;
-
-use indexmap::IndexSet;
+use core::ops::ControlFlow;
 
 /// Collects the identifiers needed to resolve all the names for a given
 /// set of identifers, excluding identifiers we already have.
@@ -35,11 +33,11 @@ use indexmap::IndexSet;
 /// resolution is successful.
 pub fn collect_unrecorded_ids<I: Interner, DB: RustIrDatabase<I>>(
     db: &DB,
-    identifiers: &'_ IndexSet<RecordedItemId<I>>,
-) -> IndexSet<RecordedItemId<I>> {
+    identifiers: &'_ FxIndexSet<RecordedItemId<I>>,
+) -> FxIndexSet<RecordedItemId<I>> {
     let mut collector = IdCollector {
         db,
-        found_identifiers: IndexSet::new(),
+        found_identifiers: FxIndexSet::default(),
     };
     for id in identifiers {
         match *id {
@@ -97,7 +95,7 @@ pub fn collect_unrecorded_ids<I: Interner, DB: RustIrDatabase<I>>(
 
 struct IdCollector<'i, I: Interner, DB: RustIrDatabase<I>> {
     db: &'i DB,
-    found_identifiers: IndexSet<RecordedItemId<I>>,
+    found_identifiers: FxIndexSet<RecordedItemId<I>>,
 }
 
 impl<'i, I: Interner, DB: RustIrDatabase<I>> IdCollector<'i, I, DB> {
@@ -112,6 +110,13 @@ impl<'i, I: Interner, DB: RustIrDatabase<I>> IdCollector<'i, I, DB> {
                 self.record(assoc_ty_datum.trait_id)
             }
             AliasTy::Opaque(opaque_ty) => self.record(opaque_ty.opaque_ty_id),
+            AliasTy::Inherent(inherent_ty) => {
+                if let Some(inherent_ty_datum) =
+                    self.db.inherent_assoc_ty_data(inherent_ty.associated_ty_id)
+                {
+                    self.record(inherent_ty_datum.adt_id)
+                }
+            }
         }
     }
 }
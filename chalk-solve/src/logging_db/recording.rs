@@ -0,0 +1,699 @@
+//! Records the calls made to a `RustIrDatabase`, and their results, so that
+//! a solve can be replayed without the database that was recorded.
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{
+    borrow::Borrow,
+    fmt::{self, Debug},
+    hash::Hash,
+    marker::PhantomData,
+};
+
+use crate::clauses::builder::ClauseBuilder;
+use crate::clauses::custom_builtins::CustomBuiltin;
+use crate::rust_ir::*;
+use crate::{
+    into_inner, lock,
+    simplified_type::{ImplFilter, SimplifiedType},
+    variance::VarianceItem,
+    FxHashMap, Lock, RustIrDatabase,
+};
+use chalk_ir::{interner::Interner, *};
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+
+/// Wraps another `RustIrDatabase` (`DB`) and records every call made to it,
+/// together with its result, in a [`Recording`].
+///
+/// Where [`LoggingRustIrDatabase`] writes out the definitions used as a
+/// `.chalk` program, the recording holds exactly the answers the database
+/// gave, so it reproduces a solve even if the database can't be written
+/// as a program (e.g. because of closures or hidden opaque types).
+///
+/// Uses a separate type, `P`, for the database stored inside to account for
+/// `Arc` or wrapping other storage mediums.
+///
+/// [`LoggingRustIrDatabase`]: super::LoggingRustIrDatabase
+pub struct RecordingRustIrDatabase<I, DB, P = DB>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB>,
+    I: Interner,
+{
+    db: P,
+    recording: Lock<Recording<I>>,
+    _phantom: PhantomData<DB>,
+}
+
+impl<I, DB, P> RecordingRustIrDatabase<I, DB, P>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB>,
+    I: Interner,
+{
+    pub fn new(db: P) -> Self {
+        let interner = db.borrow().interner();
+        RecordingRustIrDatabase {
+            db,
+            recording: Lock::new(Recording::new(interner)),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the calls recorded so far.
+    pub fn recording(&self) -> Recording<I> {
+        lock(&self.recording).clone()
+    }
+
+    pub fn into_recording(self) -> Recording<I> {
+        into_inner(self.recording)
+    }
+
+    fn db(&self) -> &DB {
+        self.db.borrow()
+    }
+
+    fn record<K, V>(
+        &self,
+        calls: fn(&mut Recording<I>) -> &mut FxHashMap<K, V>,
+        key: K,
+        value: V,
+    ) -> V
+    where
+        K: Eq + Hash,
+        V: Clone,
+    {
+        calls(&mut lock(&self.recording)).insert(key, value.clone());
+        value
+    }
+}
+
+impl<I, DB, P> Debug for RecordingRustIrDatabase<I, DB, P>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+    I: Interner,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingRustIrDatabase")
+            .field("db", &self.db)
+            .finish()
+    }
+}
+
+/// The arguments of `impls_for_trait`: the trait, and the parameters and
+/// binders of the `ImplFilter`.
+type ImplsForTraitKey<I> = (TraitId<I>, Vec<GenericArg<I>>, CanonicalVarKinds<I>);
+
+/// The arguments of `impls_for_trait_by_self_type`.
+type SelfTypeKey<I> = (TraitId<I>, SimplifiedType<I>);
+
+/// The arguments of the `closure_*` methods.
+type ClosureKey<I> = (ClosureId<I>, Substitution<I>);
+
+/// The calls made to a database wrapped in a [`RecordingRustIrDatabase`],
+/// keyed by their arguments, with the results they returned.
+///
+/// A recording is itself a `RustIrDatabase` which replays the recorded
+/// results, so that the solve that was recorded can be run again on it
+/// alone. Asking it for anything that wasn't recorded panics. With the
+/// `serde` feature, recordings can be serialized, e.g. to attach a
+/// self-contained reproduction of a solver bug to an issue.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: chalk_ir::serialize::SerdeInterner")
+)]
+pub struct Recording<I: Interner> {
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip, default = "chalk_ir::serialize::SerdeInterner::serde_interner")
+    )]
+    interner: I,
+    custom_clauses: Option<Vec<ProgramClause<I>>>,
+    associated_ty_data: FxHashMap<AssocTypeId<I>, Arc<AssociatedTyDatum<I>>>,
+    trait_datum: FxHashMap<TraitId<I>, Arc<TraitDatum<I>>>,
+    adt_datum: FxHashMap<AdtId<I>, Arc<AdtDatum<I>>>,
+    generator_datum: FxHashMap<GeneratorId<I>, Arc<GeneratorDatum<I>>>,
+    generator_witness_datum: FxHashMap<GeneratorId<I>, Arc<GeneratorWitnessDatum<I>>>,
+    adt_repr: FxHashMap<AdtId<I>, Arc<AdtRepr<I>>>,
+    adt_size_align: FxHashMap<AdtId<I>, Arc<AdtSizeAlign>>,
+    fn_def_datum: FxHashMap<FnDefId<I>, Arc<FnDefDatum<I>>>,
+    impl_datum: FxHashMap<ImplId<I>, Arc<ImplDatum<I>>>,
+    associated_ty_value: FxHashMap<AssociatedTyValueId<I>, Arc<AssociatedTyValue<I>>>,
+    inherent_assoc_ty_data: FxHashMap<AssocTypeId<I>, Option<Arc<InherentAssocTyDatum<I>>>>,
+    opaque_ty_data: FxHashMap<OpaqueTyId<I>, Arc<OpaqueTyDatum<I>>>,
+    hidden_opaque_type: FxHashMap<OpaqueTyId<I>, Ty<I>>,
+    impls_for_trait: FxHashMap<ImplsForTraitKey<I>, Vec<ImplId<I>>>,
+    impls_for_trait_by_self_type: FxHashMap<SelfTypeKey<I>, Vec<ImplId<I>>>,
+    local_impls_to_coherence_check: FxHashMap<TraitId<I>, Vec<ImplId<I>>>,
+    impl_provided_for: FxHashMap<(TraitId<I>, TyKind<I>), bool>,
+    well_known_trait_id: FxHashMap<WellKnownTrait, Option<TraitId<I>>>,
+    program_features: Option<ProgramFeatures>,
+    program_clauses_for_env: FxHashMap<Environment<I>, ProgramClauses<I>>,
+    is_object_safe: FxHashMap<TraitId<I>, bool>,
+    closure_kind: FxHashMap<ClosureKey<I>, ClosureKind>,
+    closure_inputs_and_output: FxHashMap<ClosureKey<I>, Binders<FnDefInputsAndOutputDatum<I>>>,
+    closure_upvars: FxHashMap<ClosureKey<I>, Binders<Ty<I>>>,
+    closure_fn_substitution: FxHashMap<ClosureKey<I>, Substitution<I>>,
+    trait_name: FxHashMap<TraitId<I>, String>,
+    adt_name: FxHashMap<AdtId<I>, String>,
+    assoc_type_name: FxHashMap<AssocTypeId<I>, String>,
+    opaque_type_name: FxHashMap<OpaqueTyId<I>, String>,
+    fn_def_name: FxHashMap<FnDefId<I>, String>,
+    discriminant_type: FxHashMap<Ty<I>, Ty<I>>,
+    fn_def_variance: FxHashMap<FnDefId<I>, Variances<I>>,
+    adt_variance: FxHashMap<AdtId<I>, Variances<I>>,
+    variance_of: FxHashMap<VarianceItem<I>, Variances<I>>,
+}
+
+impl<I: Interner> Recording<I> {
+    fn new(interner: I) -> Self {
+        Recording {
+            interner,
+            custom_clauses: Default::default(),
+            associated_ty_data: Default::default(),
+            trait_datum: Default::default(),
+            adt_datum: Default::default(),
+            generator_datum: Default::default(),
+            generator_witness_datum: Default::default(),
+            adt_repr: Default::default(),
+            adt_size_align: Default::default(),
+            fn_def_datum: Default::default(),
+            impl_datum: Default::default(),
+            associated_ty_value: Default::default(),
+            inherent_assoc_ty_data: Default::default(),
+            opaque_ty_data: Default::default(),
+            hidden_opaque_type: Default::default(),
+            impls_for_trait: Default::default(),
+            impls_for_trait_by_self_type: Default::default(),
+            local_impls_to_coherence_check: Default::default(),
+            impl_provided_for: Default::default(),
+            well_known_trait_id: Default::default(),
+            program_features: Default::default(),
+            program_clauses_for_env: Default::default(),
+            is_object_safe: Default::default(),
+            closure_kind: Default::default(),
+            closure_inputs_and_output: Default::default(),
+            closure_upvars: Default::default(),
+            closure_fn_substitution: Default::default(),
+            trait_name: Default::default(),
+            adt_name: Default::default(),
+            assoc_type_name: Default::default(),
+            opaque_type_name: Default::default(),
+            fn_def_name: Default::default(),
+            discriminant_type: Default::default(),
+            fn_def_variance: Default::default(),
+            adt_variance: Default::default(),
+            variance_of: Default::default(),
+        }
+    }
+
+    /// Returns the result recorded for the call of `method` with `key`.
+    fn replay<K, V>(method: &str, calls: &FxHashMap<K, V>, key: &K) -> V
+    where
+        K: Eq + Hash + Debug,
+        V: Clone,
+    {
+        match calls.get(key) {
+            Some(value) => value.clone(),
+            None => panic!("`{}` was not called with {:?} when recording", method, key),
+        }
+    }
+}
+
+impl<I, DB, P> UnificationDatabase<I> for RecordingRustIrDatabase<I, DB, P>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+    I: Interner,
+{
+    fn fn_def_variance(&self, fn_def_id: FnDefId<I>) -> Variances<I> {
+        let variances = self.db().unification_database().fn_def_variance(fn_def_id);
+        self.record(|r| &mut r.fn_def_variance, fn_def_id, variances)
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<I>) -> Variances<I> {
+        let variances = self.db().unification_database().adt_variance(adt_id);
+        self.record(|r| &mut r.adt_variance, adt_id, variances)
+    }
+}
+
+impl<I, DB, P> RustIrDatabase<I> for RecordingRustIrDatabase<I, DB, P>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+    I: Interner,
+{
+    fn custom_clauses(&self) -> Vec<ProgramClause<I>> {
+        let clauses = self.db().custom_clauses();
+        lock(&self.recording).custom_clauses = Some(clauses.clone());
+        clauses
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<I>) -> Arc<AssociatedTyDatum<I>> {
+        let datum = self.db().associated_ty_data(ty);
+        self.record(|r| &mut r.associated_ty_data, ty, datum)
+    }
+
+    fn trait_datum(&self, trait_id: TraitId<I>) -> Arc<TraitDatum<I>> {
+        let datum = self.db().trait_datum(trait_id);
+        self.record(|r| &mut r.trait_datum, trait_id, datum)
+    }
+
+    fn adt_datum(&self, adt_id: AdtId<I>) -> Arc<AdtDatum<I>> {
+        let datum = self.db().adt_datum(adt_id);
+        self.record(|r| &mut r.adt_datum, adt_id, datum)
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        let datum = self.db().generator_datum(generator_id);
+        self.record(|r| &mut r.generator_datum, generator_id, datum)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        let datum = self.db().generator_witness_datum(generator_id);
+        self.record(|r| &mut r.generator_witness_datum, generator_id, datum)
+    }
+
+    fn adt_repr(&self, id: AdtId<I>) -> Arc<AdtRepr<I>> {
+        let repr = self.db().adt_repr(id);
+        self.record(|r| &mut r.adt_repr, id, repr)
+    }
+
+    fn adt_size_align(&self, id: AdtId<I>) -> Arc<AdtSizeAlign> {
+        let size_align = self.db().adt_size_align(id);
+        self.record(|r| &mut r.adt_size_align, id, size_align)
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>> {
+        let datum = self.db().fn_def_datum(fn_def_id);
+        self.record(|r| &mut r.fn_def_datum, fn_def_id, datum)
+    }
+
+    fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>> {
+        let datum = self.db().impl_datum(impl_id);
+        self.record(|r| &mut r.impl_datum, impl_id, datum)
+    }
+
+    fn associated_ty_value(&self, id: AssociatedTyValueId<I>) -> Arc<AssociatedTyValue<I>> {
+        let value = self.db().associated_ty_value(id);
+        self.record(|r| &mut r.associated_ty_value, id, value)
+    }
+
+    fn inherent_assoc_ty_data(&self, id: AssocTypeId<I>) -> Option<Arc<InherentAssocTyDatum<I>>> {
+        let datum = self.db().inherent_assoc_ty_data(id);
+        self.record(|r| &mut r.inherent_assoc_ty_data, id, datum)
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
+        let datum = self.db().opaque_ty_data(id);
+        self.record(|r| &mut r.opaque_ty_data, id, datum)
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I> {
+        let ty = self.db().hidden_opaque_type(id);
+        self.record(|r| &mut r.hidden_opaque_type, id, ty)
+    }
+
+    fn impls_for_trait(&self, trait_id: TraitId<I>, filter: &ImplFilter<'_, I>) -> Vec<ImplId<I>> {
+        let impl_ids = self.db().impls_for_trait(trait_id, filter);
+        let key = (
+            trait_id,
+            filter.parameters().to_vec(),
+            filter.binders().clone(),
+        );
+        self.record(|r| &mut r.impls_for_trait, key, impl_ids)
+    }
+
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: TraitId<I>,
+        self_ty: &SimplifiedType<I>,
+    ) -> Vec<ImplId<I>> {
+        let impl_ids = self.db().impls_for_trait_by_self_type(trait_id, self_ty);
+        self.record(
+            |r| &mut r.impls_for_trait_by_self_type,
+            (trait_id, *self_ty),
+            impl_ids,
+        )
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
+        let impl_ids = self.db().local_impls_to_coherence_check(trait_id);
+        self.record(
+            |r| &mut r.local_impls_to_coherence_check,
+            trait_id,
+            impl_ids,
+        )
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<I>, ty: &TyKind<I>) -> bool {
+        let provided = self.db().impl_provided_for(auto_trait_id, ty);
+        self.record(
+            |r| &mut r.impl_provided_for,
+            (auto_trait_id, ty.clone()),
+            provided,
+        )
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>> {
+        let trait_id = self.db().well_known_trait_id(well_known_trait);
+        self.record(|r| &mut r.well_known_trait_id, well_known_trait, trait_id)
+    }
+
+    // Not recorded, as the generators are closures: replays of programs
+    // using custom builtins do not have them.
+    fn custom_builtin(&self, trait_id: TraitId<I>) -> Option<CustomBuiltin<I>> {
+        self.db().custom_builtin(trait_id)
+    }
+
+    fn custom_predicate_clauses(
+        &self,
+        builder: &mut ClauseBuilder<'_, I>,
+        predicate: &CustomPredicate<I>,
+    ) -> Result<(), Floundered> {
+        self.db().custom_predicate_clauses(builder, predicate)
+    }
+
+    fn program_features(&self) -> ProgramFeatures {
+        let features = self.db().program_features();
+        lock(&self.recording).program_features = Some(features);
+        features
+    }
+
+    fn variance_of(&self, item: VarianceItem<I>) -> Variances<I> {
+        let variances = self.db().variance_of(item);
+        self.record(|r| &mut r.variance_of, item, variances)
+    }
+
+    fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I> {
+        let clauses = self.db().program_clauses_for_env(environment);
+        self.record(
+            |r| &mut r.program_clauses_for_env,
+            environment.clone(),
+            clauses,
+        )
+    }
+
+    fn interner(&self) -> I {
+        self.db().interner()
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        let object_safe = self.db().is_object_safe(trait_id);
+        self.record(|r| &mut r.is_object_safe, trait_id, object_safe)
+    }
+
+    fn closure_kind(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> ClosureKind {
+        let kind = self.db().closure_kind(closure_id, substs);
+        self.record(|r| &mut r.closure_kind, (closure_id, substs.clone()), kind)
+    }
+
+    fn closure_inputs_and_output(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Binders<FnDefInputsAndOutputDatum<I>> {
+        let inputs_and_output = self.db().closure_inputs_and_output(closure_id, substs);
+        self.record(
+            |r| &mut r.closure_inputs_and_output,
+            (closure_id, substs.clone()),
+            inputs_and_output,
+        )
+    }
+
+    fn closure_upvars(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> Binders<Ty<I>> {
+        let upvars = self.db().closure_upvars(closure_id, substs);
+        self.record(
+            |r| &mut r.closure_upvars,
+            (closure_id, substs.clone()),
+            upvars,
+        )
+    }
+
+    fn closure_fn_substitution(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Substitution<I> {
+        let substitution = self.db().closure_fn_substitution(closure_id, substs);
+        self.record(
+            |r| &mut r.closure_fn_substitution,
+            (closure_id, substs.clone()),
+            substitution,
+        )
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self
+    }
+
+    fn trait_name(&self, trait_id: TraitId<I>) -> String {
+        let name = self.db().trait_name(trait_id);
+        self.record(|r| &mut r.trait_name, trait_id, name)
+    }
+
+    fn adt_name(&self, adt_id: AdtId<I>) -> String {
+        let name = self.db().adt_name(adt_id);
+        self.record(|r| &mut r.adt_name, adt_id, name)
+    }
+
+    fn assoc_type_name(&self, assoc_ty_id: AssocTypeId<I>) -> String {
+        let name = self.db().assoc_type_name(assoc_ty_id);
+        self.record(|r| &mut r.assoc_type_name, assoc_ty_id, name)
+    }
+
+    fn opaque_type_name(&self, opaque_ty_id: OpaqueTyId<I>) -> String {
+        let name = self.db().opaque_type_name(opaque_ty_id);
+        self.record(|r| &mut r.opaque_type_name, opaque_ty_id, name)
+    }
+
+    fn fn_def_name(&self, fn_def_id: FnDefId<I>) -> String {
+        let name = self.db().fn_def_name(fn_def_id);
+        self.record(|r| &mut r.fn_def_name, fn_def_id, name)
+    }
+
+    fn discriminant_type(&self, ty: Ty<I>) -> Ty<I> {
+        let discriminant = self.db().discriminant_type(ty.clone());
+        self.record(|r| &mut r.discriminant_type, ty, discriminant)
+    }
+}
+
+impl<I: Interner> UnificationDatabase<I> for Recording<I> {
+    fn fn_def_variance(&self, fn_def_id: FnDefId<I>) -> Variances<I> {
+        Self::replay("fn_def_variance", &self.fn_def_variance, &fn_def_id)
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<I>) -> Variances<I> {
+        Self::replay("adt_variance", &self.adt_variance, &adt_id)
+    }
+}
+
+impl<I: Interner> RustIrDatabase<I> for Recording<I> {
+    fn custom_clauses(&self) -> Vec<ProgramClause<I>> {
+        self.custom_clauses
+            .clone()
+            .expect("`custom_clauses` was not called when recording")
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<I>) -> Arc<AssociatedTyDatum<I>> {
+        Self::replay("associated_ty_data", &self.associated_ty_data, &ty)
+    }
+
+    fn trait_datum(&self, trait_id: TraitId<I>) -> Arc<TraitDatum<I>> {
+        Self::replay("trait_datum", &self.trait_datum, &trait_id)
+    }
+
+    fn adt_datum(&self, adt_id: AdtId<I>) -> Arc<AdtDatum<I>> {
+        Self::replay("adt_datum", &self.adt_datum, &adt_id)
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        Self::replay("generator_datum", &self.generator_datum, &generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        Self::replay(
+            "generator_witness_datum",
+            &self.generator_witness_datum,
+            &generator_id,
+        )
+    }
+
+    fn adt_repr(&self, id: AdtId<I>) -> Arc<AdtRepr<I>> {
+        Self::replay("adt_repr", &self.adt_repr, &id)
+    }
+
+    fn adt_size_align(&self, id: AdtId<I>) -> Arc<AdtSizeAlign> {
+        Self::replay("adt_size_align", &self.adt_size_align, &id)
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>> {
+        Self::replay("fn_def_datum", &self.fn_def_datum, &fn_def_id)
+    }
+
+    fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>> {
+        Self::replay("impl_datum", &self.impl_datum, &impl_id)
+    }
+
+    fn associated_ty_value(&self, id: AssociatedTyValueId<I>) -> Arc<AssociatedTyValue<I>> {
+        Self::replay("associated_ty_value", &self.associated_ty_value, &id)
+    }
+
+    fn inherent_assoc_ty_data(&self, id: AssocTypeId<I>) -> Option<Arc<InherentAssocTyDatum<I>>> {
+        Self::replay("inherent_assoc_ty_data", &self.inherent_assoc_ty_data, &id)
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
+        Self::replay("opaque_ty_data", &self.opaque_ty_data, &id)
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I> {
+        Self::replay("hidden_opaque_type", &self.hidden_opaque_type, &id)
+    }
+
+    fn impls_for_trait(&self, trait_id: TraitId<I>, filter: &ImplFilter<'_, I>) -> Vec<ImplId<I>> {
+        let key = (
+            trait_id,
+            filter.parameters().to_vec(),
+            filter.binders().clone(),
+        );
+        Self::replay("impls_for_trait", &self.impls_for_trait, &key)
+    }
+
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: TraitId<I>,
+        self_ty: &SimplifiedType<I>,
+    ) -> Vec<ImplId<I>> {
+        Self::replay(
+            "impls_for_trait_by_self_type",
+            &self.impls_for_trait_by_self_type,
+            &(trait_id, *self_ty),
+        )
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
+        Self::replay(
+            "local_impls_to_coherence_check",
+            &self.local_impls_to_coherence_check,
+            &trait_id,
+        )
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<I>, ty: &TyKind<I>) -> bool {
+        Self::replay(
+            "impl_provided_for",
+            &self.impl_provided_for,
+            &(auto_trait_id, ty.clone()),
+        )
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>> {
+        Self::replay(
+            "well_known_trait_id",
+            &self.well_known_trait_id,
+            &well_known_trait,
+        )
+    }
+
+    fn program_features(&self) -> ProgramFeatures {
+        self.program_features
+            .expect("`program_features` was not called when recording")
+    }
+
+    fn variance_of(&self, item: VarianceItem<I>) -> Variances<I> {
+        Self::replay("variance_of", &self.variance_of, &item)
+    }
+
+    fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I> {
+        Self::replay(
+            "program_clauses_for_env",
+            &self.program_clauses_for_env,
+            environment,
+        )
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        Self::replay("is_object_safe", &self.is_object_safe, &trait_id)
+    }
+
+    fn closure_kind(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> ClosureKind {
+        Self::replay(
+            "closure_kind",
+            &self.closure_kind,
+            &(closure_id, substs.clone()),
+        )
+    }
+
+    fn closure_inputs_and_output(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Binders<FnDefInputsAndOutputDatum<I>> {
+        Self::replay(
+            "closure_inputs_and_output",
+            &self.closure_inputs_and_output,
+            &(closure_id, substs.clone()),
+        )
+    }
+
+    fn closure_upvars(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> Binders<Ty<I>> {
+        Self::replay(
+            "closure_upvars",
+            &self.closure_upvars,
+            &(closure_id, substs.clone()),
+        )
+    }
+
+    fn closure_fn_substitution(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Substitution<I> {
+        Self::replay(
+            "closure_fn_substitution",
+            &self.closure_fn_substitution,
+            &(closure_id, substs.clone()),
+        )
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self
+    }
+
+    fn trait_name(&self, trait_id: TraitId<I>) -> String {
+        Self::replay("trait_name", &self.trait_name, &trait_id)
+    }
+
+    fn adt_name(&self, adt_id: AdtId<I>) -> String {
+        Self::replay("adt_name", &self.adt_name, &adt_id)
+    }
+
+    fn assoc_type_name(&self, assoc_ty_id: AssocTypeId<I>) -> String {
+        Self::replay("assoc_type_name", &self.assoc_type_name, &assoc_ty_id)
+    }
+
+    fn opaque_type_name(&self, opaque_ty_id: OpaqueTyId<I>) -> String {
+        Self::replay("opaque_type_name", &self.opaque_type_name, &opaque_ty_id)
+    }
+
+    fn fn_def_name(&self, fn_def_id: FnDefId<I>) -> String {
+        Self::replay("fn_def_name", &self.fn_def_name, &fn_def_id)
+    }
+
+    fn discriminant_type(&self, ty: Ty<I>) -> Ty<I> {
+        Self::replay("discriminant_type", &self.discriminant_type, &ty)
+    }
+}
@@ -0,0 +1,428 @@
+//! Provides a wrapper over `RustIrDatabase` which caches the data it
+//! returns, for databases that build their data on every call.
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{
+    borrow::Borrow,
+    cell::RefCell,
+    fmt::{self, Debug},
+    hash::Hash,
+    marker::PhantomData,
+};
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+use crate::clauses::builder::ClauseBuilder;
+use crate::clauses::custom_builtins::CustomBuiltin;
+use crate::rust_ir::*;
+use crate::FxHashMap;
+use crate::{
+    simplified_type::{ImplFilter, SimplifiedType},
+    variance::VarianceItem,
+    RustIrDatabase,
+};
+use chalk_ir::{interner::Interner, *};
+
+/// Wraps another `RustIrDatabase` (`DB`) and caches the data it returns
+/// for definitions (`trait_datum`, `impl_datum`, `adt_datum` and so on),
+/// as well as `well_known_trait_id`, `is_object_safe`, the variances and
+/// `program_clauses_for_env`, so each of them is only computed once.
+/// Queries whose answer depends on more than a definition's id, like
+/// `impls_for_trait`, are passed through.
+///
+/// The cache is kept in `S`, which is a `RefCell` by default; use
+/// [`SyncMemoizingDatabase`] to share the database between threads.
+/// The wrapped database is assumed not to change; [`clear`] the cache
+/// if it does.
+///
+/// Uses a separate type, `P`, for the database stored inside to account for
+/// `Arc` or wrapping other storage mediums.
+///
+/// [`clear`]: MemoizingDatabase::clear
+pub struct MemoizingDatabase<I, DB, P = DB, S = RefCell<Memos<I>>>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB>,
+    S: MemoStorage<I>,
+    I: Interner,
+{
+    db: P,
+    memos: S,
+    _phantom: PhantomData<(I, DB)>,
+}
+
+/// A [`MemoizingDatabase`] whose cache is behind a `Mutex`, so that it is
+/// `Sync` if the wrapped database is.
+#[cfg(feature = "std")]
+pub type SyncMemoizingDatabase<I, DB, P = DB> = MemoizingDatabase<I, DB, P, Mutex<Memos<I>>>;
+
+/// Interior mutability for the cache of a [`MemoizingDatabase`].
+pub trait MemoStorage<I: Interner>: Default {
+    /// Calls `op` with the cache. `op` never calls back into the storage.
+    fn with<R>(&self, op: impl FnOnce(&mut Memos<I>) -> R) -> R;
+}
+
+impl<I: Interner> MemoStorage<I> for RefCell<Memos<I>> {
+    fn with<R>(&self, op: impl FnOnce(&mut Memos<I>) -> R) -> R {
+        op(&mut self.borrow_mut())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I: Interner> MemoStorage<I> for Mutex<Memos<I>> {
+    fn with<R>(&self, op: impl FnOnce(&mut Memos<I>) -> R) -> R {
+        op(&mut self.lock().unwrap())
+    }
+}
+
+/// The data cached by a [`MemoizingDatabase`].
+#[derive(Debug)]
+pub struct Memos<I: Interner> {
+    associated_ty_data: FxHashMap<AssocTypeId<I>, Arc<AssociatedTyDatum<I>>>,
+    trait_datum: FxHashMap<TraitId<I>, Arc<TraitDatum<I>>>,
+    adt_datum: FxHashMap<AdtId<I>, Arc<AdtDatum<I>>>,
+    generator_datum: FxHashMap<GeneratorId<I>, Arc<GeneratorDatum<I>>>,
+    generator_witness_datum: FxHashMap<GeneratorId<I>, Arc<GeneratorWitnessDatum<I>>>,
+    adt_repr: FxHashMap<AdtId<I>, Arc<AdtRepr<I>>>,
+    adt_size_align: FxHashMap<AdtId<I>, Arc<AdtSizeAlign>>,
+    fn_def_datum: FxHashMap<FnDefId<I>, Arc<FnDefDatum<I>>>,
+    impl_datum: FxHashMap<ImplId<I>, Arc<ImplDatum<I>>>,
+    associated_ty_value: FxHashMap<AssociatedTyValueId<I>, Arc<AssociatedTyValue<I>>>,
+    inherent_assoc_ty_data: FxHashMap<AssocTypeId<I>, Option<Arc<InherentAssocTyDatum<I>>>>,
+    opaque_ty_data: FxHashMap<OpaqueTyId<I>, Arc<OpaqueTyDatum<I>>>,
+    hidden_opaque_type: FxHashMap<OpaqueTyId<I>, Ty<I>>,
+    well_known_trait_id: FxHashMap<WellKnownTrait, Option<TraitId<I>>>,
+    is_object_safe: FxHashMap<TraitId<I>, bool>,
+    program_clauses_for_env: FxHashMap<Environment<I>, ProgramClauses<I>>,
+    fn_def_variance: FxHashMap<FnDefId<I>, Variances<I>>,
+    adt_variance: FxHashMap<AdtId<I>, Variances<I>>,
+}
+
+impl<I: Interner> Default for Memos<I> {
+    fn default() -> Self {
+        Memos {
+            associated_ty_data: Default::default(),
+            trait_datum: Default::default(),
+            adt_datum: Default::default(),
+            generator_datum: Default::default(),
+            generator_witness_datum: Default::default(),
+            adt_repr: Default::default(),
+            adt_size_align: Default::default(),
+            fn_def_datum: Default::default(),
+            impl_datum: Default::default(),
+            associated_ty_value: Default::default(),
+            inherent_assoc_ty_data: Default::default(),
+            opaque_ty_data: Default::default(),
+            hidden_opaque_type: Default::default(),
+            well_known_trait_id: Default::default(),
+            is_object_safe: Default::default(),
+            program_clauses_for_env: Default::default(),
+            fn_def_variance: Default::default(),
+            adt_variance: Default::default(),
+        }
+    }
+}
+
+impl<I, DB, P, S> MemoizingDatabase<I, DB, P, S>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB>,
+    S: MemoStorage<I>,
+    I: Interner,
+{
+    pub fn new(db: P) -> Self {
+        MemoizingDatabase {
+            db,
+            memos: S::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Forgets everything cached so far.
+    pub fn clear(&self) {
+        self.memos.with(|memos| *memos = Memos::default());
+    }
+
+    fn db(&self) -> &DB {
+        self.db.borrow()
+    }
+
+    /// Returns the value cached in `memos` for `key`, computing it with
+    /// `compute` if there is none. The cache isn't borrowed while
+    /// computing, in case the wrapped database calls back into this one.
+    fn memoize<K, V>(
+        &self,
+        memos: fn(&mut Memos<I>) -> &mut FxHashMap<K, V>,
+        key: &K,
+        compute: impl FnOnce(&DB) -> V,
+    ) -> V
+    where
+        K: Clone + Eq + Hash,
+        V: Clone,
+    {
+        if let Some(value) = self.memos.with(|m| memos(m).get(key).cloned()) {
+            return value;
+        }
+        let value = compute(self.db());
+        self.memos
+            .with(|m| memos(m).entry(key.clone()).or_insert(value).clone())
+    }
+}
+
+impl<I, DB, P, S> Debug for MemoizingDatabase<I, DB, P, S>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+    S: MemoStorage<I>,
+    I: Interner,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoizingDatabase")
+            .field("db", &self.db)
+            .finish()
+    }
+}
+
+impl<I, DB, P, S> UnificationDatabase<I> for MemoizingDatabase<I, DB, P, S>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+    S: MemoStorage<I>,
+    I: Interner,
+{
+    fn fn_def_variance(&self, fn_def_id: FnDefId<I>) -> Variances<I> {
+        self.memoize(
+            |m| &mut m.fn_def_variance,
+            &fn_def_id,
+            |db| db.unification_database().fn_def_variance(fn_def_id),
+        )
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<I>) -> Variances<I> {
+        self.memoize(
+            |m| &mut m.adt_variance,
+            &adt_id,
+            |db| db.unification_database().adt_variance(adt_id),
+        )
+    }
+}
+
+impl<I, DB, P, S> RustIrDatabase<I> for MemoizingDatabase<I, DB, P, S>
+where
+    DB: RustIrDatabase<I>,
+    P: Borrow<DB> + Debug,
+    S: MemoStorage<I>,
+    I: Interner,
+{
+    fn custom_clauses(&self) -> Vec<ProgramClause<I>> {
+        self.db().custom_clauses()
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<I>) -> Arc<AssociatedTyDatum<I>> {
+        self.memoize(
+            |m| &mut m.associated_ty_data,
+            &ty,
+            |db| db.associated_ty_data(ty),
+        )
+    }
+
+    fn trait_datum(&self, trait_id: TraitId<I>) -> Arc<TraitDatum<I>> {
+        self.memoize(
+            |m| &mut m.trait_datum,
+            &trait_id,
+            |db| db.trait_datum(trait_id),
+        )
+    }
+
+    fn adt_datum(&self, adt_id: AdtId<I>) -> Arc<AdtDatum<I>> {
+        self.memoize(|m| &mut m.adt_datum, &adt_id, |db| db.adt_datum(adt_id))
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        self.memoize(
+            |m| &mut m.generator_datum,
+            &generator_id,
+            |db| db.generator_datum(generator_id),
+        )
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        self.memoize(
+            |m| &mut m.generator_witness_datum,
+            &generator_id,
+            |db| db.generator_witness_datum(generator_id),
+        )
+    }
+
+    fn adt_repr(&self, id: AdtId<I>) -> Arc<AdtRepr<I>> {
+        self.memoize(|m| &mut m.adt_repr, &id, |db| db.adt_repr(id))
+    }
+
+    fn adt_size_align(&self, id: AdtId<I>) -> Arc<AdtSizeAlign> {
+        self.memoize(|m| &mut m.adt_size_align, &id, |db| db.adt_size_align(id))
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>> {
+        self.memoize(
+            |m| &mut m.fn_def_datum,
+            &fn_def_id,
+            |db| db.fn_def_datum(fn_def_id),
+        )
+    }
+
+    fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>> {
+        self.memoize(|m| &mut m.impl_datum, &impl_id, |db| db.impl_datum(impl_id))
+    }
+
+    fn associated_ty_value(&self, id: AssociatedTyValueId<I>) -> Arc<AssociatedTyValue<I>> {
+        self.memoize(
+            |m| &mut m.associated_ty_value,
+            &id,
+            |db| db.associated_ty_value(id),
+        )
+    }
+
+    fn inherent_assoc_ty_data(&self, id: AssocTypeId<I>) -> Option<Arc<InherentAssocTyDatum<I>>> {
+        self.memoize(
+            |m| &mut m.inherent_assoc_ty_data,
+            &id,
+            |db| db.inherent_assoc_ty_data(id),
+        )
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
+        self.memoize(|m| &mut m.opaque_ty_data, &id, |db| db.opaque_ty_data(id))
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I> {
+        self.memoize(
+            |m| &mut m.hidden_opaque_type,
+            &id,
+            |db| db.hidden_opaque_type(id),
+        )
+    }
+
+    fn impls_for_trait(&self, trait_id: TraitId<I>, filter: &ImplFilter<'_, I>) -> Vec<ImplId<I>> {
+        self.db().impls_for_trait(trait_id, filter)
+    }
+
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: TraitId<I>,
+        self_ty: &SimplifiedType<I>,
+    ) -> Vec<ImplId<I>> {
+        self.db().impls_for_trait_by_self_type(trait_id, self_ty)
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
+        self.db().local_impls_to_coherence_check(trait_id)
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<I>, ty: &TyKind<I>) -> bool {
+        self.db().impl_provided_for(auto_trait_id, ty)
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>> {
+        self.memoize(
+            |m| &mut m.well_known_trait_id,
+            &well_known_trait,
+            |db| db.well_known_trait_id(well_known_trait),
+        )
+    }
+
+    // Not memoized, as the generators are closures.
+    fn custom_builtin(&self, trait_id: TraitId<I>) -> Option<CustomBuiltin<I>> {
+        self.db().custom_builtin(trait_id)
+    }
+
+    fn custom_predicate_clauses(
+        &self,
+        builder: &mut ClauseBuilder<'_, I>,
+        predicate: &CustomPredicate<I>,
+    ) -> Result<(), Floundered> {
+        self.db().custom_predicate_clauses(builder, predicate)
+    }
+
+    // Not memoized, as hosts return it as is.
+    fn program_features(&self) -> ProgramFeatures {
+        self.db().program_features()
+    }
+
+    fn variance_of(&self, item: VarianceItem<I>) -> Variances<I> {
+        self.db().variance_of(item)
+    }
+
+    fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I> {
+        self.memoize(
+            |m| &mut m.program_clauses_for_env,
+            environment,
+            |db| db.program_clauses_for_env(environment),
+        )
+    }
+
+    fn interner(&self) -> I {
+        self.db().interner()
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        self.memoize(
+            |m| &mut m.is_object_safe,
+            &trait_id,
+            |db| db.is_object_safe(trait_id),
+        )
+    }
+
+    fn closure_kind(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> ClosureKind {
+        self.db().closure_kind(closure_id, substs)
+    }
+
+    fn closure_inputs_and_output(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Binders<FnDefInputsAndOutputDatum<I>> {
+        self.db().closure_inputs_and_output(closure_id, substs)
+    }
+
+    fn closure_upvars(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> Binders<Ty<I>> {
+        self.db().closure_upvars(closure_id, substs)
+    }
+
+    fn closure_fn_substitution(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Substitution<I> {
+        self.db().closure_fn_substitution(closure_id, substs)
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self
+    }
+
+    fn trait_name(&self, trait_id: TraitId<I>) -> String {
+        self.db().trait_name(trait_id)
+    }
+
+    fn adt_name(&self, adt_id: AdtId<I>) -> String {
+        self.db().adt_name(adt_id)
+    }
+
+    fn assoc_type_name(&self, assoc_ty_id: AssocTypeId<I>) -> String {
+        self.db().assoc_type_name(assoc_ty_id)
+    }
+
+    fn opaque_type_name(&self, opaque_ty_id: OpaqueTyId<I>) -> String {
+        self.db().opaque_type_name(opaque_ty_id)
+    }
+
+    fn fn_def_name(&self, fn_def_id: FnDefId<I>) -> String {
+        self.db().fn_def_name(fn_def_id)
+    }
+
+    fn discriminant_type(&self, ty: Ty<I>) -> Ty<I> {
+        self.db().discriminant_type(ty)
+    }
+}
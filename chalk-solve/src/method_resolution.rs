@@ -0,0 +1,158 @@
+//! A prototype of method resolution, for hosts building method lookup on
+//! chalk: given the type of the receiver of a method call and the methods
+//! of the traits in scope with the name of the method, finds the methods
+//! which apply, the way rustc's method probe does.
+//!
+//! The receiver is dereferenced step by step (see [`autoderef`]). At each
+//! step `U`, the methods taking `self` by value, then by `&self` and then
+//! by `&mut self` are tried, in this order, and the first of these which
+//! applies is the one called: in `v.len()` with `v: &Vec<T>`, `len(&self)`
+//! is found at the first step, as `&Vec<T>` matches `&Self` with
+//! `Self = Vec<T>`.
+//!
+//! Chalk does not know about methods, so they are described by the host as
+//! [`TraitMethod`]s; inherent methods are not supported.
+
+use crate::autoderef::{autoderef, solve};
+use crate::solve::{Solution, Solver};
+use crate::RustIrDatabase;
+use alloc::vec;
+use alloc::vec::Vec;
+use chalk_ir::cast::Cast;
+use chalk_ir::fold::shift::Shift;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+
+/// How a method takes its receiver.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Receiver {
+    /// `self`
+    Value,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+}
+
+/// A method of a trait which may be called, as described by the host.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TraitMethod<I: Interner> {
+    pub trait_id: TraitId<I>,
+    pub receiver: Receiver,
+}
+
+/// A method which applies to the receiver, at the autoderef step
+/// `autoderefs`, after taking a reference to the receiver if `autoref` is
+/// set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MethodCandidate<I: Interner> {
+    /// The index of the method in the methods given to [`probe_methods`].
+    pub method: usize,
+    pub autoderefs: usize,
+    pub autoref: Option<Mutability>,
+    /// The type the method is called on, i.e. `Self`.
+    pub self_ty: Canonical<Ty<I>>,
+    /// The solution of `Self: Trait<..>`, which is ambiguous if the method
+    /// may not apply.
+    pub solution: Solution<I>,
+}
+
+/// Finds the methods among `methods` which apply to a receiver of type
+/// `receiver` in `environment`, ranked: the first candidates are those
+/// rustc would pick, and if there are several of them at the same
+/// autoderef step and autoref, the call is ambiguous.
+pub fn probe_methods<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    solver: &mut dyn Solver<I>,
+    environment: &Environment<I>,
+    receiver: &Canonical<Ty<I>>,
+    methods: &[TraitMethod<I>],
+) -> Vec<MethodCandidate<I>> {
+    let interner = db.interner();
+    let mut candidates = vec![];
+    let steps: Vec<_> = autoderef(db, solver, environment, receiver.clone()).collect();
+    for (autoderefs, step) in steps.into_iter().enumerate() {
+        for autoref in [None, Some(Mutability::Not), Some(Mutability::Mut)] {
+            for (method, trait_method) in methods.iter().enumerate() {
+                let self_ty = match self_ty(interner, &step.value, autoref, trait_method.receiver) {
+                    Some(self_ty) => Canonical {
+                        binders: step.binders.clone(),
+                        value: self_ty,
+                    },
+                    None => continue,
+                };
+                let goal = implemented(db, trait_method.trait_id, &self_ty.value);
+                if let Some(solution) = solve(db, solver, environment, &self_ty.binders, goal) {
+                    candidates.push(MethodCandidate {
+                        method,
+                        autoderefs,
+                        autoref,
+                        self_ty,
+                        solution,
+                    });
+                }
+            }
+        }
+    }
+    candidates
+}
+
+/// The `Self` type of a method taking its receiver as `receiver`, when the
+/// receiver of the call has the type `ty` (after taking a reference to it
+/// if `autoref` is set), if the method can be called on it.
+fn self_ty<I: Interner>(
+    interner: I,
+    ty: &Ty<I>,
+    autoref: Option<Mutability>,
+    receiver: Receiver,
+) -> Option<Ty<I>> {
+    match (autoref, receiver) {
+        (None, Receiver::Value) => Some(ty.clone()),
+        (None, Receiver::Ref) => match ty.kind(interner) {
+            TyKind::Ref(Mutability::Not, _, referent) => Some(referent.clone()),
+            _ => None,
+        },
+        (None, Receiver::RefMut) => match ty.kind(interner) {
+            TyKind::Ref(Mutability::Mut, _, referent) => Some(referent.clone()),
+            _ => None,
+        },
+        (Some(Mutability::Not), Receiver::Ref) | (Some(Mutability::Mut), Receiver::RefMut) => {
+            Some(ty.clone())
+        }
+        (Some(_), _) => None,
+    }
+}
+
+/// The goal `exists<P1..Pn> { self_ty: Trait<P1..Pn> }`, where `P1..Pn`
+/// are the parameters of the trait other than `Self`.
+fn implemented<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_id: TraitId<I>,
+    self_ty: &Ty<I>,
+) -> Goal<I> {
+    let interner = db.interner();
+    let trait_datum = db.trait_datum(trait_id);
+    let parameters = VariableKinds::from_iter(
+        interner,
+        trait_datum.binders.binders.iter(interner).skip(1).cloned(),
+    );
+    let self_ty = self_ty.clone().shifted_in(interner);
+    let substitution = Substitution::from_iter(
+        interner,
+        Some(self_ty.cast(interner)).into_iter().chain(
+            parameters
+                .iter(interner)
+                .enumerate()
+                .map(|(index, kind)| (index, kind).to_generic_arg(interner)),
+        ),
+    );
+    let trait_ref = TraitRef {
+        trait_id,
+        substitution,
+    };
+    GoalData::Quantified(
+        QuantifierKind::Exists,
+        Binders::new(parameters, trait_ref.cast::<Goal<I>>(interner)),
+    )
+    .intern(interner)
+}
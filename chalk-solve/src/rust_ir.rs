@@ -2,31 +2,65 @@
 //! version of the AST, roughly corresponding to [the HIR] in the Rust
 //! compiler.
 
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_derive::{Fold, HasInterner, Visit};
 use chalk_ir::cast::Cast;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
+#[cfg(feature = "serde")]
+use chalk_ir::serialize::SerdeInterner;
 use chalk_ir::{
     try_break, visit::Visit, AdtId, AliasEq, AliasTy, AssocTypeId, Binders, DebruijnIndex, FnDefId,
-    GenericArg, ImplId, OpaqueTyId, ProjectionTy, QuantifiedWhereClause, Substitution,
-    ToGenericArg, TraitId, TraitRef, Ty, TyKind, VariableKind, WhereClause, WithKind,
+    GenericArg, ImplId, Lifetime, OpaqueTyId, ProjectionTy, QuantifiedWhereClause, Substitution,
+    ToGenericArg, TraitId, TraitRef, Ty, TyKind, TypeOutlives, VariableKind, WhereClause, WithKind,
 };
-use std::iter;
-use std::ops::ControlFlow;
+use core::iter;
+use core::ops::ControlFlow;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
 
 /// Identifier for an "associated type value" found in some impl.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyValueId<I: Interner>(pub I::DefId);
 
 chalk_ir::id_visit!(AssociatedTyValueId);
 chalk_ir::id_fold!(AssociatedTyValueId);
 
+/// Where a declaration is in the source it was lowered from: the index of
+/// the source, in whatever list of sources the frontend keeps, and a range
+/// of byte offsets into it. The solver does not look at spans; they let the
+/// errors found in a declaration point at it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    pub source: usize,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+chalk_ir::const_visit!(Span);
+chalk_ir::copy_fold!(Span);
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ImplDatum<I: Interner> {
     pub polarity: Polarity,
     pub binders: Binders<ImplDatumBound<I>>,
     pub impl_type: ImplType,
     pub associated_ty_value_ids: Vec<AssociatedTyValueId<I>>,
+
+    /// Where the impl was declared, if it was lowered from source.
+    pub span: Option<Span>,
 }
 
 impl<I: Interner> ImplDatum<I> {
@@ -53,12 +87,18 @@ impl<I: Interner> ImplDatum<I> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct ImplDatumBound<I: Interner> {
     pub trait_ref: TraitRef<I>,
     pub where_clauses: Vec<QuantifiedWhereClause<I>>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ImplType {
     Local,
     External,
@@ -67,25 +107,69 @@ pub enum ImplType {
 chalk_ir::const_visit!(ImplType);
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct DefaultImplDatum<I: Interner> {
     pub binders: Binders<DefaultImplDatumBound<I>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct DefaultImplDatumBound<I: Interner> {
     pub trait_ref: TraitRef<I>,
     pub accessible_tys: Vec<Ty<I>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AdtDatum<I: Interner> {
     pub binders: Binders<AdtDatumBound<I>>,
     pub id: AdtId<I>,
     pub flags: AdtFlags,
     pub kind: AdtKind,
+
+    /// Where the ADT was declared, if it was lowered from source.
+    pub span: Option<Span>,
+}
+
+impl<I: Interner> AdtDatum<I> {
+    /// Gives the type of the last field of a struct, the only one which
+    /// may be unsized, quantified over the type parameters of the struct:
+    ///
+    /// ```ignore
+    /// struct Foo<T: ?Sized> { len: usize, data: T }
+    ///                                           ^
+    /// ```
+    ///
+    /// Returns `None` for enums, unions and structs without fields.
+    pub fn tail_field(&self) -> Option<Binders<Ty<I>>> {
+        if self.kind != AdtKind::Struct {
+            return None;
+        }
+        self.binders
+            .map_ref(|bound| {
+                bound
+                    .variants
+                    .last()
+                    .and_then(|variant| variant.fields.last())
+            })
+            .filter_map(|field| field.cloned())
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AdtKind {
     Struct,
     Enum,
@@ -95,17 +179,32 @@ pub enum AdtKind {
 chalk_ir::const_visit!(AdtKind);
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AdtDatumBound<I: Interner> {
     pub variants: Vec<AdtVariantDatum<I>>,
     pub where_clauses: Vec<QuantifiedWhereClause<I>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AdtVariantDatum<I: Interner> {
     pub fields: Vec<Ty<I>>,
+    /// The explicitly given discriminant of an enum variant (`A = 1`).
+    /// Variants without one have the discriminant of the previous
+    /// variant plus one, or zero if they are the first variant.
+    pub discriminant: Option<i64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AdtFlags {
     pub upstream: bool,
     pub fundamental: bool,
@@ -115,14 +214,24 @@ pub struct AdtFlags {
 chalk_ir::const_visit!(AdtFlags);
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AdtRepr<I: Interner> {
     pub c: bool,
     pub packed: bool,
+    /// The ADT has the layout and ABI of its only field which is not a 1-ZST.
+    pub transparent: bool,
+    /// The ADT is a SIMD vector of its fields, which all have the same type.
+    pub simd: bool,
     pub int: Option<chalk_ir::Ty<I>>,
 }
 
 /// Information about the size and alignment of an ADT.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AdtSizeAlign {
     one_zst: bool,
 }
@@ -150,10 +259,18 @@ impl AdtSizeAlign {
 /// Note this is distinct from a function pointer, which points to
 /// a function with a given type signature, whereas this represents
 /// a specific function definition.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnDefDatum<I: Interner> {
     pub id: FnDefId<I>,
     pub sig: chalk_ir::FnSig<I>,
     pub binders: Binders<FnDefDatumBound<I>>,
+
+    /// Where the function was declared, if it was lowered from source.
+    pub span: Option<Span>,
 }
 
 /// Avoids visiting `I::FnAbi`
@@ -171,6 +288,11 @@ impl<I: Interner> Visit<I> for FnDefDatum<I> {
 /// Represents the inputs and outputs on a `FnDefDatum`. This is split
 /// from the where clauses, since these can contain bound lifetimes.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnDefInputsAndOutputDatum<I: Interner> {
     /// Types of the function's arguments
     /// ```ignore
@@ -190,6 +312,11 @@ pub struct FnDefInputsAndOutputDatum<I: Interner> {
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner, Visit)]
 /// Represents the bounds on a `FnDefDatum`, including
 /// the function definition's type signature and where clauses.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct FnDefDatumBound<I: Interner> {
     /// Inputs and outputs defined on a function
     /// These are needed for late-bound regions in rustc. For example the
@@ -218,7 +345,7 @@ pub struct FnDefDatumBound<I: Interner> {
 /// example, given the following rust code:
 ///
 /// ```compile_fail
-/// use std::fmt::Debug;
+/// use core::fmt::Debug;
 ///
 /// trait Foo<T>
 /// where
@@ -239,6 +366,11 @@ pub struct FnDefDatumBound<I: Interner> {
 /// [`ImplDatum`]: struct.ImplDatum.html
 /// [`AssociatedTyDatum`]: struct.AssociatedTyDatum.html
 #[derive(Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitDatum<I: Interner> {
     pub id: TraitId<I>,
 
@@ -254,11 +386,15 @@ pub struct TraitDatum<I: Interner> {
     /// If this is a well-known trait, which one? If `None`, this is a regular,
     /// user-defined trait.
     pub well_known: Option<WellKnownTrait>,
+
+    /// Where the trait was declared, if it was lowered from source.
+    pub span: Option<Span>,
 }
 
 /// A list of the traits that are "well known" to chalk, which means that
 /// the chalk-solve crate has special, hard-coded impls for them.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WellKnownTrait {
     Sized,
     Copy,
@@ -276,10 +412,73 @@ pub enum WellKnownTrait {
     DiscriminantKind,
     Generator,
     DispatchFromDyn,
+    /// The marker trait `Tuple`, implemented by all tuple types. The `Args` of
+    /// the `Fn` family of traits are required to implement it.
+    Tuple,
+    /// The marker trait `FnPtr`, implemented by all function pointer types.
+    FnPtr,
+    /// The trait `MetaSized`, implemented by the types whose size can be
+    /// computed from the metadata of pointers to them: all types but
+    /// `extern` types and the structs and tuples ending in one. It sits
+    /// between `Sized` and `PointeeSized` in the hierarchy of sizedness
+    /// traits proposed for Rust.
+    MetaSized,
+    /// The trait `PointeeSized`, implemented by all types.
+    PointeeSized,
+    /// The trait `Deref`, whose `Target` is what autoderef steps to (see
+    /// `autoderef`).
+    Deref,
+    /// The trait `IntoIterator`, whose first associated type is `Item`.
+    IntoIterator,
+    /// The trait `Iterator`, whose first associated type is `Item`.
+    Iterator,
 }
 
 chalk_ir::const_visit!(WellKnownTrait);
 
+/// The rustc features a program is solved with, so that programs modeling
+/// different configurations of rustc can be solved side by side. The
+/// default is what chalk has always modeled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProgramFeatures {
+    /// Whether an impl may specialize another one it overlaps with. If not,
+    /// all overlapping impls are coherence errors.
+    pub specialization: bool,
+    /// Whether the where clauses of types and functions are implied bounds:
+    /// if not, `FromEnv(T: Eq)` does not follow from `FromEnv(Foo<T>)` for
+    /// `struct Foo<T> where T: Eq`.
+    pub implied_bounds: bool,
+    /// Which goals have coinductive semantics.
+    pub coinduction: CoinductionSemantics,
+    /// Whether methods may take `self` by types other than `Self`,
+    /// references and smart pointers to it. No rules of chalk depend on it;
+    /// it is for the hosts answering `RustIrDatabase::is_object_safe`.
+    pub arbitrary_self_types: bool,
+}
+
+impl Default for ProgramFeatures {
+    fn default() -> Self {
+        ProgramFeatures {
+            specialization: true,
+            implied_bounds: true,
+            coinduction: CoinductionSemantics::Standard,
+            arbitrary_self_types: false,
+        }
+    }
+}
+
+/// See [`ProgramFeatures::coinduction`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CoinductionSemantics {
+    /// Auto traits, `#[coinductive]` traits and `WellFormed(T: Trait)` are
+    /// coinductive, as in rustc.
+    Standard,
+    /// Every trait is coinductive, as proposed for rustc's next solver.
+    AllTraits,
+}
+
 impl<I: Interner> TraitDatum<I> {
     pub fn is_auto_trait(&self) -> bool {
         self.flags.auto
@@ -305,6 +504,11 @@ impl<I: Interner> TraitDatum<I> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, HasInterner, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitDatumBound<I: Interner> {
     /// Where clauses defined on the trait:
     ///
@@ -316,6 +520,7 @@ pub struct TraitDatumBound<I: Interner> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TraitFlags {
     /// An "auto trait" is one that is "automatically implemented" for every
     /// struct, so long as no explicit impl is given.
@@ -352,9 +557,16 @@ chalk_ir::const_visit!(TraitFlags);
 
 /// An inline bound, e.g. `: Foo<K>` in `impl<K, T: Foo<K>> SomeType<T>`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum InlineBound<I: Interner> {
     TraitBound(TraitBound<I>),
     AliasEqBound(AliasEqBound<I>),
+    /// `'a`, the bound of a type that outlives `'a`.
+    Outlives(Lifetime<I>),
 }
 
 #[allow(type_alias_bounds)]
@@ -378,6 +590,10 @@ impl<I: Interner> IntoWhereClauses<I> for InlineBound<I> {
         match self {
             InlineBound::TraitBound(b) => b.into_where_clauses(interner, self_ty),
             InlineBound::AliasEqBound(b) => b.into_where_clauses(interner, self_ty),
+            InlineBound::Outlives(lifetime) => vec![WhereClause::TypeOutlives(TypeOutlives {
+                ty: self_ty,
+                lifetime: lifetime.clone(),
+            })],
         }
     }
 }
@@ -396,6 +612,11 @@ impl<I: Interner> IntoWhereClauses<I> for QuantifiedInlineBound<I> {
 /// Represents a trait bound on e.g. a type or type parameter.
 /// Does not know anything about what it's binding.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct TraitBound<I: Interner> {
     pub trait_id: TraitId<I>,
     pub args_no_self: Vec<GenericArg<I>>,
@@ -421,6 +642,11 @@ impl<I: Interner> TraitBound<I> {
 /// Represents an alias equality bound on e.g. a type or type parameter.
 /// Does not know anything about what it's binding.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AliasEqBound<I: Interner> {
     pub trait_bound: TraitBound<I>,
     pub associated_ty_id: AssocTypeId<I>,
@@ -485,6 +711,11 @@ impl<I: Interner, T> Anonymize<I> for [WithKind<I, T>] {
 /// * The *where clauses* `where_clauses` are things that the impl can *assume* to be true
 ///   (but which projectors must prove).
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyDatum<I: Interner> {
     /// The trait this associated type is defined in.
     pub trait_id: TraitId<I>,
@@ -501,6 +732,9 @@ pub struct AssociatedTyDatum<I: Interner> {
     /// that "inner" binders are lower indices, although within a
     /// given binder we do not have an ordering).
     pub binders: Binders<AssociatedTyDatumBound<I>>,
+
+    /// Where the associated type was declared, if it was lowered from source.
+    pub span: Option<Span>,
 }
 
 // Manual implementation to avoid I::Identifier type.
@@ -519,6 +753,11 @@ impl<I: Interner> Visit<I> for AssociatedTyDatum<I> {
 /// Encodes the parts of `AssociatedTyDatum` where the parameters
 /// `P0..Pm` are in scope (`bounds` and `where_clauses`).
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyDatumBound<I: Interner> {
     /// Bounds on the associated type itself.
     ///
@@ -581,6 +820,11 @@ impl<I: Interner> AssociatedTyDatum<I> {
 /// }
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyValue<I: Interner> {
     /// Impl in which this associated type value is found.  You might
     /// need to look at this to find the generic parameters defined on
@@ -617,29 +861,103 @@ pub struct AssociatedTyValue<I: Interner> {
     /// }
     /// ```
     pub value: Binders<AssociatedTyValueBound<I>>,
+
+    /// Where the associated type value was declared, if it was lowered from source.
+    pub span: Option<Span>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct AssociatedTyValueBound<I: Interner> {
     /// Type that we normalize to. The X in `type Foo<'a> = X`.
     pub ty: Ty<I>,
 }
 
+/// Represents an inherent associated type, i.e. one declared in an
+/// inherent impl rather than in a trait, together with its values.
+///
+/// ```ignore
+/// impl<T> Foo<T> where T: Clone {
+///     type Bar = Vec<T>; // <-- represents this line!
+/// }
+/// ```
+///
+/// The inherent associated types of the same name on the same ADT share a
+/// single datum, with one value for each impl that defines them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct InherentAssocTyDatum<I: Interner> {
+    /// The ID of this associated type.
+    pub id: AssocTypeId<I>,
+
+    /// The ADT whose inherent impls define this associated type.
+    pub adt_id: AdtId<I>,
+
+    /// Name of this associated type.
+    pub name: I::Identifier,
+
+    /// The values defined by each impl; the binders are those of the impl.
+    pub values: Vec<Binders<InherentAssocTyValue<I>>>,
+
+    /// Where the associated type was declared, if it was lowered from source.
+    pub span: Option<Span>,
+}
+
+/// The value of an inherent associated type in one impl, in the scope of
+/// the impl's parameters.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub struct InherentAssocTyValue<I: Interner> {
+    /// The self type of the impl. The `Foo<T>` in `impl<T> Foo<T>`.
+    pub self_ty: Ty<I>,
+
+    /// Where clauses of the impl, which must hold for the value to apply.
+    pub where_clauses: Vec<QuantifiedWhereClause<I>>,
+
+    /// Type that we normalize to. The X in `type Bar = X`.
+    pub ty: Ty<I>,
+}
+
 /// Represents the bounds for an `impl Trait` type.
 ///
 /// ```ignore
 /// opaque type T: A + B = HiddenTy;
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTyDatum<I: Interner> {
     /// The placeholder `!T` that corresponds to the opaque type `T`.
     pub opaque_ty_id: OpaqueTyId<I>,
 
     /// The type bound to when revealed.
     pub bound: Binders<OpaqueTyDatumBound<I>>,
+
+    /// Where the opaque type was declared, if it was lowered from source.
+    pub span: Option<Span>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner, Visit)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct OpaqueTyDatumBound<I: Interner> {
     /// Trait bounds for the opaque type. These are bounds that the hidden type must meet.
     pub bounds: Binders<Vec<QuantifiedWhereClause<I>>>,
@@ -652,6 +970,7 @@ pub struct OpaqueTyDatumBound<I: Interner> {
 // The movability of a generator: whether a generator contains self-references,
 // causing it to be !Unpin
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Movability {
     Static,
     Movable,
@@ -660,6 +979,11 @@ chalk_ir::copy_fold!(Movability);
 
 /// Represents a generator type.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct GeneratorDatum<I: Interner> {
     // Can the generator be moved (is Unpin or not)
     pub movability: Movability,
@@ -671,6 +995,11 @@ pub struct GeneratorDatum<I: Interner> {
 
 /// The nested types for a generator. This always appears inside a `GeneratorDatum`
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct GeneratorInputOutputDatum<I: Interner> {
     /// The generator resume type - a value of this type
     /// is supplied by the caller when resuming the generator.
@@ -699,6 +1028,11 @@ pub struct GeneratorInputOutputDatum<I: Interner> {
 /// matters when we treat the witness type as a 'constituent type for the
 /// purposes of determining auto trait implementations.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct GeneratorWitnessDatum<I: Interner> {
     /// This binder is identical to the `input_output` binder in `GeneratorWitness` -
     /// it binds the types and lifetimes that the generator is generic over.
@@ -718,11 +1052,17 @@ pub struct GeneratorWitnessDatum<I: Interner> {
 /// via an `Ty`. Instead, we handle this `Binders` specially when determining
 /// auto trait impls. See `push_auto_trait_impls_generator_witness` for more details.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub struct GeneratorWitnessExistential<I: Interner> {
     pub types: Binders<Vec<Ty<I>>>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Polarity {
     Positive,
     Negative,
@@ -743,6 +1083,7 @@ impl Polarity {
 /// If the closure kind for a closure is FnMut, for example, then the closure
 /// implements FnMut and FnOnce.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ClosureKind {
     Fn,
     FnMut,
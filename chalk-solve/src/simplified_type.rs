@@ -0,0 +1,308 @@
+//! "Simplified types": cheap keys summarizing the outermost constructor of
+//! a type, used to quickly rule out impls that cannot possibly apply.
+//!
+//! If two types unify, their simplified types (when both have one) are
+//! equal. The converse does not hold, so an impl found through its key
+//! still has to be checked in full. Types like inference variables and
+//! projections can unify with types of any shape, so they have no key at
+//! all, and neither do the self types of blanket impls like
+//! `impl<T> Foo for T`.
+
+use crate::FxHashMap;
+use alloc::vec::Vec;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use hashbrown::hash_map::Entry;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+
+/// The outermost constructor of a type, without any of its parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: chalk_ir::serialize::SerdeInterner")
+)]
+pub enum SimplifiedType<I: Interner> {
+    Adt(AdtId<I>),
+    AssociatedType(AssocTypeId<I>),
+    Scalar(Scalar),
+    /// A tuple with the given arity.
+    Tuple(usize),
+    Array,
+    Slice,
+    Raw(Mutability),
+    Ref(Mutability),
+    OpaqueType(OpaqueTyId<I>),
+    FnDef(FnDefId<I>),
+    Str,
+    Never,
+    Closure(ClosureId<I>),
+    Generator(GeneratorId<I>),
+    GeneratorWitness(GeneratorId<I>),
+    Foreign(ForeignDefId<I>),
+    Custom,
+    Placeholder(PlaceholderIndex),
+    Dyn,
+    /// A function pointer with the given number of inputs.
+    Function(usize),
+}
+
+impl<I: Interner> SimplifiedType<I> {
+    /// Computes the simplified type of `ty`, or `None` if `ty` could unify
+    /// with types of different shapes (variables, aliases, errors).
+    pub fn from_ty(interner: I, ty: &Ty<I>) -> Option<Self> {
+        Some(match ty.kind(interner) {
+            TyKind::Adt(id, _) => SimplifiedType::Adt(*id),
+            TyKind::AssociatedType(id, _) => SimplifiedType::AssociatedType(*id),
+            TyKind::Scalar(scalar) => SimplifiedType::Scalar(*scalar),
+            TyKind::Tuple(arity, _) => SimplifiedType::Tuple(*arity),
+            TyKind::Array(..) => SimplifiedType::Array,
+            TyKind::Slice(_) => SimplifiedType::Slice,
+            TyKind::Raw(mutability, _) => SimplifiedType::Raw(*mutability),
+            TyKind::Ref(mutability, ..) => SimplifiedType::Ref(*mutability),
+            TyKind::OpaqueType(id, _) => SimplifiedType::OpaqueType(*id),
+            TyKind::FnDef(id, _) => SimplifiedType::FnDef(*id),
+            TyKind::Str => SimplifiedType::Str,
+            TyKind::Never => SimplifiedType::Never,
+            TyKind::Closure(id, _) => SimplifiedType::Closure(*id),
+            TyKind::Generator(id, _) => SimplifiedType::Generator(*id),
+            TyKind::GeneratorWitness(id, _) => SimplifiedType::GeneratorWitness(*id),
+            TyKind::Foreign(id) => SimplifiedType::Foreign(*id),
+            TyKind::Custom(_) => SimplifiedType::Custom,
+            TyKind::Placeholder(idx) => SimplifiedType::Placeholder(*idx),
+            TyKind::Dyn(_) => SimplifiedType::Dyn,
+            TyKind::Function(fn_ptr) => {
+                SimplifiedType::Function(fn_ptr.substitution.0.len(interner) - 1)
+            }
+            TyKind::Alias(_) | TyKind::BoundVar(_) | TyKind::InferenceVar(..) | TyKind::Error => {
+                return None
+            }
+        })
+    }
+
+    /// Computes the simplified type of the self type in `parameters`, the
+    /// parameters of a trait reference.
+    pub fn from_self_parameter(interner: I, parameters: &[GenericArg<I>]) -> Option<Self> {
+        let self_ty = parameters.first()?.ty(interner)?;
+        Self::from_ty(interner, self_ty)
+    }
+}
+
+/// An index of the impls of each trait by the simplified type of their
+/// self type, for implementors of `RustIrDatabase` to answer
+/// `impls_for_trait` without checking every impl of the trait.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: chalk_ir::serialize::SerdeInterner")
+)]
+pub struct ImplIndex<I: Interner> {
+    traits: FxHashMap<TraitId<I>, TraitImpls<I>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: chalk_ir::serialize::SerdeInterner")
+)]
+struct TraitImpls<I: Interner> {
+    /// Impls whose self type has no simplified type, which may apply to
+    /// any self type.
+    blanket: Vec<ImplId<I>>,
+    by_self_type: FxHashMap<SimplifiedType<I>, Vec<ImplId<I>>>,
+}
+
+impl<I: Interner> Default for ImplIndex<I> {
+    fn default() -> Self {
+        ImplIndex {
+            traits: FxHashMap::default(),
+        }
+    }
+}
+
+impl<I: Interner> ImplIndex<I> {
+    /// Adds an impl of `trait_id` whose self type simplifies to `self_ty`.
+    pub fn insert(
+        &mut self,
+        trait_id: TraitId<I>,
+        self_ty: Option<SimplifiedType<I>>,
+        impl_id: ImplId<I>,
+    ) {
+        let impls = match self.traits.entry(trait_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(TraitImpls {
+                blanket: Vec::new(),
+                by_self_type: FxHashMap::default(),
+            }),
+        };
+        match self_ty {
+            Some(self_ty) => impls.by_self_type.entry(self_ty).or_default().push(impl_id),
+            None => impls.blanket.push(impl_id),
+        }
+    }
+
+    /// Returns the impls of `trait_id` that could apply to a self type
+    /// that simplifies to `self_ty`: those indexed under `self_ty` and the
+    /// blanket impls. If `self_ty` is `None`, all impls of the trait are
+    /// returned. Impls are returned in the order they were inserted
+    /// within each of these groups.
+    pub fn impls_for_self_type(
+        &self,
+        trait_id: TraitId<I>,
+        self_ty: Option<&SimplifiedType<I>>,
+    ) -> Vec<ImplId<I>> {
+        let impls = match self.traits.get(&trait_id) {
+            Some(impls) => impls,
+            None => return Vec::new(),
+        };
+        let mut impl_ids = impls.blanket.clone();
+        match self_ty {
+            Some(self_ty) => {
+                if let Some(matching) = impls.by_self_type.get(self_ty) {
+                    impl_ids.extend(matching.iter().copied());
+                }
+            }
+            None => {
+                for matching in impls.by_self_type.values() {
+                    impl_ids.extend(matching.iter().copied());
+                }
+            }
+        }
+        impl_ids
+    }
+}
+
+/// What a parameter of a trait reference we are looking for impls of
+/// could unify with, as far as its outermost constructor is concerned.
+#[derive(Clone, Debug)]
+enum ParameterShape<I: Interner> {
+    /// Only types with this simplified type (or blanket parameters).
+    Exact(SimplifiedType<I>),
+    /// An integer variable, which only unifies with integer types.
+    Integer,
+    /// A float variable, which only unifies with float types.
+    Float,
+    /// Anything (a general variable, an alias, a lifetime, a const...).
+    Any,
+}
+
+impl<I: Interner> ParameterShape<I> {
+    fn admits(&self, impl_shape: Option<&SimplifiedType<I>>) -> bool {
+        let impl_shape = match impl_shape {
+            Some(impl_shape) => impl_shape,
+            None => return true,
+        };
+        match self {
+            ParameterShape::Exact(shape) => shape == impl_shape,
+            ParameterShape::Integer => matches!(
+                impl_shape,
+                SimplifiedType::Scalar(Scalar::Int(_)) | SimplifiedType::Scalar(Scalar::Uint(_))
+            ),
+            ParameterShape::Float => {
+                matches!(impl_shape, SimplifiedType::Scalar(Scalar::Float(_)))
+            }
+            ParameterShape::Any => true,
+        }
+    }
+}
+
+/// The parameters of a trait reference that `RustIrDatabase::impls_for_trait`
+/// is asked to find impls for, together with a fast check rejecting impls
+/// that cannot possibly match them.
+///
+/// The check only looks at the outermost constructor of each parameter, but
+/// it is aware of the canonical `binders` of the parameters: a variable
+/// bound there that is known to be an integer (resp. float) only matches
+/// impls for integer (resp. float) types. It only needs the parameters of
+/// the impl's trait reference, so databases can use it before building the
+/// full `ImplDatum` of an impl.
+#[derive(Debug)]
+pub struct ImplFilter<'a, I: Interner> {
+    interner: I,
+    parameters: &'a [GenericArg<I>],
+    binders: &'a CanonicalVarKinds<I>,
+    shapes: Vec<ParameterShape<I>>,
+}
+
+impl<'a, I: Interner> ImplFilter<'a, I> {
+    pub fn new(
+        interner: I,
+        parameters: &'a [GenericArg<I>],
+        binders: &'a CanonicalVarKinds<I>,
+    ) -> Self {
+        let shapes = parameters
+            .iter()
+            .map(|parameter| match parameter.ty(interner) {
+                Some(ty) => Self::shape_of(interner, binders, ty),
+                None => ParameterShape::Any,
+            })
+            .collect();
+        ImplFilter {
+            interner,
+            parameters,
+            binders,
+            shapes,
+        }
+    }
+
+    fn shape_of(interner: I, binders: &CanonicalVarKinds<I>, ty: &Ty<I>) -> ParameterShape<I> {
+        let var_kind = match ty.kind(interner) {
+            TyKind::InferenceVar(_, kind) => Some(kind),
+            TyKind::BoundVar(bound_var) => bound_var
+                .index_if_innermost()
+                .and_then(|index| binders.as_slice(interner).get(index))
+                .and_then(|binder| match &binder.kind {
+                    VariableKind::Ty(kind) => Some(kind),
+                    _ => None,
+                }),
+            _ => None,
+        };
+        match var_kind {
+            Some(TyVariableKind::Integer) => ParameterShape::Integer,
+            Some(TyVariableKind::Float) => ParameterShape::Float,
+            Some(TyVariableKind::General) => ParameterShape::Any,
+            None => match SimplifiedType::from_ty(interner, ty) {
+                Some(shape) => ParameterShape::Exact(shape),
+                None => ParameterShape::Any,
+            },
+        }
+    }
+
+    /// The parameters of the trait reference, as passed to `new`.
+    pub fn parameters(&self) -> &'a [GenericArg<I>] {
+        self.parameters
+    }
+
+    /// The canonical binders of `parameters`; if the recursive solver is
+    /// used, the parameters can contain bound variables referring to them.
+    pub fn binders(&self) -> &'a CanonicalVarKinds<I> {
+        self.binders
+    }
+
+    /// The simplified type of the self type, if it is known to have one.
+    pub fn self_type(&self) -> Option<&SimplifiedType<I>> {
+        match self.shapes.first() {
+            Some(ParameterShape::Exact(shape)) => Some(shape),
+            _ => None,
+        }
+    }
+
+    /// Returns false if an impl whose trait reference has the parameters
+    /// `impl_parameters` cannot possibly match the parameters we are
+    /// looking for. Returning true does not mean the impl applies.
+    pub fn may_match(&self, impl_parameters: &[GenericArg<I>]) -> bool {
+        let interner = self.interner;
+        self.shapes
+            .iter()
+            .zip(impl_parameters)
+            .all(|(shape, impl_parameter)| {
+                let impl_shape = impl_parameter
+                    .ty(interner)
+                    .and_then(|ty| SimplifiedType::from_ty(interner, ty));
+                shape.admits(impl_shape.as_ref())
+            })
+    }
+}
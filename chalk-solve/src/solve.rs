@@ -1,14 +1,33 @@
+use crate::FxHashMap;
 use crate::RustIrDatabase;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use chalk_derive::HasInterner;
 use chalk_ir::interner::Interner;
+#[cfg(feature = "serde")]
+use chalk_ir::serialize::SerdeInterner;
 use chalk_ir::*;
-use std::fmt;
+use core::fmt;
+use core::time::Duration;
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::time::Instant;
 use tracing::debug;
 
+pub mod aggregate;
+pub mod canonical_diff;
 pub mod truncate;
 
 /// A (possible) solution for a proposed goal.
 #[derive(Clone, Debug, PartialEq, Eq, HasInterner)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum Solution<I: Interner> {
     /// The goal indeed holds, and there is a unique value for all existential
     /// variables. In this case, we also record a set of lifetime constraints
@@ -18,13 +37,98 @@ pub enum Solution<I: Interner> {
     /// The goal may be provable in multiple ways, but regardless we may have some guidance
     /// for type inference. In this case, we don't return any lifetime
     /// constraints, since we have not "committed" to any particular solution
-    /// yet.
-    Ambig(Guidance<I>),
+    /// yet. The `AmbiguityCause` tells why the solver could not commit.
+    ///
+    /// The substitutions are those of some of the answers which made the
+    /// goal ambiguous, so that hosts can point at them (like rustc's
+    /// "multiple applicable impls" notes). The SLG solver reports up to
+    /// `SLGSolver::with_max_candidates` of them, and none by default.
+    Ambig(Guidance<I>, AmbiguityCause, Vec<Canonical<Substitution<I>>>),
+}
+
+/// Why a solution is ambiguous, so that hosts can choose how to fall back
+/// depending on the cause: e.g., only retry with larger limits on
+/// `Overflow` or `Truncated`.
+///
+/// When a goal is ambiguous because one of its subgoals is, it has the
+/// cause of that subgoal.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AmbiguityCause {
+    /// The goal holds in several ways, which disagree on the values of its
+    /// existential variables: `count` is the number of them the solver
+    /// looked at before giving up on a unique solution.
+    MultipleCandidates { count: usize },
+    /// The solver gave up on a subgoal because it overflowed its depth
+    /// limit or reached its deadline, or ran out of time before finding
+    /// all the answers.
+    Overflow,
+    /// A subgoal could not be solved with what is known of its inference
+    /// variables: a negative goal which still has some, a goal whose
+    /// clauses cannot be enumerated (like `?T: Sized`), or a `CannotProve`
+    /// goal.
+    FlounderedNegative,
+    /// A subgoal or an answer was bigger than the solver's size limit, or
+    /// was one of a chain of growing goals, and was given up on.
+    Truncated,
+}
+
+/// Why a goal has no solution, so that hosts can explain the failure (e.g.,
+/// "cyclic type of infinite size").
+///
+/// When a goal has no solution because one of its subgoals has none, it
+/// has the cause of that subgoal; among several failed clauses, the first
+/// known cause is kept.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NoSolutionCause<I: Interner> {
+    /// No more precise cause is known.
+    Unknown,
+    /// Proving the goal would have required a type to contain itself. The
+    /// goal equates the inference variable to the type it occurs in, like
+    /// `^0.0 = Vec<^0.0>`.
+    OccursCheck(Canonical<Goal<I>>),
+}
+
+impl<I: Interner> From<NoSolution> for NoSolutionCause<I> {
+    fn from(_: NoSolution) -> Self {
+        NoSolutionCause::Unknown
+    }
+}
+
+impl<I: Interner> From<NoSolutionCause<I>> for NoSolution {
+    fn from(_: NoSolutionCause<I>) -> Self {
+        NoSolution
+    }
+}
+
+impl<I: Interner> NoSolutionCause<I> {
+    /// Keeps the first known cause of `self` and `other`.
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            NoSolutionCause::Unknown => other,
+            cause => cause,
+        }
+    }
+}
+
+impl AmbiguityCause {
+    /// The number of candidates of a solution with this cause.
+    fn candidates(self) -> usize {
+        match self {
+            AmbiguityCause::MultipleCandidates { count } => count,
+            _ => 1,
+        }
+    }
 }
 
 /// When a goal holds ambiguously (e.g., because there are multiple possible
 /// solutions), we issue a set of *guidance* back to type inference.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
 pub enum Guidance<I: Interner> {
     /// The existential variables *must* have the given values if the goal is
     /// ever to hold, but that alone isn't enough to guarantee the goal will
@@ -44,24 +148,30 @@ impl<I: Interner> Solution<I> {
     /// There are multiple candidate solutions, which may or may not agree on
     /// the values for existential variables; attempt to combine them. This
     /// operation does not depend on the order of its arguments.
+    ///
+    /// `binders` are the binders of the canonical goal that both solutions
+    /// are solutions to. When the solutions disagree, the combined guidance
+    /// is computed by anti-unifying their substitutions argument by
+    /// argument (see `aggregate::merge_into_guidance`), so e.g. combining
+    /// `?0 := Vec<u32>, ?1 := u32` with `?0 := Vec<i32>, ?1 := i32` still
+    /// yields the guidance `?0 := Vec<?X>`.
     //
-    // This actually isn't as precise as it could be, in two ways:
-    //
-    // a. It might be that while there are multiple distinct candidates, they
-    //    all agree about *some things*. To be maximally precise, we would
-    //    compute the intersection of what they agree on. It's not clear though
-    //    that this is actually what we want Rust's inference to do, and it's
-    //    certainly not what it does today.
+    // This actually isn't as precise as it could be:
     //
-    // b. There might also be an ambiguous candidate and a successful candidate,
-    //    both with the same refined-goal. In that case, we could probably claim
-    //    success, since if the conditions of the ambiguous candidate were met,
-    //    we know the success would apply.  Example: `?0: Clone` yields ambiguous
-    //    candidate `Option<?0>: Clone` and successful candidate `Option<?0>:
-    //    Clone`.
+    // There might also be an ambiguous candidate and a successful candidate,
+    // both with the same refined-goal. In that case, we could probably claim
+    // success, since if the conditions of the ambiguous candidate were met,
+    // we know the success would apply.  Example: `?0: Clone` yields ambiguous
+    // candidate `Option<?0>: Clone` and successful candidate `Option<?0>:
+    // Clone`.
     //
     // But you get the idea.
-    pub fn combine(self, other: Solution<I>, interner: I) -> Solution<I> {
+    pub fn combine(
+        self,
+        other: Solution<I>,
+        interner: I,
+        binders: &CanonicalVarKinds<I>,
+    ) -> Solution<I> {
         use self::Guidance::*;
 
         if self == other {
@@ -76,6 +186,11 @@ impl<I: Interner> Solution<I> {
 
         // Otherwise, always downgrade to Ambig:
 
+        let cause = AmbiguityCause::MultipleCandidates {
+            count: self.candidates() + other.candidates(),
+        };
+        let mut candidates = self.ambiguity_candidates().to_vec();
+        candidates.extend_from_slice(other.ambiguity_candidates());
         let guidance = match (self.into_guidance(), other.into_guidance()) {
             (Definite(ref subst1), Definite(ref subst2)) if subst1 == subst2 => {
                 Definite(subst1.clone())
@@ -83,9 +198,37 @@ impl<I: Interner> Solution<I> {
             (Suggested(ref subst1), Suggested(ref subst2)) if subst1 == subst2 => {
                 Suggested(subst1.clone())
             }
+            (Definite(subst1), Definite(subst2)) => {
+                Self::merge_guidance(interner, binders, subst1, subst2, Definite)
+            }
+            (Definite(subst1), Suggested(subst2))
+            | (Suggested(subst1), Definite(subst2))
+            | (Suggested(subst1), Suggested(subst2)) => {
+                Self::merge_guidance(interner, binders, subst1, subst2, Suggested)
+            }
             _ => Unknown,
         };
-        Solution::Ambig(guidance)
+        Solution::Ambig(guidance, cause, candidates)
+    }
+
+    /// Anti-unifies two (distinct) guidance substitutions, producing
+    /// `Guidance::Unknown` if nothing is left in common.
+    fn merge_guidance(
+        interner: I,
+        binders: &CanonicalVarKinds<I>,
+        subst1: Canonical<Substitution<I>>,
+        subst2: Canonical<Substitution<I>>,
+        make_guidance: fn(Canonical<Substitution<I>>) -> Guidance<I>,
+    ) -> Guidance<I> {
+        if subst1.value.is_empty(interner) {
+            return Guidance::Unknown;
+        }
+        let merged = aggregate::merge_into_guidance(interner, binders, subst1, &subst2.value);
+        if aggregate::is_trivial(interner, &merged) {
+            Guidance::Unknown
+        } else {
+            make_guidance(merged)
+        }
     }
 
     /// View this solution purely in terms of type inference guidance
@@ -95,7 +238,7 @@ impl<I: Interner> Solution<I> {
                 value: constrained.value.subst,
                 binders: constrained.binders,
             }),
-            Solution::Ambig(guidance) => guidance,
+            Solution::Ambig(guidance, ..) => guidance,
         }
     }
 
@@ -103,8 +246,8 @@ impl<I: Interner> Solution<I> {
     pub fn constrained_subst(&self, interner: I) -> Option<Canonical<ConstrainedSubst<I>>> {
         match *self {
             Solution::Unique(ref constrained) => Some(constrained.clone()),
-            Solution::Ambig(Guidance::Definite(ref canonical))
-            | Solution::Ambig(Guidance::Suggested(ref canonical)) => {
+            Solution::Ambig(Guidance::Definite(ref canonical), ..)
+            | Solution::Ambig(Guidance::Suggested(ref canonical), ..) => {
                 let value = ConstrainedSubst {
                     subst: canonical.value.clone(),
                     constraints: Constraints::empty(interner),
@@ -114,7 +257,7 @@ impl<I: Interner> Solution<I> {
                     binders: canonical.binders.clone(),
                 })
             }
-            Solution::Ambig(_) => None,
+            Solution::Ambig(..) => None,
         }
     }
 
@@ -123,7 +266,7 @@ impl<I: Interner> Solution<I> {
     pub fn definite_subst(&self, interner: I) -> Option<Canonical<ConstrainedSubst<I>>> {
         match self {
             Solution::Unique(constrained) => Some(constrained.clone()),
-            Solution::Ambig(Guidance::Definite(canonical)) => {
+            Solution::Ambig(Guidance::Definite(canonical), ..) => {
                 let value = ConstrainedSubst {
                     subst: canonical.value.clone(),
                     constraints: Constraints::empty(interner),
@@ -142,7 +285,33 @@ impl<I: Interner> Solution<I> {
     }
 
     pub fn is_ambig(&self) -> bool {
-        matches!(*self, Solution::Ambig(_))
+        matches!(*self, Solution::Ambig(..))
+    }
+
+    /// Why this solution is ambiguous, if it is.
+    pub fn ambiguity_cause(&self) -> Option<AmbiguityCause> {
+        match self {
+            Solution::Unique(_) => None,
+            Solution::Ambig(_, cause, _) => Some(*cause),
+        }
+    }
+
+    /// The substitutions of some of the answers which made this solution
+    /// ambiguous, see `Solution::Ambig`.
+    pub fn ambiguity_candidates(&self) -> &[Canonical<Substitution<I>>] {
+        match self {
+            Solution::Unique(_) => &[],
+            Solution::Ambig(_, _, candidates) => candidates,
+        }
+    }
+
+    /// The number of candidates of this solution: those it was combined
+    /// from, if it is ambiguous because there are several.
+    fn candidates(&self) -> usize {
+        match self {
+            Solution::Unique(_) => 1,
+            Solution::Ambig(_, cause, _) => cause.candidates(),
+        }
     }
 
     pub fn display(&self, interner: I) -> SolutionDisplay<'_, I> {
@@ -173,17 +342,17 @@ impl<'a, I: Interner> fmt::Display for SolutionDisplay<'a, I> {
 
             Solution::Unique(constrained) => write!(f, "Unique; {}", constrained.display(*interner)),
 
-            Solution::Ambig(Guidance::Definite(subst)) => write!(
+            Solution::Ambig(Guidance::Definite(subst), ..) => write!(
                 f,
                 "Ambiguous; definite substitution {}",
                 subst.display(*interner)
             ),
-            Solution::Ambig(Guidance::Suggested(subst)) => write!(
+            Solution::Ambig(Guidance::Suggested(subst), ..) => write!(
                 f,
                 "Ambiguous; suggested substitution {}",
                 subst.display(*interner)
             ),
-            Solution::Ambig(Guidance::Unknown) => write!(f, "Ambiguous; no inference guidance"),
+            Solution::Ambig(Guidance::Unknown, ..) => write!(f, "Ambiguous; no inference guidance"),
         }
     }
 }
@@ -280,7 +449,7 @@ where
         &mut self,
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
-        should_continue: &dyn std::ops::Fn() -> bool,
+        should_continue: &dyn core::ops::Fn() -> bool,
     ) -> Option<Solution<I>>;
 
     /// Attempts to solve the given goal, which must be in canonical
@@ -312,6 +481,24 @@ where
         f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool;
 
+    /// Solves each of `goals`, returning their solutions in the same order.
+    /// Identical goals are solved only once, and all the goals share the
+    /// cached state of the solver, so this is cheaper than solving them one
+    /// by one for batch workloads, like checking every obligation of a
+    /// function body. See `solve_all_parallel` to use several solvers.
+    fn solve_all(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goals: &[UCanonical<InEnvironment<Goal<I>>>],
+    ) -> Vec<Option<Solution<I>>> {
+        let (unique, indices) = dedup_goals(goals);
+        let solutions: Vec<_> = unique
+            .into_iter()
+            .map(|goal| self.solve(program, goal))
+            .collect();
+        indices.into_iter().map(|i| solutions[i].clone()).collect()
+    }
+
     /// A convenience method for when one doesn't need the actual solution,
     /// only whether or not one exists.
     fn has_unique_solution(
@@ -324,4 +511,335 @@ where
             None => false,
         }
     }
+
+    /// Like `solve`, but with `options` overriding the limits the solver
+    /// was created with, for this goal only. The results of solving with
+    /// another `max_size`, coinductive traits or (for the recursive
+    /// solver) a timeout are not cached, since they can differ from those
+    /// with the solver's own limits. Solvers that do not support options
+    /// only apply the timeout, as in `solve_limited`.
+    fn solve_with_options(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        options: &GoalOptions<I>,
+    ) -> Option<Solution<I>> {
+        let deadline = options.deadline();
+        self.solve_limited(program, goal, &|| {
+            deadline.is_none_or(|deadline| !deadline.is_reached())
+        })
+    }
+
+    /// Like `solve`, but if the solution is ambiguous because the solver
+    /// exceeded its depth or size limits, returns an `Overflow` with the
+    /// goals it was solving at that point. Solvers that do not keep track
+    /// of this just return the solution.
+    fn solve_or_overflow(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Result<Option<Solution<I>>, Overflow<I>> {
+        Ok(self.solve(program, goal))
+    }
+
+    /// Like `solve`, but if the goal cannot be proven, returns why (see
+    /// `NoSolutionCause`). Solvers that do not keep track of this report
+    /// `NoSolutionCause::Unknown`.
+    fn solve_explained(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Result<Solution<I>, NoSolutionCause<I>> {
+        self.solve(program, goal).ok_or(NoSolutionCause::Unknown)
+    }
+
+    /// Returns statistics about the work the solver has done so far.
+    /// Solvers that do not keep track of this report all zeroes, and no
+    /// answers.
+    fn stats(&self) -> SolverStats {
+        SolverStats::default()
+    }
+
+    /// Draws the goals the solver has worked on so far, and how they
+    /// depend on each other, in the GraphViz dot language, for debugging.
+    /// Solvers that keep no such state return `None`.
+    fn graphviz(&self, program: &dyn RustIrDatabase<I>) -> Option<String> {
+        let _ = program;
+        None
+    }
+
+    /// Advances the search for the next answer to `goal` by one step, for
+    /// interactive debuggers, which can inspect where the search is with
+    /// `step_stack` after each step. Stepping through another goal, or
+    /// solving any goal, abandons the search. Solvers which cannot be
+    /// stepped through return `None`.
+    fn step(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<SolverStep<I>> {
+        let _ = (program, goal);
+        None
+    }
+
+    /// The goals on the stack of the search `step` is stepping through,
+    /// outermost first. Empty if there is no such search, or if the last
+    /// step completed it.
+    fn step_stack(&self, program: &dyn RustIrDatabase<I>) -> Vec<StackFrame<I>> {
+        let _ = program;
+        vec![]
+    }
+}
+
+type UCanonicalGoal<I> = UCanonical<InEnvironment<Goal<I>>>;
+
+/// Like `Solver::solve_all`, but splits the distinct goals between
+/// `solvers`, each solving its share on its own thread. Each solver only
+/// reuses the work it does itself, so this pays off for many independent
+/// goals.
+#[cfg(feature = "std")]
+pub fn solve_all_parallel<I, S>(
+    solvers: &mut [S],
+    program: &(dyn RustIrDatabase<I> + Sync),
+    goals: &[UCanonical<InEnvironment<Goal<I>>>],
+) -> Vec<Option<Solution<I>>>
+where
+    I: Interner,
+    S: Solver<I> + Send,
+    UCanonical<InEnvironment<Goal<I>>>: Sync,
+    Solution<I>: Send,
+{
+    assert!(!solvers.is_empty(), "no solvers to solve the goals with");
+    let (unique, indices) = dedup_goals(goals);
+    let chunk_size = unique.len().div_ceil(solvers.len()).max(1);
+    let solutions: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = solvers
+            .iter_mut()
+            .zip(unique.chunks(chunk_size))
+            .map(|(solver, chunk)| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|goal| solver.solve(program, goal))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(solutions) => solutions,
+                Err(panic) => std::panic::resume_unwind(panic),
+            })
+            .collect()
+    });
+    indices.into_iter().map(|i| solutions[i].clone()).collect()
+}
+
+/// The distinct `goals`, in the order they first appear, and for each goal
+/// the index of its distinct goal.
+fn dedup_goals<I: Interner>(goals: &[UCanonicalGoal<I>]) -> (Vec<&UCanonicalGoal<I>>, Vec<usize>) {
+    let mut unique = vec![];
+    let mut seen = FxHashMap::default();
+    let indices = goals
+        .iter()
+        .map(|goal| {
+            *seen.entry(goal).or_insert_with(|| {
+                unique.push(goal);
+                unique.len() - 1
+            })
+        })
+        .collect();
+    (unique, indices)
+}
+
+/// What `Solver::step` did.
+#[derive(Debug)]
+pub enum SolverStep<I: Interner> {
+    /// The search went on without finding an answer yet.
+    Searching,
+    /// The search found the next answer, as `Solver::solve_multiple`
+    /// would give it.
+    Answer(SubstitutionResult<Canonical<ConstrainedSubst<I>>>),
+    /// The goal has no more answers.
+    NoMoreSolutions,
+}
+
+/// A goal on the stack of the search `Solver::step` is stepping through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackFrame<I: Interner> {
+    pub goal: UCanonical<InEnvironment<Goal<I>>>,
+    /// The strand of the SLG solver being pursued for the goal, if any.
+    pub active_strand: Option<ActiveStrand<I>>,
+}
+
+/// A strand of the SLG solver: one way of proving a goal, partway through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActiveStrand<I: Interner> {
+    /// The variables of the strand, which its other fields refer to.
+    pub binders: CanonicalVarKinds<I>,
+    /// The values the strand gives the variables of the goal so far.
+    pub subst: Substitution<I>,
+    /// The subgoals left to prove, negative ones as `not { .. }`.
+    pub subgoals: Vec<InEnvironment<Goal<I>>>,
+    /// The index in `subgoals` of the subgoal being proven, if any.
+    pub selected_subgoal: Option<usize>,
+    pub ambiguous: bool,
+    /// The answers applied to the subgoals proven so far, as the index of
+    /// their table and their index in it, in the order they were applied.
+    pub answer_sources: Vec<(usize, usize)>,
+}
+
+/// Limits for solving a single goal, which override those the solver was
+/// created with; see `Solver::solve_with_options`. This lets e.g. hosts use
+/// small budgets for interactive requests and large ones for batch checks,
+/// with the same solver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoalOptions<I: Interner> {
+    /// The size past which subgoals are truncated, instead of the
+    /// solver's.
+    pub max_size: Option<usize>,
+    /// The maximum number of answers combined into the solution; once
+    /// reached, the solution is ambiguous, suggesting what the answers
+    /// so far have in common. The recursive solver, which does not
+    /// enumerate answers to find a solution, ignores it.
+    pub max_answers: Option<usize>,
+    /// Traits whose goals are coinductive, like those of auto traits.
+    pub coinductive_overrides: Vec<TraitId<I>>,
+    /// How long to work on the goal before giving up with an ambiguous
+    /// solution. Ignored without the `std` feature, which has no clock.
+    pub timeout: Option<Duration>,
+}
+
+impl<I: Interner> GoalOptions<I> {
+    /// The time at which to give up, for a goal solved from now on.
+    pub fn deadline(&self) -> Option<Deadline> {
+        self.timeout.and_then(Deadline::after)
+    }
+}
+
+/// The time at which a solver gives up on a goal. Without the `std`
+/// feature there is no clock, and it is never reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline {
+    #[cfg(feature = "std")]
+    instant: Instant,
+}
+
+impl Deadline {
+    /// The deadline `timeout` from now, or `None` if that is too far in
+    /// the future to be represented, in which case there is no deadline.
+    pub fn after(timeout: Duration) -> Option<Self> {
+        #[cfg(not(feature = "std"))]
+        let _ = timeout;
+        Some(Deadline {
+            #[cfg(feature = "std")]
+            instant: Instant::now().checked_add(timeout)?,
+        })
+    }
+
+    pub fn is_reached(&self) -> bool {
+        #[cfg(feature = "std")]
+        return Instant::now() >= self.instant;
+        #[cfg(not(feature = "std"))]
+        return false;
+    }
+}
+
+impl<I: Interner> Default for GoalOptions<I> {
+    fn default() -> Self {
+        GoalOptions {
+            max_size: None,
+            max_answers: None,
+            coinductive_overrides: vec![],
+            timeout: None,
+        }
+    }
+}
+
+/// The error returned by `Solver::solve_or_overflow` when a solver exceeds
+/// its depth or size limits: the stack of goals it was solving, each
+/// required by the one before it, ending with the goal that overflowed.
+/// Hosts can render it as a chain of "required because of..." notes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overflow<I: Interner> {
+    /// The innermost goals of the stack, at most `Overflow::MAX_STACK`.
+    pub stack: Vec<UCanonical<InEnvironment<Goal<I>>>>,
+    /// The number of outer goals left out of `stack`.
+    pub omitted: usize,
+}
+
+impl<I: Interner> Overflow<I> {
+    /// The maximum number of goals kept in `stack`.
+    pub const MAX_STACK: usize = 32;
+
+    /// Creates an `Overflow` from the stack of goals, outermost first,
+    /// keeping only the innermost `MAX_STACK` goals.
+    pub fn new(mut stack: Vec<UCanonical<InEnvironment<Goal<I>>>>) -> Self {
+        let omitted = stack.len().saturating_sub(Self::MAX_STACK);
+        stack.drain(..omitted);
+        Overflow { stack, omitted }
+    }
+}
+
+/// Something a solver did while solving a goal, as reported to its
+/// `SolverEventSink`. Tools can compute their own metrics from these
+/// events, or drive visualizations of the search, without parsing the
+/// logs of the solver.
+///
+/// The goals are those the solver makes tables for: the goal it was asked
+/// to solve and its subgoals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolverEvent<I: Interner> {
+    /// The solver created a table for `goal`, to start solving it.
+    TableCreated {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+    },
+    /// The solver tried to prove `goal` with `clause`.
+    ClauseTried {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        clause: ProgramClause<I>,
+    },
+    /// The solver found an answer to `goal`. The SLG solver reports each
+    /// new answer of the table; the recursive solver reports the solution
+    /// it settled on, if any.
+    AnswerProduced {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        ambiguous: bool,
+    },
+    /// Solving `goal` floundered: the clauses which may prove it could not
+    /// be enumerated, its answers grew too big, or (in the SLG solver) all
+    /// the subgoals left in one of the strands of its table floundered.
+    StrandFloundered {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+    },
+    /// `goal`, which the solver is solving, was found to be its own
+    /// subgoal. The cycle is `coinductive` if all the goals in it are, in
+    /// which case it holds instead of failing.
+    CycleDetected {
+        goal: UCanonical<InEnvironment<Goal<I>>>,
+        coinductive: bool,
+    },
+}
+
+/// A callback the solvers report their `SolverEvent`s to, as they happen.
+/// It is `Send` and `Sync`, like the solvers which hold it.
+pub type SolverEventSink<I> = Box<dyn FnMut(&SolverEvent<I>) + Send + Sync>;
+
+/// Statistics about the work a solver has done, as reported by
+/// `Solver::stats`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// Number of goals the solver has recorded results for: the tables of
+    /// the SLG solver, or the goals in the cache of the recursive solver.
+    pub tables: usize,
+    /// Number of answers recorded for those goals, or `None` for solvers
+    /// which only record a single result per goal, like the recursive
+    /// solver.
+    pub answers: Option<usize>,
+    /// Number of program clauses the solver has tried to prove goals with.
+    pub clauses: usize,
+    /// Number of duplicate program clauses generated for goals, which the
+    /// solver removed instead of trying them again.
+    pub duplicate_clauses: usize,
 }
@@ -0,0 +1,244 @@
+//! Explains why two canonical goals which "should be the same table" are
+//! not. Solvers look up tables by the exact canonical goal, so two goals
+//! which differ only in the universe of a placeholder, or in the universe
+//! of one of their variables, get different tables; such cache misses are
+//! hard to see by comparing the goals by eye.
+
+use alloc::format;
+use alloc::string::ToString;
+use chalk_ir::cast::Cast;
+use chalk_ir::diff::{diff_tys, TyDiff};
+use chalk_ir::fold::Fold;
+use chalk_ir::interner::{HasInterner, Interner};
+use chalk_ir::zip::{Zip, Zipper};
+use chalk_ir::*;
+use core::fmt;
+
+/// The first difference between two canonical goals, in the order
+/// `explain_difference` looks for them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CanonicalDifference<I: Interner> {
+    /// The goals are quantified over different numbers of universes.
+    Universes { a: usize, b: usize },
+    /// The `index`th variable of the goals differs in kind or universe, or
+    /// is missing (`None`) from one of them.
+    Variable {
+        index: usize,
+        a: Option<CanonicalVarKind<I>>,
+        b: Option<CanonicalVarKind<I>>,
+    },
+    /// The placeholders `a` and `b`, found in matching places, are the
+    /// same but for their universe.
+    PlaceholderUniverse { a: GenericArg<I>, b: GenericArg<I> },
+    /// The types, lifetimes or consts `a` and `b`, found in matching
+    /// places, differ.
+    Term { a: GenericArg<I>, b: GenericArg<I> },
+    /// The environments differ in something other than their types,
+    /// lifetimes and consts (e.g. in their number of clauses).
+    Environment {
+        a: Environment<I>,
+        b: Environment<I>,
+    },
+    /// The goals differ in something other than their types, lifetimes
+    /// and consts (e.g. in their traits).
+    Goal { a: Goal<I>, b: Goal<I> },
+}
+
+impl<I: Interner> fmt::Display for CanonicalDifference<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanonicalDifference::Universes { a, b } => {
+                write!(f, "the goals have {} and {} universes", a, b)
+            }
+            CanonicalDifference::Variable { index, a, b } => {
+                let describe = |kind: &Option<CanonicalVarKind<I>>| match kind {
+                    Some(kind) => format!("{:?} in {:?}", kind.kind, kind.skip_kind()),
+                    None => "missing".to_string(),
+                };
+                write!(
+                    f,
+                    "variable {} is {} in the first goal and {} in the second",
+                    index,
+                    describe(a),
+                    describe(b)
+                )
+            }
+            CanonicalDifference::PlaceholderUniverse { a, b } => write!(
+                f,
+                "the placeholders `{:?}` and `{:?}` are in different universes",
+                a, b
+            ),
+            CanonicalDifference::Term { a, b } => write!(f, "`{:?}` differs from `{:?}`", a, b),
+            CanonicalDifference::Environment { a, b } => {
+                write!(f, "the environments `{:?}` and `{:?}` differ", a, b)
+            }
+            CanonicalDifference::Goal { a, b } => {
+                write!(f, "the goals `{:?}` and `{:?}` differ", a, b)
+            }
+        }
+    }
+}
+
+/// Explains the first difference between the canonical goals `a` and `b`,
+/// or returns `None` if they are the same (and so get the same table).
+/// Differences are looked for in the number of universes, then in the
+/// variables, then in the environments and lastly in the goals; types are
+/// compared with `diff_tys`, down to the components that differ.
+pub fn explain_difference<I: Interner>(
+    interner: I,
+    db: &dyn UnificationDatabase<I>,
+    a: &UCanonical<InEnvironment<Goal<I>>>,
+    b: &UCanonical<InEnvironment<Goal<I>>>,
+) -> Option<CanonicalDifference<I>> {
+    if a.universes() != b.universes() {
+        return Some(CanonicalDifference::Universes {
+            a: a.universes(),
+            b: b.universes(),
+        });
+    }
+
+    let binders_a = a.canonical().binders.as_slice(interner);
+    let binders_b = b.canonical().binders.as_slice(interner);
+    for index in 0..binders_a.len().max(binders_b.len()) {
+        let (var_a, var_b) = (binders_a.get(index), binders_b.get(index));
+        if var_a != var_b {
+            return Some(CanonicalDifference::Variable {
+                index,
+                a: var_a.cloned(),
+                b: var_b.cloned(),
+            });
+        }
+    }
+
+    let (a, b) = (&a.canonical().value, &b.canonical().value);
+    let mut zipper = DifferenceZipper {
+        interner,
+        db,
+        difference: None,
+    };
+    // Zipping environments requires them to have as many clauses.
+    let same_clause_count =
+        a.environment.clauses.len(interner) == b.environment.clauses.len(interner);
+    if !same_clause_count
+        || Zip::zip_with(
+            &mut zipper,
+            Variance::Invariant,
+            &a.environment,
+            &b.environment,
+        )
+        .is_err()
+    {
+        return Some(
+            zipper
+                .difference
+                .unwrap_or_else(|| CanonicalDifference::Environment {
+                    a: a.environment.clone(),
+                    b: b.environment.clone(),
+                }),
+        );
+    }
+    if Zip::zip_with(&mut zipper, Variance::Invariant, &a.goal, &b.goal).is_err() {
+        return Some(
+            zipper
+                .difference
+                .unwrap_or_else(|| CanonicalDifference::Goal {
+                    a: a.goal.clone(),
+                    b: b.goal.clone(),
+                }),
+        );
+    }
+    None
+}
+
+/// Zips two values, recording the first pair of types, lifetimes or
+/// consts which differ.
+struct DifferenceZipper<'i, I: Interner> {
+    interner: I,
+    db: &'i dyn UnificationDatabase<I>,
+    difference: Option<CanonicalDifference<I>>,
+}
+
+impl<I: Interner> DifferenceZipper<'_, I> {
+    /// Records the different `a` and `b` as the difference: as placeholders
+    /// in different universes if that is all that differs between them.
+    fn differ(&mut self, a: GenericArg<I>, b: GenericArg<I>) -> Fallible<()> {
+        let interner = self.interner;
+        let universe_only = match (a.data(interner), b.data(interner)) {
+            (GenericArgData::Ty(ty_a), GenericArgData::Ty(ty_b)) => {
+                match (ty_a.kind(interner), ty_b.kind(interner)) {
+                    (TyKind::Placeholder(p_a), TyKind::Placeholder(p_b)) => p_a.idx == p_b.idx,
+                    _ => false,
+                }
+            }
+            (GenericArgData::Lifetime(lt_a), GenericArgData::Lifetime(lt_b)) => {
+                match (lt_a.data(interner), lt_b.data(interner)) {
+                    (LifetimeData::Placeholder(p_a), LifetimeData::Placeholder(p_b)) => {
+                        p_a.idx == p_b.idx
+                    }
+                    (LifetimeData::Empty(_), LifetimeData::Empty(_)) => true,
+                    _ => false,
+                }
+            }
+            (GenericArgData::Const(c_a), GenericArgData::Const(c_b)) => {
+                match (&c_a.data(interner).value, &c_b.data(interner).value) {
+                    (ConstValue::Placeholder(p_a), ConstValue::Placeholder(p_b)) => {
+                        p_a.idx == p_b.idx && c_a.data(interner).ty == c_b.data(interner).ty
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        self.difference = Some(if universe_only {
+            CanonicalDifference::PlaceholderUniverse { a, b }
+        } else {
+            CanonicalDifference::Term { a, b }
+        });
+        Err(NoSolution)
+    }
+}
+
+impl<I: Interner> Zipper<I> for DifferenceZipper<'_, I> {
+    fn zip_tys(&mut self, _: Variance, a: &Ty<I>, b: &Ty<I>) -> Fallible<()> {
+        match diff_tys(self.interner, a, b) {
+            Some(TyDiff {
+                expected, found, ..
+            }) => self.differ(expected, found),
+            None => Ok(()),
+        }
+    }
+
+    fn zip_lifetimes(&mut self, _: Variance, a: &Lifetime<I>, b: &Lifetime<I>) -> Fallible<()> {
+        if a == b {
+            return Ok(());
+        }
+        let interner = self.interner;
+        self.differ(a.clone().cast(interner), b.clone().cast(interner))
+    }
+
+    fn zip_consts(&mut self, _: Variance, a: &Const<I>, b: &Const<I>) -> Fallible<()> {
+        if a == b {
+            return Ok(());
+        }
+        let interner = self.interner;
+        self.differ(a.clone().cast(interner), b.clone().cast(interner))
+    }
+
+    fn zip_binders<T>(&mut self, variance: Variance, a: &Binders<T>, b: &Binders<T>) -> Fallible<()>
+    where
+        T: Clone + HasInterner<Interner = I> + Zip<I> + Fold<I, Result = T>,
+    {
+        if a.binders != b.binders {
+            return Err(NoSolution);
+        }
+        Zip::zip_with(self, variance, a.skip_binders(), b.skip_binders())
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<I> {
+        self.db
+    }
+}
@@ -7,7 +7,7 @@ use crate::db::ChalkDatabase;
 use crate::query::{ProgramSolverChoice, ProgramText};
 use chalk_solve::SolverChoice;
 use ir;
-use std::sync::Arc;
+use alloc::sync::Arc;
 
 use super::{assert_result, parse_and_lower_goal, parse_and_lower_program};
 
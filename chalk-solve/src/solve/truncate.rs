@@ -4,8 +4,8 @@ use crate::infer::InferenceTable;
 use chalk_ir::interner::Interner;
 use chalk_ir::visit::{SuperVisit, Visit, Visitor};
 use chalk_ir::*;
-use std::cmp::max;
-use std::ops::ControlFlow;
+use core::cmp::max;
+use core::ops::ControlFlow;
 
 /// "Truncation" (called "abstraction" in the papers referenced below)
 /// refers to the act of modifying a goal or answer that has become
@@ -87,6 +87,8 @@ impl<'infer, I: Interner> Visitor<I> for TySizeVisitor<'infer, I> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
     use chalk_integration::{arg, ty};
 
     #[test]
@@ -1,8 +1,8 @@
 use crate::rust_ir::*;
 use crate::RustIrDatabase;
+use alloc::sync::Arc;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use std::sync::Arc;
 use tracing::{debug, instrument};
 
 /// Methods for splitting up the projections for associated types from
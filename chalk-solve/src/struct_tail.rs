@@ -0,0 +1,84 @@
+//! The "unsized tail" of a type: the innermost type in its last field (or
+//! last element, for tuples), which is the only part of a struct or tuple
+//! that may be unsized.
+//!
+//! For example, given
+//!
+//! ```ignore
+//! struct Slice<T> { len: usize, data: [T] }
+//! struct Wrapper<T: ?Sized> { id: u32, inner: T }
+//! ```
+//!
+//! the tail of `Wrapper<Slice<u8>>` is `[u8]`, and the tail of `Wrapper<T>`
+//! is `T`: whether such a type is `Sized` only depends on its tail.
+
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+
+/// The number of fields followed before giving up, in case of structs like
+/// `struct Foo<T>(Foo<Box<T>>)` whose tail is infinite.
+const TAIL_DEPTH_LIMIT: usize = 128;
+
+/// Returns the unsized tail of `ty`, following the last fields of structs
+/// and the last elements of tuples until reaching any other type.
+pub fn struct_tail<I: Interner>(db: &dyn RustIrDatabase<I>, ty: Ty<I>) -> Ty<I> {
+    let mut ty = ty;
+    for _ in 0..TAIL_DEPTH_LIMIT {
+        match tail_step(db, &ty) {
+            Some(tail) => ty = tail,
+            None => break,
+        }
+    }
+    ty
+}
+
+/// Returns the unsized tails of `source` and `target`, following their
+/// last fields in lockstep for as long as both are the same struct, or
+/// tuples of the same arity. This gives the types actually unsized when
+/// coercing `source` to `target`, e.g. `[u8; 4]` and `[u8]` for
+/// `Wrapper<[u8; 4]>` and `Wrapper<[u8]>`.
+pub fn struct_tails<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    source: Ty<I>,
+    target: Ty<I>,
+) -> (Ty<I>, Ty<I>) {
+    let interner = db.interner();
+    let (mut source, mut target) = (source, target);
+    for _ in 0..TAIL_DEPTH_LIMIT {
+        let same_constructor = match (source.kind(interner), target.kind(interner)) {
+            (TyKind::Adt(id_a, _), TyKind::Adt(id_b, _)) => id_a == id_b,
+            (TyKind::Tuple(arity_a, _), TyKind::Tuple(arity_b, _)) => arity_a == arity_b,
+            _ => false,
+        };
+        if !same_constructor {
+            break;
+        }
+        match (tail_step(db, &source), tail_step(db, &target)) {
+            (Some(source_tail), Some(target_tail)) => {
+                source = source_tail;
+                target = target_tail;
+            }
+            _ => break,
+        }
+    }
+    (source, target)
+}
+
+/// Returns the type of the last field of `ty`, if it is a struct or tuple
+/// with at least one field.
+fn tail_step<I: Interner>(db: &dyn RustIrDatabase<I>, ty: &Ty<I>) -> Option<Ty<I>> {
+    let interner = db.interner();
+    match ty.kind(interner) {
+        TyKind::Adt(adt_id, substitution) => db
+            .adt_datum(*adt_id)
+            .tail_field()
+            .map(|field| field.substitute(interner, substitution)),
+        TyKind::Tuple(_, substitution) => substitution
+            .iter(interner)
+            .last()
+            .and_then(|elem| elem.ty(interner))
+            .cloned(),
+        _ => None,
+    }
+}
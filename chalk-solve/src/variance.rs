@@ -0,0 +1,386 @@
+//! Inference of the variances of the parameters of ADTs and fn defs from
+//! their definitions, for hosts which do not compute them themselves (see
+//! `RustIrDatabase::variance_of`).
+//!
+//! A parameter gets the variance of the positions it is used in: the
+//! fields of an ADT are covariant, the arguments of a fn def are
+//! contravariant and its return type is covariant, and the variance of a
+//! position within a type is the one `unify` relates it with. Positions
+//! `unify` relates invariantly (associated types, the bounds of `dyn`
+//! types, closures, ...) make their parameters invariant. As the variances
+//! of an item depend on those of the items it uses, which may use it in
+//! turn, they are computed together, until they no longer change.
+//!
+//! Parameters which are not used at all are bivariant, which
+//! `chalk_ir::Variance` does not have: they are given as invariant.
+
+use crate::FxHashMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+
+use crate::RustIrDatabase;
+#[cfg(feature = "serde")]
+use chalk_ir::serialize::SerdeInterner;
+use chalk_ir::{interner::Interner, visit::Visit, visit::Visitor, *};
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+
+/// An item whose parameters have variances.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound = "I: SerdeInterner")
+)]
+pub enum VarianceItem<I: Interner> {
+    Adt(AdtId<I>),
+    FnDef(FnDefId<I>),
+}
+
+/// The variances of the parameters of `item`, inferred from the types of
+/// its fields or from its signature, and from the variances of the items
+/// these types use. `declared` gives the variances of the items which
+/// declare theirs; these are used as they are rather than inferred.
+pub fn infer_variances<I, DB>(
+    db: &DB,
+    item: VarianceItem<I>,
+    declared: &dyn Fn(VarianceItem<I>) -> Option<Variances<I>>,
+) -> Variances<I>
+where
+    I: Interner,
+    DB: RustIrDatabase<I> + ?Sized,
+{
+    if let Some(variances) = declared(item) {
+        return variances;
+    }
+
+    let mut solver = VarianceSolver {
+        db,
+        declared,
+        items: vec![],
+        inferred: FxHashMap::default(),
+    };
+    solver.add(item);
+
+    // Each pass computes the variances of every item from those of the
+    // previous pass, which only ever grow towards invariance, and may add
+    // the items found to be used to the end of `items`.
+    loop {
+        let mut changed = false;
+        let mut index = 0;
+        while index < solver.items.len() {
+            let item = solver.items[index];
+            let inferred = solver.compute(item);
+            if solver.inferred[&item] != inferred {
+                solver.inferred.insert(item, inferred);
+                changed = true;
+            }
+            index += 1;
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    Variances::from_iter(
+        db.interner(),
+        solver.inferred[&item]
+            .iter()
+            .map(|inferred| match inferred {
+                Inferred::Bivariant => Variance::Invariant,
+                Inferred::Variant(variance) => *variance,
+            }),
+    )
+}
+
+/// The variance of a parameter, as far as its uses seen so far tell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Inferred {
+    /// Not used (yet).
+    Bivariant,
+    Variant(Variance),
+}
+
+impl Inferred {
+    /// The variance of a parameter used both with `self` and `other`.
+    fn join(self, other: Inferred) -> Inferred {
+        match (self, other) {
+            (Inferred::Bivariant, inferred) | (inferred, Inferred::Bivariant) => inferred,
+            (Inferred::Variant(a), Inferred::Variant(b)) if a == b => Inferred::Variant(a),
+            _ => Inferred::Variant(Variance::Invariant),
+        }
+    }
+}
+
+struct VarianceSolver<'a, I: Interner, DB: ?Sized> {
+    db: &'a DB,
+    declared: &'a dyn Fn(VarianceItem<I>) -> Option<Variances<I>>,
+    /// The items whose variances are inferred, in the order they were found.
+    items: Vec<VarianceItem<I>>,
+    inferred: FxHashMap<VarianceItem<I>, Vec<Inferred>>,
+}
+
+impl<'a, I, DB> VarianceSolver<'a, I, DB>
+where
+    I: Interner,
+    DB: RustIrDatabase<I> + ?Sized,
+{
+    fn add(&mut self, item: VarianceItem<I>) {
+        let interner = self.db.interner();
+        let parameters = match item {
+            VarianceItem::Adt(id) => self.db.adt_datum(id).binders.len(interner),
+            VarianceItem::FnDef(id) => self.db.fn_def_datum(id).binders.len(interner),
+        };
+        self.items.push(item);
+        self.inferred
+            .insert(item, vec![Inferred::Bivariant; parameters]);
+    }
+
+    /// The variance of the parameter at `index` of `item`, adding `item` to
+    /// the items to infer if it was not yet.
+    fn variance(&mut self, item: VarianceItem<I>, index: usize) -> Inferred {
+        if let Some(variances) = (self.declared)(item) {
+            return Inferred::Variant(variances.as_slice(self.db.interner())[index]);
+        }
+        if !self.inferred.contains_key(&item) {
+            self.add(item);
+        }
+        self.inferred[&item][index]
+    }
+
+    /// The variances of the parameters of `item` given the current
+    /// variances of the items it uses.
+    fn compute(&mut self, item: VarianceItem<I>) -> Vec<Inferred> {
+        let mut parameters = vec![Inferred::Bivariant; self.inferred[&item].len()];
+        match item {
+            VarianceItem::Adt(id) => {
+                let datum = self.db.adt_datum(id);
+                for variant in &datum.binders.skip_binders().variants {
+                    for field in &variant.fields {
+                        self.add_ty(
+                            &mut parameters,
+                            field,
+                            Variance::Covariant,
+                            DebruijnIndex::INNERMOST,
+                        );
+                    }
+                }
+            }
+            VarianceItem::FnDef(id) => {
+                let datum = self.db.fn_def_datum(id);
+                let inputs_and_output = datum
+                    .binders
+                    .skip_binders()
+                    .inputs_and_output
+                    .skip_binders();
+                // The signature is under the binders of the late-bound
+                // lifetimes.
+                let depth = DebruijnIndex::INNERMOST.shifted_in();
+                for argument in &inputs_and_output.argument_types {
+                    self.add_ty(&mut parameters, argument, Variance::Contravariant, depth);
+                }
+                self.add_ty(
+                    &mut parameters,
+                    &inputs_and_output.return_type,
+                    Variance::Covariant,
+                    depth,
+                );
+            }
+        }
+        parameters
+    }
+
+    /// Records the uses of the parameters in `ty`, which is in a position
+    /// of variance `variance`, `depth` binders within the item.
+    fn add_ty(
+        &mut self,
+        parameters: &mut [Inferred],
+        ty: &Ty<I>,
+        variance: Variance,
+        depth: DebruijnIndex,
+    ) {
+        let interner = self.db.interner();
+        match ty.kind(interner) {
+            TyKind::BoundVar(bound_var) => {
+                if let Some(index) = bound_var.index_if_bound_at(depth) {
+                    parameters[index] = parameters[index].join(Inferred::Variant(variance));
+                }
+            }
+            TyKind::Adt(id, substitution) => self.add_item_arguments(
+                parameters,
+                VarianceItem::Adt(*id),
+                substitution,
+                variance,
+                depth,
+            ),
+            TyKind::FnDef(id, substitution) => self.add_item_arguments(
+                parameters,
+                VarianceItem::FnDef(*id),
+                substitution,
+                variance,
+                depth,
+            ),
+            TyKind::Ref(mutability, lifetime, ty) => {
+                self.add_lifetime(
+                    parameters,
+                    lifetime,
+                    variance.xform(Variance::Contravariant),
+                    depth,
+                );
+                self.add_ty(
+                    parameters,
+                    ty,
+                    variance.xform(mutability_variance(*mutability)),
+                    depth,
+                );
+            }
+            TyKind::Raw(mutability, ty) => self.add_ty(
+                parameters,
+                ty,
+                variance.xform(mutability_variance(*mutability)),
+                depth,
+            ),
+            TyKind::Slice(ty) => self.add_ty(parameters, ty, variance, depth),
+            TyKind::Array(ty, const_) => {
+                self.add_ty(parameters, ty, variance, depth);
+                add_invariant(interner, parameters, const_, depth);
+            }
+            TyKind::Tuple(_, substitution) => {
+                for argument in substitution.iter(interner) {
+                    self.add_generic_arg(parameters, argument, variance, depth);
+                }
+            }
+            TyKind::Function(fn_ptr) => {
+                // The arguments, then the return type.
+                let arguments = fn_ptr.substitution.0.as_slice(interner);
+                let depth = depth.shifted_in();
+                for (index, argument) in arguments.iter().enumerate() {
+                    let variance = if index + 1 < arguments.len() {
+                        variance.xform(Variance::Contravariant)
+                    } else {
+                        variance
+                    };
+                    self.add_generic_arg(parameters, argument, variance, depth);
+                }
+            }
+            TyKind::Dyn(dyn_ty) => {
+                self.add_lifetime(
+                    parameters,
+                    &dyn_ty.lifetime,
+                    variance.xform(Variance::Contravariant),
+                    depth,
+                );
+                add_invariant(interner, parameters, &dyn_ty.bounds, depth);
+            }
+            _ => add_invariant(interner, parameters, ty, depth),
+        }
+    }
+
+    fn add_lifetime(
+        &mut self,
+        parameters: &mut [Inferred],
+        lifetime: &Lifetime<I>,
+        variance: Variance,
+        depth: DebruijnIndex,
+    ) {
+        if let LifetimeData::BoundVar(bound_var) = lifetime.data(self.db.interner()) {
+            if let Some(index) = bound_var.index_if_bound_at(depth) {
+                parameters[index] = parameters[index].join(Inferred::Variant(variance));
+            }
+        }
+    }
+
+    fn add_generic_arg(
+        &mut self,
+        parameters: &mut [Inferred],
+        argument: &GenericArg<I>,
+        variance: Variance,
+        depth: DebruijnIndex,
+    ) {
+        let interner = self.db.interner();
+        match argument.data(interner) {
+            GenericArgData::Ty(ty) => self.add_ty(parameters, ty, variance, depth),
+            GenericArgData::Lifetime(lifetime) => {
+                self.add_lifetime(parameters, lifetime, variance, depth)
+            }
+            GenericArgData::Const(const_) => add_invariant(interner, parameters, const_, depth),
+        }
+    }
+
+    /// Records the uses of the parameters in the arguments `substitution`
+    /// given to `item`: each is in a position of the variance of the
+    /// corresponding parameter of `item`, within `variance`. Arguments for
+    /// parameters which `item` does not use do not use anything either.
+    fn add_item_arguments(
+        &mut self,
+        parameters: &mut [Inferred],
+        item: VarianceItem<I>,
+        substitution: &Substitution<I>,
+        variance: Variance,
+        depth: DebruijnIndex,
+    ) {
+        let interner = self.db.interner();
+        for (index, argument) in substitution.iter(interner).enumerate() {
+            if let Inferred::Variant(item_variance) = self.variance(item, index) {
+                self.add_generic_arg(parameters, argument, variance.xform(item_variance), depth);
+            }
+        }
+    }
+}
+
+fn mutability_variance(mutability: Mutability) -> Variance {
+    match mutability {
+        Mutability::Not => Variance::Covariant,
+        Mutability::Mut => Variance::Invariant,
+    }
+}
+
+/// Records every use of the parameters in `value` as invariant.
+fn add_invariant<I: Interner>(
+    interner: I,
+    parameters: &mut [Inferred],
+    value: &impl Visit<I>,
+    depth: DebruijnIndex,
+) {
+    let _ = value.visit_with(
+        &mut InvariantUses {
+            interner,
+            parameters,
+        },
+        depth,
+    );
+}
+
+struct InvariantUses<'p, I: Interner> {
+    interner: I,
+    parameters: &'p mut [Inferred],
+}
+
+impl<'p, I: Interner> Visitor<I> for InvariantUses<'p, I> {
+    type BreakTy = ();
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<I, BreakTy = Self::BreakTy> {
+        self
+    }
+
+    fn interner(&self) -> I {
+        self.interner
+    }
+
+    fn visit_free_var(
+        &mut self,
+        bound_var: BoundVar,
+        outer_binder: DebruijnIndex,
+    ) -> ControlFlow<()> {
+        // Visiting starts at the depth of the parameters.
+        if let Some(index) = bound_var
+            .shifted_out_to(outer_binder)
+            .and_then(|bound_var| bound_var.index_if_innermost())
+        {
+            self.parameters[index] =
+                self.parameters[index].join(Inferred::Variant(Variance::Invariant));
+        }
+        ControlFlow::Continue(())
+    }
+}
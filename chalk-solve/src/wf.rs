@@ -1,5 +1,7 @@
-use std::ops::ControlFlow;
-use std::{fmt, iter};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use core::{fmt, iter};
 
 use crate::{
     ext::*, goal_builder::GoalBuilder, rust_ir::*, solve::Solver, split::Split, RustIrDatabase,
@@ -13,7 +15,7 @@ use chalk_ir::{
 };
 use tracing::debug;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum WfError<I: Interner> {
     IllFormedTypeDecl(chalk_ir::AdtId<I>),
     IllFormedOpaqueTypeDecl(chalk_ir::OpaqueTyId<I>),
@@ -42,6 +44,7 @@ impl<I: Interner> fmt::Display for WfError<I> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<I: Interner> std::error::Error for WfError<I> {}
 
 pub struct WfSolver<'a, I: Interner> {
@@ -177,6 +180,10 @@ impl<I: Interner> Visitor<I> for InputTypeCollector<I> {
                 push_ty();
                 ControlFlow::Continue(())
             }
+            TyKind::Custom(custom_ty) => {
+                push_ty();
+                interner.visit_custom_ty(custom_ty, self, outer_binder)
+            }
             TyKind::Error => {
                 push_ty();
                 ControlFlow::Continue(())
@@ -197,6 +204,11 @@ impl<I: Interner> Visitor<I> for InputTypeCollector<I> {
                 opaque_ty.visit_with(self, outer_binder)
             }
 
+            TyKind::Alias(AliasTy::Inherent(inherent_ty)) => {
+                push_ty();
+                inherent_ty.visit_with(self, outer_binder)
+            }
+
             TyKind::Placeholder(_) => {
                 push_ty();
                 ControlFlow::Continue(())
@@ -244,6 +256,10 @@ where
         let adt_datum = self.db.adt_datum(adt_id);
         let is_enum = adt_datum.kind == AdtKind::Enum;
 
+        if !adt_repr_is_legal(self.db, &adt_datum) {
+            return Err(WfError::IllFormedTypeDecl(adt_id));
+        }
+
         let mut gb = GoalBuilder::new(self.db);
         let adt_data = adt_datum
             .binders
@@ -361,7 +377,8 @@ where
         let goal = gb.forall(bound, opaque_ty_id, |gb, _, bound, opaque_ty_id| {
             let interner = gb.interner();
 
-            let subst = Substitution::from1(interner, gb.db().hidden_opaque_type(opaque_ty_id));
+            let hidden_ty = gb.db().hidden_opaque_type(opaque_ty_id);
+            let subst = Substitution::from1(interner, hidden_ty.clone());
 
             let bounds = bound.bounds.clone().substitute(interner, &subst);
             let where_clauses = bound.where_clauses.clone().substitute(interner, &subst);
@@ -375,12 +392,18 @@ where
             gb.implies(clauses, |gb| {
                 let interner = gb.interner();
 
-                // all(WellFormed(Baz: Clone))
+                // all(WellFormed(Baz: Clone), WellFormed(Baz))
                 gb.all(
                     bounds
                         .iter()
                         .cloned()
-                        .map(|b| b.into_well_formed_goal(interner)),
+                        .map(|b| b.into_well_formed_goal(interner))
+                        .chain(Some(Binders::empty(
+                            interner,
+                            DomainGoal::WellFormed(WellFormed::Ty(
+                                hidden_ty.clone().shifted_in(interner),
+                            )),
+                        ))),
                 )
             })
         });
@@ -427,15 +450,23 @@ where
                     &impl_datum,
                 )
             }
-            WellKnownTrait::Clone | WellKnownTrait::Unpin => true,
+            WellKnownTrait::Clone
+            | WellKnownTrait::Unpin
+            | WellKnownTrait::Deref
+            | WellKnownTrait::IntoIterator
+            | WellKnownTrait::Iterator => true,
             // You can't add a manual implementation for the following traits:
             WellKnownTrait::Fn
             | WellKnownTrait::FnOnce
             | WellKnownTrait::FnMut
             | WellKnownTrait::Unsize
             | WellKnownTrait::Sized
+            | WellKnownTrait::MetaSized
+            | WellKnownTrait::PointeeSized
             | WellKnownTrait::DiscriminantKind
-            | WellKnownTrait::Generator => false,
+            | WellKnownTrait::Generator
+            | WellKnownTrait::Tuple
+            | WellKnownTrait::FnPtr => false,
         };
 
         if is_legal {
@@ -658,6 +689,84 @@ fn compute_assoc_ty_goal<I: Interner>(
     ))
 }
 
+/// Whether the `repr` attributes of an ADT are legal for it:
+///   - `repr(transparent)` cannot be combined with other representations,
+///     and the ADT must have a single variant, with at most one field which
+///     is not a 1-ZST
+///   - `repr(simd)` ADTs must be structs with at least one field, and all
+///     their fields must have the same type
+fn adt_repr_is_legal<I: Interner>(db: &dyn RustIrDatabase<I>, adt_datum: &AdtDatum<I>) -> bool {
+    let repr = db.adt_repr(adt_datum.id);
+    let variants = &adt_datum.binders.skip_binders().variants;
+
+    if repr.transparent {
+        if repr.c || repr.packed || repr.simd || repr.int.is_some() || variants.len() != 1 {
+            return false;
+        }
+        let non_zst_fields = variants[0]
+            .fields
+            .iter()
+            .filter(|field| !is_one_zst(db, field))
+            .count();
+        if non_zst_fields > 1 {
+            return false;
+        }
+    }
+
+    if repr.simd {
+        if adt_datum.kind != AdtKind::Struct {
+            return false;
+        }
+        let fields = &variants[0].fields;
+        if fields.is_empty() || fields.iter().any(|field| *field != fields[0]) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Whether `ty` is known to be a 1-ZST, a type of size 0 and alignment 1:
+/// an ADT declared `#[one_zst]`, or a `repr(transparent)` struct whose
+/// fields are all 1-ZSTs.
+fn is_one_zst<I: Interner>(db: &dyn RustIrDatabase<I>, ty: &Ty<I>) -> bool {
+    fn is_one_zst_inner<I: Interner>(
+        db: &dyn RustIrDatabase<I>,
+        ty: &Ty<I>,
+        visited: &mut Vec<AdtId<I>>,
+    ) -> bool {
+        let interner = db.interner();
+        let (adt_id, substitution) = match ty.kind(interner) {
+            TyKind::Adt(adt_id, substitution) => (*adt_id, substitution),
+            _ => return false,
+        };
+        if db.adt_size_align(adt_id).one_zst() {
+            return true;
+        }
+        // A struct containing itself is not a 1-ZST, nor well-formed.
+        if !db.adt_repr(adt_id).transparent || visited.contains(&adt_id) {
+            return false;
+        }
+        let adt_datum = db.adt_datum(adt_id);
+        if adt_datum.kind != AdtKind::Struct {
+            return false;
+        }
+        visited.push(adt_id);
+        let is_one_zst = adt_datum
+            .binders
+            .clone()
+            .substitute(interner, substitution)
+            .variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .all(|field| is_one_zst_inner(db, field, visited));
+        visited.pop();
+        is_one_zst
+    }
+
+    is_one_zst_inner(db, ty, &mut Vec::new())
+}
+
 /// Defines methods to compute well-formedness goals for well-known
 /// traits (e.g. a goal for all fields of struct in a Copy impl to be Copy)
 struct WfWellKnownConstraints;
@@ -1154,10 +1263,7 @@ impl WfWellKnownConstraints {
                 let mut non_zst_fields: Vec<_> = source_fields
                     .iter()
                     .zip(target_fields.iter())
-                    .filter(|(sf, _)| match sf.adt_id(interner) {
-                        Some(adt) => !db.adt_size_align(adt).one_zst(),
-                        None => true,
-                    })
+                    .filter(|(sf, _)| !is_one_zst(db, sf))
                     .collect();
 
                 if non_zst_fields.len() != 1 {
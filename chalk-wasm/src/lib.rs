@@ -0,0 +1,54 @@
+//! Browser bindings for chalk: a program is parsed and checked once, in a
+//! `Session`, and goals are then solved in it, with the results reported as
+//! JSON. See the README for how to build and call them.
+
+use chalk_integration::batch::{self, Format};
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use wasm_bindgen::prelude::*;
+
+/// A program, and the solver which keeps its tables from one goal to the
+/// next.
+#[wasm_bindgen]
+pub struct Session {
+    db: ChalkDatabase,
+    program: String,
+    solver: String,
+}
+
+#[wasm_bindgen]
+impl Session {
+    /// Parses and checks `program`, whose goals are solved by the solver
+    /// named `solver`, `slg` or `recursive`. Throws the error message if the
+    /// program is not valid.
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: &str, solver: &str) -> Result<Session, JsValue> {
+        let solver_choice = SolverChoice::from_name(solver)
+            .ok_or_else(|| JsValue::from_str(&format!("unknown solver `{}`", solver)))?;
+        let db = ChalkDatabase::with(program, solver_choice);
+        db.checked_program()
+            .map_err(|error| JsValue::from_str(&error.to_string()))?;
+        Ok(Session {
+            db,
+            program: program.to_string(),
+            solver: solver.to_string(),
+        })
+    }
+
+    /// Solves each goal of `goals`, one per line (or continued on the next
+    /// lines until its brackets are balanced), and returns the JSON report
+    /// of `chalk solve --format=json`. Goals which don't parse are reported
+    /// as errors.
+    pub fn solve(&self, goals: &str) -> String {
+        self.db.with_program(|_| {
+            batch::solve(
+                &self.db,
+                &self.program,
+                &self.solver,
+                &batch::entries(goals),
+                Format::Json,
+            )
+        })
+    }
+}
@@ -1,28 +1,50 @@
 #[macro_use]
 extern crate serde_derive;
 
-use std::fs::File;
-use std::io::Read;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::time::Duration;
 
+use chalk_integration::batch::{self, entries, is_incomplete};
+use chalk_integration::conformance;
 use chalk_integration::db::ChalkDatabase;
+use chalk_integration::debugger::{Command, Debugger};
+use chalk_integration::error::ChalkError;
+use chalk_integration::explain;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::*;
+use chalk_integration::minimize::{Minimizer, Problem};
 use chalk_integration::query::LoweringDatabase;
 use chalk_integration::SolverChoice;
+use chalk_parse::ParseDiagnostics;
+use chalk_solve::display::DisplayWithDb;
 use chalk_solve::ext::*;
 use chalk_solve::logging;
-use chalk_solve::RustIrDatabase;
+use chalk_solve::{RustIrDatabase, SolverStep};
 use docopt::Docopt;
+use rustyline::completion::Completer;
 use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::Helper;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// How many goals deep `explain` goes when no depth is given.
+const EXPLAIN_DEPTH: usize = 8;
+
 const USAGE: &str = "
 chalk repl
 
 Usage:
   chalk [options]
+  chalk --script=PATH [options]
+  chalk solve [options] --goals=PATH
+  chalk minimize [options] (--panics | --answer=ANSWER | --timeout=SECS)
+  chalk conformance [options] --corpus=DIR
+  chalk debug [options] --goal=GOAL
   chalk (-h | --help)
 
 Options:
@@ -32,6 +54,42 @@ Options:
   --overflow-depth=N  Specifies the overflow depth [default: 10].
   --multiple          Output multiple answers instead of ambiguous solution.
   --solver=S          Specifies the solver to use. `slg` or `recursive`. Default is SLG.
+  --script=PATH       Runs the commands and goals in the script at PATH instead of the REPL.
+  --dump-forest=PATH  Writes the SLG forest to PATH as a GraphViz graph after solving
+                      the goals given with `--goal`.
+
+Solving:
+  `chalk solve` solves each goal in the file given with `--goals` in the program
+  given with `--program`, and reports the results. Goals are laid out as in
+  scripts (see below).
+  --goals=PATH        The file of goals to solve.
+  --format=FORMAT     Reports the results as `text` or `json` [default: text].
+
+Minimizing:
+  `chalk minimize` shrinks the program given with `--program` to a smaller one
+  on which the goal given with `--goal` still shows a problem, and prints it.
+  --panics            The problem is that solving the goal panics.
+  --answer=ANSWER     The problem is that the goal has this answer.
+  --timeout=SECS      The problem is that solving the goal takes longer than SECS seconds.
+
+Conformance:
+  `chalk conformance` reads each `.rs` file in the directory given with `--corpus`
+  as a rustc UI test, and reports, as a table, whether chalk agrees with rustc on
+  whether it compiles. Fails if they disagree on any test.
+  --corpus=DIR        The directory of tests.
+
+Debugging:
+  `chalk debug` steps through the SLG solver's search for the answers to the goal
+  given with `--goal`, in the program given with `--program`, in a terminal UI
+  showing the solver's tables, the stack of the search, the answers of a table
+  and the strand pursued for a goal of the stack. Type the keys shown at the
+  bottom of the screen, followed by enter; enter alone takes a step.
+
+Scripts:
+  A script holds REPL commands and goals, one per line; a goal may span several
+  lines until its brackets are balanced. A goal may be followed by a line
+  `=> ANSWER`, and the script fails if the goal's answer does not start with
+  ANSWER. Empty lines and lines starting with `//` are skipped.
 ";
 
 /// This struct represents the various command line options available.
@@ -42,12 +100,28 @@ struct Args {
     flag_goal: Vec<String>,
     flag_overflow_depth: usize,
     flag_multiple: bool,
+    flag_answer: Option<String>,
+    flag_timeout: Option<f64>,
+    flag_script: Option<String>,
+    flag_goals: Option<String>,
+    flag_dump_forest: Option<String>,
+    flag_format: String,
+    flag_corpus: Option<String>,
+    cmd_solve: bool,
+    cmd_minimize: bool,
+    cmd_conformance: bool,
+    cmd_debug: bool,
 }
 
 /// A loaded and parsed program.
 struct LoadedProgram {
     text: String,
+    /// The file the program was loaded from, if any, for `reload`.
+    path: Option<PathBuf>,
     db: ChalkDatabase,
+    /// The goal last given to `step`, which `step` without a goal steps
+    /// through.
+    step_goal: RefCell<Option<String>>,
 }
 
 impl LoadedProgram {
@@ -57,22 +131,25 @@ impl LoadedProgram {
     /// [`SolverChoice`]: struct.solve.SolverChoice.html
     fn new(text: String, solver_choice: SolverChoice) -> Result<LoadedProgram> {
         let db = ChalkDatabase::with(&text, solver_choice);
-        Ok(LoadedProgram { text, db })
+        Ok(LoadedProgram {
+            text,
+            path: None,
+            db,
+            step_goal: RefCell::new(None),
+        })
     }
 
     /// Parse a goal and attempt to solve it, using the specified solver.
     fn goal(
         &self,
-        mut rl: Option<&mut rustyline::Editor<()>>,
+        mut rl: Option<&mut rustyline::Editor<ReplHelper>>,
         text: &str,
         multiple_answers: bool,
     ) -> Result<()> {
-        let program = self.db.checked_program()?;
-        let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &*program)?;
-        let peeled_goal = goal.into_peeled_goal(self.db.interner());
         if multiple_answers {
+            let peeled_goal = self.peeled_goal(text)?;
             let no_more_solutions = self.db.solve_multiple(&peeled_goal, &mut |v, has_next| {
-                println!("{}\n", v.as_ref().map(|v| v.display(ChalkIr)));
+                println!("{}\n", v.as_ref().map(|v| v.display_with(&self.db)));
                 if has_next {
                     if let Some(ref mut rl) = rl {
                         loop {
@@ -99,13 +176,134 @@ impl LoadedProgram {
                 println!("No more solutions");
             }
         } else {
-            match self.db.solve(&peeled_goal) {
-                Some(v) => println!("{}\n", v.display(ChalkIr)),
-                None => println!("No possible solution.\n"),
+            println!("{}\n", self.answer(text)?);
+        }
+        Ok(())
+    }
+
+    /// Parse a goal, optionally preceded by a depth limit, and print how it
+    /// is proven or why it can't be.
+    fn explain(&self, text: &str) -> Result<()> {
+        let text = text.trim();
+        let (depth, text) = text
+            .split_once(char::is_whitespace)
+            .and_then(|(depth, goal)| Some((depth.parse().ok()?, goal)))
+            .unwrap_or((EXPLAIN_DEPTH, text));
+        let peeled_goal = self.peeled_goal(text)?;
+        println!("{}", explain::explain(&self.db, &peeled_goal, depth));
+        Ok(())
+    }
+
+    /// Writes the forest of the SLG solver as a GraphViz graph to `path`,
+    /// or prints it.
+    fn dump_forest(&self, path: Option<&Path>) -> Result<()> {
+        let dot = self
+            .db
+            .solver()
+            .lock()
+            .unwrap()
+            .graphviz(&self.db)
+            .ok_or("only the SLG solver has a forest to dump")?;
+        match path {
+            Some(path) => std::fs::write(path, dot)
+                .map_err(|e| format!("error writing {}: {}", path.display(), e))?,
+            None => print!("{}", dot),
+        }
+        Ok(())
+    }
+
+    /// Advances the search for the next answer to a goal, or to the goal
+    /// last stepped through, by one step of the SLG solver, and prints the
+    /// stack of the search.
+    fn step(&self, text: Option<&str>) -> Result<()> {
+        let text = match text {
+            Some(text) => {
+                *self.step_goal.borrow_mut() = Some(text.to_string());
+                text.to_string()
+            }
+            None => self
+                .step_goal
+                .borrow()
+                .clone()
+                .ok_or("no goal to step through; use `step <goal>`")?,
+        };
+        let peeled_goal = self.peeled_goal(&text)?;
+        let solver = self.db.solver();
+        let mut solver = solver.lock().unwrap();
+        let step = solver
+            .step(&self.db, &peeled_goal)
+            .ok_or("only the SLG solver can be stepped through")?;
+        let stack = solver.step_stack(&self.db);
+        for (depth, frame) in stack.iter().enumerate() {
+            println!("{}: {:?}", depth, frame.goal.canonical().value.goal);
+            if let Some(strand) = &frame.active_strand {
+                println!("   strand {:?}", strand.subst);
+                for (index, subgoal) in strand.subgoals.iter().enumerate() {
+                    let marker = if strand.selected_subgoal == Some(index) {
+                        "->"
+                    } else {
+                        "  "
+                    };
+                    println!("   {} {:?}", marker, subgoal.goal);
+                }
             }
         }
+        match step {
+            SolverStep::Searching => println!("Searching\n"),
+            SolverStep::Answer(answer) => {
+                println!(
+                    "Answer: {}\n",
+                    answer.as_ref().map(|v| v.display_with(&self.db))
+                )
+            }
+            SolverStep::NoMoreSolutions => println!("No more solutions\n"),
+        }
         Ok(())
     }
+
+    /// Parse a goal and solve it, returning the answer as it is printed.
+    fn answer(&self, text: &str) -> Result<String> {
+        let peeled_goal = self.peeled_goal(text)?;
+        Ok(match self.db.solve(&peeled_goal) {
+            Some(v) => v.display_with(&self.db).to_string(),
+            None => "No possible solution.".to_string(),
+        })
+    }
+
+    fn peeled_goal(
+        &self,
+        text: &str,
+    ) -> Result<chalk_ir::UCanonical<chalk_ir::InEnvironment<chalk_ir::Goal<ChalkIr>>>> {
+        let program = self.db.checked_program()?;
+        let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &*program)?;
+        Ok(goal.into_peeled_goal(self.db.interner()))
+    }
+}
+
+/// Makes rustyline keep reading lines, letting the user edit all of them,
+/// until the brackets of the entry are balanced.
+struct ReplHelper;
+
+impl Helper for ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(if is_incomplete(ctx.input()) {
+            ValidationResult::Incomplete
+        } else {
+            ValidationResult::Valid(None)
+        })
+    }
 }
 
 fn run() -> Result<()> {
@@ -120,13 +318,33 @@ fn run() -> Result<()> {
         exit(1);
     }
 
+    if args.cmd_solve {
+        return solve(args);
+    }
+
+    if args.cmd_minimize {
+        return minimize(args);
+    }
+
+    if args.cmd_conformance {
+        return conformance(args);
+    }
+
+    if args.cmd_debug {
+        return debug(args);
+    }
+
+    if let Some(script) = &args.flag_script {
+        return run_script(args, Path::new(script));
+    }
+
     // Load the .chalk file, if given.
     let mut prog = None;
     if let Some(program) = &args.flag_program {
-        match load_program(args, program) {
+        match load_program(args, Path::new(program)) {
             Ok(p) => prog = Some(p),
             Err(err) => {
-                eprintln!("error loading program: {}", err);
+                report_error("error loading program", &*err);
                 exit(1);
             }
         }
@@ -134,11 +352,24 @@ fn run() -> Result<()> {
 
     if args.flag_goal.is_empty() {
         // The user specified no goal. Enter interactive mode.
-        readline_loop(&mut rustyline::Editor::new(), "?- ", |rl, line| {
-            if let Err(e) = process(args, line, rl, &mut prog) {
-                eprintln!("error: {}", e);
+        let mut rl = rustyline::Editor::new();
+        rl.set_helper(Some(ReplHelper));
+        let history = history_path();
+        if let Some(history) = &history {
+            // There is no history the first time the REPL is run.
+            let _ = rl.load_history(history);
+        }
+        let result = readline_loop(&mut rl, "?- ", |rl, line| {
+            if let Err(e) = process(args, line, Some(rl), &mut prog) {
+                report_error("error", &*e);
             }
-        })
+        });
+        if let Some(history) = &history {
+            if let Err(e) = rl.save_history(history) {
+                report_error("error saving history", &e);
+            }
+        }
+        result
     } else {
         // Check that a program was provided.
         // TODO: It's customary to print Usage info when an error like this
@@ -151,7 +382,13 @@ fn run() -> Result<()> {
         prog.db.with_program(|_| -> Result<()> {
             for g in &args.flag_goal {
                 if let Err(e) = prog.goal(None, g, args.flag_multiple) {
-                    eprintln!("error: {}", e);
+                    report_error("error", &*e);
+                    exit(1);
+                }
+            }
+            if let Some(path) = &args.flag_dump_forest {
+                if let Err(e) = prog.dump_forest(Some(Path::new(path))) {
+                    report_error("error dumping forest", &*e);
                     exit(1);
                 }
             }
@@ -162,20 +399,210 @@ fn run() -> Result<()> {
     }
 }
 
+/// Solves the goals in the file given with `--goals`, and prints the
+/// results in the format given with `--format`.
+fn solve(args: &Args) -> Result<()> {
+    let format = batch::Format::from_name(&args.flag_format)
+        .ok_or_else(|| format!("error: unknown format `{}`", args.flag_format))?;
+    let program = args
+        .flag_program
+        .as_deref()
+        .ok_or("error: cannot solve without a program; use `--program` to specify one.")?;
+    let goals = args.flag_goals.as_deref().unwrap();
+    let goals =
+        std::fs::read_to_string(goals).map_err(|e| format!("error reading {}: {}", goals, e))?;
+
+    let prog = match load_program(args, Path::new(program)) {
+        Ok(prog) => prog,
+        Err(err) => {
+            report_error("error loading program", &*err);
+            exit(1);
+        }
+    };
+    let solver = args.flag_solver.as_deref().unwrap_or("slg");
+    let report = prog
+        .db
+        .with_program(|_| batch::solve(&prog.db, program, solver, &entries(&goals), format));
+    print!("{}", report);
+    Ok(())
+}
+
+/// Minimizes the program given with `--program` for the problem given on the
+/// command line, and prints the result.
+fn minimize(args: &Args) -> Result<()> {
+    let filename = args
+        .flag_program
+        .as_deref()
+        .ok_or("error: cannot minimize without a program; use `--program` to specify one.")?;
+    let goal = match args.flag_goal.as_slice() {
+        [goal] => goal,
+        _ => return Err("error: minimizing needs exactly one goal, given with `--goal`.".into()),
+    };
+    let problem = match (&args.flag_answer, args.flag_timeout) {
+        (Some(answer), _) => Problem::Answer(answer.clone()),
+        (None, Some(seconds)) => Problem::Timeout(Duration::from_secs_f64(seconds)),
+        (None, None) => Problem::Panic,
+    };
+
+    let program = chalk_parse::parse_program_file(Path::new(filename))?;
+    // Don't print the panic of every candidate that panics.
+    std::panic::set_hook(Box::new(|_| {}));
+    let minimized = Minimizer::new(goal, problem, args.solver_choice()).minimize(&program);
+    let _ = std::panic::take_hook();
+    match minimized {
+        Ok(minimized) => print!("{}", minimized),
+        Err(err) => {
+            report_error("error minimizing program", &err);
+            exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the debugger on the goal given with `--goal`, redrawing the screen
+/// after each command.
+fn debug(args: &Args) -> Result<()> {
+    let program = args
+        .flag_program
+        .as_deref()
+        .ok_or("error: cannot debug without a program; use `--program` to specify one.")?;
+    let goal = match args.flag_goal.as_slice() {
+        [goal] => goal,
+        _ => return Err("error: debugging needs exactly one goal, given with `--goal`.".into()),
+    };
+    if !matches!(args.solver_choice(), SolverChoice::SLG { .. }) {
+        return Err("error: only the SLG solver can be debugged.".into());
+    }
+    let prog = load_program(args, Path::new(program))?;
+    let goal = prog.peeled_goal(goal)?;
+
+    let mut debugger = Debugger::new(&prog.db, goal);
+    let mut rl = rustyline::Editor::<()>::new();
+    loop {
+        // Clear the screen, and draw the debugger at its top.
+        print!("\x1b[2J\x1b[H{}", debugger.render());
+        let line = match rl.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if debugger.command(&line) == Command::Quit {
+            return Ok(());
+        }
+    }
+}
+
+/// Compares chalk with rustc on the tests in the directory given with
+/// `--corpus`, and prints the report.
+fn conformance(args: &Args) -> Result<()> {
+    let corpus = args.flag_corpus.as_deref().unwrap();
+    let report = conformance::check_corpus(Path::new(corpus), args.solver_choice())
+        .map_err(|e| format!("error reading {}: {}", corpus, e))?;
+    print!("{}", report);
+    match report.divergences().count() {
+        0 => Ok(()),
+        1 => Err("error: chalk diverges from rustc on 1 test\n".into()),
+        n => Err(format!("error: chalk diverges from rustc on {} tests\n", n).into()),
+    }
+}
+
+/// Runs the script at `path` (see "Scripts" in `USAGE`), reporting each
+/// command that fails and each goal whose answer is not the expected one.
+fn run_script(args: &Args, path: &Path) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("error reading {}: {}", path.display(), e))?;
+    let mut prog: Option<LoadedProgram> = None;
+    let mut failures = 0;
+    for entry in script_entries(&text) {
+        let location = format!("{}:{}", path.display(), entry.line);
+        println!("?- {}", entry.text);
+        let result = match (&entry.expected, &prog) {
+            (Some(expected), Some(loaded)) => loaded.db.with_program(|_| -> Result<()> {
+                let answer = loaded.answer(&entry.text)?;
+                println!("{}\n", answer);
+                if answer.starts_with(expected.as_str()) {
+                    Ok(())
+                } else {
+                    Err(format!("expected `{}`, got `{}`", expected, answer).into())
+                }
+            }),
+            _ => process(args, &entry.text, None, &mut prog),
+        };
+        if let Err(e) = result {
+            report_error(&location, &*e);
+            failures += 1;
+        }
+    }
+    match failures {
+        0 => Ok(()),
+        1 => Err("error: 1 command or goal in the script failed\n".into()),
+        n => Err(format!("error: {} commands or goals in the script failed\n", n).into()),
+    }
+}
+
+/// A command or goal in a script.
+struct ScriptEntry {
+    /// The line the entry starts on, counting from 1.
+    line: usize,
+    text: String,
+    /// The start of the answer the goal is expected to have.
+    expected: Option<String>,
+}
+
+fn script_entries(text: &str) -> Vec<ScriptEntry> {
+    let mut script: Vec<ScriptEntry> = Vec::new();
+    for (line, text) in entries(text) {
+        if let (Some(expected), Some(entry)) = (text.strip_prefix("=>"), script.last_mut()) {
+            if entry.expected.is_none() {
+                entry.expected = Some(expected.trim().to_string());
+                continue;
+            }
+        }
+        // Anything else, including an answer with no goal before it, is
+        // a command or goal; the latter fails to parse.
+        script.push(ScriptEntry {
+            line,
+            text,
+            expected: None,
+        });
+    }
+    script
+}
+
+/// The file the REPL history is kept in, `~/.chalk_history`.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".chalk_history"))
+}
+
 /// Reads input lines from the user. Lines start with the string given by `prompt`.
-/// Each line the user enters is passed to the function `f` for processing.
+/// Each line the user enters is passed to the function `f` for processing; an
+/// entry whose brackets are not balanced continues on the following lines.
 ///
 /// The loop terminates (and the program ends) when EOF is reached or if an error
 /// occurs while reading the next line.
-fn readline_loop<F>(rl: &mut rustyline::Editor<()>, prompt: &str, mut f: F) -> Result<()>
+fn readline_loop<F>(rl: &mut rustyline::Editor<ReplHelper>, prompt: &str, mut f: F) -> Result<()>
 where
-    F: FnMut(&mut rustyline::Editor<()>, &str),
+    F: FnMut(&mut rustyline::Editor<ReplHelper>, &str),
 {
     loop {
         match rl.readline(prompt) {
-            Ok(line) => {
-                // Save the line to the history list.
-                rl.add_history_entry(&line);
+            Ok(mut line) => {
+                // When the input is not a terminal, rustyline doesn't
+                // validate it, so read the rest of the entry here.
+                while is_incomplete(&line) {
+                    match rl.readline("   ") {
+                        Ok(next) => {
+                            line.push('\n');
+                            line.push_str(&next);
+                        }
+                        Err(ReadlineError::Eof) => break,
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+
+                // Save the entry to the history list, on one line so
+                // that it is read back as one entry.
+                rl.add_history_entry(line.replace('\n', " "));
 
                 // Process the line.
                 f(rl, &line);
@@ -199,9 +626,12 @@ where
 fn process(
     args: &Args,
     command: &str,
-    rl: &mut rustyline::Editor<()>,
+    rl: Option<&mut rustyline::Editor<ReplHelper>>,
     prog: &mut Option<LoadedProgram>,
 ) -> Result<()> {
+    // Commands may be written with a leading `:`.
+    let command = command.trim();
+    let command = command.strip_prefix(':').unwrap_or(command);
     if command.is_empty() {
         // Ignore empty commands.
     } else if command == "help" || command == "h" {
@@ -210,16 +640,21 @@ fn process(
         help()
     } else if command == "program" {
         // Load a .chalk file via stdin, until EOF is found.
+        let rl = rl.ok_or("`program` can only be used in the REPL; use `load` instead")?;
         let chalk_prog = LoadedProgram::new(read_program(rl)?, args.solver_choice())?;
         // Let's do a sanity check before going forward.
         let _ = chalk_prog.db.checked_program()?;
         *prog = Some(chalk_prog);
     } else if let Some(filename) = command.strip_prefix("load ") {
         // Load a .chalk file.
-        let chalk_prog = load_program(args, filename)?;
-        // Let's do a sanity check before going forward.
-        let _ = chalk_prog.db.checked_program()?;
-        *prog = Some(chalk_prog);
+        *prog = Some(load_program(args, Path::new(filename.trim()))?);
+    } else if command == "reload" {
+        // Load the last loaded .chalk file again.
+        let path = prog
+            .as_ref()
+            .and_then(|prog| prog.path.clone())
+            .ok_or("no program file to reload; `reload` reloads the file given to `load`")?;
+        *prog = Some(load_program(args, &path)?);
     } else if let Some(level) = command.strip_prefix("debug ") {
         if level.is_empty() {
             println!("debug <level> set debug level to <level>");
@@ -227,7 +662,8 @@ fn process(
             std::env::set_var("CHALK_DEBUG", level);
         }
     } else {
-        // The command is either "print", "lowered", or a goal.
+        // The command is either "print", "lowered", "explain", "dump-forest",
+        // "step", or a goal.
 
         // Check that a program has been loaded.
         let prog = prog
@@ -243,10 +679,24 @@ fn process(
                 // TODO: Write a line of documentation here.
                 "lowered" => println!("{:#?}", prog.db.environment()),
 
-                // Assume this is a goal.
-                // TODO: Print out "type 'help' to see available commands" if it
-                // fails to parse?
-                _ => prog.goal(Some(rl), command, args.flag_multiple)?,
+                "dump-forest" => prog.dump_forest(None)?,
+
+                "step" => prog.step(None)?,
+
+                _ => {
+                    if let Some(goal) = command.strip_prefix("explain ") {
+                        prog.explain(goal)?
+                    } else if let Some(goal) = command.strip_prefix("step ") {
+                        prog.step(Some(goal))?
+                    } else if let Some(path) = command.strip_prefix("dump-forest ") {
+                        prog.dump_forest(Some(Path::new(path.trim())))?
+                    } else {
+                        // Assume this is a goal.
+                        // TODO: Print out "type 'help' to see available commands" if it
+                        // fails to parse?
+                        prog.goal(rl, command, args.flag_multiple)?
+                    }
+                }
             }
             Ok(())
         })?
@@ -258,28 +708,80 @@ fn process(
 /// Load the file into a string, and parse it.
 // TODO: Could we pass in an Options struct or something? The Args struct
 // still has Strings where it should have Enums... (e.g. solver_choice)
-fn load_program(args: &Args, filename: &str) -> Result<LoadedProgram> {
-    let mut text = String::new();
-    File::open(filename)?.read_to_string(&mut text)?;
-    LoadedProgram::new(text, args.solver_choice())
+fn load_program(args: &Args, path: &Path) -> Result<LoadedProgram> {
+    let db = ChalkDatabase::with_file(path, args.solver_choice())?;
+    // Let's do a sanity check before going forward.
+    let _ = db.checked_program()?;
+    let text = db.program_text().to_string();
+    Ok(LoadedProgram {
+        text,
+        path: Some(path.to_path_buf()),
+        db,
+        step_goal: RefCell::new(None),
+    })
+}
+
+/// Prints `error` after `context`. Syntax errors, and the errors found
+/// while lowering a program, are listed one after the other, followed by
+/// how many there were.
+fn report_error(context: &str, error: &(dyn std::error::Error + 'static)) {
+    let diagnostics = error.downcast_ref::<ParseDiagnostics>().or_else(|| {
+        error
+            .downcast_ref::<ChalkError>()
+            .and_then(ChalkError::parse_diagnostics)
+    });
+    let lowering_errors = error
+        .downcast_ref::<ChalkError>()
+        .map_or(&[][..], ChalkError::lowering_errors);
+    match diagnostics {
+        Some(diagnostics) => {
+            eprintln!("{}\n", diagnostics);
+            let count = diagnostics.diagnostics.len();
+            let plural = if count == 1 { "" } else { "s" };
+            eprintln!("{}: {} syntax error{}", context, count, plural);
+        }
+        None if !lowering_errors.is_empty() => {
+            for error in lowering_errors {
+                eprintln!("error: {}\n", error);
+            }
+            let count = lowering_errors.len();
+            let plural = if count == 1 { "" } else { "s" };
+            eprintln!("{}: {} error{} in the program", context, count, plural);
+        }
+        None => eprintln!("{}: {}", context, error),
+    }
 }
 
 /// Print out help for commands in interpreter mode.
 // TODO: Implement "help <command>" for more info.
 fn help() {
-    println!("Commands:");
+    println!("Commands (which may also be written with a leading `:`):");
     println!("  help          print this output");
     println!("  program       provide a program via stdin");
     println!("  load <file>   load program from <file>");
+    println!("  reload        load the last loaded file again");
     println!("  print         print the current program");
     println!("  lowered       print the lowered program");
     println!("  <goal>        attempt to solve <goal>");
+    println!("  explain [<depth>] <goal>");
+    println!("                print how <goal> is proven, or why it can't be,");
+    println!(
+        "                at most <depth> goals deep (default {})",
+        EXPLAIN_DEPTH
+    );
+    println!("  dump-forest [<file>]");
+    println!("                write the SLG forest to <file> as a GraphViz graph,");
+    println!("                or print it");
+    println!("  step [<goal>] advance the search for the next answer to <goal>, or to");
+    println!("                the goal last stepped through, by one step of the SLG");
+    println!("                solver, and print the goals it is working on");
     println!("  debug <level> set debug level to <level>");
+    println!("Goals whose brackets are not balanced continue on the next line.");
 }
 
 /// Read a program from the command-line. Stop reading when EOF is read. If
 /// an error occurs while reading, a `Err` is returned.
-fn read_program(rl: &mut rustyline::Editor<()>) -> Result<String> {
+fn read_program(rl: &mut rustyline::Editor<ReplHelper>) -> Result<String> {
     println!("Enter a program; press Ctrl-D when finished");
     let mut text = String::new();
     readline_loop(rl, "| ", |_, line| {
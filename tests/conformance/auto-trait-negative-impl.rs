@@ -0,0 +1,20 @@
+// A negative impl opts a type out of an auto trait, and with it every type
+// which contains it.
+
+#![feature(auto_traits, negative_impls)]
+
+auto trait Send {}
+
+struct Rc<T>(T);
+impl<T> !Send for Rc<T> {}
+
+struct Wrapper<T> {
+    inner: T,
+}
+
+fn is_send<T: Send>() {}
+
+fn main() {
+    is_send::<Wrapper<Rc<u8>>>();
+    //~^ ERROR `Rc<u8>` cannot be sent between threads safely
+}
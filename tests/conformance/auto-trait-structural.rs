@@ -0,0 +1,25 @@
+// check-pass
+// An auto trait holds for a type whose fields all implement it.
+
+#![feature(auto_traits)]
+
+auto trait Send {}
+
+struct Node<T> {
+    value: T,
+    next: Option<Box<Node<T>>>,
+}
+
+struct Box<T>(T);
+
+enum Option<T> {
+    None,
+    Some(T),
+}
+
+fn is_send<T: Send>() {}
+
+fn main() {
+    is_send::<Node<u32>>();
+    is_send::<Option<Box<Node<Node<u8>>>>>();
+}
@@ -0,0 +1,15 @@
+// A blanket impl only applies to the types satisfying its where clauses.
+
+trait Display {}
+trait ToString {}
+
+impl<T> ToString for T where T: Display {}
+
+struct Point;
+
+fn needs_to_string<T: ToString>() {}
+
+fn main() {
+    needs_to_string::<Point>();
+    //~^ ERROR the trait bound `Point: Display` is not satisfied
+}
@@ -0,0 +1,17 @@
+// check-pass
+// The bounds of a generic function hold in its body, and imply the
+// supertraits of their traits.
+
+trait PartialEq {}
+trait Eq: PartialEq {}
+
+fn needs_partial_eq<T: PartialEq>() {}
+
+fn compare<T>()
+where
+    T: Eq,
+{
+    needs_partial_eq::<T>();
+}
+
+fn main() {}
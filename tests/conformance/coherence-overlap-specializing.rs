@@ -0,0 +1,14 @@
+// Two impls of a trait may not apply to the same type, even if one of them
+// is more specific than the other (without `feature(specialization)`).
+//
+// Chalk's coherence check allows this, as it models specialization.
+
+trait Greet {}
+
+struct Vec<T>(T);
+
+impl<T> Greet for Vec<T> {}
+impl Greet for Vec<u8> {}
+//~^ ERROR conflicting implementations of trait `Greet` for type `Vec<u8>`
+
+fn main() {}
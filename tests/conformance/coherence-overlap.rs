@@ -0,0 +1,9 @@
+// Two impls of a trait may not apply to the same type.
+
+trait Greet {}
+
+impl<T> Greet for (T, u8) {}
+impl<T> Greet for (u8, T) {}
+//~^ ERROR conflicting implementations of trait `Greet` for type `(u8, u8)`
+
+fn main() {}
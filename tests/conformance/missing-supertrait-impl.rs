@@ -0,0 +1,11 @@
+// An impl of a trait needs its supertraits to be implemented too.
+
+trait Animal {}
+trait Dog: Animal {}
+
+struct Rex;
+
+impl Dog for Rex {}
+//~^ ERROR the trait bound `Rex: Animal` is not satisfied
+
+fn main() {}
@@ -0,0 +1,11 @@
+// check-pass
+// Functions defined in a body are checked like the others.
+
+trait Copy {}
+
+impl Copy for u32 {}
+
+fn main() {
+    fn assert_copy<T: Copy>() {}
+    assert_copy::<u32>();
+}
@@ -0,0 +1,17 @@
+trait Default {
+    fn default() -> Self;
+}
+
+struct Config;
+struct Settings;
+
+impl Default for Config {
+    fn default() -> Self {
+        Config
+    }
+}
+
+fn main() {
+    let _settings = <Settings as Default>::default();
+    //~^ ERROR the trait bound `Settings: Default` is not satisfied
+}
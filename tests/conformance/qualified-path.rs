@@ -0,0 +1,19 @@
+// check-pass
+// Calling a trait function through a qualified path needs the type to
+// implement the trait.
+
+trait Default {
+    fn default() -> Self;
+}
+
+struct Config;
+
+impl Default for Config {
+    fn default() -> Self {
+        Config
+    }
+}
+
+fn main() {
+    let _config = <Config as Default>::default();
+}
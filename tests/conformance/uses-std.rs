@@ -0,0 +1,8 @@
+// check-pass
+// Refers to the standard library, which the corpus cannot express.
+
+fn assert_clone<T: Clone>() {}
+
+fn main() {
+    assert_clone::<String>();
+}
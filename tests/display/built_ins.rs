@@ -283,3 +283,21 @@ fn test_array_types() {
         }
     );
 }
+
+#[test]
+fn test_unsupported_items_are_an_error() {
+    use chalk_integration::query::LoweringDatabase;
+
+    for (program_text, unsupported) in [
+        ("closure foo(self,) {}", "closures"),
+        ("extern type Foo;", "foreign types"),
+        ("extern \"C\" fn foo();", "functions with a non-Rust ABI"),
+    ] {
+        let db = chalk_integration::db::ChalkDatabase::with(program_text, <_>::default());
+        let program = db.program_ir().unwrap();
+        assert_eq!(
+            program.to_chalk_text().unwrap_err().to_string(),
+            format!("cannot write {} as `.chalk` text", unsupported)
+        );
+    }
+}
@@ -58,3 +58,16 @@ fn test_basic_const_values_in_assoc_ty_values() {
         }
     );
 }
+
+#[test]
+fn test_typed_const_generics() {
+    // Test we render the types of consts which are not `usize`s.
+    reparse_test!(
+        program {
+            struct Foo<const N: u32, const B: bool> { }
+            trait Bar<const M: i8> { }
+            impl<const N: u32, const B: bool> Bar<3i8> for Foo<N, B> { }
+            impl<const B: bool> Bar<0i8> for Foo<4u32, B> { }
+        }
+    );
+}
@@ -104,3 +104,20 @@ fn test_enum_repr_and_keywords_ordered_correctly() {
         }
     );
 }
+
+#[test]
+fn test_enum_discriminants() {
+    reparse_test!(
+        program {
+            #[repr(i8)]
+            enum Foo {
+                A = 1,
+                B,
+                C(u32) = -1,
+                D {
+                    x: u32
+                } = 5,
+            }
+        }
+    );
+}
@@ -96,3 +96,26 @@ fn test_fn_as_type_in_struct_field() {
         }
     );
 }
+
+#[test]
+fn test_unsafe_and_variadic_fns() {
+    reparse_test!(
+        program {
+            unsafe fn foo(a: u32);
+            fn bar(a: u32, b: ...);
+            unsafe fn baz<T>(a: T, b: ...) -> T;
+        }
+    );
+}
+
+#[test]
+fn test_fn_variances() {
+    reparse_test!(
+        program {
+            #[variance(Covariant, Contravariant)]
+            fn foo<T, U>(a: T) -> U;
+            #[variance(Invariant)]
+            fn bar<T>(a: T);
+        }
+    );
+}
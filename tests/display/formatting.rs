@@ -154,3 +154,17 @@ fn test_name_disambiguation() {
         }
     );
 }
+
+#[test]
+fn test_program_features() {
+    // Test we render the features a program sets.
+    reparse_test!(
+        program {
+            #![no_specialization]
+            #![no_implied_bounds]
+            #![all_traits_coinductive]
+            #![arbitrary_self_types]
+            struct Foo { }
+        }
+    );
+}
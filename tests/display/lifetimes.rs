@@ -51,3 +51,68 @@ fn test_lifetime_outlives() {
         }
     );
 }
+
+#[test]
+fn test_type_outlives() {
+    // Test printing type outlives bounds in where clauses and in the bounds
+    // of associated types.
+    reparse_test!(
+        program {
+            struct Foo<'a, T>
+            where
+                T: 'a
+            { }
+
+            trait Baz<'a> { }
+
+            trait Biz {
+                type Bex<'a>: Baz<'a> + 'a
+                where
+                    Self: 'a;
+            }
+
+            impl<'a, T> Baz<'a> for Foo<'a, T>
+            where
+                T: 'a,
+                T: 'static
+            { }
+        }
+    );
+}
+
+#[test]
+fn test_for_binders() {
+    // Test `for<'a>` binders, which are printed as `forall<'a>`.
+    reparse_test!(
+        program {
+            struct Foo<'c, T>
+            where
+                for<'a> T: Baz<'a> + 'c,
+                T: for<'b> Baz<'b> + 'static,
+                for<'a> 'a: 'c,
+                for<'a> fn(&'a T): Baz<'c>
+            { }
+            trait Baz<'a> { }
+            trait Biz {
+                type Bex: for<'a> Baz<'a> + 'static;
+            }
+            impl<'c> Baz<'c> for dyn for<'a> Baz<'a> + 'c { }
+        }
+        produces {
+            struct Foo<'c, T>
+            where
+                forall<'a> T: Baz<'a>,
+                forall<'a> T: 'c,
+                forall<'b> T: Baz<'b>,
+                T: 'static,
+                forall<'a> 'a: 'c,
+                for<'a> fn(&'a T): Baz<'c>
+            { }
+            trait Baz<'a> { }
+            trait Biz {
+                type Bex: forall<'a> Baz<'a> + 'static;
+            }
+            impl<'c> Baz<'c> for dyn forall<'a> Baz<'a> + 'c { }
+        }
+    );
+}
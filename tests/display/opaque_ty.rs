@@ -132,3 +132,17 @@ fn multiple_bounds() {
         }
     );
 }
+
+#[test]
+fn test_opaque_type_where_clauses() {
+    // Test printing the where clauses of opaque types, with and without
+    // bounds
+    reparse_test!(
+        program {
+            trait Bar {}
+            struct Foo<T> {}
+            opaque type Baz<T>: Bar where T: Bar = Foo<T>;
+            opaque type Boz<T> where T: Bar = Foo<T>;
+        }
+    );
+}
@@ -98,6 +98,12 @@ fn test_struct_repr() {
             #[repr(packed)]
             #[repr(C)]
             struct PackedCFoo {}
+
+            #[repr(transparent)]
+            struct TransparentFoo { field: u32 }
+
+            #[repr(simd)]
+            struct SimdFoo { x: f32, y: f32 }
         }
     );
 }
@@ -113,3 +119,19 @@ fn test_struct_repr_with_flags() {
         }
     );
 }
+
+#[test]
+fn test_struct_variances_and_one_zst() {
+    reparse_test!(
+        program {
+            #[variance(Invariant, Covariant)]
+            #[one_zst]
+            struct Foo<T, U> {}
+            #[upstream]
+            #[fundamental]
+            #[one_zst]
+            #[repr(C)]
+            struct Bar<T> {}
+        }
+    );
+}
@@ -7,7 +7,7 @@ use chalk_solve::{
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use super::util::{program_item_ids, ReparseTestResult};
+use super::util::ReparseTestResult;
 
 /// `DuplicateNamesDb` implements `RustIrDatabase`, and returns `Foo` for all
 /// requested item names. This allows us to test that names are correctly
@@ -112,6 +112,12 @@ where
     ) -> std::sync::Arc<chalk_solve::rust_ir::GeneratorWitnessDatum<I>> {
         self.db.generator_witness_datum(generator_id)
     }
+    fn inherent_assoc_ty_data(
+        &self,
+        id: chalk_ir::AssocTypeId<I>,
+    ) -> Option<std::sync::Arc<chalk_solve::rust_ir::InherentAssocTyDatum<I>>> {
+        self.db.inherent_assoc_ty_data(id)
+    }
     fn opaque_ty_data(
         &self,
         id: chalk_ir::OpaqueTyId<I>,
@@ -124,10 +130,16 @@ where
     fn impls_for_trait(
         &self,
         trait_id: chalk_ir::TraitId<I>,
-        parameters: &[chalk_ir::GenericArg<I>],
-        binders: &chalk_ir::CanonicalVarKinds<I>,
+        filter: &chalk_solve::simplified_type::ImplFilter<'_, I>,
+    ) -> Vec<chalk_ir::ImplId<I>> {
+        self.db.impls_for_trait(trait_id, filter)
+    }
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: chalk_ir::TraitId<I>,
+        self_ty: &chalk_solve::simplified_type::SimplifiedType<I>,
     ) -> Vec<chalk_ir::ImplId<I>> {
-        self.db.impls_for_trait(trait_id, parameters, binders)
+        self.db.impls_for_trait_by_self_type(trait_id, self_ty)
     }
     fn local_impls_to_coherence_check(
         &self,
@@ -148,6 +160,12 @@ where
     ) -> Option<chalk_ir::TraitId<I>> {
         self.db.well_known_trait_id(well_known_trait)
     }
+    fn program_features(&self) -> chalk_solve::rust_ir::ProgramFeatures {
+        self.db.program_features()
+    }
+    fn variance_of(&self, item: chalk_solve::variance::VarianceItem<I>) -> chalk_ir::Variances<I> {
+        self.db.variance_of(item)
+    }
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -205,7 +223,7 @@ where
 /// across `write_items` calls, by making one write_items call per item.
 pub fn write_program_duplicated_names(db: &Program) -> String {
     let mut out = String::new();
-    let ids = program_item_ids(db);
+    let ids = db.item_ids();
     let db = DuplicateNamesDb::new(db);
     let ws = WriterState::new(db);
     // Test that names are preserved between write_items calls
@@ -3,11 +3,7 @@
 //! This can't live as a submodule of `test_util.rs`, as then it would conflict
 //! with `display/mod.rs` for the name `mod display` when `test_util.rs` is
 //! compiled as a standalone test (rather than from `lib.rs`).
-use chalk_integration::{interner::ChalkIr, program::Program, query::LoweringDatabase, tls};
-use chalk_solve::{
-    display::{write_items, WriterState},
-    logging_db::RecordedItemId,
-};
+use chalk_integration::{program::Program, query::LoweringDatabase, tls};
 use regex::Regex;
 use std::{fmt::Debug, sync::Arc};
 
@@ -45,45 +41,10 @@ macro_rules! reparse_test {
     };
 }
 
-/// Retrieves all item ids from a given `Program` necessary to print the entire
-/// program.
-pub fn program_item_ids(program: &Program) -> impl Iterator<Item = RecordedItemId<ChalkIr>> + '_ {
-    macro_rules! grab_ids {
-        ($map:expr) => {
-            $map.keys()
-                .copied()
-                .map(|id| (id.0, RecordedItemId::from(id)))
-        };
-    }
-    let mut ids = std::iter::empty()
-        .chain(grab_ids!(program.adt_data))
-        .chain(grab_ids!(program.trait_data))
-        .chain(grab_ids!(program.impl_data))
-        .chain(grab_ids!(program.opaque_ty_data))
-        .chain(grab_ids!(program.fn_def_data))
-        .collect::<Vec<_>>();
-
-    // sort by the RawIds so we maintain exact program input order (note: this
-    // is here rather than in logging_db.rs as we can't in general maintain this
-    // - only for `chalk_integration`'s RawIds).
-    //
-    // We need to maintain exact order because we abuse Program's Eq
-    // implementation to check the results of our tests, and which structs have
-    // which ids is part of that data.
-    ids.sort_by_key(|(raw_id, _)| *raw_id);
-
-    // then discard the RawId since the RecordedItemId has the same information,
-    // and is what we actually want to consume.
-    ids.into_iter().map(|(_, id)| id)
-}
-
 /// Sends all items in a `chalk_integration::Program` through `display` code and
 /// returns the string representing the program.
 pub fn write_program(program: &Program) -> String {
-    let mut out = String::new();
-    let ids = program_item_ids(program);
-    write_items::<_, _, Program, _, _>(&mut out, &WriterState::new(program), ids).unwrap();
-    out
+    program.to_chalk_text().unwrap()
 }
 
 /// Diffs two `Program`s. This diffs the verbose debug output of `Program`, so
@@ -153,6 +114,35 @@ fn program_diff(original: &impl Debug, produced: &impl Debug) -> String {
     out
 }
 
+/// Forgets where the declarations of `program` are in its source, which
+/// printing and reparsing it does not preserve.
+fn without_spans(program: &Program) -> Program {
+    let mut program = program.clone();
+    program.sources.clear();
+    for datum in program.adt_data.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    for datum in program.fn_def_data.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    for datum in program.trait_data.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    for datum in program.impl_data.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    for datum in program.associated_ty_data.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    for datum in program.associated_ty_values.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    for datum in program.opaque_ty_data.values_mut() {
+        Arc::make_mut(datum).span = None;
+    }
+    program
+}
+
 /// Data from performing a reparse test which can be used to make additional
 /// assertions.
 ///
@@ -208,13 +198,17 @@ pub fn reparse_into_different_test<'a>(
             e, output_text
         )
     });
-    if output_program != target_program {
+    let (target_without_spans, output_without_spans) = (
+        without_spans(&target_program),
+        without_spans(&output_program),
+    );
+    if output_without_spans != target_without_spans {
         panic!(
             "WriteProgram produced different program.\n\
              Diff:\n{}\n\
              Source:\n{}\n{}\
              New Source:\n{}\n",
-            program_diff(&target_program, &output_program),
+            program_diff(&target_without_spans, &output_without_spans),
             program_text,
             if target_text != program_text {
                 format!(
@@ -0,0 +1,121 @@
+use super::util;
+use chalk_engine::solve::SLGSolver;
+use chalk_recursive::RecursiveSolver;
+use chalk_solve::{AmbiguityCause, Guidance, Solution, Solver};
+
+#[test]
+fn ambiguity_candidates() {
+    let db = util::db(
+        "
+        struct Foo {}
+        struct Bar {}
+        struct Baz {}
+        trait Trait {}
+        impl Trait for Foo {}
+        impl Trait for Bar {}
+        impl Trait for Baz {}
+        ",
+    );
+    let goal = util::goal(&db, "exists<T> { T: Trait }");
+
+    db.with_program(|_| {
+        let mut solver = util::slg().with_max_candidates(2);
+        let solution = solver.solve(&db, &goal).unwrap();
+        assert_eq!(
+            solution.ambiguity_cause(),
+            Some(AmbiguityCause::MultipleCandidates { count: 2 })
+        );
+        let candidates: Vec<_> = solution
+            .ambiguity_candidates()
+            .iter()
+            .map(|c| format!("{:?}", c.value))
+            .collect();
+        assert_eq!(candidates, vec!["[?0 := Foo]", "[?0 := Bar]"]);
+
+        // Unique solutions have none.
+        let goal = util::goal(&db, "Foo: Trait");
+        let solution = solver.solve(&db, &goal).unwrap();
+        assert!(solution.is_unique());
+        assert!(solution.ambiguity_candidates().is_empty());
+
+        // Nor are they collected by default.
+        let goal = util::goal(&db, "exists<T> { T: Trait }");
+        assert_eq!(
+            util::slg().solve(&db, &goal),
+            Some(Solution::Ambig(
+                Guidance::Unknown,
+                AmbiguityCause::MultipleCandidates { count: 2 },
+                vec![],
+            ))
+        );
+    });
+}
+
+/// Why `goal` is ambiguous, with the SLG and recursive solvers.
+fn causes(program: &str, goal: &str) -> [AmbiguityCause; 2] {
+    let db = util::db(program);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| {
+        [
+            util::slg().solve(&db, &goal),
+            RecursiveSolver::new(100, 10, None).solve(&db, &goal),
+        ]
+        .map(|solution| solution.unwrap().ambiguity_cause().unwrap())
+    })
+}
+
+#[test]
+fn ambiguity_causes() {
+    let program = "
+        struct Foo {}
+        struct Bar {}
+        struct S<T> {}
+        trait Trait {}
+        impl Trait for Foo {}
+        impl Trait for Bar {}
+        trait Grow {}
+        impl<T> Grow for T where S<T>: Grow {}
+        #[non_enumerable]
+        trait NonEnumerable {}
+    ";
+
+    for cause in causes(program, "exists<T> { T: Trait }") {
+        assert_eq!(cause, AmbiguityCause::MultipleCandidates { count: 2 });
+    }
+    for cause in causes(program, "Foo: Grow") {
+        assert_eq!(cause, AmbiguityCause::Truncated);
+    }
+    for cause in causes(program, "exists<T> { T: NonEnumerable }") {
+        assert_eq!(cause, AmbiguityCause::FlounderedNegative);
+    }
+    for cause in causes(program, "exists<T> { not { T: Trait } }") {
+        assert_eq!(cause, AmbiguityCause::FlounderedNegative);
+    }
+}
+
+#[test]
+fn overflow_cause() {
+    let db = util::db(
+        "
+        struct Foo {}
+        struct S<T> {}
+        trait Deep {}
+        impl<T> Deep for T where S<T>: Deep {}
+        ",
+    );
+    let goal = util::goal(&db, "Foo: Deep");
+    db.with_program(|_| {
+        // The recursive solver overflows its depth limit before the goals
+        // get too big; the SLG solver runs out of time.
+        let solution = RecursiveSolver::new(5, 100, None).solve(&db, &goal);
+        assert_eq!(
+            solution.unwrap().ambiguity_cause(),
+            Some(AmbiguityCause::Overflow)
+        );
+        let solution = SLGSolver::new(100, None).solve_limited(&db, &goal, &|| false);
+        assert_eq!(
+            solution.unwrap().ambiguity_cause(),
+            Some(AmbiguityCause::Overflow)
+        );
+    });
+}
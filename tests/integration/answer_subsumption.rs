@@ -0,0 +1,55 @@
+use super::util;
+use chalk_solve::Solver;
+
+const PROGRAM: &str = "
+    struct Foo<T> {}
+    struct Vec<T> {}
+    struct Pair<T, U> {}
+    struct Bar {}
+    struct Baz {}
+    trait Trait {}
+    trait Other {}
+    trait Elem {}
+    impl Elem for Bar {}
+    impl Elem for Baz {}
+    impl<T> Other for Vec<T> where T: Elem {}
+    impl<T, U> Other for Pair<T, U> where T: Elem, U: Elem {}
+    impl<T> Trait for Foo<T> where T: Other {}
+    forall<T> { Foo<Vec<T>>: Trait }
+    forall<T> { Foo<Pair<T, T>>: Trait }
+";
+
+/// Solves `goal` with the SLG solver, with answer subsumption on or off,
+/// returning all its answers and the number of answers in the tables.
+fn solve(goal: &str, answer_subsumption: bool) -> (Vec<String>, usize) {
+    let db = util::db(PROGRAM);
+    let mut solver = util::slg().with_answer_subsumption(answer_subsumption);
+    let answers = util::answers(&db, &mut solver, goal);
+    (answers, solver.stats().answers.unwrap())
+}
+
+#[test]
+fn drops_subsumed_answers() {
+    // `Foo<Vec<Bar>>: Trait` and `Foo<Vec<Baz>>: Trait` follow from
+    // `forall<T> { Foo<Vec<T>>: Trait }`.
+    let goal = "exists<T> { Foo<T>: Trait }";
+    let (answers, num_answers) = solve(goal, true);
+    assert!(answers.contains(&"Definite([?0 := Vec<^0.0>])".to_string()));
+    assert!(!answers.contains(&"Definite([?0 := Vec<Bar>])".to_string()));
+    assert!(!answers.contains(&"Definite([?0 := Vec<Baz>])".to_string()));
+
+    // They are kept with answer subsumption disabled.
+    let (answers, num_answers_unsubsumed) = solve(goal, false);
+    assert!(answers.contains(&"Definite([?0 := Vec<Bar>])".to_string()));
+    assert!(answers.contains(&"Definite([?0 := Vec<Baz>])".to_string()));
+    assert!(num_answers < num_answers_unsubsumed);
+}
+
+#[test]
+fn keeps_answers_which_are_not_instances() {
+    // `Pair<Bar, Baz>` is not an instance of `Pair<T, T>`.
+    let goal = "exists<T, U> { Foo<Pair<T, U>>: Trait }";
+    let (answers, _) = solve(goal, true);
+    assert!(answers.contains(&"Definite([?0 := ^0.0, ?1 := ^0.0])".to_string()));
+    assert!(answers.contains(&"Definite([?0 := Bar, ?1 := Baz])".to_string()));
+}
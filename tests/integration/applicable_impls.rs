@@ -0,0 +1,107 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::program::Program;
+use chalk_integration::Identifier;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::find_applicable_impls;
+
+const PROGRAM: &str = "
+    trait Foo {}
+    trait Bar {}
+    struct A {}
+    struct Vec<T> {}
+    impl Foo for A {}
+    impl Foo for Vec<A> {}
+    impl<T> Foo for Vec<T> where T: Bar {}
+    impl<T> Foo for T where T: Bar {}
+";
+
+/// The applicable impls of `Foo` for `self_ty`, in which the variables of
+/// `binders` can occur, as the self types of the impls followed by the
+/// substitutions of their parameters.
+fn applicable(
+    program: &Program,
+    self_ty: Ty<ChalkIr>,
+    binders: CanonicalVarKinds<ChalkIr>,
+) -> Vec<String> {
+    let interner = ChalkIr;
+    let trait_ref = Canonical {
+        value: TraitRef {
+            trait_id: program.trait_ids[&Identifier::from("Foo")],
+            substitution: Substitution::from1(interner, self_ty),
+        },
+        binders,
+    };
+    find_applicable_impls(program, &trait_ref)
+        .into_iter()
+        .map(|(impl_id, parameters)| {
+            let impl_trait_ref = &program.impl_data[&impl_id].binders.skip_binders().trait_ref;
+            format!(
+                "{:?}: {:?} for {} variables",
+                impl_trait_ref.self_type_parameter(interner),
+                parameters.value,
+                parameters.binders.len(interner)
+            )
+        })
+        .collect()
+}
+
+fn adt(program: &Program, name: &str, parameters: Vec<Ty<ChalkIr>>) -> Ty<ChalkIr> {
+    let interner = ChalkIr;
+    TyKind::Adt(
+        program.adt_ids[&Identifier::from(name)],
+        Substitution::from_iter(interner, parameters.into_iter().map(|ty| ty.cast(interner))),
+    )
+    .intern(interner)
+}
+
+#[test]
+fn impls_applying_to_a_type() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let a = adt(program, "A", vec![]);
+        let vec_a = adt(program, "Vec", vec![a.clone()]);
+
+        // The where clauses of the impls are not proven: `A: Bar` does not
+        // hold, but the generic impls are still candidates.
+        assert_eq!(
+            applicable(program, a, CanonicalVarKinds::empty(ChalkIr)),
+            vec!["A: [] for 0 variables", "^0.0: [?0 := A] for 0 variables"]
+        );
+        assert_eq!(
+            applicable(program, vec_a, CanonicalVarKinds::empty(ChalkIr)),
+            vec![
+                "Vec<A>: [] for 0 variables",
+                "Vec<^0.0>: [?0 := A] for 0 variables",
+                "^0.0: [?0 := Vec<A>] for 0 variables",
+            ]
+        );
+    });
+}
+
+#[test]
+fn impls_applying_to_a_type_with_variables() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let interner = ChalkIr;
+        let var = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(interner);
+        let vec_t = adt(program, "Vec", vec![var]);
+        let binders = CanonicalVarKinds::from1(
+            interner,
+            CanonicalVarKind::new(
+                VariableKind::Ty(TyVariableKind::General),
+                UniverseIndex::ROOT,
+            ),
+        );
+        // The parameters the match leaves unconstrained are variables.
+        assert_eq!(
+            applicable(program, vec_t, binders),
+            vec![
+                "Vec<A>: [] for 0 variables",
+                "Vec<^0.0>: [?0 := ^0.0] for 1 variables",
+                "^0.0: [?0 := Vec<^0.0>] for 1 variables",
+            ]
+        );
+    });
+}
@@ -0,0 +1,96 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::program::Program;
+use chalk_integration::{Identifier, SolverChoice};
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::autoderef::autoderef;
+
+const PROGRAM: &str = "
+    #[lang(deref)]
+    trait Deref { type Target; }
+
+    struct Box<T> {}
+    struct Rc<T> {}
+    struct A {}
+    struct B {}
+
+    impl<T> Deref for Box<T> { type Target = T; }
+    impl<T> Deref for Rc<T> { type Target = Box<T>; }
+    impl Deref for A { type Target = B; }
+    impl Deref for B { type Target = A; }
+";
+
+/// The autoderef steps of `ty`, in which the variables of `binders` can
+/// occur.
+fn steps(program: &Program, ty: Ty<ChalkIr>, binders: CanonicalVarKinds<ChalkIr>) -> Vec<String> {
+    let interner = ChalkIr;
+    let mut solver = SolverChoice::default().into_solver();
+    let environment = Environment::new(interner);
+    let ty = Canonical { value: ty, binders };
+    autoderef(program, &mut *solver, &environment, ty)
+        .map(|step| format!("{:?}", step.value))
+        .collect()
+}
+
+fn adt(program: &Program, name: &str, parameters: Vec<Ty<ChalkIr>>) -> Ty<ChalkIr> {
+    let interner = ChalkIr;
+    TyKind::Adt(
+        program.adt_ids[&Identifier::from(name)],
+        Substitution::from_iter(interner, parameters.into_iter().map(|ty| ty.cast(interner))),
+    )
+    .intern(interner)
+}
+
+#[test]
+fn deref_chain() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(ChalkIr);
+        let lifetime = LifetimeData::Static.intern(ChalkIr);
+        let rc = adt(program, "Rc", vec![u32]);
+        let reference = TyKind::Ref(Mutability::Not, lifetime, rc).intern(ChalkIr);
+        assert_eq!(
+            steps(program, reference, CanonicalVarKinds::empty(ChalkIr)),
+            vec![
+                "(&'static Rc<Uint(U32)>)",
+                "Rc<Uint(U32)>",
+                "Box<Uint(U32)>",
+                "Uint(U32)",
+            ]
+        );
+    });
+}
+
+#[test]
+fn deref_cycle() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        // `A` derefs to `B`, which derefs back to `A`: the chain stops
+        // instead of looping.
+        let a = adt(program, "A", vec![]);
+        assert_eq!(
+            steps(program, a, CanonicalVarKinds::empty(ChalkIr)),
+            vec!["A", "B"]
+        );
+    });
+}
+
+#[test]
+fn ambiguous_deref() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let interner = ChalkIr;
+        let var = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(interner);
+        let boxed = adt(program, "Box", vec![var]);
+        let binders = CanonicalVarKinds::from1(
+            interner,
+            CanonicalVarKind::new(
+                VariableKind::Ty(TyVariableKind::General),
+                UniverseIndex::ROOT,
+            ),
+        );
+        // `Box<?T>` derefs to `?T`, whose target is not a single type.
+        assert_eq!(steps(program, boxed, binders), vec!["Box<^0.0>", "^0.0"]);
+    });
+}
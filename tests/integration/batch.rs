@@ -0,0 +1,81 @@
+use chalk_integration::batch::{self, Format};
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::SolverChoice;
+use serde_json::{json, Value};
+
+const PROGRAM: &str = "
+    struct Foo {}
+    struct Bar {}
+    trait Clone {}
+    impl Clone for Foo {}
+";
+
+fn solve(goals: &str, format: Format) -> String {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::from_name("slg").unwrap());
+    db.with_program(|_| batch::solve(&db, "program.chalk", "slg", &batch::entries(goals), format))
+}
+
+#[test]
+fn entries() {
+    assert_eq!(
+        batch::entries("// comment\nFoo: Clone\n\nexists<T> {\n  T: Clone\n}\n"),
+        vec![
+            (2, "Foo: Clone".to_string()),
+            (4, "exists<T> {\nT: Clone\n}".to_string())
+        ]
+    );
+}
+
+#[test]
+fn text_report() {
+    assert_eq!(
+        solve("Foo: Clone\nBar: Clone\nBaz: Clone", Format::Text),
+        "?- Foo: Clone\nUnique\n\n\
+         ?- Bar: Clone\nNo possible solution.\n\n\
+         ?- Baz: Clone\nerror: invalid parameter name `Baz`\n\n"
+    );
+}
+
+#[test]
+fn json_report() {
+    let report = solve("exists<T> { T: Clone }\nBaz: Clone", Format::Json);
+    assert!(report.starts_with(
+        "{\n  \"program\": \"program.chalk\",\n  \"solver\": \"slg\",\n  \"goals\": [\n"
+    ));
+    let report: Value = serde_json::from_str(&report).unwrap();
+    let goals = &report["goals"];
+    assert_eq!(goals[0]["goal"], "exists<T> { T: Clone }");
+    assert_eq!(goals[0]["result"], "unique");
+    assert_eq!(goals[0]["substitution"], json!(["Foo"]));
+    assert!(goals[0]["time_ms"].is_f64());
+    assert!(goals[0]["stats"]["answers"].is_u64());
+    assert_eq!(goals[1]["error"], "invalid parameter name `Baz`");
+}
+
+#[test]
+fn json_report_without_answers() {
+    // The recursive solver records a single result per goal, not answers.
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::from_name("recursive").unwrap());
+    let report = db.with_program(|_| {
+        batch::solve(
+            &db,
+            "program.chalk",
+            "recursive",
+            &batch::entries("Foo: Clone"),
+            Format::Json,
+        )
+    });
+    let report: Value = serde_json::from_str(&report).unwrap();
+    let stats = report["goals"][0]["stats"].as_object().unwrap();
+    assert!(stats["tables"].is_u64());
+    assert!(!stats.contains_key("answers"));
+}
+
+#[test]
+fn solver_names() {
+    assert_eq!(
+        SolverChoice::from_name("recursive"),
+        Some(SolverChoice::recursive_default())
+    );
+    assert_eq!(SolverChoice::from_name("prolog"), None);
+}
@@ -0,0 +1,205 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_recursive::{Cache, RecursiveSolver};
+use chalk_solve::{Solver, SolverStats};
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    struct Foo {}
+    struct Bar {}
+    struct Vec<T> {}
+    trait Trait {}
+    impl Trait for Foo {}
+    impl Trait for Bar {}
+    impl<T> Trait for Vec<T> where T: Trait {}
+";
+
+#[test]
+fn shared_cache() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let goal = util::goal(&db, "Vec<Vec<Foo>>: Trait");
+
+    db.with_program(|_| {
+        let cache = Cache::new();
+        let mut first = RecursiveSolver::new(100, 30, Some(cache.clone()));
+        assert!(first.solve(&db, &goal).unwrap().is_unique());
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.entries, stats.insertions);
+        assert!(stats.entries > 0);
+
+        // A second solver sharing the cache finds the answer there.
+        let mut second = RecursiveSolver::with_cache(100, 30, cache.clone());
+        assert!(second.solve(&db, &goal).unwrap().is_unique());
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(second.cache().unwrap().stats(), cache.stats());
+    });
+}
+
+#[test]
+fn cache_eviction() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let goals: Vec<_> = ["Foo: Trait", "Bar: Trait"]
+        .iter()
+        .map(|goal| util::goal(&db, goal))
+        .collect();
+
+    db.with_program(|_| {
+        let cache = Cache::with_capacity(1);
+        let mut solver = RecursiveSolver::with_cache(100, 30, cache.clone());
+        for goal in &goals {
+            assert!(solver.solve(&db, goal).unwrap().is_unique());
+        }
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.evictions, stats.insertions - 1);
+
+        // Only the last result, for `Bar: Trait`, is kept.
+        assert!(cache.get(&goals[0]).is_none());
+        assert!(cache.get(&goals[1]).is_some());
+
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+    });
+}
+
+#[test]
+fn env_clauses_follow_program_changes() {
+    let mut db = util::db("trait Foo {} trait Bar where Self: Foo {}");
+    let goal_text = "forall<X> { if (X: Bar) { X: Foo } }";
+    let goal = util::goal(&db, goal_text);
+    let solution = db.with_program(|_| db.solve(&goal));
+    assert!(solution.unwrap().is_unique());
+
+    // Without the supertrait, the elaborated environment no longer
+    // contains `FromEnv(X: Foo)`.
+    db.set_program_text(Arc::new("trait Foo {} trait Bar {}".to_string()));
+    let goal = util::goal(&db, goal_text);
+    let solution = db.with_program(|_| db.solve(&goal));
+    assert!(solution.is_none());
+}
+
+#[test]
+fn goal_fingerprints() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    let goal = |text: &str| util::goal(&db, text);
+
+    db.with_program(|_| {
+        let vec_foo = goal("Vec<Foo>: Trait");
+        assert_eq!(vec_foo.fingerprint(), goal("Vec<Foo>: Trait").fingerprint());
+        assert_eq!(vec_foo, goal("Vec<Foo>: Trait"));
+
+        let vec_bar = goal("Vec<Bar>: Trait");
+        assert_ne!(vec_foo.fingerprint(), vec_bar.fingerprint());
+        assert_ne!(vec_foo, vec_bar);
+    });
+}
+
+#[test]
+fn solver_stats() {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, "Vec<Vec<Foo>>: Trait");
+
+    db.with_program(|_| {
+        for choice in [
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ] {
+            let mut solver = choice.into_solver();
+            // Only the SLG solver records answers.
+            let answers = match choice {
+                SolverChoice::SLG { .. } => Some(0),
+                _ => None,
+            };
+            assert_eq!(
+                solver.stats(),
+                SolverStats {
+                    answers,
+                    ..SolverStats::default()
+                }
+            );
+            assert!(solver.solve(&db, &goal).unwrap().is_unique());
+            let stats = solver.stats();
+            assert!(stats.tables > 0, "{:?}", choice);
+            assert_eq!(stats.answers.is_some(), answers.is_some(), "{:?}", choice);
+            assert!(
+                stats.answers.is_none_or(|answers| answers > 0),
+                "{:?}",
+                choice
+            );
+            assert!(stats.clauses > 0, "{:?}", choice);
+        }
+    });
+}
+
+#[test]
+fn duplicate_clauses_are_removed() {
+    // The custom clause is the same as the one for the impl, and the
+    // environment has the same clause twice.
+    let db = util::db(
+        "
+        struct Foo {}
+        trait Trait {}
+        impl Trait for Foo {}
+        forall<> { Foo: Trait }
+        ",
+    );
+
+    db.with_program(|_| {
+        for goal in [
+            "Foo: Trait",
+            "forall<T> { if (T: Trait; T: Trait) { T: Trait } }",
+        ] {
+            let goal = util::goal(&db, goal);
+            for choice in [
+                SolverChoice::slg_default(),
+                SolverChoice::recursive_default(),
+            ] {
+                let mut solver = choice.into_solver();
+                assert!(solver.solve(&db, &goal).unwrap().is_unique());
+                let stats = solver.stats();
+                assert!(stats.clauses > 0, "{:?}", choice);
+                assert_eq!(stats.duplicate_clauses, 1, "{:?}", choice);
+            }
+        }
+    });
+}
+
+#[test]
+fn merged_universes_share_tables() {
+    let db = util::db(
+        "
+        struct Pair<T, U> {}
+        struct Wrap<T> {}
+        trait Trait {}
+        impl<T, U> Trait for Pair<T, U> {}
+        impl<T> Trait for Wrap<T> where T: Trait {}
+        ",
+    );
+    // Proving the first goal requires proving `Pair<!1_0, !2_0>: Trait`,
+    // with the placeholders for `T` and `U` in distinct universes. Since
+    // no variable can tell these universes apart, this is the same
+    // u-canonical goal as the second one, so its table is reused.
+    let goals: Vec<_> = [
+        "forall<T> { forall<U> { Wrap<Pair<T, U>>: Trait } }",
+        "forall<T, U> { Pair<T, U>: Trait }",
+    ]
+    .iter()
+    .map(|goal| util::goal(&db, goal))
+    .collect();
+
+    db.with_program(|_| {
+        for choice in [
+            SolverChoice::slg_default(),
+            SolverChoice::recursive_default(),
+        ] {
+            let mut solver = choice.into_solver();
+            assert!(solver.solve(&db, &goals[0]).unwrap().is_unique());
+            let tables = solver.stats().tables;
+            assert!(solver.solve(&db, &goals[1]).unwrap().is_unique());
+            assert_eq!(solver.stats().tables, tables, "{:?}", choice);
+        }
+    });
+}
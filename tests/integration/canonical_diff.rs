@@ -0,0 +1,121 @@
+use super::util;
+use chalk_derive::FallibleFolder;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::fold::{Fold, Folder};
+use chalk_ir::{DebruijnIndex, PlaceholderIndex, Ty, UCanonical};
+use chalk_solve::solve::canonical_diff::{explain_difference, CanonicalDifference};
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    trait Foo {}
+    trait Bar {}
+    struct A {}
+    struct B {}
+    struct Vec<T> {}
+";
+
+/// Explains the first difference between the peeled goals `a` and `b`.
+fn explained(a: &str, b: &str) -> Option<String> {
+    let db = util::db(PROGRAM);
+    db.with_program(|_| {
+        let [a, b] = [a, b].map(|goal| util::goal(&db, goal));
+        explain_difference(db.interner(), db.unification_database(), &a, &b)
+            .map(|difference| difference.to_string())
+    })
+}
+
+#[test]
+fn same_goals() {
+    assert_eq!(
+        explained("exists<T> { Vec<T>: Foo }", "exists<U> { Vec<U>: Foo }"),
+        None
+    );
+}
+
+#[test]
+fn universes() {
+    assert_eq!(
+        explained("forall<T> { T: Foo }", "A: Foo").as_deref(),
+        Some("the goals have 2 and 1 universes")
+    );
+}
+
+#[test]
+fn variables() {
+    assert_eq!(
+        explained("exists<T> { T: Foo }", "exists<'a> { &'a A: Foo }").as_deref(),
+        Some("variable 0 is type in U0 in the first goal and lifetime in U0 in the second")
+    );
+}
+
+/// Moves the placeholders of universe 1 to universe 2, like a goal which was
+/// not u-canonicalized after its first universe went unused.
+#[derive(FallibleFolder)]
+#[has_interner(ChalkIr)]
+struct Lift;
+
+impl Folder<ChalkIr> for Lift {
+    fn as_dyn(&mut self) -> &mut dyn Folder<ChalkIr> {
+        self
+    }
+
+    fn fold_free_placeholder_ty(
+        &mut self,
+        universe: PlaceholderIndex,
+        _outer_binder: DebruijnIndex,
+    ) -> Ty<ChalkIr> {
+        PlaceholderIndex {
+            ui: universe.ui.next(),
+            idx: universe.idx,
+        }
+        .to_ty(ChalkIr)
+    }
+
+    fn interner(&self) -> ChalkIr {
+        ChalkIr
+    }
+}
+
+#[test]
+fn placeholder_universes() {
+    let db = util::db(PROGRAM);
+    db.with_program(|_| {
+        let goal = util::goal(&db, "forall<T> { Vec<T>: Foo }");
+        let lifted = UCanonical::new(
+            goal.canonical()
+                .clone()
+                .fold_with(&mut Lift, DebruijnIndex::INNERMOST),
+            goal.universes(),
+        );
+        assert_eq!(
+            explain_difference(db.interner(), db.unification_database(), &goal, &lifted)
+                .unwrap()
+                .to_string(),
+            "the placeholders `!1_0` and `!2_0` are in different universes"
+        );
+    });
+}
+
+#[test]
+fn innermost_terms() {
+    assert_eq!(
+        explained("Vec<Vec<A>>: Foo", "Vec<Vec<B>>: Foo").as_deref(),
+        Some("`A` differs from `B`")
+    );
+}
+
+#[test]
+fn structure() {
+    assert_eq!(
+        explained("A: Foo", "A: Bar").as_deref(),
+        Some("the goals `Implemented(A: Foo)` and `Implemented(A: Bar)` differ")
+    );
+    let db = util::db(PROGRAM);
+    db.with_program(|_| {
+        let [a, b] = ["if (A: Foo) { B: Foo }", "B: Foo"].map(|goal| util::goal(&db, goal));
+        assert!(matches!(
+            explain_difference(db.interner(), db.unification_database(), &a, &b),
+            Some(CanonicalDifference::Environment { .. })
+        ));
+    });
+}
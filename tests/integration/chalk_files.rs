@@ -0,0 +1,146 @@
+//! Runs the goals embedded in the `.chalk` files of `tests/chalk` as
+//! `#[test] goal { G } yields { "Y" }` blocks, checking that both solvers
+//! give each goal the expected solution. Like `yields` in the `test!`
+//! macro, whitespace is ignored and the expected solution may be a prefix
+//! of the actual one.
+//!
+//! Files in the subdirectories of `tests/chalk` are only loaded as modules
+//! of the files which declare them.
+
+use crate::test::format_solution;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_parse::ast::Item;
+use chalk_parse::Location;
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SOLVERS: &[&str] = &["slg", "recursive"];
+
+/// A goal of a `#[test]` block, with the solution it should have.
+struct GoalTest {
+    /// The file and line of the block.
+    location: String,
+    goal: String,
+    expected: String,
+}
+
+fn chalk_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("chalk");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "chalk"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// The goal tests of the program in the file at `path`, including those of
+/// its modules.
+fn goal_tests(path: &Path) -> Vec<GoalTest> {
+    let program = chalk_parse::parse_program_file(path).unwrap_or_else(|e| panic!("{}", e));
+    program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Test(test) => Some(test),
+            _ => None,
+        })
+        .map(|test| {
+            let source = &program.sources[test.span.source];
+            let line = Location::new(&source.text, test.span).start.line;
+            GoalTest {
+                location: format!("{}:{}", source.path.as_ref().unwrap().display(), line),
+                goal: source.text[test.goal_span.lo..test.goal_span.hi].to_string(),
+                expected: test.expected.clone(),
+            }
+        })
+        .collect()
+}
+
+fn is_same(actual: &str, expected: &str) -> bool {
+    let strip = |s: &str| -> String { s.chars().filter(|c| !c.is_whitespace()).collect() };
+    strip(actual).starts_with(&strip(expected))
+}
+
+/// Solves the goal tests of the program in the file at `path` with each
+/// solver, describing the solutions which differ from the expected ones.
+fn run_goal_tests(path: &Path) -> Vec<String> {
+    let tests = goal_tests(path);
+    let mut failures = vec![];
+    for &solver in SOLVERS {
+        let db = ChalkDatabase::with_file(path, SolverChoice::from_name(solver).unwrap()).unwrap();
+        if let Err(error) = db.checked_program() {
+            failures.push(format!("{}: {}", path.display(), error));
+            break;
+        }
+        for test in &tests {
+            let goal = match db.parse_and_lower_goal(&test.goal) {
+                Ok(goal) => goal.into_peeled_goal(db.interner()),
+                Err(error) => {
+                    failures.push(format!("{}: {}", test.location, error));
+                    continue;
+                }
+            };
+            let actual = db.with_program(|_| format_solution(db.solve(&goal), &db));
+            if !is_same(&actual, &test.expected) {
+                failures.push(format!(
+                    "{}: goal `{}` with the {} solver\n  expected: {}\n    actual: {}",
+                    test.location,
+                    test.goal.trim(),
+                    solver,
+                    test.expected,
+                    actual
+                ));
+            }
+        }
+    }
+    failures
+}
+
+#[test]
+fn chalk_file_goals() {
+    let files = chalk_files();
+    assert!(!files.is_empty());
+    let failures: Vec<_> = files.iter().flat_map(|path| run_goal_tests(path)).collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn mismatches_are_reported() {
+    let dir = std::env::temp_dir().join(format!("chalk-goal-tests-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.chalk");
+    fs::write(
+        &path,
+        "struct Foo {}\n\
+         trait Clone {}\n\
+         impl Clone for Foo {}\n\
+         #[test] goal { Foo: Clone } yields { \"Unique\" }\n\
+         #[test] goal { exists<T> { T: Clone } } yields { \"Unique; substitution [?0 := Bar]\" }\n",
+    )
+    .unwrap();
+
+    let tests = goal_tests(&path);
+    assert_eq!(tests.len(), 2);
+    assert_eq!(tests[1].goal.trim(), "exists<T> { T: Clone }");
+    assert_eq!(tests[1].expected, "Unique; substitution [?0 := Bar]");
+
+    // The second goal fails with both solvers.
+    let failures = run_goal_tests(&path);
+    assert_eq!(failures.len(), 2, "{:?}", failures);
+    assert!(failures[0].contains("main.chalk:5"), "{}", failures[0]);
+    assert!(
+        failures[0].contains("actual: Unique; substitution [?0 := Foo]"),
+        "{}",
+        failures[0]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
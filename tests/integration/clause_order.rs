@@ -0,0 +1,83 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::*;
+use chalk_recursive::{ClauseOrder, ClauseSource, RecursiveSolver};
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    trait Foo {}
+    trait Bar {}
+    struct A {}
+    struct B {}
+    struct C {}
+    impl<T> Foo for T where T: Bar {}
+    impl Foo for A {}
+    impl Bar for B {}
+    trait Baz {}
+    impl Baz for B {}
+    forall<> { C: Baz }
+";
+
+/// The answers of `goal` with the recursive solver, trying the clauses in
+/// `clause_order` if it is given.
+fn answers(goal: &str, clause_order: Option<ClauseOrder<ChalkIr>>) -> Vec<String> {
+    let mut solver = RecursiveSolver::new(100, 30, None);
+    if let Some(clause_order) = clause_order {
+        solver = solver.with_clause_order(clause_order);
+    }
+    util::answers(&util::db(PROGRAM), &mut solver, goal)
+}
+
+#[test]
+fn blanket_impls_last() {
+    fn blanket_impls_last(
+        db: &dyn RustIrDatabase<ChalkIr>,
+        _: ClauseSource,
+        clause: &ProgramClause<ChalkIr>,
+    ) -> usize {
+        let interner = db.interner();
+        match &clause.data(interner).0.skip_binders().consequence {
+            DomainGoal::Holds(WhereClause::Implemented(trait_ref)) => {
+                match trait_ref.self_type_parameter(interner).kind(interner) {
+                    TyKind::BoundVar(_) => 1,
+                    _ => 0,
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    let goal = "exists<T> { T: Foo }";
+    assert_eq!(
+        answers(goal, None),
+        vec!["Definite([?0 := B])", "Definite([?0 := A])"]
+    );
+    assert_eq!(
+        answers(goal, Some(blanket_impls_last)),
+        vec!["Definite([?0 := A])", "Definite([?0 := B])"]
+    );
+}
+
+#[test]
+fn custom_clauses_last() {
+    fn custom_clauses_last(
+        _: &dyn RustIrDatabase<ChalkIr>,
+        source: ClauseSource,
+        _: &ProgramClause<ChalkIr>,
+    ) -> usize {
+        match source {
+            ClauseSource::Custom => 1,
+            ClauseSource::Program | ClauseSource::Environment => 0,
+        }
+    }
+
+    let goal = "exists<T> { T: Baz }";
+    assert_eq!(
+        answers(goal, None),
+        vec!["Definite([?0 := C])", "Definite([?0 := B])"]
+    );
+    assert_eq!(
+        answers(goal, Some(custom_clauses_last)),
+        vec!["Definite([?0 := B])", "Definite([?0 := C])"]
+    );
+}
@@ -0,0 +1,93 @@
+use chalk_integration::conformance::{check_corpus, check_test, Case, Expectation, Verdict};
+use chalk_integration::rust_source::parse_rust_obligations;
+use chalk_integration::SolverChoice;
+use std::path::Path;
+
+fn names<'r>(cases: impl Iterator<Item = &'r Case>) -> Vec<&'r str> {
+    cases.map(|case| case.name.as_str()).collect()
+}
+
+#[test]
+fn corpus() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("conformance");
+    for solver in &["slg", "recursive"] {
+        let report = check_corpus(&dir, SolverChoice::from_name(solver).unwrap()).unwrap();
+        // The known divergences: chalk's coherence check models
+        // specialization.
+        assert_eq!(
+            names(report.divergences()),
+            ["coherence-overlap-specializing.rs"],
+            "with the {} solver:\n{}",
+            solver,
+            report
+        );
+        assert_eq!(names(report.unsupported()), ["uses-std.rs"]);
+    }
+}
+
+#[test]
+fn expectations() {
+    assert_eq!(
+        Expectation::of_test("// check-pass\nfn main() {}"),
+        Expectation::Pass
+    );
+    assert_eq!(
+        Expectation::of_test("//@ run-pass\nfn main() {}"),
+        Expectation::Pass
+    );
+    assert_eq!(
+        Expectation::of_test("fn main() {\n    f(); //~ ERROR\n}"),
+        Expectation::Fail
+    );
+    assert_eq!(Expectation::of_test("fn main() {}"), Expectation::Fail);
+}
+
+#[test]
+fn verdicts() {
+    let program = "
+        trait Clone {}
+        struct Foo;
+        struct Bar;
+        impl Clone for Foo {}
+        fn clone<T: Clone>() {}
+    ";
+    let check = |body: &str| {
+        check_test(
+            &format!("{}\nfn main() {{ {} }}", program, body),
+            SolverChoice::default(),
+        )
+    };
+    assert_eq!(check("clone::<Foo>();"), Verdict::Pass);
+    // Calls whose generic arguments are inferred have no obligations.
+    assert_eq!(check("clone(Bar);"), Verdict::Pass);
+    assert_eq!(
+        check("clone::<Bar>();"),
+        Verdict::Fail("the obligation of `clone::<Bar>` on line 8 does not hold".to_string())
+    );
+    assert!(matches!(
+        check("clone::<Foo, Bar>();"),
+        Verdict::Unsupported(reason) if reason.contains("takes 1 generic arguments, not 2")
+    ));
+}
+
+#[test]
+fn obligations() {
+    let text = "
+        trait Eq {}
+        trait Hash {}
+        fn insert<'a, K: Eq, V>(_: &'a K) where K: Hash, V: 'a {}
+        fn generic<T: Eq + Hash>() {
+            insert::<T, u32>();
+            <T as Eq>::eq();
+            foo.bar::<T>();
+        }
+    ";
+    let obligations = parse_rust_obligations(text, None).unwrap();
+    let callees: Vec<_> = obligations
+        .iter()
+        .map(|obligation| (obligation.callee.as_str(), obligation.location.start.line))
+        .collect();
+    assert_eq!(callees, [("insert::<T, u32>", 6), ("<T as Eq>::eq", 7)]);
+}
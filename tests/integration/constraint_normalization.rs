@@ -0,0 +1,149 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_recursive::{Cache, RecursiveSolver};
+use chalk_solve::Solver;
+
+const PROGRAM: &str = "
+    struct Ref<'a> {}
+    trait Outlives<'a> {}
+    impl<'a, 'b> Outlives<'a> for Ref<'b> where 'b: 'a {}
+    trait TyOutlives<'a> {}
+    impl<'a, T> TyOutlives<'a> for T where T: 'a {}
+";
+
+fn solvers(normalize: bool) -> Vec<Box<dyn Solver<ChalkIr>>> {
+    vec![
+        Box::new(util::slg().with_constraint_normalization(normalize)),
+        Box::new(
+            RecursiveSolver::new(100, 30, Some(Cache::default()))
+                .with_constraint_normalization(normalize),
+        ),
+    ]
+}
+
+/// Solves `goal` with both solvers, with the normalization of constraints
+/// on or off.
+fn solve(goal: &str, normalize: bool) -> Vec<String> {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| {
+        solvers(normalize)
+            .iter_mut()
+            .map(|solver| match solver.solve(&db, &goal) {
+                Some(solution) => solution.display(ChalkIr).to_string(),
+                None => "No possible solution".to_string(),
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn trivial_constraints_are_dropped() {
+    for goal in [
+        "forall<'a> { Ref<'a>: Outlives<'a> }",
+        "forall<'a> { Ref<'static>: Outlives<'a> }",
+    ] {
+        for solution in solve(goal, true) {
+            assert_eq!(solution, "Unique", "{}", goal);
+        }
+        for solution in solve(goal, false) {
+            assert!(
+                solution.starts_with("Unique; lifetime constraints"),
+                "{}",
+                solution
+            );
+        }
+    }
+}
+
+#[test]
+fn constraints_do_not_depend_on_the_order_of_the_goals() {
+    let solutions = [
+        "forall<'a, 'b, 'c> { Ref<'a>: Outlives<'b>, Ref<'c>: Outlives<'b>, Ref<'a>: Outlives<'c> }",
+        "forall<'a, 'b, 'c> { Ref<'a>: Outlives<'c>, Ref<'c>: Outlives<'b>, Ref<'a>: Outlives<'b> }",
+    ]
+    .map(|goal| solve(goal, true));
+    assert_eq!(solutions[0], solutions[1]);
+    assert_eq!(solutions[0][0], solutions[0][1]);
+    assert!(solutions[0][0].starts_with("Unique; lifetime constraints"));
+}
+
+fn placeholder(idx: usize) -> Lifetime<ChalkIr> {
+    PlaceholderIndex {
+        ui: UniverseIndex::ROOT,
+        idx,
+    }
+    .to_lifetime(ChalkIr)
+}
+
+fn outlives(a: usize, b: usize) -> InEnvironment<Constraint<ChalkIr>> {
+    InEnvironment::new(
+        &Environment::new(ChalkIr),
+        Constraint::LifetimeOutlives(placeholder(a), placeholder(b)),
+    )
+}
+
+/// The environment with the facts `a: b` for each pair of placeholders.
+fn environment(facts: &[(usize, usize)]) -> Environment<ChalkIr> {
+    Environment::new(ChalkIr).add_clauses(
+        ChalkIr,
+        facts.iter().map(|&(a, b)| {
+            DomainGoal::Holds(WhereClause::LifetimeOutlives(LifetimeOutlives {
+                a: placeholder(a),
+                b: placeholder(b),
+            }))
+            .cast(ChalkIr)
+        }),
+    )
+}
+
+#[test]
+fn repeated_constraints_are_dropped() {
+    let interner = ChalkIr;
+    let constraints = Constraints::from_iter(
+        interner,
+        vec![
+            outlives(0, 1),
+            outlives(1, 1),
+            outlives(1, 0),
+            outlives(0, 1),
+        ],
+    );
+    let normalized = constraints.normalized(interner);
+    assert_eq!(normalized.len(interner), 2);
+    assert_eq!(
+        normalized,
+        Constraints::from_iter(interner, vec![outlives(1, 0), outlives(0, 1)]).normalized(interner)
+    );
+}
+
+#[test]
+fn constraints_implied_by_the_environment_are_dropped() {
+    for goal in [
+        "forall<'a, 'b> { if ('a: 'b) { Ref<'a>: Outlives<'b> } }",
+        "forall<'a, T> { if (T: 'a) { T: TyOutlives<'a> } }",
+    ] {
+        for solution in solve(goal, true) {
+            assert_eq!(solution, "Unique", "{}", goal);
+        }
+    }
+
+    let interner = ChalkIr;
+    let constraints = Constraints::from_iter(
+        interner,
+        vec![
+            outlives(0, 1),
+            outlives(0, 2),
+            outlives(2, 0),
+            outlives(1, 3),
+        ],
+    );
+    // `0: 2` follows from `0: 1` and `1: 2`, but `2: 0` does not.
+    let environment = environment(&[(0, 1), (1, 2)]);
+    assert_eq!(
+        constraints.without_implied(interner, &environment),
+        Constraints::from_iter(interner, vec![outlives(2, 0), outlives(1, 3)])
+    );
+}
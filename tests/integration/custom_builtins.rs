@@ -0,0 +1,77 @@
+use super::util;
+use crate::test::format_solution;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Floundered, Substitution, TraitRef, TyKind};
+
+const PROGRAM: &str = "
+    trait Pod {}
+    trait Other {}
+    struct Foo {}
+    struct Bar {}
+    impl Pod for Bar {}
+";
+
+/// A database whose `Pod` trait is implemented by all scalars, and by the
+/// tuples of `Pod` types.
+fn database(solver_choice: SolverChoice) -> ChalkDatabase {
+    let mut db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let pod = db.with_program(|program| program.trait_ids[&"Pod".into()]);
+    db.register_custom_builtin(pod, |builder, trait_ref, self_ty| {
+        let interner = builder.interner();
+        match self_ty.kind(interner) {
+            TyKind::Scalar(_) => builder.push_fact(trait_ref),
+            TyKind::Tuple(_, substitution) => {
+                let conditions: Vec<_> = substitution
+                    .iter(interner)
+                    .map(|ty| TraitRef {
+                        trait_id: trait_ref.trait_id,
+                        substitution: Substitution::from1(interner, ty.clone()),
+                    })
+                    .collect();
+                builder.push_clause(trait_ref, conditions);
+            }
+            TyKind::Alias(_) => return Err(Floundered),
+            _ => {}
+        }
+        Ok(())
+    });
+    db
+}
+
+fn solve(db: &ChalkDatabase, goal: &str) -> String {
+    let goal = util::goal(db, goal);
+    db.with_program(|_| format_solution(db.solve(&goal), db))
+}
+
+#[test]
+fn custom_builtins_give_impls() {
+    for solver_choice in [
+        SolverChoice::slg_default(),
+        SolverChoice::recursive_default(),
+    ] {
+        let db = database(solver_choice);
+        assert_eq!(solve(&db, "u32: Pod"), "Unique");
+        assert_eq!(solve(&db, "(u8, (bool, Bar)): Pod"), "Unique");
+        assert_eq!(solve(&db, "(u8, Foo): Pod"), "No possible solution");
+        assert_eq!(solve(&db, "Foo: Pod"), "No possible solution");
+        // Only the registered trait is affected.
+        assert_eq!(solve(&db, "u32: Other"), "No possible solution");
+        // Like those of well-known traits, custom builtin impls cannot be
+        // enumerated.
+        assert_eq!(
+            solve(&db, "exists<T> { T: Pod }"),
+            "Ambiguous; no inference guidance"
+        );
+    }
+}
+
+#[test]
+fn without_custom_builtins() {
+    let db = util::db(PROGRAM);
+    assert_eq!(solve(&db, "u32: Pod"), "No possible solution");
+    assert_eq!(
+        solve(&db, "exists<T> { T: Pod }"),
+        "Unique; substitution [?0 := Bar]"
+    );
+}
@@ -0,0 +1,143 @@
+use super::util;
+use crate::test::format_solution;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::{ChalkIr, ChalkPredicate};
+use chalk_integration::SolverChoice;
+use chalk_ir::cast::Cast;
+use chalk_ir::{
+    Binders, BoundVar, CustomPredicate, DebruijnIndex, DomainGoal, Floundered, Goal, GoalData,
+    Goals, QuantifierKind, Scalar, Substitution, Ty, TyKind, TyVariableKind, UintTy, VariableKind,
+    VariableKinds,
+};
+use chalk_solve::ext::*;
+
+const PROGRAM: &str = "
+    struct Foo {}
+";
+
+fn scalar(ty: Ty<ChalkIr>) -> Goal<ChalkIr> {
+    let interner = ChalkIr;
+    DomainGoal::Custom(CustomPredicate(ChalkPredicate {
+        name: "Scalar".into(),
+        args: vec![ty.cast(interner)],
+    }))
+    .cast(interner)
+}
+
+/// A database in which `Scalar(T)` holds for the scalar types, and for the
+/// tuples of types for which it holds.
+fn database(solver_choice: SolverChoice) -> ChalkDatabase {
+    let mut db = ChalkDatabase::with(PROGRAM, solver_choice);
+    db.set_custom_predicate_clauses(|builder, predicate| {
+        let interner = builder.interner();
+        let ty = match (&*predicate.name, &predicate.args[..]) {
+            ("Scalar", [ty]) => ty.assert_ty_ref(interner).clone(),
+            _ => return Ok(()),
+        };
+        let consequence = DomainGoal::Custom(CustomPredicate(predicate.clone()));
+        match ty.kind(interner) {
+            TyKind::Scalar(_) => builder.push_fact(consequence),
+            TyKind::Tuple(_, substitution) => {
+                let conditions: Vec<_> = substitution
+                    .iter(interner)
+                    .map(|ty| {
+                        DomainGoal::Custom(CustomPredicate(ChalkPredicate {
+                            name: predicate.name.clone(),
+                            args: vec![ty.clone()],
+                        }))
+                    })
+                    .collect();
+                builder.push_clause(consequence, conditions);
+            }
+            TyKind::BoundVar(_) | TyKind::InferenceVar(_, _) | TyKind::Alias(_) => {
+                return Err(Floundered)
+            }
+            _ => {}
+        }
+        Ok(())
+    });
+    db
+}
+
+fn solve(db: &ChalkDatabase, goal: Goal<ChalkIr>) -> String {
+    let goal = goal.into_peeled_goal(ChalkIr);
+    db.with_program(|_| format_solution(db.solve(&goal), db))
+}
+
+fn tuple(tys: &[TyKind<ChalkIr>]) -> TyKind<ChalkIr> {
+    let interner = ChalkIr;
+    TyKind::Tuple(
+        tys.len(),
+        Substitution::from_iter(interner, tys.iter().map(|ty| ty.clone().intern(interner))),
+    )
+}
+
+#[test]
+fn custom_predicates_are_proven_by_the_host() {
+    let interner = ChalkIr;
+    let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32));
+    let bool = TyKind::Scalar(Scalar::Bool);
+    for solver_choice in [
+        SolverChoice::slg_default(),
+        SolverChoice::recursive_default(),
+    ] {
+        let db = database(solver_choice);
+        let foo = db.with_program(|program| {
+            TyKind::Adt(
+                program.adt_ids[&"Foo".into()],
+                Substitution::empty(interner),
+            )
+        });
+        assert_eq!(solve(&db, scalar(u32.clone().intern(interner))), "Unique");
+        assert_eq!(
+            solve(
+                &db,
+                scalar(tuple(&[u32.clone(), bool.clone()]).intern(interner))
+            ),
+            "Unique"
+        );
+        assert_eq!(
+            solve(&db, scalar(foo.clone().intern(interner))),
+            "No possible solution"
+        );
+        assert_eq!(
+            solve(&db, scalar(tuple(&[u32.clone(), foo]).intern(interner))),
+            "No possible solution"
+        );
+
+        // `exists<T> { Scalar(T) }` flounders, as the host asks.
+        let exists = GoalData::Quantified(
+            QuantifierKind::Exists,
+            Binders::new(
+                VariableKinds::from1(interner, VariableKind::Ty(TyVariableKind::General)),
+                scalar(
+                    TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(interner),
+                ),
+            ),
+        )
+        .intern(interner);
+        assert_eq!(solve(&db, exists), "Ambiguous; no inference guidance");
+
+        // Goals mixing custom predicates with the others.
+        let both = GoalData::All(Goals::from_iter(
+            interner,
+            vec![
+                scalar(u32.clone().intern(interner)),
+                scalar(bool.clone().intern(interner)),
+            ],
+        ))
+        .intern(interner);
+        assert_eq!(solve(&db, both), "Unique");
+    }
+}
+
+#[test]
+fn without_custom_predicate_clauses() {
+    let interner = ChalkIr;
+    let db = util::db(PROGRAM);
+    let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32));
+    assert_eq!(
+        solve(&db, scalar(u32.intern(interner))),
+        "No possible solution"
+    );
+}
@@ -0,0 +1,110 @@
+use super::util;
+use chalk_integration::interner::{ChalkIr, ChalkPatternTy};
+use chalk_ir::cast::Cast;
+use chalk_ir::fold::shift::Shift;
+use chalk_ir::*;
+use chalk_solve::ext::*;
+use chalk_solve::Solution;
+
+fn u8_ty() -> Ty<ChalkIr> {
+    TyKind::Scalar(Scalar::Uint(UintTy::U8)).intern(ChalkIr)
+}
+
+/// `ty is start..=end`
+fn pattern(ty: Ty<ChalkIr>, start: u128, end: u128) -> Ty<ChalkIr> {
+    TyKind::Custom(ChalkPatternTy { ty, start, end }).intern(ChalkIr)
+}
+
+fn bound_ty(debruijn: DebruijnIndex) -> Ty<ChalkIr> {
+    TyKind::BoundVar(BoundVar::new(debruijn, 0)).intern(ChalkIr)
+}
+
+/// `exists<T> { a = b }`, where `T` is bound in `a` and `b`.
+fn exists_eq(a: Ty<ChalkIr>, b: Ty<ChalkIr>) -> Goal<ChalkIr> {
+    let eq = GoalData::EqGoal(EqGoal {
+        a: a.cast(ChalkIr),
+        b: b.cast(ChalkIr),
+    })
+    .intern(ChalkIr);
+    GoalData::Quantified(
+        QuantifierKind::Exists,
+        Binders::new(
+            VariableKinds::from1(ChalkIr, VariableKind::Ty(TyVariableKind::General)),
+            eq,
+        ),
+    )
+    .intern(ChalkIr)
+}
+
+fn solve(goal: Goal<ChalkIr>) -> Option<Solution<ChalkIr>> {
+    let db = util::db("");
+    db.solve(&goal.into_peeled_goal(ChalkIr))
+}
+
+#[test]
+fn fold_and_flags() {
+    let ty = pattern(bound_ty(DebruijnIndex::INNERMOST), 0, 5);
+    assert_eq!(ty.outer_exclusive_binder(ChalkIr), DebruijnIndex::ONE);
+    assert!(ty.data(ChalkIr).flags.contains(TypeFlags::HAS_TY_BOUND));
+    assert!(!pattern(u8_ty(), 0, 5)
+        .data(ChalkIr)
+        .flags
+        .contains(TypeFlags::HAS_TY_BOUND));
+
+    let shifted = ty.clone().shifted_in(ChalkIr);
+    assert_eq!(shifted, pattern(bound_ty(DebruijnIndex::ONE), 0, 5));
+    assert_eq!(
+        shifted.outer_exclusive_binder(ChalkIr),
+        DebruijnIndex::new(2)
+    );
+
+    let substituted = Binders::new(
+        VariableKinds::from1(ChalkIr, VariableKind::Ty(TyVariableKind::General)),
+        ty,
+    )
+    .substitute(ChalkIr, &[u8_ty().cast::<GenericArg<ChalkIr>>(ChalkIr)]);
+    assert_eq!(substituted, pattern(u8_ty(), 0, 5));
+}
+
+#[test]
+fn unify_pattern_types() {
+    let solution = solve(exists_eq(
+        pattern(bound_ty(DebruijnIndex::INNERMOST), 0, 5),
+        pattern(u8_ty(), 0, 5),
+    ))
+    .unwrap();
+    match solution {
+        Solution::Unique(subst) => assert_eq!(
+            subst.value.subst.as_slice(ChalkIr),
+            &[u8_ty().cast::<GenericArg<ChalkIr>>(ChalkIr)]
+        ),
+        _ => panic!("expected a unique solution, got {:?}", solution),
+    }
+}
+
+#[test]
+fn pattern_type_mismatches() {
+    // Different ranges
+    assert!(solve(exists_eq(
+        pattern(bound_ty(DebruijnIndex::INNERMOST), 0, 5),
+        pattern(u8_ty(), 0, 6),
+    ))
+    .is_none());
+    // A pattern type is distinct from its base type
+    assert!(solve(exists_eq(
+        pattern(bound_ty(DebruijnIndex::INNERMOST), 0, 5),
+        u8_ty(),
+    ))
+    .is_none());
+}
+
+#[test]
+fn pattern_types_are_well_formed() {
+    let goal = GoalData::DomainGoal(DomainGoal::WellFormed(WellFormed::Ty(pattern(
+        u8_ty(),
+        0,
+        5,
+    ))))
+    .intern(ChalkIr);
+    assert!(solve(goal).unwrap().is_unique());
+}
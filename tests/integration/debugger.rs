@@ -0,0 +1,88 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::debugger::{Command, Debugger};
+use chalk_integration::SolverChoice;
+
+const PROGRAM: &str = "
+    trait Clone {}
+    trait Copy {}
+    struct Foo {}
+    struct Vec<T> {}
+    impl Copy for Foo {}
+    impl<T> Clone for Vec<T> where T: Copy {}
+";
+
+fn debugger<'db>(db: &'db ChalkDatabase, goal: &str) -> Debugger<'db> {
+    let goal = util::goal(db, goal);
+    Debugger::new(db, goal)
+}
+
+#[test]
+fn run_to_answers() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let mut debugger = debugger(&db, "exists<T> { Vec<T>: Clone }");
+    assert_eq!(debugger.command("r"), Command::Continue);
+    assert_eq!(debugger.answers(), ["substitution [?0 := Foo]"]);
+    assert!(debugger.stack_tables().is_empty());
+    debugger.command("r");
+    assert!(debugger.is_finished());
+    assert_eq!(debugger.answers().len(), 1);
+    assert_eq!(debugger.command("q"), Command::Quit);
+}
+
+#[test]
+fn steps_show_the_stack() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let mut debugger = debugger(&db, "Vec<Foo>: Clone");
+    // An empty line takes a step.
+    debugger.command("");
+    assert_eq!(debugger.steps(), 1);
+    assert_eq!(debugger.stack_tables(), [0, 1]);
+
+    let screen = debugger.render();
+    assert!(screen.contains("Step 1: Searching"), "{}", screen);
+    assert!(
+        screen.contains("*    0 Implemented(Vec<Foo>: Clone) (0 answers, 1 strand)"),
+        "{}",
+        screen
+    );
+    assert!(
+        screen.contains("  1 table 1: FromEnv(Vec<Foo>: Clone)"),
+        "{}",
+        screen
+    );
+    // The strand of the root goal selected the goal of table 1.
+    assert!(screen.contains("── Strand of frame 0"), "{}", screen);
+    assert!(
+        screen.contains("  -> FromEnv(Vec<Foo>: Clone)"),
+        "{}",
+        screen
+    );
+}
+
+#[test]
+fn breakpoints_stop_runs() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let mut debugger = debugger(&db, "Vec<Foo>: Clone");
+    debugger.command("b 3");
+    debugger.command("r");
+    assert!(debugger.answers().is_empty());
+    assert_eq!(debugger.stack_tables().last(), Some(&3));
+    assert!(debugger
+        .render()
+        .contains("Breakpoint: table 3 was pushed on the stack"));
+
+    // Without the breakpoint, the run goes on to the answer.
+    debugger.command("b 3");
+    debugger.command("r");
+    assert_eq!(debugger.answers(), ["Proven"]);
+}
+
+#[test]
+fn unknown_commands() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let mut debugger = debugger(&db, "Vec<Foo>: Clone");
+    assert_eq!(debugger.command("b x"), Command::Continue);
+    assert_eq!(debugger.steps(), 0);
+    assert!(debugger.render().contains("Unknown command `b x`"));
+}
@@ -0,0 +1,25 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::SolverChoice;
+use chalk_recursive::{Cache, RecursiveSolver};
+use chalk_solve::Solver;
+
+/// Proving an auto trait for the last struct of a long chain of
+/// structs, each containing the previous one, requires nesting goals
+/// as deep as the chain is long.
+#[test]
+fn deep_auto_trait_chain() {
+    const DEPTH: usize = 10_000;
+
+    let mut program = String::from("#[auto] trait Send {}\nstruct S0 {}\n");
+    for i in 1..DEPTH {
+        program.push_str(&format!("struct S{} {{ field: S{} }}\n", i, i - 1));
+    }
+    let db = ChalkDatabase::with(&program, SolverChoice::recursive_default());
+    let goal = util::goal(&db, &format!("S{}: Send", DEPTH - 1));
+
+    db.with_program(|_| {
+        let mut solver = RecursiveSolver::new(2 * DEPTH, 30, Some(Cache::new()));
+        assert!(solver.solve(&db, &goal).unwrap().is_unique());
+    });
+}
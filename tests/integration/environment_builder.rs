@@ -0,0 +1,68 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::{Environment, EnvironmentBuilder, ProgramClause};
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The clauses of the environment of the peeled goal `if (<hypotheses>) { A: Foo }`.
+fn clauses(hypotheses: &str) -> Vec<ProgramClause<ChalkIr>> {
+    let db = util::db("trait Foo {} trait Bar {} struct A {} struct B {}");
+    let goal = db
+        .parse_and_lower_goal(&format!("if ({}) {{ A: Foo }}", hypotheses))
+        .unwrap()
+        .into_peeled_goal(db.interner());
+    goal.canonical()
+        .value
+        .environment
+        .clauses
+        .iter(ChalkIr)
+        .cloned()
+        .collect()
+}
+
+fn hash(environment: &Environment<ChalkIr>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    environment.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn order_and_duplicates_do_not_matter() {
+    let clauses = clauses("A: Bar; B: Bar; B: Foo");
+    let (a_bar, b_bar, b_foo) = (&clauses[0], &clauses[1], &clauses[2]);
+
+    let first = EnvironmentBuilder::new(ChalkIr)
+        .add_clauses(vec![a_bar.clone(), b_bar.clone(), b_foo.clone()])
+        .build();
+    let second = EnvironmentBuilder::new(ChalkIr)
+        .add_clause(b_foo.clone())
+        .add_clause(a_bar.clone())
+        .add_clause(b_foo.clone())
+        .add_clause(b_bar.clone())
+        .add_clause(a_bar.clone())
+        .build();
+    assert_eq!(first, second);
+    assert_eq!(hash(&first), hash(&second));
+    assert_eq!(first.clauses.len(ChalkIr), 3);
+
+    // `add_clauses` keeps the order and the duplicates.
+    let added = Environment::new(ChalkIr).add_clauses(
+        ChalkIr,
+        vec![b_foo.clone(), a_bar.clone(), b_foo.clone(), b_bar.clone()],
+    );
+    assert_ne!(added, first);
+    assert_eq!(
+        EnvironmentBuilder::from_environment(ChalkIr, &added).build(),
+        first
+    );
+}
+
+#[test]
+fn empty() {
+    assert_eq!(
+        EnvironmentBuilder::new(ChalkIr).build(),
+        Environment::new(ChalkIr)
+    );
+}
@@ -0,0 +1,73 @@
+use super::util;
+use chalk_integration::explain::explain;
+
+fn explained(program: &str, goal: &str, depth: usize) -> String {
+    let db = util::db(program);
+    db.with_program(|_| {
+        let goal = util::goal(&db, goal);
+        explain(&db, &goal, depth).to_string()
+    })
+}
+
+const PROGRAM: &str = "
+trait Clone { }
+struct Foo { }
+struct Bar { }
+struct Vec<T> { }
+impl Clone for Foo { }
+impl<T> Clone for Vec<T> where T: Clone { }
+";
+
+#[test]
+fn proofs_show_the_clauses_used() {
+    assert_eq!(
+        explained(PROGRAM, "Vec<Foo>: Clone", 8),
+        "\
+Implemented(Vec<Foo>: Clone): Unique
+`-- clause for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+    |   unified: Implemented(Vec<Foo>: Clone) :- ForAll<> { Implemented(Foo: Clone) }
+    `-- Implemented(Foo: Clone): Unique
+        `-- clause for<> Implemented(Foo: Clone)
+                unified: Implemented(Foo: Clone)
+"
+    );
+}
+
+#[test]
+fn failures_show_every_clause_tried() {
+    assert_eq!(
+        explained(PROGRAM, "Vec<Vec<Bar>>: Clone", 8),
+        "\
+Implemented(Vec<Vec<Bar>>: Clone): No possible solution
+|-- clause for<type> Implemented(^0.0: Clone) :- FromEnv(^0.0: Clone)
+|   |   unified: Implemented(Vec<Vec<Bar>>: Clone) :- FromEnv(Vec<Vec<Bar>>: Clone)
+|   `-- FromEnv(Vec<Vec<Bar>>: Clone): No possible solution
+`-- clause for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+    |   unified: Implemented(Vec<Vec<Bar>>: Clone) :- ForAll<> { Implemented(Vec<Bar>: Clone) }
+    `-- Implemented(Vec<Bar>: Clone): No possible solution
+        |-- clause for<type> Implemented(^0.0: Clone) :- FromEnv(^0.0: Clone)
+        |   |   unified: Implemented(Vec<Bar>: Clone) :- FromEnv(Vec<Bar>: Clone)
+        |   `-- FromEnv(Vec<Bar>: Clone): No possible solution
+        `-- clause for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+            |   unified: Implemented(Vec<Bar>: Clone) :- ForAll<> { Implemented(Bar: Clone) }
+            `-- Implemented(Bar: Clone): No possible solution
+                `-- clause for<type> Implemented(^0.0: Clone) :- FromEnv(^0.0: Clone)
+                    |   unified: Implemented(Bar: Clone) :- FromEnv(Bar: Clone)
+                    `-- FromEnv(Bar: Clone): No possible solution
+"
+    );
+}
+
+#[test]
+fn explanations_stop_at_the_depth_limit() {
+    assert_eq!(
+        explained(PROGRAM, "Vec<Vec<Foo>>: Clone", 1),
+        "\
+Implemented(Vec<Vec<Foo>>: Clone): Unique
+`-- clause for<type> Implemented(Vec<^0.0>: Clone) :- ForAll<> { Implemented(^1.0: Clone) }
+    |   unified: Implemented(Vec<Vec<Foo>>: Clone) :- ForAll<> { Implemented(Vec<Foo>: Clone) }
+    `-- Implemented(Vec<Foo>: Clone): Unique
+        `-- ...
+"
+    );
+}
@@ -0,0 +1,35 @@
+use chalk_integration::fuzz::{self, FuzzConfig};
+
+#[test]
+fn cases_are_reproducible_from_their_seed() {
+    let config = FuzzConfig::default();
+    assert_eq!(fuzz::generate(7, &config), fuzz::generate(7, &config));
+    assert_ne!(fuzz::generate(7, &config), fuzz::generate(8, &config));
+}
+
+#[test]
+fn generated_programs_are_well_formed() {
+    let config = FuzzConfig {
+        goals: 0,
+        ..FuzzConfig::default()
+    };
+    for seed in 0..100 {
+        let case = fuzz::generate(seed, &config);
+        if let Err(failure) = fuzz::check(&case, &config) {
+            panic!("seed {}: {}\n{}", seed, failure, case.program);
+        }
+    }
+}
+
+#[test]
+fn solvers_agree_on_random_goals() {
+    if let Some((case, failure)) = fuzz::fuzz(0..50, &FuzzConfig::default()) {
+        panic!(
+            "seed {}: {}\nprogram:\n{}goals:\n{}",
+            case.seed,
+            failure,
+            case.program,
+            case.goals.join("\n")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::ext::*;
+use chalk_solve::goal_builder::{goal, GoalBuilder};
+
+const PROGRAM: &str = "
+    trait Clone {}
+    trait Into<T> {}
+    struct Foo {}
+    struct Vec<T> {}
+    impl Clone for Foo {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+    impl<T> Into<Vec<T>> for T {}
+";
+
+fn ids(db: &ChalkDatabase) -> (TraitId<ChalkIr>, TraitId<ChalkIr>, AdtId<ChalkIr>) {
+    db.with_program(|program| {
+        (
+            program.trait_ids[&"Clone".into()],
+            program.trait_ids[&"Into".into()],
+            program.adt_ids[&"Vec".into()],
+        )
+    })
+}
+
+fn vec_of(vec: AdtId<ChalkIr>, ty: Ty<ChalkIr>) -> Ty<ChalkIr> {
+    TyKind::Adt(vec, Substitution::from1(ChalkIr, ty)).intern(ChalkIr)
+}
+
+/// Checks that `built` is the goal `text` lowers to, and that it can be
+/// proven.
+fn check(db: &ChalkDatabase, built: Goal<ChalkIr>, text: &str) {
+    let lowered = db.parse_and_lower_goal(text).unwrap();
+    db.with_program(|_| assert_eq!(format!("{:?}", built), format!("{:?}", lowered)));
+    assert_eq!(built, lowered);
+    let goal = built.into_peeled_goal(ChalkIr);
+    assert!(db.solve(&goal).unwrap().is_unique());
+}
+
+#[test]
+fn forall_implies() {
+    let db = util::db(PROGRAM);
+    let (clone, _, vec) = ids(&db);
+    let built = goal(&db)
+        .forall(|t| {
+            let vec_t = vec_of(vec, t.ty());
+            goal(&db).implies(
+                vec![t.implements(clone)],
+                TraitRef {
+                    trait_id: clone,
+                    substitution: Substitution::from1(ChalkIr, vec_t),
+                },
+            )
+        })
+        .build();
+    check(&db, built, "forall<T> { if (T: Clone) { Vec<T>: Clone } }");
+}
+
+#[test]
+fn nested_parameters() {
+    let db = util::db(PROGRAM);
+    let (clone, into, vec) = ids(&db);
+    // The outer parameter is used under the inner binder, where its index
+    // must be shifted.
+    let built = goal(&db)
+        .forall(|t| {
+            goal(&db).exists(|u| {
+                goal(&db)
+                    .and(u.equals(vec_of(vec, t.ty())))
+                    .and(t.implements_with(into, [u.ty()]))
+            })
+        })
+        .and(goal(&db).exists(|t| t.implements(clone)))
+        .build();
+    check(
+        &db,
+        built,
+        "forall<T> { exists<U> { U = Vec<T>, T: Into<U> } }, exists<T> { T: Clone }",
+    );
+}
+
+#[test]
+fn lifetimes() {
+    let db = util::db(PROGRAM);
+    let built = goal(&db)
+        .forall_lifetime(|a| goal(&db).exists_lifetime(|b| b.outlives(a)))
+        .build();
+    let lowered = db.parse_and_lower_goal("forall<'a> { exists<'b> { 'b: 'a } }");
+    assert_eq!(built, lowered.unwrap());
+}
+
+#[test]
+fn quantified_hypotheses() {
+    // Without an impl of `Clone` for `Vec`, so that the goal can only be
+    // proven from the hypotheses.
+    let program = "
+        trait Clone {}
+        struct Vec<T> {}
+    ";
+    for solver in ["slg", "recursive"] {
+        let db = ChalkDatabase::with(program, SolverChoice::from_name(solver).unwrap());
+        let (clone, vec) = db.with_program(|program| {
+            (
+                program.trait_ids[&"Clone".into()],
+                program.adt_ids[&"Vec".into()],
+            )
+        });
+        let vec_clone = |ty| TraitRef {
+            trait_id: clone,
+            substitution: Substitution::from1(ChalkIr, vec_of(vec, ty)),
+        };
+        let built = goal(&db)
+            .forall(|t| {
+                // `forall<U> { if (U: Clone) { Vec<U>: Clone } }`, as a
+                // generated clause set would give it.
+                let vec_impl = goal(&db)
+                    .forall(|u| {
+                        vec_clone(u.ty()).cast::<Goal<ChalkIr>>(ChalkIr).implied_by(
+                            ChalkIr,
+                            ProgramClauses::from1(ChalkIr, u.implements(clone)),
+                        )
+                    })
+                    .build();
+                goal(&db)
+                    .implies_goals(
+                        vec![t.implements(clone).cast(ChalkIr), vec_impl],
+                        vec_clone(vec_of(vec, t.ty())),
+                    )
+                    .unwrap()
+            })
+            .build();
+        check(
+            &db,
+            built,
+            "forall<T> { \
+                 if (T: Clone; forall<U> { Vec<U>: Clone :- U: Clone }) { Vec<Vec<T>>: Clone } \
+             }",
+        );
+    }
+}
+
+#[test]
+fn hypotheses_which_cannot_be_assumed() {
+    let db = util::db(PROGRAM);
+    for text in [
+        "exists<T> { T: Clone }",
+        "not { Foo: Clone }",
+        // A condition of the hypothesis of the implication cannot be
+        // assumed in turn.
+        "if (forall<T> { Vec<T>: Clone :- exists<U> { U: Clone } }) { Foo: Clone }",
+    ] {
+        let hypothesis = db.parse_and_lower_goal(text).unwrap();
+        let built = goal(&db).implies_goals(vec![hypothesis.clone()], hypothesis);
+        assert!(built.is_none(), "{}", text);
+    }
+}
+
+#[test]
+fn hypotheses_with_conditions() {
+    let db = util::db(PROGRAM);
+    // The clause of the hypothesis of the implication is assumed as an
+    // implication in turn.
+    let hypothesis = db
+        .parse_and_lower_goal("if (forall<T> { Vec<T>: Clone :- T: Clone }) { Vec<Foo>: Clone }")
+        .unwrap();
+    let foo_clone = db.parse_and_lower_goal("Foo: Clone").unwrap();
+    let built = goal(&db)
+        .implies_goals(vec![hypothesis], foo_clone)
+        .unwrap()
+        .build();
+    check(
+        &db,
+        built,
+        "if (Vec<Foo>: Clone :- forall<T> { if (T: Clone) { Vec<T>: Clone } }) { Foo: Clone }",
+    );
+}
+
+#[test]
+fn protocols() {
+    let db = util::db(
+        "
+        #[lang(into_iterator)]
+        trait IntoIterator { type Item; }
+        #[lang(iterator)]
+        trait Iterator { type Item; }
+        #[lang(fn_once)]
+        trait FnOnce<Args> { type Output; }
+        struct Vec<T> {}
+        struct IntoIter<T> {}
+        impl<T> IntoIterator for Vec<T> { type Item = T; }
+        impl<T> Iterator for IntoIter<T> { type Item = T; }
+        ",
+    );
+    let (vec, into_iter) = db.with_program(|program| {
+        (
+            program.adt_ids[&"Vec".into()],
+            program.adt_ids[&"IntoIter".into()],
+        )
+    });
+    let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(ChalkIr);
+    let bool = TyKind::Scalar(Scalar::Bool).intern(ChalkIr);
+    let mut builder = GoalBuilder::new(&db);
+
+    let built = builder
+        .into_iterator_with_item(vec_of(vec, u32.clone()), u32.clone())
+        .unwrap();
+    check(&db, built, "Vec<u32>: IntoIterator<Item = u32>");
+
+    let iter = TyKind::Adt(into_iter, Substitution::from1(ChalkIr, u32.clone())).intern(ChalkIr);
+    let built = builder.iterator_with_item(iter, u32.clone()).unwrap();
+    check(&db, built, "IntoIter<u32>: Iterator<Item = u32>");
+
+    let fn_ptr = db.parse_and_lower_goal("exists<F> { F = fn(u32, bool) -> u32 }");
+    let fn_ptr = match fn_ptr.unwrap().data(ChalkIr) {
+        GoalData::Quantified(_, binders) => match binders.skip_binders().data(ChalkIr) {
+            GoalData::EqGoal(eq) => eq.b.assert_ty_ref(ChalkIr).clone(),
+            goal => panic!("unexpected goal {:?}", goal),
+        },
+        goal => panic!("unexpected goal {:?}", goal),
+    };
+    let built = builder.callable(fn_ptr, [u32.clone(), bool], u32).unwrap();
+    check(
+        &db,
+        built,
+        "fn(u32, bool) -> u32: FnOnce<(u32, bool), Output = u32>",
+    );
+}
+
+#[test]
+fn protocols_without_lang_items() {
+    let db = util::db(PROGRAM);
+    let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(ChalkIr);
+    let mut builder = GoalBuilder::new(&db);
+    assert!(builder
+        .into_iterator_with_item(u32.clone(), u32.clone())
+        .is_none());
+    assert!(builder.callable(u32.clone(), None, u32).is_none());
+}
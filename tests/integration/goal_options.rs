@@ -0,0 +1,135 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_solve::{GoalOptions, Solver};
+use std::time::Duration;
+
+const PROGRAM: &str = "
+    struct Vec<T> {}
+    struct Foo {}
+    struct Bar {}
+    trait Trait {}
+    trait Cyclic {}
+    trait Marker {}
+    impl Trait for Foo {}
+    impl<T> Trait for Vec<T> where T: Trait {}
+    impl Cyclic for Foo where Foo: Cyclic {}
+    impl Marker for Foo {}
+    impl Marker for Bar {}
+";
+
+/// Solves `goal` with `solver`, with `options` if any, and returns the
+/// solution as a string.
+fn solve(
+    solver: &mut dyn Solver<ChalkIr>,
+    goal: &str,
+    options: Option<&GoalOptions<ChalkIr>>,
+) -> String {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| {
+        util::display(match options {
+            Some(options) => solver.solve_with_options(&db, &goal, options),
+            None => solver.solve(&db, &goal),
+        })
+    })
+}
+
+#[test]
+fn max_size() {
+    let goal = "Vec<Vec<Vec<Vec<Foo>>>>: Trait";
+    let options = GoalOptions {
+        max_size: Some(2),
+        ..GoalOptions::default()
+    };
+    for mut solver in util::solvers() {
+        let solver = &mut *solver;
+        assert_eq!(
+            solve(solver, goal, Some(&options)),
+            "Ambiguous; no inference guidance"
+        );
+        // The solver's own limits still apply to later goals.
+        assert!(solve(solver, goal, None).starts_with("Unique"));
+        assert_eq!(
+            solve(solver, goal, Some(&options)),
+            "Ambiguous; no inference guidance"
+        );
+    }
+}
+
+#[test]
+fn coinductive_overrides() {
+    let db = util::db(PROGRAM);
+    let cyclic = db.with_program(|program| program.trait_ids[&"Cyclic".into()]);
+    let options = GoalOptions {
+        coinductive_overrides: vec![cyclic],
+        ..GoalOptions::default()
+    };
+    for mut solver in util::solvers() {
+        let solver = &mut *solver;
+        assert!(solve(solver, "Foo: Cyclic", Some(&options)).starts_with("Unique"));
+        assert_eq!(solve(solver, "Foo: Cyclic", None), "No possible solution");
+        assert!(solve(solver, "Foo: Cyclic", Some(&options)).starts_with("Unique"));
+    }
+}
+
+#[test]
+fn max_answers() {
+    let goal = "exists<T> { T: Marker }";
+    let mut solver = util::slg();
+    assert_eq!(
+        solve(&mut solver, goal, None),
+        "Ambiguous; no inference guidance"
+    );
+    let options = GoalOptions {
+        max_answers: Some(1),
+        ..GoalOptions::default()
+    };
+    assert_eq!(
+        solve(&mut solver, goal, Some(&options)),
+        "Ambiguous; suggested substitution [?0 := Foo]"
+    );
+}
+
+#[test]
+fn timeout() {
+    let goal = "Vec<Foo>: Trait";
+    let expired = GoalOptions {
+        timeout: Some(Duration::ZERO),
+        ..GoalOptions::default()
+    };
+    let mut solver = util::recursive();
+    assert_eq!(
+        solve(&mut solver, goal, Some(&expired)),
+        "Ambiguous; no inference guidance"
+    );
+    assert!(solve(&mut solver, goal, None).starts_with("Unique"));
+
+    let generous = GoalOptions {
+        timeout: Some(Duration::from_secs(600)),
+        ..GoalOptions::default()
+    };
+    for mut solver in util::solvers() {
+        assert!(solve(&mut *solver, goal, Some(&generous)).starts_with("Unique"));
+    }
+
+    // A timeout too long to be represented is no deadline at all.
+    let unbounded = GoalOptions {
+        timeout: Some(Duration::MAX),
+        ..GoalOptions::default()
+    };
+    for mut solver in util::solvers() {
+        assert!(solve(&mut *solver, goal, Some(&unbounded)).starts_with("Unique"));
+    }
+}
+
+#[test]
+fn default_options() {
+    let options = GoalOptions::default();
+    for mut solver in util::solvers() {
+        let solver = &mut *solver;
+        assert_eq!(
+            solve(solver, "Foo: Marker", Some(&options)),
+            solve(solver, "Foo: Marker", None)
+        );
+    }
+}
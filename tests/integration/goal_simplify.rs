@@ -0,0 +1,37 @@
+use super::util;
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    #[lang(sized)]
+    trait Sized {}
+    trait Bar {}
+    struct Foo {}
+    impl Bar for Foo {}
+";
+
+#[test]
+fn simplify_conjunctions() {
+    let db = util::db(PROGRAM);
+    let goal = |text| db.parse_and_lower_goal(text).unwrap();
+
+    db.with_program(|_| {
+        // Nested conjunctions are flattened, duplicates and sized primitives dropped.
+        assert_eq!(
+            goal("Foo: Bar, (u32: Sized, (Foo: Bar, &'static str: Sized))").simplified(&db),
+            goal("Foo: Bar")
+        );
+        assert_eq!(
+            goal("forall<T> { T: Sized, T: Bar, T: Sized }").simplified(&db),
+            goal("forall<T> { T: Sized, T: Bar }")
+        );
+        assert!(goal("u32: Sized, !: Sized")
+            .simplified(&db)
+            .is_trivially_true(db.interner()));
+        // Unsized types are kept.
+        assert_eq!(
+            goal("u32: Sized, str: Sized").simplified(&db),
+            goal("str: Sized")
+        );
+    });
+}
@@ -0,0 +1,51 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::SolverChoice;
+
+const PROGRAM: &str = "
+    trait Trait {}
+    struct Foo {}
+    struct Vec<T> {}
+    impl<T> Trait for Vec<T> where T: Trait {}
+";
+
+#[test]
+fn slg_forest() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let goal = util::goal(&db, "not { Vec<Foo>: Trait }");
+    db.with_program(|_| {
+        let mut solver = SolverChoice::slg_default().into_solver();
+        assert!(solver.solve(&db, &goal).unwrap().is_unique());
+        assert_eq!(
+            solver.graphviz(&db).unwrap(),
+            r#"digraph forest {
+    node [shape=box];
+    t0 [label="not { Implemented(Vec<Foo>: Trait) }\n1 answer"];
+    t0 -> t1 [style=dashed];
+    t1 [label="Implemented(Vec<Foo>: Trait)\n0 answers"];
+    t1 -> t2;
+    t1 -> t3;
+    t2 [label="FromEnv(Vec<Foo>: Trait)\n0 answers"];
+    t3 [label="ForAll<> { Implemented(Foo: Trait) }\n0 answers"];
+    t3 -> t4;
+    t4 [label="Implemented(Foo: Trait)\n0 answers"];
+    t4 -> t5;
+    t5 [label="FromEnv(Foo: Trait)\n0 answers"];
+}
+"#
+        );
+    });
+}
+
+#[test]
+fn recursive_solver_has_no_forest() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    db.with_program(|_| {
+        assert_eq!(
+            SolverChoice::recursive_default()
+                .into_solver()
+                .graphviz(&db),
+            None
+        );
+    });
+}
@@ -0,0 +1,55 @@
+use super::util;
+use chalk_solve::{Solution, Solver};
+
+const PROGRAM: &str = "
+    struct Vec<T> {}
+    struct Foo {}
+    struct Bar {}
+    trait Trait {}
+    trait Other {}
+    impl<T> Trait for T where Vec<T>: Trait {}
+    impl Other for Bar {}
+";
+
+#[test]
+fn reports_growing_goals() {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, "Foo: Trait");
+    db.with_program(|_| {
+        let mut solver = util::slg().with_growth_limit(Some(3));
+        let chain = solver.try_solve(&db, &goal).unwrap_err();
+        let goals: Vec<_> = chain
+            .goals
+            .iter()
+            .map(|goal| format!("{:?}", goal.canonical().value.goal))
+            .collect();
+        assert_eq!(
+            goals,
+            vec![
+                "Implemented(Foo: Trait)",
+                "Implemented(Vec<Foo>: Trait)",
+                "Implemented(Vec<Vec<Foo>>: Trait)",
+            ]
+        );
+        let tables = solver.stats().tables;
+
+        // The chain is still reported once the tables are cached.
+        assert_eq!(solver.try_solve(&db, &goal), Err(chain));
+
+        // Without detection, the goals grow until they are truncated.
+        let mut solver = util::slg();
+        assert!(solver.try_solve(&db, &goal).unwrap().unwrap().is_ambig());
+        assert!(solver.stats().tables > tables);
+    });
+}
+
+#[test]
+fn terminating_goals_are_solved() {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, "Bar: Other");
+    db.with_program(|_| {
+        let mut solver = util::slg().with_growth_limit(Some(3));
+        let solution = solver.try_solve(&db, &goal).unwrap();
+        assert!(matches!(solution, Some(Solution::Unique(_))));
+    });
+}
@@ -0,0 +1,124 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::program::Program;
+use chalk_integration::Identifier;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::simplified_type::{ImplFilter, SimplifiedType};
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    trait Foo {}
+    trait Bar {}
+    struct A {}
+    struct B {}
+    struct Vec<T> {}
+    impl Foo for A {}
+    impl Foo for B {}
+    impl Foo for u32 {}
+    impl<T> Foo for Vec<T> {}
+    impl<T> Foo for T where T: Bar {}
+";
+
+fn impls_with_self_types(program: &Program, impl_ids: Vec<ImplId<ChalkIr>>) -> Vec<String> {
+    impl_ids
+        .into_iter()
+        .map(|impl_id| {
+            let trait_ref = &program.impl_data[&impl_id].binders.skip_binders().trait_ref;
+            format!("{:?}", trait_ref.self_type_parameter(ChalkIr))
+        })
+        .collect()
+}
+
+#[test]
+fn impls_by_self_type() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let foo = program.trait_ids[&Identifier::from("Foo")];
+        let a = program.adt_ids[&Identifier::from("A")];
+        let vec = program.adt_ids[&Identifier::from("Vec")];
+
+        assert_eq!(
+            impls_with_self_types(
+                program,
+                program.impls_for_trait_by_self_type(foo, &SimplifiedType::Adt(a))
+            ),
+            vec!["A", "^0.0"]
+        );
+        assert_eq!(
+            impls_with_self_types(
+                program,
+                program.impls_for_trait_by_self_type(foo, &SimplifiedType::Adt(vec))
+            ),
+            vec!["Vec<^0.0>", "^0.0"]
+        );
+        assert_eq!(
+            impls_with_self_types(
+                program,
+                program.impls_for_trait_by_self_type(foo, &SimplifiedType::Str)
+            ),
+            vec!["^0.0"]
+        );
+    });
+}
+
+fn canonical_ty_var(kind: TyVariableKind) -> CanonicalVarKinds<ChalkIr> {
+    CanonicalVarKinds::from1(
+        ChalkIr,
+        CanonicalVarKind::new(VariableKind::Ty(kind), UniverseIndex::ROOT),
+    )
+}
+
+#[test]
+fn impls_for_trait_uses_self_type() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let foo = program.trait_ids[&Identifier::from("Foo")];
+        let interner = ChalkIr;
+        let u32_ty: GenericArg<ChalkIr> = TyKind::Scalar(Scalar::Uint(UintTy::U32))
+            .intern(interner)
+            .cast(interner);
+        let var: GenericArg<ChalkIr> = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0))
+            .intern(interner)
+            .cast(interner);
+        let binders = canonical_ty_var(TyVariableKind::General);
+
+        let parameters = [u32_ty];
+        let filter = ImplFilter::new(interner, &parameters, &binders);
+        assert_eq!(
+            impls_with_self_types(program, program.impls_for_trait(foo, &filter)),
+            vec!["Uint(U32)", "^0.0"]
+        );
+
+        let parameters = [var];
+        let filter = ImplFilter::new(interner, &parameters, &binders);
+        assert_eq!(program.impls_for_trait(foo, &filter).len(), 5);
+    });
+}
+
+#[test]
+fn impl_filter_uses_variable_kinds() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let foo = program.trait_ids[&Identifier::from("Foo")];
+        let interner = ChalkIr;
+        let var: GenericArg<ChalkIr> = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0))
+            .intern(interner)
+            .cast(interner);
+        let parameters = [var];
+
+        let binders = canonical_ty_var(TyVariableKind::Integer);
+        let filter = ImplFilter::new(interner, &parameters, &binders);
+        assert_eq!(
+            impls_with_self_types(program, program.impls_for_trait(foo, &filter)),
+            vec!["Uint(U32)", "^0.0"]
+        );
+
+        let binders = canonical_ty_var(TyVariableKind::Float);
+        let filter = ImplFilter::new(interner, &parameters, &binders);
+        assert_eq!(
+            impls_with_self_types(program, program.impls_for_trait(foo, &filter)),
+            vec!["^0.0"]
+        );
+    });
+}
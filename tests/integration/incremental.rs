@@ -0,0 +1,94 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    trait Clone {}
+    struct Foo {}
+    struct Bar {}
+    impl Clone for Foo {}
+    impl Clone for Bar {}
+";
+
+/// Checks `PROGRAM`, then changes its text to `program` and checks it
+/// again, recording the queries executed by the second check.
+fn recheck(program: &str) -> ChalkDatabase {
+    let mut db = util::db(PROGRAM);
+    db.checked_program().unwrap();
+    db.set_program_text(Arc::new(program.to_string()));
+    db.record_executed_queries();
+    db.checked_program().unwrap();
+    db
+}
+
+fn count(db: &ChalkDatabase, query: &str) -> usize {
+    let prefix = format!("{}(", query);
+    db.take_executed_queries()
+        .iter()
+        .filter(|executed| executed.starts_with(&prefix))
+        .count()
+}
+
+#[test]
+fn first_check_executes_everything() {
+    let db = util::db(PROGRAM);
+    db.record_executed_queries();
+    db.checked_program().unwrap();
+    assert!(db.did_recompute("program_ir"));
+    assert!(db.did_recompute("trait_coherence"));
+    assert!(db.did_recompute("adt_wf"));
+    assert_eq!(count(&db, "impl_wf"), 2);
+}
+
+#[test]
+fn moving_items_rechecks_nothing() {
+    // All the spans change, but not the items.
+    let db = recheck(&format!("// a comment\n{}", PROGRAM));
+    assert!(db.did_recompute("program_ir"));
+    assert!(db.did_recompute("lowered_impl"));
+    assert!(!db.did_recompute("trait_coherence"));
+    assert!(!db.did_recompute("impl_orphan_check"));
+    assert!(!db.did_recompute("adt_wf"));
+    assert!(!db.did_recompute("impl_wf"));
+}
+
+#[test]
+fn changing_an_impl_rechecks_its_dependents() {
+    let db = recheck(&PROGRAM.replace(
+        "impl Clone for Foo {}",
+        "impl Clone for Foo where Bar: Clone {}",
+    ));
+    // The impls of `Clone` are checked for overlap again, and the changed
+    // impl for well-formedness, but not the other impl, nor the structs.
+    assert!(db.did_recompute("trait_coherence"));
+    assert!(!db.did_recompute("adt_wf"));
+    assert_eq!(count(&db, "impl_wf"), 1);
+}
+
+#[test]
+fn changing_an_impl_relowers_only_that_impl() {
+    // The impl is the last item, so the spans of the others don't move.
+    let db = recheck(&PROGRAM.replace(
+        "impl Clone for Bar {}",
+        "impl Clone for Bar where Foo: Clone {}",
+    ));
+    let executed = db.take_executed_queries();
+    let lowered_impls: Vec<_> = executed
+        .iter()
+        .filter(|executed| executed.starts_with("lowered_impl("))
+        .collect();
+    assert_eq!(lowered_impls.len(), 1);
+    assert!(!executed.iter().any(|executed| {
+        executed.starts_with("lowered_adt(") || executed.starts_with("lowered_trait(")
+    }));
+}
+
+#[test]
+fn executed_queries_are_taken() {
+    let db = recheck(&format!("{}\n", PROGRAM));
+    assert!(db.did_recompute("program_ir"));
+    assert!(!db.take_executed_queries().is_empty());
+    assert!(db.take_executed_queries().is_empty());
+    assert!(!db.did_recompute("program_ir"));
+}
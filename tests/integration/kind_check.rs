@@ -0,0 +1,105 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::program::Program;
+use chalk_integration::Identifier;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::kind_check::{GenericItem, KindChecker, KindError};
+use chalk_solve::rust_ir::{AdtDatumBound, AdtVariantDatum, ImplDatumBound};
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    trait Trait<T> {}
+    struct Foo<'a, T> {}
+    struct Bar {}
+    impl Trait<u32> for Bar {}
+";
+
+fn lowered() -> Program {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| program.clone())
+}
+
+#[test]
+fn well_kinded() {
+    let program = lowered();
+    let checker = KindChecker::new(&program);
+    for &id in program.adt_ids.values() {
+        assert_eq!(checker.check_adt_decl(id), Ok(()));
+    }
+    for &id in program.trait_ids.values() {
+        assert_eq!(checker.check_trait_decl(id), Ok(()));
+    }
+    for &id in program.impl_data.keys() {
+        assert_eq!(checker.check_impl(id), Ok(()));
+    }
+}
+
+#[test]
+fn wrong_number_of_arguments() {
+    let interner = ChalkIr;
+    let mut program = lowered();
+    let foo = program.adt_ids[&Identifier::from("Foo")];
+    let bar = program.adt_ids[&Identifier::from("Bar")];
+
+    // Give `Bar` a field of type `Foo<u32>`, which lacks the lifetime.
+    let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(interner);
+    let field = TyKind::Adt(foo, Substitution::from1(interner, u32)).intern(interner);
+    let mut datum = (*program.adt_data[&bar]).clone();
+    datum.binders = Binders::empty(
+        interner,
+        AdtDatumBound {
+            variants: vec![AdtVariantDatum {
+                fields: vec![field],
+                discriminant: None,
+            }],
+            where_clauses: vec![],
+        },
+    );
+    program.adt_data.insert(bar, Arc::new(datum));
+
+    assert_eq!(
+        KindChecker::new(&program).check_adt_decl(bar),
+        Err(KindError::WrongNumberOfArguments {
+            item: GenericItem::Adt(foo),
+            expected: 2,
+            actual: 1,
+        })
+    );
+}
+
+#[test]
+fn wrong_kind_of_argument() {
+    let interner = ChalkIr;
+    let mut program = lowered();
+    let trait_id = program.trait_ids[&Identifier::from("Trait")];
+    let (&impl_id, datum) = program.impl_data.iter().next().unwrap();
+
+    // Turn the impl into `impl Trait<'static> for Bar`.
+    let lifetime: GenericArg<ChalkIr> = LifetimeData::Static.intern(interner).cast(interner);
+    let mut datum = (**datum).clone();
+    datum.binders = datum.binders.map_ref(|bound| {
+        let self_ty = bound.trait_ref.self_type_parameter(interner);
+        ImplDatumBound {
+            trait_ref: TraitRef {
+                trait_id,
+                substitution: Substitution::from_iter(
+                    interner,
+                    [self_ty.cast(interner), lifetime.clone()],
+                ),
+            },
+            where_clauses: vec![],
+        }
+    });
+    program.impl_data.insert(impl_id, Arc::new(datum));
+
+    assert_eq!(
+        KindChecker::new(&program).check_impl(impl_id),
+        Err(KindError::WrongKindOfArgument {
+            item: GenericItem::Trait(trait_id),
+            index: 1,
+            expected: VariableKind::Ty(TyVariableKind::General),
+            actual: lifetime,
+        })
+    );
+}
@@ -0,0 +1,58 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_solve::Solver;
+
+const PROGRAM: &str = "
+    struct Ref<'a> {}
+    trait Trait<'a> {}
+    impl<'a> Trait<'a> for Ref<'a> {}
+";
+
+/// Solves `goal` with both solvers, with the leak check on or off.
+fn solve(goal: &str, leak_check: bool) -> Vec<String> {
+    let db = util::db(PROGRAM);
+    let solvers: Vec<Box<dyn Solver<ChalkIr>>> = vec![
+        Box::new(util::slg().with_leak_check(leak_check)),
+        Box::new(util::recursive().with_leak_check(leak_check)),
+    ];
+    solvers
+        .into_iter()
+        .map(|mut solver| util::solve(&db, &mut *solver, goal))
+        .collect()
+}
+
+#[test]
+fn rejects_placeholders_leaking_to_outer_variables() {
+    let goal = "exists<'x> { forall<'a> { Ref<'a>: Trait<'x> } }";
+    for solution in solve(goal, false) {
+        assert!(solution
+            .starts_with("Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints"));
+    }
+    for solution in solve(goal, true) {
+        assert_eq!(solution, "No possible solution");
+    }
+}
+
+#[test]
+fn rejects_placeholders_related_to_each_other() {
+    let goal = "forall<'a, 'b> { Ref<'a>: Trait<'b> }";
+    for solution in solve(goal, false) {
+        assert!(solution.starts_with("Unique; lifetime constraints"));
+    }
+    for solution in solve(goal, true) {
+        assert_eq!(solution, "No possible solution");
+    }
+}
+
+#[test]
+fn accepts_placeholders_that_do_not_leak() {
+    for goal in [
+        "forall<'a> { Ref<'a>: Trait<'a> }",
+        "forall<'a> { exists<'x> { Ref<'a>: Trait<'x> } }",
+    ] {
+        assert_eq!(solve(goal, true), solve(goal, false));
+        for solution in solve(goal, true) {
+            assert!(solution.starts_with("Unique"), "{}: {}", goal, solution);
+        }
+    }
+}
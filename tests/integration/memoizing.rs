@@ -0,0 +1,268 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::program::Program;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_ir::*;
+use chalk_solve::ext::*;
+use chalk_solve::memoizing_db::{MemoizingDatabase, SyncMemoizingDatabase};
+use chalk_solve::rust_ir::*;
+use chalk_solve::simplified_type::{ImplFilter, SimplifiedType};
+use chalk_solve::RustIrDatabase;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const PROGRAM: &str = "
+    trait Clone {}
+    struct Foo {}
+    struct Vec<T> {}
+    impl Clone for Foo {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+";
+
+/// Builds a fresh datum for every `trait_datum` and `impl_datum` call, and
+/// counts the calls.
+#[derive(Debug)]
+struct CountingDatabase {
+    program: Arc<Program>,
+    calls: AtomicUsize,
+}
+
+impl CountingDatabase {
+    fn new() -> Self {
+        let db = util::db(PROGRAM);
+        CountingDatabase {
+            program: db.program_ir().unwrap(),
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    fn goal(&self, text: &str) -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
+        chalk_integration::lowering::lower_goal(
+            &chalk_parse::parse_goal(text).unwrap(),
+            &self.program,
+        )
+        .unwrap()
+        .into_peeled_goal(ChalkIr)
+    }
+}
+
+impl UnificationDatabase<ChalkIr> for CountingDatabase {
+    fn fn_def_variance(&self, fn_def_id: FnDefId<ChalkIr>) -> Variances<ChalkIr> {
+        self.program.fn_def_variance(fn_def_id)
+    }
+
+    fn adt_variance(&self, adt_id: AdtId<ChalkIr>) -> Variances<ChalkIr> {
+        self.program.adt_variance(adt_id)
+    }
+}
+
+impl RustIrDatabase<ChalkIr> for CountingDatabase {
+    fn custom_clauses(&self) -> Vec<ProgramClause<ChalkIr>> {
+        self.program.custom_clauses()
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<ChalkIr>) -> Arc<AssociatedTyDatum<ChalkIr>> {
+        self.program.associated_ty_data(ty)
+    }
+
+    fn trait_datum(&self, id: TraitId<ChalkIr>) -> Arc<TraitDatum<ChalkIr>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Arc::new((*self.program.trait_datum(id)).clone())
+    }
+
+    fn impl_datum(&self, id: ImplId<ChalkIr>) -> Arc<ImplDatum<ChalkIr>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Arc::new((*self.program.impl_datum(id)).clone())
+    }
+
+    fn associated_ty_value(
+        &self,
+        id: AssociatedTyValueId<ChalkIr>,
+    ) -> Arc<AssociatedTyValue<ChalkIr>> {
+        self.program.associated_ty_value(id)
+    }
+
+    fn inherent_assoc_ty_data(
+        &self,
+        id: AssocTypeId<ChalkIr>,
+    ) -> Option<Arc<InherentAssocTyDatum<ChalkIr>>> {
+        self.program.inherent_assoc_ty_data(id)
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<ChalkIr>) -> Arc<OpaqueTyDatum<ChalkIr>> {
+        self.program.opaque_ty_data(id)
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<ChalkIr>) -> Ty<ChalkIr> {
+        self.program.hidden_opaque_type(id)
+    }
+
+    fn adt_datum(&self, id: AdtId<ChalkIr>) -> Arc<AdtDatum<ChalkIr>> {
+        self.program.adt_datum(id)
+    }
+
+    fn adt_repr(&self, id: AdtId<ChalkIr>) -> Arc<AdtRepr<ChalkIr>> {
+        self.program.adt_repr(id)
+    }
+
+    fn adt_size_align(&self, id: AdtId<ChalkIr>) -> Arc<AdtSizeAlign> {
+        self.program.adt_size_align(id)
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<ChalkIr>) -> Arc<FnDefDatum<ChalkIr>> {
+        self.program.fn_def_datum(fn_def_id)
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        self.program.generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        self.program.generator_witness_datum(generator_id)
+    }
+
+    fn impls_for_trait(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+        filter: &ImplFilter<'_, ChalkIr>,
+    ) -> Vec<ImplId<ChalkIr>> {
+        self.program.impls_for_trait(trait_id, filter)
+    }
+
+    fn impls_for_trait_by_self_type(
+        &self,
+        trait_id: TraitId<ChalkIr>,
+        self_ty: &SimplifiedType<ChalkIr>,
+    ) -> Vec<ImplId<ChalkIr>> {
+        self.program.impls_for_trait_by_self_type(trait_id, self_ty)
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<ChalkIr>) -> Vec<ImplId<ChalkIr>> {
+        self.program.local_impls_to_coherence_check(trait_id)
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<ChalkIr>, ty: &TyKind<ChalkIr>) -> bool {
+        self.program.impl_provided_for(auto_trait_id, ty)
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<ChalkIr>> {
+        self.program.well_known_trait_id(well_known_trait)
+    }
+
+    fn program_clauses_for_env(
+        &self,
+        environment: &Environment<ChalkIr>,
+    ) -> ProgramClauses<ChalkIr> {
+        chalk_solve::program_clauses_for_env(self, environment)
+    }
+
+    fn interner(&self) -> ChalkIr {
+        ChalkIr
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
+        self.program.is_object_safe(trait_id)
+    }
+
+    fn closure_kind(
+        &self,
+        closure_id: ClosureId<ChalkIr>,
+        substs: &Substitution<ChalkIr>,
+    ) -> ClosureKind {
+        self.program.closure_kind(closure_id, substs)
+    }
+
+    fn closure_inputs_and_output(
+        &self,
+        closure_id: ClosureId<ChalkIr>,
+        substs: &Substitution<ChalkIr>,
+    ) -> Binders<FnDefInputsAndOutputDatum<ChalkIr>> {
+        self.program.closure_inputs_and_output(closure_id, substs)
+    }
+
+    fn closure_upvars(
+        &self,
+        closure_id: ClosureId<ChalkIr>,
+        substs: &Substitution<ChalkIr>,
+    ) -> Binders<Ty<ChalkIr>> {
+        self.program.closure_upvars(closure_id, substs)
+    }
+
+    fn closure_fn_substitution(
+        &self,
+        closure_id: ClosureId<ChalkIr>,
+        substs: &Substitution<ChalkIr>,
+    ) -> Substitution<ChalkIr> {
+        self.program.closure_fn_substitution(closure_id, substs)
+    }
+
+    fn unification_database(&self) -> &dyn UnificationDatabase<ChalkIr> {
+        self
+    }
+
+    fn discriminant_type(&self, ty: Ty<ChalkIr>) -> Ty<ChalkIr> {
+        self.program.discriminant_type(ty)
+    }
+}
+
+#[test]
+fn caches_datums() {
+    let counting = CountingDatabase::new();
+    let goal = counting.goal("Vec<Vec<Foo>>: Clone");
+    let mut solver = SolverChoice::slg_default().into_solver();
+    let expected = solver.solve(&counting, &goal);
+    assert!(expected.as_ref().unwrap().is_unique());
+
+    let counting = CountingDatabase::new();
+    let db = MemoizingDatabase::<_, CountingDatabase, _>::new(&counting);
+    let mut solver = SolverChoice::slg_default().into_solver();
+    assert_eq!(solver.solve(&db, &goal), expected);
+    let calls = counting.calls();
+    assert!(calls > 0);
+
+    // Everything the solver needs is cached now.
+    let mut solver = SolverChoice::slg_default().into_solver();
+    assert_eq!(solver.solve(&db, &goal), expected);
+    assert_eq!(counting.calls(), calls);
+
+    let impl_id = *counting.program.impl_data.keys().next().unwrap();
+    assert!(Arc::ptr_eq(
+        &db.impl_datum(impl_id),
+        &db.impl_datum(impl_id)
+    ));
+    assert_eq!(counting.calls(), calls);
+
+    db.clear();
+    db.impl_datum(impl_id);
+    assert_eq!(counting.calls(), calls + 1);
+}
+
+#[test]
+fn shares_cache_between_threads() {
+    let db = Arc::new(SyncMemoizingDatabase::<_, CountingDatabase, _>::new(
+        CountingDatabase::new(),
+    ));
+    let goal = CountingDatabase::new().goal("Vec<Foo>: Clone");
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let db = db.clone();
+            let goal = goal.clone();
+            thread::spawn(move || {
+                let mut solver = SolverChoice::slg_default().into_solver();
+                solver.solve(&*db, &goal)
+            })
+        })
+        .collect();
+    for thread in threads {
+        assert!(thread.join().unwrap().unwrap().is_unique());
+    }
+}
@@ -0,0 +1,112 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::program::Program;
+use chalk_integration::{Identifier, SolverChoice};
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::method_resolution::{probe_methods, Receiver, TraitMethod};
+
+const PROGRAM: &str = "
+    #[lang(deref)]
+    trait Deref { type Target; }
+
+    trait Len {}
+    trait Push {}
+    trait IntoIter {}
+
+    struct Vec<T> {}
+    struct Box<T> {}
+
+    impl<T> Deref for Box<T> { type Target = T; }
+    impl<T> Len for Vec<T> {}
+    impl<T> Push for Vec<T> {}
+    impl<T> IntoIter for Vec<T> {}
+    impl<'a, T> IntoIter for &'a Vec<T> {}
+";
+
+/// The candidates for calling the method of `trait_name` taking its
+/// receiver as `receiver` on a receiver of type `ty`, as their autoderef
+/// step, autoref and `Self` type.
+fn probe(program: &Program, ty: Ty<ChalkIr>, trait_name: &str, receiver: Receiver) -> Vec<String> {
+    let interner = ChalkIr;
+    let methods = [TraitMethod {
+        trait_id: program.trait_ids[&Identifier::from(trait_name)],
+        receiver,
+    }];
+    let mut solver = SolverChoice::default().into_solver();
+    probe_methods(
+        program,
+        &mut *solver,
+        &Environment::new(interner),
+        &Canonical {
+            value: ty,
+            binders: CanonicalVarKinds::empty(interner),
+        },
+        &methods,
+    )
+    .into_iter()
+    .map(|candidate| {
+        assert!(candidate.solution.is_unique());
+        format!(
+            "{} {:?}: {:?}",
+            candidate.autoderefs, candidate.autoref, candidate.self_ty.value
+        )
+    })
+    .collect()
+}
+
+fn adt(program: &Program, name: &str, parameters: Vec<Ty<ChalkIr>>) -> Ty<ChalkIr> {
+    let interner = ChalkIr;
+    TyKind::Adt(
+        program.adt_ids[&Identifier::from(name)],
+        Substitution::from_iter(interner, parameters.into_iter().map(|ty| ty.cast(interner))),
+    )
+    .intern(interner)
+}
+
+fn vec_u32(program: &Program) -> Ty<ChalkIr> {
+    let u32 = TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(ChalkIr);
+    adt(program, "Vec", vec![u32])
+}
+
+#[test]
+fn autoref_through_deref() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        // `b.len()` with `b: Box<Vec<u32>>` derefs `b`, then borrows it.
+        let boxed = adt(program, "Box", vec![vec_u32(program)]);
+        assert_eq!(
+            probe(program, boxed, "Len", Receiver::Ref),
+            vec!["1 Some(Not): Vec<Uint(U32)>"]
+        );
+    });
+}
+
+#[test]
+fn by_value_before_deref() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        // `v.into_iter()` with `v: &Vec<u32>` is `<&Vec<u32>>::into_iter`,
+        // the impl for `Vec<u32>` only being found at the next step.
+        let lifetime = LifetimeData::Static.intern(ChalkIr);
+        let reference = TyKind::Ref(Mutability::Not, lifetime, vec_u32(program)).intern(ChalkIr);
+        assert_eq!(
+            probe(program, reference, "IntoIter", Receiver::Value),
+            vec![
+                "0 None: (&'static Vec<Uint(U32)>)",
+                "1 None: Vec<Uint(U32)>"
+            ]
+        );
+    });
+}
+
+#[test]
+fn mutable_autoref() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        assert_eq!(
+            probe(program, vec_u32(program), "Push", Receiver::RefMut),
+            vec!["0 Some(Mut): Vec<Uint(U32)>"]
+        );
+    });
+}
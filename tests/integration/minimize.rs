@@ -0,0 +1,68 @@
+use chalk_integration::minimize::{Minimizer, Problem};
+use chalk_integration::SolverChoice;
+use std::time::Duration;
+
+const UNIQUE: &str = "Unique";
+
+#[test]
+fn irrelevant_parts_are_removed() {
+    let program = chalk_parse::parse_program(
+        "
+        trait Foo {}
+        trait Bar {}
+        trait Baz { type Assoc: Bar; }
+        struct A {}
+        struct B<T> where T: Bar { t: T }
+        impl Bar for A {}
+        impl Baz for A { type Assoc = A; }
+        impl<T> Foo for B<T> where T: Bar, T: Baz {}
+        impl Foo for A {}
+        struct C { a: A, b: B<A> }
+        ",
+    )
+    .unwrap();
+
+    let minimizer = Minimizer::new(
+        "B<A>: Foo",
+        Problem::Answer(UNIQUE.to_string()),
+        SolverChoice::default(),
+    );
+    let minimized = minimizer.minimize(&program).unwrap();
+    assert_eq!(
+        minimized,
+        "trait Foo {}\nstruct A {}\nstruct B<_1_0> {}\nimpl<_1_0> Foo for B<_1_0> {}\n"
+    );
+}
+
+#[test]
+fn programs_without_the_problem_are_an_error() {
+    let program = chalk_parse::parse_program("trait Foo {} struct A {}").unwrap();
+    let minimizer = Minimizer::new(
+        "A: Foo",
+        Problem::Answer(UNIQUE.to_string()),
+        SolverChoice::default(),
+    );
+    assert_eq!(
+        minimizer.minimize(&program).unwrap_err().to_string(),
+        "the program does not show the problem: the answer is `No possible solution`"
+    );
+}
+
+#[test]
+fn timeouts_are_minimized() {
+    let program =
+        chalk_parse::parse_program("trait Foo {} trait Bar {} struct A {} impl Foo for A {}")
+            .unwrap();
+
+    // Every candidate in which the goal can be solved at all reaches a
+    // timeout of zero, but the candidates that don't lower don't.
+    let minimizer = Minimizer::new(
+        "A: Foo",
+        Problem::Timeout(Duration::ZERO),
+        SolverChoice::default(),
+    );
+    assert_eq!(
+        minimizer.minimize(&program).unwrap(),
+        "trait Foo {}\nstruct A {}\n"
+    );
+}
@@ -1 +1,54 @@
+mod ambiguity;
+mod answer_subsumption;
+mod applicable_impls;
+mod autoderef;
+mod batch;
+mod cache;
+mod canonical_diff;
+mod chalk_files;
+mod clause_order;
+mod conformance;
+mod constraint_normalization;
+mod custom_builtins;
+mod custom_predicate;
+mod custom_ty;
+mod debugger;
+mod deep_goals;
+mod environment_builder;
+mod explain;
+mod fuzz;
+mod goal_builder;
+mod goal_options;
+mod goal_simplify;
+mod graphviz;
+mod growing_goals;
+mod impl_index;
+mod incremental;
+mod kind_check;
+mod leak_check;
+mod memoizing;
+mod method_resolution;
+mod minimize;
+mod modules;
+mod name_map;
+mod no_solution_cause;
+mod object_candidates;
+mod overflow;
 mod panic;
+mod parse_diagnostics;
+mod pretty_options;
+mod program_snapshot;
+mod recording;
+mod rust_source;
+mod serialization;
+mod simple_interner;
+mod solve_all;
+mod solver_events;
+mod source_spans;
+mod stepping;
+mod struct_tail;
+mod substitution;
+mod sync_forest;
+mod util;
+mod variable_origins;
+mod visit;
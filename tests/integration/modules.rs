@@ -0,0 +1,135 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes `files` (relative paths and contents) into a fresh directory.
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("chalk-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, text) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    dir
+}
+
+fn solve(db: &ChalkDatabase, goal: &str) -> bool {
+    let goal = util::goal(db, goal);
+    db.with_program(|_| db.solve(&goal)).is_some()
+}
+
+#[test]
+fn program_split_into_modules() {
+    let dir = write_files(
+        "modules",
+        &[
+            ("main.chalk", "mod types; mod traits; impl Clone for Foo {}"),
+            ("types.chalk", "struct Foo {} mod nested;"),
+            ("types/nested.chalk", "struct Bar {}"),
+            (
+                "traits/mod.chalk",
+                "trait Clone {} impl<T> Clone for Vec<T> where T: Clone {} struct Vec<T> {}",
+            ),
+        ],
+    );
+
+    let db = ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    assert!(solve(&db, "Foo: Clone"));
+    assert!(solve(&db, "Vec<Foo>: Clone"));
+    assert!(!solve(&db, "Vec<Bar>: Clone"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_module() {
+    let dir = write_files("missing-module", &[("main.chalk", "mod missing;")]);
+
+    let db = ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    let error = db.program_ir().unwrap_err().to_string();
+    assert!(error.contains("error reading"), "{}", error);
+    assert!(
+        error.contains(&*Path::new("missing").join("mod.chalk").to_string_lossy()),
+        "{}",
+        error
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn module_declared_twice() {
+    let dir = write_files(
+        "module-declared-twice",
+        &[
+            ("main.chalk", "mod types; mod types;"),
+            ("types.chalk", "struct Foo {}"),
+        ],
+    );
+
+    let db = ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    let error = db.program_ir().unwrap_err().to_string();
+    assert!(
+        error.contains("module `types` is declared more than once"),
+        "{}",
+        error
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn module_file_in_both_places() {
+    let dir = write_files(
+        "module-file-in-both-places",
+        &[
+            ("main.chalk", "mod types;"),
+            ("types.chalk", "struct Foo {}"),
+            ("types/mod.chalk", "struct Foo {}"),
+        ],
+    );
+
+    let db = ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    let error = db.program_ir().unwrap_err().to_string();
+    assert!(
+        error.contains("file for module `types` found at both"),
+        "{}",
+        error
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn reload_module_files() {
+    let dir = write_files(
+        "reload-module-files",
+        &[
+            ("main.chalk", "mod impls; struct Foo {} trait Clone {}"),
+            ("impls.chalk", ""),
+        ],
+    );
+
+    let mut db =
+        ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    assert!(!solve(&db, "Foo: Clone"));
+
+    // The database keeps the texts it read until it is reloaded.
+    fs::write(dir.join("impls.chalk"), "impl Clone for Foo {}").unwrap();
+    assert!(!solve(&db, "Foo: Clone"));
+    db.reload().unwrap();
+    assert!(solve(&db, "Foo: Clone"));
+
+    // Moving a module to its other place is seen too.
+    fs::remove_file(dir.join("impls.chalk")).unwrap();
+    fs::create_dir(dir.join("impls")).unwrap();
+    fs::write(dir.join("impls").join("mod.chalk"), "").unwrap();
+    db.reload().unwrap();
+    assert!(!solve(&db, "Foo: Clone"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
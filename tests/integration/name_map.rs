@@ -0,0 +1,67 @@
+use super::util;
+use chalk_ir::TyKind;
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    struct Foo {}
+    struct Vec<T> {}
+    trait Iterator {
+        type Item;
+    }
+    trait IntoIterator {
+        type Item;
+        type IntoIter;
+    }
+    impl<T> Iterator for Vec<T> {
+        type Item = T;
+    }
+";
+
+#[test]
+fn names_and_ids() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let names = program.name_map();
+
+        let vec = names.adt_id("Vec").unwrap();
+        assert_eq!(names.adt_name(vec), Some("Vec"));
+        assert_eq!(names.adt_id("Iterator"), None);
+
+        let iterator = names.trait_id("Iterator").unwrap();
+        let into_iterator = names.trait_id("IntoIterator").unwrap();
+        assert_eq!(names.trait_name(iterator), Some("Iterator"));
+        assert_eq!(names.trait_id("Foo"), None);
+
+        // Associated types of different traits may have the same name.
+        let item = names.assoc_type_id(iterator, "Item").unwrap();
+        let into_item = names.assoc_type_id(into_iterator, "Item").unwrap();
+        assert_ne!(item, into_item);
+        assert_eq!(names.assoc_type_name(item), Some((iterator, "Item")));
+        assert_eq!(names.assoc_type_id(iterator, "IntoIter"), None);
+    });
+}
+
+#[test]
+fn names_of_lowered_items() {
+    let db = util::db(PROGRAM);
+    db.with_program(|program| {
+        let names = program.name_map();
+        let impl_ids = program.local_impls_to_coherence_check(names.trait_id("Iterator").unwrap());
+        let impl_datum = program.impl_datum(impl_ids[0]);
+        let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
+        assert_eq!(names.trait_name(trait_ref.trait_id), Some("Iterator"));
+        match trait_ref
+            .self_type_parameter(db.interner())
+            .kind(db.interner())
+        {
+            TyKind::Adt(adt_id, _) => assert_eq!(names.adt_name(*adt_id), Some("Vec")),
+            kind => panic!("unexpected self type {:?}", kind),
+        }
+
+        let value = program.associated_ty_value(impl_datum.associated_ty_value_ids[0]);
+        assert_eq!(
+            names.assoc_type_name(value.associated_ty_id),
+            Some((trait_ref.trait_id, "Item"))
+        );
+    });
+}
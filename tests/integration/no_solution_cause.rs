@@ -0,0 +1,47 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_recursive::RecursiveSolver;
+use chalk_solve::{NoSolutionCause, Solver};
+
+/// `T: Foo` only holds if `T = Vec<T>`, which would be a type of infinite
+/// size: proving it fails the occurs check in a subgoal.
+const PROGRAM: &str = "
+    struct Vec<T> {}
+    trait Same<T> {}
+    impl<T> Same<T> for T {}
+    trait Foo {}
+    impl<T> Foo for T where T: Same<Vec<T>> {}
+    trait Bar {}
+";
+
+/// Why `goal` has no solution, as `solver` explains it: the goal of an
+/// occurs check failure, or `Unknown`.
+fn cause(solver: &mut dyn Solver<ChalkIr>, goal: &str) -> String {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| match solver.solve_explained(&db, &goal) {
+        Ok(solution) => panic!("unexpected solution {:?}", solution),
+        Err(NoSolutionCause::OccursCheck(goal)) => format!("{:?}", goal.value),
+        Err(NoSolutionCause::Unknown) => "Unknown".to_string(),
+    })
+}
+
+#[test]
+fn nested_occurs_check_slg() {
+    let mut solver = util::slg();
+    assert_eq!(
+        cause(&mut solver, "exists<T> { T: Foo }"),
+        "(^0.0 = Vec<^0.0>)"
+    );
+    assert_eq!(cause(&mut solver, "exists<T> { T: Bar }"), "Unknown");
+}
+
+#[test]
+fn nested_occurs_check_recursive() {
+    let mut solver = RecursiveSolver::new(100, 10, None);
+    assert_eq!(
+        cause(&mut solver, "exists<T> { T: Foo }"),
+        "(^0.0 = Vec<^0.0>)"
+    );
+    assert_eq!(cause(&mut solver, "exists<T> { T: Bar }"), "Unknown");
+}
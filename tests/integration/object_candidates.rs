@@ -0,0 +1,58 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::*;
+use chalk_solve::clauses::dyn_object_candidate_clauses;
+
+const PROGRAM: &str = "
+    #[auto] trait Send {}
+    trait Base<T> {}
+    trait Iterator { type Item; }
+    #[object_safe]
+    trait Stream where Self: Base<u32>, Self: Iterator<Item = u32> { type Error; }
+";
+
+/// The object candidate clauses of the `dyn` type `ty`, which is shown as
+/// `Self` in them.
+fn object_candidates(db: &ChalkDatabase, ty: &str) -> Vec<String> {
+    let goal = db
+        .parse_and_lower_goal(&format!("exists<T> {{ T = {} }}", ty))
+        .unwrap();
+    db.with_program(|program| {
+        let dyn_ty = match goal.data(ChalkIr) {
+            GoalData::Quantified(_, binders) => match binders.skip_binders().data(ChalkIr) {
+                GoalData::EqGoal(eq) => match eq.b.assert_ty_ref(ChalkIr).kind(ChalkIr) {
+                    TyKind::Dyn(dyn_ty) => dyn_ty.clone(),
+                    ty => panic!("not a `dyn` type: {:?}", ty),
+                },
+                goal => panic!("unexpected goal {:?}", goal),
+            },
+            goal => panic!("unexpected goal {:?}", goal),
+        };
+        let self_ty = format!("{:?}", TyKind::Dyn(dyn_ty.clone()).intern(ChalkIr));
+        dyn_object_candidate_clauses(program, &dyn_ty)
+            .into_iter()
+            .map(|clause| format!("{:?}", clause).replace(&self_ty, "Self"))
+            .collect()
+    })
+}
+
+#[test]
+fn principal_and_supertraits() {
+    let db = util::db(PROGRAM);
+    assert_eq!(
+        object_candidates(&db, "dyn Stream<Error = u32> + Send + 'static"),
+        vec![
+            // The where clauses of `Stream` are conditions of its clause,
+            // and its supertraits are elaborated into clauses of their own.
+            "for<> Implemented(Self: Stream) :- \
+             ForAll<> { Implemented(Self: Base<Uint(U32)>) }, \
+             ForAll<> { AliasEq(<Self as Iterator>::Item = Uint(U32)) }, \
+             ForAll<> { Implemented(Self: Iterator) }",
+            "for<> Implemented(Self: Base<Uint(U32)>)",
+            "for<> Implemented(Self: Iterator)",
+            "for<> Implemented(Self: Send)",
+            "for<> AliasEq(<Self as Stream>::Error = Uint(U32))",
+        ]
+    );
+}
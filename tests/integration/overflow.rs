@@ -0,0 +1,116 @@
+use super::util;
+use chalk_engine::solve::SLGSolver;
+use chalk_integration::interner::ChalkIr;
+use chalk_recursive::{Cache, RecursiveSolver};
+use chalk_solve::{Overflow, Solver};
+
+const PROGRAM: &str = "
+    struct Vec<T> {}
+    struct Foo {}
+    struct Bar {}
+    trait Trait {}
+    trait Other {}
+    impl<T> Trait for T where Vec<T>: Trait {}
+    impl Other for Bar {}
+";
+
+/// Solves `goal` with `solver`, returning the goals of the overflow stack.
+fn overflow(solver: &mut dyn Solver<ChalkIr>, goal: &str) -> Option<(Vec<String>, usize)> {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| match solver.solve_or_overflow(&db, &goal) {
+        Ok(solution) => {
+            assert!(solution.unwrap().is_unique());
+            None
+        }
+        Err(Overflow { stack, omitted }) => {
+            let stack = stack
+                .iter()
+                .map(|goal| format!("{:?}", goal.canonical().value.goal))
+                .collect();
+            Some((stack, omitted))
+        }
+    })
+}
+
+#[test]
+fn size_overflow() {
+    // The SLG solver makes tables of the (empty) `ForAll` goals of the impl
+    // conditions too.
+    let mut slg = SLGSolver::new(3, None);
+    assert_eq!(
+        overflow(&mut slg, "Foo: Trait"),
+        Some((
+            vec![
+                "Implemented(Foo: Trait)".to_string(),
+                "ForAll<> { Implemented(Vec<Foo>: Trait) }".to_string(),
+                "Implemented(Vec<Foo>: Trait)".to_string(),
+                "ForAll<> { Implemented(Vec<Vec<Foo>>: Trait) }".to_string(),
+                "Implemented(Vec<Vec<Foo>>: Trait)".to_string(),
+                "ForAll<> { Implemented(Vec<Vec<Vec<Foo>>>: Trait) }".to_string(),
+            ],
+            0
+        ))
+    );
+    let mut recursive = RecursiveSolver::new(100, 3, Some(Cache::new()));
+    assert_eq!(
+        overflow(&mut recursive, "Foo: Trait"),
+        Some((
+            vec![
+                "Implemented(Foo: Trait)".to_string(),
+                "Implemented(Vec<Foo>: Trait)".to_string(),
+                "Implemented(Vec<Vec<Foo>>: Trait)".to_string(),
+                "Implemented(Vec<Vec<Vec<Foo>>>: Trait)".to_string(),
+            ],
+            0
+        ))
+    );
+}
+
+#[test]
+fn depth_overflow() {
+    let mut recursive = RecursiveSolver::new(3, 100, Some(Cache::new()));
+    assert_eq!(
+        overflow(&mut recursive, "Foo: Trait"),
+        Some((
+            vec![
+                "Implemented(Foo: Trait)".to_string(),
+                "Implemented(Vec<Foo>: Trait)".to_string(),
+                "Implemented(Vec<Vec<Foo>>: Trait)".to_string(),
+                "FromEnv(Vec<Vec<Foo>>: Trait)".to_string(),
+            ],
+            0
+        ))
+    );
+}
+
+#[test]
+fn overflow_stack_is_bounded() {
+    let mut recursive = RecursiveSolver::new(40, 1000, Some(Cache::new()));
+    let (stack, omitted) = overflow(&mut recursive, "Foo: Trait").unwrap();
+    assert_eq!(stack.len(), Overflow::<ChalkIr>::MAX_STACK);
+    assert_eq!(stack.len() + omitted, 41);
+}
+
+#[test]
+fn overflow_of_cached_goals() {
+    // The overflow is reported for goals which reuse the cached results of
+    // goals that overflowed.
+    let mut slg = SLGSolver::new(3, None);
+    let mut recursive = RecursiveSolver::new(100, 3, Some(Cache::new()));
+    for solver in [&mut slg as &mut dyn Solver<ChalkIr>, &mut recursive] {
+        let (stack, _) = overflow(solver, "Vec<Foo>: Trait").unwrap();
+        assert_eq!(stack[0], "Implemented(Vec<Foo>: Trait)");
+        let (stack, _) = overflow(solver, "Foo: Trait").unwrap();
+        assert_eq!(stack[0], "Implemented(Foo: Trait)");
+        assert!(stack.last().unwrap().contains("Vec<Vec<Vec<Foo>>>"));
+    }
+}
+
+#[test]
+fn no_overflow() {
+    let mut slg = SLGSolver::new(3, None);
+    assert_eq!(overflow(&mut slg, "Bar: Other"), None);
+    let mut recursive = RecursiveSolver::new(100, 3, Some(Cache::new()));
+    assert_eq!(overflow(&mut recursive, "Bar: Other"), None);
+}
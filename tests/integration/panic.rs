@@ -2,6 +2,7 @@ use chalk_integration::interner::{ChalkIr, RawId};
 use chalk_integration::SolverChoice;
 use chalk_ir::*;
 use chalk_solve::rust_ir::*;
+use chalk_solve::simplified_type::ImplFilter;
 use chalk_solve::RustIrDatabase;
 use std::sync::Arc;
 
@@ -83,6 +84,7 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
             },
             associated_ty_ids: vec![],
             well_known: None,
+            span: None,
         })
     }
 
@@ -115,6 +117,7 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
             binders,
             impl_type: ImplType::Local,
             associated_ty_value_ids: vec![],
+            span: None,
         })
     }
 
@@ -150,6 +153,7 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
             },
             id,
             kind: AdtKind::Enum,
+            span: None,
         })
     }
 
@@ -180,8 +184,7 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
     fn impls_for_trait(
         &self,
         trait_id: TraitId<ChalkIr>,
-        parameters: &[GenericArg<ChalkIr>],
-        binders: &CanonicalVarKinds<ChalkIr>,
+        _filter: &ImplFilter<'_, ChalkIr>,
     ) -> Vec<ImplId<ChalkIr>> {
         if let PanickingMethod::ImplsForTrait = self.panicking_method {
             panic!("impls_for_trait panic");
@@ -274,8 +277,8 @@ fn prepare_goal() -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
     // Goal:
     //
     // Foo: Bar
-    UCanonical {
-        canonical: Canonical {
+    UCanonical::new(
+        Canonical {
             binders: CanonicalVarKinds::empty(ChalkIr),
             value: InEnvironment {
                 environment: Environment::new(ChalkIr),
@@ -295,8 +298,8 @@ fn prepare_goal() -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
                 .intern(ChalkIr),
             },
         },
-        universes: 1,
-    }
+        1,
+    )
 }
 
 #[test]
@@ -0,0 +1,92 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_parse::{ParseDiagnostics, Position};
+
+fn diagnostics(text: &str) -> ParseDiagnostics {
+    let error = chalk_parse::parse_program(text).unwrap_err();
+    error.downcast_ref::<ParseDiagnostics>().unwrap().clone()
+}
+
+#[test]
+fn reports_every_malformed_item() {
+    let diagnostics = diagnostics(
+        "struct Foo { x: }
+         trait Bar { }
+         struct Baz<T> where T: { }
+         impl Bar for Foo { }",
+    );
+    let errors = &diagnostics.diagnostics;
+    assert_eq!(errors.len(), 2);
+
+    assert_eq!(errors[0].message, "Unrecognized token `}`");
+    assert!(errors[0].expected.contains(&"identifier".to_string()));
+    let location = errors[0].location.as_ref().unwrap();
+    assert_eq!(
+        location.start,
+        Position {
+            line: 1,
+            column: 17
+        }
+    );
+    assert_eq!(
+        location.end,
+        Position {
+            line: 1,
+            column: 18
+        }
+    );
+    assert_eq!(location.source_line, "struct Foo { x: }");
+
+    assert_eq!(errors[1].message, "Unrecognized token `{`");
+    assert!(errors[1].expected.contains(&"lifetime".to_string()));
+    let location = errors[1].location.as_ref().unwrap();
+    assert_eq!(
+        location.start,
+        Position {
+            line: 3,
+            column: 33
+        }
+    );
+}
+
+#[test]
+fn display_underlines_errors() {
+    let diagnostics = diagnostics("struct Foo { }\nstruct Bar<T> where T: Baz<u32 { }");
+    assert_eq!(
+        diagnostics.to_string(),
+        "parse error: Unrecognized token `{`, expected one of `)`, `,`, `;`, `>`, `}`\n \
+         --> 2:32\n  \
+         | struct Bar<T> where T: Baz<u32 { }\n  \
+         |                                ^"
+    );
+}
+
+#[test]
+fn user_errors_have_no_location() {
+    let diagnostics = diagnostics("struct Foo<T> where T: ?Bar { }");
+    assert_eq!(diagnostics.diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics.to_string(),
+        "parse error: `?Trait` bounds are only supported for `Sized`"
+    );
+}
+
+#[test]
+fn diagnostics_name_the_module_file() {
+    let dir = std::env::temp_dir().join(format!("chalk-diagnostics-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.chalk"), "mod broken; struct Foo { }").unwrap();
+    std::fs::write(dir.join("broken.chalk"), "struct Bar {").unwrap();
+
+    let db = ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    let error = db.program_ir().unwrap_err();
+    let diagnostics = error.parse_diagnostics().unwrap();
+    assert_eq!(diagnostics.path, Some(dir.join("broken.chalk")));
+    assert_eq!(
+        diagnostics.diagnostics[0].message,
+        "Unexpected end of input"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
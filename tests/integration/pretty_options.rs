@@ -0,0 +1,98 @@
+use super::util;
+use chalk_solve::display::{DisplayWithDb, PrettyOptions};
+
+const PROGRAM: &str = "
+    struct Foo {}
+    struct Vec<T> {}
+    trait Trait {}
+    trait Bound<T> {}
+    impl Trait for Vec<Vec<Foo>> {}
+    impl<T> Bound<T> for Foo {}
+    opaque type Hidden<T>: Bound<T> = Foo;
+";
+
+/// Solves `goal` and writes the solution with `options`.
+fn solve(goal: &str, options: PrettyOptions) -> String {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| {
+        db.solve(&goal)
+            .unwrap()
+            .display_with(&db)
+            .with_options(options)
+            .to_string()
+    })
+}
+
+#[test]
+fn defaults() {
+    let options = PrettyOptions::default();
+    assert_eq!(
+        solve("exists<T> { T: Trait }", options),
+        "Unique; substitution [?0 := Vec<Vec<Foo>>]"
+    );
+    assert_eq!(
+        solve("forall<T> { exists<U> { U = T } }", options),
+        "Unique; substitution [?0 := !1_0]"
+    );
+    assert_eq!(
+        solve("exists<T, U> { T = U }", options),
+        "Unique; for<_0_0> { substitution [?0 := _0_0, ?1 := _0_0] }"
+    );
+    assert_eq!(
+        solve("exists<T> { T = Vec<Hidden<Foo>> }", options),
+        "Unique; substitution [?0 := Vec<Hidden<Foo>>]"
+    );
+}
+
+#[test]
+fn max_depth() {
+    let elide = |max_depth| {
+        solve(
+            "exists<T> { T: Trait }",
+            PrettyOptions {
+                max_depth: Some(max_depth),
+                ..PrettyOptions::default()
+            },
+        )
+    };
+    assert_eq!(elide(0), "Unique; substitution [?0 := …]");
+    assert_eq!(elide(2), "Unique; substitution [?0 := Vec<Vec<…>>]");
+    assert_eq!(elide(3), "Unique; substitution [?0 := Vec<Vec<Foo>>]");
+}
+
+#[test]
+fn universes() {
+    let options = PrettyOptions {
+        show_universes: false,
+        ..PrettyOptions::default()
+    };
+    assert_eq!(
+        solve("forall<T> { exists<U> { U = T } }", options),
+        "Unique; substitution [?0 := !0]"
+    );
+}
+
+#[test]
+fn debrujin_indices() {
+    let options = PrettyOptions {
+        debrujin_indices: true,
+        ..PrettyOptions::default()
+    };
+    assert_eq!(
+        solve("exists<T, U> { T = U }", options),
+        "Unique; for<^0.0> { substitution [?0 := ^0.0, ?1 := ^0.0] }"
+    );
+}
+
+#[test]
+fn expand_aliases() {
+    let options = PrettyOptions {
+        expand_aliases: true,
+        ..PrettyOptions::default()
+    };
+    assert_eq!(
+        solve("exists<T> { T = Vec<Hidden<Foo>> }", options),
+        "Unique; substitution [?0 := Vec<impl Bound<Foo>>]"
+    );
+}
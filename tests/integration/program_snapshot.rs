@@ -0,0 +1,68 @@
+use super::util;
+use chalk_integration::program::Program;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+
+const PROGRAM: &str = "
+    #[lang(drop)]
+    trait Drop {}
+    #[object_safe]
+    trait Clone {}
+    #[fundamental]
+    struct Box<T> {}
+    #[repr(C)]
+    struct Foo {}
+    enum Option<T> { None, Some(T) }
+    trait Iterator { type Item: Clone; }
+    struct Vec<T> where T: Clone {}
+    impl Clone for Foo {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+    impl<T> Iterator for Vec<T> where T: Clone { type Item = T; }
+    impl !Clone for Box<Foo> {}
+    opaque type Hidden: Clone = Foo;
+    fn bar<T>(t: T) -> Vec<T>;
+    closure baz(self,) { Foo }
+    generator gen<T>[resume = Foo, yield = T] {
+        upvars [T; Foo]
+        witnesses exists<'a> [&'a T]
+    }
+    extern type Extern;
+";
+
+#[test]
+fn round_trip_lowered_program() {
+    let db = util::db(PROGRAM);
+    let program = db.with_program(|program| program.clone());
+
+    let bytes = program.to_bytes();
+    assert_eq!(Program::from_bytes(&bytes).unwrap(), program);
+}
+
+#[test]
+fn solve_with_loaded_program() {
+    let db = util::db(PROGRAM);
+    let bytes = db.with_program(|program| program.to_bytes());
+    let program = Program::from_bytes(&bytes).unwrap();
+
+    let goal = db.parse_and_lower_goal("Vec<Foo>: Iterator").unwrap();
+    let peeled_goal = goal.into_peeled_goal(program.interner());
+    let solution = SolverChoice::default()
+        .into_solver()
+        .solve(&program, &peeled_goal);
+    assert_eq!(solution, db.solve(&peeled_goal));
+    assert!(
+        matches!(solution, Some(Solution::Unique(_))),
+        "{:?}",
+        solution
+    );
+}
+
+#[test]
+fn reject_truncated_snapshot() {
+    let db = util::db(PROGRAM);
+    let bytes = db.with_program(|program| program.to_bytes());
+
+    assert!(Program::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    assert!(Program::from_bytes(b"").is_err());
+}
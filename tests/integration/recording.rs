@@ -0,0 +1,86 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_solve::logging_db::{Recording, RecordingRustIrDatabase};
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    trait Clone {}
+    #[lang(fn_once)]
+    trait FnOnce<Args> { type Output; }
+    trait Iterator { type Item: Clone; }
+    struct Foo {}
+    struct Bar {}
+    struct Vec<T> {}
+    impl Clone for Foo {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+    impl<T> Iterator for Vec<T> where T: Clone { type Item = T; }
+    opaque type Hidden: Clone = Foo;
+    closure baz(self,) { Foo }
+";
+
+const GOALS: &[&str] = &[
+    "Vec<Vec<Foo>>: Clone",
+    "Vec<Bar>: Clone",
+    "exists<T> { Vec<T>: Iterator<Item = Foo> }",
+    "Hidden: Clone",
+    "baz: FnOnce<()>",
+];
+
+fn goals(db: &ChalkDatabase) -> Vec<util::UCanonicalGoal> {
+    GOALS.iter().map(|goal| util::goal(db, goal)).collect()
+}
+
+/// Solves `GOALS` through a `RecordingRustIrDatabase`, returning the
+/// recording and the solutions.
+fn record(solver_choice: SolverChoice) -> (Recording<ChalkIr>, Vec<String>) {
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let recording_db = RecordingRustIrDatabase::<_, ChalkDatabase, _>::new(&db);
+    let mut solver = solver_choice.into_solver();
+    let solutions = goals(&db)
+        .iter()
+        .map(|goal| format!("{:?}", solver.solve(&recording_db, goal)))
+        .collect();
+    (recording_db.into_recording(), solutions)
+}
+
+fn replay(recording: &Recording<ChalkIr>, solver_choice: SolverChoice) -> Vec<String> {
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let mut solver = solver_choice.into_solver();
+    goals(&db)
+        .iter()
+        .map(|goal| format!("{:?}", solver.solve(recording, goal)))
+        .collect()
+}
+
+#[test]
+fn replays_recorded_solve() {
+    for solver_choice in [
+        SolverChoice::slg_default(),
+        SolverChoice::recursive_default(),
+    ] {
+        let (recording, solutions) = record(solver_choice);
+        assert!(solutions[0].starts_with("Some(Unique"));
+        assert_eq!(solutions[1], "None");
+        assert_eq!(replay(&recording, solver_choice), solutions);
+    }
+}
+
+#[test]
+fn replays_deserialized_recording() {
+    let solver_choice = SolverChoice::slg_default();
+    let (recording, solutions) = record(solver_choice);
+    let bytes = bincode::serialize(&recording).unwrap();
+    let recording: Recording<ChalkIr> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(replay(&recording, solver_choice), solutions);
+}
+
+#[test]
+#[should_panic(expected = "`trait_datum` was not called")]
+fn unrecorded_calls_panic() {
+    let db = util::db(PROGRAM);
+    let recording = RecordingRustIrDatabase::<_, ChalkDatabase, _>::new(&db).into_recording();
+    let clone = db.with_program(|program| program.trait_ids[&"Clone".into()]);
+    recording.trait_datum(clone);
+}
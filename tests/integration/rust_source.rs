@@ -0,0 +1,119 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use std::fs;
+
+fn solve(db: &ChalkDatabase, goal: &str) -> bool {
+    let goal = util::goal(db, goal);
+    db.with_program(|_| db.solve(&goal)).is_some()
+}
+
+const SKELETON: &str = r#"
+use std::fmt;
+
+pub trait Clone {
+    fn clone(&self) -> Self;
+}
+
+pub trait Iterator {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+
+pub struct Vec<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+pub struct IntoIter<T>(Vec<T>);
+
+pub enum Option<T> {
+    None,
+    Some(T),
+}
+
+pub type Pair<T> = (T, T);
+
+mod impls {
+    impl<T> Clone for Vec<T> where T: Clone {
+        fn clone(&self) -> Self { unimplemented!() }
+    }
+
+    impl Clone for u32 {
+        fn clone(&self) -> Self { *self }
+    }
+
+    impl<T: Clone> Clone for Pair<T> {
+        fn clone(&self) -> Self { unimplemented!() }
+    }
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+        fn next(&mut self) -> Option<T> { None }
+    }
+
+    impl<T> Vec<T> {
+        pub fn new() -> Self { unimplemented!() }
+    }
+}
+
+impl fmt::Debug for Vec<u32> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { Ok(()) }
+}
+
+trait Debug {}
+"#;
+
+#[test]
+fn crate_skeleton() {
+    let db = ChalkDatabase::with_rust(SKELETON, SolverChoice::default());
+    assert!(solve(&db, "Vec<u32>: Clone"));
+    assert!(solve(&db, "(u32, u32): Clone"));
+    assert!(!solve(&db, "Vec<Option<u32>>: Clone"));
+    assert!(solve(&db, "<IntoIter<u32> as Iterator>::Item = u32"));
+    assert!(solve(&db, "Vec<u32>: Debug"));
+}
+
+#[test]
+fn rust_files_are_read_as_rust() {
+    let path = std::env::temp_dir().join(format!("chalk-rust-{}.rs", std::process::id()));
+    fs::write(&path, "trait Foo {}\nstruct Bar;\nimpl Foo for Bar {}").unwrap();
+
+    let db = ChalkDatabase::with_file(&path, SolverChoice::default()).unwrap();
+    assert!(solve(&db, "Bar: Foo"));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn unsupported_constructs_are_errors() {
+    let db = ChalkDatabase::with_rust(
+        "trait Foo {}\nimpl Foo for &u32 {}",
+        SolverChoice::default(),
+    );
+    assert_eq!(
+        db.program_ir().unwrap_err().to_string(),
+        "error: elided lifetimes are not supported\n \
+         --> 2:14\n  \
+         | impl Foo for &u32 {}\n  \
+         |              ^^^^"
+    );
+
+    let db = ChalkDatabase::with_rust(
+        "type A = B;\ntype B = A;\nstruct S(A);",
+        SolverChoice::default(),
+    );
+    assert!(db
+        .program_ir()
+        .unwrap_err()
+        .to_string()
+        .contains("is recursive"));
+
+    let db = ChalkDatabase::with_rust("struct S {", SolverChoice::default());
+    assert!(db
+        .program_ir()
+        .unwrap_err()
+        .to_string()
+        .starts_with("error: "));
+}
@@ -0,0 +1,66 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::{Environment, Goal, InEnvironment, ProgramClauses, UCanonical};
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+
+/// Round-trips a value through JSON and checks the result is the same.
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + fmt::Debug>(value: &T) {
+    let serialized = serde_json::to_string(value).unwrap();
+    let deserialized: T = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(*value, deserialized);
+}
+
+#[test]
+fn fingerprints_are_not_serialized() {
+    let db = util::db("struct Foo {} trait Clone {}");
+    let goal = util::goal(&db, "Foo: Clone");
+
+    // Only the canonical goal and the number of universes are serialized,
+    // and the fingerprint is computed again from them.
+    let serialized = serde_json::to_value(&goal).unwrap();
+    let fields: Vec<_> = serialized.as_object().unwrap().keys().collect();
+    assert_eq!(fields, ["canonical", "universes"]);
+    let deserialized: UCanonical<InEnvironment<Goal<ChalkIr>>> =
+        serde_json::from_value(serialized).unwrap();
+    assert_eq!(deserialized.fingerprint(), goal.fingerprint());
+}
+
+#[test]
+fn round_trip_goals_and_solutions() {
+    let db = util::db(
+        "
+        struct Foo {}
+        struct Vec<T> {}
+        trait Clone {}
+        trait Iterator { type Item; }
+        impl Clone for Foo {}
+        impl<T> Clone for Vec<T> where T: Clone {}
+        impl<T> Iterator for Vec<T> { type Item = T; }
+        ",
+    );
+
+    for goal in &[
+        "exists<T> { Vec<T>: Clone }",
+        "forall<T> { if (T: Clone) { Vec<T>: Clone } }",
+        "exists<T> { <Vec<Foo> as Iterator>::Item = T }",
+        "forall<'a> { exists<'b> { &'a Vec<Foo>: Clone } }",
+        "Foo = Foo",
+    ] {
+        let goal: Goal<ChalkIr> = db.parse_and_lower_goal(goal).unwrap();
+        round_trip(&goal);
+
+        let peeled: UCanonical<InEnvironment<Goal<ChalkIr>>> = goal.into_peeled_goal(db.interner());
+        round_trip(&peeled);
+
+        let solution: Option<Solution<ChalkIr>> = db.solve(&peeled);
+        round_trip(&solution);
+    }
+
+    let clauses: ProgramClauses<ChalkIr> =
+        db.program_clauses_for_env(&Environment::new(db.interner()));
+    round_trip(&clauses);
+}
@@ -0,0 +1,49 @@
+use chalk_derive::SimpleInterner;
+use chalk_ir::arena::{ArenaInterner, Arenas};
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, SimpleInterner)]
+#[interner(def_id = u32, identifier = String)]
+struct ArcInterner;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, SimpleInterner)]
+#[interner(arena, def_id = u32, adt_id = u64, identifier = String, concrete_const = u8)]
+struct ArenaTypes;
+
+/// Builds `A0<A1, A2<()>>` and checks it can be taken apart again.
+fn check_interner<I, Id>(interner: I)
+where
+    I: Interner<InternedAdtId = Id>,
+    Id: From<u8> + Eq + std::fmt::Debug,
+{
+    let unit = TyKind::Tuple(0, Substitution::empty(interner)).intern(interner);
+    let adt = |id: u8, args: Vec<Ty<I>>| {
+        TyKind::Adt(AdtId(id.into()), Substitution::from_iter(interner, args)).intern(interner)
+    };
+    let ty = adt(0, vec![adt(1, vec![]), adt(2, vec![unit.clone()])]);
+
+    match ty.kind(interner) {
+        TyKind::Adt(id, substitution) => {
+            assert_eq!(*id, AdtId(0.into()));
+            let args: Vec<_> = substitution
+                .iter(interner)
+                .map(|arg| arg.assert_ty_ref(interner).clone())
+                .collect();
+            assert_eq!(args, vec![adt(1, vec![]), adt(2, vec![unit])]);
+        }
+        kind => panic!("unexpected type kind {:?}", kind),
+    }
+    assert_eq!(ty, ty.clone());
+}
+
+#[test]
+fn arc_interner() {
+    check_interner(ArcInterner);
+}
+
+#[test]
+fn arena_interner() {
+    let arenas = Arenas::<ArenaTypes>::new();
+    check_interner(ArenaInterner::new(&arenas));
+}
@@ -0,0 +1,55 @@
+use super::util;
+use chalk_integration::query::LoweringDatabase;
+use chalk_recursive::RecursiveSolver;
+use chalk_solve::{solve_all_parallel, Solver};
+
+const PROGRAM: &str = "
+    struct Vec<T> {}
+    struct Foo {}
+    struct Bar {}
+    trait Clone {}
+    impl Clone for Foo {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+";
+
+const GOALS: &[&str] = &[
+    "Foo: Clone",
+    "Bar: Clone",
+    "Vec<Foo>: Clone",
+    "Foo: Clone",
+    "Vec<Vec<Foo>>: Clone",
+    "Vec<Bar>: Clone",
+    "exists<T> { Vec<T>: Clone }",
+    "Vec<Foo>: Clone",
+];
+
+#[test]
+fn solve_all() {
+    let db = util::db(PROGRAM);
+    let program = db.checked_program().unwrap();
+    let goals: Vec<_> = GOALS.iter().map(|goal| util::goal(&db, goal)).collect();
+
+    let expected: Vec<_> = goals
+        .iter()
+        .map(|goal| util::slg().solve(&*program, goal))
+        .collect();
+
+    let mut slg = util::slg();
+    assert_eq!(slg.solve_all(&*program, &goals), expected);
+
+    let mut recursive = RecursiveSolver::new(100, 30, None);
+    assert_eq!(recursive.solve_all(&*program, &goals), expected);
+
+    let mut solvers: Vec<_> = (0..3).map(|_| util::slg()).collect();
+    assert_eq!(
+        solve_all_parallel(&mut solvers, &*program, &goals),
+        expected
+    );
+    // There are more solvers than distinct goals to give them.
+    let mut solvers: Vec<_> = (0..10).map(|_| util::slg()).collect();
+    assert_eq!(
+        solve_all_parallel(&mut solvers, &*program, &goals),
+        expected
+    );
+    assert_eq!(solve_all_parallel(&mut solvers, &*program, &[]), vec![]);
+}
@@ -0,0 +1,132 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_recursive::RecursiveSolver;
+use chalk_solve::{Solver, SolverEvent};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+    trait Foo {}
+    #[coinductive]
+    trait Co {}
+    trait Ind {}
+    #[non_enumerable]
+    trait NonEnumerable {}
+    struct A {}
+    struct B {}
+    impl Foo for A {}
+    impl Foo for B {}
+    impl Co for A where A: Co {}
+    impl Ind for A where A: Ind {}
+";
+
+/// The events each engine reports while solving `goal`, SLG first.
+fn events(goal: &str) -> [Vec<SolverEvent<ChalkIr>>; 2] {
+    let db = util::db(PROGRAM);
+    let goal = util::goal(&db, goal);
+    db.with_program(|_| {
+        let slg_events = Arc::new(Mutex::new(vec![]));
+        let sink = slg_events.clone();
+        let mut slg = util::slg().with_event_sink(Box::new(move |event| {
+            sink.lock().unwrap().push(event.clone())
+        }));
+        slg.solve(&db, &goal);
+
+        let recursive_events = Arc::new(Mutex::new(vec![]));
+        let sink = recursive_events.clone();
+        let mut recursive =
+            RecursiveSolver::new(100, 30, None).with_event_sink(Box::new(move |event| {
+                sink.lock().unwrap().push(event.clone())
+            }));
+        recursive.solve(&db, &goal);
+
+        let slg_events = mem::take(&mut *slg_events.lock().unwrap());
+        let recursive_events = mem::take(&mut *recursive_events.lock().unwrap());
+        [slg_events, recursive_events]
+    })
+}
+
+/// The kinds of `events`, without their goals.
+fn kinds(events: &[SolverEvent<ChalkIr>]) -> Vec<String> {
+    events
+        .iter()
+        .map(|event| match event {
+            SolverEvent::TableCreated { .. } => "TableCreated".to_string(),
+            SolverEvent::ClauseTried { .. } => "ClauseTried".to_string(),
+            SolverEvent::AnswerProduced { ambiguous, .. } => {
+                format!("AnswerProduced(ambiguous: {})", ambiguous)
+            }
+            SolverEvent::StrandFloundered { .. } => "StrandFloundered".to_string(),
+            SolverEvent::CycleDetected { coinductive, .. } => {
+                format!("CycleDetected(coinductive: {})", coinductive)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn clauses_and_answers() {
+    for events in events("A: Foo") {
+        assert!(matches!(events[0], SolverEvent::TableCreated { .. }));
+        let clauses = events
+            .iter()
+            .filter(|event| matches!(event, SolverEvent::ClauseTried { .. }))
+            .count();
+        assert!(clauses >= 1, "{:?}", kinds(&events));
+        assert!(
+            kinds(&events).contains(&"AnswerProduced(ambiguous: false)".to_string()),
+            "{:?}",
+            kinds(&events)
+        );
+    }
+}
+
+#[test]
+fn answers_of_the_goal() {
+    // Both impls of `Foo` prove the goal: the SLG solver reports each of
+    // the answers, the recursive solver the ambiguous solution they make.
+    let [slg, recursive] = events("exists<T> { T: Foo }");
+    let answers = |events: &[SolverEvent<ChalkIr>]| {
+        kinds(events)
+            .into_iter()
+            .filter(|kind| kind.starts_with("AnswerProduced"))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        answers(&slg),
+        vec![
+            "AnswerProduced(ambiguous: false)",
+            "AnswerProduced(ambiguous: false)"
+        ]
+    );
+    assert_eq!(answers(&recursive), vec!["AnswerProduced(ambiguous: true)"]);
+}
+
+#[test]
+fn cycles() {
+    for events in events("A: Co") {
+        assert!(
+            kinds(&events).contains(&"CycleDetected(coinductive: true)".to_string()),
+            "{:?}",
+            kinds(&events)
+        );
+    }
+    for events in events("A: Ind") {
+        assert!(
+            kinds(&events).contains(&"CycleDetected(coinductive: false)".to_string()),
+            "{:?}",
+            kinds(&events)
+        );
+    }
+}
+
+#[test]
+fn floundering() {
+    for events in events("exists<T> { T: NonEnumerable }") {
+        assert!(
+            kinds(&events).contains(&"StrandFloundered".to_string()),
+            "{:?}",
+            kinds(&events)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::{Identifier, SolverChoice};
+use std::fs;
+
+#[test]
+fn datums_know_where_they_are_declared() {
+    let text = "struct Foo { }
+trait Bar { type Assoc; }
+impl Bar for Foo { type Assoc = Foo; }";
+    let db = util::db(text);
+    db.with_program(|program| {
+        let declared = |span: Option<chalk_solve::rust_ir::Span>| {
+            let span = span.unwrap();
+            assert_eq!(span.source, 0);
+            &text[span.lo..span.hi]
+        };
+
+        let foo = program.adt_ids[&Identifier::from("Foo")];
+        assert_eq!(declared(program.adt_data[&foo].span), "struct Foo { }");
+
+        let bar = program.trait_ids[&Identifier::from("Bar")];
+        assert_eq!(
+            declared(program.trait_data[&bar].span),
+            "trait Bar { type Assoc; }"
+        );
+
+        let assoc = program.associated_ty_data.values().next().unwrap();
+        assert_eq!(declared(assoc.span), "type Assoc;");
+
+        let impl_datum = program.impl_data.values().next().unwrap();
+        assert_eq!(
+            declared(impl_datum.span),
+            "impl Bar for Foo { type Assoc = Foo; }"
+        );
+
+        let value = program.associated_ty_values.values().next().unwrap();
+        assert_eq!(declared(value.span), "type Assoc = Foo;");
+    });
+}
+
+#[test]
+fn wf_errors_point_at_the_declaration() {
+    let db = util::db(
+        "trait Clone { }
+trait Copy where Self: Clone { }
+struct Foo { }
+impl Copy for Foo { }",
+    );
+    assert_eq!(
+        db.checked_program().unwrap_err().to_string(),
+        "trait impl for `Copy` does not meet well-formedness requirements\n \
+         --> 4:1\n  \
+         | impl Copy for Foo { }\n  \
+         | ^^^^^^^^^^^^^^^^^^^^^"
+    );
+}
+
+#[test]
+fn coherence_errors_name_the_module_file() {
+    let dir = std::env::temp_dir().join(format!("chalk-spans-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("main.chalk"),
+        "#[upstream] trait Foo { }\n#[upstream] struct Bar { }\nmod impls;",
+    )
+    .unwrap();
+    fs::write(dir.join("impls.chalk"), "// local\nimpl Foo for Bar { }").unwrap();
+
+    let db = ChalkDatabase::with_file(&dir.join("main.chalk"), SolverChoice::default()).unwrap();
+    let error = db.checked_program().unwrap_err().to_string();
+    assert_eq!(
+        error,
+        format!(
+            "impl for trait `Foo` violates the orphan rules\n \
+             --> {}:2:1\n  \
+             | impl Foo for Bar {{ }}\n  \
+             | ^^^^^^^^^^^^^^^^^^^^",
+            dir.join("impls.chalk").display()
+        )
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,160 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_solve::{RustIrDatabase, Solver, SolverStep};
+
+const PROGRAM: &str = "
+    trait Clone {}
+    trait Copy {}
+    struct Foo {}
+    struct Bar {}
+    struct Vec<T> {}
+    impl Clone for Foo {}
+    impl Clone for Bar {}
+    impl Copy for Foo {}
+    impl Copy for Bar {}
+    impl<T> Clone for Vec<T> where T: Copy {}
+";
+
+/// Steps through `goal` until it has no more answers, returning its
+/// answers, and the largest stack seen on the way.
+fn step_through(
+    db: &ChalkDatabase,
+    solver: &mut dyn Solver<ChalkIr>,
+    goal: &util::UCanonicalGoal,
+) -> (Vec<String>, usize) {
+    let mut answers = vec![];
+    let mut max_stack = 0;
+    for _ in 0..1000 {
+        match solver.step(db, goal).unwrap() {
+            SolverStep::Searching => {
+                let stack = solver.step_stack(db);
+                if let Some(bottom) = stack.first() {
+                    assert_eq!(&bottom.goal, goal);
+                }
+                max_stack = max_stack.max(stack.len());
+            }
+            SolverStep::Answer(answer) => {
+                assert!(solver.step_stack(db).is_empty());
+                answers.push(format!(
+                    "{}",
+                    answer.as_ref().map(|answer| answer.display(db.interner()))
+                ));
+            }
+            SolverStep::NoMoreSolutions => return (answers, max_stack),
+        }
+    }
+    panic!("the search never ended");
+}
+
+#[test]
+fn stepping_finds_the_answers_of_solve_multiple() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    db.with_program(|_| {
+        let goal = util::goal(&db, "exists<T> { Vec<T>: Clone }");
+        let mut solver = SolverChoice::slg_default().into_solver();
+        let (answers, max_stack) = step_through(&db, &mut *solver, &goal);
+        assert!(max_stack >= 3, "{}", max_stack);
+
+        let mut expected = vec![];
+        SolverChoice::slg_default()
+            .into_solver()
+            .solve_multiple(&db, &goal, &mut |answer, _| {
+                expected.push(format!(
+                    "{}",
+                    answer.as_ref().map(|answer| answer.display(db.interner()))
+                ));
+                true
+            });
+        assert_eq!(answers, expected);
+        assert_eq!(answers.len(), 2);
+    });
+}
+
+#[test]
+fn the_active_strands_select_subgoals() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    db.with_program(|_| {
+        let goal = util::goal(&db, "Vec<Foo>: Clone");
+        let mut solver = SolverChoice::slg_default().into_solver();
+        assert!(matches!(
+            solver.step(&db, &goal).unwrap(),
+            SolverStep::Searching
+        ));
+        let stack = solver.step_stack(&db);
+        assert_eq!(stack.len(), 2);
+        // The strand of each goal but the last selected the goal above it.
+        let strand = stack[0].active_strand.as_ref().unwrap();
+        let selected = strand.selected_subgoal.unwrap();
+        assert_eq!(
+            strand.subgoals[selected].goal,
+            stack[1].goal.canonical().value.goal
+        );
+    });
+}
+
+#[test]
+fn active_strands_record_the_answers_they_applied() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    db.with_program(|_| {
+        let goal = util::goal(&db, "exists<T> { T: Copy, Vec<T>: Clone }");
+        let mut solver = SolverChoice::slg_default().into_solver();
+        // Step until the root strand proved `T: Copy` and went on with
+        // `Vec<T>: Clone`.
+        for _ in 0..100 {
+            solver.step(&db, &goal);
+            let stack = solver.step_stack(&db);
+            let strand = match stack.first().and_then(|frame| frame.active_strand.as_ref()) {
+                Some(strand) => strand,
+                None => continue,
+            };
+            if !strand.answer_sources.is_empty() {
+                // The first answer of the table of `?T: Copy`, which was
+                // created right after the one of the root goal.
+                assert_eq!(strand.answer_sources, [(1, 0)]);
+                assert_eq!(strand.subgoals.len(), 1);
+                return;
+            }
+        }
+        panic!("the root strand never applied an answer");
+    });
+}
+
+#[test]
+fn solving_abandons_the_search() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    db.with_program(|_| {
+        let goal = util::goal(&db, "Vec<Foo>: Clone");
+        let mut solver = SolverChoice::slg_default().into_solver();
+        for _ in 0..3 {
+            solver.step(&db, &goal);
+        }
+        assert!(!solver.step_stack(&db).is_empty());
+
+        // The strands on the stack are returned to their tables.
+        assert!(solver.solve(&db, &goal).unwrap().is_unique());
+        assert!(solver.step_stack(&db).is_empty());
+
+        // Stepping through the goal again starts from its first answer,
+        // which is now cached.
+        assert!(matches!(
+            solver.step(&db, &goal).unwrap(),
+            SolverStep::Answer(_)
+        ));
+        let other = util::goal(&db, "exists<T> { T: Clone }");
+        let (answers, _) = step_through(&db, &mut *solver, &other);
+        assert_eq!(answers.len(), 4);
+    });
+}
+
+#[test]
+fn recursive_solver_cannot_be_stepped_through() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive_default());
+    db.with_program(|_| {
+        let goal = util::goal(&db, "Foo: Clone");
+        let mut solver = SolverChoice::recursive_default().into_solver();
+        assert!(solver.step(&db, &goal).is_none());
+        assert!(solver.step_stack(&db).is_empty());
+    });
+}
@@ -0,0 +1,69 @@
+use super::util;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::*;
+use chalk_solve::struct_tail::{struct_tail, struct_tails};
+
+const PROGRAM: &str = "
+    #[lang(sized)]
+    trait Sized {}
+    struct Empty {}
+    struct Slice<T> where T: Sized { len: usize, data: [T] }
+    struct Wrapper<T> { id: u32, inner: T }
+    struct Other<T> { inner: T }
+    enum Either<T> where T: Sized { Left(T), Right }
+";
+
+/// Lowers the two sides of the goal `A = B`.
+fn tys(db: &ChalkDatabase, text: &str) -> (Ty<ChalkIr>, Ty<ChalkIr>) {
+    let goal = db.parse_and_lower_goal(&format!("forall<T> {{ {} }}", text));
+    match goal.unwrap().data(ChalkIr) {
+        GoalData::Quantified(_, binders) => match binders.skip_binders().data(ChalkIr) {
+            GoalData::EqGoal(EqGoal { a, b }) => (
+                a.ty(ChalkIr).unwrap().clone(),
+                b.ty(ChalkIr).unwrap().clone(),
+            ),
+            goal => panic!("expected `A = B`, got {:?}", goal),
+        },
+        goal => panic!("expected `forall<T> {{ A = B }}`, got {:?}", goal),
+    }
+}
+
+#[test]
+fn tails() {
+    let db = util::db(PROGRAM);
+    for (ty, tail) in [
+        ("u8", "u8"),
+        ("Empty", "Empty"),
+        ("Slice<u8>", "[u8]"),
+        ("Wrapper<Wrapper<Slice<u8>>>", "[u8]"),
+        ("Wrapper<(u8, Wrapper<str>)>", "str"),
+        ("Wrapper<Either<u8>>", "Either<u8>"),
+        ("Wrapper<T>", "T"),
+        ("(u8, Wrapper<T>)", "T"),
+    ] {
+        let (ty, tail) = tys(&db, &format!("{} = {}", ty, tail));
+        assert_eq!(struct_tail(&db, ty), tail);
+    }
+}
+
+#[test]
+fn lockstep_tails() {
+    let db = util::db(PROGRAM);
+    for (source, target, tails) in [
+        ("Wrapper<[u8; 4]>", "Wrapper<[u8]>", "[u8; 4] = [u8]"),
+        ("Wrapper<Wrapper<T>>", "Wrapper<Wrapper<str>>", "T = str"),
+        ("(u8, Wrapper<T>)", "(u8, Wrapper<u8>)", "T = u8"),
+        // The tails diverge at different structs.
+        (
+            "Wrapper<Wrapper<T>>",
+            "Wrapper<Other<T>>",
+            "Wrapper<T> = Other<T>",
+        ),
+        ("(u8, T)", "(u8, u8, T)", "(u8, T) = (u8, u8, T)"),
+    ] {
+        let (source, target) = tys(&db, &format!("{} = {}", source, target));
+        let tails = tys(&db, tails);
+        assert_eq!(struct_tails(&db, source, target), tails);
+    }
+}
@@ -0,0 +1,96 @@
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use std::collections::BTreeMap;
+
+fn var(index: usize) -> Ty<ChalkIr> {
+    BoundVar::new(DebruijnIndex::INNERMOST, index).to_ty(ChalkIr)
+}
+
+fn scalar(scalar: Scalar) -> Ty<ChalkIr> {
+    TyKind::Scalar(scalar).intern(ChalkIr)
+}
+
+fn tuple(elements: &[Ty<ChalkIr>]) -> Ty<ChalkIr> {
+    TyKind::Tuple(
+        elements.len(),
+        Substitution::from_iter(ChalkIr, elements.iter().cloned()),
+    )
+    .intern(ChalkIr)
+}
+
+fn subst(values: &[Ty<ChalkIr>]) -> Substitution<ChalkIr> {
+    Substitution::from_iter(ChalkIr, values.iter().cloned())
+}
+
+#[test]
+fn compose() {
+    let u32 = scalar(Scalar::Uint(UintTy::U32));
+    let bool = scalar(Scalar::Bool);
+    let a = subst(&[tuple(&[var(1), var(0)]), u32.clone()]);
+    let b = subst(&[bool.clone(), tuple(&[])]);
+    let composed = a.compose(ChalkIr, &b);
+    assert_eq!(composed, subst(&[tuple(&[tuple(&[]), bool]), u32]));
+
+    let value = tuple(&[var(0), var(1), var(0)]);
+    assert_eq!(
+        composed.apply(value.clone(), ChalkIr),
+        b.apply(a.apply(value, ChalkIr), ChalkIr)
+    );
+}
+
+#[test]
+fn apply_to_inference_vars() {
+    let infer = |index: u32| InferenceVar::from(index).to_ty(ChalkIr, TyVariableKind::General);
+    let free_vars: Vec<GenericArg<ChalkIr>> = vec![infer(0).cast(ChalkIr), infer(1).cast(ChalkIr)];
+    // The answer `?0 := (^0.0,)`, `?1 := bool`, with a variable of its own.
+    let bool = scalar(Scalar::Bool);
+    let answer = subst(&[tuple(&[var(0)]), bool.clone()]);
+
+    // Under the binder of the value, the variable of the answer is `^1.0`.
+    let value = Binders::new(
+        VariableKinds::from1(ChalkIr, VariableKind::Ty(TyVariableKind::General)),
+        tuple(&[infer(0), var(0), infer(1), infer(2)]),
+    );
+    let expected = Binders::new(
+        VariableKinds::from1(ChalkIr, VariableKind::Ty(TyVariableKind::General)),
+        tuple(&[
+            tuple(&[BoundVar::new(DebruijnIndex::ONE, 0).to_ty(ChalkIr)]),
+            var(0),
+            bool,
+            infer(2),
+        ]),
+    );
+    assert_eq!(
+        answer.apply_to_inference_vars(ChalkIr, &free_vars, value),
+        expected
+    );
+}
+
+#[test]
+fn maps() {
+    let answer = subst(&[scalar(Scalar::Bool), tuple(&[var(0)])]);
+    let map = answer.to_map(ChalkIr);
+    assert_eq!(
+        map.keys().copied().collect::<Vec<_>>(),
+        vec![
+            BoundVar::new(DebruijnIndex::INNERMOST, 0),
+            BoundVar::new(DebruijnIndex::INNERMOST, 1)
+        ]
+    );
+    assert_eq!(Substitution::from_map(ChalkIr, map.clone()), Some(answer));
+    assert_eq!(
+        Substitution::<ChalkIr>::from_map(ChalkIr, BTreeMap::new()),
+        Some(Substitution::empty(ChalkIr))
+    );
+
+    let mut missing = map.clone();
+    missing.remove(&BoundVar::new(DebruijnIndex::INNERMOST, 0));
+    assert_eq!(Substitution::from_map(ChalkIr, missing), None);
+    let mut outer = map;
+    outer.insert(
+        BoundVar::new(DebruijnIndex::ONE, 0),
+        scalar(Scalar::Bool).cast(ChalkIr),
+    );
+    assert_eq!(Substitution::from_map(ChalkIr, outer), None);
+}
@@ -0,0 +1,128 @@
+use super::util;
+use chalk_engine::solve::SLGSolver;
+use chalk_engine::sync::SyncForest;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_solve::Solver;
+use std::thread;
+
+const PROGRAM: &str = "
+    struct Vec<T> {}
+    struct Foo {}
+    struct Bar {}
+    trait Clone {}
+    impl Clone for Foo {}
+    impl<T> Clone for Vec<T> where T: Clone {}
+";
+
+const GOALS: &[&str] = &[
+    "Foo: Clone",
+    "Bar: Clone",
+    "Vec<Foo>: Clone",
+    "Vec<Vec<Foo>>: Clone",
+    "Vec<Bar>: Clone",
+    "exists<T> { Vec<T>: Clone }",
+];
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn solvers_are_send_and_sync() {
+    assert_send_sync::<SLGSolver<ChalkIr>>();
+    assert_send_sync::<SyncForest<ChalkIr>>();
+}
+
+/// Solves `GOALS` from several threads with `forest`, checking the
+/// solutions against those of a solver used from a single thread.
+fn solve_from_threads(forest: &SyncForest<ChalkIr>) {
+    let db = util::db(PROGRAM);
+    let program = db.checked_program().unwrap();
+    let goals: Vec<_> = GOALS.iter().map(|goal| util::goal(&db, goal)).collect();
+
+    let mut solver = util::slg();
+    let expected: Vec<_> = goals
+        .iter()
+        .map(|goal| solver.solve(&*program, goal))
+        .collect();
+
+    thread::scope(|scope| {
+        for offset in 0..4 {
+            let (program, goals, expected) = (&program, &goals, &expected);
+            scope.spawn(move || {
+                for i in 0..goals.len() {
+                    let i = (i + offset) % goals.len();
+                    assert_eq!(forest.solve(&**program, 0, &goals[i]), expected[i]);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn shared_between_threads() {
+    let forest = SyncForest::new(util::slg());
+    solve_from_threads(&forest);
+
+    // The threads built their tables in the same forest.
+    let stats = forest.stats();
+    assert!(stats.tables >= GOALS.len());
+    let solvers = forest.into_inner();
+    assert_eq!(solvers.len(), 1);
+    assert_eq!(solvers[0].stats(), stats);
+}
+
+#[test]
+fn sharded() {
+    let forest = SyncForest::with_shards((0..3).map(|_| util::slg()).collect());
+    solve_from_threads(&forest);
+
+    let stats = forest.stats();
+    assert!(stats.tables >= GOALS.len());
+    let solvers = forest.into_inner();
+    assert_eq!(solvers.len(), 3);
+    assert_eq!(
+        solvers
+            .iter()
+            .map(|solver| solver.stats().tables)
+            .sum::<usize>(),
+        stats.tables
+    );
+}
+
+#[test]
+fn solutions_are_remembered_per_program() {
+    let with_impl = util::db("struct Foo {} trait Clone {} impl Clone for Foo {}");
+    let without_impl = util::db("struct Foo {} trait Clone {}");
+    let goal = util::goal(&with_impl, "Foo: Clone");
+    let (with_impl, without_impl) = (
+        with_impl.checked_program().unwrap(),
+        without_impl.checked_program().unwrap(),
+    );
+
+    // The solution for one program is not reused for the other, and
+    // neither are the tables built for it.
+    let forest = SyncForest::new(util::slg());
+    assert!(forest.solve(&*with_impl, 0, &goal).is_some());
+    assert!(forest.solve(&*without_impl, 1, &goal).is_none());
+    assert!(forest.solve(&*with_impl, 2, &goal).is_some());
+}
+
+#[test]
+fn solutions_are_dropped_with_their_program() {
+    let goal_db = util::db("struct Foo {} trait Clone {}");
+    let goal = util::goal(&goal_db, "Foo: Clone");
+
+    // A program dropped and replaced by another, which may well live at
+    // the same address, gets a new id and none of the old solutions.
+    let forest = SyncForest::new(util::slg());
+    let programs = [
+        ("struct Foo {} trait Clone {} impl Clone for Foo {}", true),
+        ("struct Foo {} trait Clone {}", false),
+    ];
+    for (program_id, &(text, provable)) in programs.iter().enumerate() {
+        let db = util::db(text);
+        let program = db.checked_program().unwrap();
+        let solution = forest.solve(&*program, program_id as u64, &goal);
+        assert_eq!(solution.is_some(), provable);
+    }
+}
@@ -0,0 +1,73 @@
+//! Helpers for the integration tests which drive the solvers through the
+//! database API, for what `test!` can't check: solver configurations other
+//! than the default ones, and the APIs besides `Solver::solve`.
+
+use chalk_engine::solve::SLGSolver;
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_recursive::{Cache, RecursiveSolver};
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution, Solver};
+
+pub type UCanonicalGoal = UCanonical<InEnvironment<Goal<ChalkIr>>>;
+
+/// The database of the program `text`, with the default solver.
+pub fn db(text: &str) -> ChalkDatabase {
+    ChalkDatabase::with(text, SolverChoice::default())
+}
+
+/// The goal `text`, lowered in `db`, as the solvers take it.
+pub fn goal(db: &ChalkDatabase, text: &str) -> UCanonicalGoal {
+    db.parse_and_lower_goal(text)
+        .unwrap()
+        .into_peeled_goal(db.interner())
+}
+
+/// An SLG solver configured like `SolverChoice::slg_default`.
+pub fn slg() -> SLGSolver<ChalkIr> {
+    SLGSolver::new(10, None)
+}
+
+/// A recursive solver configured like `SolverChoice::recursive_default`.
+pub fn recursive() -> RecursiveSolver<ChalkIr> {
+    RecursiveSolver::new(100, 30, Some(Cache::new()))
+}
+
+/// The solvers `test!` checks goals with by default.
+pub fn solvers() -> Vec<Box<dyn Solver<ChalkIr>>> {
+    vec![Box::new(slg()), Box::new(recursive())]
+}
+
+/// `solution` as the REPL prints it.
+pub fn display(solution: Option<Solution<ChalkIr>>) -> String {
+    match solution {
+        Some(solution) => solution.display(ChalkIr).to_string(),
+        None => "No possible solution".to_string(),
+    }
+}
+
+/// Solves the goal `text` with `solver` in `db`, returning the solution as
+/// the REPL prints it.
+pub fn solve(db: &ChalkDatabase, solver: &mut dyn Solver<ChalkIr>, text: &str) -> String {
+    let goal = goal(db, text);
+    db.with_program(|_| display(solver.solve(db, &goal)))
+}
+
+/// The substitutions of all the answers `solver` gets for the goal `text`
+/// in `db`, in order.
+pub fn answers(db: &ChalkDatabase, solver: &mut dyn Solver<ChalkIr>, text: &str) -> Vec<String> {
+    let goal = goal(db, text);
+    db.with_program(|_| {
+        let mut answers = vec![];
+        solver.solve_multiple(db, &goal, &mut |answer, _| {
+            answers.push(format!(
+                "{:?}",
+                answer.as_ref().map(|subst| &subst.value.subst)
+            ));
+            true
+        });
+        answers
+    })
+}
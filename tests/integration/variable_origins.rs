@@ -0,0 +1,78 @@
+use super::util;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::Identifier;
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::clauses::program_clauses_for_goal;
+use chalk_solve::infer::{InferenceTable, VariableOrigin};
+
+#[test]
+fn impl_parameters_of_instantiated_clauses() {
+    let db = util::db(
+        "
+        trait Foo {}
+        struct A {}
+        struct Vec<T> {}
+        impl<T> Foo for Vec<T> {}
+        ",
+    );
+    db.with_program(|program| {
+        let interner = ChalkIr;
+        let vec_a = TyKind::Adt(
+            program.adt_ids[&Identifier::from("Vec")],
+            Substitution::from1(
+                interner,
+                TyKind::Adt(
+                    program.adt_ids[&Identifier::from("A")],
+                    Substitution::empty(interner),
+                )
+                .intern(interner),
+            ),
+        )
+        .intern(interner);
+        let goal: DomainGoal<ChalkIr> = TraitRef {
+            trait_id: program.trait_ids[&Identifier::from("Foo")],
+            substitution: Substitution::from1(interner, vec_a),
+        }
+        .cast(interner);
+        let goal = UCanonical::new(
+            Canonical {
+                value: InEnvironment::new(&Environment::new(interner), goal),
+                binders: CanonicalVarKinds::empty(interner),
+            },
+            1,
+        );
+        let impl_id = *program.impl_data.keys().next().unwrap();
+
+        // Only the rule of the impl has variables coming from it, and only
+        // if the table records origins.
+        let origins = |record: bool| {
+            program_clauses_for_goal(program, &goal)
+                .ok()
+                .unwrap()
+                .iter()
+                .flat_map(|clause| {
+                    let mut table = InferenceTable::new();
+                    if record {
+                        table.record_variable_origins();
+                    }
+                    table.instantiate_clause_existentially(program, clause);
+                    // The table is fresh, so its variables are those of the
+                    // clause's binders.
+                    (0..clause.data(interner).0.len(interner))
+                        .filter_map(|var| {
+                            table
+                                .variable_origin(InferenceVar::from(var as u32))
+                                .cloned()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(origins(false), vec![]);
+        assert_eq!(
+            origins(true),
+            vec![VariableOrigin::ImplParameter { impl_id, index: 0 }]
+        );
+    });
+}
@@ -0,0 +1,204 @@
+use chalk_integration::interner::ChalkIr;
+use chalk_ir::cast::Cast;
+use chalk_ir::visit::VisitExt;
+use chalk_ir::*;
+
+fn placeholder(ui: usize, idx: usize) -> PlaceholderIndex {
+    PlaceholderIndex {
+        ui: UniverseIndex { counter: ui },
+        idx,
+    }
+}
+
+#[test]
+fn first_placeholder_in_universe() {
+    let interner = ChalkIr;
+    let ty = |kind: TyKind<ChalkIr>| kind.intern(interner);
+    // `for<'a> (!1_0, ^0.0, ^1.0, '!2_1)`
+    let tuple = ty(TyKind::Tuple(
+        4,
+        Substitution::from_iter(
+            interner,
+            vec![
+                GenericArg::new(
+                    interner,
+                    GenericArgData::Ty(ty(TyKind::Placeholder(placeholder(1, 0)))),
+                ),
+                GenericArg::new(
+                    interner,
+                    GenericArgData::Ty(ty(TyKind::BoundVar(BoundVar::new(
+                        DebruijnIndex::INNERMOST,
+                        0,
+                    )))),
+                ),
+                GenericArg::new(
+                    interner,
+                    GenericArgData::Ty(ty(TyKind::BoundVar(BoundVar::new(DebruijnIndex::ONE, 0)))),
+                ),
+                GenericArg::new(
+                    interner,
+                    GenericArgData::Lifetime(
+                        LifetimeData::Placeholder(placeholder(2, 1)).intern(interner),
+                    ),
+                ),
+            ],
+        ),
+    ));
+    let binders = Binders::new(
+        VariableKinds::from1(interner, VariableKind::Lifetime),
+        tuple,
+    );
+
+    assert_eq!(
+        binders.first_placeholder_in(interner, UniverseIndex { counter: 2 }),
+        Some(placeholder(2, 1))
+    );
+    assert_eq!(
+        binders.first_placeholder_in(interner, UniverseIndex { counter: 3 }),
+        None
+    );
+    assert_eq!(
+        binders.first_free_var(interner),
+        Some(BoundVar::new(DebruijnIndex::INNERMOST, 0))
+    );
+    assert!(binders.has_free_vars(interner));
+}
+
+#[test]
+fn walk_in_pre_order() {
+    let interner = ChalkIr;
+    let ty = |kind: TyKind<ChalkIr>| kind.intern(interner);
+    // `&'!1_0 [(u32, !1_1)]`
+    let u32_ty = ty(TyKind::Scalar(Scalar::Uint(UintTy::U32)));
+    let placeholder_ty = ty(TyKind::Placeholder(placeholder(1, 1)));
+    let tuple = ty(TyKind::Tuple(
+        2,
+        Substitution::from_iter(interner, vec![u32_ty.clone(), placeholder_ty.clone()]),
+    ));
+    let slice = ty(TyKind::Slice(tuple.clone()));
+    let lifetime = LifetimeData::Placeholder(placeholder(1, 0)).intern(interner);
+    let reference = ty(TyKind::Ref(Mutability::Not, lifetime, slice.clone()));
+
+    let components: Vec<GenericArg<ChalkIr>> = reference.walk(interner).collect();
+    assert_eq!(
+        components,
+        vec![
+            reference.clone().cast(interner),
+            lifetime.cast(interner),
+            slice.cast(interner),
+            tuple.cast(interner),
+            u32_ty.cast(interner),
+            placeholder_ty.cast(interner),
+        ]
+    );
+
+    // Walking a value that is not a type only yields its components.
+    let goal: Goal<ChalkIr> = GoalData::EqGoal(EqGoal {
+        a: reference.clone().cast(interner),
+        b: ty(TyKind::Str).cast(interner),
+    })
+    .intern(interner);
+    assert_eq!(goal.walk(interner).count(), components.len() + 1);
+    assert_eq!(goal.walk(interner).next(), Some(reference.cast(interner)));
+}
+
+#[test]
+fn inconsistent_bound_vars() {
+    let interner = ChalkIr;
+    let ty = |kind: TyKind<ChalkIr>| kind.intern(interner);
+    let var = |debruijn: u32, index: usize| BoundVar::new(DebruijnIndex::new(debruijn), index);
+    let kinds = |kinds: Vec<VariableKind<ChalkIr>>| VariableKinds::from_iter(interner, kinds);
+    let ty_kind = || VariableKind::Ty(TyVariableKind::General);
+
+    // `(^0.0, &'^0.1 ^2.5)`: the last variable is bound further out.
+    let tuple = ty(TyKind::Tuple(
+        2,
+        Substitution::from_iter(
+            interner,
+            vec![
+                ty(TyKind::BoundVar(var(0, 0))),
+                ty(TyKind::Ref(
+                    Mutability::Not,
+                    LifetimeData::BoundVar(var(0, 1)).intern(interner),
+                    ty(TyKind::BoundVar(var(2, 5))),
+                )),
+            ],
+        ),
+    ));
+    assert_eq!(
+        tuple.inconsistent_bound_var(interner, &kinds(vec![ty_kind(), VariableKind::Lifetime])),
+        None
+    );
+    assert_eq!(
+        tuple.inconsistent_bound_var(interner, &kinds(vec![ty_kind()])),
+        Some(var(0, 1))
+    );
+    assert_eq!(
+        tuple.inconsistent_bound_var(
+            interner,
+            &kinds(vec![VariableKind::Lifetime, VariableKind::Lifetime])
+        ),
+        Some(var(0, 0))
+    );
+
+    // `for<T> (^0.0, ^1.0)`: only the variables of the binder outside of
+    // the value are checked.
+    let binders = Binders::new(
+        kinds(vec![ty_kind()]),
+        ty(TyKind::Tuple(
+            2,
+            Substitution::from_iter(
+                interner,
+                vec![
+                    ty(TyKind::BoundVar(var(0, 0))),
+                    ty(TyKind::BoundVar(var(1, 0))),
+                ],
+            ),
+        )),
+    );
+    assert_eq!(
+        binders.inconsistent_bound_var(interner, &kinds(vec![ty_kind()])),
+        None
+    );
+    assert_eq!(
+        binders.inconsistent_bound_var(interner, &kinds(vec![VariableKind::Lifetime])),
+        Some(var(0, 0))
+    );
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "is inconsistent with the binders")]
+fn debug_assert_bound_vars_consistent() {
+    let interner = ChalkIr;
+    let ty = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 1)).intern(interner);
+    ty.debug_assert_bound_vars_consistent(
+        interner,
+        &VariableKinds::from1(interner, VariableKind::Ty(TyVariableKind::General)),
+    );
+}
+
+/// With `validate-bound-vars`, a `for<'a> fn(&'^0.1 u32)` cannot be
+/// interned: its binder has a single lifetime.
+#[cfg(all(feature = "validate-bound-vars", debug_assertions))]
+#[test]
+#[should_panic(expected = "is inconsistent with the binders")]
+fn validate_bound_vars_of_fn_pointers() {
+    let interner = ChalkIr;
+    let reference = TyKind::Ref(
+        Mutability::Not,
+        LifetimeData::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 1)).intern(interner),
+        TyKind::Scalar(Scalar::Uint(UintTy::U32)).intern(interner),
+    )
+    .intern(interner);
+    TyKind::Function(FnPointer {
+        num_binders: 1,
+        sig: FnSig {
+            abi: chalk_integration::interner::ChalkFnAbi::Rust,
+            safety: Safety::Safe,
+            variadic: false,
+        },
+        substitution: FnSubst(Substitution::from1(interner, reference)),
+    })
+    .intern(interner);
+}
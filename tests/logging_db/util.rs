@@ -66,7 +66,7 @@ pub fn logging_db_output_sufficient(
                 match expected {
                     TestGoal::Aggregated(expected) => {
                         let result = solver.solve(&wrapped, &peeled_goal);
-                        assert_result_str(result, expected, db.interner());
+                        assert_result_str(result, expected, &db);
                     }
                     _ => panic!("only aggregated test goals supported for logger goals"),
                 }
@@ -108,7 +108,7 @@ pub fn logging_db_output_sufficient(
             match expected {
                 TestGoal::Aggregated(expected) => {
                     let result = solver.solve(&db, &peeled_goal);
-                    assert_result_str(result, expected, db.interner());
+                    assert_result_str(result, expected, &db);
                 }
                 _ => panic!("only aggregated test goals supported for logger goals"),
             }
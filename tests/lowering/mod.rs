@@ -195,6 +195,13 @@ fn atc_accounting() {
     value: for<lifetime, type> AssociatedTyValueBound {
         ty: Iter<'^0.0, ^0.1>
     },
+    span: Some(
+        Span {
+            source: 0,
+            lo: 165,
+            hi: 193,
+        },
+    ),
 }"#
             .replace(",\n", "\n"),
         );
@@ -682,6 +689,72 @@ fn type_outlives() {
     }
 }
 
+#[test]
+fn where_clause_shorthands() {
+    lowering_success! {
+        program {
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+            #[lang(fn)]
+            trait Fn<Args> where Self: FnOnce<Args> { }
+            trait Iterator {
+                type Item;
+                type Other;
+            }
+            trait Clone { }
+
+            struct Foo<'a, 'b, T, F> where
+                T: Iterator<Item = u32, Other = u8> + Clone + 'a + ?Sized,
+                F: Fn(T) -> u32,
+                'a: 'b + 'static
+            { }
+
+            trait Bar {
+                type Assoc: Iterator<Item = u32> + ?Sized;
+                type Callback: Fn(u8);
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo { }
+            struct Bar<T> where T: ?Foo { }
+        }
+        error_msg {
+            "parse error: `?Trait` bounds are only supported for `Sized`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Iterator<T> {
+                type Item;
+            }
+            struct Bar<T> where T: Iterator<Item = u32, u8> { }
+        }
+        error_msg {
+            "parse error: generic arguments must come before associated type bindings"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+            trait NotFn<Args> { }
+            struct Bar<F> where F: NotFn(u8) -> u8 { }
+        }
+        error_msg {
+            "no associated type `Output` defined in trait"
+        }
+    }
+}
+
 #[test]
 fn phantom_data() {
     lowering_success! {
@@ -692,6 +765,104 @@ fn phantom_data() {
     }
 }
 
+#[test]
+fn enum_discriminants() {
+    lowering_success! {
+        program {
+            enum Foo { A = 1, B, C(u32) = -1, D { x: u32 } }
+        }
+    }
+
+    lowering_error! {
+        program {
+            enum Foo { A = 1, B = 0, C }
+        }
+        error_msg {
+            "discriminant of variant `C` is already used by another variant"
+        }
+    }
+}
+
+#[test]
+fn modules_need_a_file() {
+    lowering_error! {
+        program {
+            mod foo;
+            struct Bar {}
+        }
+        error_msg {
+            "module `foo` cannot be loaded: modules are only supported in programs read from files"
+        }
+    }
+}
+
+#[test]
+fn unknown_features() {
+    lowering_error! {
+        program {
+            #![no_coherence]
+            struct Bar {}
+        }
+        error_msg {
+            "unknown feature `no_coherence`"
+        }
+    }
+}
+
+#[test]
+fn applied_non_types() {
+    lowering_error! {
+        program {
+            trait Foo {}
+            struct Bar { field: Foo<u32> }
+        }
+        error_msg {
+            "expected a struct, found `Foo`, which is not a struct"
+        }
+    }
+
+    lowering_error! {
+        program {
+            extern type Foo;
+            struct Bar { field: Foo<u32> }
+        }
+        error_msg {
+            "`Foo` takes 0 type parameters, not 1"
+        }
+    }
+}
+
+#[test]
+fn all_errors_are_reported() {
+    let db = ChalkDatabase::with(
+        "
+        trait Foo {}
+        struct Bar { field: Baz }
+        impl Foo for Bar {}
+        impl Qux for Bar {}
+        #![no_coherence]
+        ",
+        SolverChoice::default(),
+    );
+    let error = db.program_ir().unwrap_err();
+    let errors: Vec<_> = error
+        .lowering_errors()
+        .iter()
+        .map(|error| {
+            let span = error.span().unwrap();
+            (error.code(), error.error().to_string(), span.hi - span.lo)
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            ("L0001", "invalid parameter name `Baz`".to_string(), 3),
+            ("L0002", "invalid trait name `Qux`".to_string(), 3),
+            ("L0012", "unknown feature `no_coherence`".to_string(), 12),
+        ]
+    );
+}
+
 #[test]
 fn extern_functions() {
     lowering_success! {
@@ -763,6 +934,12 @@ fn struct_repr() {
             #[repr(C)]
             #[repr(packed)]
             struct FooBar {}
+
+            #[repr(transparent)]
+            struct Transparent { inner: u32 }
+
+            #[repr(simd)]
+            struct Simd { x: f32, y: f32 }
         }
     }
 }
@@ -813,3 +990,43 @@ fn algebraic_data_types() {
         }
     }
 }
+
+#[test]
+fn inherent_assoc_types() {
+    lowering_success! {
+        program {
+            struct Foo<T> { }
+            struct Baz { }
+
+            impl<T> Foo<T> {
+                type Bar = Baz;
+            }
+
+            struct Wrapper<T> {
+                field: <Foo<T>>::Bar
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            struct Wrapper {
+                field: <Foo>::Bar
+            }
+        }
+        error_msg {
+            "no associated type `Bar` defined in inherent impls"
+        }
+    }
+
+    lowering_error! {
+        program {
+            struct Foo { }
+            #[upstream] impl Foo { }
+        }
+        error_msg {
+            "parse error: inherent impls cannot be upstream or negative"
+        }
+    }
+}
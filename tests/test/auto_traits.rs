@@ -196,6 +196,14 @@ fn enum_auto_trait() {
                     z: Foo,
                 }
             }
+
+            enum D {
+                X = 1,
+                Y(Foo) = 5,
+                Z {
+                    z: Bar,
+                } = 3,
+            }
         }
 
         goal {
@@ -215,6 +223,12 @@ fn enum_auto_trait() {
         } yields {
             expect![["No possible solution"]]
         }
+
+        goal {
+            D: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
     }
 }
 
@@ -368,3 +382,136 @@ fn phantom_auto_trait() {
         }
     }
 }
+
+/// Tests that the auto trait impls of recursive types, whose constituent
+/// types lead back to the type itself, are found coinductively instead of
+/// overflowing.
+#[test]
+fn recursive_types_auto_trait() {
+    test! {
+        program {
+            #[auto] trait Send {}
+
+            struct Box<T> {}
+            impl<T> Send for Box<T> where T: Send {}
+            enum Option<T> { None, Some(T) }
+            struct NotSend {}
+            impl !Send for NotSend {}
+
+            struct Direct { me: Direct }
+            struct List { next: Box<List> }
+            struct Pair { pair: (u32, Option<Box<Pair>>) }
+            struct Tree { children: Option<Box<Tree>>, sibling: Option<Box<Tree>> }
+            struct Even { odd: Box<Odd> }
+            struct Odd { even: Option<Box<Even>> }
+            struct GenericList<T> { data: T, next: Option<Box<GenericList<T>>> }
+            struct BadList { next: Box<BadList>, bad: NotSend }
+            struct BadEven { odd: Box<BadOdd> }
+            struct BadOdd { even: Box<BadEven>, bad: NotSend }
+            struct Grow<T> { data: T, next: Box<Grow<Box<T>>> }
+        }
+
+        goal {
+            Direct: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            List: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            Pair: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            Tree: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            Even: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            GenericList<Odd>: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            GenericList<NotSend>: Send
+        } yields {
+            expect![[r#"No possible solution"#]]
+        }
+
+        goal {
+            forall<T> { if (T: Send) { GenericList<T>: Send } }
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            BadList: Send
+        } yields {
+            expect![[r#"No possible solution"#]]
+        }
+
+        goal {
+            BadEven: Send
+        } yields {
+            expect![[r#"No possible solution"#]]
+        }
+
+        // Polymorphic recursion does not lead back to the same goal, but to
+        // ever bigger ones, which overflow.
+        goal {
+            Grow<List>: Send
+        } yields {
+            expect![[r#"Ambiguous; no inference guidance"#]]
+        }
+    }
+}
+
+/// Tests cycles of auto trait goals which go through opaque types and
+/// through impls: only cycles made of auto trait goals alone hold.
+#[test]
+fn recursive_auto_trait_through_opaque_types_and_impls() {
+    test! {
+        program {
+            #[auto] trait Send {}
+            trait Foo {}
+
+            struct Box<T> {}
+            impl<T> Send for Box<T> where T: Send {}
+            struct Rc<T> {}
+            impl<T> Send for Rc<T> where T: Foo {}
+            impl<T> Foo for T where T: Send {}
+
+            struct Wrapper<T> { t: Box<T> }
+            opaque type Recursive: Foo = Wrapper<Recursive>;
+
+            struct Shared { next: Rc<Shared> }
+        }
+
+        goal {
+            Recursive: Send
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+
+        goal {
+            Shared: Send
+        } yields {
+            expect![[r#"No possible solution"#]]
+        }
+    }
+}
@@ -280,3 +280,142 @@ fn closure_implements_fn_traits() {
         }
     }
 }
+
+#[test]
+fn fn_trait_bound_sugar() {
+    test! {
+        program {
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+
+            #[lang(fn_mut)]
+            trait FnMut<Args> where Self: FnOnce<Args> { }
+
+            #[lang(fn)]
+            trait Fn<Args> where Self: FnMut<Args> { }
+
+            trait Callback { }
+            struct Wrapper<F> { }
+            impl<F> Callback for Wrapper<F> where F: Fn(u8, f32) -> u32 + ?Sized { }
+
+            trait Action { }
+            impl<F> Action for Wrapper<F> where F: FnMut() { }
+
+            closure returns_u32(&self, a: u8, b: f32) -> u32 { }
+            closure returns_u8(&self, a: u8, b: f32) -> u8 { }
+            closure no_args(&mut self,) { }
+        }
+
+        goal {
+            Wrapper<returns_u32>: Callback
+        } yields {
+            expect![["Unique"]]
+        }
+        goal {
+            Wrapper<returns_u8>: Callback
+        } yields {
+            expect![["No possible solution"]]
+        }
+        goal {
+            Wrapper<no_args>: Action
+        } yields {
+            expect![["Unique"]]
+        }
+        goal {
+            Wrapper<returns_u32>: Action
+        } yields {
+            expect![["No possible solution"]]
+        }
+        goal {
+            Normalize(<returns_u32 as Fn<(u8, f32)>>::Output -> u32)
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
+
+#[test]
+fn closure_auto_traits_look_through_upvars() {
+    test! {
+        program {
+            #[auto] trait Send { }
+
+            struct NotSend {}
+            impl !Send for NotSend {}
+            struct Foo {}
+
+            generator not_send_future<>[resume = (), yield = ()] {
+                upvars []
+                witnesses [NotSend]
+            }
+
+            generator send_future<>[resume = (), yield = ()] {
+                upvars [Foo]
+                witnesses [u8]
+            }
+
+            closure no_upvars(self,) {}
+            closure send_upvars(self,) { Foo; u8 }
+            closure not_send_upvar(self,) { Foo; NotSend }
+            closure generic<T>(self,) { T; Foo }
+            closure captures_not_send_future(self,) { not_send_future }
+            closure captures_send_future(self,) { send_future }
+        }
+
+        goal {
+            no_upvars: Send
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            send_upvars: Send
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            not_send_upvar: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            generic<Foo>: Send
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            generic<NotSend>: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> { generic<T>: Send }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> { if (T: Send) { generic<T>: Send } }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            captures_not_send_future: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            captures_send_future: Send
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
@@ -587,3 +587,32 @@ fn fundamental_type_multiple_parameters() {
         }
     }
 }
+
+#[test]
+fn overlapping_impls_without_specialization() {
+    lowering_error! {
+        program {
+            #![no_specialization]
+
+            trait Foo { }
+            struct Bar { }
+            impl Foo for Bar { }
+            impl<T> Foo for T { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`"
+        }
+    }
+
+    lowering_success! {
+        program {
+            #![no_specialization]
+
+            trait Foo { }
+            struct Bar { }
+            struct Baz { }
+            impl Foo for Bar { }
+            impl Foo for Baz { }
+        }
+    }
+}
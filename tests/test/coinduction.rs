@@ -168,7 +168,7 @@ fn coinductive_trivial_variant3() {
         goal {
             exists<T, U> { T: C1<U> }
         } yields {
-            expect![["Unique; for<?U0,?U0> { substitution [?0 := ^0.0, ?1 := ^0.1] }"]]
+            expect![["Unique; for<_0_0, _0_1> { substitution [?0 := _0_0, ?1 := _0_1] }"]]
         }
     }
 }
@@ -638,3 +638,44 @@ fn coinductive_multicycle4() {
         }
     }
 }
+
+#[test]
+fn all_traits_coinductive() {
+    test! {
+        program {
+            #![all_traits_coinductive]
+
+            trait Foo { }
+            trait Bar { }
+
+            struct Baz { }
+
+            impl Foo for Baz where Baz: Bar { }
+            impl Bar for Baz where Baz: Foo { }
+        }
+
+        goal {
+            Baz: Foo
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+
+    test! {
+        program {
+            trait Foo { }
+            trait Bar { }
+
+            struct Baz { }
+
+            impl Foo for Baz where Baz: Bar { }
+            impl Bar for Baz where Baz: Foo { }
+        }
+
+        goal {
+            Baz: Foo
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
@@ -92,7 +92,7 @@ fn generic_impl() {
                 S<N>: Trait
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0] }"]]
+            expect![["Unique; for<const _0_0> { substitution [?0 := _0_0] }"]]
         }
 
         goal {
@@ -147,3 +147,93 @@ fn placeholders_eq() {
         }
     }
 }
+
+#[test]
+fn typed_consts() {
+    test! {
+        program {
+            struct S<const N: u32> {}
+
+            trait Trait {}
+
+            impl Trait for S<3u32> {}
+        }
+
+        goal {
+            exists<const N: u32> {
+                S<N>: Trait
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := 3u32]"]]
+        }
+
+        goal {
+            S<3u32>: Trait
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<const N: u32> {
+                N = 3u32
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := 3u32]"]]
+        }
+
+        goal {
+            forall<const N: u32> {
+                exists<const M: u32> {
+                    N = M
+                }
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := !1_0]"]]
+        }
+    }
+}
+
+#[test]
+fn mismatched_const_types() {
+    test! {
+        program {
+            struct S<const N: u32> {}
+
+            trait Trait {}
+
+            impl Trait for S<3u32> {}
+        }
+
+        goal {
+            S<3>: Trait
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<const N> {
+                S<N>: Trait
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<const N: u32> {
+                N = 3
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<const N: u32> {
+                exists<const M: u64> {
+                    N = M
+                }
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
@@ -164,3 +164,41 @@ fn discriminant_kind_assoc() {
         }
     }
 }
+
+#[test]
+fn discriminant_kind_variants() {
+    test! {
+        program {
+            #[lang(discriminant_kind)]
+            trait DiscriminantKind {
+                type Discriminant;
+            }
+
+            struct A { }
+
+            enum B { X = 1, Y(u32), Z { z: u32 } = 7 }
+
+            #[repr(i8)]
+            enum C { X = -1, Y, Z(u32) = 3 }
+        }
+
+        // Structs have no variants to tell apart
+        goal {
+            Normalize(<A as DiscriminantKind>::Discriminant -> u8)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Normalize(<B as DiscriminantKind>::Discriminant -> isize)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Normalize(<C as DiscriminantKind>::Discriminant -> i8)
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
@@ -31,9 +31,13 @@ fn dispatch_from_dyn_wf() {
             #[one_zst]
             struct Zst<T> {}
 
+            #[repr(transparent)]
+            struct Wrapper<T> { inner: Zst<T> }
+
             struct Foo<T> {
                 f: *mut T,
                 f2: Zst<u8>,
+                f3: Wrapper<u8>,
             }
 
             // References and pointers
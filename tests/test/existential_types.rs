@@ -306,7 +306,7 @@ fn dyn_higher_ranked_type_arguments() {
             }
         } yields {
             // Note that this requires 'a == 's, so it would be resolveable later on.
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
     }
 }
@@ -333,7 +333,7 @@ fn dyn_binders_reverse() {
                 >
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!5_0: '!5_1 }, InEnvironment { environment: Env([]), goal: '!5_1: '!5_0 }]"]]
+            expect![["Unique; lifetime constraints ['!5_0: '!5_1, '!5_1: '!5_0]"]]
         }
 
         // Note: these constraints are ultimately unresolveable (we
@@ -345,7 +345,7 @@ fn dyn_binders_reverse() {
                 >
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!3_0: '!3_1 }, InEnvironment { environment: Env([]), goal: '!3_1: '!3_0 }]"]]
+            expect![["Unique; lifetime constraints ['!3_0: '!3_1, '!3_1: '!3_0]"]]
         }
 
         // Note: ordering of parameters is reversed here, but that's no problem
@@ -379,7 +379,128 @@ fn dyn_lifetime_bound() {
                 }
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
+        }
+    }
+}
+
+#[test]
+fn dyn_bounds_in_any_order() {
+    test! {
+        program {
+            trait Clone { }
+            trait Foo<T> { }
+            trait Iterator { type Item; }
+            #[auto] trait Send { }
+            struct Bar { }
+
+            trait Eq<A> { }
+
+            impl<A> Eq<A> for A { }
+        }
+
+        goal {
+            forall<'s> {
+                dyn Clone + Send + 's: Eq<dyn Send + Clone + 's>
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<'s> {
+                dyn Clone + Send + Clone + 's: Eq<dyn Send + Clone + 's>
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<'s> {
+                dyn Iterator<Item = Bar> + Send + 's: Eq<dyn Send + Iterator<Item = Bar> + 's>
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T> {
+                forall<'s> {
+                    dyn Foo<T> + Send + 's: Eq<dyn Send + Foo<Bar> + 's>
+                }
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := Bar]"]]
+        }
+
+        goal {
+            forall<'s> {
+                dyn Clone + Send + 's: Eq<dyn Clone + 's>
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+/// Bounds of the same trait are paired in every way until one pairing
+/// relates, whatever their order.
+#[test]
+fn dyn_bounds_of_the_same_trait_in_any_order() {
+    test! {
+        program {
+            trait Foo<T> { }
+            struct Vec<T> { }
+
+            trait Eq<A> { }
+
+            impl<A> Eq<A> for A { }
+        }
+
+        goal {
+            exists<X, Y> {
+                forall<'s> {
+                    dyn Foo<X> + Foo<Vec<Y>> + 's: Eq<dyn Foo<Vec<u8>> + Foo<u16> + 's>
+                }
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := u16, ?1 := u8]"]]
+        }
+
+        goal {
+            exists<X, Y> {
+                forall<'s> {
+                    dyn Foo<X> + Foo<Vec<Y>> + 's: Eq<dyn Foo<u16> + Foo<Vec<u8>> + 's>
+                }
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := u16, ?1 := u8]"]]
+        }
+    }
+}
+
+/// Bounds of the same trait which can be paired in several ways, binding
+/// different inference variables, are ambiguous rather than paired in
+/// whichever way is tried first.
+#[test]
+fn dyn_bounds_paired_in_several_ways() {
+    test! {
+        program {
+            trait Foo<T> { }
+
+            trait Eq<A> { }
+
+            impl<A> Eq<A> for A { }
+        }
+
+        goal {
+            exists<X, Y> {
+                forall<'s> {
+                    dyn Foo<X> + Foo<Y> + 's: Eq<dyn Foo<u8> + Foo<u16> + 's>
+                }
+            }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
         }
     }
 }
@@ -398,7 +519,7 @@ fn dyn_associated_type_binding() {
                 }
             }
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; substitution [?0 := Int(I32)]"]]
+            expect![["Unique; substitution [?0 := i32]"]]
         } yields[SolverChoice::slg_default()] {
             // #234
             expect![["Ambiguous; no inference guidance"]]
@@ -418,7 +539,7 @@ fn dyn_well_formed() {
                 WellFormed(dyn MyTrait + 's)
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0] }"]]
         }
     }
 }
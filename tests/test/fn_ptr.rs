@@ -0,0 +1,258 @@
+use super::*;
+
+// Test that user-provided impls of `FnPtr` are prohibited
+#[test]
+fn no_fn_ptr_impls() {
+    lowering_error! {
+        program {
+            #[lang(fn_ptr_trait)]
+            trait FnPtr { }
+
+            struct Foo { }
+
+            impl FnPtr for Foo { }
+        } error_msg {
+            "trait impl for `FnPtr` does not meet well-formedness requirements"
+        }
+    }
+}
+
+#[test]
+fn fn_ptrs_implement_fn_ptr() {
+    test! {
+        program {
+            #[lang(fn_ptr_trait)]
+            trait FnPtr { }
+
+            fn foo(x: u8) -> u32;
+            closure bar(self, x: u8) -> u32 { }
+        }
+
+        goal {
+            fn(u8) -> u32: FnPtr
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            for<'a> unsafe extern "C" fn(&'a u8): FnPtr
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            foo: FnPtr
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            bar: FnPtr
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            u32: FnPtr
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<T> { T: FnPtr }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
+#[test]
+fn fn_def_coerces_to_fn_ptr() {
+    test! {
+        program {
+            fn foo(x: u8) -> u32;
+            unsafe fn bar(x: u8);
+            extern "C" fn baz();
+            fn qux<T>(x: T) -> T;
+            fn quux<'a>(x: &'a u8) -> &'a u8;
+        }
+
+        goal {
+            FnPtrCoercion(foo, fn(u8) -> u32)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, fn(u32) -> u32)
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, fn() -> u32)
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<T> { FnPtrCoercion(foo, T) }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+
+        goal {
+            FnPtrCoercion(bar, unsafe fn(u8))
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(bar, fn(u8))
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, unsafe fn(u8) -> u32)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, unsafe fn(u32) -> u32)
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            FnPtrCoercion(baz, extern "C" fn())
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(baz, fn())
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            FnPtrCoercion(qux<u8>, fn(u8) -> u8)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T> { FnPtrCoercion(qux<T>, fn(u8) -> u8) }
+        } yields {
+            expect![["Unique; substitution [?0 := u8]"]]
+        }
+
+        goal {
+            forall<'a> { FnPtrCoercion(quux<'a>, fn(&'a u8) -> &'a u8) }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T> { FnPtrCoercion(T, fn()) }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+
+        goal {
+            FnPtrCoercion(fn(u8) -> u32, fn(u8) -> u32)
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+#[test]
+fn non_capturing_closure_coerces_to_fn_ptr() {
+    test! {
+        program {
+            closure foo(self, x: u8) -> u32 { }
+            closure bar(&self, x: u8) -> u32 { u8 }
+            closure baz(&mut self,) { }
+        }
+
+        goal {
+            FnPtrCoercion(foo, fn(u8) -> u32)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, unsafe fn(u8) -> u32)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, extern "C" fn(u8) -> u32)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            FnPtrCoercion(foo, fn() -> u32)
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        // `bar` captures a `u8`
+        goal {
+            FnPtrCoercion(bar, fn(u8) -> u32)
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            FnPtrCoercion(baz, fn())
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T> { FnPtrCoercion(baz, T) }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
+// Function items passed where a function pointer implementing `FnOnce` is
+// expected: the coerced pointer can then be called.
+#[test]
+fn coerced_fn_ptr_is_callable() {
+    test! {
+        program {
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+
+            fn foo(x: u8) -> u32;
+            unsafe fn bar(x: u8) -> u32;
+        }
+
+        // `foo` also coerces to `unsafe fn(u8) -> u32`, which the recursive
+        // solver does not rule out.
+        goal {
+            exists<F> { FnPtrCoercion(foo, F), F: FnOnce<(u8,)> }
+        } yields[SolverChoice::slg_default()] {
+            expect![["Unique; substitution [?0 := fn(u8) -> u32]"]]
+        } yields[SolverChoice::recursive_default()] {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+
+        // `FnOnce` is not implemented for `unsafe` function pointers.
+        goal {
+            exists<F> { FnPtrCoercion(bar, F), F: FnOnce<(u8,)> }
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
@@ -191,7 +191,7 @@ fn function_implement_fn_traits() {
                 for<'c> fn(&'c u8, &'c i32): FnOnce<(&'a u8, &'b i32)>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
 
         // Tests the opposite case as the previous test: a 'less strict' function
@@ -105,7 +105,7 @@ fn generator_test() {
                 }
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["Unique; lifetime constraints ['!2_0: '!2_1, '!2_1: '!2_0]"]]
         }
 
         goal {
@@ -133,3 +133,279 @@ fn generator_test() {
         }
     }
 }
+
+#[test]
+fn future_is_not_send() {
+    test! {
+        program {
+            #[auto] trait Send { }
+            #[auto] trait Sync { }
+
+            struct Rc<T> {}
+            impl<T> !Send for Rc<T> {}
+            struct Cell<T> { value: T }
+            impl<T> !Sync for Cell<T> {}
+            impl<'a, T> Send for &'a T where T: Sync {}
+
+            // Holds an `Rc` across an await point.
+            generator holds_rc<T>[resume = (), yield = ()] {
+                upvars [T]
+                witnesses [Rc<T>]
+            }
+
+            // Holds a reference to a `Cell` across an await point.
+            generator holds_cell_ref<>[resume = (), yield = ()] {
+                upvars []
+                witnesses exists<'a> [&'a Cell<u8>]
+            }
+
+            // Only holds `u8`s across await points.
+            generator holds_u8<>[resume = (), yield = ()] {
+                upvars [u8]
+                witnesses [u8]
+            }
+
+            // Awaits the other futures.
+            generator awaits_rc<>[resume = (), yield = ()] {
+                upvars []
+                witnesses [holds_rc<u8>]
+            }
+
+            generator awaits_u8<>[resume = (), yield = ()] {
+                upvars []
+                witnesses [holds_u8]
+            }
+
+            // `async move { .. }` blocks passed to a `spawn` function.
+            closure spawn_rc(self,) { awaits_rc }
+            closure spawn_u8(self,) { awaits_u8 }
+        }
+
+        goal {
+            holds_rc<u8>: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            holds_cell_ref: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            holds_u8: Send
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            awaits_rc: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            awaits_u8: Send
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            spawn_rc: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            spawn_u8: Send
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
+
+#[test]
+fn generator_copy_clone() {
+    test! {
+        program {
+            #[lang(copy)]
+            trait Copy { }
+
+            #[lang(clone)]
+            trait Clone { }
+
+            struct NotCopy {}
+            struct CopyRef<'a> {}
+            impl<'a> Copy for CopyRef<'a> {}
+            impl<'a> Clone for CopyRef<'a> {}
+            struct Plain {}
+            impl Copy for Plain {}
+            impl Clone for Plain {}
+
+            generator empty_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+
+            generator upvars_gen<T>[resume = (), yield = ()] {
+                upvars [T; Plain]
+                witnesses []
+            }
+
+            generator witness_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses exists<'a> [CopyRef<'a>; Plain]
+            }
+
+            generator not_copy_witness<>[resume = (), yield = ()] {
+                upvars []
+                witnesses [NotCopy]
+            }
+
+            generator static static_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+        }
+
+        goal {
+            empty_gen: Copy
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            empty_gen: Clone
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            upvars_gen<Plain>: Copy
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            upvars_gen<NotCopy>: Copy
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> {
+                if (T: Copy) {
+                    upvars_gen<T>: Copy
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            witness_gen: Copy
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            not_copy_witness: Copy
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            not_copy_witness: Clone
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            static_gen: Copy
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+#[test]
+fn generator_auto_trait_opt_out() {
+    test! {
+        program {
+            #[auto] #[lang(unpin)] trait Unpin { }
+            #[auto] trait Send { }
+
+            generator movable_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+
+            generator pinned_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+            impl !Unpin for pinned_gen { }
+
+            generator static unpinned_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+            impl Unpin for unpinned_gen { }
+
+            generator local_gen<T>[resume = (), yield = ()] {
+                upvars [T]
+                witnesses []
+            }
+            impl<T> !Send for local_gen<T> { }
+        }
+
+        goal {
+            movable_gen: Unpin
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // The negative impl takes precedence over the builtin impl for
+        // movable generators.
+        goal {
+            pinned_gen: Unpin
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            not { pinned_gen: Unpin }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            pinned_gen: Send
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // A positive impl takes precedence over immovable generators never
+        // being `Unpin`.
+        goal {
+            unpinned_gen: Unpin
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // Negative impls also take precedence over the structural impls of
+        // other auto traits.
+        goal {
+            local_gen<u32>: Send
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            local_gen<u32>: Unpin
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
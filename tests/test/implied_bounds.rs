@@ -157,3 +157,37 @@ fn higher_ranked_implied_bounds() {
         }
     }
 }
+
+#[test]
+fn without_implied_bounds() {
+    test! {
+        program {
+            #![no_implied_bounds]
+
+            trait Eq { }
+            trait Ord where Self: Eq { }
+            struct Set<T> where T: Eq { }
+        }
+
+        goal {
+            forall<T> {
+                if (FromEnv(Set<T>)) {
+                    T: Eq
+                }
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        // The supertraits of traits are still elaborated.
+        goal {
+            forall<T> {
+                if (T: Ord) {
+                    T: Eq
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
@@ -350,7 +350,7 @@ fn definite_guidance() {
                 T: Debug
             }
         } yields {
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := Foo<^0.0>] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := Foo<_0_0>] }"]]
         }
     }
 }
@@ -654,7 +654,7 @@ fn unify_types_in_ambiguous_impl() {
         goal {
             exists<T,U> { A<T>: Trait<U> }
         } yields {
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := ^0.0, ?1 := ^0.0] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := _0_0, ?1 := _0_0] }"]]
         }
     }
 }
@@ -673,7 +673,7 @@ fn unify_types_in_impl() {
         goal {
             exists<T,U> { A<T>: Trait<U> }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0, ?1 := _0_0] }"]]
         }
     }
 }
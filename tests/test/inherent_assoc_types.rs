@@ -0,0 +1,155 @@
+use super::*;
+
+#[test]
+fn normalize_inherent_assoc_ty() {
+    test! {
+        program {
+            struct Foo { }
+            struct Baz { }
+
+            impl Foo {
+                type Bar = Baz;
+            }
+        }
+
+        goal {
+            Normalize(<Foo>::Bar -> Baz)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T> {
+                Normalize(<Foo>::Bar -> T)
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := Baz]"]]
+        }
+
+        goal {
+            <Foo>::Bar = Baz
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            <Foo>::Bar = Foo
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+#[test]
+fn generic_inherent_assoc_ty() {
+    test! {
+        program {
+            struct Foo<T> { }
+            struct Vec<T> { }
+            struct Baz { }
+            trait Clone { }
+            impl Clone for Baz { }
+
+            impl<T> Foo<T> where T: Clone {
+                type Bar = Vec<T>;
+            }
+        }
+
+        goal {
+            exists<U> {
+                <Foo<Baz>>::Bar = U
+            }
+        } yields {
+            expect![["Unique; substitution [?0 := Vec<Baz>]"]]
+        }
+
+        goal {
+            exists<U> {
+                <Foo<Foo<Baz>>>::Bar = U
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> {
+                if (T: Clone) {
+                    <Foo<T>>::Bar = Vec<T>
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
+
+#[test]
+fn inherent_assoc_ty_in_several_impls() {
+    test! {
+        program {
+            struct Foo<T> { }
+            struct A { }
+            struct B { }
+
+            impl Foo<A> {
+                type Bar = B;
+            }
+
+            impl Foo<B> {
+                type Bar = A;
+            }
+        }
+
+        goal {
+            <Foo<A>>::Bar = B
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            <Foo<B>>::Bar = A
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T, U> {
+                Normalize(<Foo<T>>::Bar -> U)
+            }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
+#[test]
+fn inherent_assoc_ty_in_fields() {
+    test! {
+        program {
+            struct Foo { }
+            struct Baz { }
+            trait Copy { }
+            impl Copy for Baz { }
+
+            impl Foo {
+                type Bar = Baz;
+            }
+
+            struct Wrapper {
+                field: <Foo>::Bar
+            }
+        }
+
+        goal {
+            WellFormed(Wrapper)
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            <Foo>::Bar: Copy
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
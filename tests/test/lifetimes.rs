@@ -50,7 +50,7 @@ fn static_outlives() {
                 Bar: Foo<'a>
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: 'static }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints ['_0_0: 'static] }"]]
         }
 
         goal {
@@ -58,7 +58,7 @@ fn static_outlives() {
                 Bar: Foo<'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: 'static }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: 'static]"]]
         }
     }
 }
@@ -78,7 +78,7 @@ fn empty_outlives() {
                 Bar: Foo<'a>
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '<empty> }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints ['_0_0: '<empty>] }"]]
         }
 
         goal {
@@ -86,7 +86,7 @@ fn empty_outlives() {
                 Bar: Foo<'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '<empty> }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '<empty>]"]]
         }
     }
 }
@@ -106,7 +106,7 @@ fn erased_outlives() {
                 Bar: Foo<'a>
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '<erased> }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints ['_0_0: '_] }"]]
         }
 
         goal {
@@ -114,7 +114,7 @@ fn erased_outlives() {
                 Bar: Foo<'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '<erased> }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '_]"]]
         }
     }
 }
@@ -143,7 +143,7 @@ fn static_impls() {
         goal {
             exists<'a> { &'a Foo: Bar }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0] }"]]
         }
     }
 }
@@ -181,3 +181,36 @@ fn empty_impls() {
         }
     }
 }
+
+#[test]
+fn outlives_bounds() {
+    test! {
+        program {
+            trait Foo {}
+            struct Bar<'a, T> {}
+            impl<'a, T> Foo for Bar<'a, T> where T: 'a + 'static {}
+
+            trait Container {
+                type Item<'a>: 'a;
+            }
+            struct Baz {}
+            impl Container for Baz {
+                type Item<'a> = &'a Baz;
+            }
+        }
+
+        goal {
+            forall<'a, T> {
+                Bar<'a, T>: Foo
+            }
+        } yields {
+            expect![["Unique; lifetime constraints [!1_1: '!1_0, !1_1: 'static]"]]
+        }
+
+        goal {
+            WellFormed(Baz: Container)
+        } yields {
+            expect![[r#"Unique"#]]
+        }
+    }
+}
@@ -36,7 +36,7 @@ fn futures_ambiguity() {
         goal {
             forall<T> { if (T: FutureResult) { exists<I, E> { T: Future<Output = Result<I, E>> } } }
         } yields {
-            expect![["Unique; substitution [?0 := (FutureResult::Item)<!1_0>, ?1 := (FutureResult::Error)<!1_0>]"]]
+            expect![["Unique; substitution [?0 := <!1_0 as FutureResult>::Item, ?1 := <!1_0 as FutureResult>::Error]"]]
         }
     }
 }
@@ -157,9 +157,92 @@ fn only_draw_so_many_blow_up() {
         goal {
             exists<T> { T: Foo }
         } yields[SolverChoice::slg(10, Some(2))] {
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := Vec<^0.0>] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := Vec<_0_0>] }"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := Vec<^0.0>] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := Vec<_0_0>] }"]]
+        }
+    }
+}
+
+#[test]
+fn guidance_per_parameter() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct A { }
+            struct B { }
+            struct C { }
+
+            trait Foo<U> { }
+            impl Foo<A> for Vec<A> { }
+            impl Foo<B> for Vec<B> { }
+
+            trait Bar { }
+            impl Bar for (Vec<A>, &'static B) { }
+            impl Bar for (Vec<B>, &'static C) { }
+        }
+
+        goal {
+            exists<T, U> { T: Foo<U> }
+        } yields {
+            expect![["Ambiguous; definite substitution for<_0_0, _0_1> { [?0 := Vec<_0_0>, ?1 := _0_1] }"]]
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields {
+            expect![["Ambiguous; definite substitution for<_0_0, _0_1> { [?0 := (Vec<_0_0>, &'static _0_1)] }"]]
+        }
+    }
+}
+
+#[test]
+fn recursive_solver_multiple_answers() {
+    test! {
+        program {
+            struct Vec<T> { }
+            struct A { }
+            struct B { }
+
+            trait Foo { }
+            impl Foo for A { }
+            impl Foo for B { }
+            impl<T> Foo for Vec<T> where T: Foo { }
+
+            trait Bar { }
+            impl Bar for A { }
+
+            #[non_enumerable]
+            trait NonEnumerable { }
+            impl NonEnumerable for A { }
+        }
+
+        goal {
+            exists<T> { T: Foo }
+        } yields_all[SolverChoice::recursive_default()] {
+            expect![["substitution [?0 := A]"]],
+            expect![["substitution [?0 := B]"]],
+            expect![["Ambiguous(for<_0_0> { substitution [?0 := Vec<_0_0>] })"]]
+        }
+
+        goal {
+            exists<T> { T: Foo, T: Bar }
+        } yields_all[SolverChoice::recursive_default()] {
+            expect![["substitution [?0 := A]"]]
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields_first[SolverChoice::recursive_default()] {
+            expect![["substitution [?0 := A]"]]
+        }
+
+        // An ambiguous solution without guidance is an answer which says
+        // nothing about the variables of the goal.
+        goal {
+            exists<T> { T: NonEnumerable }
+        } yields_all[SolverChoice::recursive_default()] {
+            expect![["Ambiguous(for<_0_0> { substitution [?0 := _0_0] })"]]
         }
     }
 }
@@ -180,7 +263,7 @@ fn subgoal_cycle_uninhabited() {
         goal {
             exists<T> { T: Foo }
         } yields_first[SolverChoice::slg(2, None)] {
-            expect![["Ambiguous(for<?U0> { substitution [?0 := Box<^0.0>] })"]]
+            expect![["Ambiguous(for<_0_0> { substitution [?0 := Box<_0_0>] })"]]
         }
 
         // Unsurprisingly, applying negation also flounders.
@@ -217,7 +300,7 @@ fn subgoal_cycle_uninhabited() {
             forall<U> { if (U: Foo) { exists<T> { T: Foo } } }
         } yields_first[SolverChoice::slg(2, None)] {
             expect![["substitution [?0 := !1_0]"]],
-            expect![["Ambiguous(for<?U1> { substitution [?0 := Box<^0.0>] })"]]
+            expect![["Ambiguous(for<_0_0> { substitution [?0 := Box<_0_0>] })"]]
         }
     }
 }
@@ -240,7 +323,7 @@ fn subgoal_cycle_inhabited() {
             exists<T> { T: Foo }
         } yields_first[SolverChoice::slg(3, None)] {
             expect![["substitution [?0 := Alice]"]],
-            expect![["Ambiguous(for<?U0> { substitution [?0 := Box<^0.0>] })"]]
+            expect![["Ambiguous(for<_0_0> { substitution [?0 := Box<_0_0>] })"]]
         }
     }
 }
@@ -258,7 +341,7 @@ fn basic_region_constraint_from_positive_impl() {
         goal {
             forall<'a, 'b, T> { Ref<'a, 'b, T>: Foo }
         } yields_all[SolverChoice::slg(3, None)] {
-            expect![["lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["lifetime constraints ['!1_1: '!1_0, '!1_0: '!1_1]"]]
         }
     }
 }
@@ -622,7 +705,7 @@ fn lifetime_outlives_constraints() {
                 Bar: Foo<'a, 'b>
             }
         } yields {
-            expect![["Unique; for<?U0,?U0> { substitution [?0 := '^0.0, ?1 := '^0.1], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '^0.1 }] }"]]
+            expect![["Unique; for<'_0_0, '_0_1> { substitution [?0 := '_0_0, ?1 := '_0_1], lifetime constraints ['_0_0: '_0_1] }"]]
         }
 
         goal {
@@ -632,7 +715,7 @@ fn lifetime_outlives_constraints() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints ['!1_0: '_0_0] }"]]
         }
     }
 }
@@ -651,7 +734,7 @@ fn type_outlives_constraints() {
                 Bar: Foo<'a, T>
             }
         } yields {
-            expect![["Unique; for<?U0,?U0> { substitution [?0 := '^0.0, ?1 := ^0.1], lifetime constraints [InEnvironment { environment: Env([]), goal: ^0.1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0, _0_1> { substitution [?0 := '_0_0, ?1 := _0_1], lifetime constraints [_0_1: '_0_0] }"]]
         }
 
         goal {
@@ -661,7 +744,7 @@ fn type_outlives_constraints() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: !1_0: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints [!1_0: '_0_0] }"]]
         }
     }
 }
@@ -689,7 +772,7 @@ fn not_really_ambig() {
         goal {
             exists<T> { Vec<T>: A }
         } yields[SolverChoice::slg_default()] {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
         } yields[SolverChoice::recursive_default()] {
             expect![["Ambiguous; no inference guidance"]]
         }
@@ -717,7 +800,7 @@ fn canonicalization_regression() {
                 }
             }
         } yields {
-            expect![["Unique; substitution [?0 := !2_0]"]]
+            expect![["Unique; substitution [?0 := !1_1]"]]
         }
     }
 }
@@ -772,9 +855,9 @@ fn ambiguous_unification_in_fn() {
                 MyClosure<fn(&'static U) -> ()>: FnOnce<(&'static T,)>
             }
         } yields[SolverChoice::slg_default()] {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: 'static: 'static }, InEnvironment { environment: Env([]), goal: 'static: 'static }] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0, ?1 := _0_0] }"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0, ?1 := ^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: 'static: 'static }] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0, ?1 := _0_0] }"]]
         }
     }
 }
@@ -798,7 +881,7 @@ fn endless_loop() {
                 <MyClosure<fn() -> T> as FnOnce>::Output = T
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0] }"]]
         }
     }
 }
@@ -813,7 +896,7 @@ fn env_bound_vars() {
                 }
             }
         } yields {
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := '^0.0] }"]]
+            expect![["Ambiguous; definite substitution for<'_0_0> { [?0 := '_0_0] }"]]
         }
         goal {
             exists<'a> {
@@ -822,7 +905,7 @@ fn env_bound_vars() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: 0: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints [(): '_0_0] }"]]
         }
     }
 }
@@ -839,9 +922,9 @@ fn recursive_hang() {
                 }
             }
         } yields[SolverChoice::slg_default()] {
-            expect![["Ambiguous; definite substitution for<?U0,?U0> { [?0 := ^0.0, ?1 := '^0.1] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0, '_0_1> { [?0 := _0_0, ?1 := '_0_1] }"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Ambiguous; suggested substitution for<?U0,?U0> { [?0 := ^0.0, ?1 := '^0.1] }"]]
+            expect![["Ambiguous; suggested substitution for<_0_0, '_0_1> { [?0 := _0_0, ?1 := '_0_1] }"]]
         }
     }
 }
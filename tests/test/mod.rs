@@ -12,17 +12,24 @@ use chalk_integration::lowering::lower_goal;
 use chalk_integration::query::LoweringDatabase;
 use chalk_integration::SolverChoice;
 use chalk_ir::Constraints;
+use chalk_solve::display::DisplayWithDb;
 use chalk_solve::ext::*;
 use chalk_solve::logging::with_tracing_logs;
 use chalk_solve::RustIrDatabase;
 use chalk_solve::Solution;
+use snapshot::Snapshot;
 
 #[cfg(feature = "bench")]
 mod bench;
 mod coherence;
+mod snapshot;
 mod wf_lowering;
 
-fn format_solution(mut result: Option<Solution<ChalkIr>>, interner: ChalkIr) -> String {
+pub fn format_solution(
+    mut result: Option<Solution<ChalkIr>>,
+    db: &dyn RustIrDatabase<ChalkIr>,
+) -> String {
+    let interner = db.interner();
     // sort constraints, since the different solvers may output them in different order
     if let Some(Solution::Unique(solution)) = &mut result {
         let mut sorted = solution.value.constraints.as_slice(interner).to_vec();
@@ -30,18 +37,17 @@ fn format_solution(mut result: Option<Solution<ChalkIr>>, interner: ChalkIr) ->
         solution.value.constraints = Constraints::from_iter(interner, sorted);
     }
     match result {
-        Some(v) => v.display(ChalkIr).to_string(),
+        Some(v) => v.display_with(db).to_string(),
         None => "No possible solution".to_string(),
     }
 }
 
-pub fn assert_result(result: Option<Solution<ChalkIr>>, expected: &Expect, interner: ChalkIr) {
-    let result = format_solution(result, interner);
-    expected.assert_eq(&result);
-}
-
-pub fn assert_result_str(result: Option<Solution<ChalkIr>>, expected: &str, interner: ChalkIr) {
-    let result = format_solution(result, interner);
+pub fn assert_result_str(
+    result: Option<Solution<ChalkIr>>,
+    expected: &str,
+    db: &dyn RustIrDatabase<ChalkIr>,
+) {
+    let result = format_solution(result, db);
     assert_same(&result, expected);
 }
 
@@ -97,8 +103,8 @@ macro_rules! parse_test_data {
     };
 
     // goal { G } yields_all { "Y1", "Y2", ... , "YN" } -- test that the SLG
-    // solver gets exactly N answers in this order (the recursive solver can't
-    // return multiple answers)
+    // solver gets exactly N answers in this order (use `yields_all[C]` to test
+    // the recursive solver)
     (@program[$program:tt] @parsed_goals[$($parsed_goals:tt)*] @unparsed_goals[
         goal $goal:tt yields_all { $($expected:expr),* }
         $($unparsed_goals:tt)*
@@ -247,6 +253,7 @@ fn solve_goal(
             &program_text[1..program_text.len() - 1],
             SolverChoice::default(),
         );
+        let mut snapshot = Snapshot::default();
 
         let program = if coherence {
             db.checked_program().unwrap()
@@ -271,19 +278,12 @@ fn solve_goal(
                         goal_text,
                         solver_choices,
                         expected,
+                        &mut snapshot,
                     );
                     continue;
                 }
             };
 
-            match (&solver_choice, &expected) {
-                (SolverChoice::Recursive { .. }, TestGoal::All(_))
-                | (SolverChoice::Recursive { .. }, TestGoal::First(_)) => {
-                    panic!("cannot test the recursive solver with yields_first or yields_all");
-                }
-                _ => {}
-            };
-
             if db.solver_choice() != solver_choice {
                 db.set_solver_choice(solver_choice);
             }
@@ -301,10 +301,12 @@ fn solve_goal(
 
                 println!("using solver: {:?}", solver_choice);
                 let peeled_goal = goal.into_peeled_goal(db.interner());
+                snapshot.goal(goal_text);
                 match &expected {
                     TestGoal::Aggregated(expected) => {
-                        let result = db.solve(&peeled_goal);
-                        assert_result(result, expected, db.interner());
+                        let result = format_solution(db.solve(&peeled_goal), &db);
+                        snapshot.solution(solver_choice, &result);
+                        expected.assert_eq(&result);
                     }
                     TestGoal::All(expected) => {
                         let mut expected = expected.iter();
@@ -314,8 +316,9 @@ fn solve_goal(
                                     Some(expected) => {
                                         let actual = format!(
                                             "{}",
-                                            result.as_ref().map(|v| v.display(ChalkIr))
+                                            result.as_ref().map(|v| v.display_with(&db))
                                         );
+                                        snapshot.solution(solver_choice, &actual);
                                         expected.assert_eq(&actual)
                                     }
                                     None => {
@@ -337,7 +340,8 @@ fn solve_goal(
                         {
                             Some(solution) => {
                                 let actual =
-                                    format!("{}", result.as_ref().map(|v| v.display(ChalkIr)));
+                                    format!("{}", result.as_ref().map(|v| v.display_with(&db)));
+                                snapshot.solution(solver_choice, &actual);
                                 solution.assert_eq(&actual);
                                 if !next_result {
                                     assert!(
@@ -354,6 +358,7 @@ fn solve_goal(
                 }
             });
         }
+        snapshot.check();
     })
 }
 
@@ -363,8 +368,10 @@ fn solve_aggregated(
     goal_text: &str,
     choices: &[SolverChoice],
     expected: Expect,
+    snapshot: &mut Snapshot,
 ) {
     let mut solutions = vec![];
+    snapshot.goal(goal_text);
 
     for solver_choice in choices.iter().copied() {
         if db.solver_choice() != solver_choice {
@@ -384,8 +391,9 @@ fn solve_aggregated(
 
             println!("using solver: {:?}", solver_choice);
             let peeled_goal = goal.into_peeled_goal(db.interner());
-            let result = db.solve(&peeled_goal);
-            solutions.push(format_solution(result, db.interner()));
+            let result = format_solution(db.solve(&peeled_goal), db);
+            snapshot.solution(solver_choice, &result);
+            solutions.push(result);
         });
     }
 
@@ -414,11 +422,13 @@ mod cycle;
 mod discriminant_kind;
 mod existential_types;
 mod fn_def;
+mod fn_ptr;
 mod foreign_types;
 mod functions;
 mod generators;
 mod implied_bounds;
 mod impls;
+mod inherent_assoc_types;
 mod lifetimes;
 mod misc;
 mod negation;
@@ -429,9 +439,11 @@ mod opaque_types;
 mod projection;
 mod refs;
 mod scalars;
+mod sized;
 mod slices;
 mod string;
 mod subtype;
+mod tuple_trait;
 mod tuples;
 mod type_flags;
 mod unify;
@@ -28,7 +28,7 @@ fn integer_index() {
                 Slice: Index<N>
             }
         } yields {
-            expect![["Unique; substitution [?0 := Uint(Usize)]"]]
+            expect![["Unique; substitution [?0 := usize]"]]
         }
     }
 }
@@ -54,7 +54,7 @@ fn integer_kind_trait() {
                 N: Foo
             }
         } yields {
-            expect![["Unique; substitution [?0 := Uint(Usize)]"]]
+            expect![["Unique; substitution [?0 := usize]"]]
         }
     }
 }
@@ -77,7 +77,7 @@ fn float_kind_trait() {
                 N: Foo
             }
         } yields {
-            expect![["Unique; substitution [?0 := Float(F32)]"]]
+            expect![["Unique; substitution [?0 := f32]"]]
         }
     }
 }
@@ -134,7 +134,7 @@ fn integer_and_float_are_specialized_ty_kinds() {
                 T = N, N = usize
             }
         } yields {
-            expect![["Unique; substitution [?0 := Uint(Usize), ?1 := Uint(Usize)]"]]
+            expect![["Unique; substitution [?0 := usize, ?1 := usize]"]]
         }
 
         goal {
@@ -142,7 +142,7 @@ fn integer_and_float_are_specialized_ty_kinds() {
                 T = N, N = f32
             }
         } yields {
-            expect![["Unique; substitution [?0 := Float(F32), ?1 := Float(F32)]"]]
+            expect![["Unique; substitution [?0 := f32, ?1 := f32]"]]
         }
     }
 }
@@ -197,7 +197,7 @@ fn integers_are_copy() {
                 I: Copy
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0] }"]]
         }
     }
 }
@@ -215,7 +215,7 @@ fn integers_are_sized() {
                 I: Sized
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0] }"]]
         }
     }
 }
@@ -260,10 +260,8 @@ fn shl_ice() {
             exists<U> {
                 u32: Shl<U>
             }
-        } yields[SolverChoice::slg_default()] {
-            expect![["Ambiguous; definite substitution for<?U0,?U0> { [?0 := (&'^0.0 ^0.1)] }"]]
-        } yields[SolverChoice::recursive_default()] {
-            expect![["Ambiguous; no inference guidance"]]
+        } yields {
+            expect![["Ambiguous; definite substitution for<'_0_0, _0_1> { [?0 := &'_0_0 _0_1] }"]]
         }
     }
 }
@@ -286,7 +284,7 @@ fn unify_general_then_specific_ty() {
                 Bar<(N, T, T, T)>: Foo
             }
         } yields {
-            expect![["Unique; substitution [?0 := Int(I32), ?1 := Int(I32)]"]]
+            expect![["Unique; substitution [?0 := i32, ?1 := i32]"]]
         }
     }
 }
@@ -106,6 +106,54 @@ fn opaque_where_clause() {
     }
 }
 
+#[test]
+fn opaque_bounds_require_where_clauses() {
+    test! {
+        program {
+            trait Clone { }
+
+            struct Vec<T> { }
+            impl<T> Clone for Vec<T> where T: Clone { }
+
+            struct Foo { }
+            impl Clone for Foo { }
+            struct Bar { }
+
+            opaque type S<U>: Clone where U: Clone = Vec<U>;
+        }
+
+        goal {
+            S<Foo>: Clone
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            S<Bar>: Clone
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<U> {
+                if (U: Clone) {
+                    S<U>: Clone
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<U> {
+                S<U>: Clone
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
 #[test]
 fn opaque_generics_simple() {
     test! {
@@ -188,7 +236,7 @@ fn opaque_trait_generic() {
                 Bar: Trait<T>
             }
         } yields {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
         }
     }
 }
@@ -51,7 +51,7 @@ fn normalize_basic() {
                 }
             }
         } yields {
-            expect![["Unique; substitution [?0 := (Iterator::Item)<!1_0>]"]]
+            expect![["Unique; substitution [?0 := <!1_0 as Iterator>::Item]"]]
         }
 
         goal {
@@ -63,7 +63,7 @@ fn normalize_basic() {
                 }
             }
         } yields {
-            expect![["Unique; substitution [?0 := (Iterator::Item)<!1_0>]"]]
+            expect![["Unique; substitution [?0 := <!1_0 as Iterator>::Item]"]]
         }
 
         goal {
@@ -143,7 +143,7 @@ fn projection_equality() {
             // this is wrong, chalk#234
             expect![["Ambiguous; no inference guidance"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
         }
 
         goal {
@@ -154,7 +154,7 @@ fn projection_equality() {
             // this is wrong, chalk#234
             expect![["Ambiguous; no inference guidance"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
         }
     }
 }
@@ -182,7 +182,7 @@ fn projection_equality_priority1() {
             }
         } yields[SolverChoice::slg_default()] {
             // this is wrong, chalk#234
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := S2, ?1 := ^0.0] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := S2, ?1 := _0_0] }"]]
         } yields[SolverChoice::recursive_default()] {
             // This is.. interesting, but not necessarily wrong.
             // It's certainly true that based on the impls we see
@@ -194,7 +194,7 @@ fn projection_equality_priority1() {
             // constrained `T` at all? I can't come up with
             // an example where that's the case, so maybe
             // not. -Niko
-            expect![["Unique; substitution [?0 := S2, ?1 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := S2, ?1 := u32]"]]
         }
     }
 }
@@ -240,7 +240,7 @@ fn projection_equality_priority2() {
             }
         } yields {
             // Constraining Out1 = Y gives us only one choice.
-            expect![["Unique; substitution [?0 := !1_1, ?1 := (Trait1::Type)<!1_0, !1_1>]"]]
+            expect![["Unique; substitution [?0 := !1_1, ?1 := <!1_0 as Trait1>::Type<!1_1>]"]]
         }
 
         goal {
@@ -254,7 +254,7 @@ fn projection_equality_priority2() {
             }
         } yields {
             // Constraining Out1 = Y gives us only one choice.
-            expect![["Unique; substitution [?0 := !1_1, ?1 := (Trait1::Type)<!1_0, !1_1>]"]]
+            expect![["Unique; substitution [?0 := !1_1, ?1 := <!1_0 as Trait1>::Type<!1_1>]"]]
         }
 
         goal {
@@ -270,11 +270,11 @@ fn projection_equality_priority2() {
             // chalk#234: Constraining Out1 = S1 gives us only the choice to
             // use the impl, but the SLG solver can't decide between
             // the placeholder and the normalized form.
-            expect![["Ambiguous; definite substitution for<?U1> { [?0 := S1, ?1 := ^0.0] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := S1, ?1 := _0_0] }"]]
         } yields[SolverChoice::recursive_default()] {
             // Constraining Out1 = S1 gives us only one choice, use the impl,
             // and the recursive solver prefers the normalized form.
-            expect![["Unique; substitution [?0 := S1, ?1 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := S1, ?1 := u32]"]]
         }
     }
 }
@@ -299,7 +299,60 @@ fn projection_equality_from_env() {
             // this is wrong, chalk#234
             expect![["Ambiguous; no inference guidance"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
+        }
+    }
+}
+
+#[test]
+fn projection_equality_bound_lists() {
+    test! {
+        program {
+            trait Iterator {
+                type Item;
+                type Other;
+            }
+            trait Clone { }
+            trait Good { }
+
+            struct S<'a, T> { }
+            impl<'a, T> Good for S<'a, T> where T: Iterator<Item = u32, Other = u8> + Clone + 'a { }
+
+            struct A { }
+            impl Iterator for A {
+                type Item = u32;
+                type Other = u8;
+            }
+            impl Clone for A { }
+
+            struct B { }
+            impl Iterator for B {
+                type Item = u32;
+                type Other = u32;
+            }
+            impl Clone for B { }
+
+            struct C { }
+            impl Iterator for C {
+                type Item = u32;
+                type Other = u8;
+            }
+        }
+
+        goal {
+            S<'static, A>: Good
+        } yields {
+            expect![["Unique; lifetime constraints [A: 'static]"]]
+        }
+        goal {
+            S<'static, B>: Good
+        } yields {
+            expect![["No possible solution"]]
+        }
+        goal {
+            S<'static, C>: Good
+        } yields {
+            expect![["No possible solution"]]
         }
     }
 }
@@ -327,7 +380,7 @@ fn projection_equality_nested() {
             // this is wrong, chalk#234
             expect![["Ambiguous; no inference guidance"]]
         }  yields[SolverChoice::recursive_default()] {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
         }
     }
 }
@@ -369,7 +422,7 @@ fn iterator_flatten() {
             // this is wrong, chalk#234
             expect![["Ambiguous; no inference guidance"]]
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; substitution [?0 := Uint(U32)]"]]
+            expect![["Unique; substitution [?0 := u32]"]]
         }
     }
 }
@@ -409,7 +462,7 @@ fn normalize_gat1() {
                 }
             }
         } yields {
-            expect![["Unique; substitution [?0 := Iter<'!2_0, !1_0>]"]]
+            expect![["Unique; substitution [?0 := Iter<'!1_1, !1_0>]"]]
         }
     }
 }
@@ -764,12 +817,7 @@ fn normalize_under_binder() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0> { \
-             substitution [?0 := Ref<'^0.0, I32>], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] \
-             }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := Ref<'_0_0, I32>], lifetime constraints ['!1_0: '_0_0, '_0_0: '!1_0] }"]]
         }
     }
 }
@@ -798,11 +846,7 @@ fn normalize_under_binder_multi() {
             }
         } yields_all {
             expect![["substitution [?0 := I32]"]],
-            expect![["for<?U0,?U0> { substitution [?0 := (Deref::Item)<Ref<'^0.0, I32>, '^0.1>], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!1_0: '^0.1 }, \
-            InEnvironment { environment: Env([]), goal: '^0.1: '!1_0 }, \
-            InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-            InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] }"]]
+            expect![["for<'_0_0, '_0_1> { substitution [?0 := <Ref<'_0_0, I32> as Deref>::Item<'_0_1>], lifetime constraints ['!1_0: '_0_1, '_0_0: '!1_0, '_0_1: '!1_0, '!1_0: '_0_0] }"]]
         }
 
         goal {
@@ -1039,7 +1083,7 @@ fn guidance_for_projection_on_flounder() {
                 }
             }
         } yields[SolverChoice::recursive_default()] {
-            expect![["Ambiguous; definite substitution for<?U0> { [?0 := ^0.0, ?1 := ^0.0] }"]]
+            expect![["Ambiguous; definite substitution for<_0_0> { [?0 := _0_0, ?1 := _0_0] }"]]
         }
     }
 }
@@ -12,7 +12,7 @@ fn immut_refs_are_well_formed() {
                 WellFormed(&'a T)
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [!1_1: '!1_0]"]]
         }
 
         goal {
@@ -20,7 +20,7 @@ fn immut_refs_are_well_formed() {
                 WellFormed(&'a A)
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: A: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints [A: '_0_0] }"]]
         }
     }
 }
@@ -47,7 +47,7 @@ fn mut_refs_are_well_formed() {
         goal {
             forall<'a, T> { WellFormed(&'a mut T) }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: !1_1: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [!1_1: '!1_0]"]]
         }
     }
 }
@@ -0,0 +1,219 @@
+//! Tests related to `Sized` for structs, which depends on the unsized tail
+//! of their last field.
+
+use super::*;
+
+#[test]
+fn struct_tails_are_sized() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            struct Empty { }
+            struct Slice<T> where T: Sized { len: usize, data: [T] }
+            struct Wrapper<T> { id: u32, inner: T }
+            struct Pair<T> { first: u8, second: (u8, T) }
+        }
+
+        goal {
+            Empty: Sized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Slice<u8>: Sized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Wrapper<Wrapper<Slice<u8>>>: Sized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Wrapper<Wrapper<Empty>>: Sized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Pair<Wrapper<str>>: Sized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            (u8, Wrapper<[u8]>): Sized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Wrapper<(u8, Slice<u8>)>: Sized
+        } yields {
+            expect![["No possible solution"]]
+        }
+    }
+}
+
+#[test]
+fn generic_struct_tails_are_sized() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            struct Wrapper<T> { id: u32, inner: T }
+            struct Pair<T> { first: u8, second: (u8, T) }
+        }
+
+        goal {
+            forall<T> { Wrapper<Pair<T>>: Sized }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> { if (T: Sized) { Wrapper<Pair<T>>: Sized } }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            exists<T> { Wrapper<Pair<T>>: Sized }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
+#[test]
+fn sizedness_hierarchy() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            #[lang(meta_sized)]
+            trait MetaSized { }
+
+            #[lang(pointee_sized)]
+            trait PointeeSized { }
+
+            trait Trait { }
+
+            extern type Opaque;
+
+            struct Slice { len: usize, data: [u8] }
+            struct Object { vtable: u8, data: dyn Trait + 'static }
+            struct Header { len: usize, data: Opaque }
+            struct Wrapper<T> { inner: T }
+        }
+
+        goal {
+            u8: MetaSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            str: MetaSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            [u8]: MetaSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            dyn Trait + 'static: MetaSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Wrapper<Slice>: MetaSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Object: MetaSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            (u8, Opaque): MetaSized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Opaque: MetaSized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Wrapper<Header>: MetaSized
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Wrapper<Header>: PointeeSized
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<T> {
+                if (T: Sized) {
+                    T: MetaSized
+                }
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<T> {
+                T: MetaSized
+            }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> {
+                T: PointeeSized
+            }
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
+
+#[test]
+fn sizedness_traits_cannot_be_implemented() {
+    lowering_error! {
+        program {
+            #[lang(meta_sized)]
+            trait MetaSized { }
+
+            extern type Opaque;
+
+            impl MetaSized for Opaque { }
+        } error_msg {
+            "trait impl for `MetaSized` does not meet well-formedness requirements"
+        }
+    }
+}
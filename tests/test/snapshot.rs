@@ -0,0 +1,129 @@
+//! Snapshots of the solutions the `test!` macro gets, with every solver it
+//! runs each goal with, so that a change to a solution shows up even when
+//! the test only expects its start, or only expects the solution of one
+//! solver. See `tests/snapshots/README.md` for how to review them.
+
+use chalk_integration::SolverChoice;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    /// How many snapshots each test has taken so far, for tests which use
+    /// `test!` more than once.
+    static SNAPSHOTS_TAKEN: RefCell<HashMap<String, usize>> = RefCell::new(HashMap::new());
+}
+
+/// The solutions of one use of `test!`.
+#[derive(Default)]
+pub struct Snapshot {
+    text: String,
+}
+
+impl Snapshot {
+    pub fn goal(&mut self, goal_text: &str) {
+        let goal = goal_text
+            .strip_prefix('{')
+            .and_then(|goal| goal.strip_suffix('}'))
+            .unwrap_or(goal_text);
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        writeln!(self.text, "?- {}", goal.trim()).unwrap();
+    }
+
+    /// Records the solution `solver_choice` gets for the last goal. Solvers
+    /// which get several answers record each of them in turn.
+    pub fn solution(&mut self, solver_choice: SolverChoice, solution: &str) {
+        // The default solvers are named as in `SolverChoice::from_name`.
+        if solver_choice == SolverChoice::slg_default() {
+            write!(self.text, "slg")
+        } else if solver_choice == SolverChoice::recursive_default() {
+            write!(self.text, "recursive")
+        } else {
+            write!(self.text, "{:?}", solver_choice)
+        }
+        .unwrap();
+        writeln!(self.text, ": {}", solution).unwrap();
+    }
+
+    /// Compares the snapshot with the one recorded for the current test.
+    /// If they differ, the snapshot is written next to the recorded one,
+    /// with the `.snap.new` extension, and the test fails; with
+    /// `CHALK_SNAPSHOTS=accept`, it replaces the recorded one instead.
+    pub fn check(self) {
+        let path = match snapshot_path() {
+            Some(path) => path,
+            None => return,
+        };
+        let pending = path.with_extension("snap.new");
+        let recorded = fs::read_to_string(&path).ok();
+        if recorded.as_deref() == Some(self.text.as_str()) {
+            let _ = fs::remove_file(&pending);
+            return;
+        }
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        if std::env::var("CHALK_SNAPSHOTS").as_deref() == Ok("accept") {
+            fs::write(&path, &self.text).unwrap();
+            let _ = fs::remove_file(&pending);
+            return;
+        }
+        fs::write(&pending, &self.text).unwrap();
+        let accept = "if it is right, accept it with `CHALK_SNAPSHOTS=accept cargo test`";
+        match recorded {
+            None => panic!(
+                "there is no snapshot of the solutions at {}; review the new one at {}, and {}",
+                path.display(),
+                pending.display(),
+                accept
+            ),
+            Some(recorded) => panic!(
+                "the solutions differ from the snapshot at {}:\n{}\nThe new snapshot is at {}; {}",
+                path.display(),
+                diff(&recorded, &self.text),
+                pending.display(),
+                accept
+            ),
+        }
+    }
+}
+
+/// The file the snapshot of the current test is recorded in: the test
+/// `test::module::name` is recorded in `tests/snapshots/module/name.snap`,
+/// and its second use of `test!` in `name-2.snap`. Tests not run by the
+/// test harness, whose thread is not named after them, are not recorded.
+fn snapshot_path() -> Option<PathBuf> {
+    let thread = std::thread::current();
+    let test = thread.name()?.strip_prefix("test::")?;
+    let taken = SNAPSHOTS_TAKEN.with(|taken| {
+        let mut taken = taken.borrow_mut();
+        let taken = taken.entry(test.to_string()).or_insert(0);
+        *taken += 1;
+        *taken
+    });
+    let mut path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots");
+    path.extend(test.split("::"));
+    if taken > 1 {
+        let name = format!("{}-{}", path.file_name().unwrap().to_string_lossy(), taken);
+        path.set_file_name(name);
+    }
+    Some(path.with_extension("snap"))
+}
+
+fn diff(recorded: &str, new: &str) -> String {
+    let mut out = String::new();
+    for line in diff::lines(recorded, new) {
+        match line {
+            diff::Result::Left(l) => writeln!(out, "-{}", l),
+            diff::Result::Both(l, _) => writeln!(out, " {}", l),
+            diff::Result::Right(r) => writeln!(out, "+{}", r),
+        }
+        .expect("writing to string never fails");
+    }
+    out
+}
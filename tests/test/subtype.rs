@@ -44,7 +44,7 @@ fn struct_lifetime_variance() {
                 Subtype(Foo<'a>, Foo<'b>)
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_1: '!1_0]"]]
         }
     }
 }
@@ -59,7 +59,7 @@ fn ref_lifetime_variance() {
             }
         } yields {
             // Seems good!
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1]"]]
         }
     }
 }
@@ -70,9 +70,9 @@ fn fn_lifetime_variance_args() {
         goal {
             for<'a, 'b> fn(&'a u32, &'b u32) = for<'a> fn(&'a u32, &'a u32)
         } yields[SolverChoice::recursive_default()] {
-            expect![["Unique; for<?U1,?U2,?U2> { lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }, InEnvironment { environment: Env([]), goal: '!2_0: '^0.1 }, InEnvironment { environment: Env([]), goal: '!2_0: '^0.2 }] }"]]
+            expect![["Unique; for<'_0_0, '_0_1, '_0_2> { lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0, '!2_0: '_0_1, '!2_0: '_0_2] }"]]
         } yields[SolverChoice::slg_default()] {
-            expect![["Unique; for<?U2,?U2,?U1> { lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.2 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.2 }, InEnvironment { environment: Env([]), goal: '!2_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!2_0: '^0.1 }] }"]]
+            expect![["Unique; for<'_0_0, '_0_1, '_0_2> { lifetime constraints ['!1_0: '_0_2, '!1_1: '_0_2, '!2_0: '_0_0, '!2_0: '_0_1] }"]]
         }
     }
 }
@@ -84,12 +84,12 @@ fn fn_lifetime_variance_with_return_type() {
             Subtype(for<'a, 'b> fn(&'a u32, &'b u32) -> &'a u32, for<'a> fn(&'a u32, &'a u32) -> &'a u32)
         } yields {
             // TODO: are these results actually correct?
-            expect![["Unique; for<?U1,?U1> { lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_0: '^0.1 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] }"]]
+            expect![["Unique; for<'_0_0, '_0_1> { lifetime constraints ['!1_0: '_0_0, '!1_0: '_0_1, '_0_0: '!1_0] }"]]
         }
         goal {
             Subtype(for<'a> fn(&'a u32, &'a u32) -> &'a u32, for<'a, 'b> fn(&'a u32, &'b u32) -> &'a u32)
         } yields {
-            expect![["Unique; for<?U1> { lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] }"]]
+            expect![["Unique; for<'_0_0> { lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0, '_0_0: '!1_0] }"]]
         }
     }
 }
@@ -111,7 +111,7 @@ fn generalize() {
             // If this is invariant, then the generalizer might be doing
             // the right thing here by creating the general form of `&'a u32` equal to
             // just `&'a u32`
-            expect![["Unique; substitution [?0 := (&'!1_0 Uint(U32))]"]]
+            expect![["Unique; substitution [?0 := &'!1_0 u32]"]]
         }
     }
 }
@@ -137,7 +137,7 @@ fn multi_lifetime() {
             // This is incorrect, as we shouldn't be requiring 'a and 'b to be
             // related to eachother. Instead, U should be &'?1 u32, with constraints
             // ?1 : 'a, ?1: 'b.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
     }
 }
@@ -164,7 +164,7 @@ fn multi_lifetime_inverted() {
             // This is incorrect, as we shouldn't be requiring 'a and 'b to be
             // related to eachother. Instead, U should be &'?1 u32, with constraints
             // ?1 : 'a, ?1: 'b.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -187,7 +187,7 @@ fn multi_lifetime_covariant_struct() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -198,7 +198,7 @@ fn multi_lifetime_covariant_struct() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -221,7 +221,7 @@ fn multi_lifetime_contravariant_struct() {
             }
         } yields {
             // Result should be opposite multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -232,7 +232,7 @@ fn multi_lifetime_contravariant_struct() {
             }
         } yields {
             // Result should be opposite multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
     }
 }
@@ -255,10 +255,10 @@ fn multi_lifetime_invariant_struct() {
             }
         } yields[SolverChoice::recursive_default()] {
             // Because A is invariant, we require the lifetimes to be equal
-            expect![["Unique; substitution [?0 := (&'!1_0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; substitution [?0 := &'!1_0 u32], lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         } yields[SolverChoice::slg_default()] {
             // Because A is invariant, we require the lifetimes to be equal
-            expect![["Unique; substitution [?0 := (&'!1_1 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; substitution [?0 := &'!1_1 u32], lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
 
         goal {
@@ -270,10 +270,10 @@ fn multi_lifetime_invariant_struct() {
             }
         } yields[SolverChoice::recursive_default()] {
             // Because A is invariant, we require the lifetimes to be equal
-            expect![["Unique; substitution [?0 := (&'!1_0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; substitution [?0 := &'!1_0 u32], lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         } yields[SolverChoice::slg_default()] {
             // Because A is invariant, we require the lifetimes to be equal
-            expect![["Unique; substitution [?0 := (&'!1_1 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; substitution [?0 := &'!1_1 u32], lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
     }
 }
@@ -291,7 +291,7 @@ fn multi_lifetime_slice() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -302,7 +302,7 @@ fn multi_lifetime_slice() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -320,7 +320,7 @@ fn multi_lifetime_tuple() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -331,7 +331,7 @@ fn multi_lifetime_tuple() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -349,7 +349,7 @@ fn multi_lifetime_array() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -360,7 +360,7 @@ fn multi_lifetime_array() {
             }
         } yields {
             // Result should be identical to multi_lifetime result.
-            expect![["Unique; for<?U1> { substitution [?0 := (&'^0.0 Uint(U32))], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := &'_0_0 u32], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -382,7 +382,7 @@ fn generalize_covariant_struct() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1> { substitution [?0 := Foo<(&'^0.0 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := Foo<&'_0_0 u32>], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
     }
 }
@@ -405,7 +405,7 @@ fn generalize_contravariant_struct() {
             }
         } yields {
             // Result should be opposite generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := Foo<(&'^0.0 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := Foo<&'_0_0 u32>], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -428,9 +428,9 @@ fn generalize_invariant_struct() {
             }
         } yields[SolverChoice::recursive_default()] {
             // Because A is invariant, we require the lifetimes to be equal
-            expect![["Unique; substitution [?0 := Foo<(&'!1_0 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; substitution [?0 := Foo<&'!1_0 u32>], lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         } yields[SolverChoice::slg_default()] {
-            expect![["Unique; substitution [?0 := Foo<(&'!1_1 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; substitution [?0 := Foo<&'!1_1 u32>], lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
     }
 }
@@ -448,7 +448,7 @@ fn generalize_slice() {
             }
         } yields {
             // Result should be identical to generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := [(&'^0.0 Uint(U32))]], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := [&'_0_0 u32]], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -459,7 +459,7 @@ fn generalize_slice() {
             }
         } yields {
             // Result should be identical to generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := [(&'^0.0 Uint(U32))]], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := [&'_0_0 u32]], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -477,7 +477,7 @@ fn generalize_tuple() {
             }
         } yields {
             // Result should be identical to generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := 1<(&'^0.0 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := (&'_0_0 u32,)], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
         goal {
             forall<'a, 'b> {
@@ -488,7 +488,7 @@ fn generalize_tuple() {
             }
         } yields {
             // Result should be identical to generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := 1<(&'^0.0 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := (&'_0_0 u32,)], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
         }
     }
 }
@@ -505,7 +505,7 @@ fn generalize_2tuple() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1,?U1> { substitution [?0 := 2<(&'^0.0 Uint(U32)), (&'^0.1 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_2: '^0.1 }, InEnvironment { environment: Env([]), goal: '!1_3: '^0.1 }] }"]]
+            expect![["Unique; for<'_0_0, '_0_1> { substitution [?0 := (&'_0_0 u32, &'_0_1 u32)], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0, '!1_2: '_0_1, '!1_3: '_0_1] }"]]
         }
         goal {
             forall<'a, 'b, 'c, 'd> {
@@ -515,7 +515,7 @@ fn generalize_2tuple() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1,?U1> { substitution [?0 := 2<(&'^0.0 Uint(U32)), (&'^0.1 Uint(U32))>], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }, InEnvironment { environment: Env([]), goal: '^0.1: '!1_2 }, InEnvironment { environment: Env([]), goal: '^0.1: '!1_3 }] }"]]
+            expect![["Unique; for<'_0_0, '_0_1> { substitution [?0 := (&'_0_0 u32, &'_0_1 u32)], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1, '_0_1: '!1_2, '_0_1: '!1_3] }"]]
         }
     }
 }
@@ -533,7 +533,7 @@ fn generalize_array() {
             }
         } yields {
             // Result should be identical to generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := [(&'^0.0 Uint(U32)); 16]], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := [&'_0_0 u32; 16]], lifetime constraints ['!1_0: '_0_0, '!1_1: '_0_0] }"]]
         }
 
         goal {
@@ -545,7 +545,77 @@ fn generalize_array() {
             }
         } yields {
             // Result should be identical to generalize_covariant_struct result.
-            expect![["Unique; for<?U1> { substitution [?0 := [(&'^0.0 Uint(U32)); 16]], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_1 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := [&'_0_0 u32; 16]], lifetime constraints ['_0_0: '!1_0, '_0_0: '!1_1] }"]]
+        }
+    }
+}
+
+/// Test that the variances of items without a `#[variance]` attribute are
+/// inferred from their definitions: `Foo<'a>` relates `'a` as `&'a u32`
+/// does, and `Bar<'a>`, which uses it behind `&mut`, invariantly.
+#[test]
+fn inferred_struct_lifetime_variance() {
+    test! {
+        program {
+            struct Foo<'a> { r: &'a u32 }
+            struct Bar<'a> { r: &'a mut Foo<'a> }
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(Foo<'a>, Foo<'b>)
+            }
+        } yields {
+            expect![[r#"Unique; lifetime constraints ['!1_0: '!1_1]"#]]
+        }
+
+        goal {
+            forall<'a, 'b> {
+                Subtype(Bar<'a>, Bar<'b>)
+            }
+        } yields {
+            expect![[r#"Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"#]]
+        }
+    }
+}
+
+/// Test that the variances of mutually recursive items are inferred
+/// together, `Even` and `Odd` being covariant in `T` and contravariant in
+/// `U` through each other.
+#[test]
+fn inferred_recursive_variance() {
+    test! {
+        program {
+            struct Even<T, U> { t: T, odd: Box<Odd<T, U>> }
+            struct Odd<T, U> { even: Box<Even<T, U>>, f: fn(U) }
+            #[variance(Covariant)]
+            struct Box<T> {}
+        }
+
+        goal {
+            forall<'a, 'b, 'c, 'd> {
+                Subtype(Odd<&'a u32, &'c u32>, Odd<&'b u32, &'d u32>)
+            }
+        } yields {
+            expect![[r#"Unique; lifetime constraints ['!1_0: '!1_1, '!1_3: '!1_2]"#]]
+        }
+    }
+}
+
+/// Test that the variances of fn defs are inferred from their signatures.
+#[test]
+fn inferred_fn_def_variance() {
+    test! {
+        program {
+            fn foo<'a, 'b>(a: &'a u32) -> &'b u32;
+        }
+
+        goal {
+            forall<'a, 'b, 'c, 'd> {
+                Subtype(foo<'a, 'c>, foo<'b, 'd>)
+            }
+        } yields {
+            expect![[r#"Unique; lifetime constraints ['!1_1: '!1_0, '!1_2: '!1_3]"#]]
         }
     }
 }
@@ -0,0 +1,183 @@
+use super::*;
+
+// Test that user-provided impls of `Tuple` are prohibited
+#[test]
+fn no_tuple_impls() {
+    lowering_error! {
+        program {
+            #[lang(tuple_trait)]
+            trait Tuple { }
+
+            struct Foo { }
+
+            impl Tuple for Foo { }
+        } error_msg {
+            "trait impl for `Tuple` does not meet well-formedness requirements"
+        }
+    }
+}
+
+#[test]
+fn tuples_implement_tuple() {
+    test! {
+        program {
+            #[lang(tuple_trait)]
+            trait Tuple { }
+
+            struct Foo { }
+        }
+
+        goal {
+            (): Tuple
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            (u8, Foo, [u32]): Tuple
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<T> { (T,): Tuple }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            u32: Tuple
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            Foo: Tuple
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<T> { T: Tuple }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<T> { T: Tuple }
+        } yields {
+            expect![["Ambiguous; no inference guidance"]]
+        }
+    }
+}
+
+#[test]
+fn fn_args_must_be_tuples() {
+    test! {
+        program {
+            #[lang(tuple_trait)]
+            trait Tuple { }
+
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+
+            #[lang(fn_mut)]
+            trait FnMut<Args> where Self: FnOnce<Args> { }
+
+            #[lang(fn)]
+            trait Fn<Args> where Self: FnMut<Args> { }
+        }
+
+        goal {
+            fn(u8, u32): Fn<(u8, u32)>
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<F> { WellFormed(F: FnOnce<(u8,)>) }
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            forall<F> { if (F: FnOnce<(u8,)>) { WellFormed(F: FnOnce<(u8,)>) } }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        // The argument list of a `Fn` bound is known to be a tuple.
+        goal {
+            forall<F, Args> { if (FromEnv(F: FnOnce<Args>)) { Args: Tuple } }
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            forall<F, Args> { if (FromEnv(F: Fn<Args>)) { Args: Tuple } }
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
+
+// A generic wrapper forwarding calls with any number of arguments, as with
+// `extern "rust-call" fn call(&self, args: Args) where Args: Tuple`.
+#[test]
+fn variadic_call_wrapper() {
+    test! {
+        program {
+            #[lang(tuple_trait)]
+            trait Tuple { }
+
+            #[lang(fn_once)]
+            trait FnOnce<Args> {
+                type Output;
+            }
+
+            #[lang(fn_mut)]
+            trait FnMut<Args> where Self: FnOnce<Args> { }
+
+            #[lang(fn)]
+            trait Fn<Args> where Self: FnMut<Args> { }
+
+            trait Call<Args> { }
+
+            struct Wrapper<F> { f: F }
+
+            impl<F, Args> Call<Args> for Wrapper<F> where F: Fn<Args>, Args: Tuple { }
+        }
+
+        goal {
+            Wrapper<fn()>: Call<()>
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Wrapper<fn(u8, u16, u32)>: Call<(u8, u16, u32)>
+        } yields {
+            expect![["Unique"]]
+        }
+
+        goal {
+            Wrapper<fn(u8, u16, u32)>: Call<(u8, u16)>
+        } yields {
+            expect![["No possible solution"]]
+        }
+
+        goal {
+            exists<Args> { Wrapper<fn(u8, u16)>: Call<Args> }
+        } yields {
+            expect![["Unique; substitution [?0 := (u8, u16)]"]]
+        }
+
+        goal {
+            forall<F, Args> { if (F: Fn<Args>) { Wrapper<F>: Call<Args> } }
+        } yields {
+            expect![["Unique"]]
+        }
+    }
+}
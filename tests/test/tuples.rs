@@ -146,7 +146,7 @@ fn tuples_are_sized() {
         goal {
             exists<T> { (T, u8): Sized }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := ^0.0] }"]]
+            expect![["Unique; for<_0_0> { substitution [?0 := _0_0] }"]]
         }
 
         goal {
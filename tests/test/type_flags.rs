@@ -1,5 +1,5 @@
 use chalk_integration::interner::ChalkIr;
-use chalk_integration::{empty_substitution, lifetime, ty};
+use chalk_integration::{arg, empty_substitution, lifetime, ty};
 use chalk_ir::cast::Cast;
 use chalk_ir::{PlaceholderIndex, TyKind, TypeFlags, UniverseIndex};
 
@@ -116,3 +116,47 @@ fn static_and_bound_lifetimes() {
         TypeFlags::HAS_FREE_REGIONS | TypeFlags::HAS_RE_LATE_BOUND
     );
 }
+
+#[test]
+fn bound_var_flags() {
+    let slice = |ty| TyKind::Slice(ty).intern(ChalkIr);
+
+    let bound_ty = slice(ty!(bound 0));
+    assert_eq!(bound_ty.data(ChalkIr).flags, TypeFlags::HAS_TY_BOUND);
+    assert!(bound_ty.has_type_flags(ChalkIr, TypeFlags::HAS_BOUND_VARS));
+    assert!(!bound_ty.has_type_flags(ChalkIr, TypeFlags::HAS_INFER | TypeFlags::HAS_PLACEHOLDER));
+
+    // Substituting into a type without bound variables leaves it untouched.
+    let subst = chalk_ir::Substitution::from1(ChalkIr, ty!(placeholder 0));
+    let closed = slice(TyKind::Str.intern(ChalkIr));
+    assert_eq!(subst.apply(closed.clone(), ChalkIr), closed);
+    assert_eq!(subst.apply(bound_ty, ChalkIr), slice(ty!(placeholder 0)));
+}
+
+#[test]
+fn outer_exclusive_binder() {
+    use chalk_ir::DebruijnIndex;
+
+    assert_eq!(
+        TyKind::Str.intern(ChalkIr).outer_exclusive_binder(ChalkIr),
+        DebruijnIndex::INNERMOST
+    );
+
+    // `[^1.0]` mentions a variable bound by the second binder outside of it.
+    let slice = TyKind::Slice(ty!(bound 1 0)).intern(ChalkIr);
+    assert_eq!(slice.outer_exclusive_binder(ChalkIr), DebruijnIndex::new(2));
+    assert!(slice.has_vars_bound_at_or_above(ChalkIr, DebruijnIndex::ONE));
+    assert!(!slice.has_vars_bound_at_or_above(ChalkIr, DebruijnIndex::new(2)));
+
+    // `for<'a> fn('a)`: the lifetime is bound by the function type itself.
+    let fn_ty = ty!(function 1 (lifetime (bound 0)));
+    assert_eq!(
+        fn_ty.outer_exclusive_binder(ChalkIr),
+        DebruijnIndex::INNERMOST
+    );
+    assert!(fn_ty.has_type_flags(ChalkIr, TypeFlags::HAS_RE_LATE_BOUND));
+
+    // `for<'a> fn('a, ^1.0)`: `^1.0` is bound just outside of the function type.
+    let fn_ty = ty!(function 1 (lifetime (bound 0)) (bound 1 0));
+    assert_eq!(fn_ty.outer_exclusive_binder(ChalkIr), DebruijnIndex::ONE);
+}
@@ -21,7 +21,7 @@ fn region_equality() {
                 Ref<'a, Unit>: Eq<Ref<'b, Unit>>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
 
         goal {
@@ -81,7 +81,7 @@ fn forall_equality_unsolveable_simple() {
             for<'a, 'b> fn(Ref<'a, Ref<'b, Ref<'a, Unit>>>): Eq<
                 for<'c, 'd> fn(Ref<'c, Ref<'d, Ref<'d, Unit>>>)>
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0, '!2_0: '!2_1, '!2_1: '!2_0]"]]
         }
     }
 }
@@ -118,7 +118,7 @@ fn forall_equality() {
             for<'a, 'b> fn(Ref<'a, Ref<'b, Ref<'a, Unit>>>): Eq<
                 for<'c, 'd> fn(Ref<'c, Ref<'d, Ref<'d, Unit>>>)>
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0, '!2_0: '!2_1, '!2_1: '!2_0]"]]
         }
 
         goal {
@@ -149,6 +149,14 @@ fn forall_equality() {
             expect![["Unique"]]
         }
 
+        goal {
+            // Function pointers with different numbers of arguments should
+            // not be equal.
+            fn(u8) -> u32: Eq<fn() -> u32>
+        } yields {
+            expect![["No possible solution"]]
+        }
+
         goal {
             // Variadic function pointers should not be equal to non-variadic fn pointers.
             fn(u8, ...): Eq<fn(u8)>
@@ -178,13 +186,7 @@ fn unify_quantified_lifetimes() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0> { \
-             substitution [?0 := '^0.0], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }\
-             ] \
-             }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints ['!1_0: '_0_0, '_0_0: '!1_0] }"]]
         }
 
         // Similar to the previous test, but indirect.
@@ -198,22 +200,10 @@ fn unify_quantified_lifetimes() {
                 }
             }
         } yields[SolverChoice::slg(10, None)] {
-            expect![["Unique; for<?U0> { \
-             substitution [?0 := '^0.0, ?1 := '!1_0], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }\
-             ] \
-             }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0, ?1 := '!1_0], lifetime constraints ['!1_0: '_0_0, '_0_0: '!1_0] }"]]
         } yields[SolverChoice::recursive_default()] {
             // only difference is in the value of ?1, which is equivalent
-            expect![["Unique; for<?U0> { \
-             substitution [?0 := '^0.0, ?1 := '^0.0], \
-             lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, \
-             InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }\
-             ] \
-             }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0, ?1 := '_0_0], lifetime constraints ['!1_0: '_0_0, '_0_0: '!1_0] }"]]
         }
     }
 }
@@ -235,13 +225,7 @@ fn equality_binder() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1> { \
-                 substitution [?0 := '^0.0], \
-                 lifetime constraints [\
-                 InEnvironment { environment: Env([]), goal: '!2_0: '^0.0 }, \
-                 InEnvironment { environment: Env([]), goal: '^0.0: '!2_0 }\
-                 ] \
-             }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints ['!2_0: '_0_0, '_0_0: '!2_0] }"]]
         }
     }
 }
@@ -256,13 +240,13 @@ fn equality_binder2() {
         goal {
             for<'b, 'c> fn(Ref<'b, 'c>) = for<'a> fn(Ref<'a, 'a>)
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1, '!1_1: '!1_0]"]]
         }
 
         goal {
             for<'a> fn(Ref<'a, 'a>) = for<'b, 'c> fn(Ref<'b, 'c>)
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"]]
+            expect![["Unique; lifetime constraints ['!2_0: '!2_1, '!2_1: '!2_0]"]]
         }
     }
 }
@@ -283,7 +267,7 @@ fn mixed_indices_unify() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0,?U0> { substitution [?0 := '^0.0, ?1 := ^0.1, ?2 := ^0.1] }"]]
+            expect![["Unique; for<'_0_0, _0_1> { substitution [?0 := '_0_0, ?1 := _0_1, ?2 := _0_1] }"]]
         }
     }
 }
@@ -307,7 +291,7 @@ fn mixed_indices_match_program() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0> { substitution [?0 := '^0.0, ?1 := S, ?2 := S] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0, ?1 := S, ?2 := S] }"]]
         }
     }
 }
@@ -335,7 +319,7 @@ fn mixed_indices_normalize_application() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U0,?U0> { substitution [?0 := '^0.0, ?1 := ^0.1, ?2 := ^0.1] }"]]
+            expect![["Unique; for<'_0_0, _0_1> { substitution [?0 := '_0_0, ?1 := _0_1, ?2 := _0_1] }"]]
         }
     }
 }
@@ -362,7 +346,7 @@ fn mixed_indices_normalize_gat_application() {
         } yields {
             // Our GAT parameter <X> is mapped to ?0; all others appear left to right
             // in our Normalize(...) goal.
-            expect![["Unique; for<?U0,?U0,?U0> { substitution [?0 := ^0.0, ?1 := '^0.1, ?2 := ^0.2, ?3 := ^0.0, ?4 := ^0.2] }"]]
+            expect![["Unique; for<_0_0, '_0_1, _0_2> { substitution [?0 := _0_0, ?1 := '_0_1, ?2 := _0_2, ?3 := _0_0, ?4 := _0_2] }"]]
         }
     }
 }
@@ -393,7 +377,7 @@ fn quantified_types() {
             forall<'a> { fn(fn1<'a>): Foo }
         } yields {
             // Lifetime constraints are unsatisfiable
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }, InEnvironment { environment: Env([]), goal: '!2_0: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!2_0, '!2_0: '!1_0]"]]
         }
     }
 }
@@ -37,7 +37,7 @@ fn dyn_to_dyn_unsizing() {
                 }
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1]"]]
         }
 
         goal {
@@ -47,7 +47,7 @@ fn dyn_to_dyn_unsizing() {
                 }
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }]"]]
+            expect![["Unique; lifetime constraints ['!1_0: '!1_1]"]]
         }
 
         // Target has a subset of source auto traits
@@ -56,7 +56,7 @@ fn dyn_to_dyn_unsizing() {
                 dyn Principal + Auto1 + Auto2 + 'a: Unsize<dyn Principal + Auto1 + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"]]
+            expect![["Unique"]]
         }
 
         // Both target and source don't have principal as their first trait
@@ -65,7 +65,7 @@ fn dyn_to_dyn_unsizing() {
                 dyn Auto1 + Principal + 'a: Unsize<dyn Auto1 + Principal + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"]]
+            expect![["Unique"]]
         }
 
         // Different order of traits in target and source
@@ -74,7 +74,7 @@ fn dyn_to_dyn_unsizing() {
                 dyn Principal + Auto1 + 'a: Unsize<dyn Auto1 + Principal + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"]]
+            expect![["Unique"]]
         }
 
         // See above
@@ -83,7 +83,7 @@ fn dyn_to_dyn_unsizing() {
                 dyn Principal + Auto2 + Auto1 + 'a: Unsize<dyn Principal + Auto1 + Auto2 + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"]]
+            expect![["Unique"]]
         }
 
         // Source has a subset of auto traits of target
@@ -128,7 +128,7 @@ fn dyn_to_dyn_unsizing() {
                 dyn GenericPrincipal<u64, Item = u64> + 'a: Unsize<dyn GenericPrincipal<u64, Item = u64> + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"]]
+            expect![["Unique"]]
         }
 
         // Non-matching generic principal traits
@@ -192,7 +192,7 @@ fn ty_to_dyn_unsizing() {
                 Foo: Unsize<dyn Principal + Auto + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // Principal is not the first trait
@@ -201,7 +201,7 @@ fn ty_to_dyn_unsizing() {
                 Foo: Unsize<dyn Auto + Principal + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // Auto-only trait object
@@ -210,7 +210,7 @@ fn ty_to_dyn_unsizing() {
                 Foo: Unsize<dyn Auto + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // TypeOutlives test
@@ -219,7 +219,7 @@ fn ty_to_dyn_unsizing() {
                 FooLifetime<'a>: Unsize<dyn Principal + Auto + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: FooLifetime<'!1_0>: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [FooLifetime<'!1_0>: '!1_0]"]]
         }
 
         // See above
@@ -230,7 +230,7 @@ fn ty_to_dyn_unsizing() {
                 }
             }
         } yields {
-            expect![["Unique; for<?U1> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: FooLifetime<'!1_0>: '^0.0 }] }"]]
+            expect![["Unique; for<'_0_0> { substitution [?0 := '_0_0], lifetime constraints [FooLifetime<'!1_0>: '_0_0] }"]]
         }
 
         // Source does not implement auto trait (with principal)
@@ -266,7 +266,7 @@ fn ty_to_dyn_unsizing() {
                 Foo: Unsize<dyn GenericPrincipal<u32, Item = u32> + 'a>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
 
@@ -309,7 +309,7 @@ fn ty_to_dyn_unsizing() {
                 }
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: FooNotSized<!2_0>: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [FooNotSized<!1_1>: '!1_0]"]]
         }
     }
 }
@@ -350,7 +350,7 @@ fn tuple_unsizing() {
                (u32, Foo): Unsize<(u32, dyn Principal + 'a)>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // Last field does not implement `Unsize`
@@ -386,7 +386,7 @@ fn tuple_unsizing() {
                (u32, (u32, Foo)): Unsize<(u32, (u32, dyn Principal + 'a))>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
     }
 }
@@ -497,7 +497,7 @@ fn struct_unsizing() {
                 S1<Foo>: Unsize<S1<dyn Principal + 'a>>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         goal {
@@ -524,7 +524,7 @@ fn struct_unsizing() {
                 S12<Foo, Foo>: Unsize<S12<Foo, dyn Principal + 'a>>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // Test for the unsizing parameters collector
@@ -534,7 +534,7 @@ fn struct_unsizing() {
                 SWithBinders<Foo, Foo>: Unsize<SWithBinders<dyn Principal + 'a, Foo>>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // Non-trivial unsizing of the last field
@@ -543,7 +543,7 @@ fn struct_unsizing() {
                 SNested<Foo, Bar<Foo>, Foo>: Unsize<SNested<Foo, Bar<Foo>, dyn Principal + 'a>>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         goal {
@@ -560,7 +560,7 @@ fn struct_unsizing() {
                 SLifetime<'a, Foo>: Unsize<SLifetime<'a, dyn Principal + 'a>>
             }
         } yields {
-            expect![["Unique; lifetime constraints [InEnvironment { environment: Env([]), goal: Foo: '!1_0 }]"]]
+            expect![["Unique; lifetime constraints [Foo: '!1_0]"]]
         }
 
         // Tests with constant as an unsizing parameter
@@ -1104,6 +1104,29 @@ fn no_unsize_impls() {
     }
 }
 
+#[test]
+fn opaque_ty_hidden_ty_must_be_well_formed() {
+    lowering_success! {
+        program {
+            trait Foo {}
+            struct Bar<T> where T: Foo {}
+
+            opaque type T<U> where U: Foo = Bar<U>;
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Foo {}
+            struct Bar<T> where T: Foo {}
+
+            opaque type T<U> = Bar<U>;
+        } error_msg {
+            "opaque type declaration `T` does not meet well-formedness requirements"
+        }
+    }
+}
+
 #[test]
 fn ill_formed_opaque_ty() {
     lowering_error! {
@@ -1438,3 +1461,73 @@ fn coerce_unsized_struct() {
         }
     }
 }
+
+#[test]
+fn adt_repr_wf() {
+    lowering_success! {
+        program {
+            #[one_zst]
+            struct Zst<T> {}
+
+            #[repr(transparent)]
+            struct Wrapper<T> { inner: T, zst: Zst<T> }
+
+            #[repr(transparent)]
+            enum Single { Only(u32) }
+
+            #[repr(simd)]
+            struct Vector<T> { x: T, y: T, z: T }
+        }
+    }
+
+    // More than one field which is not a 1-ZST
+    lowering_error! {
+        program {
+            #[repr(transparent)]
+            struct Wrapper { first: u32, second: u32 }
+        } error_msg {
+            "type declaration `Wrapper` does not meet well-formedness requirements"
+        }
+    }
+
+    // More than one variant
+    lowering_error! {
+        program {
+            #[repr(transparent)]
+            enum Choice { First(u32), Second(u32) }
+        } error_msg {
+            "type declaration `Choice` does not meet well-formedness requirements"
+        }
+    }
+
+    // Combined with another representation
+    lowering_error! {
+        program {
+            #[repr(transparent)]
+            #[repr(C)]
+            struct Wrapper { inner: u32 }
+        } error_msg {
+            "type declaration `Wrapper` does not meet well-formedness requirements"
+        }
+    }
+
+    // Fields of different types
+    lowering_error! {
+        program {
+            #[repr(simd)]
+            struct Vector { x: f32, y: f64 }
+        } error_msg {
+            "type declaration `Vector` does not meet well-formedness requirements"
+        }
+    }
+
+    // No fields
+    lowering_error! {
+        program {
+            #[repr(simd)]
+            struct Vector { }
+        } error_msg {
+            "type declaration `Vector` does not meet well-formedness requirements"
+        }
+    }
+}